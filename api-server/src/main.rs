@@ -1,14 +1,22 @@
 use actix_cors::Cors;
 use actix_multipart::Multipart;
+use actix_web::http::{header, StatusCode};
 use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, middleware};
-use chrono::{DateTime, Utc};
-use futures_util::StreamExt;
+use base64::Engine;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use futures_util::{Stream, StreamExt};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use uuid::Uuid;
 
 // ============================================================================
@@ -62,6 +70,44 @@ struct LicenseActivateResponse {
     message: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct LicenseDeactivateRequest {
+    license_key: String,
+    mac_address: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LicenseDeactivateResponse {
+    success: bool,
+    error: Option<String>,
+    message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenRequest {
+    license_key: String,
+    dealer_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenResponse {
+    success: bool,
+    token: Option<String>,
+    token_type: Option<String>,
+    expires_at: Option<String>,
+    expires_in: Option<i64>,
+    error: Option<String>,
+}
+
+/// Claims of the bearer token the cloud endpoints accept
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenClaims {
+    /// Dealer id
+    sub: String,
+    iat: i64,
+    exp: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct BackupInfo {
     backup_id: String,
@@ -81,6 +127,21 @@ struct CloudBackupResponse {
     error: Option<String>,
 }
 
+/// One stored version in the `GET /api/cloud/backups` listing
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupVersion {
+    backup_id: String,
+    timestamp: String,
+    size_bytes: u64,
+    checksum: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CloudBackupListResponse {
+    backups: Vec<BackupVersion>,
+    retention: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct CloudStatusResponse {
     has_backup: bool,
@@ -93,90 +154,436 @@ struct CloudStatusResponse {
 // APP STATE
 // ============================================================================
 
+/// Backups kept per dealer when `BACKUP_RETENTION` is not set
+const DEFAULT_BACKUP_RETENTION: usize = 5;
+
 struct AppState {
     licenses: Mutex<HashMap<String, License>>,
-    backups: Mutex<HashMap<String, BackupInfo>>,
+    /// Backups per dealer, oldest first
+    backups: Mutex<HashMap<String, Vec<BackupInfo>>>,
     backup_dir: PathBuf,
+    backup_retention: usize,
+    /// HMAC key the bearer tokens are signed with
+    token_secret: Vec<u8>,
+    rate_limiter: RateLimiter,
+    /// Licenses and the backup index are written through to it
+    store: Store,
+}
+
+/// Number of backup versions kept per dealer (env `BACKUP_RETENTION`, at least 1)
+fn backup_retention_from_env() -> usize {
+    std::env::var("BACKUP_RETENTION")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_BACKUP_RETENTION)
+        .max(1)
+}
+
+/// Lifetime of an issued bearer token
+const TOKEN_TTL_MINUTES: i64 = 15;
+
+/// Token signing key (env `JWT_SECRET`); without it a random key is made, so tokens do not
+/// survive a restart
+fn token_secret_from_env() -> Vec<u8> {
+    match std::env::var("JWT_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+        _ => [Uuid::new_v4().into_bytes(), Uuid::new_v4().into_bytes()].concat(),
+    }
+}
+
+/// Licenses the store is seeded with on first start
+fn test_licenses() -> HashMap<String, License> {
+    let mut licenses = HashMap::new();
+
+    // Test license 1 - Active, no expiry
+    licenses.insert(
+        "NEXUS-TEST-1234-5678".to_string(),
+        License {
+            license_key: "NEXUS-TEST-1234-5678".to_string(),
+            dealer_id: "dealer-001".to_string(),
+            dealer_name: "Test Bayi A.S.".to_string(),
+            mac_address: None,
+            activated_at: None,
+            expires_at: None,
+            is_active: true,
+            max_activations: 3,
+            current_activations: 0,
+        },
+    );
+
+    // Test license 2 - Active, with expiry
+    licenses.insert(
+        "NEXUS-DEMO-ABCD-EFGH".to_string(),
+        License {
+            license_key: "NEXUS-DEMO-ABCD-EFGH".to_string(),
+            dealer_id: "dealer-002".to_string(),
+            dealer_name: "Demo Ticaret Ltd.".to_string(),
+            mac_address: None,
+            activated_at: None,
+            expires_at: Some("2026-12-31T23:59:59Z".to_string()),
+            is_active: true,
+            max_activations: 1,
+            current_activations: 0,
+        },
+    );
+
+    // Test license 3 - Expired
+    licenses.insert(
+        "NEXUS-EXPR-1111-2222".to_string(),
+        License {
+            license_key: "NEXUS-EXPR-1111-2222".to_string(),
+            dealer_id: "dealer-003".to_string(),
+            dealer_name: "Suresi Dolmus Firma".to_string(),
+            mac_address: None,
+            activated_at: None,
+            expires_at: Some("2024-01-01T00:00:00Z".to_string()),
+            is_active: true,
+            max_activations: 1,
+            current_activations: 0,
+        },
+    );
+
+    // Test license 4 - Deactivated
+    licenses.insert(
+        "NEXUS-DEAD-0000-0000".to_string(),
+        License {
+            license_key: "NEXUS-DEAD-0000-0000".to_string(),
+            dealer_id: "dealer-004".to_string(),
+            dealer_name: "Iptal Edilmis Firma".to_string(),
+            mac_address: None,
+            activated_at: None,
+            expires_at: None,
+            is_active: false,
+            max_activations: 1,
+            current_activations: 0,
+        },
+    );
+
+    licenses
 }
 
 impl AppState {
+    /// State in `./backups` and the SQLite file named by env `DATA_DB` (default `./nexus-api.db`)
     fn new() -> Self {
-        let backup_dir = PathBuf::from("./backups");
+        let db_path = std::env::var("DATA_DB").unwrap_or_else(|_| "./nexus-api.db".to_string());
+        Self::open(Path::new(&db_path), PathBuf::from("./backups"))
+            .unwrap_or_else(|e| panic!("Could not open {}: {}", db_path, e))
+    }
+
+    /// Load licenses and the backup index from `db_path`. A new file is seeded with the test
+    /// licenses and gets its backup index rebuilt from the files already in `backup_dir`.
+    fn open(db_path: &Path, backup_dir: PathBuf) -> rusqlite::Result<Self> {
         fs::create_dir_all(&backup_dir).ok();
+        let fresh = !db_path.exists();
+        let store = Store::open(db_path)?;
+
+        let mut licenses = store.load_licenses()?;
+        if licenses.is_empty() {
+            licenses = test_licenses();
+            for license in licenses.values() {
+                store.save_license(license)?;
+            }
+        }
+        if fresh {
+            for info in scan_backup_dir(&backup_dir) {
+                log::info!("Indexed existing backup {} ({})", info.backup_id, info.file_path.display());
+                store.save_backup(&info)?;
+            }
+        }
+        let backups = store.load_backups()?;
 
-        // Pre-populate with some test licenses
-        let mut licenses = HashMap::new();
-
-        // Test license 1 - Active, no expiry
-        licenses.insert(
-            "NEXUS-TEST-1234-5678".to_string(),
-            License {
-                license_key: "NEXUS-TEST-1234-5678".to_string(),
-                dealer_id: "dealer-001".to_string(),
-                dealer_name: "Test Bayi A.S.".to_string(),
-                mac_address: None,
-                activated_at: None,
-                expires_at: None,
-                is_active: true,
-                max_activations: 3,
-                current_activations: 0,
-            },
-        );
+        Ok(AppState {
+            licenses: Mutex::new(licenses),
+            backups: Mutex::new(backups),
+            backup_dir,
+            backup_retention: backup_retention_from_env(),
+            token_secret: token_secret_from_env(),
+            rate_limiter: RateLimiter::from_env(),
+            store,
+        })
+    }
 
-        // Test license 2 - Active, with expiry
-        licenses.insert(
-            "NEXUS-DEMO-ABCD-EFGH".to_string(),
-            License {
-                license_key: "NEXUS-DEMO-ABCD-EFGH".to_string(),
-                dealer_id: "dealer-002".to_string(),
-                dealer_name: "Demo Ticaret Ltd.".to_string(),
-                mac_address: None,
-                activated_at: None,
-                expires_at: Some("2026-12-31T23:59:59Z".to_string()),
-                is_active: true,
-                max_activations: 1,
-                current_activations: 0,
-            },
-        );
+    /// Write a changed license through to the store; the in-memory table stays authoritative
+    fn persist_license(&self, license: &License) {
+        if let Err(e) = self.store.save_license(license) {
+            log::error!("Could not persist license {}: {}", license.license_key, e);
+        }
+    }
+}
 
-        // Test license 3 - Expired
-        licenses.insert(
-            "NEXUS-EXPR-1111-2222".to_string(),
-            License {
-                license_key: "NEXUS-EXPR-1111-2222".to_string(),
-                dealer_id: "dealer-003".to_string(),
-                dealer_name: "Suresi Dolmus Firma".to_string(),
-                mac_address: None,
-                activated_at: None,
-                expires_at: Some("2024-01-01T00:00:00Z".to_string()),
-                is_active: true,
-                max_activations: 1,
-                current_activations: 0,
-            },
-        );
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
 
-        // Test license 4 - Deactivated
-        licenses.insert(
-            "NEXUS-DEAD-0000-0000".to_string(),
-            License {
-                license_key: "NEXUS-DEAD-0000-0000".to_string(),
-                dealer_id: "dealer-004".to_string(),
-                dealer_name: "Iptal Edilmis Firma".to_string(),
-                mac_address: None,
-                activated_at: None,
-                expires_at: None,
-                is_active: false,
-                max_activations: 1,
-                current_activations: 0,
-            },
-        );
+/// SQLite file holding the license table and the backup index
+struct Store {
+    conn: Mutex<Connection>,
+}
 
-        AppState {
-            licenses: Mutex::new(licenses),
-            backups: Mutex::new(HashMap::new()),
-            backup_dir,
+impl Store {
+    fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS licenses (
+                license_key TEXT PRIMARY KEY,
+                dealer_id TEXT NOT NULL,
+                dealer_name TEXT NOT NULL,
+                mac_address TEXT,
+                activated_at TEXT,
+                expires_at TEXT,
+                is_active INTEGER NOT NULL,
+                max_activations INTEGER NOT NULL,
+                current_activations INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS backups (
+                backup_id TEXT PRIMARY KEY,
+                dealer_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                file_size INTEGER NOT NULL,
+                checksum TEXT NOT NULL
+            );",
+        )?;
+        Ok(Store { conn: Mutex::new(conn) })
+    }
+
+    fn load_licenses(&self) -> rusqlite::Result<HashMap<String, License>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT license_key, dealer_id, dealer_name, mac_address, activated_at, expires_at,
+                    is_active, max_activations, current_activations
+             FROM licenses",
+        )?;
+        let licenses = stmt
+            .query_map([], |row| {
+                Ok(License {
+                    license_key: row.get(0)?,
+                    dealer_id: row.get(1)?,
+                    dealer_name: row.get(2)?,
+                    mac_address: row.get(3)?,
+                    activated_at: row.get(4)?,
+                    expires_at: row.get(5)?,
+                    is_active: row.get(6)?,
+                    max_activations: row.get(7)?,
+                    current_activations: row.get(8)?,
+                })
+            })?
+            .map(|license| license.map(|l| (l.license_key.clone(), l)))
+            .collect();
+        licenses
+    }
+
+    fn save_license(&self, license: &License) -> rusqlite::Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO licenses (license_key, dealer_id, dealer_name, mac_address, activated_at,
+                 expires_at, is_active, max_activations, current_activations)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                license.license_key,
+                license.dealer_id,
+                license.dealer_name,
+                license.mac_address,
+                license.activated_at,
+                license.expires_at,
+                license.is_active,
+                license.max_activations,
+                license.current_activations,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Backups per dealer, oldest first
+    fn load_backups(&self) -> rusqlite::Result<HashMap<String, Vec<BackupInfo>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT backup_id, dealer_id, timestamp, file_path, file_size, checksum
+             FROM backups ORDER BY timestamp, rowid",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let timestamp: String = row.get(2)?;
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?
+                .with_timezone(&Utc);
+            Ok(BackupInfo {
+                backup_id: row.get(0)?,
+                dealer_id: row.get(1)?,
+                timestamp,
+                file_path: PathBuf::from(row.get::<_, String>(3)?),
+                file_size: row.get::<_, i64>(4)? as u64,
+                checksum: row.get(5)?,
+            })
+        })?;
+
+        let mut backups: HashMap<String, Vec<BackupInfo>> = HashMap::new();
+        for info in rows {
+            let info = info?;
+            backups.entry(info.dealer_id.clone()).or_default().push(info);
+        }
+        Ok(backups)
+    }
+
+    fn save_backup(&self, info: &BackupInfo) -> rusqlite::Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO backups (backup_id, dealer_id, timestamp, file_path, file_size, checksum)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                info.backup_id,
+                info.dealer_id,
+                info.timestamp.to_rfc3339(),
+                info.file_path.to_string_lossy(),
+                info.file_size as i64,
+                info.checksum,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn delete_backup(&self, backup_id: &str) -> rusqlite::Result<()> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM backups WHERE backup_id = ?1", params![backup_id])?;
+        Ok(())
+    }
+}
+
+/// Index entry for a stored backup named `<dealer>_<YYYYmmdd>_<HHMMSS>[_<backup id>].db`.
+/// Files from before backup ids were part of the name get a new id.
+fn backup_from_file(path: &Path) -> Option<BackupInfo> {
+    let stem = path.file_name()?.to_str()?.strip_suffix(".db")?;
+    let (backup_id, rest) = match stem.rsplit_once('_') {
+        Some((rest, id)) if Uuid::parse_str(id).is_ok() => (id.to_string(), rest),
+        _ => (Uuid::new_v4().to_string(), stem),
+    };
+    let (rest, time) = rest.rsplit_once('_')?;
+    let (dealer_id, date) = rest.rsplit_once('_')?;
+    let timestamp = NaiveDateTime::parse_from_str(&format!("{}{}", date, time), "%Y%m%d%H%M%S")
+        .ok()?
+        .and_utc();
+
+    let mut hasher = HashingWriter::new(std::io::sink());
+    std::io::copy(&mut fs::File::open(path).ok()?, &mut hasher).ok()?;
+    let (checksum, file_size) = hasher.finish().ok()?;
+
+    Some(BackupInfo {
+        backup_id,
+        dealer_id: dealer_id.to_string(),
+        timestamp,
+        file_path: path.to_path_buf(),
+        file_size,
+        checksum,
+    })
+}
+
+/// Every backup file in `dir`; partial uploads and unrelated files are skipped
+fn scan_backup_dir(dir: &Path) -> Vec<BackupInfo> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| backup_from_file(&entry.path()))
+        .collect()
+}
+
+// ============================================================================
+// RATE LIMITING
+// ============================================================================
+
+/// Requests a client may send at once when `RATE_LIMIT_BURST` is not set
+const DEFAULT_RATE_LIMIT_BURST: u32 = 20;
+
+/// Sustained requests per minute when `RATE_LIMIT_PER_MINUTE` is not set
+const DEFAULT_RATE_LIMIT_PER_MINUTE: u32 = 60;
+
+/// Buckets kept before full (idle) ones are dropped
+const RATE_LIMIT_MAX_BUCKETS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per client key (IP address, license key): `capacity` requests at once, refilled
+/// at `refill_per_sec`
+struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    fn new(capacity: u32, per_minute: u32) -> Self {
+        RateLimiter {
+            capacity: capacity.max(1) as f64,
+            refill_per_sec: per_minute.max(1) as f64 / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limits from env `RATE_LIMIT_BURST` and `RATE_LIMIT_PER_MINUTE`
+    fn from_env() -> Self {
+        let var = |name: &str, default: u32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(default)
+        };
+        Self::new(
+            var("RATE_LIMIT_BURST", DEFAULT_RATE_LIMIT_BURST),
+            var("RATE_LIMIT_PER_MINUTE", DEFAULT_RATE_LIMIT_PER_MINUTE),
+        )
+    }
+
+    /// Take one request from `key`'s bucket; when it is empty, the seconds until the next one
+    fn check(&self, key: &str, now: Instant) -> Result<(), u64> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= RATE_LIMIT_MAX_BUCKETS {
+            let (capacity, refill) = (self.capacity, self.refill_per_sec);
+            buckets.retain(|_, b| b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * refill < capacity);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket { tokens: self.capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / self.refill_per_sec).ceil() as u64)
+        }
+    }
+}
+
+/// Rate limit key of the connecting address; forwarded-for headers are not trusted
+fn client_ip_key(req: &HttpRequest) -> String {
+    match req.peer_addr() {
+        Some(addr) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// 429 with `Retry-After` when any of `keys` is out of requests, `None` when the call may go ahead
+fn rate_limited(data: &AppState, keys: &[String]) -> Option<HttpResponse> {
+    let now = Instant::now();
+    for key in keys {
+        if let Err(retry_after) = data.rate_limiter.check(key, now) {
+            log::warn!("Rate limit exceeded for {}", key);
+            return Some(
+                HttpResponse::TooManyRequests()
+                    .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                    .json(serde_json::json!({
+                        "success": false,
+                        "error": "RATE_LIMITED",
+                        "message": format!("Cok fazla istek, {} sn sonra tekrar deneyin", retry_after),
+                    })),
+            );
         }
     }
+    None
 }
 
 // ============================================================================
@@ -185,9 +592,13 @@ impl AppState {
 
 /// POST /api/license/validate
 async fn validate_license(
+    req: HttpRequest,
     data: web::Data<AppState>,
     body: web::Json<LicenseValidateRequest>,
 ) -> HttpResponse {
+    if let Some(response) = rate_limited(&data, &[client_ip_key(&req), format!("license:{}", body.license_key)]) {
+        return response;
+    }
     log::info!("License validation request: {:?}", body.license_key);
 
     let licenses = data.licenses.lock().unwrap();
@@ -258,9 +669,13 @@ async fn validate_license(
 
 /// POST /api/license/activate
 async fn activate_license(
+    req: HttpRequest,
     data: web::Data<AppState>,
     body: web::Json<LicenseActivateRequest>,
 ) -> HttpResponse {
+    if let Some(response) = rate_limited(&data, &[client_ip_key(&req), format!("license:{}", body.license_key)]) {
+        return response;
+    }
     log::info!("License activation request: {:?}", body.license_key);
 
     let mut licenses = data.licenses.lock().unwrap();
@@ -323,6 +738,7 @@ async fn activate_license(
             license.mac_address = Some(body.mac_address.clone());
             license.activated_at = Some(now.to_rfc3339());
             license.current_activations += 1;
+            data.persist_license(license);
 
             log::info!(
                 "License {} activated for MAC {} (device: {:?})",
@@ -353,70 +769,304 @@ async fn activate_license(
     }
 }
 
+/// POST /api/license/deactivate - frees the activation of the calling device
+async fn deactivate_license(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    body: web::Json<LicenseDeactivateRequest>,
+) -> HttpResponse {
+    if let Some(response) = rate_limited(&data, &[client_ip_key(&req), format!("license:{}", body.license_key)]) {
+        return response;
+    }
+    log::info!("License deactivation request: {:?}", body.license_key);
+
+    let mut licenses = data.licenses.lock().unwrap();
+
+    let Some(license) = licenses.get_mut(&body.license_key) else {
+        return HttpResponse::NotFound().json(LicenseDeactivateResponse {
+            success: false,
+            error: Some("LICENSE_NOT_FOUND".to_string()),
+            message: Some("Lisans bulunamadi".to_string()),
+        });
+    };
+
+    match license.mac_address {
+        None => HttpResponse::Conflict().json(LicenseDeactivateResponse {
+            success: false,
+            error: Some("NOT_ACTIVATED".to_string()),
+            message: Some("Lisans herhangi bir cihazda aktif degil".to_string()),
+        }),
+        Some(ref registered_mac) if registered_mac != &body.mac_address => {
+            HttpResponse::Forbidden().json(LicenseDeactivateResponse {
+                success: false,
+                error: Some("MAC_MISMATCH".to_string()),
+                message: Some("Bu lisans baska bir cihaza kayitli".to_string()),
+            })
+        }
+        Some(_) => {
+            license.mac_address = None;
+            license.activated_at = None;
+            license.current_activations = (license.current_activations - 1).max(0);
+            data.persist_license(license);
+
+            log::info!(
+                "License {} deactivated for MAC {} ({}/{} activations)",
+                body.license_key,
+                body.mac_address,
+                license.current_activations,
+                license.max_activations
+            );
+
+            HttpResponse::Ok().json(LicenseDeactivateResponse {
+                success: true,
+                error: None,
+                message: Some("Lisans bu cihazdan kaldirildi".to_string()),
+            })
+        }
+    }
+}
+
 // ============================================================================
 // CLOUD SYNC ENDPOINTS
 // ============================================================================
 
-/// Helper to extract dealer info from headers
-fn get_dealer_from_headers(req: &HttpRequest) -> Option<(String, String)> {
-    let dealer_id = req
-        .headers()
-        .get("X-Dealer-ID")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())?;
+/// Signed bearer token for `dealer_id`, valid for `TOKEN_TTL_MINUTES` from `now`
+fn issue_token(
+    secret: &[u8],
+    dealer_id: &str,
+    now: DateTime<Utc>,
+) -> Result<(String, DateTime<Utc>), jsonwebtoken::errors::Error> {
+    let expires_at = now + Duration::minutes(TOKEN_TTL_MINUTES);
+    let claims = TokenClaims {
+        sub: dealer_id.to_string(),
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+    let token = jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(secret))?;
+    Ok((token, expires_at))
+}
 
-    let license_key = req
+/// Dealer of the request's `Authorization: Bearer` token, or the status and error to reject it with
+fn authenticate_dealer(req: &HttpRequest, data: &AppState) -> Result<String, (StatusCode, &'static str)> {
+    let token = req
         .headers()
-        .get("X-License-Key")
+        .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())?;
-
-    Some((dealer_id, license_key))
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or((StatusCode::UNAUTHORIZED, "Missing bearer token"))?;
+
+    let claims = jsonwebtoken::decode::<TokenClaims>(
+        token,
+        &DecodingKey::from_secret(&data.token_secret),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map_err(|e| {
+        log::warn!("Rejected bearer token: {}", e);
+        (StatusCode::UNAUTHORIZED, "Invalid or expired token")
+    })?
+    .claims;
+
+    // A deactivated license loses access before its last token runs out
+    let licenses = data.licenses.lock().unwrap();
+    if !licenses.values().any(|l| l.is_active && l.dealer_id == claims.sub) {
+        return Err((StatusCode::FORBIDDEN, "License no longer active"));
+    }
+    Ok(claims.sub)
 }
 
-/// POST /api/cloud/backup
-async fn cloud_backup(
+/// POST /api/auth/token
+///
+/// Exchanges a valid license key for a short-lived bearer token, so the key itself is not sent
+/// with every cloud call.
+async fn issue_auth_token(
     req: HttpRequest,
     data: web::Data<AppState>,
-    mut payload: Multipart,
+    body: web::Json<TokenRequest>,
 ) -> HttpResponse {
-    log::info!("Cloud backup request received");
+    if let Some(response) = rate_limited(&data, &[client_ip_key(&req), format!("license:{}", body.license_key)]) {
+        return response;
+    }
+    log::info!("Token request for dealer {}", body.dealer_id);
 
-    // Get dealer info from headers
-    let (dealer_id, license_key) = match get_dealer_from_headers(&req) {
-        Some(info) => info,
-        None => {
-            return HttpResponse::Unauthorized().json(CloudBackupResponse {
-                success: false,
-                backup_id: None,
-                timestamp: None,
-                message: None,
-                error: Some("Missing dealer credentials in headers".to_string()),
-            });
-        }
+    let rejected = |status: StatusCode, error: &str| {
+        HttpResponse::build(status).json(TokenResponse {
+            success: false,
+            token: None,
+            token_type: None,
+            expires_at: None,
+            expires_in: None,
+            error: Some(error.to_string()),
+        })
     };
 
-    // Verify license
     {
         let licenses = data.licenses.lock().unwrap();
-        match licenses.get(&license_key) {
-            Some(license) if license.is_active && license.dealer_id == dealer_id => {}
-            _ => {
-                return HttpResponse::Forbidden().json(CloudBackupResponse {
-                    success: false,
-                    backup_id: None,
-                    timestamp: None,
-                    message: None,
-                    error: Some("Invalid license or dealer mismatch".to_string()),
-                });
+        match licenses.get(&body.license_key) {
+            Some(license) if license.is_active && license.dealer_id == body.dealer_id => {
+                let expired = license
+                    .expires_at
+                    .as_ref()
+                    .and_then(|e| e.parse::<DateTime<Utc>>().ok())
+                    .is_some_and(|expiry| expiry < Utc::now());
+                if expired {
+                    return rejected(StatusCode::FORBIDDEN, "License expired");
+                }
             }
+            _ => return rejected(StatusCode::FORBIDDEN, "Invalid license or dealer mismatch"),
+        }
+    }
+
+    match issue_token(&data.token_secret, &body.dealer_id, Utc::now()) {
+        Ok((token, expires_at)) => HttpResponse::Ok().json(TokenResponse {
+            success: true,
+            token: Some(token),
+            token_type: Some("Bearer".to_string()),
+            expires_at: Some(expires_at.to_rfc3339()),
+            expires_in: Some(TOKEN_TTL_MINUTES * 60),
+            error: None,
+        }),
+        Err(e) => {
+            log::error!("Error signing token: {}", e);
+            rejected(StatusCode::INTERNAL_SERVER_ERROR, "Error issuing token")
         }
     }
+}
 
-    let mut file_data: Vec<u8> = Vec::new();
+/// Size of the chunks a backup is streamed to the client in
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+fn backup_failed(error: &str) -> CloudBackupResponse {
+    CloudBackupResponse {
+        success: false,
+        backup_id: None,
+        timestamp: None,
+        message: None,
+        error: Some(error.to_string()),
+    }
+}
+
+/// Writes through to `inner` and hashes everything written
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    written: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        HashingWriter { inner, hasher: Sha256::new(), written: 0 }
+    }
+
+    /// Flush and return the hex SHA-256 and size of everything written
+    fn finish(mut self) -> std::io::Result<(String, u64)> {
+        self.inner.flush()?;
+        Ok((hex::encode(self.hasher.finalize()), self.written))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Async counterpart of `HashingWriter` for request bodies: chunks are written through tokio, so a
+/// large upload does not block the worker thread
+struct UploadWriter {
+    file: tokio::fs::File,
+    hasher: Sha256,
+    written: u64,
+}
+
+impl UploadWriter {
+    async fn create(path: &Path) -> std::io::Result<Self> {
+        Ok(UploadWriter { file: tokio::fs::File::create(path).await?, hasher: Sha256::new(), written: 0 })
+    }
+
+    async fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.file.write_all(buf).await?;
+        self.hasher.update(buf);
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Flush and return the hex SHA-256 and size of everything written
+    async fn finish(mut self) -> std::io::Result<(String, u64)> {
+        self.file.flush().await?;
+        Ok((hex::encode(self.hasher.finalize()), self.written))
+    }
+}
+
+/// File that is deleted when dropped, unless `keep` is called (partial or rejected uploads)
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn keep(mut self) -> PathBuf {
+        std::mem::take(&mut self.0)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.0.as_os_str().is_empty() {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+}
+
+/// Gunzip `source` into `target` chunk by chunk; returns the SHA-256 and size of the output
+fn decompress_to(source: &Path, target: &Path) -> std::io::Result<(String, u64)> {
+    let mut decoder = GzDecoder::new(BufReader::new(fs::File::open(source)?));
+    let mut writer = HashingWriter::new(fs::File::create(target)?);
+    std::io::copy(&mut decoder, &mut writer)?;
+    writer.finish()
+}
+
+/// Size of the file at `path` and a stream of its contents in `STREAM_CHUNK_SIZE` pieces
+async fn file_stream(
+    path: &Path,
+) -> std::io::Result<(u64, impl Stream<Item = Result<web::Bytes, std::io::Error>> + 'static)> {
+    let file = tokio::fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+    let stream = futures_util::stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+        match file.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok(web::Bytes::from(buf)), Some(file)))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    Ok((len, stream))
+}
+
+/// A request body written to the upload file, with the fields sent alongside it
+struct ReceivedUpload {
+    checksum: String,
+    size: u64,
+    received_checksum: Option<String>,
+    compressed: bool,
+    encrypted: bool,
+}
+
+/// Multipart form with `file`, `checksum` and `compressed` fields
+async fn receive_multipart_upload(mut payload: Multipart, upload_path: &Path) -> Result<ReceivedUpload, HttpResponse> {
+    let mut upload_writer: Option<UploadWriter> = None;
     let mut received_checksum: Option<String> = None;
+    let mut compressed = false;
 
-    // Process multipart form
     while let Some(item) = payload.next().await {
         match item {
             Ok(mut field) => {
@@ -425,21 +1075,24 @@ async fn cloud_backup(
 
                 match field_name {
                     "file" => {
+                        let mut writer = match UploadWriter::create(upload_path).await {
+                            Ok(writer) => writer,
+                            Err(e) => {
+                                log::error!("Error creating upload file: {}", e);
+                                return Err(HttpResponse::InternalServerError().json(backup_failed("Error saving backup file")));
+                            }
+                        };
                         while let Some(chunk) = field.next().await {
-                            match chunk {
-                                Ok(bytes) => file_data.extend_from_slice(&bytes),
-                                Err(e) => {
-                                    log::error!("Error reading file chunk: {}", e);
-                                    return HttpResponse::InternalServerError().json(CloudBackupResponse {
-                                        success: false,
-                                        backup_id: None,
-                                        timestamp: None,
-                                        message: None,
-                                        error: Some("Error reading file data".to_string()),
-                                    });
-                                }
+                            let written = match chunk {
+                                Ok(bytes) => writer.write_all(&bytes).await.map_err(|e| e.to_string()),
+                                Err(e) => Err(e.to_string()),
+                            };
+                            if let Err(e) = written {
+                                log::error!("Error reading file chunk: {}", e);
+                                return Err(HttpResponse::InternalServerError().json(backup_failed("Error reading file data")));
                             }
                         }
+                        upload_writer = Some(writer);
                     }
                     "checksum" => {
                         let mut checksum_data = Vec::new();
@@ -450,132 +1103,339 @@ async fn cloud_backup(
                         }
                         received_checksum = String::from_utf8(checksum_data).ok();
                     }
+                    "compressed" => {
+                        let mut flag = Vec::new();
+                        while let Some(chunk) = field.next().await {
+                            if let Ok(bytes) = chunk {
+                                flag.extend_from_slice(&bytes);
+                            }
+                        }
+                        compressed = flag.as_slice() == b"true";
+                    }
                     _ => {}
                 }
             }
             Err(e) => {
                 log::error!("Multipart error: {}", e);
-                return HttpResponse::BadRequest().json(CloudBackupResponse {
-                    success: false,
-                    backup_id: None,
-                    timestamp: None,
-                    message: None,
-                    error: Some("Error processing multipart form".to_string()),
-                });
+                return Err(HttpResponse::BadRequest().json(backup_failed("Error processing multipart form")));
             }
         }
     }
 
-    if file_data.is_empty() {
-        return HttpResponse::BadRequest().json(CloudBackupResponse {
-            success: false,
-            backup_id: None,
-            timestamp: None,
-            message: None,
-            error: Some("No file data received".to_string()),
-        });
+    let Some(writer) = upload_writer.filter(|w| w.written > 0) else {
+        return Err(HttpResponse::BadRequest().json(backup_failed("No file data received")));
+    };
+    let (checksum, size) = writer.finish().await.map_err(|e| {
+        log::error!("Error saving backup: {}", e);
+        HttpResponse::InternalServerError().json(backup_failed("Error saving backup file"))
+    })?;
+    Ok(ReceivedUpload { checksum, size, received_checksum, compressed, encrypted: false })
+}
+
+/// Upper bound for the JSON around `backup_data`
+const JSON_FIELDS_LIMIT: usize = 64 * 1024;
+
+#[derive(Debug, PartialEq)]
+enum JsonStage {
+    Head,
+    Data,
+    Tail,
+}
+
+/// The fields of a JSON backup body besides `backup_data` (the dealer comes from the token)
+#[derive(Debug, PartialEq, Deserialize)]
+struct JsonUploadFields {
+    checksum: Option<String>,
+    #[serde(default)]
+    compressed: bool,
+    #[serde(default)]
+    encrypted: bool,
+}
+
+/// Streaming reader for the desktop client's body
+/// `{"dealer_id":..,"backup_data":"<base64>","checksum":..,"compressed"|"encrypted":true}`.
+/// `backup_data` is decoded chunk by chunk as it arrives; the remaining fields are parsed from
+/// the text around it once the body has ended.
+struct JsonUpload {
+    stage: JsonStage,
+    head: Vec<u8>,
+    base64: Vec<u8>,
+    tail: Vec<u8>,
+}
+
+impl JsonUpload {
+    fn new() -> Self {
+        JsonUpload { stage: JsonStage::Head, head: Vec::new(), base64: Vec::new(), tail: Vec::new() }
     }
 
-    // Verify checksum if provided
-    let calculated_checksum = {
-        let mut hasher = Sha256::new();
-        hasher.update(&file_data);
-        hex::encode(hasher.finalize())
-    };
+    /// Position just after the opening quote of the `backup_data` value, once it is in `head`
+    fn data_start(head: &[u8]) -> Option<usize> {
+        const KEY: &[u8] = b"\"backup_data\"";
+        let key = head.windows(KEY.len()).position(|w| w == KEY)?;
+        let mut rest = head[key + KEY.len()..].iter().enumerate().filter(|(_, b)| !b.is_ascii_whitespace());
+        match (rest.next(), rest.next()) {
+            (Some((_, b':')), Some((i, b'"'))) => Some(key + KEY.len() + i + 1),
+            _ => None,
+        }
+    }
 
-    if let Some(ref recv_checksum) = received_checksum {
-        if recv_checksum != &calculated_checksum {
-            return HttpResponse::BadRequest().json(CloudBackupResponse {
-                success: false,
-                backup_id: None,
-                timestamp: None,
-                message: None,
-                error: Some("Checksum mismatch".to_string()),
-            });
+    /// Decode as much of the pending base64 as is complete into `out`
+    fn decode_pending(&mut self, out: &mut Vec<u8>, last: bool) -> Result<(), &'static str> {
+        let complete = if last { self.base64.len() } else { self.base64.len() / 4 * 4 };
+        base64::engine::general_purpose::STANDARD
+            .decode_vec(&self.base64[..complete], out)
+            .map_err(|_| "Invalid backup_data encoding")?;
+        self.base64.drain(..complete);
+        Ok(())
+    }
+
+    /// Feed the next body chunk; decoded backup bytes are appended to `out`
+    fn feed(&mut self, mut chunk: &[u8], out: &mut Vec<u8>) -> Result<(), &'static str> {
+        if self.stage == JsonStage::Head {
+            self.head.extend_from_slice(chunk);
+            let Some(start) = Self::data_start(&self.head) else {
+                if self.head.len() > JSON_FIELDS_LIMIT {
+                    return Err("No backup_data field");
+                }
+                return Ok(());
+            };
+            let data = self.head.split_off(start);
+            self.stage = JsonStage::Data;
+            return self.feed(&data, out);
         }
+        if self.stage == JsonStage::Data {
+            let end = chunk.iter().position(|&b| b == b'"');
+            let data = &chunk[..end.unwrap_or(chunk.len())];
+            if data.contains(&b'\\') {
+                return Err("Invalid backup_data encoding");
+            }
+            self.base64.extend_from_slice(data);
+            self.decode_pending(out, end.is_some())?;
+            let Some(end) = end else {
+                return Ok(());
+            };
+            self.stage = JsonStage::Tail;
+            chunk = &chunk[end..];
+        }
+        self.tail.extend_from_slice(chunk);
+        if self.tail.len() > JSON_FIELDS_LIMIT {
+            return Err("Invalid JSON body");
+        }
+        Ok(())
+    }
+
+    /// Fields of the complete body; `backup_data` reads as empty here
+    fn finish(self) -> Result<JsonUploadFields, &'static str> {
+        if self.stage != JsonStage::Tail {
+            return Err("No backup_data field");
+        }
+        let mut json = self.head;
+        json.extend_from_slice(&self.tail);
+        serde_json::from_slice(&json).map_err(|_| "Invalid JSON body")
+    }
+}
+
+/// JSON body as the desktop client streams it (see `JsonUpload`)
+async fn receive_json_upload(mut payload: web::Payload, upload_path: &Path) -> Result<ReceivedUpload, HttpResponse> {
+    let saving_failed = |e: std::io::Error| {
+        log::error!("Error saving backup: {}", e);
+        HttpResponse::InternalServerError().json(backup_failed("Error saving backup file"))
+    };
+    let mut writer = UploadWriter::create(upload_path).await.map_err(saving_failed)?;
+    let mut body = JsonUpload::new();
+    let mut decoded = Vec::new();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| {
+            log::error!("Error reading backup body: {}", e);
+            HttpResponse::BadRequest().json(backup_failed("Error reading file data"))
+        })?;
+        decoded.clear();
+        body.feed(&chunk, &mut decoded)
+            .map_err(|error| HttpResponse::BadRequest().json(backup_failed(error)))?;
+        writer.write_all(&decoded).await.map_err(saving_failed)?;
+    }
+
+    let fields = body.finish().map_err(|error| HttpResponse::BadRequest().json(backup_failed(error)))?;
+    if writer.written == 0 {
+        return Err(HttpResponse::BadRequest().json(backup_failed("No file data received")));
     }
+    let (checksum, size) = writer.finish().await.map_err(saving_failed)?;
+    Ok(ReceivedUpload {
+        checksum,
+        size,
+        received_checksum: fields.checksum,
+        compressed: fields.compressed,
+        encrypted: fields.encrypted,
+    })
+}
+
+/// POST /api/cloud/backup
+///
+/// Accepts the desktop client's JSON body (`application/json`, base64 `backup_data`) or a
+/// multipart form with the file.
+async fn cloud_backup(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    payload: web::Payload,
+) -> HttpResponse {
+    log::info!("Cloud backup request received");
+
+    if let Some(response) = rate_limited(&data, &[client_ip_key(&req)]) {
+        return response;
+    }
+
+    let dealer_id = match authenticate_dealer(&req, &data) {
+        Ok(dealer_id) => dealer_id,
+        Err((status, error)) => return HttpResponse::build(status).json(backup_failed(error)),
+    };
 
-    // Save backup file
+    // The upload goes straight to disk, hashed on the way; nothing is buffered in memory
     let backup_id = Uuid::new_v4().to_string();
+    let upload = TempFile(data.backup_dir.join(format!("{}.upload", backup_id)));
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let received = if is_json {
+        receive_json_upload(payload, &upload.0).await
+    } else {
+        receive_multipart_upload(Multipart::new(req.headers(), payload), &upload.0).await
+    };
+    let ReceivedUpload { checksum: upload_checksum, size: upload_size, received_checksum, compressed, encrypted } =
+        match received {
+            Ok(received) => received,
+            Err(response) => return response,
+        };
+
     let timestamp = Utc::now();
-    let file_name = format!("{}_{}.db", dealer_id, timestamp.format("%Y%m%d_%H%M%S"));
-    let file_path = data.backup_dir.join(&file_name);
-
-    match fs::write(&file_path, &file_data) {
-        Ok(_) => {
-            let backup_info = BackupInfo {
-                backup_id: backup_id.clone(),
-                dealer_id: dealer_id.clone(),
-                timestamp,
-                file_path: file_path.clone(),
-                file_size: file_data.len() as u64,
-                checksum: calculated_checksum,
-            };
+    // Backup id in the name so versions taken in the same second do not overwrite each other
+    let file_name = format!("{}_{}_{}.db", dealer_id, timestamp.format("%Y%m%d_%H%M%S"), backup_id);
+    let stored = TempFile(data.backup_dir.join(&file_name));
+
+    // Gzipped uploads are stored decompressed; the checksum covers the original database
+    let (calculated_checksum, file_size) = if compressed {
+        let (source, target) = (upload.0.clone(), stored.0.clone());
+        match web::block(move || decompress_to(&source, &target)).await {
+            Ok(Ok(result)) => {
+                let _ = tokio::fs::remove_file(&upload.0).await;
+                result
+            }
+            Ok(Err(e)) => {
+                log::error!("Error decompressing backup: {}", e);
+                return HttpResponse::UnsupportedMediaType()
+                    .json(backup_failed("Compressed backup could not be decompressed"));
+            }
+            Err(e) => {
+                log::error!("Error decompressing backup: {}", e);
+                return HttpResponse::InternalServerError().json(backup_failed("Error saving backup file"));
+            }
+        }
+    } else {
+        if let Err(e) = tokio::fs::rename(&upload.0, &stored.0).await {
+            log::error!("Error saving backup: {}", e);
+            return HttpResponse::InternalServerError().json(backup_failed("Error saving backup file"));
+        }
+        (upload_checksum, upload_size)
+    };
 
-            data.backups.lock().unwrap().insert(dealer_id, backup_info);
+    // Encrypted backups are stored as sent; their checksum covers the plain database, which only
+    // the client can verify after decrypting, so it is kept for the restore header as received
+    let calculated_checksum = match received_checksum {
+        Some(recv_checksum) if encrypted => recv_checksum,
+        Some(recv_checksum) if recv_checksum != calculated_checksum => {
+            return HttpResponse::BadRequest().json(backup_failed("Checksum mismatch"));
+        }
+        _ => calculated_checksum,
+    };
 
-            log::info!("Backup saved: {} ({} bytes)", file_path.display(), file_data.len());
+    let file_path = stored.keep();
+    let backup_info = BackupInfo {
+        backup_id: backup_id.clone(),
+        dealer_id: dealer_id.clone(),
+        timestamp,
+        file_path: file_path.clone(),
+        file_size,
+        checksum: calculated_checksum,
+    };
 
-            HttpResponse::Ok().json(CloudBackupResponse {
-                success: true,
-                backup_id: Some(backup_id),
-                timestamp: Some(timestamp.to_rfc3339()),
-                message: Some("Yedekleme basariyla tamamlandi".to_string()),
-                error: None,
-            })
+    let pruned: Vec<BackupInfo> = {
+        if let Err(e) = data.store.save_backup(&backup_info) {
+            log::error!("Could not persist backup {}: {}", backup_id, e);
         }
-        Err(e) => {
-            log::error!("Error saving backup: {}", e);
-            HttpResponse::InternalServerError().json(CloudBackupResponse {
-                success: false,
-                backup_id: None,
-                timestamp: None,
-                message: None,
-                error: Some("Error saving backup file".to_string()),
-            })
+        let mut backups = data.backups.lock().unwrap();
+        let versions = backups.entry(dealer_id).or_default();
+        versions.push(backup_info);
+
+        // Prune the oldest versions beyond retention
+        let excess = versions.len().saturating_sub(data.backup_retention);
+        versions.drain(..excess).collect()
+    };
+    // Files are removed after the lock is released
+    for old in pruned {
+        if let Err(e) = data.store.delete_backup(&old.backup_id) {
+            log::error!("Could not remove pruned backup {} from the index: {}", old.backup_id, e);
+        }
+        match tokio::fs::remove_file(&old.file_path).await {
+            Ok(_) => log::info!("Pruned backup {} ({})", old.backup_id, old.file_path.display()),
+            Err(e) => log::warn!("Could not remove pruned backup {}: {}", old.file_path.display(), e),
         }
     }
+
+    log::info!("Backup saved: {} ({} bytes)", file_path.display(), file_size);
+
+    HttpResponse::Ok().json(CloudBackupResponse {
+        success: true,
+        backup_id: Some(backup_id),
+        timestamp: Some(timestamp.to_rfc3339()),
+        message: Some("Yedekleme basariyla tamamlandi".to_string()),
+        error: None,
+    })
 }
 
 /// GET /api/cloud/restore
+///
+/// Restores the newest backup, or the version named in the optional `X-Backup-ID` header.
 async fn cloud_restore(req: HttpRequest, data: web::Data<AppState>) -> HttpResponse {
     log::info!("Cloud restore request received");
 
-    // Get dealer info from headers
-    let (dealer_id, license_key) = match get_dealer_from_headers(&req) {
-        Some(info) => info,
-        None => {
-            return HttpResponse::Unauthorized().body("Missing dealer credentials");
-        }
+    if let Some(response) = rate_limited(&data, &[client_ip_key(&req)]) {
+        return response;
+    }
+
+    let dealer_id = match authenticate_dealer(&req, &data) {
+        Ok(dealer_id) => dealer_id,
+        Err((status, error)) => return HttpResponse::build(status).body(error),
     };
 
-    // Verify license
-    {
-        let licenses = data.licenses.lock().unwrap();
-        match licenses.get(&license_key) {
-            Some(license) if license.is_active && license.dealer_id == dealer_id => {}
-            _ => {
-                return HttpResponse::Forbidden().body("Invalid license or dealer mismatch");
-            }
-        }
-    }
+    let requested_id = req
+        .headers()
+        .get("X-Backup-ID")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
 
     // Find backup for dealer
-    let backups = data.backups.lock().unwrap();
-    match backups.get(&dealer_id) {
+    let selected = {
+        let backups = data.backups.lock().unwrap();
+        let versions = backups.get(&dealer_id).map(|v| v.as_slice()).unwrap_or(&[]);
+        match &requested_id {
+            Some(id) => versions.iter().find(|b| &b.backup_id == id).cloned(),
+            None => versions.last().cloned(),
+        }
+    };
+    match selected {
         Some(backup_info) => {
-            match fs::read(&backup_info.file_path) {
-                Ok(file_data) => {
-                    log::info!(
-                        "Restoring backup for dealer {}: {} bytes",
-                        dealer_id,
-                        file_data.len()
-                    );
+            // Streamed from disk in chunks; the file is never held in memory as a whole
+            match file_stream(&backup_info.file_path).await {
+                Ok((len, stream)) => {
+                    log::info!("Restoring backup for dealer {}: {} bytes", dealer_id, len);
                     HttpResponse::Ok()
                         .content_type("application/octet-stream")
                         .insert_header(("X-Backup-ID", backup_info.backup_id.as_str()))
                         .insert_header(("X-Backup-Checksum", backup_info.checksum.as_str()))
-                        .body(file_data)
+                        .no_chunking(len)
+                        .streaming(stream)
                 }
                 Err(e) => {
                     log::error!("Error reading backup file: {}", e);
@@ -583,19 +1443,63 @@ async fn cloud_restore(req: HttpRequest, data: web::Data<AppState>) -> HttpRespo
                 }
             }
         }
-        None => HttpResponse::NotFound().body("No backup found for this dealer"),
+        None => match requested_id {
+            Some(id) => HttpResponse::NotFound().body(format!("Backup {} not found for this dealer", id)),
+            None => HttpResponse::NotFound().body("No backup found for this dealer"),
+        },
     }
 }
 
+/// GET /api/cloud/backups
+///
+/// Stored versions for the dealer, newest first.
+async fn cloud_backups(req: HttpRequest, data: web::Data<AppState>) -> HttpResponse {
+    log::info!("Cloud backup list request received");
+
+    if let Some(response) = rate_limited(&data, &[client_ip_key(&req)]) {
+        return response;
+    }
+
+    let dealer_id = match authenticate_dealer(&req, &data) {
+        Ok(dealer_id) => dealer_id,
+        Err((status, error)) => return HttpResponse::build(status).body(error),
+    };
+
+    let backups = data.backups.lock().unwrap();
+    let versions = backups
+        .get(&dealer_id)
+        .map(|versions| {
+            versions
+                .iter()
+                .rev()
+                .map(|b| BackupVersion {
+                    backup_id: b.backup_id.clone(),
+                    timestamp: b.timestamp.to_rfc3339(),
+                    size_bytes: b.file_size,
+                    checksum: b.checksum.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    HttpResponse::Ok().json(CloudBackupListResponse {
+        backups: versions,
+        retention: data.backup_retention,
+    })
+}
+
 /// GET /api/cloud/status
 async fn cloud_status(req: HttpRequest, data: web::Data<AppState>) -> HttpResponse {
     log::info!("Cloud status request received");
 
-    // Get dealer info from headers
-    let (dealer_id, license_key) = match get_dealer_from_headers(&req) {
-        Some(info) => info,
-        None => {
-            return HttpResponse::Unauthorized().json(CloudStatusResponse {
+    if let Some(response) = rate_limited(&data, &[client_ip_key(&req)]) {
+        return response;
+    }
+
+    let dealer_id = match authenticate_dealer(&req, &data) {
+        Ok(dealer_id) => dealer_id,
+        Err((status, _)) => {
+            return HttpResponse::build(status).json(CloudStatusResponse {
                 has_backup: false,
                 last_backup_at: None,
                 backup_size_bytes: None,
@@ -604,30 +1508,15 @@ async fn cloud_status(req: HttpRequest, data: web::Data<AppState>) -> HttpRespon
         }
     };
 
-    // Verify license
-    {
-        let licenses = data.licenses.lock().unwrap();
-        match licenses.get(&license_key) {
-            Some(license) if license.is_active && license.dealer_id == dealer_id => {}
-            _ => {
-                return HttpResponse::Forbidden().json(CloudStatusResponse {
-                    has_backup: false,
-                    last_backup_at: None,
-                    backup_size_bytes: None,
-                    backup_count: 0,
-                });
-            }
-        }
-    }
-
     // Get backup info
     let backups = data.backups.lock().unwrap();
-    match backups.get(&dealer_id) {
+    let versions = backups.get(&dealer_id).map(|v| v.as_slice()).unwrap_or(&[]);
+    match versions.last() {
         Some(backup_info) => HttpResponse::Ok().json(CloudStatusResponse {
             has_backup: true,
             last_backup_at: Some(backup_info.timestamp.to_rfc3339()),
             backup_size_bytes: Some(backup_info.file_size),
-            backup_count: 1,
+            backup_count: versions.len() as i32,
         }),
         None => HttpResponse::Ok().json(CloudStatusResponse {
             has_backup: false,
@@ -676,6 +1565,12 @@ async fn main() -> std::io::Result<()> {
     log::info!("  Nexus API Server - Mock Backend");
     log::info!("========================================");
     log::info!("Server starting on http://localhost:{}", port);
+    log::info!("Backup retention: {} per dealer", app_state.backup_retention);
+    log::info!(
+        "Rate limit: {} requests at once, {:.0} per minute per client",
+        app_state.rate_limiter.capacity,
+        app_state.rate_limiter.refill_per_sec * 60.0
+    );
     log::info!("");
     log::info!("Test Licenses:");
     log::info!("  - NEXUS-TEST-1234-5678 (Active, no expiry)");
@@ -684,10 +1579,13 @@ async fn main() -> std::io::Result<()> {
     log::info!("  - NEXUS-DEAD-0000-0000 (Deactivated)");
     log::info!("");
     log::info!("Endpoints:");
+    log::info!("  POST /api/auth/token");
     log::info!("  POST /api/license/validate");
     log::info!("  POST /api/license/activate");
+    log::info!("  POST /api/license/deactivate");
     log::info!("  POST /api/cloud/backup");
     log::info!("  GET  /api/cloud/restore");
+    log::info!("  GET  /api/cloud/backups");
     log::info!("  GET  /api/cloud/status");
     log::info!("  GET  /api/health");
     log::info!("  GET  /api/licenses (debug)");
@@ -704,18 +1602,516 @@ async fn main() -> std::io::Result<()> {
             .app_data(app_state.clone())
             .wrap(middleware::Logger::default())
             .wrap(cors)
-            // Health
+            // Health (not rate limited)
             .route("/api/health", web::get().to(health_check))
             // License endpoints
             .route("/api/license/validate", web::post().to(validate_license))
             .route("/api/license/activate", web::post().to(activate_license))
+            .route("/api/license/deactivate", web::post().to(deactivate_license))
             .route("/api/licenses", web::get().to(list_licenses))
-            // Cloud endpoints
+            // Auth
+            .route("/api/auth/token", web::post().to(issue_auth_token))
+            // Cloud endpoints (Authorization: Bearer <token from /api/auth/token>)
             .route("/api/cloud/backup", web::post().to(cloud_backup))
             .route("/api/cloud/restore", web::get().to(cloud_restore))
+            .route("/api/cloud/backups", web::get().to(cloud_backups))
             .route("/api/cloud/status", web::get().to(cloud_status))
     })
     .bind(("0.0.0.0", port))?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test;
+
+    fn post(uri: &str, license_key: &str, mac_address: &str) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri(uri)
+            .set_json(serde_json::json!({ "license_key": license_key, "mac_address": mac_address }))
+    }
+
+    /// State in a fresh temp dir, so tests neither share nor leave behind a database
+    fn temp_state() -> (AppState, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("nexus_api_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let state = AppState::open(&dir.join("api.db"), dir.join("backups")).unwrap();
+        (state, dir)
+    }
+
+    async fn status_and_body(response: actix_web::dev::ServiceResponse) -> (u16, serde_json::Value) {
+        let status = response.status().as_u16();
+        (status, test::read_body_json(response).await)
+    }
+
+    #[actix_web::test]
+    async fn test_deactivate_frees_activation_seat() {
+        let (state, dir) = temp_state();
+        let data = web::Data::new(state);
+        let app = test::init_service(
+            App::new()
+                .app_data(data.clone())
+                .route("/api/license/activate", web::post().to(activate_license))
+                .route("/api/license/deactivate", web::post().to(deactivate_license)),
+        )
+        .await;
+        let key = "NEXUS-TEST-1234-5678";
+
+        let response = test::call_service(&app, post("/api/license/activate", key, "aa:aa").to_request()).await;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Another device cannot free this device's seat
+        let response = test::call_service(&app, post("/api/license/deactivate", key, "bb:bb").to_request()).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["error"].as_str()), (403, Some("MAC_MISMATCH")));
+
+        let response = test::call_service(&app, post("/api/license/deactivate", key, "aa:aa").to_request()).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["success"].as_bool()), (200, Some(true)));
+        {
+            let licenses = data.licenses.lock().unwrap();
+            assert_eq!(licenses[key].current_activations, 0);
+            assert!(licenses[key].mac_address.is_none());
+        }
+
+        // The new PC can activate
+        let response = test::call_service(&app, post("/api/license/activate", key, "bb:bb").to_request()).await;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(data.licenses.lock().unwrap()[key].current_activations, 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[actix_web::test]
+    async fn test_deactivate_never_activated_license() {
+        let (state, dir) = temp_state();
+        let data = web::Data::new(state);
+        let app = test::init_service(
+            App::new()
+                .app_data(data.clone())
+                .route("/api/license/deactivate", web::post().to(deactivate_license)),
+        )
+        .await;
+
+        let key = "NEXUS-DEMO-ABCD-EFGH";
+        let response = test::call_service(&app, post("/api/license/deactivate", key, "aa:aa").to_request()).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["error"].as_str()), (409, Some("NOT_ACTIVATED")));
+        assert_eq!(data.licenses.lock().unwrap()[key].current_activations, 0);
+
+        let response = test::call_service(&app, post("/api/license/deactivate", "NEXUS-NONE", "aa:aa").to_request()).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["error"].as_str()), (404, Some("LICENSE_NOT_FOUND")));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    const BOUNDARY: &str = "nexus-test-boundary";
+
+    fn multipart_body(file: &[u8], checksum: &str, compressed: bool) -> Vec<u8> {
+        let mut body = Vec::new();
+        // The file comes first, so the compressed flag is only known after it was written out
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"backup.db\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+                BOUNDARY
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(file);
+        for (name, value) in [("checksum", checksum), ("compressed", if compressed { "true" } else { "false" })] {
+            body.extend_from_slice(
+                format!("\r\n--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}", BOUNDARY, name, value).as_bytes(),
+            );
+        }
+        body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+        body
+    }
+
+    fn bearer(token: &str) -> (header::HeaderName, String) {
+        (header::AUTHORIZATION, format!("Bearer {}", token))
+    }
+
+    fn upload(token: &str, body: Vec<u8>) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri("/api/cloud/backup")
+            .insert_header(bearer(token))
+            .insert_header(("Content-Type", format!("multipart/form-data; boundary={}", BOUNDARY)))
+            .set_payload(body)
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    #[actix_web::test]
+    async fn test_large_backup_streams_to_disk_and_back() {
+        let (state, dir) = temp_state();
+        let backup_dir = state.backup_dir.clone();
+        let (token, _) = issue_token(&state.token_secret, "dealer-001", Utc::now()).unwrap();
+        let data = web::Data::new(state);
+        let app = test::init_service(
+            App::new()
+                .app_data(data.clone())
+                .route("/api/cloud/backup", web::post().to(cloud_backup))
+                .route("/api/cloud/restore", web::get().to(cloud_restore)),
+        )
+        .await;
+
+        // 12 MiB, several hundred stream chunks; not very compressible
+        let mut seed: u32 = 0x2545_f491;
+        let database: Vec<u8> = (0..12 * 1024 * 1024)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                (seed >> 24) as u8
+            })
+            .collect();
+        let checksum = sha256_hex(&database);
+
+        let response = test::call_service(&app, upload(&token, multipart_body(&database, &checksum, false)).to_request()).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["success"].as_bool()), (200, Some(true)));
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gzip.write_all(&database).unwrap();
+        let gzipped = gzip.finish().unwrap();
+        let response = test::call_service(&app, upload(&token, multipart_body(&gzipped, &checksum, true)).to_request()).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["success"].as_bool()), (200, Some(true)));
+        let gzip_backup_id = body["backup_id"].as_str().unwrap().to_string();
+
+        // Rejected uploads leave nothing behind
+        let response = test::call_service(&app, upload(&token, multipart_body(&database, "bad", false)).to_request()).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["error"].as_str()), (400, Some("Checksum mismatch")));
+        let files: Vec<_> = fs::read_dir(&backup_dir).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(files.len(), 2, "{:?}", files);
+        {
+            let backups = data.backups.lock().unwrap();
+            let versions = &backups["dealer-001"];
+            assert!(versions.iter().all(|b| b.file_size == database.len() as u64 && b.checksum == checksum));
+            for version in versions {
+                assert_eq!(sha256_hex(&fs::read(&version.file_path).unwrap()), checksum);
+            }
+        }
+
+        // Restore is a sized stream, not a buffered body
+        let request = test::TestRequest::get().uri("/api/cloud/restore").insert_header(bearer(&token)).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.headers().get("X-Backup-ID").unwrap().to_str().unwrap(), gzip_backup_id);
+        assert_eq!(response.headers().get("X-Backup-Checksum").unwrap().to_str().unwrap(), checksum);
+        assert_eq!(
+            actix_web::body::MessageBody::size(response.response().body()),
+            actix_web::body::BodySize::Sized(database.len() as u64)
+        );
+        let restored = test::read_body(response).await;
+        assert_eq!(sha256_hex(&restored), checksum);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Body as the desktop client streams it (`Base64JsonUpload` in src-tauri/src/cloud/upload.rs)
+    fn client_json_body(file: &[u8], checksum: &str, flag: Option<&str>) -> Vec<u8> {
+        let flag = flag.map(|f| format!(",\"{}\":true", f)).unwrap_or_default();
+        format!(
+            "{{\"dealer_id\":\"dealer-001\",\"backup_data\":\"{}\",\"checksum\":\"{}\"{}}}",
+            base64::engine::general_purpose::STANDARD.encode(file),
+            checksum,
+            flag
+        )
+        .into_bytes()
+    }
+
+    #[actix_web::test]
+    async fn test_json_upload_decodes_across_chunk_boundaries() {
+        let file: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let body = client_json_body(&file, "abc123", Some("encrypted"));
+
+        for chunk_size in [1, 3, 7, 4096, body.len()] {
+            let mut upload = JsonUpload::new();
+            let mut decoded = Vec::new();
+            for chunk in body.chunks(chunk_size) {
+                upload.feed(chunk, &mut decoded).unwrap();
+            }
+            assert_eq!(decoded, file, "chunk size {}", chunk_size);
+            assert_eq!(
+                upload.finish().unwrap(),
+                JsonUploadFields { checksum: Some("abc123".to_string()), compressed: false, encrypted: true }
+            );
+        }
+
+        let mut upload = JsonUpload::new();
+        assert!(upload.feed(b"{\"dealer_id\":\"dealer-001\",\"backup_data\":\"QUJD*\"}", &mut Vec::new()).is_err());
+        let mut upload = JsonUpload::new();
+        upload.feed(b"{\"dealer_id\":\"dealer-001\"}", &mut Vec::new()).unwrap();
+        assert_eq!(upload.finish(), Err("No backup_data field"));
+    }
+
+    #[actix_web::test]
+    async fn test_client_json_backup_round_trip() {
+        let (state, dir) = temp_state();
+        let data = web::Data::new(state);
+        let app = test::init_service(
+            App::new()
+                .app_data(data.clone())
+                .route("/api/auth/token", web::post().to(issue_auth_token))
+                .route("/api/cloud/backup", web::post().to(cloud_backup))
+                .route("/api/cloud/restore", web::get().to(cloud_restore)),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri("/api/auth/token")
+            .set_json(serde_json::json!({ "license_key": "NEXUS-TEST-1234-5678", "dealer_id": "dealer-001" }))
+            .to_request();
+        let (_, body) = status_and_body(test::call_service(&app, request).await).await;
+        let token = body["token"].as_str().unwrap().to_string();
+        let json_upload = |body: Vec<u8>| {
+            test::TestRequest::post()
+                .uri("/api/cloud/backup")
+                .insert_header(bearer(&token))
+                .insert_header(("Content-Type", "application/json"))
+                .set_payload(body)
+                .to_request()
+        };
+        let restore = || test::TestRequest::get().uri("/api/cloud/restore").insert_header(bearer(&token)).to_request();
+
+        let database = b"SQLite format 3\0 plain database".repeat(500);
+        let checksum = sha256_hex(&database);
+
+        let response = test::call_service(&app, json_upload(client_json_body(&database, &checksum, None))).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["success"].as_bool()), (200, Some(true)), "{}", body);
+        let response = test::call_service(&app, restore()).await;
+        assert_eq!(response.headers().get("X-Backup-Checksum").unwrap().to_str().unwrap(), checksum);
+        assert_eq!(test::read_body(response).await, database);
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gzip.write_all(&database).unwrap();
+        let response = test::call_service(&app, json_upload(client_json_body(&gzip.finish().unwrap(), &checksum, Some("compressed")))).await;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(test::read_body(test::call_service(&app, restore()).await).await, database);
+
+        // Encrypted: stored as sent, restored with the plain database's checksum for the client to verify
+        let encrypted = b"NXENC1 opaque ciphertext".repeat(300);
+        let response = test::call_service(&app, json_upload(client_json_body(&encrypted, &checksum, Some("encrypted")))).await;
+        assert_eq!(response.status().as_u16(), 200);
+        let response = test::call_service(&app, restore()).await;
+        assert_eq!(response.headers().get("X-Backup-Checksum").unwrap().to_str().unwrap(), checksum);
+        assert_eq!(test::read_body(response).await, encrypted);
+
+        let response = test::call_service(&app, json_upload(client_json_body(&database, "bad", None))).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!((status, body["error"].as_str()), (400, Some("Checksum mismatch")));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[actix_web::test]
+    async fn test_license_key_exchanged_for_bearer_token() {
+        let (state, dir) = temp_state();
+        let data = web::Data::new(state);
+        let app = test::init_service(
+            App::new()
+                .app_data(data.clone())
+                .route("/api/auth/token", web::post().to(issue_auth_token))
+                .route("/api/cloud/status", web::get().to(cloud_status)),
+        )
+        .await;
+        let token_request = |license_key: &str, dealer_id: &str| {
+            test::TestRequest::post()
+                .uri("/api/auth/token")
+                .set_json(serde_json::json!({ "license_key": license_key, "dealer_id": dealer_id }))
+                .to_request()
+        };
+
+        let response = test::call_service(&app, token_request("NEXUS-TEST-1234-5678", "dealer-001")).await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["token_type"], "Bearer");
+        assert_eq!(body["expires_in"], TOKEN_TTL_MINUTES * 60);
+        let token = body["token"].as_str().unwrap().to_string();
+        assert!(!token.contains("NEXUS-TEST"));
+
+        // Wrong dealer, expired and deactivated licenses get no token
+        for (license_key, dealer_id) in [
+            ("NEXUS-TEST-1234-5678", "dealer-002"),
+            ("NEXUS-EXPR-1111-2222", "dealer-003"),
+            ("NEXUS-DEAD-0000-0000", "dealer-004"),
+        ] {
+            let response = test::call_service(&app, token_request(license_key, dealer_id)).await;
+            let (status, body) = status_and_body(response).await;
+            assert_eq!((status, body["token"].is_null()), (403, true), "{}", license_key);
+        }
+
+        let status_with = |authorization: Option<String>| {
+            let mut request = test::TestRequest::get().uri("/api/cloud/status");
+            if let Some(authorization) = authorization {
+                request = request.insert_header((header::AUTHORIZATION, authorization));
+            }
+            request.to_request()
+        };
+        let response = test::call_service(&app, status_with(Some(format!("Bearer {}", token)))).await;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // The old plaintext headers are no longer accepted
+        let request = test::TestRequest::get()
+            .uri("/api/cloud/status")
+            .insert_header(("X-Dealer-ID", "dealer-001"))
+            .insert_header(("X-License-Key", "NEXUS-TEST-1234-5678"))
+            .to_request();
+        assert_eq!(test::call_service(&app, request).await.status().as_u16(), 401);
+        assert_eq!(test::call_service(&app, status_with(None)).await.status().as_u16(), 401);
+
+        // Expired, forged and tampered tokens
+        let (expired, _) = issue_token(&data.token_secret, "dealer-001", Utc::now() - Duration::hours(1)).unwrap();
+        let (forged, _) = issue_token(b"not-the-server-secret", "dealer-001", Utc::now()).unwrap();
+        let (other_dealer, _) = issue_token(&data.token_secret, "dealer-002", Utc::now()).unwrap();
+        let mut parts: Vec<&str> = token.split('.').collect();
+        let other_claims = other_dealer.split('.').nth(1).unwrap();
+        parts[1] = other_claims;
+        let tampered = parts.join(".");
+        for bad in [expired, forged, tampered] {
+            let response = test::call_service(&app, status_with(Some(format!("Bearer {}", bad)))).await;
+            assert_eq!(response.status().as_u16(), 401);
+        }
+
+        // Deactivating the license locks out tokens already issued
+        data.licenses.lock().unwrap().get_mut("NEXUS-TEST-1234-5678").unwrap().is_active = false;
+        let response = test::call_service(&app, status_with(Some(format!("Bearer {}", token)))).await;
+        assert_eq!(response.status().as_u16(), 403);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[actix_web::test]
+    async fn test_rate_limit_bucket_refills_over_time() {
+        use std::time::Duration as StdDuration;
+
+        let limiter = RateLimiter::new(2, 30);
+        let start = Instant::now();
+        assert_eq!(limiter.check("ip:10.0.0.1", start), Ok(()));
+        assert_eq!(limiter.check("ip:10.0.0.1", start), Ok(()));
+        // 30 per minute: one request every 2 seconds
+        assert_eq!(limiter.check("ip:10.0.0.1", start), Err(2));
+        assert_eq!(limiter.check("ip:10.0.0.2", start), Ok(()));
+
+        assert_eq!(limiter.check("ip:10.0.0.1", start + StdDuration::from_secs(1)), Err(1));
+        assert_eq!(limiter.check("ip:10.0.0.1", start + StdDuration::from_secs(2)), Ok(()));
+        assert!(limiter.check("ip:10.0.0.1", start + StdDuration::from_secs(2)).is_err());
+
+        // Never refills beyond the burst size
+        let later = start + StdDuration::from_secs(3600);
+        assert_eq!(limiter.check("ip:10.0.0.1", later), Ok(()));
+        assert_eq!(limiter.check("ip:10.0.0.1", later), Ok(()));
+        assert!(limiter.check("ip:10.0.0.1", later).is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_license_endpoints_return_429_when_limit_exceeded() {
+        let (mut state, dir) = temp_state();
+        state.rate_limiter = RateLimiter::new(3, 1);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .route("/api/health", web::get().to(health_check))
+                .route("/api/license/validate", web::post().to(validate_license)),
+        )
+        .await;
+        let validate = |ip: &str, license_key: &str| {
+            post("/api/license/validate", license_key, "AA:BB:CC:DD:EE:FF")
+                .peer_addr(format!("{}:40000", ip).parse().unwrap())
+                .to_request()
+        };
+
+        for _ in 0..3 {
+            let response = test::call_service(&app, validate("10.0.0.1", "NEXUS-GUESS-0000-0001")).await;
+            assert_eq!(response.status().as_u16(), 404);
+        }
+        let response = test::call_service(&app, validate("10.0.0.1", "NEXUS-GUESS-0000-0002")).await;
+        assert_eq!(response.status().as_u16(), 429);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap().to_str().unwrap(), "60");
+        let (_, body) = status_and_body(response).await;
+        assert_eq!(body["error"], "RATE_LIMITED");
+
+        // The guessed key is limited from other addresses too; other keys are not
+        let response = test::call_service(&app, validate("10.0.0.2", "NEXUS-GUESS-0000-0001")).await;
+        assert_eq!(response.status().as_u16(), 429);
+        let response = test::call_service(&app, validate("10.0.0.3", "NEXUS-TEST-1234-5678")).await;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Health checks are never limited
+        for _ in 0..10 {
+            let request = test::TestRequest::get()
+                .uri("/api/health")
+                .peer_addr("10.0.0.1:40000".parse().unwrap())
+                .to_request();
+            assert_eq!(test::call_service(&app, request).await.status().as_u16(), 200);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[actix_web::test]
+    async fn test_activation_and_backups_survive_restart() {
+        let (state, dir) = temp_state();
+        let (token, _) = issue_token(&state.token_secret, "dealer-001", Utc::now()).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .route("/api/license/activate", web::post().to(activate_license))
+                .route("/api/cloud/backup", web::post().to(cloud_backup)),
+        )
+        .await;
+        let key = "NEXUS-TEST-1234-5678";
+
+        let response = test::call_service(&app, post("/api/license/activate", key, "aa:aa").to_request()).await;
+        assert_eq!(response.status().as_u16(), 200);
+        let database = b"SQLite format 3\0 restart test".to_vec();
+        let response =
+            test::call_service(&app, upload(&token, multipart_body(&database, &sha256_hex(&database), false)).to_request())
+                .await;
+        let (status, body) = status_and_body(response).await;
+        assert_eq!(status, 200);
+        let backup_id = body["backup_id"].as_str().unwrap().to_string();
+        drop(app);
+
+        // "Restart": a new state from the same database file
+        let restarted = AppState::open(&dir.join("api.db"), dir.join("backups")).unwrap();
+        {
+            let licenses = restarted.licenses.lock().unwrap();
+            assert_eq!(licenses[key].current_activations, 1);
+            assert_eq!(licenses[key].mac_address.as_deref(), Some("aa:aa"));
+            assert_eq!(licenses.len(), 4);
+        }
+        let backups = restarted.backups.lock().unwrap();
+        let versions = &backups["dealer-001"];
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].backup_id, backup_id);
+        assert_eq!(versions[0].checksum, sha256_hex(&database));
+        drop(backups);
+
+        // Without the database the index is rebuilt from the stored files
+        fs::remove_file(dir.join("api.db")).unwrap();
+        fs::write(dir.join("backups").join("dealer-002_20240105_093000.db"), b"legacy").unwrap();
+        fs::write(dir.join("backups").join("unrelated.txt"), b"x").unwrap();
+        let rebuilt = AppState::open(&dir.join("api.db"), dir.join("backups")).unwrap();
+        let backups = rebuilt.backups.lock().unwrap();
+        assert_eq!(backups["dealer-001"][0].backup_id, backup_id);
+        assert_eq!(backups["dealer-001"][0].file_size, database.len() as u64);
+        let legacy = &backups["dealer-002"][0];
+        assert_eq!(legacy.timestamp.to_rfc3339(), "2024-01-05T09:30:00+00:00");
+        assert_eq!(legacy.checksum, sha256_hex(b"legacy"));
+        assert_eq!(backups.len(), 2);
+        // The seeded licenses start over; activations are lost only with the database itself
+        assert_eq!(rebuilt.licenses.lock().unwrap()[key].current_activations, 0);
+        drop(backups);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
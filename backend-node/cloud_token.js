@@ -0,0 +1,31 @@
+const jwt = require('jsonwebtoken');
+
+// Bulut çağrıları için kısa ömürlü bayi token'ı (POST /api/auth/token ile alınır).
+// Admin oturum token'larından ayrı bir anahtarla imzalanır; biri diğerinin yerine geçemez.
+
+const CLOUD_TOKEN_TTL_SECONDS = 15 * 60;
+
+const cloudTokenSecret = () => {
+    if (process.env.CLOUD_TOKEN_SECRET) return process.env.CLOUD_TOKEN_SECRET;
+    if (!process.env.JWT_SECRET) throw new Error('JWT_SECRET environment variable is required');
+    return `${process.env.JWT_SECRET}:cloud`;
+};
+
+// Süresi geçmiş lisans (expires_at NULL ise süresiz)
+const licenseExpired = (license) =>
+    license.expires_at != null && new Date(license.expires_at) <= new Date();
+
+// Token for an active license row; returns { token, expires_in }
+const issueCloudToken = (license) => {
+    const token = jwt.sign(
+        { dealer_id: license.dealer_id, license_id: license.id },
+        cloudTokenSecret(),
+        { expiresIn: CLOUD_TOKEN_TTL_SECONDS, audience: 'cloud' }
+    );
+    return { token, expires_in: CLOUD_TOKEN_TTL_SECONDS };
+};
+
+// Claims of a valid token; throws when it is forged, expired or not a cloud token
+const verifyCloudToken = (token) => jwt.verify(token, cloudTokenSecret(), { audience: 'cloud' });
+
+module.exports = { CLOUD_TOKEN_TTL_SECONDS, licenseExpired, issueCloudToken, verifyCloudToken };
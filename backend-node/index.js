@@ -1,62 +1,9 @@
-const express = require('express');
-const cors = require('cors');
-const helmet = require('helmet');
-const rateLimit = require('express-rate-limit');
-
 // Load environment variables
 require('dotenv').config();
 
-const app = express();
-const PORT = process.env.PORT || 3000;
-
-// Security Middleware
-app.use(helmet());
-
-// CORS Configuration - Production Security
-const allowedOrigins = [
-  'https://nisayapimarket.com',
-  'https://www.nisayapimarket.com',
-  'http://localhost:3002',      // Web admin dev
-  'http://127.0.0.1:3002',
-  'tauri://localhost',           // Tauri desktop app
-  'http://tauri.localhost'
-];
-
-app.use(cors({
-  origin: function (origin, callback) {
-    // Allow requests with no origin (like mobile apps, Postman, curl)
-    if (!origin) return callback(null, true);
-
-    // Development mode - allow localhost
-    if (process.env.NODE_ENV === 'development') {
-      return callback(null, true);
-    }
-
-    // Production mode - check whitelist
-    if (allowedOrigins.includes(origin)) {
-      callback(null, true);
-    } else {
-      console.warn(`CORS blocked: ${origin}`);
-      callback(new Error('Not allowed by CORS'));
-    }
-  },
-  credentials: true,
-  methods: ['GET', 'POST', 'PUT', 'DELETE', 'OPTIONS', 'PATCH'],
-  allowedHeaders: ['Content-Type', 'Authorization', 'X-Requested-With', 'Accept', 'Origin']
-}));
-
-// Handle preflight requests explicitly
-app.options('*', cors());
-
-app.use(express.json({ limit: '10mb' })); // Support larger payloads for sync
+const { createApp } = require('./app');
 
-// Rate Limiting (Basic DDoS protection)
-const limiter = rateLimit({
-  windowMs: 15 * 60 * 1000, // 15 minutes
-  max: 100, // limit each IP to 100 requests per windowMs
-  message: 'Too many requests from this IP, please try again later.'
-});
-app.use(limiter);
+const PORT = process.env.PORT || 3000;
 
 // Database Connection Pool
 const mysql = require('mysql2/promise');
@@ -86,31 +33,6 @@ pool.getConnection()
     console.error("Database connection failed:", err);
   });
 
-// Middleware to make DB pool available in routes
-app.use((req, res, next) => {
-  req.db = pool;
-  next();
-});
-
-// Basic Route
-app.get('/', (req, res) => {
-  res.json({ message: 'Nexus Inventory API is running secure and fast' });
-});
-
-// Health check endpoint
-app.get('/api/health', (req, res) => {
-  res.json({ status: 'ok', timestamp: new Date().toISOString() });
-});
-
-// Routes (Will be imported later)
-app.use('/api/auth', require('./routes/auth'));
-app.use('/api/dealers', require('./routes/dealers'));
-app.use('/api/dashboard', require('./routes/dashboard'));
-app.use('/api/license', require('./routes/license'));
-app.use('/api/sync', require('./routes/sync'));
-app.use('/api/cloud', require('./routes/sync'));
-app.use('/api/inventory', require('./routes/inventory'));
-
-app.listen(PORT, () => {
+createApp(pool).listen(PORT, () => {
   console.log(`Server is running on port ${PORT}`);
 });
@@ -30,7 +30,7 @@ CREATE TABLE IF NOT EXISTS sync_transactions (
     action_type ENUM('SALE', 'STOCK_IN', 'STOCK_OUT', 'PRICE_CHANGE', 'ITEM_CREATE', 'ITEM_UPDATE', 'ITEM_DELETE') NOT NULL,
     item_sku VARCHAR(50),
     item_name VARCHAR(255),
-    quantity_change INT DEFAULT 0,
+    quantity_change DECIMAL(12, 3) DEFAULT 0,
     old_value DECIMAL(10, 2),
     new_value DECIMAL(10, 2),
     metadata JSON,
@@ -2,6 +2,7 @@ const express = require('express');
 const router = express.Router();
 const bcrypt = require('bcryptjs');
 const jwt = require('jsonwebtoken');
+const { issueCloudToken, licenseExpired } = require('../cloud_token');
 
 // JWT Secret - Environment variable zorunlu
 const JWT_SECRET = process.env.JWT_SECRET;
@@ -65,4 +66,37 @@ router.post('/login', async (req, res) => {
     }
 });
 
+// Cloud Token Route
+// Masaüstü uygulaması lisans anahtarını burada kısa ömürlü bir bulut token'ı ile değiştirir;
+// /api/cloud çağrıları bu token ile (Authorization: Bearer) yapılır.
+router.post('/token', async (req, res) => {
+    const { license_key, dealer_id } = req.body;
+    const db = req.db;
+
+    if (!license_key || !dealer_id) {
+        return res.status(400).json({ success: false, error: 'Lisans bilgisi eksik' });
+    }
+
+    try {
+        const [rows] = await db.execute(
+            `SELECT * FROM licenses WHERE license_key = ? AND dealer_id = ? AND is_active = 1`,
+            [license_key, dealer_id]
+        );
+
+        if (rows.length === 0) {
+            return res.status(403).json({ success: false, error: 'Lisans geçersiz veya bayi eşleşmiyor' });
+        }
+        if (licenseExpired(rows[0])) {
+            return res.status(403).json({ success: false, error: 'Lisans süresi dolmuş' });
+        }
+
+        const { token, expires_in } = issueCloudToken(rows[0]);
+        res.json({ success: true, token_type: 'Bearer', token, expires_in });
+
+    } catch (error) {
+        console.error('Token error:', error);
+        res.status(500).json({ success: false, error: 'Sunucu hatası' });
+    }
+});
+
 module.exports = router;
@@ -1,16 +1,50 @@
 const express = require('express');
+const zlib = require('zlib');
 const router = express.Router();
+const { licenseExpired, verifyCloudToken } = require('../cloud_token');
 
 // Helper to get remote IP
 const getIp = (req) => req.headers['x-forwarded-for'] || req.socket.remoteAddress;
 
 // Middleware: Lisans ve Bayi Doğrulama
-// Yedekleme yapabilmek için geçerli bir lisans anahtarı ve bayi ID'si şart.
+// Yedekleme yapabilmek için geçerli bir lisans şart: ya /api/auth/token ile alınan bearer token
+// ya da (eski istemciler) lisans anahtarı ve bayi ID'si.
 const requireLicense = async (req, res, next) => {
+    const db = req.db;
+    const authHeader = req.headers.authorization || '';
+
+    if (authHeader.startsWith('Bearer ')) {
+        let claims;
+        try {
+            claims = verifyCloudToken(authHeader.slice('Bearer '.length).trim());
+        } catch (error) {
+            return res.status(401).json({ success: false, message: 'Geçersiz veya süresi dolmuş token' });
+        }
+
+        try {
+            // Lisans iptal edilirse son token'ın süresini beklemeden erişim kesilir
+            const [rows] = await db.execute(
+                `SELECT * FROM licenses WHERE id = ? AND dealer_id = ? AND is_active = 1`,
+                [claims.license_id, claims.dealer_id]
+            );
+
+            if (rows.length === 0 || licenseExpired(rows[0])) {
+                return res.status(403).json({ success: false, message: 'Yetkisiz erişim: Lisans geçersiz' });
+            }
+
+            req.license = rows[0];
+            // Bayi token'dan gelir; gövdedeki dealer_id başka bir bayiye yönlendiremez
+            req.body.dealer_id = claims.dealer_id;
+            return next();
+        } catch (error) {
+            console.error('Auth middleware error:', error);
+            return res.status(500).json({ success: false, message: 'Sunucu hatası' });
+        }
+    }
+
     // Check body first, then headers (headers are lowercase in Express)
     const license_key = req.body.license_key || req.headers['x-license-key'];
     const dealer_id = req.body.dealer_id || req.headers['x-dealer-id'];
-    const db = req.db;
 
     if (!license_key || !dealer_id) {
         return res.status(401).json({ success: false, message: 'Lisans bilgisi eksik' });
@@ -67,13 +101,24 @@ router.get('/list', async (req, res) => {
 
 // 1. Upload Backup (Yedek Gönder)
 router.post('/backup', requireLicense, async (req, res) => {
-    const { dealer_id, backup_data } = req.body; // backup_data: Base64 encoded or JSON string
+    const { dealer_id, compressed } = req.body;
+    let { backup_data } = req.body; // backup_data: Base64 encoded or JSON string
     const db = req.db;
 
     if (!backup_data) {
         return res.status(400).json({ success: false, message: 'Yedek verisi yok' });
     }
 
+    // Gzip'li yedek açılıp düz Base64 olarak saklanır (geri yükleme ve web tarafı değişmez)
+    if (compressed === true) {
+        try {
+            backup_data = zlib.gunzipSync(Buffer.from(backup_data, 'base64')).toString('base64');
+        } catch (error) {
+            console.error('Backup decompress error:', error);
+            return res.status(415).json({ success: false, message: 'Sıkıştırılmış yedek açılamadı' });
+        }
+    }
+
     // Boyut hesapla (yaklaşık)
     const sizeBytes = Buffer.byteLength(backup_data, 'utf8');
 
@@ -166,8 +211,8 @@ const handleRestore = async (req, res) => {
 router.post('/restore', requireLicense, handleRestore);
 router.get('/restore', requireLicense, handleRestore);
 
-// 3. Status Check (Son yedek ne zaman alındı?)
-router.post('/status', requireLicense, async (req, res) => {
+// 3. Status Check (Son yedek ne zaman alındı?) - POST (Web) and GET (Rust)
+const handleStatus = async (req, res) => {
     const { dealer_id } = req.body;
     const db = req.db;
 
@@ -194,7 +239,10 @@ router.post('/status', requireLicense, async (req, res) => {
         console.error('Status check error:', error);
         res.status(500).json({ success: false, message: 'Durum kontrolü hatası' });
     }
-});
+};
+
+router.post('/status', requireLicense, handleStatus);
+router.get('/status', requireLicense, handleStatus);
 
 // Helper Function for Logs
 async function logActivity(db, dealerId, type, desc, ip) {
@@ -380,9 +428,10 @@ router.post('/transactions/pull', requireLicense, async (req, res) => {
 
         const [transactions] = await db.execute(query, params);
 
-        // Parse metadata JSON
+        // Parse metadata JSON; DECIMAL columns come back as strings
         const formatted = transactions.map(txn => ({
             ...txn,
+            quantity_change: Number(txn.quantity_change),
             metadata: txn.metadata ? JSON.parse(txn.metadata) : null
         }));
 
@@ -548,7 +597,7 @@ router.get('/transactions', requireAuth, async (req, res) => {
             actionType: txn.action_type,
             itemSku: txn.item_sku,
             itemName: txn.item_name,
-            quantityChange: txn.quantity_change,
+            quantityChange: Number(txn.quantity_change),
             oldValue: txn.old_value,
             newValue: txn.new_value,
             metadata: txn.metadata ? JSON.parse(txn.metadata) : null,
@@ -615,7 +664,7 @@ router.get('/stats', requireAuth, async (req, res) => {
             onlineDevices,
             offlineDevices: totalDevices - onlineDevices,
             todayTransactions,
-            todaySales
+            todaySales: Number(todaySales)
         });
 
     } catch (error) {
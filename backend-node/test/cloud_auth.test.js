@@ -0,0 +1,149 @@
+// Bulut yedeği: masaüstü istemcisinin (src-tauri/src/cloud) sunucuyla yaptığı konuşmanın aynısı.
+// Token alınır, yedek bearer token ile yüklenir, durum sorulur ve yedek geri indirilir.
+// MySQL yerine bellekteki bir tablo kullanılır. Çalıştırma: npm test
+
+process.env.JWT_SECRET = process.env.JWT_SECRET || 'test-secret-with-at-least-32-characters';
+
+const { test } = require('node:test');
+const assert = require('node:assert');
+const crypto = require('node:crypto');
+const jwt = require('jsonwebtoken');
+const { createApp } = require('../app');
+
+// Only the queries the cloud routes run
+const fakeDb = () => {
+    const licenses = [
+        { id: 1, license_key: 'LIC-AAAA', dealer_id: 'dealer-1', is_active: 1, expires_at: null },
+        { id: 2, license_key: 'LIC-BBBB', dealer_id: 'dealer-2', is_active: 0, expires_at: null },
+    ];
+    const backups = [];
+    const execute = async (sql, params = []) => {
+        if (sql.includes('FROM licenses WHERE license_key = ?')) {
+            const [key, dealer] = params;
+            return [licenses.filter(l => l.license_key === key && l.dealer_id === dealer && l.is_active)];
+        }
+        if (sql.includes('FROM licenses WHERE id = ?')) {
+            const [id, dealer] = params;
+            return [licenses.filter(l => l.id === id && l.dealer_id === dealer && l.is_active)];
+        }
+        if (sql.startsWith('DELETE FROM cloud_backups')) {
+            backups.splice(0, backups.length, ...backups.filter(b => b.dealer_id !== params[0]));
+            return [{}];
+        }
+        if (sql.includes('INSERT INTO cloud_backups')) {
+            const [dealer_id, backup_data, size_bytes] = params;
+            backups.push({ dealer_id, backup_data, size_bytes, created_at: new Date() });
+            return [{ insertId: backups.length }];
+        }
+        if (sql.includes('FROM cloud_backups WHERE dealer_id = ?')) {
+            return [backups.filter(b => b.dealer_id === params[0])];
+        }
+        if (sql.includes('INSERT INTO remote_activity_log')) {
+            return [{}];
+        }
+        throw new Error(`Unexpected query: ${sql}`);
+    };
+    return { execute, licenses, backups };
+};
+
+const startServer = async (db) => {
+    const server = createApp(db).listen(0);
+    await new Promise(resolve => server.once('listening', resolve));
+    return { server, baseUrl: `http://127.0.0.1:${server.address().port}` };
+};
+
+// POST /api/auth/token as cloud/auth.rs `fetch_token` sends it
+const fetchToken = (baseUrl, license_key, dealer_id) =>
+    fetch(`${baseUrl}/api/auth/token`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ license_key, dealer_id }),
+    });
+
+test('desktop client backs up and restores with a bearer token', async (t) => {
+    const db = fakeDb();
+    const { server, baseUrl } = await startServer(db);
+    t.after(() => server.close());
+
+    const tokenResponse = await fetchToken(baseUrl, 'LIC-AAAA', 'dealer-1');
+    assert.strictEqual(tokenResponse.status, 200);
+    const issued = await tokenResponse.json();
+    assert.strictEqual(issued.success, true);
+    assert.strictEqual(issued.expires_in, 15 * 60);
+    const auth = { Authorization: `Bearer ${issued.token}` };
+
+    // Body as cloud/upload.rs `Base64JsonUpload::encrypted` streams it; the key is not in it
+    const encrypted = crypto.randomBytes(4096);
+    const checksum = crypto.createHash('sha256').update('plain database').digest('hex');
+    const body = `{"dealer_id":"dealer-1","backup_data":"${encrypted.toString('base64')}","checksum":"${checksum}","encrypted":true}`;
+    const upload = await fetch(`${baseUrl}/api/cloud/backup`, {
+        method: 'POST',
+        headers: { ...auth, 'Content-Type': 'application/json' },
+        body,
+    });
+    assert.strictEqual(upload.status, 200);
+    assert.strictEqual((await upload.json()).success, true);
+
+    const status = await fetch(`${baseUrl}/api/cloud/status`, { headers: auth });
+    assert.strictEqual(status.status, 200);
+    assert.strictEqual((await status.json()).has_backup, true);
+
+    const restore = await fetch(`${baseUrl}/api/cloud/restore`, { headers: auth });
+    assert.strictEqual(restore.status, 200);
+    assert.deepStrictEqual(Buffer.from(await restore.arrayBuffer()), encrypted);
+});
+
+test('token is bound to its dealer and license', async (t) => {
+    const db = fakeDb();
+    const { server, baseUrl } = await startServer(db);
+    t.after(() => server.close());
+
+    const wrongKey = await fetchToken(baseUrl, 'LIC-XXXX', 'dealer-1');
+    assert.strictEqual(wrongKey.status, 403);
+    assert.strictEqual((await wrongKey.json()).success, false);
+    assert.strictEqual((await fetchToken(baseUrl, 'LIC-BBBB', 'dealer-2')).status, 403);
+
+    const { token } = await (await fetchToken(baseUrl, 'LIC-AAAA', 'dealer-1')).json();
+    // Another dealer id in the body does not redirect the backup
+    const upload = await fetch(`${baseUrl}/api/cloud/backup`, {
+        method: 'POST',
+        headers: { Authorization: `Bearer ${token}`, 'Content-Type': 'application/json' },
+        body: JSON.stringify({ dealer_id: 'dealer-2', backup_data: Buffer.from('db').toString('base64') }),
+    });
+    assert.strictEqual(upload.status, 200);
+    assert.deepStrictEqual(db.backups.map(b => b.dealer_id), ['dealer-1']);
+
+    // A deactivated license loses access before the token runs out
+    db.licenses[0].is_active = 0;
+    const revoked = await fetch(`${baseUrl}/api/cloud/status`, { headers: { Authorization: `Bearer ${token}` } });
+    assert.strictEqual(revoked.status, 403);
+});
+
+test('forged, expired and admin tokens are rejected', async (t) => {
+    const db = fakeDb();
+    const { server, baseUrl } = await startServer(db);
+    t.after(() => server.close());
+
+    const claims = { dealer_id: 'dealer-1', license_id: 1 };
+    const tokens = [
+        jwt.sign(claims, 'some-other-secret', { expiresIn: 900, audience: 'cloud' }),
+        jwt.sign({ ...claims, exp: Math.floor(Date.now() / 1000) - 60 }, `${process.env.JWT_SECRET}:cloud`, { audience: 'cloud' }),
+        // Admin panel session token: signed with JWT_SECRET itself
+        jwt.sign({ id: 1, username: 'admin', role: 'admin' }, process.env.JWT_SECRET, { expiresIn: '24h' }),
+    ];
+    for (const token of tokens) {
+        const response = await fetch(`${baseUrl}/api/cloud/restore`, { headers: { Authorization: `Bearer ${token}` } });
+        assert.strictEqual(response.status, 401);
+    }
+});
+
+test('license key headers still work for older clients', async (t) => {
+    const db = fakeDb();
+    const { server, baseUrl } = await startServer(db);
+    t.after(() => server.close());
+
+    const headers = { 'X-License-Key': 'LIC-AAAA', 'X-Dealer-ID': 'dealer-1' };
+    const status = await fetch(`${baseUrl}/api/cloud/status`, { headers });
+    assert.strictEqual(status.status, 200);
+    assert.strictEqual((await status.json()).has_backup, false);
+});
@@ -24,6 +24,14 @@ async function updateDb() {
             }
         }
 
+        // Measured units (KG, MT) sync fractional stock deltas
+        try {
+            await connection.execute('ALTER TABLE sync_transactions MODIFY quantity_change DECIMAL(12, 3) DEFAULT 0');
+            console.log('sync_transactions.quantity_change is now DECIMAL(12, 3).');
+        } catch (e) {
+            console.error('Error changing quantity_change column:', e);
+        }
+
     } catch (error) {
         console.error('Update DB error:', error);
     } finally {
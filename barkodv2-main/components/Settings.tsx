@@ -170,7 +170,11 @@ const Settings: React.FC = () => {
         setIsSyncing(true);
         setSyncMessage('');
         try {
-            await cloudService.restore();
+            let result = await cloudService.restore();
+            if (!result.restored) {
+                if (!confirm(`${result.comparison.warnings.join(', ')}.\nYine de geri yuklemek istiyor musunuz?`)) return;
+                result = await cloudService.restore(true);
+            }
             setSyncMessage('Cloud geri yukleme basarili!');
             // Refresh all data without page reload
             triggerRefresh();
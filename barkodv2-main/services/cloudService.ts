@@ -1,5 +1,5 @@
 import { tauriInvoke, isTauri } from './tauriService';
-import { SyncStatus, CloudBackupResponse, CloudStatusResponse } from '../types';
+import { SyncStatus, CloudBackupResponse, CloudStatusResponse, CloudRestoreResult } from '../types';
 
 class CloudService {
   /**
@@ -18,14 +18,29 @@ class CloudService {
   }
 
   /**
-   * Restore database from cloud
+   * Restore database from cloud.
+   * If the cloud backup is older or smaller than local data, nothing is restored
+   * (restored=false) until called again with force=true.
    */
-  async restore(): Promise<void> {
+  async restore(force = false): Promise<CloudRestoreResult> {
     if (!isTauri()) {
       console.log('Development mode - restore simulated');
-      return;
+      const empty = { transactionCount: 0, itemCount: 0, accountCount: 0 };
+      return {
+        restored: true,
+        comparison: {
+          local: empty,
+          cloud: empty,
+          transactionDifference: 0,
+          itemDifference: 0,
+          accountDifference: 0,
+          requiresForce: false,
+          warnings: [],
+        },
+        message: 'Development mode - restore simulated',
+      };
     }
-    return tauriInvoke<void>('cloud_restore');
+    return tauriInvoke<CloudRestoreResult>('cloud_restore', { force });
   }
 
   /**
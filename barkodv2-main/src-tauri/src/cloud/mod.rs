@@ -1,11 +1,24 @@
 use crate::error::AppError;
+use crate::services::http;
 use crate::models::{CloudBackupResponse, CloudStatusResponse, SyncStatus};
 use reqwest::blocking::multipart;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OpenFlags};
 use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::Read;
 
+// Shared with the main app; each app supplies `open_read_only`
+#[path = "../../../../src-tauri/src/cloud/reconcile.rs"]
+pub mod reconcile;
+pub mod upload;
+
+pub use reconcile::{BackupComparison, CloudRestoreResult, DatabaseSnapshot};
+
+/// Open a database file read-only (used by `reconcile`)
+pub(crate) fn open_read_only(path: &std::path::Path) -> rusqlite::Result<Connection> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+}
+
 /// Calculate SHA256 checksum of a file
 pub fn calculate_checksum(file_path: &str) -> Result<String, AppError> {
     let mut file = File::open(file_path)
@@ -39,7 +52,7 @@ pub fn backup_to_cloud(
     conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
         .map_err(|e| AppError::Internal(format!("WAL checkpoint basarisiz: {}", e)))?;
 
-    // Calculate checksum first
+    // Calculate checksum first (read in 8 KB chunks, not buffered)
     let checksum = calculate_checksum(db_path)?;
 
     // Get file size
@@ -47,32 +60,31 @@ pub fn backup_to_cloud(
         .map_err(|e| AppError::Internal(format!("Dosya boyutu alinamadi: {}", e)))?;
     let file_size = metadata.len() as i64;
 
-    // Create multipart form
-    let file = File::open(db_path)
-        .map_err(|e| AppError::Internal(format!("Dosya acilamadi: {}", e)))?;
-
-    let file_part = multipart::Part::reader(file)
-        .file_name("inventory.db")
-        .mime_str("application/x-sqlite3")
-        .map_err(|e| AppError::Internal(format!("MIME type ayarlanamadi: {}", e)))?;
-
-    let form = multipart::Form::new()
-        .text("dealer_id", dealer_id.to_string())
-        .text("license_key", license_key.to_string())
-        .text("checksum", checksum)
-        .part("file", file_part);
+    // File part is streamed from disk; the form is rebuilt for every attempt
+    let build_form = || {
+        let file_part = || {
+            multipart::Part::reader_with_length(upload::LazyFileReader::new(db_path), file_size as u64)
+                .file_name("inventory.db")
+        };
+        multipart::Form::new()
+            .text("dealer_id", dealer_id.to_string())
+            .text("license_key", license_key.to_string())
+            .text("checksum", checksum.clone())
+            .part("file", file_part().mime_str("application/x-sqlite3").unwrap_or_else(|_| file_part()))
+    };
 
     let client = reqwest::blocking::Client::new();
     let url = format!("{}/api/cloud/backup", api_base_url);
 
-    let response = client
-        .post(&url)
-        .header("X-Dealer-ID", dealer_id)
-        .header("X-License-Key", license_key)
-        .multipart(form)
-        .timeout(std::time::Duration::from_secs(120))
-        .send()
-        .map_err(|e| AppError::Internal(format!("Yedekleme istegi basarisiz: {}", e)))?;
+    let response = http::send_with_retry(&url, || {
+        client
+            .post(&url)
+            .header("X-Dealer-ID", dealer_id)
+            .header("X-License-Key", license_key)
+            .multipart(build_form())
+            .timeout(std::time::Duration::from_secs(120))
+    })
+    .map_err(|e| AppError::Internal(format!("Yedekleme istegi basarisiz: {}", e)))?;
 
     if response.status().is_success() {
         let mut result: CloudBackupResponse = response
@@ -99,53 +111,34 @@ pub fn backup_to_cloud(
     }
 }
 
-/// Restore database from cloud using SQLite backup API
-/// This restores data into the existing connection without requiring app restart
-pub fn restore_from_cloud_with_conn(
-    db_path: &str,
+/// Download the cloud backup into a temp file
+fn download_cloud_backup(
     api_base_url: &str,
     dealer_id: &str,
     license_key: &str,
-) -> Result<(), AppError> {
+) -> Result<std::path::PathBuf, AppError> {
     let client = reqwest::blocking::Client::new();
     let url = format!("{}/api/cloud/restore", api_base_url);
 
-    let response = client
-        .get(&url)
-        .header("X-Dealer-ID", dealer_id)
-        .header("X-License-Key", license_key)
-        .timeout(std::time::Duration::from_secs(120))
-        .send()
-        .map_err(|e| AppError::Internal(format!("Geri yukleme istegi basarisiz: {}", e)))?;
+    let response = http::send_with_retry(&url, || {
+        client
+            .get(&url)
+            .header("X-Dealer-ID", dealer_id)
+            .header("X-License-Key", license_key)
+            .timeout(std::time::Duration::from_secs(120))
+    })
+    .map_err(|e| AppError::Internal(format!("Geri yukleme istegi basarisiz: {}", e)))?;
 
     if response.status().is_success() {
-        // Download to temp file
-        let temp_path = std::env::temp_dir().join("nexus_cloud_restore.db");
+        // Unique per download, so two restores never share (or clobber) a file
+        let temp_path = std::env::temp_dir().join(format!("nexus_cloud_restore_{}.db", uuid::Uuid::new_v4()));
         let bytes = response.bytes()
             .map_err(|e| AppError::Internal(format!("Veri alinamadi: {}", e)))?;
 
         std::fs::write(&temp_path, &bytes)
             .map_err(|e| AppError::Internal(format!("Gecici dosya yazilamadi: {}", e)))?;
 
-        // Open the downloaded database (source)
-        let source_conn = Connection::open(&temp_path)
-            .map_err(|e| AppError::Internal(format!("Indirilen veritabani acilamadi: {}", e)))?;
-
-        // Open direct mutable connection to destination
-        let mut dest_conn = Connection::open(db_path)
-            .map_err(|e| AppError::Internal(format!("Hedef veritabani acilamadi: {}", e)))?;
-
-        // Use SQLite backup API to restore into destination
-        let backup = rusqlite::backup::Backup::new(&source_conn, &mut dest_conn)
-            .map_err(|e| AppError::Internal(format!("Backup olusturulamadi: {}", e)))?;
-
-        backup.run_to_completion(100, std::time::Duration::from_millis(50), None)
-            .map_err(|e| AppError::Internal(format!("Backup tamamlanamadi: {}", e)))?;
-
-        // Clean up temp file
-        let _ = std::fs::remove_file(&temp_path);
-
-        Ok(())
+        Ok(temp_path)
     } else {
         let status = response.status();
         let body = response.text().unwrap_or_default();
@@ -153,6 +146,63 @@ pub fn restore_from_cloud_with_conn(
     }
 }
 
+/// Copy a downloaded database into db_path using the SQLite backup API
+fn restore_from_file(source_path: &std::path::Path, db_path: &str) -> Result<(), AppError> {
+    // Open the downloaded database (source)
+    let source_conn = Connection::open(source_path)
+        .map_err(|e| AppError::Internal(format!("Indirilen veritabani acilamadi: {}", e)))?;
+
+    // Open direct mutable connection to destination
+    let mut dest_conn = Connection::open(db_path)
+        .map_err(|e| AppError::Internal(format!("Hedef veritabani acilamadi: {}", e)))?;
+
+    // Use SQLite backup API to restore into destination
+    let backup = rusqlite::backup::Backup::new(&source_conn, &mut dest_conn)
+        .map_err(|e| AppError::Internal(format!("Backup olusturulamadi: {}", e)))?;
+
+    backup.run_to_completion(100, std::time::Duration::from_millis(50), None)
+        .map_err(|e| AppError::Internal(format!("Backup tamamlanamadi: {}", e)))?;
+
+    Ok(())
+}
+
+/// Restore database from cloud using SQLite backup API
+/// This restores data into the existing connection without requiring app restart.
+/// The backup is compared with local data first; if it is older or smaller,
+/// nothing is restored unless `force` is set.
+pub fn restore_from_cloud_with_conn(
+    db_path: &str,
+    api_base_url: &str,
+    dealer_id: &str,
+    license_key: &str,
+    force: bool,
+) -> Result<CloudRestoreResult, AppError> {
+    let temp_path = download_cloud_backup(api_base_url, dealer_id, license_key)?;
+
+    let result = reconcile::compare_databases(std::path::Path::new(db_path), &temp_path)
+        .and_then(|comparison| {
+            if comparison.requires_force && !force {
+                let message = format!(
+                    "Bulut yedegi yerel veriden eski veya daha kucuk: {}. Devam etmek icin onay gerekli.",
+                    comparison.warnings.join(", ")
+                );
+                return Ok(CloudRestoreResult { restored: false, comparison, message });
+            }
+
+            restore_from_file(&temp_path, db_path)?;
+            Ok(CloudRestoreResult {
+                restored: true,
+                comparison,
+                message: "Bulut yedegi geri yuklendi".to_string(),
+            })
+        });
+
+    // Clean up temp file
+    let _ = std::fs::remove_file(&temp_path);
+
+    result
+}
+
 /// Get cloud backup status
 pub fn get_cloud_status(
     api_base_url: &str,
@@ -162,13 +212,14 @@ pub fn get_cloud_status(
     let client = reqwest::blocking::Client::new();
     let url = format!("{}/api/cloud/status", api_base_url);
 
-    let response = client
-        .get(&url)
-        .header("X-Dealer-ID", dealer_id)
-        .header("X-License-Key", license_key)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .map_err(|e| AppError::Internal(format!("Durum istegi basarisiz: {}", e)))?;
+    let response = http::send_with_retry(&url, || {
+        client
+            .get(&url)
+            .header("X-Dealer-ID", dealer_id)
+            .header("X-License-Key", license_key)
+            .timeout(std::time::Duration::from_secs(30))
+    })
+    .map_err(|e| AppError::Internal(format!("Durum istegi basarisiz: {}", e)))?;
 
     if response.status().is_success() {
         let result: CloudStatusResponse = response
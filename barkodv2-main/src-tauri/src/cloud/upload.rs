@@ -0,0 +1,29 @@
+//! Streaming cloud upload
+//!
+//! Veritabanı dosyasını belleğe almadan parça parça okuyarak gönderir.
+//! Bellek kullanımı dosya boyutundan bağımsızdır.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+/// File reader that opens the file on first read, so every retry attempt gets a fresh reader
+pub struct LazyFileReader {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl LazyFileReader {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), file: None }
+    }
+}
+
+impl Read for LazyFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.file.is_none() {
+            self.file = Some(File::open(&self.path)?);
+        }
+        self.file.as_mut().map_or(Ok(0), |f| f.read(buf))
+    }
+}
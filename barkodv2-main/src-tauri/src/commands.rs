@@ -6,6 +6,7 @@ use crate::models::{
     PaginatedItemsResponse, PaginatedTransactionsResponse,
 };
 use crate::AppState;
+use crate::security::admin;
 use crate::security::password::{hash_password, verify_password};
 use crate::security::validation::{validate_sku, validate_price, validate_quantity, validate_username, validate_password_strength};
 use crate::license;
@@ -14,7 +15,9 @@ use crate::services::backup::{self, BackupInfo};
 use crate::services::startup;
 use crate::services::print as print_service;
 use crate::services::updater as updater_service;
+use crate::services::http as http_service;
 use crate::services::scanner;
+use crate::services::inventory;
 use rusqlite::{params, OptionalExtension};
 use tauri::State;
 
@@ -176,6 +179,12 @@ pub fn process_sale(
     note: Option<String>,
 ) -> Result<Transaction, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    // The stock check and the updates see the same quantities
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    if transaction_type != "RETURN" {
+        inventory::check_stock_available(&tx, &cart_items).map_err(|e| e.to_string())?;
+    }
 
     let mut total = 0.0;
     for item in &cart_items {
@@ -189,31 +198,32 @@ pub fn process_sale(
     let created_at = chrono::Utc::now().to_rfc3339();
     let items_json = serde_json::to_string(&cart_items).map_err(|e| e.to_string())?;
 
-    conn.execute(
+    tx.execute(
         "INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         params![&transaction_id, &items_json, total, &payment_method, &transaction_type, &note, &created_at],
     ).map_err(|e| e.to_string())?;
 
     // Update inventory quantities
     for item in &cart_items {
-        let current_qty: i32 = conn.query_row(
+        let current_qty: i32 = tx.query_row(
             "SELECT quantity FROM inventory_items WHERE sku = ?1",
             params![&item.sku],
             |row| row.get(0),
         ).unwrap_or(0);
 
+        // Below zero only when negative stock is allowed (checked above)
         let new_qty = if transaction_type == "RETURN" {
             current_qty + item.cart_quantity
         } else {
             current_qty - item.cart_quantity
         };
-        let final_qty = if new_qty < 0 { 0 } else { new_qty };
 
-        conn.execute(
+        tx.execute(
             "UPDATE inventory_items SET quantity = ?1, last_updated = ?2 WHERE sku = ?3",
-            params![final_qty, &created_at, &item.sku],
+            params![new_qty, &created_at, &item.sku],
         ).map_err(|e| e.to_string())?;
     }
+    tx.commit().map_err(|e| e.to_string())?;
 
     Ok(Transaction {
         id: transaction_id,
@@ -677,8 +687,8 @@ pub fn login(state: State<AppState>, username: String, password: String) -> Resu
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     // Kullanıcıyı ara (rate limiting alanları dahil)
-    let user_result: Result<(String, String, String, String, String, String, Option<String>, i32, Option<String>), _> = conn.query_row(
-        "SELECT id, username, password_hash, display_name, role, created_at, last_login, failed_login_attempts, locked_until FROM users WHERE username = ?1",
+    let user_result: Result<(String, String, String, String, String, String, Option<String>, i32, Option<String>, bool), _> = conn.query_row(
+        "SELECT id, username, password_hash, display_name, role, created_at, last_login, failed_login_attempts, locked_until, must_change_password FROM users WHERE username = ?1",
         params![&username],
         |row| {
             Ok((
@@ -691,12 +701,13 @@ pub fn login(state: State<AppState>, username: String, password: String) -> Resu
                 row.get(6)?,
                 row.get::<_, i32>(7).unwrap_or(0),
                 row.get(8)?,
+                row.get::<_, bool>(9).unwrap_or(false),
             ))
         },
     );
 
     match user_result {
-        Ok((id, uname, password_hash, display_name, role, created_at, _, failed_attempts, locked_until)) => {
+        Ok((id, uname, password_hash, display_name, role, created_at, _, failed_attempts, locked_until, must_change_pwd)) => {
             let now = chrono::Utc::now();
             
             // Hesap kilitli mi kontrol et
@@ -735,8 +746,8 @@ pub fn login(state: State<AppState>, username: String, password: String) -> Resu
                 }
             }
 
-            // Varsayılan şifre kullanılıyor mu kontrol et (admin123)
-            let must_change = password == "admin123";
+            // Geçici şifre veya eski varsayılan şifre (admin123) kullanılıyor mu kontrol et
+            let must_change = must_change_pwd || password == "admin123";
 
             // Başarılı giriş - sayaçları sıfırla
             let now_str = now.to_rfc3339();
@@ -894,7 +905,7 @@ pub fn change_password(state: State<AppState>, id: String, new_password: String)
     let password_hash = hash_password(&new_password).map_err(|e| e.to_string())?;
 
     conn.execute(
-        "UPDATE users SET password_hash = ?1 WHERE id = ?2",
+        "UPDATE users SET password_hash = ?1, must_change_password = 0 WHERE id = ?2",
         params![&password_hash, &id],
     ).map_err(|e| e.to_string())?;
 
@@ -1134,7 +1145,7 @@ pub fn activate_license(
     license_key: String,
 ) -> Result<LicenseActivateResponse, String> {
     let mac = license::get_device_mac_address().map_err(|e| e.to_string())?;
-    let response = license::activate_license_api(&api_base_url, &license_key, &mac)
+    let mut response = license::activate_license_api(&api_base_url, &license_key, &mac)
         .map_err(|e| e.to_string())?;
 
     if response.success {
@@ -1157,22 +1168,16 @@ pub fn activate_license(
         license::save_license(&conn, &license_data).map_err(|e| e.to_string())?;
 
         // Create default admin user for this dealer if no users exist
-        let user_count: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM users",
-            [],
-            |row| row.get(0),
-        ).unwrap_or(0);
-
-        if user_count == 0 {
-            let admin_id = uuid::Uuid::new_v4().to_string();
-            let password_hash = hash_password("admin123").unwrap_or_else(|_| "admin123_hashed".to_string());
-            let dealer_name = response.dealer_name.clone().unwrap_or("Admin".to_string());
-            let dealer_id = response.dealer_id.clone().unwrap_or_default();
-
-            conn.execute(
-                "INSERT INTO users (id, username, password_hash, display_name, role, created_at, dealer_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                params![&admin_id, "admin", &password_hash, &dealer_name, "admin", &now, &dealer_id],
-            ).map_err(|e| e.to_string())?;
+        let dealer_name = response.dealer_name.clone().unwrap_or("Admin".to_string());
+        let dealer_id = response.dealer_id.clone().unwrap_or_default();
+        let temp_password = admin::create_initial_admin(&conn, &dealer_name, Some(&dealer_id))
+            .map_err(|e| e.to_string())?;
+
+        if let Some(temp_password) = temp_password {
+            response.message = Some(format!(
+                "Lisans başarıyla aktifleştirildi. Geçici Yönetici Şifreniz: {} (Lütfen hemen değiştirin)",
+                temp_password
+            ));
         }
     }
 
@@ -1252,7 +1257,7 @@ pub fn cloud_backup(state: State<AppState>) -> Result<CloudBackupResponse, Strin
 }
 
 #[tauri::command]
-pub fn cloud_restore(state: State<AppState>) -> Result<(), String> {
+pub fn cloud_restore(state: State<AppState>, force: Option<bool>) -> Result<cloud::CloudRestoreResult, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     // Get license info for API auth
@@ -1262,18 +1267,22 @@ pub fn cloud_restore(state: State<AppState>) -> Result<(), String> {
 
     let db_path = state.db.get_db_path_string();
 
-    // Perform restore using SQLite backup API (no restart needed)
-    cloud::restore_from_cloud_with_conn(
+    // Perform restore using SQLite backup API (no restart needed).
+    // Older or smaller backups are only restored with force=true.
+    let result = cloud::restore_from_cloud_with_conn(
         &db_path,
         &license_data.api_base_url,
         &license_data.dealer_id,
         &license_data.license_key,
+        force.unwrap_or(false),
     ).map_err(|e| e.to_string())?;
 
     // Update last restore timestamp
-    cloud::update_last_restore(&conn).map_err(|e| e.to_string())?;
+    if result.restored {
+        cloud::update_last_restore(&conn).map_err(|e| e.to_string())?;
+    }
 
-    Ok(())
+    Ok(result)
 }
 
 #[tauri::command]
@@ -1309,6 +1318,18 @@ pub fn set_auto_sync(
     cloud::set_auto_sync_settings(&conn, enabled, interval).map_err(|e| e.to_string())
 }
 
+/// Retries after the first attempt for license/cloud HTTP calls
+#[tauri::command]
+pub fn get_http_retry_count() -> u32 {
+    http_service::get_http_max_retries()
+}
+
+#[tauri::command]
+pub fn set_http_retry_count(state: State<AppState>, retries: u32) -> Result<u32, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    http_service::save_http_max_retries(&conn, retries).map_err(|e| e.to_string())
+}
+
 // ==================== LOCAL BACKUP COMMANDS ====================
 
 #[tauri::command]
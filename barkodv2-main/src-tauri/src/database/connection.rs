@@ -80,7 +80,7 @@ impl Database {
                 sku TEXT UNIQUE NOT NULL,
                 name TEXT NOT NULL,
                 category TEXT DEFAULT 'Genel',
-                quantity INTEGER DEFAULT 0 CHECK (quantity >= 0),
+                quantity INTEGER DEFAULT 0,
                 location TEXT DEFAULT '',
                 price REAL DEFAULT 0.00 CHECK (price >= 0),
                 cost_price REAL DEFAULT 0.00,
@@ -168,6 +168,46 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_users_username ON users(username);
             ",
         )?;
+
+        // Migration: Ensure must_change_password column exists in users
+        let has_must_change_pwd: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('users') WHERE name='must_change_password'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or(0) > 0;
+
+        if !has_must_change_pwd {
+            let _ = conn.execute("ALTER TABLE users ADD COLUMN must_change_password BOOLEAN DEFAULT 0", []);
+        }
+
+        // Migration: quantity may go negative when oversold sales are allowed. SQLite cannot drop a
+        // CHECK in place, so the table is rebuilt from its schema text without it
+        let items_sql: String = conn.query_row(
+            "SELECT sql FROM sqlite_master WHERE type='table' AND name='inventory_items'",
+            [],
+            |row| row.get(0),
+        )?;
+        if let (true, Some(body_start)) = (items_sql.contains(" CHECK (quantity >= 0)"), items_sql.find('(')) {
+            let indexes: Vec<String> = conn
+                .prepare("SELECT sql FROM sqlite_master WHERE type='index' AND tbl_name='inventory_items' AND sql IS NOT NULL")?
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            let tx = conn.unchecked_transaction()?;
+            tx.execute_batch(&format!(
+                "CREATE TABLE inventory_items_new {};
+                 INSERT INTO inventory_items_new SELECT * FROM inventory_items;
+                 DROP TABLE inventory_items;
+                 ALTER TABLE inventory_items_new RENAME TO inventory_items;",
+                items_sql[body_start..].replace(" CHECK (quantity >= 0)", "")
+            ))?;
+            for index in indexes {
+                tx.execute_batch(&index)?;
+            }
+            tx.commit()?;
+        }
+
         Ok(())
     }
 
@@ -31,9 +31,16 @@ pub fn run() {
             // Initialize SQLite database
             let db = init_database().expect("SQLite veritabani baslatilamadi");
 
-            // Create default admin user if needed
-            if let Err(e) = create_default_admin(&db) {
-                eprintln!("Varsayilan admin olusturulamadi: {}", e);
+            if let Ok(conn) = db.get_conn() {
+                // Apply the configured HTTP retry count for license/cloud calls
+                if let Err(e) = services::http::load_http_settings(&conn) {
+                    eprintln!("HTTP ayarlari okunamadi: {}", e);
+                }
+
+                // Ensure an admin exists (temporary password goes to the support log)
+                if let Err(e) = security::admin::ensure_admin_exists(&conn) {
+                    eprintln!("Yonetici hesabi kontrol edilemedi: {}", e);
+                }
             }
 
             app.manage(AppState { db });
@@ -144,6 +151,8 @@ pub fn run() {
             get_sync_status,
             get_cloud_status,
             set_auto_sync,
+            get_http_retry_count,
+            set_http_retry_count,
             // Local backup commands
             create_local_backup,
             list_local_backups,
@@ -169,30 +178,3 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
-
-/// Create default admin user if no users exist
-fn create_default_admin(db: &Database) -> Result<(), AppError> {
-    let conn = db.get_conn()?;
-
-    let user_count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM users",
-        [],
-        |row| row.get(0)
-    ).unwrap_or(0);
-
-    if user_count == 0 {
-        let admin_id = uuid::Uuid::new_v4().to_string();
-        let now = chrono::Utc::now().to_rfc3339();
-        let password_hash = security::password::hash_password("admin123")
-            .expect("Varsayılan admin şifresi hash'lenemedi");
-
-        conn.execute(
-            "INSERT INTO users (id, username, password_hash, display_name, role, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![&admin_id, "admin", &password_hash, "Yönetici", "admin", &now],
-        )?;
-
-        println!("Varsayilan admin kullanicisi olusturuldu");
-    }
-
-    Ok(())
-}
@@ -1,4 +1,5 @@
 use crate::error::AppError;
+use crate::services::http;
 use crate::models::{License, LicenseActivateRequest, LicenseActivateResponse, LicenseValidateRequest, LicenseValidateResponse};
 use mac_address::get_mac_address;
 use rusqlite::{params, Connection};
@@ -33,12 +34,13 @@ pub fn validate_license_api(
         mac_address: mac_address.to_string(),
     };
 
-    let response = client
-        .post(&url)
-        .json(&request)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
+    let response = http::send_with_retry(&url, || {
+        client
+            .post(&url)
+            .json(&request)
+            .timeout(std::time::Duration::from_secs(30))
+    })
+    .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
 
     if response.status().is_success() {
         let result: LicenseValidateResponse = response
@@ -80,12 +82,13 @@ pub fn activate_license_api(
         device_name: Some(get_device_name()),
     };
 
-    let response = client
-        .post(&url)
-        .json(&request)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
+    let response = http::send_with_retry(&url, || {
+        client
+            .post(&url)
+            .json(&request)
+            .timeout(std::time::Duration::from_secs(30))
+    })
+    .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
 
     if response.status().is_success() {
         let result: LicenseActivateResponse = response
@@ -1,3 +1,4 @@
+pub mod admin;
 pub mod password;
 pub mod validation;
 
@@ -0,0 +1,245 @@
+//! HTTP Client Service Module
+//!
+//! Lisans ve bulut API çağrıları için tekrar deneme (üstel bekleme) ve devre kesici.
+//! Art arda başarısız olan sunucuya bekleme süresi boyunca istek gönderilmez.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::{RequestBuilder, Response};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+
+pub const HTTP_MAX_RETRIES_SETTING: &str = "http_max_retries";
+pub const DEFAULT_HTTP_MAX_RETRIES: u32 = 2;
+pub const MAX_HTTP_RETRIES_LIMIT: u32 = 5;
+
+/// First retry waits this long, doubling each attempt up to MAX_BACKOFF
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(4);
+
+/// Consecutive failed requests before the circuit opens
+const CIRCUIT_FAILURE_THRESHOLD: u32 = 3;
+/// How long an open circuit rejects requests
+const CIRCUIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+static MAX_RETRIES: AtomicU32 = AtomicU32::new(DEFAULT_HTTP_MAX_RETRIES);
+
+/// Failure of a request after retries, or a rejected request while the circuit is open
+#[derive(Debug)]
+pub enum HttpError {
+    CircuitOpen { host: String, retry_in_secs: u64 },
+    Request { attempts: u32, source: reqwest::Error },
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpError::CircuitOpen { host, retry_in_secs } => write!(
+                f,
+                "{} sunucusuna art arda ulasilamadi, {} sn sonra tekrar denenecek",
+                host, retry_in_secs
+            ),
+            HttpError::Request { attempts, source } => {
+                write!(f, "{} ({} deneme)", source, attempts)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct CircuitState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitState {
+    /// Remaining cooldown if the circuit is open
+    fn blocked_for(&self, now: Instant) -> Option<Duration> {
+        self.open_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    /// After the cooldown a single trial request is let through; failing it reopens the circuit
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= CIRCUIT_FAILURE_THRESHOLD {
+            self.open_until = Some(now + CIRCUIT_COOLDOWN);
+        }
+    }
+}
+
+fn circuits() -> &'static Mutex<HashMap<String, CircuitState>> {
+    static CIRCUITS: OnceLock<Mutex<HashMap<String, CircuitState>>> = OnceLock::new();
+    CIRCUITS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn with_circuit<T>(host: &str, f: impl FnOnce(&mut CircuitState) -> T) -> T {
+    let mut map = circuits().lock().unwrap_or_else(|e| e.into_inner());
+    f(map.entry(host.to_string()).or_default())
+}
+
+fn host_key(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| match u.port() {
+            Some(port) => format!("{}:{}", h, port),
+            None => h.to_string(),
+        }))
+        .unwrap_or_else(|| url.to_string())
+}
+
+fn backoff_for(retry: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(retry))
+        .min(MAX_BACKOFF)
+}
+
+/// Server errors worth retrying (the request itself was fine)
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+}
+
+/// Number of retries after the first attempt
+pub fn get_http_max_retries() -> u32 {
+    MAX_RETRIES.load(Ordering::SeqCst)
+}
+
+/// Set the retry count (0 disables retries), clamped to MAX_HTTP_RETRIES_LIMIT
+pub fn set_http_max_retries(retries: u32) -> u32 {
+    let retries = retries.min(MAX_HTTP_RETRIES_LIMIT);
+    MAX_RETRIES.store(retries, Ordering::SeqCst);
+    retries
+}
+
+/// Apply the retry count stored in settings
+pub fn load_http_settings(conn: &Connection) -> Result<u32, AppError> {
+    let value: Option<Option<String>> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![HTTP_MAX_RETRIES_SETTING],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let retries = value
+        .flatten()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_HTTP_MAX_RETRIES);
+    Ok(set_http_max_retries(retries))
+}
+
+/// Store and apply the retry count
+pub fn save_http_max_retries(conn: &Connection, retries: u32) -> Result<u32, AppError> {
+    let retries = set_http_max_retries(retries);
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![HTTP_MAX_RETRIES_SETTING, retries.to_string()],
+    )?;
+    Ok(retries)
+}
+
+/// Send with the configured retry count
+pub fn send_with_retry<F>(url: &str, build: F) -> Result<Response, HttpError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    send_with_retries(url, get_http_max_retries(), build)
+}
+
+/// Send a request, retrying network errors and 5xx/408/429 with exponential backoff.
+///
+/// `build` is called once per attempt, so a timeout set on the builder applies per attempt.
+/// If retries run out on a server error the last response is returned for the caller to handle.
+pub fn send_with_retries<F>(url: &str, max_retries: u32, build: F) -> Result<Response, HttpError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let host = host_key(url);
+
+    if let Some(remaining) = with_circuit(&host, |c| c.blocked_for(Instant::now())) {
+        return Err(HttpError::CircuitOpen {
+            host,
+            retry_in_secs: remaining.as_secs().max(1),
+        });
+    }
+
+    let mut attempt = 0;
+    loop {
+        let last_attempt = attempt >= max_retries;
+        match build().send() {
+            Ok(response) if is_retryable_status(response.status()) => {
+                if last_attempt {
+                    with_circuit(&host, |c| c.record_failure(Instant::now()));
+                    return Ok(response);
+                }
+            }
+            Ok(response) => {
+                with_circuit(&host, |c| c.record_success());
+                return Ok(response);
+            }
+            Err(e) => {
+                if last_attempt {
+                    with_circuit(&host, |c| c.record_failure(Instant::now()));
+                    return Err(HttpError::Request { attempts: attempt + 1, source: e });
+                }
+            }
+        }
+
+        std::thread::sleep(backoff_for(attempt));
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_circuit_state() {
+        assert_eq!(backoff_for(0), Duration::from_millis(500));
+        assert_eq!(backoff_for(2), Duration::from_secs(2));
+        assert_eq!(backoff_for(10), MAX_BACKOFF);
+
+        let now = Instant::now();
+        let mut circuit = CircuitState::default();
+        for _ in 0..CIRCUIT_FAILURE_THRESHOLD - 1 {
+            circuit.record_failure(now);
+        }
+        assert!(circuit.blocked_for(now).is_none());
+
+        circuit.record_failure(now);
+        assert!(circuit.blocked_for(now).is_some());
+        // Cooldown over: one trial request is allowed
+        assert!(circuit.blocked_for(now + CIRCUIT_COOLDOWN).is_none());
+
+        circuit.record_success();
+        assert!(circuit.blocked_for(now).is_none());
+        assert_eq!(circuit.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_unreachable_server_opens_circuit() {
+        // Nothing listens on port 9; connections are refused immediately
+        let url = "http://127.0.0.1:9/api/health";
+        let client = reqwest::blocking::Client::new();
+        for _ in 0..CIRCUIT_FAILURE_THRESHOLD {
+            let result = send_with_retries(url, 0, || client.get(url).timeout(Duration::from_secs(2)));
+            assert!(matches!(result, Err(HttpError::Request { attempts: 1, .. })));
+        }
+
+        let result = send_with_retries(url, 0, || client.get(url));
+        assert!(matches!(result, Err(HttpError::CircuitOpen { .. })));
+    }
+}
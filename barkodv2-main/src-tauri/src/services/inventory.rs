@@ -1,7 +1,7 @@
 use rusqlite::{params, Connection, OptionalExtension};
 
 use crate::error::AppError;
-use crate::models::{CategoryStats, DashboardStats, InventoryItem};
+use crate::models::{CartItem, CategoryStats, DashboardStats, InventoryItem};
 use crate::security::validation::{validate_price, validate_quantity, validate_sku};
 
 /// Get all inventory items
@@ -195,6 +195,49 @@ pub fn update_quantity(
     get_item_by_sku(conn, sku)
 }
 
+/// "1"/"true" lets a sale take more than is in stock; the quantity then goes negative (backorder).
+/// Off or unset, such a sale is rejected.
+pub const ALLOW_NEGATIVE_STOCK_SETTING: &str = "allow_negative_stock";
+
+/// Reject a sale that takes more of a SKU than is in stock, unless negative stock is allowed.
+/// Lines of the same SKU are added up; SKUs without an inventory row are skipped.
+pub fn check_stock_available(conn: &Connection, cart_items: &[CartItem]) -> Result<(), AppError> {
+    let allowed: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [ALLOW_NEGATIVE_STOCK_SETTING],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    if allowed.is_some_and(|v| matches!(v.trim(), "1" | "true")) {
+        return Ok(());
+    }
+
+    let mut needed: Vec<(&str, i32)> = Vec::new();
+    for item in cart_items {
+        match needed.iter_mut().find(|(sku, _)| *sku == item.sku) {
+            Some((_, total)) => *total += item.cart_quantity,
+            None => needed.push((item.sku.as_str(), item.cart_quantity)),
+        }
+    }
+
+    for (sku, quantity) in needed {
+        let available: Option<i32> = conn
+            .query_row("SELECT quantity FROM inventory_items WHERE sku = ?1", [sku], |row| row.get(0))
+            .optional()?;
+        if let Some(available) = available {
+            if quantity > available {
+                return Err(AppError::Validation(format!(
+                    "Yetersiz stok: {} (mevcut: {}, istenen: {})",
+                    sku, available, quantity
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Get dashboard statistics
 pub fn get_dashboard_stats(conn: &Connection) -> Result<DashboardStats, AppError> {
     let total_items: i32 = conn.query_row(
@@ -411,3 +454,38 @@ pub fn seed_inventory(conn: &Connection) -> Result<String, AppError> {
 
     Ok(format!("{} ornek urun eklendi", count))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(sku: &str, cart_quantity: i32) -> CartItem {
+        CartItem {
+            id: sku.to_string(),
+            sku: sku.to_string(),
+            name: sku.to_string(),
+            cart_quantity,
+            price: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_check_stock_available() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (sku TEXT PRIMARY KEY, quantity INTEGER);
+             CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT);
+             INSERT INTO inventory_items VALUES ('BOYA-1', 5), ('FIRCA-2', 0);",
+        )
+        .unwrap();
+
+        check_stock_available(&conn, &[line("BOYA-1", 5), line("YOK-9", 3)]).unwrap();
+        // Lines of the same SKU are added up
+        let err = check_stock_available(&conn, &[line("BOYA-1", 3), line("BOYA-1", 3)]).unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("BOYA-1") && m.contains("istenen: 6")));
+        assert!(check_stock_available(&conn, &[line("FIRCA-2", 1)]).is_err());
+
+        conn.execute("INSERT INTO settings VALUES (?1, 'true')", [ALLOW_NEGATIVE_STOCK_SETTING]).unwrap();
+        check_stock_available(&conn, &[line("BOYA-1", 3), line("BOYA-1", 3), line("FIRCA-2", 1)]).unwrap();
+    }
+}
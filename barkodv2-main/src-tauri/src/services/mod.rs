@@ -4,6 +4,8 @@ pub mod startup;
 pub mod print;
 pub mod updater;
 pub mod scanner;
+pub mod support_log;
+pub mod http;
 
 pub use inventory::*;
 pub use backup::*;
@@ -11,3 +13,5 @@ pub use startup::*;
 pub use print::*;
 pub use updater::*;
 pub use scanner::*;
+pub use support_log::*;
+pub use http::*;
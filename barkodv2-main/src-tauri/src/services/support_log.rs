@@ -0,0 +1,28 @@
+//! Support Log Module
+//!
+//! Destek için uygulama veri dizinindeki support.log dosyasına kayıt yazar
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+
+use crate::error::AppError;
+
+/// Path of the support log (`<data dir>/support.log`)
+pub fn get_support_log_path() -> Option<PathBuf> {
+    ProjectDirs::from("com", "nexus", "inventory").map(|proj| proj.data_dir().join("support.log"))
+}
+
+/// Append a timestamped line to the support log
+pub fn append_support_log(message: &str) -> Result<(), AppError> {
+    let path = get_support_log_path()
+        .ok_or_else(|| AppError::Internal("Destek kaydi dizini belirlenemedi".to_string()))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "[{}] {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), message)?;
+    Ok(())
+}
@@ -87,6 +87,11 @@ export const LicenseProvider: React.FC<LicenseProviderProps> = ({ children }) =>
       const response = await licenseService.activateLicense(apiBaseUrl, licenseKey);
 
       if (response.success) {
+        // Shows the temporary admin password when activation created the admin
+        if (response.message) {
+          alert(response.message);
+        }
+
         // Fetch the saved license
         const savedLicense = await licenseService.getLicenseStatus();
         setLicense(savedLicense);
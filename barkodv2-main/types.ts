@@ -118,6 +118,30 @@ export interface CloudStatusResponse {
   backup_count?: number;
 }
 
+export interface DatabaseSnapshot {
+  lastModified?: string;
+  transactionCount: number;
+  itemCount: number;
+  accountCount: number;
+}
+
+export interface BackupComparison {
+  local: DatabaseSnapshot;
+  cloud: DatabaseSnapshot;
+  daysOlder?: number;
+  transactionDifference: number;
+  itemDifference: number;
+  accountDifference: number;
+  requiresForce: boolean;
+  warnings: string[];
+}
+
+export interface CloudRestoreResult {
+  restored: boolean;
+  comparison: BackupComparison;
+  message: string;
+}
+
 // ==================== PAGINATION TYPES ====================
 
 export interface PaginationParams {
@@ -151,6 +151,13 @@ const CreateStockCard: React.FC = () => {
             };
 
             if (isTauri()) {
+                // Warn about mistyped EAN/UPC barcodes (wrong check digit)
+                const validation = await tauriInvoke<{ valid: boolean; message?: string | null }>('validate_barcode', { barcode: formData.barcode });
+                if (!validation.valid && !window.confirm(`${validation.message || 'Barkod geçersiz görünüyor.'}\n\nYine de kaydedilsin mi?`)) {
+                    setLoading(false);
+                    return;
+                }
+
                 // Check if SKU exists
                 const exists = await tauriInvoke<boolean>('check_sku_exists', { sku: formData.barcode });
                 if (exists) {
@@ -19,7 +19,7 @@ const CreditPage: React.FC = () => {
         try {
             // Get all transactions and filter for Veresiye
             const allTxns = await inventoryService.getTransactions();
-            const creditTxns = allTxns.filter(t => t.paymentMethod === 'Veresiye' && t.transactionType !== 'PAID');
+            const creditTxns = allTxns.filter(t => t.paymentMethod === 'VERESIYE' && t.transactionType !== 'PAID');
             setTransactions(creditTxns);
         } catch (error) {
             console.error(error);
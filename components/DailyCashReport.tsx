@@ -100,7 +100,7 @@ const DailyCashReport: React.FC = () => {
                     const pm = (tx.paymentMethod || '').toString();
                     if (pm === 'VERESIYE') newTotals.veresiye += tx.total;
                     else if (pm === 'MAIL_ORDER') newTotals.mailOrder += tx.total;
-                    else if (pm.startsWith('Kredi Kartı')) newTotals.creditCard += tx.total;
+                    else if (pm.startsWith('KREDI_KARTI')) newTotals.creditCard += tx.total;
                     else newTotals.cash += tx.total;
                 }
             });
@@ -172,7 +172,7 @@ const Dashboard: React.FC = () => {
     };
 
     const maxSale = Math.max(...chartData.map(d => d.value), 1);
-    const lowStockItems = useMemo(() => allItems.filter(item => item.quantity < 10).slice(0, 6), [allItems]);
+    const lowStockItems = useMemo(() => allItems.filter(item => item.quantity < (item.minStockLevel ?? 10)).slice(0, 6), [allItems]);
 
     const categoryStats = useMemo(() => {
         return allItems.reduce((acc, item) => {
@@ -259,9 +259,9 @@ const Dashboard: React.FC = () => {
                                 <td className="p-4 text-right font-mono text-sm font-bold text-gray-900 dark:text-white">{item.quantity}</td>
                                 <td className="p-4 text-right">
                                     <span className={`text-[10px] font-bold uppercase tracking-wider ${item.quantity === 0 ? 'text-red-500' :
-                                        item.quantity < 10 ? 'text-orange-500' : 'text-green-500'
+                                        item.quantity < (item.minStockLevel ?? 10) ? 'text-orange-500' : 'text-green-500'
                                         }`}>
-                                        {item.quantity === 0 ? 'Tükendi' : item.quantity < 10 ? 'Kritik' : 'Stokta'}
+                                        {item.quantity === 0 ? 'Tükendi' : item.quantity < (item.minStockLevel ?? 10) ? 'Kritik' : 'Stokta'}
                                     </span>
                                 </td>
                             </tr>
@@ -469,7 +469,7 @@ const Dashboard: React.FC = () => {
             <TableView
                 title="Kritik Stok"
                 subtitle="Stok seviyesi 10 ve altı olan ürünler"
-                data={allItems.filter(i => i.quantity < 10)}
+                data={allItems.filter(i => i.quantity < (i.minStockLevel ?? 10))}
                 type="CRITICAL"
             />
         </div>
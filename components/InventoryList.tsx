@@ -1,9 +1,11 @@
 import React, { useState, useEffect, useMemo } from 'react';
 import { InventoryItem, ViewState } from '../types';
 import { inventoryService } from '../services/inventoryService';
+import ItemImage from './ItemImage';
 import { geminiService } from '../services/geminiService';
 import { useRefresh } from '../src/context/RefreshContext';
 import { tauriInvoke, isTauri } from '../services/tauriService';
+import { formatQuantity } from '../services/units';
 
 interface CurrentAccount {
     id: string;
@@ -360,11 +362,14 @@ const InventoryList: React.FC<InventoryListProps> = ({ onNavigate }) => {
                                     >
                                         <td className="p-6">
                                             <div className="w-16 h-16 bg-gray-200 dark:bg-gray-800 rounded-lg overflow-hidden border border-gray-300 dark:border-gray-700">
-                                                {item.image ? (
-                                                    <img src={item.image} alt={item.name} className="w-full h-full object-cover" />
-                                                ) : (
-                                                    <div className="w-full h-full flex items-center justify-center text-gray-400">RESİM</div>
-                                                )}
+                                                <ItemImage
+                                                    thumbnail
+                                                    sku={item.sku}
+                                                    image={item.image}
+                                                    alt={item.name}
+                                                    className="w-full h-full object-cover"
+                                                    fallback={<div className="w-full h-full flex items-center justify-center text-gray-400">RESİM</div>}
+                                                />
                                             </div>
                                         </td>
                                         <td className="p-6">
@@ -389,8 +394,8 @@ const InventoryList: React.FC<InventoryListProps> = ({ onNavigate }) => {
                                                 )}
                                             </div>
                                         </td>
-                                        <td className={`p-6 text-right font-mono text-2xl font-bold ${item.quantity < 10 ? 'text-red-500 dark:text-white underline decoration-wavy decoration-red-500' : 'text-gray-900 dark:text-white'}`}>
-                                            {item.quantity}
+                                        <td className={`p-6 text-right font-mono text-2xl font-bold ${item.quantity < (item.minStockLevel ?? 10) ? 'text-red-500 dark:text-white underline decoration-wavy decoration-red-500' : 'text-gray-900 dark:text-white'}`}>
+                                            {formatQuantity(item.quantity, item.unit)}
                                         </td>
                                         <td className="p-6 text-right">
                                             <div className="flex justify-end gap-2">
@@ -435,11 +440,13 @@ const InventoryList: React.FC<InventoryListProps> = ({ onNavigate }) => {
                             {/* Image Preview / File Upload */}
                             <div className="flex gap-6 items-start">
                                 <div className="w-32 h-32 bg-gray-100 dark:bg-black border-2 border-dashed border-gray-300 dark:border-gray-700 rounded-xl flex items-center justify-center overflow-hidden shrink-0 relative group">
-                                    {formItem.image ? (
-                                        <img src={formItem.image} alt="Preview" className="w-full h-full object-cover" />
-                                    ) : (
-                                        <span className="text-gray-400 text-xs text-center px-2">Görsel Yok</span>
-                                    )}
+                                    <ItemImage
+                                        sku={formItem.sku || ''}
+                                        image={formItem.image}
+                                        alt="Preview"
+                                        className="w-full h-full object-cover"
+                                        fallback={<span className="text-gray-400 text-xs text-center px-2">Görsel Yok</span>}
+                                    />
                                 </div>
                                 <div className="flex-1 space-y-2">
                                     <label className="text-xs text-gray-500 dark:text-hub-muted uppercase font-bold tracking-wider">Ürün Görseli</label>
@@ -0,0 +1,30 @@
+import React, { useEffect, useState } from 'react';
+import { inventoryService } from '../services/inventoryService';
+
+interface ItemImageProps {
+    sku: string;
+    image?: string | null;
+    alt?: string;
+    className?: string;
+    fallback?: React.ReactNode;
+    // Small preview instead of the full picture (lists, grids)
+    thumbnail?: boolean;
+}
+
+// Item images are files in the app data folder; the `image` field only holds their path
+const ItemImage: React.FC<ItemImageProps> = ({ sku, image, alt, className, fallback = null, thumbnail = false }) => {
+    const [src, setSrc] = useState<string | null>(null);
+
+    useEffect(() => {
+        let cancelled = false;
+        inventoryService.getItemImageUrl(sku, image, thumbnail).then(url => {
+            if (!cancelled) setSrc(url);
+        });
+        return () => { cancelled = true; };
+    }, [sku, image, thumbnail]);
+
+    if (!src) return <>{fallback}</>;
+    return <img src={src} alt={alt ?? ''} className={className} />;
+};
+
+export default ItemImage;
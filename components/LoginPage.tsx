@@ -11,6 +11,7 @@ interface UserLoginDisplay {
     username: string;
     displayName: string;
     role: string;
+    passwordRequired?: boolean;
 }
 
 const LoginPage: React.FC = () => {
@@ -60,8 +61,8 @@ const LoginPage: React.FC = () => {
     const handleUserClick = async (user: UserLoginDisplay) => {
         setLocalError('');
 
-        // Admin için şifre modalı aç
-        if (user.role === 'admin') {
+        // Şifre zorunlu roller için şifre modalı aç (giriş politikası)
+        if (user.role === 'admin' || user.passwordRequired) {
             setSelectedUser(user);
             setAdminPassword('');
             setShowPasswordModal(true);
@@ -165,7 +166,7 @@ const LoginPage: React.FC = () => {
                                         <h3 className="font-bold text-lg text-gray-900 dark:text-white">
                                             {selectedUser.displayName || selectedUser.username}
                                         </h3>
-                                        <p className="text-xs text-gray-500 uppercase font-bold">Yönetici Girişi</p>
+                                        <p className="text-xs text-gray-500 uppercase font-bold">{selectedUser?.role === 'admin' ? 'Yönetici Girişi' : 'Şifreli Giriş'}</p>
                                     </div>
                                 </div>
                             </div>
@@ -258,8 +259,8 @@ const LoginPage: React.FC = () => {
                                     </p>
                                 </div>
 
-                                {/* Kilit ikonu admin için */}
-                                {user.role === 'admin' && (
+                                {/* Kilit ikonu şifre zorunlu kullanıcılar için */}
+                                {(user.role === 'admin' || user.passwordRequired) && (
                                     <div className="absolute top-3 right-3 w-6 h-6 bg-gray-100 dark:bg-zinc-800 rounded-full flex items-center justify-center">
                                         <svg className="w-3 h-3 text-gray-500" fill="currentColor" viewBox="0 0 20 20">
                                             <path fillRule="evenodd" d="M5 9V7a5 5 0 0110 0v2a2 2 0 012 2v5a2 2 0 01-2 2H5a2 2 0 01-2-2v-5a2 2 0 012-2zm8-2v2H7V7a3 3 0 016 0z" clipRule="evenodd" />
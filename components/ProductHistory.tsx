@@ -1,6 +1,7 @@
 import React, { useState, useEffect, useMemo } from 'react';
 import { InventoryItem } from '../types';
 import { tauriInvoke, isTauri } from '../services/tauriService';
+import ItemImage from './ItemImage';
 
 interface Transaction {
     id: string;
@@ -29,6 +30,7 @@ interface InventoryLot {
     sellPrice?: number;
     receiptDate: string;
     invoiceNo?: string;
+    expiryDate?: string;
     createdAt: string;
 }
 
@@ -329,11 +331,14 @@ const ProductHistory: React.FC<ProductHistoryProps> = ({ selectedProductId, onBa
                                     >
                                         <div className="flex items-center gap-3">
                                             <div className="w-10 h-10 bg-gray-200 dark:bg-zinc-700 rounded-lg overflow-hidden flex-shrink-0">
-                                                {item.image ? (
-                                                    <img src={item.image} alt={item.name} className="w-full h-full object-cover" />
-                                                ) : (
-                                                    <div className="w-full h-full flex items-center justify-center text-gray-400 text-xs">IMG</div>
-                                                )}
+                                                <ItemImage
+                                                    thumbnail
+                                                    sku={item.sku}
+                                                    image={item.image}
+                                                    alt={item.name}
+                                                    className="w-full h-full object-cover"
+                                                    fallback={<div className="w-full h-full flex items-center justify-center text-gray-400 text-xs">IMG</div>}
+                                                />
                                             </div>
                                             <div className="flex-1 min-w-0">
                                                 <p className="text-sm font-medium text-gray-900 dark:text-white truncate">{item.name}</p>
@@ -367,11 +372,13 @@ const ProductHistory: React.FC<ProductHistoryProps> = ({ selectedProductId, onBa
                             <div className="bg-white dark:bg-zinc-900 rounded-2xl p-6 border border-gray-200 dark:border-zinc-800">
                                 <div className="flex gap-6">
                                     <div className="w-24 h-24 bg-gray-100 dark:bg-zinc-800 rounded-xl overflow-hidden flex-shrink-0">
-                                        {selectedItem.image ? (
-                                            <img src={selectedItem.image} alt={selectedItem.name} className="w-full h-full object-cover" />
-                                        ) : (
-                                            <div className="w-full h-full flex items-center justify-center text-gray-400">GÖRSEL</div>
-                                        )}
+                                        <ItemImage
+                                            sku={selectedItem.sku}
+                                            image={selectedItem.image}
+                                            alt={selectedItem.name}
+                                            className="w-full h-full object-cover"
+                                            fallback={<div className="w-full h-full flex items-center justify-center text-gray-400">GÖRSEL</div>}
+                                        />
                                     </div>
                                     <div className="flex-1">
                                         <h2 className="text-xl font-bold text-gray-900 dark:text-white">{selectedItem.name}</h2>
@@ -464,7 +471,7 @@ const ProductHistory: React.FC<ProductHistoryProps> = ({ selectedProductId, onBa
                                             </svg>
                                             Lot/Parti Takibi ({productLots.length})
                                         </h3>
-                                        <span className="text-xs text-gray-500 dark:text-zinc-400">FIFO - İlk giren ilk çıkar</span>
+                                        <span className="text-xs text-gray-500 dark:text-zinc-400">SKT'si en yakın önce, sonra ilk giren ilk çıkar</span>
                                     </div>
                                     <div className="overflow-x-auto">
                                         <table className="w-full text-sm">
@@ -473,6 +480,7 @@ const ProductHistory: React.FC<ProductHistoryProps> = ({ selectedProductId, onBa
                                                     <th className="px-4 py-3 text-left text-xs font-bold uppercase text-gray-500 dark:text-zinc-400">Tarih</th>
                                                     <th className="px-4 py-3 text-left text-xs font-bold uppercase text-gray-500 dark:text-zinc-400">Tedarikçi</th>
                                                     <th className="px-4 py-3 text-left text-xs font-bold uppercase text-gray-500 dark:text-zinc-400">Fatura No</th>
+                                                    <th className="px-4 py-3 text-left text-xs font-bold uppercase text-gray-500 dark:text-zinc-400">SKT</th>
                                                     <th className="px-4 py-3 text-right text-xs font-bold uppercase text-gray-500 dark:text-zinc-400">Giriş</th>
                                                     <th className="px-4 py-3 text-right text-xs font-bold uppercase text-gray-500 dark:text-zinc-400">Kalan</th>
                                                     <th className="px-4 py-3 text-right text-xs font-bold uppercase text-gray-500 dark:text-zinc-400">Alış ₺</th>
@@ -492,6 +500,9 @@ const ProductHistory: React.FC<ProductHistoryProps> = ({ selectedProductId, onBa
                                                         <td className="px-4 py-3 text-gray-500 dark:text-zinc-400">
                                                             {lot.invoiceNo || '-'}
                                                         </td>
+                                                        <td className="px-4 py-3 text-gray-500 dark:text-zinc-400">
+                                                            {lot.expiryDate ? new Date(lot.expiryDate).toLocaleDateString('tr-TR') : '-'}
+                                                        </td>
                                                         <td className="px-4 py-3 text-right font-medium text-gray-900 dark:text-white">
                                                             {lot.initialQuantity}
                                                         </td>
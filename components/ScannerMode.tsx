@@ -1,8 +1,10 @@
 import React, { useState, useEffect } from 'react';
 import { InventoryItem, CartItem, Transaction } from '../types';
 import { inventoryService } from '../services/inventoryService';
+import ItemImage from './ItemImage';
 import { useRefresh } from '../src/context/RefreshContext';
 import { tauriInvoke } from '../services/tauriService';
+import { allowsFraction, formatQuantity, roundQuantity } from '../services/units';
 
 interface POSModeProps {
     defaultTab?: 'NAKIT' | 'VERESIYE' | 'MAIL_ORDER' | 'IADE';
@@ -18,6 +20,7 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
     const [isCheckingOut, setIsCheckingOut] = useState(false);
     const [successMsg, setSuccessMsg] = useState('');
     const [stockWarning, setStockWarning] = useState<string | null>(null);
+    const [scanNote, setScanNote] = useState<string | null>(null);
 
     // Tabs: 'NAKIT' | 'VERESIYE' | 'MAIL_ORDER' | 'IADE'
     const [activeTab, setActiveTab] = useState<'NAKIT' | 'VERESIYE' | 'MAIL_ORDER' | 'IADE'>(defaultTab);
@@ -145,7 +148,7 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
         setCart(prevCart => {
             return prevCart.map(cartItem => {
                 if (cartItem.cartId === cartId) {
-                    const newQty = cartItem.cartQuantity + delta;
+                    const newQty = roundQuantity(cartItem.cartQuantity + delta);
                     if (activeTab !== 'IADE' && delta > 0) {
                         const originalItem = items.find(i => i.id === cartItem.id);
                         if (originalItem && newQty > originalItem.quantity) {
@@ -159,6 +162,20 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
         });
     };
 
+    // Measured units (KG, MT...) take a typed amount like 2,5 instead of +/- steps
+    const setCartQuantity = (cartId: string, value: string) => {
+        const quantity = roundQuantity(Number(value.replace(',', '.')));
+        if (!Number.isFinite(quantity) || quantity <= 0) return;
+        setCart(prevCart => prevCart.map(cartItem => {
+            if (cartItem.cartId !== cartId) return cartItem;
+            const originalItem = items.find(i => i.id === cartItem.id);
+            if (activeTab !== 'IADE' && originalItem && quantity > originalItem.quantity) {
+                return cartItem;
+            }
+            return { ...cartItem, cartQuantity: quantity };
+        }));
+    };
+
     const removeFromCart = (cartId: string) => {
         setCart(cart.filter(i => i.cartId !== cartId));
     };
@@ -405,7 +422,7 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
                             {cart.filter(item => item.id !== 'discount').map((item, i) => (
                                 <tr key={i} className={`border-b border-gray-300 ${i % 2 === 0 ? 'bg-white' : 'bg-gray-50'}`}>
                                     <td className="py-3 px-4 font-medium">{item.name}</td>
-                                    <td className="py-3 px-2 text-center font-mono">{item.cartQuantity}</td>
+                                    <td className="py-3 px-2 text-center font-mono">{formatQuantity(item.cartQuantity, item.unit)}</td>
                                     <td className="py-3 px-4 text-right font-mono">₺{item.price.toFixed(2)}</td>
                                     <td className="py-3 px-4 text-right font-mono font-bold">₺{(item.price * item.cartQuantity).toFixed(2)}</td>
                                 </tr>
@@ -474,17 +491,22 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
                         placeholder="BARKOD TARA VEYA ÜRÜN ARA..."
                         value={search}
                         onChange={(e) => setSearch(e.target.value)}
-                        onKeyDown={(e) => {
+                        onKeyDown={async (e) => {
                             if (e.key === 'Enter') {
                                 const query = search.trim();
                                 if (!query) return;
 
-                                // Find exact match first
-                                const exactMatch = items.find(i => i.sku === query || i.id === query);
-
-                                if (exactMatch) {
-                                    addToCart(exactMatch);
+                                // Scanned code: SKU, stock card barcode or scale label
+                                const found = await inventoryService.lookupByBarcode(query).catch(() => null);
+                                if (found) {
+                                    addToCart(found.item);
                                     setSearch(''); // Clear after auto-add
+                                    if (found.matchedBy === 'StockCard') {
+                                        setScanNote(`${found.item.name} stok kartı barkodundan bulundu`);
+                                    } else if (found.matchedBy === 'PriceEmbedded') {
+                                        setScanNote(`${found.item.name} terazi etiketinden bulundu (${found.embeddedPrice?.toFixed(2)} TL)`);
+                                    }
+                                    setTimeout(() => setScanNote(null), 3000);
                                     return;
                                 }
 
@@ -505,6 +527,12 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
                     />
                 </div>
 
+                {scanNote && (
+                    <div className="mb-4 px-4 py-3 rounded-xl bg-blue-50 dark:bg-blue-900/20 border border-blue-200 dark:border-blue-800 text-blue-600 dark:text-blue-400 text-sm font-bold">
+                        {scanNote}
+                    </div>
+                )}
+
                 {/* Stock Warning Toast */}
                 {stockWarning && (
                     <div className="mb-4 px-4 py-3 rounded-xl bg-red-50 dark:bg-red-900/20 border border-red-200 dark:border-red-800 text-red-600 dark:text-red-400 text-sm font-bold flex items-center gap-2 animate-pulse">
@@ -525,7 +553,7 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
                                 className="text-left rounded-2xl border overflow-hidden flex flex-col justify-between transition-all min-h-[220px] shadow-sm hover:shadow-md bg-white dark:bg-zinc-900 border-gray-200 dark:border-zinc-800 hover:border-blue-500"
                             >
                                 <div className="h-32 bg-gray-200 dark:bg-zinc-800 relative">
-                                    {item.image ? <img src={item.image} className="w-full h-full object-cover" /> : <div className="p-4 text-xs text-gray-400 text-center flex items-center justify-center h-full">IMG</div>}
+                                    <ItemImage thumbnail sku={item.sku} image={item.image} className="w-full h-full object-cover" fallback={<div className="p-4 text-xs text-gray-400 text-center flex items-center justify-center h-full">IMG</div>} />
                                     <div className="absolute top-2 right-2 bg-black text-white text-[10px] px-2 py-1 rounded-full font-bold">{item.quantity} ADET</div>
                                 </div>
                                 <div className="p-3">
@@ -578,21 +606,32 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
                         return (
                             <div key={item.cartId} className={`bg-white dark:bg-zinc-800 p-3 rounded-xl border flex gap-3 shadow-sm group ${isAtMaxStock ? 'border-red-300 dark:border-red-800' : isLowStock ? 'border-yellow-300 dark:border-yellow-800' : 'border-gray-200 dark:border-zinc-700'}`}>
                                 <div className="w-12 h-12 bg-gray-200 dark:bg-zinc-700 rounded-lg shrink-0 overflow-hidden">
-                                    {item.image && <img src={item.image} className="w-full h-full object-cover" />}
+                                    <ItemImage thumbnail sku={item.sku} image={item.image} className="w-full h-full object-cover" />
                                 </div>
                                 <div className="flex-1 min-w-0">
                                     <div className="flex items-center gap-2">
                                         <div className="font-bold text-sm truncate">{item.name}</div>
                                         {activeTab !== 'IADE' && (
                                             <span className={`text-[9px] font-bold px-1.5 py-0.5 rounded ${isAtMaxStock ? 'bg-red-100 text-red-600 dark:bg-red-900/50' : isLowStock ? 'bg-yellow-100 text-yellow-600 dark:bg-yellow-900/50' : 'bg-gray-100 text-gray-500 dark:bg-zinc-700'}`}>
-                                                Stok: {availableStock}
+                                                Stok: {formatQuantity(availableStock, originalItem?.unit)}
                                             </span>
                                         )}
                                     </div>
                                     <div className="flex justify-between items-center mt-2">
                                         <div className="flex items-center gap-2 bg-gray-100 dark:bg-black rounded-lg p-1">
                                             <button onClick={() => updateQuantity(item.cartId, -1)} className="w-6 h-6 flex items-center justify-center bg-white dark:bg-zinc-800 rounded font-bold hover:bg-red-50">-</button>
-                                            <span className={`w-6 text-center font-mono font-bold text-sm ${isAtMaxStock ? 'text-red-500' : ''}`}>{item.cartQuantity}</span>
+                                            {allowsFraction(item.unit) ? (
+                                                <input
+                                                    key={item.cartQuantity}
+                                                    defaultValue={item.cartQuantity}
+                                                    inputMode="decimal"
+                                                    onBlur={e => setCartQuantity(item.cartId, e.target.value)}
+                                                    onKeyDown={e => e.key === 'Enter' && e.currentTarget.blur()}
+                                                    className={`w-14 text-center font-mono font-bold text-sm bg-transparent outline-none ${isAtMaxStock ? 'text-red-500' : ''}`}
+                                                />
+                                            ) : (
+                                                <span className={`w-6 text-center font-mono font-bold text-sm ${isAtMaxStock ? 'text-red-500' : ''}`}>{item.cartQuantity}</span>
+                                            )}
                                             <button
                                                 onClick={() => updateQuantity(item.cartId, 1)}
                                                 className={`w-6 h-6 flex items-center justify-center bg-white dark:bg-zinc-800 rounded font-bold ${isAtMaxStock ? 'opacity-50 cursor-not-allowed' : 'hover:bg-green-50'}`}
@@ -885,7 +924,7 @@ const POSMode: React.FC<POSModeProps> = ({ defaultTab = 'NAKIT', hideTabs = fals
                                     {printData.cart.filter(item => item.id !== 'discount').map((item, i) => (
                                         <tr key={i} className={`border-b border-gray-300 ${i % 2 === 0 ? 'bg-white' : 'bg-gray-50'}`}>
                                             <td className="py-3 px-4 font-medium">{item.name}</td>
-                                            <td className="py-3 px-2 text-center font-mono">{item.cartQuantity}</td>
+                                            <td className="py-3 px-2 text-center font-mono">{formatQuantity(item.cartQuantity, item.unit)}</td>
                                             <td className="py-3 px-4 text-right font-mono">₺{item.price.toFixed(2)}</td>
                                             <td className="py-3 px-4 text-right font-mono font-bold">₺{(item.price * item.cartQuantity).toFixed(2)}</td>
                                         </tr>
@@ -27,7 +27,10 @@ const LicenseSettings: React.FC = () => {
 
     const handleDeactivate = async () => {
         if (confirm('Bu lisansı bu cihazdan kaldırmak istediğinize emin misiniz? Tekrar giriş yapmanız gerekecektir.')) {
-            await deactivateLicense();
+            const warning = await deactivateLicense();
+            if (warning) {
+                alert(warning);
+            }
             window.location.reload();
         }
     };
@@ -250,9 +253,9 @@ const Settings: React.FC = () => {
     const handleClearDatabase = async () => {
         setIsLoading(true);
         try {
-            await inventoryService.clearDatabase();
+            const message = await inventoryService.clearDatabase();
             setDbStats({ itemCount: 0, totalQuantity: 0 });
-            setImportStatus('Veritabanı temizlendi.');
+            setImportStatus(message || 'Veritabanı temizlendi.');
             setIsDeleteModalOpen(false);
         } catch (error) {
             setImportStatus(`Hata: ${error}`);
@@ -423,18 +426,12 @@ const Settings: React.FC = () => {
                                                 onClick={async () => {
                                                     setIsLoading(true);
                                                     try {
-                                                        interface CloudBackupResult {
-                                                            success: boolean;
-                                                            message?: string;
-                                                            error?: string;
-                                                        }
-                                                        const { tauriInvoke } = await import('../services/tauriService');
-                                                        const result = await tauriInvoke<CloudBackupResult>('cloud_backup');
-                                                        if (result.success) {
-                                                            setImportStatus('✅ Bulut yedeği başarıyla alındı!');
-                                                        } else {
-                                                            setImportStatus(`❌ Bulut yedekleme hatası: ${result.message || result.error}`);
-                                                        }
+                                                        const { cloudService } = await import('../services/cloudService');
+                                                        await cloudService.backup((p) => {
+                                                            const percent = Math.round((p.bytesSent / Math.max(p.totalBytes, 1)) * 100);
+                                                            setImportStatus(`⏳ Bulut yedeği gönderiliyor... %${percent}`);
+                                                        });
+                                                        setImportStatus('✅ Bulut yedeği başarıyla alındı!');
                                                     } catch (e) {
                                                         setImportStatus(`❌ Hata: ${e}`);
                                                     } finally {
@@ -454,8 +451,12 @@ const Settings: React.FC = () => {
 
                                                     setIsLoading(true);
                                                     try {
-                                                        const { tauriInvoke } = await import('../services/tauriService');
-                                                        await tauriInvoke('cloud_restore');
+                                                        const { cloudService } = await import('../services/cloudService');
+                                                        let result = await cloudService.restore();
+                                                        if (!result.restored) {
+                                                            if (!confirm(`${result.comparison.warnings.join(', ')}.\nYine de geri yüklemek istiyor musunuz?`)) return;
+                                                            result = await cloudService.restore(true);
+                                                        }
                                                         setImportStatus('✅ Buluttan başarıyla geri yüklendi! Uygulama yeniden başlatılıyor...');
                                                         setTimeout(() => window.location.reload(), 2000);
                                                     } catch (e) {
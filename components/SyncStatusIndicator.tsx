@@ -1,11 +1,12 @@
 import React, { useEffect, useState } from 'react';
-import { Cloud, CloudOff, RefreshCw, Check } from 'lucide-react';
+import { Cloud, CloudOff, RefreshCw, Check, AlertTriangle } from 'lucide-react';
 import { tauriInvoke, isTauri } from '../services/tauriService';
 
 interface SyncState {
   last_push_at: string | null;
   last_pull_at: string | null;
   sync_in_progress: boolean;
+  last_error: string | null;
   pending_count: number;
 }
 
@@ -33,7 +34,34 @@ const SyncStatusIndicator: React.FC = () => {
     fetchSyncStatus();
     // Refresh every 30 seconds
     const interval = setInterval(fetchSyncStatus, 30000);
-    return () => clearInterval(interval);
+
+    // The background sync reports each cycle; refresh as soon as one ends
+    const unlisteners: Array<() => void> = [];
+    let disposed = false;
+    if (isTauri()) {
+      import('@tauri-apps/api/event').then(({ listen }) =>
+        Promise.all([
+          listen('device-sync-started', () => setIsSyncing(true)),
+          listen<{ pushed: number; pulled: number }>('device-sync-done', () => {
+            setIsSyncing(false);
+            fetchSyncStatus();
+          }),
+          listen<{ message: string }>('device-sync-error', () => {
+            setIsSyncing(false);
+            fetchSyncStatus();
+          }),
+        ])
+      ).then((fns) => {
+        if (disposed) fns.forEach((unlisten) => unlisten());
+        else unlisteners.push(...fns);
+      });
+    }
+
+    return () => {
+      disposed = true;
+      clearInterval(interval);
+      unlisteners.forEach((unlisten) => unlisten());
+    };
   }, []);
 
   const handleManualSync = async () => {
@@ -80,6 +108,12 @@ const SyncStatusIndicator: React.FC = () => {
         </span>
       </div>
 
+      {syncState?.last_error && (
+        <span title={`Son senkronizasyon basarisiz: ${syncState.last_error}`}>
+          <AlertTriangle size={12} className="text-red-500" />
+        </span>
+      )}
+
       {hasPending && (
         <span className="px-1.5 py-0.5 text-[10px] font-bold bg-amber-100 dark:bg-amber-900/30 text-amber-700 dark:text-amber-400 rounded">
           {syncState.pending_count}
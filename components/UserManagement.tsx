@@ -2,6 +2,7 @@ import React, { useState, useEffect } from 'react';
 import { tauriInvoke } from '../services/tauriService';
 import { User } from '../types';
 import { inventoryService } from '../services/inventoryService';
+import { useAuth } from '../src/context/AuthContext';
 
 interface Transaction {
     id: string;
@@ -26,6 +27,7 @@ interface UserManagementProps {
 }
 
 const UserManagement: React.FC<UserManagementProps> = ({ defaultTab = 'USERS', isStatementMode = false }) => {
+    const { user: currentUser } = useAuth();
     const [users, setUsers] = useState<User[]>([]);
     const [isLoading, setIsLoading] = useState(true);
     const [showCreateModal, setShowCreateModal] = useState(false);
@@ -143,7 +145,7 @@ const UserManagement: React.FC<UserManagementProps> = ({ defaultTab = 'USERS', i
         if (!confirm(`"${user.displayName}" kullanıcısını silmek istediğinize emin misiniz?`)) return;
 
         try {
-            await tauriInvoke('delete_user', { id: user.id });
+            await tauriInvoke('delete_user', { id: user.id, sessionToken: currentUser?.token ?? '' });
             setSuccess('Kullanıcı silindi');
             loadUsers();
         } catch (err) {
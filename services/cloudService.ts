@@ -1,11 +1,13 @@
 import { tauriInvoke, isTauri } from './tauriService';
-import { SyncStatus, CloudBackupResponse, CloudStatusResponse } from '../types';
+import { SyncStatus, CloudBackupResponse, CloudBackupProgress, CloudStatusResponse, CloudRestoreResult, AutoSyncSettingsResult } from '../types';
 
 class CloudService {
   /**
-   * Backup database to cloud
+   * Backup database to cloud.
+   * The upload runs in the background; resolves when the `cloud-backup-complete`
+   * event for the started job arrives and rejects on `cloud-backup-error`.
    */
-  async backup(): Promise<CloudBackupResponse> {
+  async backup(onProgress?: (progress: CloudBackupProgress) => void): Promise<CloudBackupResponse> {
     if (!isTauri()) {
       return {
         success: true,
@@ -14,18 +16,65 @@ class CloudService {
         message: 'Development mode - backup simulated',
       };
     }
-    return tauriInvoke<CloudBackupResponse>('cloud_backup');
+
+    const { listen } = await import('@tauri-apps/api/event');
+    // Subscribe before starting so a fast job's events are not missed
+    let jobId: string | null = null;
+    const early: Array<() => void> = [];
+    const unlisteners: Array<() => void> = [];
+    try {
+      return await new Promise<CloudBackupResponse>((resolve, reject) => {
+        const forJob = (id: string, handle: () => void) => {
+          if (jobId === null) early.push(() => id === jobId && handle());
+          else if (id === jobId) handle();
+        };
+        Promise.all([
+          listen<CloudBackupProgress>('cloud-backup-progress', (e) =>
+            forJob(e.payload.jobId, () => onProgress?.(e.payload))),
+          listen<{ jobId: string; response: CloudBackupResponse }>('cloud-backup-complete', (e) =>
+            forJob(e.payload.jobId, () => resolve(e.payload.response))),
+          listen<{ jobId: string; error: string }>('cloud-backup-error', (e) =>
+            forJob(e.payload.jobId, () => reject(new Error(e.payload.error)))),
+        ])
+          .then((fns) => {
+            unlisteners.push(...fns);
+            return tauriInvoke<string>('cloud_backup');
+          })
+          .then((id) => {
+            jobId = id;
+            early.splice(0).forEach((run) => run());
+          })
+          .catch(reject);
+      });
+    } finally {
+      unlisteners.forEach((unlisten) => unlisten());
+    }
   }
 
   /**
-   * Restore database from cloud
+   * Restore database from cloud.
+   * If the cloud backup is older or smaller than local data, nothing is restored
+   * (restored=false) until called again with force=true.
    */
-  async restore(): Promise<void> {
+  async restore(force = false): Promise<CloudRestoreResult> {
     if (!isTauri()) {
       console.log('Development mode - restore simulated');
-      return;
+      const empty = { transactionCount: 0, itemCount: 0, accountCount: 0 };
+      return {
+        restored: true,
+        comparison: {
+          local: empty,
+          cloud: empty,
+          transactionDifference: 0,
+          itemDifference: 0,
+          accountDifference: 0,
+          requiresForce: false,
+          warnings: [],
+        },
+        message: 'Development mode - restore simulated',
+      };
     }
-    return tauriInvoke<void>('cloud_restore');
+    return tauriInvoke<CloudRestoreResult>('cloud_restore', { force });
   }
 
   /**
@@ -61,14 +110,23 @@ class CloudService {
   /**
    * Set auto sync settings
    */
-  async setAutoSync(enabled: boolean, intervalMinutes: number): Promise<void> {
+  async setAutoSync(enabled: boolean, intervalMinutes: number, checkServer = true): Promise<AutoSyncSettingsResult> {
     if (!isTauri()) {
       console.log('Development mode - auto sync settings:', { enabled, intervalMinutes });
-      return;
+      const effective = Math.min(Math.max(intervalMinutes, 5), 1440);
+      return {
+        status: { autoSyncEnabled: enabled, autoSyncIntervalMinutes: effective },
+        requestedIntervalMinutes: intervalMinutes,
+        effectiveIntervalMinutes: effective,
+        intervalClamped: effective !== intervalMinutes,
+        firstSyncScheduled: enabled,
+        warnings: [],
+      };
     }
-    return tauriInvoke<void>('set_auto_sync', {
+    return tauriInvoke<AutoSyncSettingsResult>('set_auto_sync', {
       enabled,
       intervalMinutes,
+      checkServer,
     });
   }
 
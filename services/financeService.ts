@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { FinanceRecord, FinanceSummary } from '../types';
+import { AccountStatement, FinanceRecord, FinanceSummary, RecurringExpense, Transaction, ZReport } from '../types';
 
 export const financeService = {
     // Add new finance record
@@ -31,6 +31,16 @@ export const financeService = {
         }
     },
 
+    // Update finance record in place; a goods receipt (MAL_KABUL) amount needs force
+    updateRecord: async (id: number, record: FinanceRecord, force = false): Promise<void> => {
+        try {
+            await invoke('update_finance_record', { id, record, force });
+        } catch (error) {
+            console.error('Error updating finance record:', error);
+            throw error;
+        }
+    },
+
     // Delete finance record
     deleteRecord: async (id: number): Promise<void> => {
         try {
@@ -52,17 +62,49 @@ export const financeService = {
         }
     },
 
+    // Close the day (Z raporu); fails if the day was already closed
+    generateZReport: async (date: string): Promise<ZReport> => {
+        try {
+            return await invoke('generate_z_report', { date });
+        } catch (error) {
+            console.error('Error generating Z report:', error);
+            throw error;
+        }
+    },
+
+    // Current account statement (ekstre) between two dates, YYYY-MM-DD inclusive
+    getAccountStatement: async (accountId: string, startDate: string, endDate: string): Promise<AccountStatement> => {
+        try {
+            return await invoke('get_account_statement', { accountId, startDate, endDate });
+        } catch (error) {
+            console.error('Error getting account statement:', error);
+            throw error;
+        }
+    },
+
+    // Customer paying their debt or us paying a supplier; overpaying needs allowOverpay
+    recordAccountPayment: async (accountId: string, amount: number, paymentMethod: string, date: string, note?: string | null, allowOverpay = false): Promise<Transaction> => {
+        try {
+            return await invoke('record_account_payment', { accountId, amount, paymentMethod, date, note: note || null, allowOverpay });
+        } catch (error) {
+            console.error('Error recording account payment:', error);
+            throw error;
+        }
+    },
+
     // Process Goods Receipt (Mal Kabul)
-    processGoodsReceipt: async (items: any[], totalAmount: number, paymentMethod: string, description: string, date: string, supplierId?: string | null, invoiceNo?: string | null): Promise<void> => {
+    // Returns warnings when quantities differ from the purchase order
+    processGoodsReceipt: async (items: any[], totalAmount: number, paymentMethod: string, description: string, date: string, supplierId?: string | null, invoiceNo?: string | null, purchaseOrderId?: string | null): Promise<string[]> => {
         try {
-            await invoke('process_goods_receipt', {
+            return await invoke<string[]>('process_goods_receipt', {
                 items,
                 totalAmount,
                 paymentMethod,
                 description,
                 date,
                 supplierId: supplierId || null,
-                invoiceNo: invoiceNo || null
+                invoiceNo: invoiceNo || null,
+                purchaseOrderId: purchaseOrderId || null
             });
         } catch (error) {
             console.error('Error processing goods receipt:', error);
@@ -70,6 +112,43 @@ export const financeService = {
         }
     },
 
+    // Recurring expenses (kira, fatura); the app writes each one once a month on its day
+    getRecurringExpenses: async (): Promise<RecurringExpense[]> => {
+        try {
+            return await invoke('get_recurring_expenses');
+        } catch (error) {
+            console.error('Error getting recurring expenses:', error);
+            return [];
+        }
+    },
+
+    createRecurringExpense: async (expense: RecurringExpense): Promise<RecurringExpense> => {
+        try {
+            return await invoke('create_recurring_expense', { expense });
+        } catch (error) {
+            console.error('Error creating recurring expense:', error);
+            throw error;
+        }
+    },
+
+    updateRecurringExpense: async (expense: RecurringExpense): Promise<void> => {
+        try {
+            await invoke('update_recurring_expense', { expense });
+        } catch (error) {
+            console.error('Error updating recurring expense:', error);
+            throw error;
+        }
+    },
+
+    deleteRecurringExpense: async (id: string): Promise<void> => {
+        try {
+            await invoke('delete_recurring_expense', { id });
+        } catch (error) {
+            console.error('Error deleting recurring expense:', error);
+            throw error;
+        }
+    },
+
     // Expense Categories
     getExpenseCategories: async (): Promise<string[]> => {
         try {
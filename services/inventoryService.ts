@@ -1,5 +1,5 @@
 import api from '../web/services/api';
-import { InventoryItem, CartItem, Transaction, DashboardStats } from '../types';
+import { InventoryItem, BarcodeLookup, DeletedItem, CartItem, SaleDiscount, ItemSortColumn, SortDirection, Transaction, DashboardStats, DatabaseSchemaInfo, AttributeValues, ImportStageSummary, PriceOutlier, ImportCommitResult, CsvImportResult } from '../types';
 import { isTauri, tauriInvoke, saveFileDialog, openFileDialog } from './tauriService';
 
 // Empty array for web development fallback - no mock data
@@ -8,6 +8,8 @@ const MOCK_DATA: InventoryItem[] = [];
 class InventoryService {
   private items: InventoryItem[] = [...MOCK_DATA];
   private transactions: Transaction[] = [];
+  // Object URLs of image files, by stored path (the path is the content hash, so it never goes stale)
+  private imageUrls = new Map<string, Promise<string | null>>();
 
   async getAllItems(): Promise<InventoryItem[]> {
     if (isTauri()) {
@@ -34,10 +36,10 @@ class InventoryService {
   }
 
   // Get Items with pagination and search (Web & Tauri)
-  async getItems(page: number, perPage: number, search: string = '', category: string = ''): Promise<{ data: InventoryItem[], total: number }> {
+  async getItems(page: number, perPage: number, search: string = '', category: string = '', attributes?: Record<string, string>, sortBy?: ItemSortColumn, sortDir?: SortDirection): Promise<{ data: InventoryItem[], total: number }> {
     if (isTauri()) {
       try {
-        const result = await tauriInvoke<{ data: InventoryItem[], total: number }>('get_items_paginated', { page, perPage, search, category });
+        const result = await tauriInvoke<{ data: InventoryItem[], total: number }>('get_items_paginated', { page, perPage, search, category, attributes, sortBy, sortDir });
         return { data: result.data || [], total: result.total || 0 };
       } catch (error) {
         // If get_items is not implemented, fallback to getAll and filter
@@ -73,10 +75,13 @@ class InventoryService {
     return this.items.find(i => i.sku === sku);
   }
 
-  async addItem(item: InventoryItem): Promise<void> {
+  /**
+   * onConflict: mevcut SKU için 'error' (varsayılan), 'update' (üzerine yaz) veya 'skip'
+   */
+  async addItem(item: InventoryItem, onConflict?: 'error' | 'update' | 'skip'): Promise<void> {
     if (isTauri()) {
       try {
-        await tauriInvoke('add_item', { item });
+        await tauriInvoke('add_item', { item, onConflict });
         return;
       } catch (error) {
         console.error('Tauri addItem failed:', error);
@@ -115,6 +120,85 @@ class InventoryService {
     this.items = this.items.filter(i => i.sku !== sku);
   }
 
+  // Ranked full-text search (Turkish letters folded), best match first
+  async searchItems(query: string, limit?: number): Promise<InventoryItem[]> {
+    if (isTauri()) {
+      return tauriInvoke<InventoryItem[]>('search_items', { query, limit });
+    }
+    const q = query.toLowerCase();
+    return this.items
+      .filter(i => i.name.toLowerCase().includes(q) || i.sku.toLowerCase().includes(q))
+      .slice(0, limit ?? 50);
+  }
+
+  // Item of a scanned barcode: by SKU, stock card barcode or scale label item code
+  async lookupByBarcode(barcode: string): Promise<BarcodeLookup | null> {
+    if (isTauri()) {
+      return tauriInvoke<BarcodeLookup | null>('lookup_by_barcode', { barcode });
+    }
+    const item = this.items.find(i => i.sku === barcode);
+    return item ? { item, matchedBy: 'Sku' } : null;
+  }
+
+  // Trash (deleted items) - Tauri only
+  async restoreItem(sku: string): Promise<InventoryItem> {
+    return tauriInvoke<InventoryItem>('restore_item', { sku });
+  }
+
+  async listDeletedItems(): Promise<DeletedItem[]> {
+    if (!isTauri()) return [];
+    return tauriInvoke<DeletedItem[]>('list_deleted_items');
+  }
+
+  // Permanently removes items deleted more than olderThanDays days ago
+  async purgeDeletedItems(olderThanDays: number): Promise<number> {
+    return tauriInvoke<number>('purge_deleted_items', { olderThanDays });
+  }
+
+  // Variant attributes (size, color, base type...) - Tauri only
+  async getItemAttributes(itemId: string): Promise<Record<string, string>> {
+    if (!isTauri()) return {};
+    return tauriInvoke<Record<string, string>>('get_item_attributes', { itemId });
+  }
+
+  async setItemAttributes(itemId: string, attributes: Record<string, string>, replace: boolean = false): Promise<Record<string, string>> {
+    return tauriInvoke<Record<string, string>>('set_item_attributes', { itemId, attributes, replace });
+  }
+
+  async getAttributeValues(): Promise<AttributeValues[]> {
+    if (!isTauri()) return [];
+    return tauriInvoke<AttributeValues[]>('get_attribute_values');
+  }
+
+  // Displayable URL of an item image (or its small thumbnail): inline data URLs as they are,
+  // image files loaded once - Tauri only
+  async getItemImageUrl(sku: string, image?: string | null, thumbnail: boolean = false): Promise<string | null> {
+    if (!image) return null;
+    if (image.startsWith('data:') || image.startsWith('http') || !isTauri()) return image;
+    const key = thumbnail ? `${image}#thumb` : image;
+    let url = this.imageUrls.get(key);
+    if (!url) {
+      url = tauriInvoke<number[] | null>(thumbnail ? 'get_item_thumbnail' : 'get_item_image', { sku })
+        .then(bytes => bytes ? URL.createObjectURL(new Blob([new Uint8Array(bytes)])) : null)
+        .catch(error => {
+          console.error('Item image load failed:', error);
+          this.imageUrls.delete(key);
+          return null;
+        });
+      this.imageUrls.set(key, url);
+    }
+    return url;
+  }
+
+  async saveItemImage(sku: string, bytes: Uint8Array): Promise<string> {
+    return tauriInvoke<string>('save_item_image', { sku, bytes: Array.from(bytes) });
+  }
+
+  // Stock count correction with a reason; returns the signed difference - Tauri only
+  async adjustStock(sku: string, newQuantity: number, reason: string): Promise<number> {
+    return tauriInvoke<number>('adjust_stock', { sku, newQuantity, reason });
+  }
+
   async updateQuantity(sku: string, delta: number): Promise<InventoryItem | undefined> {
     if (isTauri()) {
       try {
@@ -136,12 +220,12 @@ class InventoryService {
     return undefined;
   }
 
-  async processSale(cartItems: CartItem[], paymentMethod: string, transactionType: string, note: string = '', customerId?: string): Promise<boolean> {
+  async processSale(cartItems: CartItem[], paymentMethod: string, transactionType: string, note: string = '', customerId?: string, discount?: SaleDiscount): Promise<boolean> {
     console.log('[inventoryService] processSale called, isTauri:', isTauri());
     if (isTauri()) {
       try {
         console.log('[inventoryService] Using Tauri backend for processSale', { customerId });
-        await tauriInvoke('process_sale', { cartItems, paymentMethod, transactionType, note, customerId });
+        await tauriInvoke('process_sale', { cartItems, paymentMethod, transactionType, note, customerId, discount });
         return true;
       } catch (error) {
         console.error('Tauri processSale failed:', error);
@@ -154,8 +238,10 @@ class InventoryService {
     for (const item of cartItems) {
       const qtyChange = transactionType === 'RETURN' ? (item.cartQuantity || 1) : -(item.cartQuantity || 1);
       await this.updateQuantity(item.sku, qtyChange);
-      total += item.price * (item.cartQuantity || 1);
+      const gross = item.price * (item.cartQuantity || 1);
+      total += gross - (item.discountAmount || gross * (item.discountPercent || 0) / 100);
     }
+    total -= discount?.amount || total * (discount?.percent || 0) / 100;
 
     if (transactionType === 'RETURN') total = -total;
 
@@ -207,6 +293,30 @@ class InventoryService {
     }
   }
 
+  // Accepts a transaction id or the text of a scanned receipt QR
+  async getTransactionById(id: string): Promise<Transaction> {
+    if (isTauri()) {
+      return await tauriInvoke<Transaction>('get_transaction_by_id', { id });
+    }
+    // Web Fallback
+    const txId = id.trim().replace(/^FIS:/, '').split('.')[0];
+    const tx = this.transactions.find(t => t.id === txId);
+    if (!tx) throw new Error('İşlem bulunamadı');
+    return tx;
+  }
+
+  async voidTransaction(id: string): Promise<Transaction> {
+    if (isTauri()) {
+      return await tauriInvoke<Transaction>('void_transaction', { id });
+    }
+    // Web Fallback
+    const tx = this.transactions.find(t => t.id === id);
+    if (!tx) throw new Error('İşlem bulunamadı');
+    if (tx.status === 'voided') throw new Error('Bu işlem zaten iptal edilmiş');
+    tx.status = 'voided';
+    return tx;
+  }
+
   async getTransactionsByDateRange(startDate: string, endDate: string): Promise<Transaction[]> {
     if (isTauri()) {
       try {
@@ -225,6 +335,20 @@ class InventoryService {
     });
   }
 
+  // Transactions with a line for the given SKU - Tauri only
+  async getTransactionsBySku(itemSku: string, page: number = 1, perPage: number = 20): Promise<{ data: Transaction[], total: number, total_pages: number }> {
+    if (!isTauri()) return { data: [], total: 0, total_pages: 0 };
+    return tauriInvoke<{ data: Transaction[], total: number, total_pages: number }>('get_transactions_with_pagination', {
+      page,
+      perPage,
+      startDate: null,
+      endDate: null,
+      transactionType: null,
+      customerId: null,
+      itemSku
+    });
+  }
+
   async getCustomerTransactions(customerId: string, page: number = 1, perPage: number = 20): Promise<{ data: Transaction[], total: number, total_pages: number }> {
     if (isTauri()) {
       try {
@@ -250,25 +374,45 @@ class InventoryService {
     };
   }
 
-  async getDashboardStats(): Promise<DashboardStats> {
+  async getDashboardStats(startDate?: string, endDate?: string): Promise<DashboardStats> {
     if (isTauri()) {
       try {
-        return await tauriInvoke<DashboardStats>('get_dashboard_stats');
+        return await tauriInvoke<DashboardStats>('get_dashboard_stats', { startDate, endDate });
       } catch (error) {
         console.error('Tauri getDashboardStats failed:', error);
         // Fallback to empty stats or rethrow?
         // Let's return empty stats to prevent crash
-        return { totalItems: 0, totalQuantity: 0, lowStockCount: 0, totalRevenue: 0 };
+        return {
+          totalItems: 0, totalQuantity: 0, lowStockCount: 0, totalRevenue: 0,
+          saleCount: 0, returnCount: 0, averageBasket: 0, periodStart: '', periodEnd: ''
+        };
       }
     }
 
     // Web Fallback Calculation
     const totalItems = this.items.length;
     const totalQuantity = this.items.reduce((acc, i) => acc + i.quantity, 0);
-    const lowStockCount = this.items.filter(i => i.quantity < 10).length;
-    const totalRevenue = (this.transactions || []).reduce((acc, t) => acc + t.total, 0);
+    const lowStockCount = this.items.filter(i => i.quantity < (i.minStockLevel ?? 10)).length;
+    const counted = (this.transactions || []).filter(t => t.status !== 'voided');
+    const totalRevenue = counted.reduce((acc, t) => acc + t.total, 0);
+    const sales = counted.filter(t => t.transactionType === 'SALE');
+    const saleCount = sales.length;
+    const returnCount = counted.filter(t => t.transactionType === 'RETURN').length;
+    const averageBasket = saleCount > 0 ? sales.reduce((acc, t) => acc + t.total, 0) / saleCount : 0;
+
+    return {
+      totalItems, totalQuantity, lowStockCount, totalRevenue,
+      saleCount, returnCount, averageBasket, periodStart: startDate ?? '', periodEnd: endDate ?? ''
+    };
+  }
 
-    return { totalItems, totalQuantity, lowStockCount, totalRevenue };
+  async getLowStockItems(): Promise<InventoryItem[]> {
+    if (isTauri()) {
+      return await tauriInvoke<InventoryItem[]>('get_low_stock_items');
+    }
+    return this.items
+      .filter(i => i.quantity < (i.minStockLevel ?? 10))
+      .sort((a, b) => (a.quantity - (a.minStockLevel ?? 10)) - (b.quantity - (b.minStockLevel ?? 10)));
   }
 
   async seedDatabase(): Promise<string> {
@@ -371,11 +515,22 @@ class InventoryService {
     return 'Dosya indirildi (Web)';
   }
 
-  async clearDatabase(): Promise<void> {
+  // Nightly stock + transactions CSV into a folder (e.g. the accountant's shared folder)
+  async startScheduledExport(dir: string, hour: number, minute: number): Promise<void> {
+    if (!isTauri()) throw new Error('Zamanlanmış dışa aktarım yalnızca masaüstü uygulamasında çalışır');
+    await tauriInvoke('start_scheduled_export', { dir, hour, minute });
+  }
+
+  async stopScheduledExport(): Promise<void> {
+    if (isTauri()) {
+      await tauriInvoke('stop_scheduled_export');
+    }
+  }
+
+  async clearDatabase(): Promise<string | void> {
     if (isTauri()) {
       try {
-        await tauriInvoke('clear_database');
-        return;
+        return await tauriInvoke<string>('clear_database');
       } catch (error) {
         console.error('Clear DB failed:', error);
         throw error;
@@ -407,34 +562,36 @@ class InventoryService {
       }
     });
   }
-  async importFromCsv(csvContent?: string): Promise<string> {
+  async importFromCsv(csvContent?: string, delimiter = ';'): Promise<CsvImportResult | null> {
     if (isTauri()) {
       try {
         // Open file dialog to select CSV file
         const filePath = await openFileDialog([{ name: 'CSV', extensions: ['csv'] }]);
         if (!filePath || Array.isArray(filePath)) {
-          return 'Dosya secilmedi';
+          return null;
         }
-        const count = await tauriInvoke<number>('import_from_csv', { filePath });
-        return `${count} urun basariyla ice aktarildi`;
+        return await tauriInvoke<CsvImportResult>('import_from_csv', { filePath, delimiter });
       } catch (error) {
         console.error('Import failed:', error);
         throw error;
       }
     }
-    // Web Fallback - parse csvContent if provided
+    // Web Fallback - parse csvContent if provided (no quoted field support)
     if (csvContent) {
       const lines = csvContent.split('\n');
-      let imported = 0;
+      const result: CsvImportResult = { imported: 0, skipped: 0, errors: [] };
       for (let i = 1; i < lines.length; i++) {
         const line = lines[i].trim();
         if (!line) continue;
-        const parts = line.split(';');
-        if (parts.length < 6) continue;
+        const parts = line.split(delimiter);
 
         const sku = parts[1]?.trim();
         const name = parts[2]?.trim();
-        if (!sku || !name) continue;
+        if (!sku || !name) {
+          result.skipped++;
+          result.errors.push(`Satir ${i + 1}: SKU veya ad bos`);
+          continue;
+        }
 
         this.items.push({
           id: Math.random().toString(36).substr(2, 9),
@@ -448,15 +605,36 @@ class InventoryService {
           lastUpdated: new Date().toISOString(),
           currency: 'TL'
         });
-        imported++;
+        result.imported++;
       }
-      return `${imported} urun ice aktarildi(Web)`;
+      return result;
     }
-    return "Web: Dosya secilmedi";
+    return null;
   }
 
   // ==================== DB EXPORT/IMPORT ====================
 
+  // Dry-run CSV import: stage, review price outliers, then commit (Tauri only)
+  async stageCsvImport(delimiter = ';'): Promise<ImportStageSummary | null> {
+    const filePath = await openFileDialog([{ name: 'CSV', extensions: ['csv'] }]);
+    if (!filePath || Array.isArray(filePath)) {
+      return null;
+    }
+    return tauriInvoke<ImportStageSummary>('stage_csv_import', { filePath, delimiter });
+  }
+
+  async reviewImportPrices(batchId: string, thresholdPercent: number): Promise<PriceOutlier[]> {
+    return tauriInvoke<PriceOutlier[]>('review_import_prices', { batchId, thresholdPercent });
+  }
+
+  async commitStagedImport(batchId: string, excludedSkus: string[] = []): Promise<ImportCommitResult> {
+    return tauriInvoke<ImportCommitResult>('commit_staged_import', { batchId, excludedSkus });
+  }
+
+  async discardStagedImport(batchId: string): Promise<void> {
+    await tauriInvoke('discard_staged_import', { batchId });
+  }
+
   async exportDatabase(): Promise<string> {
     if (isTauri()) {
       try {
@@ -481,8 +659,12 @@ class InventoryService {
         if (!filePath || Array.isArray(filePath)) {
           return 'Dosya secilmedi';
         }
-        await tauriInvoke('import_database', { filePath });
-        return 'Veritabani basariyla ice aktarildi. Sayfa yenilenecek...';
+        const schema = await tauriInvoke<DatabaseSchemaInfo>('import_database', { filePath });
+        const backupNote = schema.backupPath ? ` Onceki veriler yedeklendi: ${schema.backupPath}.` : '';
+        if (schema.needsMigration) {
+          return `Veritabani (sema v${schema.detectedVersion}) guncellenerek ice aktarildi.${backupNote} Sayfa yenilenecek...`;
+        }
+        return `Veritabani basariyla ice aktarildi.${backupNote} Sayfa yenilenecek...`;
       } catch (error) {
         console.error('DB Import failed:', error);
         throw error;
@@ -490,6 +672,34 @@ class InventoryService {
     }
     return 'Web modunda DB ice aktarma desteklenmiyor';
   }
+
+  async exportJson(): Promise<string> {
+    if (isTauri()) {
+      try {
+        const filePath = await saveFileDialog('nexus_export.json', [{ name: 'JSON', extensions: ['json'] }]);
+        if (!filePath) {
+          return 'Dosya secilmedi';
+        }
+        await tauriInvoke('export_json', { filePath });
+        return `Veriler JSON olarak kaydedildi: ${filePath}`;
+      } catch (error) {
+        console.error('JSON Export failed:', error);
+        throw error;
+      }
+    }
+    return 'Web modunda JSON disa aktarma desteklenmiyor';
+  }
+
+  // Only into a database without sales, receipts or finance records; the catalog seed is replaced.
+  // Returns the imported row count per table
+  async importJson(): Promise<Record<string, number> | null> {
+    if (!isTauri()) return null;
+    const filePath = await openFileDialog([{ name: 'JSON', extensions: ['json'] }]);
+    if (!filePath || Array.isArray(filePath)) {
+      return null;
+    }
+    return tauriInvoke<Record<string, number>>('import_json', { filePath });
+  }
 }
 
 export const inventoryService = new InventoryService();
\ No newline at end of file
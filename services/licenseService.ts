@@ -1,5 +1,5 @@
 import { tauriInvoke, isTauri } from './tauriService';
-import { License, LicenseValidateResponse, LicenseActivateResponse } from '../types';
+import { License, LicenseValidateResponse, LicenseActivateResponse, LicenseCheckResult, LicensePolicy } from '../types';
 
 // Development mode - set to false for production build
 const DEVELOPMENT_MODE = false;
@@ -72,21 +72,36 @@ class LicenseService {
   /**
    * Check if current license is valid (includes expiry and revalidation check)
    */
-  async checkLicenseValidity(): Promise<boolean> {
+  async checkLicenseValidity(): Promise<LicenseCheckResult> {
     if (!isTauri() || DEVELOPMENT_MODE) {
-      return true; // Always valid in dev mode
+      return { valid: true, offlineGraceRemainingDays: null, needsOnlineSoon: false }; // Always valid in dev mode
     }
-    return tauriInvoke<boolean>('check_license_validity');
+    return tauriInvoke<LicenseCheckResult>('check_license_validity');
   }
 
   /**
-   * Deactivate/remove license from local database
+   * Revalidation interval and offline grace window
    */
-  async deactivateLicense(): Promise<void> {
+  async getLicensePolicy(): Promise<LicensePolicy | null> {
     if (!isTauri() || DEVELOPMENT_MODE) {
-      return;
+      return null;
     }
-    return tauriInvoke<void>('deactivate_license');
+    return tauriInvoke<LicensePolicy>('get_license_policy');
+  }
+
+  async setLicensePolicy(policy: LicensePolicy): Promise<LicensePolicy> {
+    return tauriInvoke<LicensePolicy>('set_license_policy', { policy });
+  }
+
+  /**
+   * Release the server activation and remove license from local database.
+   * Returns a warning when the server could not release the activation.
+   */
+  async deactivateLicense(): Promise<string | null> {
+    if (!isTauri() || DEVELOPMENT_MODE) {
+      return null;
+    }
+    return tauriInvoke<string | null>('deactivate_license');
   }
 
   /**
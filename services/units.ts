@@ -0,0 +1,46 @@
+// Units of measure - mirrors src-tauri/src/services/units.rs
+// ADET, PAKET, KOLİ gibi sayılan birimler tam sayı; KG, LT, MT gibi ölçülen birimler kesirli satılır.
+
+export const DEFAULT_UNIT = 'ADET';
+
+const UNIT_ALIASES: Record<string, string> = {
+  KILOGRAM: 'KG',
+  KILO: 'KG',
+  GRAM: 'GR',
+  LITRE: 'LT',
+  METRE: 'MT',
+  M: 'MT',
+  METREKARE: 'M2',
+};
+
+export const normalizeUnit = (unit?: string): string => {
+  const code = (unit || '').trim().toLocaleUpperCase('tr-TR');
+  if (!code) return DEFAULT_UNIT;
+  return UNIT_ALIASES[code] || code;
+};
+
+// Decimals a quantity is entered and shown with; 0 for counted units
+export const quantityDecimals = (unit?: string): number => {
+  switch (normalizeUnit(unit)) {
+    case 'KG':
+    case 'LT':
+      return 3;
+    case 'MT':
+    case 'M2':
+      return 2;
+    default:
+      return 0;
+  }
+};
+
+export const allowsFraction = (unit?: string): boolean => quantityDecimals(unit) > 0;
+
+// Rounded to the backend's 3 decimals so repeated +/- steps do not drift
+export const roundQuantity = (quantity: number): number => Math.round(quantity * 1000) / 1000;
+
+// "2,50 MT" / "3" - counted units are shown as-is without the unit label
+export const formatQuantity = (quantity: number, unit?: string): string => {
+  const decimals = quantityDecimals(unit);
+  if (decimals === 0) return String(quantity);
+  return `${quantity.toLocaleString('tr-TR', { minimumFractionDigits: decimals, maximumFractionDigits: decimals })} ${normalizeUnit(unit)}`;
+};
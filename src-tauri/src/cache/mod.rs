@@ -2,9 +2,11 @@
 
 use lru::LruCache;
 use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use std::time::{Duration, Instant};
 
-use crate::models::{CategoryStats, DashboardStats, InventoryItem};
+use crate::models::{CacheSegmentStats, CacheStats, CategoryStats, DashboardStats, InventoryItem};
 
 /// Cache entry with TTL (Time To Live)
 struct CacheEntry<T> {
@@ -25,6 +27,72 @@ impl<T: Clone> CacheEntry<T> {
     }
 }
 
+/// Hit/miss/eviction counters of one cache segment.
+/// Evictions are entries dropped for expiry or LRU capacity, not invalidations.
+#[derive(Default)]
+struct SegmentCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl SegmentCounters {
+    fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn evict(&self) {
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> CacheSegmentStats {
+        CacheSegmentStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.evictions.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Counters of every cache segment
+#[derive(Default)]
+struct CacheMetrics {
+    items_by_sku: SegmentCounters,
+    all_items: SegmentCounters,
+    dashboard_stats: SegmentCounters,
+    category_stats: SegmentCounters,
+}
+
+/// Read a single-value segment, dropping it once expired
+fn get_entry<T: Clone>(slot: &mut Option<CacheEntry<T>>, counters: &SegmentCounters) -> Option<T> {
+    match slot {
+        Some(entry) if !entry.is_expired() => {
+            counters.hit();
+            Some(entry.value.clone())
+        }
+        Some(_) => {
+            *slot = None;
+            counters.evict();
+            counters.miss();
+            None
+        }
+        None => {
+            counters.miss();
+            None
+        }
+    }
+}
+
 /// Application-level cache with LRU eviction and TTL
 pub struct AppCache {
     // Cache for individual items by SKU (5 min TTL, 500 items max)
@@ -38,6 +106,11 @@ pub struct AppCache {
 
     // Cache for category stats (1 min TTL)
     category_stats: Option<CacheEntry<Vec<CategoryStats>>>,
+
+    // Bumped by every invalidation; a load that raced with one is not cached
+    generation: u64,
+
+    metrics: CacheMetrics,
 }
 
 impl AppCache {
@@ -47,6 +120,8 @@ impl AppCache {
             all_items: None,
             dashboard_stats: None,
             category_stats: None,
+            generation: 0,
+            metrics: CacheMetrics::default(),
         }
     }
 
@@ -55,20 +130,30 @@ impl AppCache {
     pub fn get_item(&mut self, sku: &str) -> Option<InventoryItem> {
         if let Some(entry) = self.items_by_sku.get(sku) {
             if !entry.is_expired() {
+                self.metrics.items_by_sku.hit();
                 return Some(entry.value.clone());
             }
-            // Entry expired, remove it
         }
-        self.items_by_sku.pop(sku);
+        // Entry expired, remove it
+        if self.items_by_sku.pop(sku).is_some() {
+            self.metrics.items_by_sku.evict();
+        }
+        self.metrics.items_by_sku.miss();
         None
     }
 
     pub fn set_item(&mut self, sku: String, item: InventoryItem) {
         let entry = CacheEntry::new(item, Duration::from_secs(300)); // 5 min TTL
-        self.items_by_sku.put(sku, entry);
+        // `push` also returns the replaced entry of the same SKU, which is not an eviction
+        if let Some((evicted, _)) = self.items_by_sku.push(sku.clone(), entry) {
+            if evicted != sku {
+                self.metrics.items_by_sku.evict();
+            }
+        }
     }
 
     pub fn invalidate_item(&mut self, sku: &str) {
+        self.generation += 1;
         self.items_by_sku.pop(sku);
         // Also invalidate aggregate caches since item data changed
         self.all_items = None;
@@ -78,11 +163,8 @@ impl AppCache {
 
     // ==================== All Items Cache ====================
 
-    pub fn get_all_items(&self) -> Option<Vec<InventoryItem>> {
-        self.all_items
-            .as_ref()
-            .filter(|e| !e.is_expired())
-            .map(|e| e.value.clone())
+    pub fn get_all_items(&mut self) -> Option<Vec<InventoryItem>> {
+        get_entry(&mut self.all_items, &self.metrics.all_items)
     }
 
     pub fn set_all_items(&mut self, items: Vec<InventoryItem>) {
@@ -91,11 +173,8 @@ impl AppCache {
 
     // ==================== Dashboard Stats Cache ====================
 
-    pub fn get_dashboard_stats(&self) -> Option<DashboardStats> {
-        self.dashboard_stats
-            .as_ref()
-            .filter(|e| !e.is_expired())
-            .map(|e| e.value.clone())
+    pub fn get_dashboard_stats(&mut self) -> Option<DashboardStats> {
+        get_entry(&mut self.dashboard_stats, &self.metrics.dashboard_stats)
     }
 
     pub fn set_dashboard_stats(&mut self, stats: DashboardStats) {
@@ -104,11 +183,8 @@ impl AppCache {
 
     // ==================== Category Stats Cache ====================
 
-    pub fn get_category_stats(&self) -> Option<Vec<CategoryStats>> {
-        self.category_stats
-            .as_ref()
-            .filter(|e| !e.is_expired())
-            .map(|e| e.value.clone())
+    pub fn get_category_stats(&mut self) -> Option<Vec<CategoryStats>> {
+        get_entry(&mut self.category_stats, &self.metrics.category_stats)
     }
 
     pub fn set_category_stats(&mut self, stats: Vec<CategoryStats>) {
@@ -119,6 +195,7 @@ impl AppCache {
 
     /// Invalidate all caches (after bulk operations like import/seed)
     pub fn invalidate_all(&mut self) {
+        self.generation += 1;
         self.items_by_sku.clear();
         self.all_items = None;
         self.dashboard_stats = None;
@@ -127,10 +204,31 @@ impl AppCache {
 
     /// Invalidate only aggregate caches (after transaction)
     pub fn invalidate_aggregates(&mut self) {
+        self.generation += 1;
         self.all_items = None;
         self.dashboard_stats = None;
         self.category_stats = None;
     }
+
+    // ==================== Metrics ====================
+
+    /// Counters since startup or the last `reset_stats`
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            items_by_sku: self.metrics.items_by_sku.snapshot(),
+            all_items: self.metrics.all_items.snapshot(),
+            dashboard_stats: self.metrics.dashboard_stats.snapshot(),
+            category_stats: self.metrics.category_stats.snapshot(),
+            items_by_sku_len: self.items_by_sku.len(),
+        }
+    }
+
+    pub fn reset_stats(&self) {
+        self.metrics.items_by_sku.reset();
+        self.metrics.all_items.reset();
+        self.metrics.dashboard_stats.reset();
+        self.metrics.category_stats.reset();
+    }
 }
 
 impl Default for AppCache {
@@ -138,3 +236,106 @@ impl Default for AppCache {
         Self::new()
     }
 }
+
+/// Lock the shared cache; a panic while it was held only loses cached values
+pub fn lock(cache: &Mutex<AppCache>) -> MutexGuard<'_, AppCache> {
+    cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Return the cached value from `get`, otherwise `load` it from the database and `set` it.
+/// The cache is not locked while loading; if it was invalidated meanwhile the result is not stored.
+pub fn read_through<T: Clone, E>(
+    cache: &Mutex<AppCache>,
+    get: impl FnOnce(&mut AppCache) -> Option<T>,
+    set: impl FnOnce(&mut AppCache, T),
+    load: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let generation = {
+        let mut cache = lock(cache);
+        if let Some(value) = get(&mut cache) {
+            return Ok(value);
+        }
+        cache.generation
+    };
+
+    let value = load()?;
+    let mut cache = lock(cache);
+    if cache.generation == generation {
+        set(&mut cache, value.clone());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn item(sku: &str) -> InventoryItem {
+        serde_json::from_value(serde_json::json!({
+            "id": sku, "sku": sku, "name": sku, "category": "Genel", "quantity": 1, "location": "",
+            "price": 10.0, "lastUpdated": "now"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_read_through_hits_cache_until_invalidated() {
+        let cache = Mutex::new(AppCache::new());
+        let queries = Cell::new(0);
+        let load = || {
+            queries.set(queries.get() + 1);
+            Ok::<_, String>(vec![item("RP1-25")])
+        };
+
+        let first = read_through(&cache, |c| c.get_all_items(), |c, v| c.set_all_items(v), load).unwrap();
+        let second = read_through(&cache, |c| c.get_all_items(), |c, v| c.set_all_items(v), load).unwrap();
+        assert_eq!((first.len(), second.len()), (1, 1));
+        assert_eq!(queries.get(), 1);
+
+        lock(&cache).invalidate_item("RP1-25");
+        read_through(&cache, |c| c.get_all_items(), |c, v| c.set_all_items(v), load).unwrap();
+        assert_eq!(queries.get(), 2);
+
+        // Failed loads are not cached
+        let failed = read_through(&cache, |c| c.get_item("X"), |c, v| c.set_item("X".into(), v), || Err::<InventoryItem, _>("db"));
+        assert!(failed.is_err());
+        assert!(lock(&cache).get_item("X").is_none());
+    }
+
+    #[test]
+    fn test_stats_count_miss_then_hit() {
+        let cache = Mutex::new(AppCache::new());
+        for _ in 0..2 {
+            read_through(&cache, |c| c.get_all_items(), |c, v| c.set_all_items(v), || Ok::<_, String>(vec![item("A")]))
+                .unwrap();
+        }
+        lock(&cache).get_item("A");
+
+        let stats = lock(&cache).stats();
+        assert_eq!((stats.all_items.misses, stats.all_items.hits), (1, 1));
+        assert_eq!((stats.items_by_sku.misses, stats.items_by_sku.hits), (1, 0));
+        assert_eq!(stats.items_by_sku_len, 0);
+
+        lock(&cache).reset_stats();
+        assert_eq!(lock(&cache).stats().all_items.hits, 0);
+    }
+
+    #[test]
+    fn test_load_racing_an_invalidation_is_not_cached() {
+        let cache = Mutex::new(AppCache::new());
+        let stale = read_through(
+            &cache,
+            |c| c.get_item("RP1-25"),
+            |c, v| c.set_item("RP1-25".into(), v),
+            || {
+                // A write lands while the read is still running
+                lock(&cache).invalidate_item("RP1-25");
+                Ok::<_, String>(item("RP1-25"))
+            },
+        )
+        .unwrap();
+        assert_eq!(stale.sku, "RP1-25");
+        assert!(lock(&cache).get_item("RP1-25").is_none());
+    }
+}
@@ -0,0 +1,140 @@
+//! Cloud API authentication
+//!
+//! Lisans anahtarı yalnızca `/api/auth/token` çağrısında gönderilir. Bulut çağrıları karşılığında
+//! alınan kısa ömürlü bearer token ile yapılır; token süresi dolana kadar bellekte tutulur.
+
+use crate::error::AppError;
+use crate::services::http;
+use reqwest::blocking::Response;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// A token this close to expiry is renewed before it is used
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Lifetime assumed when the server does not say
+const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    success: bool,
+    token: Option<String>,
+    expires_in: Option<u64>,
+    error: Option<String>,
+}
+
+struct CachedToken {
+    token: String,
+    expires: Instant,
+}
+
+/// (api base url, dealer id, license key) -> token
+type TokenKey = (String, String, String);
+
+fn tokens() -> &'static Mutex<HashMap<TokenKey, CachedToken>> {
+    static TOKENS: OnceLock<Mutex<HashMap<TokenKey, CachedToken>>> = OnceLock::new();
+    TOKENS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn token_key(api_base_url: &str, dealer_id: &str, license_key: &str) -> TokenKey {
+    (api_base_url.to_string(), dealer_id.to_string(), license_key.to_string())
+}
+
+/// Exchange the license key for a new bearer token and its lifetime
+fn fetch_token(api_base_url: &str, dealer_id: &str, license_key: &str) -> Result<(String, Duration), AppError> {
+    let client = reqwest::blocking::Client::new();
+    let url = format!("{}/api/auth/token", api_base_url);
+    let body = serde_json::json!({ "license_key": license_key, "dealer_id": dealer_id });
+
+    let response = http::send_with_retry(&url, || {
+        client
+            .post(&url)
+            .json(&body)
+            .timeout(std::time::Duration::from_secs(30))
+    })
+    .map_err(|e| AppError::Internal(format!("Token istegi basarisiz: {}", e)))?;
+
+    let status = response.status();
+    let text = response.text().unwrap_or_default();
+    let parsed = serde_json::from_str::<TokenResponse>(&text).ok();
+    match parsed {
+        Some(TokenResponse { success: true, token: Some(token), expires_in, .. }) if status.is_success() => {
+            let ttl = expires_in.map_or(DEFAULT_TOKEN_TTL, Duration::from_secs);
+            Ok((token, ttl))
+        }
+        Some(TokenResponse { error: Some(error), .. }) => {
+            Err(AppError::Validation(format!("Bulut oturumu acilamadi: {}", error)))
+        }
+        _ => Err(AppError::Internal(format!("Bulut oturumu acilamadi: {} - {}", status, text))),
+    }
+}
+
+/// Bearer token for the dealer, from the cache while it is still valid
+pub fn bearer_token(api_base_url: &str, dealer_id: &str, license_key: &str) -> Result<String, AppError> {
+    let key = token_key(api_base_url, dealer_id, license_key);
+    if let Some(cached) = tokens().lock().unwrap().get(&key) {
+        if cached.expires > Instant::now() + REFRESH_MARGIN {
+            return Ok(cached.token.clone());
+        }
+    }
+
+    let (token, ttl) = fetch_token(api_base_url, dealer_id, license_key)?;
+    tokens().lock().unwrap().insert(
+        key,
+        CachedToken {
+            token: token.clone(),
+            expires: Instant::now() + ttl,
+        },
+    );
+    Ok(token)
+}
+
+/// Forget the cached token so the next call fetches a new one
+pub fn invalidate_token(api_base_url: &str, dealer_id: &str, license_key: &str) {
+    tokens()
+        .lock()
+        .unwrap()
+        .remove(&token_key(api_base_url, dealer_id, license_key));
+}
+
+/// Send a request with the dealer's bearer token. `send` gets the token and sends the request.
+/// A 401 (token expired early, or the server restarted with a new key) drops the cached token
+/// and the request is sent once more with a fresh one; the server rejected it unprocessed, so
+/// this is safe for uploads too.
+pub fn send_authorized(
+    api_base_url: &str,
+    dealer_id: &str,
+    license_key: &str,
+    send: impl Fn(&str) -> Result<Response, AppError>,
+) -> Result<Response, AppError> {
+    let response = send(&bearer_token(api_base_url, dealer_id, license_key)?)?;
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok(response);
+    }
+    invalidate_token(api_base_url, dealer_id, license_key);
+    send(&bearer_token(api_base_url, dealer_id, license_key)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_token_reused_until_invalidated() {
+        let key = token_key("http://cloud.test", "dealer-1", "LIC-1");
+        tokens().lock().unwrap().insert(
+            key.clone(),
+            CachedToken {
+                token: "cached".to_string(),
+                expires: Instant::now() + Duration::from_secs(600),
+            },
+        );
+        assert_eq!(bearer_token("http://cloud.test", "dealer-1", "LIC-1").unwrap(), "cached");
+
+        invalidate_token("http://cloud.test", "dealer-1", "LIC-1");
+        assert!(!tokens().lock().unwrap().contains_key(&key));
+    }
+}
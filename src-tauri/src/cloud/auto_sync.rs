@@ -0,0 +1,140 @@
+//! Cloud auto-sync scheduler
+//!
+//! Otomatik bulut yedeklemesi: ayarlar her turda sync_status tablosundan okunur.
+//! Otomatik senkron açıldığında ilk yedek bir aralık beklemeden hemen alınır.
+
+use crate::error::AppError;
+use crate::license;
+use crate::services::http;
+use rusqlite::Connection;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use super::{backup_to_cloud, get_local_sync_status, update_last_backup};
+
+pub const MIN_AUTO_SYNC_INTERVAL_MINUTES: i32 = 5;
+pub const MAX_AUTO_SYNC_INTERVAL_MINUTES: i32 = 1440;
+
+/// How often the scheduler wakes up to check settings and triggers
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Settings are re-read from the database every this many polls
+const SETTINGS_REFRESH_POLLS: u32 = 30;
+
+static AUTO_SYNC_RUNNING: AtomicBool = AtomicBool::new(false);
+static SYNC_NOW: AtomicBool = AtomicBool::new(false);
+
+/// Clamp an interval to the supported range (5 minutes to 24 hours)
+pub fn clamp_auto_sync_interval(interval_minutes: i32) -> i32 {
+    interval_minutes.clamp(MIN_AUTO_SYNC_INTERVAL_MINUTES, MAX_AUTO_SYNC_INTERVAL_MINUTES)
+}
+
+/// Check that the sync server answers on /api/health
+pub fn check_server_health(api_base_url: &str) -> Result<(), AppError> {
+    let url = format!("{}/api/health", api_base_url.trim_end_matches('/'));
+    // Probe only: no retries, but failures still count towards the circuit breaker
+    let client = reqwest::blocking::Client::new();
+    let response = http::send_with_retries(&url, 0, || client.get(&url).timeout(Duration::from_secs(5)))
+        .map_err(|e| AppError::Internal(format!("Sunucuya ulasilamadi: {}", e)))?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(AppError::Internal(format!("Sunucu saglik kontrolu basarisiz: HTTP {}", response.status())))
+    }
+}
+
+/// Ask the scheduler to run a backup on its next poll
+pub fn request_immediate_sync() {
+    SYNC_NOW.store(true, Ordering::SeqCst);
+}
+
+/// Backup is due when there is no previous backup or the last one is older than the interval
+fn is_backup_due(last_backup_at: Option<&str>, interval_minutes: i32) -> bool {
+    let Some(last) = last_backup_at.and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok()) else {
+        return true;
+    };
+    chrono::Utc::now().signed_duration_since(last) >= chrono::Duration::minutes(interval_minutes as i64)
+}
+
+/// Upload the database using the locally stored license
+fn run_scheduled_backup(conn: &Connection, db_path: &str) -> Result<(), AppError> {
+    let license_data = license::get_local_license(conn)?
+        .ok_or_else(|| AppError::NotFound("Lisans bulunamadi".to_string()))?;
+
+    let response = backup_to_cloud(
+        conn,
+        &license_data.api_base_url,
+        &license_data.dealer_id,
+        &license_data.license_key,
+        db_path,
+    )?;
+
+    if !response.success {
+        return Err(AppError::Internal(
+            response.error.unwrap_or_else(|| "Bulut yedeklemesi basarisiz".to_string()),
+        ));
+    }
+    update_last_backup(conn)
+}
+
+/// Start the cloud auto-sync scheduler (no-op if already running)
+pub fn start_auto_sync_scheduler(db_path: String) {
+    if AUTO_SYNC_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let mut last_attempt: Option<Instant> = None;
+        let mut polls_until_refresh = 0u32;
+
+        loop {
+            let triggered = SYNC_NOW.load(Ordering::SeqCst);
+
+            if triggered || polls_until_refresh == 0 {
+                polls_until_refresh = SETTINGS_REFRESH_POLLS;
+                if let Ok(conn) = crate::database::open_database(&db_path) {
+                    if let Ok(status) = get_local_sync_status(&conn) {
+                        let interval = clamp_auto_sync_interval(status.auto_sync_interval_minutes);
+                        // Failed attempts wait a full interval before retrying
+                        let retry_wait = Duration::from_secs(interval as u64 * 60);
+                        let retry_ok = !matches!(last_attempt, Some(t) if t.elapsed() < retry_wait);
+                        let due = triggered
+                            || (retry_ok && is_backup_due(status.last_backup_at.as_deref(), interval));
+
+                        if status.auto_sync_enabled && due {
+                            SYNC_NOW.store(false, Ordering::SeqCst);
+                            last_attempt = Some(Instant::now());
+                            match run_scheduled_backup(&conn, &db_path) {
+                                Ok(()) => println!("[CloudSync] Scheduled backup completed"),
+                                Err(e) => eprintln!("[CloudSync] Scheduled backup failed: {}", e),
+                            }
+                        } else if triggered {
+                            SYNC_NOW.store(false, Ordering::SeqCst);
+                        }
+                    }
+                }
+            }
+
+            polls_until_refresh -= 1;
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_clamp_and_due() {
+        assert_eq!(clamp_auto_sync_interval(1), 5);
+        assert_eq!(clamp_auto_sync_interval(60), 60);
+        assert_eq!(clamp_auto_sync_interval(5000), 1440);
+
+        assert!(is_backup_due(None, 30));
+        let recent = chrono::Utc::now().to_rfc3339();
+        assert!(!is_backup_due(Some(&recent), 30));
+        let old = (chrono::Utc::now() - chrono::Duration::minutes(31)).to_rfc3339();
+        assert!(is_backup_due(Some(&old), 30));
+    }
+}
@@ -0,0 +1,105 @@
+//! Background cloud backup job
+//!
+//! Manuel bulut yedeklemesi arayüzü dondurmamak için ayrı bir thread'de çalışır.
+//! İlerleme ve sonuç olaylarla bildirilir; aynı anda yalnızca bir yedekleme işi çalışabilir.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use rusqlite::Connection;
+
+use crate::error::AppError;
+use crate::models::{CloudBackupComplete, CloudBackupFailed, CloudBackupProgress};
+
+use super::{update_last_backup, upload, upload_backup_file_with_progress};
+
+/// Upload progress, at most once per percent
+pub const CLOUD_BACKUP_PROGRESS_EVENT: &str = "cloud-backup-progress";
+/// Backup accepted by the server
+pub const CLOUD_BACKUP_COMPLETE_EVENT: &str = "cloud-backup-complete";
+/// Backup failed or was rejected by the server
+pub const CLOUD_BACKUP_ERROR_EVENT: &str = "cloud-backup-error";
+
+static CLOUD_BACKUP_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// What a running job reports to its listener
+#[derive(Debug, Clone)]
+pub enum CloudBackupEvent {
+    Progress(CloudBackupProgress),
+    Complete(CloudBackupComplete),
+    Error(CloudBackupFailed),
+}
+
+/// Whether a backup job is uploading right now
+pub fn is_cloud_backup_running() -> bool {
+    CLOUD_BACKUP_RUNNING.load(Ordering::SeqCst)
+}
+
+/// Checkpoint the WAL on `conn` and upload `db_path` on a background thread.
+/// Returns the job id at once; the result arrives as an event carrying that id.
+pub fn start_cloud_backup_job(
+    conn: &Connection,
+    db_path: String,
+    api_base_url: String,
+    dealer_id: String,
+    license_key: String,
+    listener: impl Fn(CloudBackupEvent) + Send + Sync + 'static,
+) -> Result<String, AppError> {
+    if CLOUD_BACKUP_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(AppError::Validation("Bulut yedeklemesi zaten devam ediyor".to_string()));
+    }
+
+    // Flush all data to the main database file before it is read
+    if let Err(e) = conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);") {
+        CLOUD_BACKUP_RUNNING.store(false, Ordering::SeqCst);
+        return Err(AppError::Internal(format!("WAL checkpoint basarisiz: {}", e)));
+    }
+
+    let job_id = uuid::Uuid::new_v4().to_string();
+    let listener = Arc::new(listener);
+    let progress = progress_reporter(job_id.clone(), listener.clone());
+
+    let id = job_id.clone();
+    std::thread::spawn(move || {
+        let result = upload_backup_file_with_progress(&api_base_url, &dealer_id, &license_key, &db_path, Some(progress))
+            .and_then(|response| {
+                if response.success {
+                    update_last_backup(&crate::database::open_database(&db_path)?)?;
+                }
+                Ok(response)
+            });
+        CLOUD_BACKUP_RUNNING.store(false, Ordering::SeqCst);
+
+        listener(match result {
+            Ok(response) if response.success => CloudBackupEvent::Complete(CloudBackupComplete { job_id: id, response }),
+            Ok(response) => CloudBackupEvent::Error(CloudBackupFailed {
+                job_id: id,
+                error: response
+                    .error
+                    .or(response.message)
+                    .unwrap_or_else(|| "Bulut yedeklemesi basarisiz".to_string()),
+            }),
+            Err(e) => CloudBackupEvent::Error(CloudBackupFailed { job_id: id, error: e.to_string() }),
+        });
+    });
+
+    Ok(job_id)
+}
+
+/// Forward upload progress whenever the whole percentage changes (or a new body starts)
+fn progress_reporter(
+    job_id: String,
+    listener: Arc<impl Fn(CloudBackupEvent) + Send + Sync + 'static>,
+) -> upload::UploadProgress {
+    let last_percent = AtomicU64::new(u64::MAX);
+    Arc::new(move |bytes_sent, total_bytes| {
+        let percent = bytes_sent.saturating_mul(100) / total_bytes.max(1);
+        if last_percent.swap(percent, Ordering::Relaxed) != percent {
+            listener(CloudBackupEvent::Progress(CloudBackupProgress {
+                job_id: job_id.clone(),
+                bytes_sent,
+                total_bytes,
+            }));
+        }
+    })
+}
@@ -0,0 +1,306 @@
+//! Cloud backup encryption
+//!
+//! Buluta gönderilen yedek AES-256-GCM ile şifrelenir; sunucu yalnızca okunamayan bir blob saklar.
+//! Anahtar lisans anahtarı ve kuruluma özel bir tuzdan (salt) Argon2id ile türetilir. Tuz ilk
+//! yedeklemede üretilip yerelde saklanır. Tuz ve nonce öneki şifreli verinin başına yazılır, böylece
+//! yedek aynı lisans anahtarıyla başka bir kurulumda da açılabilir.
+//!
+//! Dosya sabit boyutlu parçalar halinde şifrelenir (STREAM yapısı): her parçanın nonce'u önek, parça
+//! sayacı ve son parça bayrağından oluşur. Böylece bellek kullanımı dosya boyutundan bağımsızdır;
+//! parçaların yeri değiştirilirse, biri çıkarılırsa veya dosya kesilirse çözme başarısız olur.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use directories::ProjectDirs;
+use rand::RngCore;
+
+use crate::error::AppError;
+
+/// First bytes of an encrypted backup
+const MAGIC: &[u8; 8] = b"NXBKENC1";
+
+pub const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+
+/// Random part of every chunk nonce; a 4-byte chunk counter and a last-chunk flag follow it
+const NONCE_PREFIX_LEN: usize = 7;
+
+/// Plain bytes per chunk; only the last chunk is shorter (possibly empty)
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Magic, salt and nonce prefix in front of the chunks; authenticated with every chunk
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_PREFIX_LEN;
+
+fn salt_file_path() -> Option<PathBuf> {
+    ProjectDirs::from("com", "nexus", "inventory").map(|proj| proj.data_dir().join("cloud_backup.salt"))
+}
+
+/// This install's salt, created on first use
+pub fn load_or_create_install_salt() -> Result<[u8; SALT_LEN], AppError> {
+    let path = salt_file_path().ok_or_else(|| AppError::Internal("Veri dizini belirlenemedi".to_string()))?;
+    load_or_create_salt_at(&path)
+}
+
+fn load_or_create_salt_at(path: &Path) -> Result<[u8; SALT_LEN], AppError> {
+    if let Ok(bytes) = std::fs::read(path) {
+        if let Ok(salt) = <[u8; SALT_LEN]>::try_from(bytes.as_slice()) {
+            return Ok(salt);
+        }
+        eprintln!("[Cloud] Yedek tuzu gecersiz, yenisi uretiliyor: {}", path.display());
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| AppError::Internal(format!("Veri dizini olusturulamadi: {}", e)))?;
+    }
+    std::fs::write(path, salt).map_err(|e| AppError::Internal(format!("Yedek tuzu kaydedilemedi: {}", e)))?;
+    Ok(salt)
+}
+
+fn cipher(license_key: &str, salt: &[u8]) -> Result<Aes256Gcm, AppError> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(license_key.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::Internal(format!("Yedek anahtari turetilemedi: {}", e)))?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+fn chunk_nonce(prefix: &[u8], counter: u32, last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    nonce
+}
+
+/// Read until `buf` is full or the input ends; returns the bytes read
+fn fill(input: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn undecryptable() -> AppError {
+    AppError::Validation("Bulut yedegi cozulemedi: lisans anahtari farkli veya veri bozuk".to_string())
+}
+
+/// Whether downloaded bytes start like an encrypted backup (older backups were uploaded in the clear)
+pub fn is_encrypted_backup(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Whether the file at `path` is an encrypted backup
+pub fn is_encrypted_file(path: &Path) -> Result<bool, AppError> {
+    let mut start = [0u8; MAGIC.len()];
+    let len = fill(&mut File::open(path)?, &mut start)?;
+    Ok(is_encrypted_backup(&start[..len]))
+}
+
+/// Encrypt `input` into `output` as magic | salt | nonce prefix | chunks, one chunk in memory at a time
+pub fn encrypt_stream(
+    mut input: impl Read,
+    mut output: impl Write,
+    license_key: &str,
+    salt: &[u8; SALT_LEN],
+) -> Result<(), AppError> {
+    let cipher = cipher(license_key, salt)?;
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut prefix);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(salt);
+    header.extend_from_slice(&prefix);
+    output.write_all(&header)?;
+
+    let mut buf = vec![0u8; CHUNK_LEN];
+    let mut counter: u32 = 0;
+    loop {
+        let len = fill(&mut input, &mut buf)?;
+        let last = len < CHUNK_LEN;
+        let chunk = cipher
+            .encrypt(Nonce::from_slice(&chunk_nonce(&prefix, counter, last)), Payload { msg: &buf[..len], aad: &header })
+            .map_err(|_| AppError::Internal("Yedek sifrelenemedi".to_string()))?;
+        output.write_all(&chunk)?;
+        if last {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| AppError::Internal("Yedek sifrelenemedi: dosya cok buyuk".to_string()))?;
+    }
+    output.flush()?;
+    Ok(())
+}
+
+/// Decrypt a stream made by `encrypt_stream` into `output`.
+/// Fails on a different license key, a changed or reordered chunk and a cut-off stream.
+pub fn decrypt_stream(mut input: impl Read, mut output: impl Write, license_key: &str) -> Result<(), AppError> {
+    let mut header = [0u8; HEADER_LEN];
+    if fill(&mut input, &mut header)? < HEADER_LEN || !header.starts_with(MAGIC) {
+        return Err(AppError::Validation("Sifreli bulut yedegi okunamadi".to_string()));
+    }
+    let cipher = cipher(license_key, &header[MAGIC.len()..MAGIC.len() + SALT_LEN])?;
+    let prefix = &header[MAGIC.len() + SALT_LEN..];
+
+    let mut buf = vec![0u8; CHUNK_LEN + TAG_LEN];
+    let mut counter: u32 = 0;
+    loop {
+        // A full chunk is never the last one; the encryptor ends with a short (maybe empty) chunk
+        let len = fill(&mut input, &mut buf)?;
+        let last = len < buf.len();
+        if len < TAG_LEN {
+            return Err(undecryptable());
+        }
+        let plain = cipher
+            .decrypt(Nonce::from_slice(&chunk_nonce(prefix, counter, last)), Payload { msg: &buf[..len], aad: &header })
+            .map_err(|_| undecryptable())?;
+        output.write_all(&plain)?;
+        if last {
+            break;
+        }
+        counter = counter.checked_add(1).ok_or_else(undecryptable)?;
+    }
+    output.flush()?;
+    Ok(())
+}
+
+/// Encrypt the file at `source` into `target`
+pub fn encrypt_file(source: &Path, target: &Path, license_key: &str, salt: &[u8; SALT_LEN]) -> Result<(), AppError> {
+    let input = File::open(source).map_err(|e| AppError::Internal(format!("Dosya okunamadi: {}", e)))?;
+    let output = File::create(target).map_err(|e| AppError::Internal(format!("Gecici dosya yazilamadi: {}", e)))?;
+    encrypt_stream(BufReader::new(input), BufWriter::new(output), license_key, salt)
+}
+
+/// Decrypt the file at `source` into `target`; nothing is left at `target` when it fails
+pub fn decrypt_file(source: &Path, target: &Path, license_key: &str) -> Result<(), AppError> {
+    let input = File::open(source).map_err(|e| AppError::Internal(format!("Dosya okunamadi: {}", e)))?;
+    let output = File::create(target).map_err(|e| AppError::Internal(format!("Gecici dosya yazilamadi: {}", e)))?;
+    let result = decrypt_stream(BufReader::new(input), BufWriter::new(output), license_key);
+    if result.is_err() {
+        let _ = std::fs::remove_file(target);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cloud::upload::{compress_file, write_backup_file, Base64JsonUpload};
+    use base64::Engine as _;
+    use std::io::Read;
+
+    #[test]
+    fn test_encrypted_backup_round_trip() {
+        let dir = std::env::temp_dir().join(format!("nexus_cloud_enc_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("inventory.db");
+        let gz_path = dir.join("upload.db.gz");
+        let enc_path = dir.join("upload.enc");
+        let server_path = dir.join("server_blob");
+        let decrypted_path = dir.join("decrypted");
+        let restored_path = dir.join("restored.db");
+
+        {
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE inventory_items (id INTEGER PRIMARY KEY, name TEXT);
+                 CREATE TABLE current_accounts (id TEXT PRIMARY KEY, name TEXT);
+                 INSERT INTO current_accounts VALUES ('c1', 'Gizli Musteri');",
+            )
+            .unwrap();
+        }
+        let checksum = crate::cloud::calculate_checksum(db_path.to_str().unwrap()).unwrap();
+
+        let salt = load_or_create_salt_at(&dir.join("cloud_backup.salt")).unwrap();
+        assert_eq!(load_or_create_salt_at(&dir.join("cloud_backup.salt")).unwrap(), salt);
+
+        compress_file(&db_path, &gz_path).unwrap();
+        encrypt_file(&gz_path, &enc_path, "LIC-123", &salt).unwrap();
+
+        // "Upload": the server keeps the decoded backup_data as it is
+        let mut body = Vec::new();
+        Base64JsonUpload::encrypted(&enc_path, "dealer-1", checksum.clone())
+            .read_to_end(&mut body)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["encrypted"], true);
+        let blob = base64::engine::general_purpose::STANDARD
+            .decode(json["backup_data"].as_str().unwrap())
+            .unwrap();
+        std::fs::write(&server_path, &blob).unwrap();
+
+        let stored = std::fs::read(&server_path).unwrap();
+        assert!(is_encrypted_backup(&stored));
+        assert!(!stored.windows(b"Gizli Musteri".len()).any(|w| w == b"Gizli Musteri"));
+        assert!(decrypt_file(&server_path, &decrypted_path, "LIC-999").is_err());
+        assert!(!decrypted_path.exists());
+
+        // Restore: decrypt, gunzip, open
+        decrypt_file(&server_path, &decrypted_path, "LIC-123").unwrap();
+        write_backup_file(&decrypted_path, &restored_path).unwrap();
+        assert_eq!(crate::cloud::calculate_checksum(restored_path.to_str().unwrap()).unwrap(), checksum);
+
+        let conn = rusqlite::Connection::open(&restored_path).unwrap();
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(tables, ["current_accounts", "inventory_items"]);
+        drop(conn);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn encrypt(plain: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        encrypt_stream(plain, &mut out, "LIC-1", &[3u8; SALT_LEN]).unwrap();
+        out
+    }
+
+    fn decrypt(data: &[u8]) -> Result<Vec<u8>, AppError> {
+        let mut out = Vec::new();
+        decrypt_stream(data, &mut out, "LIC-1").map(|()| out)
+    }
+
+    #[test]
+    fn test_chunked_encryption_detects_reordering_and_truncation() {
+        let chunk = CHUNK_LEN + TAG_LEN;
+        for len in [0, 10, CHUNK_LEN, 2 * CHUNK_LEN + 5] {
+            let plain: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let encrypted = encrypt(&plain);
+            assert_eq!(encrypted.len(), HEADER_LEN + plain.len() + (len / CHUNK_LEN + 1) * TAG_LEN);
+            assert_eq!(decrypt(&encrypted).unwrap(), plain);
+        }
+
+        let plain = vec![7u8; 2 * CHUNK_LEN + 5];
+        let encrypted = encrypt(&plain);
+        // Cut at a chunk boundary, cut inside the last chunk, first two chunks swapped
+        assert!(decrypt(&encrypted[..HEADER_LEN + 2 * chunk]).is_err());
+        assert!(decrypt(&encrypted[..encrypted.len() - 1]).is_err());
+        let mut swapped = encrypted[..HEADER_LEN].to_vec();
+        swapped.extend_from_slice(&encrypted[HEADER_LEN + chunk..HEADER_LEN + 2 * chunk]);
+        swapped.extend_from_slice(&encrypted[HEADER_LEN..HEADER_LEN + chunk]);
+        swapped.extend_from_slice(&encrypted[HEADER_LEN + 2 * chunk..]);
+        assert!(decrypt(&swapped).is_err());
+    }
+}
@@ -1,10 +1,28 @@
 use crate::error::AppError;
+use crate::services::http;
 use crate::models::{CloudBackupResponse, CloudStatusResponse, SyncStatus};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OpenFlags};
 use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::Read;
-use base64::Engine as _;
+
+pub mod auth;
+pub mod auto_sync;
+pub mod backup_job;
+pub mod crypto;
+pub mod reconcile;
+pub mod upload;
+
+pub use auto_sync::{
+    check_server_health, clamp_auto_sync_interval, request_immediate_sync, start_auto_sync_scheduler,
+};
+pub use backup_job::{start_cloud_backup_job, CloudBackupEvent};
+pub use reconcile::{BackupComparison, CloudRestoreResult, DatabaseSnapshot};
+
+/// Open a database file read-only the way this app stores them (used by `reconcile`)
+pub(crate) fn open_read_only(path: &std::path::Path) -> rusqlite::Result<Connection> {
+    crate::database::encryption::open_database_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+}
 
 /// Calculate SHA256 checksum of a file
 pub fn calculate_checksum(file_path: &str) -> Result<String, AppError> {
@@ -39,116 +57,174 @@ pub fn backup_to_cloud(
     conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
         .map_err(|e| AppError::Internal(format!("WAL checkpoint basarisiz: {}", e)))?;
 
-    // Calculate checksum first
-    let checksum = calculate_checksum(db_path)?;
+    upload_backup_file(api_base_url, dealer_id, license_key, db_path)
+}
 
-    // Get file size
-    let metadata = std::fs::metadata(db_path)
-        .map_err(|e| AppError::Internal(format!("Dosya boyutu alinamadi: {}", e)))?;
-    let file_size = metadata.len() as i64;
+/// Upload a database file to the cloud API as the dealer's backup.
+/// A plaintext copy of the file is gzipped and then encrypted with a key derived from the license
+/// key (see `crypto`), so the backup opens on any install with that license.
+pub fn upload_backup_file(
+    api_base_url: &str,
+    dealer_id: &str,
+    license_key: &str,
+    db_path: &str,
+) -> Result<CloudBackupResponse, AppError> {
+    upload_backup_file_with_progress(api_base_url, dealer_id, license_key, db_path, None)
+}
 
-    // Read and encode file content
-    let mut file = File::open(db_path)
-        .map_err(|e| AppError::Internal(format!("Dosya acilamadi: {}", e)))?;
-    
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)
-        .map_err(|e| AppError::Internal(format!("Dosya okunamadi: {}", e)))?;
+/// `upload_backup_file` reporting the bytes sent of each request body to `progress`
+pub fn upload_backup_file_with_progress(
+    api_base_url: &str,
+    dealer_id: &str,
+    license_key: &str,
+    db_path: &str,
+    progress: Option<upload::UploadProgress>,
+) -> Result<CloudBackupResponse, AppError> {
+    let temp_id = uuid::Uuid::new_v4();
+    let snapshot_path = std::env::temp_dir().join(format!("nexus_cloud_upload_{}.db", temp_id));
+    let gz_path = std::env::temp_dir().join(format!("nexus_cloud_upload_{}.db.gz", temp_id));
+    let enc_path = std::env::temp_dir().join(format!("nexus_cloud_upload_{}.enc", temp_id));
+
+    let sent = (|| -> Result<(i64, CloudBackupResponse), AppError> {
+        // The local file is keyed with this machine's keyring key; another install could not open it
+        crate::database::encryption::export_plaintext_copy(std::path::Path::new(db_path), &snapshot_path)?;
+        let file_size = std::fs::metadata(&snapshot_path)
+            .map_err(|e| AppError::Internal(format!("Dosya boyutu alinamadi: {}", e)))?
+            .len() as i64;
+
+        // Checksum always covers the plain database so restore verification is unchanged
+        let checksum = calculate_checksum(&snapshot_path.to_string_lossy())?;
+        let salt = crypto::load_or_create_install_salt()?;
+
+        let plain_path = match upload::compress_file(&snapshot_path, &gz_path) {
+            Ok(()) => gz_path.as_path(),
+            Err(e) => {
+                eprintln!("[Cloud] Yedek sikistirilamadi, sikistirmasiz sifreleniyor: {}", e);
+                snapshot_path.as_path()
+            }
+        };
+        crypto::encrypt_file(plain_path, &enc_path, license_key, &salt)?;
+        let (_, result) = post_backup(api_base_url, dealer_id, license_key, &enc_path, progress.as_ref(), || {
+            upload::Base64JsonUpload::encrypted(&enc_path, dealer_id, checksum.clone())
+        })?;
+        Ok((file_size, result))
+    })();
+    for path in [&snapshot_path, &gz_path, &enc_path] {
+        let _ = std::fs::remove_file(path);
+    }
 
-    let backup_data = base64::engine::general_purpose::STANDARD.encode(&buffer);
+    let (file_size, mut result) = sent?;
+    if result.success {
+        result.size_bytes = Some(file_size);
+    }
+    Ok(result)
+}
+
+/// POST one backup body; server errors are returned as an unsuccessful response with their status
+fn post_backup(
+    api_base_url: &str,
+    dealer_id: &str,
+    license_key: &str,
+    body_path: &std::path::Path,
+    progress: Option<&upload::UploadProgress>,
+    make_upload: impl Fn() -> upload::Base64JsonUpload,
+) -> Result<(reqwest::StatusCode, CloudBackupResponse), AppError> {
+    let body_size = std::fs::metadata(body_path)
+        .map_err(|e| AppError::Internal(format!("Dosya boyutu alinamadi: {}", e)))?
+        .len();
+    let length = make_upload().content_length(body_size);
 
     let client = reqwest::blocking::Client::new();
     let url = format!("{}/api/cloud/backup", api_base_url);
 
-    let body = serde_json::json!({
-        "dealer_id": dealer_id,
-        "license_key": license_key,
-        "checksum": checksum,
-        "backup_data": backup_data
-    });
-
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .header("X-Dealer-ID", dealer_id)
-        .header("X-License-Key", license_key)
-        .json(&body)
-        .timeout(std::time::Duration::from_secs(120))
-        .send()
-        .map_err(|e| AppError::Internal(format!("Yedekleme istegi basarisiz: {}", e)))?;
+    // Body is streamed: file is read in chunks, base64 encoded and hashed on the fly
+    // Not safe to send twice: only retried when the server was never reached
+    let response = auth::send_authorized(api_base_url, dealer_id, license_key, |token| {
+        http::send_non_idempotent(&url, || {
+            // A fresh reader per attempt; progress starts over with it
+            let body = match progress {
+                Some(progress) => make_upload().with_progress(length, progress.clone()),
+                None => make_upload(),
+            };
+            client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .bearer_auth(token)
+                .body(reqwest::blocking::Body::sized(body, length))
+                .timeout(std::time::Duration::from_secs(120))
+        })
+        .map_err(|e| AppError::Internal(format!("Yedekleme istegi basarisiz: {}", e)))
+    })?;
 
-    if response.status().is_success() {
-        let mut result: CloudBackupResponse = response
+    let status = response.status();
+    if status.is_success() {
+        let result: CloudBackupResponse = response
             .json()
             .map_err(|e| AppError::Internal(format!("API yaniti ayristirilamadi: {}", e)))?;
-        result.size_bytes = Some(file_size);
-        Ok(result)
+        Ok((status, result))
     } else {
-        let status = response.status();
         let body = response.text().unwrap_or_default();
 
         if let Ok(error_response) = serde_json::from_str::<CloudBackupResponse>(&body) {
-            Ok(error_response)
+            Ok((status, error_response))
         } else {
-            Ok(CloudBackupResponse {
+            Ok((status, CloudBackupResponse {
                 success: false,
                 backup_id: None,
                 timestamp: None,
                 size_bytes: None,
                 message: None,
                 error: Some(format!("Sunucu hatasi: {} - {}", status, body)),
-            })
+            }))
         }
     }
 }
 
-/// Restore database from cloud using SQLite backup API
-/// This restores data into the existing connection without requiring app restart
-pub fn restore_from_cloud_with_conn(
-    db_path: &str,
+/// Download the cloud backup into a temp file. The response is streamed to disk and decrypted
+/// file to file, so the backup is never held in memory.
+pub(crate) fn download_cloud_backup(
     api_base_url: &str,
     dealer_id: &str,
     license_key: &str,
-) -> Result<(), AppError> {
+) -> Result<std::path::PathBuf, AppError> {
     let client = reqwest::blocking::Client::new();
     let url = format!("{}/api/cloud/restore", api_base_url);
 
-    let response = client
-        .get(&url)
-        .header("X-Dealer-ID", dealer_id)
-        .header("X-License-Key", license_key)
-        .timeout(std::time::Duration::from_secs(120))
-        .send()
-        .map_err(|e| AppError::Internal(format!("Geri yukleme istegi basarisiz: {}", e)))?;
+    let mut response = auth::send_authorized(api_base_url, dealer_id, license_key, |token| {
+        http::send_with_retry(&url, || {
+            client
+                .get(&url)
+                .bearer_auth(token)
+                .timeout(std::time::Duration::from_secs(120))
+        })
+        .map_err(|e| AppError::Internal(format!("Geri yukleme istegi basarisiz: {}", e)))
+    })?;
 
     if response.status().is_success() {
-        // Download to temp file
-        let temp_path = std::env::temp_dir().join("nexus_cloud_restore.db");
-        let bytes = response.bytes()
-            .map_err(|e| AppError::Internal(format!("Veri alinamadi: {}", e)))?;
-
-        std::fs::write(&temp_path, &bytes)
-            .map_err(|e| AppError::Internal(format!("Gecici dosya yazilamadi: {}", e)))?;
-
-        // Open the downloaded database (source)
-        let source_conn = Connection::open(&temp_path)
-            .map_err(|e| AppError::Internal(format!("Indirilen veritabani acilamadi: {}", e)))?;
-
-        // Open direct mutable connection to destination
-        let mut dest_conn = Connection::open(db_path)
-            .map_err(|e| AppError::Internal(format!("Hedef veritabani acilamadi: {}", e)))?;
-
-        // Use SQLite backup API to restore into destination
-        let backup = rusqlite::backup::Backup::new(&source_conn, &mut dest_conn)
-            .map_err(|e| AppError::Internal(format!("Backup olusturulamadi: {}", e)))?;
-
-        backup.run_to_completion(100, std::time::Duration::from_millis(50), None)
-            .map_err(|e| AppError::Internal(format!("Backup tamamlanamadi: {}", e)))?;
-
-        // Clean up temp file
-        let _ = std::fs::remove_file(&temp_path);
-
-        Ok(())
+        let temp_id = uuid::Uuid::new_v4();
+        let download_path = std::env::temp_dir().join(format!("nexus_cloud_download_{}", temp_id));
+        let temp_path = std::env::temp_dir().join(format!("nexus_cloud_restore_{}.db", temp_id));
+        let expected_checksum = response
+            .headers()
+            .get("X-Backup-Checksum")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
+        let written = (|| -> Result<(), AppError> {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(&download_path)?);
+            response
+                .copy_to(&mut file)
+                .map_err(|e| AppError::Internal(format!("Veri alinamadi: {}", e)))?;
+            std::io::Write::flush(&mut file)?;
+            drop(file);
+            write_verified_backup(&download_path, expected_checksum.as_deref(), license_key, &temp_path)
+        })();
+        let _ = std::fs::remove_file(&download_path);
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        Ok(temp_path)
     } else {
         let status = response.status();
         let body = response.text().unwrap_or_default();
@@ -156,6 +232,79 @@ pub fn restore_from_cloud_with_conn(
     }
 }
 
+/// Write a downloaded backup file to `target` as a plain database and check it against the
+/// `X-Backup-Checksum` header (SHA256 of the plain database). A truncated or altered download
+/// is rejected here, before anything is restored from it.
+fn write_verified_backup(
+    downloaded: &std::path::Path,
+    expected_checksum: Option<&str>,
+    license_key: &str,
+    target: &std::path::Path,
+) -> Result<(), AppError> {
+    // Encrypted since backups stopped going up in the clear; older ones are read as they are
+    let decrypted_path = target.with_extension("decrypted");
+    let plain_path = if crypto::is_encrypted_file(downloaded)? {
+        crypto::decrypt_file(downloaded, &decrypted_path, license_key)?;
+        decrypted_path.as_path()
+    } else {
+        downloaded
+    };
+
+    // Servers that store the upload as sent return it gzipped
+    let written = upload::write_backup_file(plain_path, target);
+    let _ = std::fs::remove_file(&decrypted_path);
+    written.map_err(|e| AppError::Validation(format!("Bulut yedegi acilamadi, indirme eksik olabilir: {}", e)))?;
+
+    let Some(expected) = expected_checksum else {
+        eprintln!("[Cloud] Sunucu X-Backup-Checksum gondermedi, yedek dogrulanamadi");
+        return Ok(());
+    };
+    let actual = calculate_checksum(&target.to_string_lossy())?;
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(AppError::Validation(
+            "Bulut yedegi dogrulanamadi: checksum uyusmuyor, indirme eksik veya bozuk. Yerel veri degistirilmedi.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Restore database from cloud using SQLite backup API
+/// This restores data into the existing connection without requiring app restart.
+/// The backup is compared with local data first; if it is older or smaller,
+/// nothing is restored unless `force` is set.
+pub fn restore_from_cloud_with_conn(
+    db_path: &str,
+    api_base_url: &str,
+    dealer_id: &str,
+    license_key: &str,
+    force: bool,
+) -> Result<CloudRestoreResult, AppError> {
+    let temp_path = download_cloud_backup(api_base_url, dealer_id, license_key)?;
+
+    let result = reconcile::compare_databases(std::path::Path::new(db_path), &temp_path)
+        .and_then(|comparison| {
+            if comparison.requires_force && !force {
+                let message = format!(
+                    "Bulut yedegi yerel veriden eski veya daha kucuk: {}. Devam etmek icin onay gerekli.",
+                    comparison.warnings.join(", ")
+                );
+                return Ok(CloudRestoreResult { restored: false, comparison, message });
+            }
+
+            crate::database::restore_database_from_file(&temp_path, db_path)?;
+            Ok(CloudRestoreResult {
+                restored: true,
+                comparison,
+                message: "Bulut yedegi geri yuklendi".to_string(),
+            })
+        });
+
+    // Clean up temp file
+    let _ = std::fs::remove_file(&temp_path);
+
+    result
+}
+
 /// Get cloud backup status
 pub fn get_cloud_status(
     api_base_url: &str,
@@ -165,13 +314,15 @@ pub fn get_cloud_status(
     let client = reqwest::blocking::Client::new();
     let url = format!("{}/api/cloud/status", api_base_url);
 
-    let response = client
-        .get(&url)
-        .header("X-Dealer-ID", dealer_id)
-        .header("X-License-Key", license_key)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .map_err(|e| AppError::Internal(format!("Durum istegi basarisiz: {}", e)))?;
+    let response = auth::send_authorized(api_base_url, dealer_id, license_key, |token| {
+        http::send_with_retry(&url, || {
+            client
+                .get(&url)
+                .bearer_auth(token)
+                .timeout(std::time::Duration::from_secs(30))
+        })
+        .map_err(|e| AppError::Internal(format!("Durum istegi basarisiz: {}", e)))
+    })?;
 
     if response.status().is_success() {
         let result: CloudStatusResponse = response
@@ -275,3 +426,54 @@ pub fn set_auto_sync_settings(
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downloaded_backup_is_verified_before_restore() {
+        let dir = std::env::temp_dir().join(format!("nexus_cloud_verify_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("inventory.db");
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE inventory_items (id INTEGER PRIMARY KEY, name TEXT);
+                 INSERT INTO inventory_items (name) VALUES ('Vida'), ('Somun');",
+            )
+            .unwrap();
+        }
+        let checksum = calculate_checksum(db_path.to_str().unwrap()).unwrap();
+        let good = std::fs::read(&db_path).unwrap();
+        let target = dir.join("download.db");
+        let downloaded = dir.join("downloaded");
+        let verify = |bytes: &[u8], expected: &str| {
+            std::fs::write(&downloaded, bytes).unwrap();
+            write_verified_backup(&downloaded, Some(expected), "LIC-1", &target)
+        };
+
+        verify(&good, &checksum.to_uppercase()).unwrap();
+        let conn = Connection::open(&target).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM inventory_items", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 2);
+        drop(conn);
+
+        // One flipped byte and a truncated download are both rejected
+        let mut tampered = good.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0xff;
+        assert!(matches!(verify(&tampered, &checksum), Err(AppError::Validation(_))));
+        assert!(matches!(verify(&good[..good.len() / 2], &checksum), Err(AppError::Validation(_))));
+
+        // Encrypted downloads are decrypted file to file and checked after decryption
+        let encrypted_path = dir.join("encrypted");
+        crypto::encrypt_file(&db_path, &encrypted_path, "LIC-1", &[7u8; crypto::SALT_LEN]).unwrap();
+        let encrypted = std::fs::read(&encrypted_path).unwrap();
+        verify(&encrypted, &checksum).unwrap();
+        assert!(matches!(verify(&encrypted[..encrypted.len() - 1], &checksum), Err(AppError::Validation(_))));
+        assert!(!target.with_extension("decrypted").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,204 @@
+//! Cloud restore reconciliation
+//!
+//! Buluttan geri yüklemeden önce yedeği yerel veritabanıyla karşılaştırır.
+//! Yedek daha eski veya daha az kayıt içeriyorsa geri yükleme için `force` gerekir.
+
+use crate::error::AppError;
+use chrono::DateTime;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Row counts and latest change time of a database file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseSnapshot {
+    #[serde(rename = "lastModified")]
+    pub last_modified: Option<String>,
+    #[serde(rename = "transactionCount")]
+    pub transaction_count: i64,
+    #[serde(rename = "itemCount")]
+    pub item_count: i64,
+    #[serde(rename = "accountCount")]
+    pub account_count: i64,
+}
+
+/// Cloud backup vs local data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupComparison {
+    pub local: DatabaseSnapshot,
+    pub cloud: DatabaseSnapshot,
+    /// How many days the cloud backup is behind local data (negative = newer)
+    #[serde(rename = "daysOlder")]
+    pub days_older: Option<f64>,
+    /// Local count minus cloud count (positive = cloud has fewer)
+    #[serde(rename = "transactionDifference")]
+    pub transaction_difference: i64,
+    #[serde(rename = "itemDifference")]
+    pub item_difference: i64,
+    #[serde(rename = "accountDifference")]
+    pub account_difference: i64,
+    /// True when restoring would likely lose data
+    #[serde(rename = "requiresForce")]
+    pub requires_force: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Result of a cloud restore attempt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudRestoreResult {
+    pub restored: bool,
+    pub comparison: BackupComparison,
+    pub message: String,
+}
+
+fn table_exists(conn: &Connection, table: &str) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get::<_, i64>(0),
+    )
+    .unwrap_or(0)
+        > 0
+}
+
+fn count_rows(conn: &Connection, table: &str) -> i64 {
+    if !table_exists(conn, table) {
+        return 0;
+    }
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+        .unwrap_or(0)
+}
+
+fn max_timestamp(conn: &Connection, table: &str, column: &str) -> Option<String> {
+    if !table_exists(conn, table) {
+        return None;
+    }
+    conn.query_row(&format!("SELECT MAX({}) FROM {}", column, table), [], |row| {
+        row.get::<_, Option<String>>(0)
+    })
+    .ok()
+    .flatten()
+}
+
+/// Read row counts and the newest change timestamp from a database file (read-only)
+pub fn snapshot_database(path: &Path) -> Result<DatabaseSnapshot, AppError> {
+    let conn = super::open_read_only(path)
+        .map_err(|e| AppError::Internal(format!("Veritabani acilamadi: {}", e)))?;
+
+    let last_modified = [
+        max_timestamp(&conn, "transactions", "created_at"),
+        max_timestamp(&conn, "inventory_items", "last_updated"),
+        max_timestamp(&conn, "activity_log", "created_at"),
+    ]
+    .into_iter()
+    .flatten()
+    .max_by(|a, b| compare_timestamps(a, b));
+
+    Ok(DatabaseSnapshot {
+        last_modified,
+        transaction_count: count_rows(&conn, "transactions"),
+        item_count: count_rows(&conn, "inventory_items"),
+        account_count: count_rows(&conn, "current_accounts"),
+    })
+}
+
+fn compare_timestamps(a: &str, b: &str) -> std::cmp::Ordering {
+    match (DateTime::parse_from_rfc3339(a), DateTime::parse_from_rfc3339(b)) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Compare a downloaded cloud backup with the local database
+pub fn compare_databases(local_path: &Path, cloud_path: &Path) -> Result<BackupComparison, AppError> {
+    let local = snapshot_database(local_path)?;
+    let cloud = snapshot_database(cloud_path)?;
+
+    let days_older = match (&local.last_modified, &cloud.last_modified) {
+        (Some(l), Some(c)) => match (DateTime::parse_from_rfc3339(l), DateTime::parse_from_rfc3339(c)) {
+            (Ok(l), Ok(c)) => Some((l - c).num_seconds() as f64 / 86_400.0),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let transaction_difference = local.transaction_count - cloud.transaction_count;
+    let item_difference = local.item_count - cloud.item_count;
+    let account_difference = local.account_count - cloud.account_count;
+
+    let mut warnings = Vec::new();
+    let cloud_is_older = match (&local.last_modified, &cloud.last_modified) {
+        (Some(l), Some(c)) => compare_timestamps(c, l) == std::cmp::Ordering::Less,
+        (Some(_), None) => true,
+        _ => false,
+    };
+    if cloud_is_older {
+        match days_older {
+            Some(days) if days >= 1.0 => warnings.push(format!("Bulut yedegi yerel veriden {:.0} gun daha eski", days)),
+            Some(days) => warnings.push(format!("Bulut yedegi yerel veriden {:.0} saat daha eski", (days * 24.0).ceil())),
+            None => warnings.push("Bulut yedegi yerel veriden daha eski".to_string()),
+        }
+    }
+    if transaction_difference > 0 {
+        warnings.push(format!("{} daha az islem iceriyor", transaction_difference));
+    }
+    if item_difference > 0 {
+        warnings.push(format!("{} daha az urun iceriyor", item_difference));
+    }
+    if account_difference > 0 {
+        warnings.push(format!("{} daha az cari hesap iceriyor", account_difference));
+    }
+
+    Ok(BackupComparison {
+        local,
+        cloud,
+        days_older,
+        transaction_difference,
+        item_difference,
+        account_difference,
+        requires_force: !warnings.is_empty(),
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_db(path: &Path, transactions: &[&str]) {
+        let _ = std::fs::remove_file(path);
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE transactions (id TEXT PRIMARY KEY, created_at TEXT NOT NULL);
+             CREATE TABLE inventory_items (id TEXT PRIMARY KEY, last_updated TEXT NOT NULL);",
+        )
+        .unwrap();
+        for (i, created_at) in transactions.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO transactions (id, created_at) VALUES (?1, ?2)",
+                rusqlite::params![i.to_string(), created_at],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_older_smaller_backup_requires_force() {
+        let dir = std::env::temp_dir();
+        let local = dir.join(format!("reconcile_local_{}.db", std::process::id()));
+        let cloud = dir.join(format!("reconcile_cloud_{}.db", std::process::id()));
+        make_db(&local, &["2024-03-01T10:00:00+00:00", "2024-03-03T10:00:00+00:00"]);
+        make_db(&cloud, &["2024-03-01T10:00:00+00:00"]);
+
+        let comparison = compare_databases(&local, &cloud).unwrap();
+        assert!(comparison.requires_force);
+        assert_eq!(comparison.transaction_difference, 1);
+        assert_eq!(comparison.days_older, Some(2.0));
+
+        let same = compare_databases(&local, &local).unwrap();
+        assert!(!same.requires_force);
+
+        let _ = std::fs::remove_file(&local);
+        let _ = std::fs::remove_file(&cloud);
+    }
+}
@@ -0,0 +1,322 @@
+//! Streaming cloud upload
+//!
+//! Veritabanı dosyasını belleğe almadan parça parça okuyarak gönderir.
+//! SHA256 özeti okuma sırasında hesaplanır; bellek kullanımı dosya boyutundan bağımsızdır.
+//! Dosya önce gzip ile sıkıştırılabilir ve şifrelenebilir; özet her zaman sıkıştırılmamış veri
+//! üzerinden alınır.
+
+use base64::Engine as _;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Raw bytes read per chunk; a multiple of 3 so base64 chunks concatenate without padding
+const CHUNK_SIZE: usize = 3 * 16 * 1024;
+
+/// Length of a hex encoded SHA256 digest
+const CHECKSUM_HEX_LEN: u64 = 64;
+
+/// File reader that opens the file on first read, so every retry attempt gets a fresh reader
+pub struct LazyFileReader {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl LazyFileReader {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), file: None }
+    }
+}
+
+impl Read for LazyFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.file.is_none() {
+            self.file = Some(File::open(&self.path)?);
+        }
+        self.file.as_mut().map_or(Ok(0), |f| f.read(buf))
+    }
+}
+
+enum Stage {
+    Prefix,
+    Data,
+    Suffix,
+    Done,
+}
+
+/// Called with (body bytes read so far, total body bytes) as the upload is streamed
+pub type UploadProgress = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// First bytes of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Gzip `source` into `target`
+pub fn compress_file(source: &Path, target: &Path) -> io::Result<()> {
+    let mut input = BufReader::new(File::open(source)?);
+    let mut encoder = GzEncoder::new(BufWriter::new(File::create(target)?), Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.flush()
+}
+
+/// Write a downloaded backup to `target`, decompressing it when the server sent it gzipped
+pub fn write_backup_file(source: &Path, target: &Path) -> io::Result<()> {
+    let mut input = BufReader::new(File::open(source)?);
+    let gzipped = input.fill_buf()?.starts_with(&GZIP_MAGIC);
+    let mut output = BufWriter::new(File::create(target)?);
+    if gzipped {
+        io::copy(&mut GzDecoder::new(input), &mut output)?;
+    } else {
+        io::copy(&mut input, &mut output)?;
+    }
+    output.flush()
+}
+
+/// JSON backup body `{"dealer_id":..,"backup_data":"<base64>","checksum":".."}` produced on the
+/// fly. The license key is not part of it; the request carries a bearer token (see `auth`). The checksum is written last, once the whole file has been hashed.
+/// Compressed bodies carry the checksum of the original file and `"compressed":true`,
+/// encrypted ones `"encrypted":true`.
+pub struct Base64JsonUpload {
+    prefix: Vec<u8>,
+    file: LazyFileReader,
+    hasher: Sha256,
+    checksum: Option<String>,
+    compressed: bool,
+    encrypted: bool,
+    raw: Vec<u8>,
+    pending: Vec<u8>,
+    pending_pos: usize,
+    stage: Stage,
+    progress: Option<(UploadProgress, u64)>,
+    sent: u64,
+}
+
+impl Base64JsonUpload {
+    pub fn new(path: impl Into<PathBuf>, dealer_id: &str) -> Self {
+        let prefix = format!("{{\"dealer_id\":{},\"backup_data\":\"", serde_json::Value::from(dealer_id));
+        Self {
+            prefix: prefix.into_bytes(),
+            file: LazyFileReader::new(path),
+            hasher: Sha256::new(),
+            checksum: None,
+            compressed: false,
+            encrypted: false,
+            raw: vec![0u8; CHUNK_SIZE],
+            pending: Vec::new(),
+            pending_pos: 0,
+            stage: Stage::Prefix,
+            progress: None,
+            sent: 0,
+        }
+    }
+
+    /// Body for a gzip file made by `compress_file`; `checksum` is the SHA256 of the uncompressed database
+    pub fn compressed(gz_path: impl Into<PathBuf>, dealer_id: &str, checksum: String) -> Self {
+        Self {
+            checksum: Some(checksum),
+            compressed: true,
+            ..Self::new(gz_path, dealer_id)
+        }
+    }
+
+    /// Body for a file made by `crypto::encrypt_file`; `checksum` is the SHA256 of the plain database.
+    /// The server stores it as it is and cannot read it.
+    pub fn encrypted(enc_path: impl Into<PathBuf>, dealer_id: &str, checksum: String) -> Self {
+        Self {
+            checksum: Some(checksum),
+            encrypted: true,
+            ..Self::new(enc_path, dealer_id)
+        }
+    }
+
+    /// Report progress against `total` (the body's `content_length`) after every chunk
+    pub fn with_progress(mut self, total: u64, progress: UploadProgress) -> Self {
+        self.progress = Some((progress, total));
+        self
+    }
+
+    /// Exact body length for a file of `file_size` bytes (sent as Content-Length)
+    pub fn content_length(&self, file_size: u64) -> u64 {
+        let base64_len = file_size.div_ceil(3) * 4;
+        self.prefix.len() as u64 + base64_len + self.suffix_len()
+    }
+
+    fn suffix(&self, checksum: &str) -> String {
+        if self.encrypted {
+            format!("\",\"checksum\":\"{}\",\"encrypted\":true}}", checksum)
+        } else if self.compressed {
+            format!("\",\"checksum\":\"{}\",\"compressed\":true}}", checksum)
+        } else {
+            format!("\",\"checksum\":\"{}\"}}", checksum)
+        }
+    }
+
+    fn suffix_len(&self) -> u64 {
+        self.suffix("").len() as u64 + CHECKSUM_HEX_LEN
+    }
+
+    /// Fill `raw` as far as possible so only the last chunk may need base64 padding
+    fn read_chunk(&mut self) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < self.raw.len() {
+            let n = self.file.read(&mut self.raw[filled..])?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        Ok(filled)
+    }
+
+    fn refill(&mut self) -> io::Result<()> {
+        self.pending.clear();
+        self.pending_pos = 0;
+        match self.stage {
+            Stage::Prefix => {
+                self.pending.extend_from_slice(&self.prefix);
+                self.stage = Stage::Data;
+            }
+            Stage::Data => {
+                let n = self.read_chunk()?;
+                if n == 0 {
+                    self.stage = Stage::Suffix;
+                    return self.refill();
+                }
+                self.hasher.update(&self.raw[..n]);
+                let encoded = base64::engine::general_purpose::STANDARD.encode(&self.raw[..n]);
+                self.pending.extend_from_slice(encoded.as_bytes());
+            }
+            Stage::Suffix => {
+                let hashed = format!("{:x}", std::mem::take(&mut self.hasher).finalize());
+                let suffix = self.suffix(self.checksum.as_deref().unwrap_or(&hashed));
+                self.pending.extend_from_slice(suffix.as_bytes());
+                self.stage = Stage::Done;
+            }
+            Stage::Done => {}
+        }
+        Ok(())
+    }
+}
+
+impl Read for Base64JsonUpload {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pending_pos >= self.pending.len() {
+            if matches!(self.stage, Stage::Done) {
+                return Ok(0);
+            }
+            self.refill()?;
+        }
+        let n = buf.len().min(self.pending.len() - self.pending_pos);
+        buf[..n].copy_from_slice(&self.pending[self.pending_pos..self.pending_pos + n]);
+        self.pending_pos += n;
+        self.sent += n as u64;
+        if let Some((progress, total)) = &self.progress {
+            // Once per chunk; the body reader is usually given larger buffers than a chunk
+            if self.pending_pos == self.pending.len() {
+                progress(self.sent, *total);
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streamed_body_matches_buffered_encoding() {
+        let path = std::env::temp_dir().join(format!("upload_stream_{}.db", std::process::id()));
+        // Not a multiple of the chunk size or of 3
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 7).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let upload = Base64JsonUpload::new(&path, "dealer \"1\"");
+        let expected_len = upload.content_length(data.len() as u64);
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let mut upload = upload.with_progress(expected_len, Arc::new(move |sent, total| sink.lock().unwrap().push((sent, total))));
+        let mut body = Vec::new();
+        upload.read_to_end(&mut body).unwrap();
+        assert_eq!(body.len() as u64, expected_len);
+
+        // Prefix, three data chunks and the suffix, ending at the full length
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 5);
+        assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(reports.last(), Some(&(expected_len, expected_len)));
+
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["dealer_id"], "dealer \"1\"");
+        assert!(json.get("license_key").is_none());
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(json["backup_data"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(decoded, data);
+        assert_eq!(
+            json["checksum"].as_str().unwrap(),
+            super::super::calculate_checksum(path.to_str().unwrap()).unwrap()
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_compressed_backup_round_trip() {
+        let dir = std::env::temp_dir();
+        let id = uuid::Uuid::new_v4();
+        let db_path = dir.join(format!("upload_gz_{}.db", id));
+        let gz_path = dir.join(format!("upload_gz_{}.db.gz", id));
+        let restored_path = dir.join(format!("upload_gz_{}_restored.db", id));
+        let stored_path = dir.join(format!("upload_gz_{}_stored", id));
+
+        {
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            conn.execute_batch("CREATE TABLE inventory_items (id INTEGER PRIMARY KEY, name TEXT);").unwrap();
+            for i in 0..2000 {
+                conn.execute("INSERT INTO inventory_items (name) VALUES (?1)", [format!("Urun {}", i % 50)])
+                    .unwrap();
+            }
+        }
+        let checksum = super::super::calculate_checksum(db_path.to_str().unwrap()).unwrap();
+
+        compress_file(&db_path, &gz_path).unwrap();
+        let gz_size = std::fs::metadata(&gz_path).unwrap().len();
+        assert!(gz_size < std::fs::metadata(&db_path).unwrap().len());
+
+        let mut upload = Base64JsonUpload::compressed(&gz_path, "dealer-1", checksum.clone());
+        let expected_len = upload.content_length(gz_size);
+        let mut body = Vec::new();
+        upload.read_to_end(&mut body).unwrap();
+        assert_eq!(body.len() as u64, expected_len);
+
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["compressed"], true);
+        assert_eq!(json["checksum"].as_str().unwrap(), checksum);
+
+        // What the server would store and send back
+        let stored = base64::engine::general_purpose::STANDARD
+            .decode(json["backup_data"].as_str().unwrap())
+            .unwrap();
+        std::fs::write(&stored_path, &stored).unwrap();
+        write_backup_file(&stored_path, &restored_path).unwrap();
+        assert_eq!(super::super::calculate_checksum(restored_path.to_str().unwrap()).unwrap(), checksum);
+
+        let conn = rusqlite::Connection::open(&restored_path).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM inventory_items", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 2000);
+        drop(conn);
+
+        // Plain downloads are written as they are
+        write_backup_file(&db_path, &restored_path).unwrap();
+        assert_eq!(super::super::calculate_checksum(restored_path.to_str().unwrap()).unwrap(), checksum);
+
+        for path in [&db_path, &gz_path, &stored_path, &restored_path] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
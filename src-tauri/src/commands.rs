@@ -1,35 +1,74 @@
 use crate::models::{
-    CartItem, DashboardStats, InventoryItem, Transaction, SalesDataPoint, CategoryStats,
-    ActivityLog, User, CreateUserRequest, UpdateUserRequest,
-    License, LicenseValidateResponse, LicenseActivateResponse,
-    SyncStatus, CloudBackupResponse, CloudStatusResponse,
+    CartItem, DashboardStats, DeletedItem, InventoryItem, Transaction, SalesDataPoint, CategoryStats,
+    ActivityLog, AuthEvent, AuthSession, User, CreateUserRequest, UpdateUserRequest,
+    License, LicenseValidateResponse, LicenseActivateResponse, LicenseCheckResult, LicensePolicy,
+    SyncStatus, AutoSyncSettingsResult, CloudStatusResponse,
     PaginatedItemsResponse, PaginatedTransactionsResponse,
     FinanceRecord, FinanceSummary, AccessCode, GoodsReceiptItem,
     CurrentAccount, CreateCurrentAccountRequest, StockCard, CreateStockCardRequest,
-    Category, CreateCategoryRequest, InventoryLot,
+    Category, CategoryNode, CreateCategoryRequest, InventoryLot, ExpiringLot, ExchangeRate, AccountTreeNode, ConsolidatedBalance,
+    BalanceDiscrepancy, AccountStatement, DatabaseSchemaInfo, AttributeValues,
+    ImportStageSummary, PriceOutlier, ImportCommitResult, CsvImportResult, DbHealthSnapshot, DEFAULT_MIN_STOCK_LEVEL,
+    IntegrityReport, VacuumReport,
+    CategoryMargin, PaginatedGoodsReceiptsResponse, POLine, PurchaseOrder, ProductComponent, BundleComponentInput, BundleSummary,
+    CacheStats, ZReport, ReturnItem, Payment, SaleDiscount,
 };
 use crate::AppState;
-use crate::security::password::{hash_password, verify_password};
-use crate::security::validation::{validate_sku, validate_price, validate_quantity, validate_username, validate_password_strength};
+use crate::cache;
+use crate::security::admin;
+use crate::security::login_policy;
+use crate::security::auth_events;
+use crate::security::login;
+use crate::security::password_history;
+use crate::security::session;
+use crate::security::password::hash_password;
+use crate::security::validation::{validate_sku, validate_price, validate_quantity, validate_min_stock_level, validate_username, validate_password_strength};
 use crate::license;
 use crate::cloud;
 use crate::services::backup::{self, BackupInfo};
+use crate::services::backup_target::{self, BackupTargetConfig};
 use crate::services::startup;
 use crate::services::print as print_service;
 use crate::services::updater as updater_service;
+use crate::services::http as http_service;
 use crate::services::scanner;
 use crate::services::inventory;
+use crate::services::accounts;
+use crate::services::pricing;
+use crate::services::goods_receipts;
+use crate::services::currency;
+use crate::services::images;
+use crate::services::units;
+use crate::services::scheduled_export;
+use crate::services::recurring_expenses::{self, RecurringExpense};
+use crate::services::bundles;
+use crate::services::categories;
+use crate::services::json_export;
+use crate::services::attributes as attributes_service;
+use crate::services::import_staging;
+use crate::services::health;
+use crate::services::finance;
+use crate::services::settings;
+use crate::services::xlsx;
+use crate::services::purchase_orders;
 use rusqlite::{params, OptionalExtension};
-use tauri::State;
+use std::collections::{BTreeMap, HashMap};
+use tauri::{Emitter, Manager, State};
 
 // ==================== INVENTORY COMMANDS ====================
 
 #[tauri::command]
 pub fn get_all_items(state: State<AppState>) -> Result<Vec<InventoryItem>, String> {
+    cache::read_through(&state.cache, |c| c.get_all_items(), |c, items| c.set_all_items(items), || {
+        load_all_items(&state)
+    })
+}
+
+fn load_all_items(state: &AppState) -> Result<Vec<InventoryItem>, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency, supplier_id, brand FROM inventory_items ORDER BY name"
+        "SELECT id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency, supplier_id, brand, min_stock_level, unit FROM inventory_items WHERE deleted_at IS NULL ORDER BY name"
     ).map_err(|e| e.to_string())?;
 
     let items = stmt.query_map([], |row| {
@@ -52,6 +91,8 @@ pub fn get_all_items(state: State<AppState>) -> Result<Vec<InventoryItem>, Strin
             currency: row.get(12)?,
             supplier_id: row.get(13)?,
             brand: row.get(14).unwrap_or(None),
+            min_stock_level: row.get(15).unwrap_or(DEFAULT_MIN_STOCK_LEVEL),
+            unit: row.get(16).unwrap_or_else(|_| units::DEFAULT_UNIT.to_string()),
         })
     }).map_err(|e| e.to_string())?;
 
@@ -60,13 +101,27 @@ pub fn get_all_items(state: State<AppState>) -> Result<Vec<InventoryItem>, Strin
 
 #[tauri::command]
 pub fn get_item_by_sku(state: State<AppState>, sku: String) -> Result<Option<InventoryItem>, String> {
+    // Only found items are cached; a miss is looked up again next time
+    cache::read_through(
+        &state.cache,
+        |c| c.get_item(&sku).map(Some),
+        |c, item| {
+            if let Some(item) = item {
+                c.set_item(item.sku.clone(), item);
+            }
+        },
+        || load_item_by_sku(&state, &sku),
+    )
+}
+
+fn load_item_by_sku(state: &AppState, sku: &str) -> Result<Option<InventoryItem>, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency, supplier_id, brand FROM inventory_items WHERE sku = ?1"
+        "SELECT id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency, supplier_id, brand, min_stock_level, unit FROM inventory_items WHERE sku = ?1 AND deleted_at IS NULL"
     ).map_err(|e| e.to_string())?;
 
-    let item = stmt.query_row(params![&sku], |row| {
+    let item = stmt.query_row(params![sku], |row| {
         let ai_tags_str: Option<String> = row.get(10)?;
         let ai_tags: Option<Vec<String>> = ai_tags_str.and_then(|s| serde_json::from_str(&s).ok());
 
@@ -86,53 +141,52 @@ pub fn get_item_by_sku(state: State<AppState>, sku: String) -> Result<Option<Inv
             currency: row.get(12)?,
             supplier_id: row.get(13)?,
             brand: row.get(14).unwrap_or(None),
+            min_stock_level: row.get(15).unwrap_or(DEFAULT_MIN_STOCK_LEVEL),
+            unit: row.get(16).unwrap_or_else(|_| units::DEFAULT_UNIT.to_string()),
         })
     }).optional().map_err(|e| e.to_string())?;
 
     Ok(item)
 }
 
+/// Ranked full-text search over item name, SKU, description and brand
 #[tauri::command]
-pub fn add_item(state: State<AppState>, item: InventoryItem) -> Result<(), String> {
+pub fn search_items(state: State<AppState>, query: String, limit: Option<i64>) -> Result<Vec<InventoryItem>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    inventory::search_items(&conn, &query, limit.unwrap_or(inventory::DEFAULT_SEARCH_LIMIT)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_item(
+    state: State<AppState>,
+    mut item: InventoryItem,
+    on_conflict: Option<String>,
+) -> Result<inventory::AddItemOutcome, String> {
     // Input validation
     validate_sku(&item.sku)?;
     validate_price(item.price)?;
-    validate_quantity(item.quantity)?;
+    validate_quantity(item.quantity, &item.unit)?;
+    validate_min_stock_level(item.min_stock_level)?;
 
-    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    let ai_tags_str = serde_json::to_string(&item.ai_tags).unwrap_or("[]".to_string());
+    // on_conflict: "error" (varsayılan) | "update" | "skip"
+    let policy = inventory::SkuConflictPolicy::parse(on_conflict.as_deref()).map_err(|e| e.to_string())?;
+    item.image = images::externalize_image(&state.db.data_dir(), item.image).map_err(|e| e.to_string())?;
 
-    conn.execute(
-        "INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency, supplier_id, brand) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
-        params![
-            &item.id,
-            &item.sku,
-            &item.name,
-            &item.category,
-            item.quantity,
-            &item.location,
-            item.price,
-            item.cost_price,
-            &item.image,
-            &item.description,
-            &ai_tags_str,
-            &item.last_updated,
-            &item.currency,
-            &item.supplier_id,
-            &item.brand,
-        ],
-    ).map_err(|e| e.to_string())?;
-
-    Ok(())
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let outcome = inventory::add_item_with_policy(&conn, &item, policy).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_item(&item.sku);
+    Ok(outcome)
 }
 
 #[tauri::command]
-pub fn update_item(state: State<AppState>, item: InventoryItem) -> Result<(), String> {
+pub fn update_item(state: State<AppState>, mut item: InventoryItem) -> Result<(), String> {
+    validate_min_stock_level(item.min_stock_level)?;
+    item.image = images::externalize_image(&state.db.data_dir(), item.image).map_err(|e| e.to_string())?;
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
     let ai_tags_str = serde_json::to_string(&item.ai_tags).unwrap_or("[]".to_string());
 
     conn.execute(
-        "UPDATE inventory_items SET name = ?1, category = ?2, quantity = ?3, location = ?4, price = ?5, cost_price = ?6, image = ?7, description = ?8, ai_tags = ?9, last_updated = ?10, currency = ?11, supplier_id = ?12, brand = ?13 WHERE sku = ?14",
+        "UPDATE inventory_items SET name = ?1, category = ?2, quantity = ?3, location = ?4, price = ?5, cost_price = ?6, image = ?7, description = ?8, ai_tags = ?9, last_updated = ?10, currency = ?11, supplier_id = ?12, brand = ?13, min_stock_level = ?14 WHERE sku = ?15",
         params![
             &item.name,
             &item.category,
@@ -147,38 +201,103 @@ pub fn update_item(state: State<AppState>, item: InventoryItem) -> Result<(), St
             &item.currency,
             &item.supplier_id,
             &item.brand,
+            item.min_stock_level,
             &item.sku,
         ],
     ).map_err(|e| e.to_string())?;
 
+    cache::lock(&state.cache).invalidate_item(&item.sku);
     Ok(())
 }
 
+/// Moves the item to the trash (restore_item brings it back)
 #[tauri::command]
 pub fn delete_item(state: State<AppState>, sku: String) -> Result<(), String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    inventory::delete_item(&conn, &sku).map_err(|e| e.to_string())?;
 
-    conn.execute("DELETE FROM inventory_items WHERE sku = ?1", params![&sku])
-        .map_err(|e| e.to_string())?;
-
+    cache::lock(&state.cache).invalidate_item(&sku);
     Ok(())
 }
 
+/// Store an uploaded image for the item as a file; returns the relative path now in `image`
+#[tauri::command]
+pub fn save_item_image(state: State<AppState>, sku: String, bytes: Vec<u8>) -> Result<String, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let path = images::save_item_image(&conn, &state.db.data_dir(), &sku, &bytes).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_item(&sku);
+    Ok(path)
+}
+
+#[tauri::command]
+pub fn get_item_image(state: State<AppState>, sku: String) -> Result<Option<Vec<u8>>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    images::get_item_image(&conn, &state.db.data_dir(), &sku).map_err(|e| e.to_string())
+}
+
+/// Small WebP preview of the item image, for lists and grids
+#[tauri::command]
+pub fn get_item_thumbnail(state: State<AppState>, sku: String) -> Result<Option<Vec<u8>>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    images::get_item_thumbnail(&conn, &state.db.data_dir(), &sku).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_item(state: State<AppState>, sku: String) -> Result<InventoryItem, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let item = inventory::restore_item(&conn, &sku).map_err(|e| e.to_string())?;
+
+    cache::lock(&state.cache).invalidate_item(&sku);
+    Ok(item)
+}
+
 #[tauri::command]
-pub fn update_quantity(state: State<AppState>, sku: String, quantity: i32) -> Result<(), String> {
+pub fn list_deleted_items(state: State<AppState>) -> Result<Vec<DeletedItem>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    inventory::list_deleted_items(&conn).map_err(|e| e.to_string())
+}
+
+/// Permanently removes items in the trash for more than older_than_days days, with their lots
+#[tauri::command]
+pub fn purge_deleted_items(state: State<AppState>, older_than_days: i64) -> Result<usize, String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let purged = inventory::purge_deleted_items(&mut conn, older_than_days).map_err(|e| e.to_string())?;
+
+    cache::lock(&state.cache).invalidate_all();
+    Ok(purged)
+}
+
+#[tauri::command]
+pub fn update_quantity(state: State<AppState>, sku: String, quantity: f64) -> Result<(), String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
     let now = chrono::Utc::now().to_rfc3339();
+    let unit = inventory::item_unit(&conn, &sku).map_err(|e| e.to_string())?.unwrap_or_default();
+    validate_quantity(quantity, &unit)?;
 
     conn.execute(
         "UPDATE inventory_items SET quantity = ?1, last_updated = ?2 WHERE sku = ?3",
         params![quantity, &now, &sku],
     ).map_err(|e| e.to_string())?;
 
+    cache::lock(&state.cache).invalidate_item(&sku);
     Ok(())
 }
 
+/// Set stock to a counted quantity with a reason; returns the signed difference
+#[tauri::command]
+pub fn adjust_stock(state: State<AppState>, sku: String, new_quantity: f64, reason: String) -> Result<f64, String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let delta = inventory::adjust_stock(&mut conn, &sku, new_quantity, &reason).map_err(|e| e.to_string())?;
+
+    cache::lock(&state.cache).invalidate_item(&sku);
+    Ok(delta)
+}
+
 // ==================== TRANSACTION COMMANDS ====================
 
+/// `payments` splits a sale over several methods (e.g. part cash, part card); it must add up to
+/// the total and replaces `payment_method`. `discount` is a whole-ticket discount (amount or
+/// percent) taken off after the lines' own discounts.
 #[tauri::command]
 pub fn process_sale(
     state: State<AppState>,
@@ -187,17 +306,57 @@ pub fn process_sale(
     transaction_type: String,
     note: Option<String>,
     customer_id: Option<String>,
+    payments: Option<Vec<Payment>>,
+    discount: Option<SaleDiscount>,
 ) -> Result<Transaction, String> {
     let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let sold: Vec<(String, f64)> = cart_items.iter().map(|i| (i.sku.clone(), i.cart_quantity)).collect();
 
-    inventory::process_sale_transaction(
+    let transaction = inventory::process_sale_with_payments(
         &mut conn,
         cart_items,
         payment_method,
+        payments,
+        discount,
         transaction_type,
         note,
         customer_id
-    ).map_err(|e| e.to_string())
+    ).map_err(|e| e.to_string())?;
+
+    // Bundles move their components' stock, not their own
+    let mut cache = cache::lock(&state.cache);
+    for (sku, quantity) in sold {
+        for (moved_sku, _) in bundles::expand_stock_movements(&conn, &sku, quantity).unwrap_or_default() {
+            cache.invalidate_item(&moved_sku);
+        }
+        cache.invalidate_item(&sku);
+    }
+    cache.invalidate_aggregates();
+
+    Ok(transaction)
+}
+
+/// Return part of a sale; quantities are checked against the sale and its earlier returns
+#[tauri::command]
+pub fn process_partial_return(
+    state: State<AppState>,
+    original_transaction_id: String,
+    returned_items: Vec<ReturnItem>,
+) -> Result<Transaction, String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let transaction = inventory::process_partial_return(&mut conn, &original_transaction_id, returned_items)
+        .map_err(|e| e.to_string())?;
+
+    let mut cache = cache::lock(&state.cache);
+    for line in &transaction.items {
+        for (moved_sku, _) in bundles::expand_stock_movements(&conn, &line.sku, line.cart_quantity).unwrap_or_default() {
+            cache.invalidate_item(&moved_sku);
+        }
+        cache.invalidate_item(&line.sku);
+    }
+    cache.invalidate_aggregates();
+
+    Ok(transaction)
 }
 
 #[tauri::command]
@@ -205,7 +364,7 @@ pub fn get_transactions(state: State<AppState>) -> Result<Vec<Transaction>, Stri
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id FROM transactions ORDER BY created_at DESC"
+        "SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id, status, tax_total, line_discount, ticket_discount FROM transactions ORDER BY created_at DESC"
     ).map_err(|e| e.to_string())?;
 
     let transactions = stmt.query_map([], |row| {
@@ -218,7 +377,10 @@ pub fn get_transactions(state: State<AppState>) -> Result<Vec<Transaction>, Stri
             total: row.get(2)?,
             payment_method: row.get(3)?,
             transaction_type: row.get(4)?,
-            status: "completed".to_string(),
+            status: row.get(8)?,
+            tax_total: row.get(9)?,
+            line_discount: row.get(10)?,
+            ticket_discount: row.get(11)?,
             note: row.get(5)?,
             created_at: row.get(6)?,
             customer_id: row.get(7).unwrap_or(None),
@@ -237,7 +399,7 @@ pub fn get_transactions_by_date_range(
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id FROM transactions WHERE substr(created_at, 1, 10) >= ?1 AND substr(created_at, 1, 10) <= ?2 ORDER BY created_at DESC"
+        "SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id, status, tax_total, line_discount, ticket_discount FROM transactions WHERE substr(created_at, 1, 10) >= ?1 AND substr(created_at, 1, 10) <= ?2 ORDER BY created_at DESC"
     ).map_err(|e| e.to_string())?;
 
     let transactions = stmt.query_map(params![&start_date, &end_date], |row| {
@@ -250,7 +412,10 @@ pub fn get_transactions_by_date_range(
             total: row.get(2)?,
             payment_method: row.get(3)?,
             transaction_type: row.get(4)?,
-            status: "completed".to_string(),
+            status: row.get(8)?,
+            tax_total: row.get(9)?,
+            line_discount: row.get(10)?,
+            ticket_discount: row.get(11)?,
             note: row.get(5)?,
             created_at: row.get(6)?,
             customer_id: row.get(7).unwrap_or(None),
@@ -260,6 +425,25 @@ pub fn get_transactions_by_date_range(
     transactions.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
+/// Transaction by id or by a scanned receipt QR (used for viewing and returning a purchase)
+#[tauri::command]
+pub fn get_transaction_by_id(state: State<AppState>, id: String) -> Result<Transaction, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let secret = print_service::receipt_qr_secret(&conn).map_err(|e| e.to_string())?;
+    let id = print_service::parse_receipt_qr(&id, &secret).map_err(|e| e.to_string())?;
+    inventory::get_transaction(&conn, &id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("İşlem bulunamadı: {}", id))
+}
+
+#[tauri::command]
+pub fn void_transaction(state: State<AppState>, id: String) -> Result<Transaction, String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let transaction = inventory::void_transaction(&mut conn, &id).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(transaction)
+}
+
 #[derive(serde::Deserialize)]
 pub struct TransactionUpdate {
     pub payment_method: Option<String>,
@@ -277,7 +461,7 @@ pub fn update_transaction(
     if let Some(pm) = updates.payment_method {
         conn.execute(
             "UPDATE transactions SET payment_method = ?1 WHERE id = ?2",
-            params![&pm, &id],
+            params![finance::canonical_payment_method(&pm), &id],
         ).map_err(|e| e.to_string())?;
     }
 
@@ -293,42 +477,30 @@ pub fn update_transaction(
 
 // ==================== DASHBOARD & ANALYTICS COMMANDS ====================
 
+/// Revenue figures cover `start_date`..=`end_date`, this month when omitted (only that default is cached)
 #[tauri::command]
-pub fn get_dashboard_stats(state: State<AppState>) -> Result<DashboardStats, String> {
-    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-
-    let total_items: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM inventory_items",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(0);
-
-    let total_quantity: i32 = conn.query_row(
-        "SELECT COALESCE(SUM(quantity), 0) FROM inventory_items",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(0);
-
-    let low_stock_count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM inventory_items WHERE quantity < 10",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(0);
-
-    let total_revenue: f64 = conn.query_row(
-        "SELECT COALESCE(SUM(total), 0) FROM transactions",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(0.0);
-
-    Ok(DashboardStats {
-        total_items,
-        total_quantity,
-        low_stock_count,
-        total_revenue,
+pub fn get_dashboard_stats(
+    state: State<AppState>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<DashboardStats, String> {
+    if start_date.is_some() || end_date.is_some() {
+        let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+        return inventory::get_dashboard_stats(&conn, start_date.as_deref(), end_date.as_deref())
+            .map_err(|e| e.to_string());
+    }
+    cache::read_through(&state.cache, |c| c.get_dashboard_stats(), |c, stats| c.set_dashboard_stats(stats), || {
+        let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+        inventory::get_dashboard_stats(&conn, None, None).map_err(|e| e.to_string())
     })
 }
 
+#[tauri::command]
+pub fn get_low_stock_items(state: State<AppState>) -> Result<Vec<InventoryItem>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    inventory::get_low_stock_items(&conn).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_sales_by_date_range(
     state: State<AppState>,
@@ -341,7 +513,7 @@ pub fn get_sales_by_date_range(
     let end_dt = format!("{}T23:59:59", end_date);
 
     let mut stmt = conn.prepare(
-        "SELECT date(created_at) as sale_date, COALESCE(SUM(total), 0) as daily_total, COUNT(*) as sale_count FROM transactions WHERE created_at >= ?1 AND created_at <= ?2 GROUP BY date(created_at) ORDER BY sale_date ASC"
+        "SELECT date(created_at) as sale_date, COALESCE(SUM(total), 0) as daily_total, COUNT(*) as sale_count FROM transactions WHERE created_at >= ?1 AND created_at <= ?2 AND status != 'voided' GROUP BY date(created_at) ORDER BY sale_date ASC"
     ).map_err(|e| e.to_string())?;
 
     let sales = stmt.query_map(params![&start_dt, &end_dt], |row| {
@@ -357,22 +529,26 @@ pub fn get_sales_by_date_range(
 
 #[tauri::command]
 pub fn get_category_stats(state: State<AppState>) -> Result<Vec<CategoryStats>, String> {
-    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    cache::read_through(&state.cache, |c| c.get_category_stats(), |c, stats| c.set_category_stats(stats), || {
+        load_category_stats(&state)
+    })
+}
 
-    let mut stmt = conn.prepare(
-        "SELECT category, COUNT(*) as count, COALESCE(SUM(quantity), 0) as total_qty, COALESCE(SUM(price * quantity), 0) as total_val FROM inventory_items GROUP BY category ORDER BY count DESC"
-    ).map_err(|e| e.to_string())?;
+fn load_category_stats(state: &AppState) -> Result<Vec<CategoryStats>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    inventory::get_category_stats(&conn).map_err(|e| e.to_string())
+}
 
-    let stats = stmt.query_map([], |row| {
-        Ok(CategoryStats {
-            category: row.get(0)?,
-            count: row.get(1)?,
-            total_quantity: row.get(2)?,
-            total_value: row.get(3)?,
-        })
-    }).map_err(|e| e.to_string())?;
+/// Cache hit/miss/eviction counters, to tune the TTLs
+#[tauri::command]
+pub fn get_cache_stats(state: State<AppState>) -> Result<CacheStats, String> {
+    Ok(cache::lock(&state.cache).stats())
+}
 
-    stats.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+#[tauri::command]
+pub fn reset_cache_stats(state: State<AppState>) -> Result<(), String> {
+    cache::lock(&state.cache).reset_stats();
+    Ok(())
 }
 
 // ==================== ACTIVITY LOG COMMANDS ====================
@@ -408,7 +584,7 @@ pub fn log_activity(
     description: String,
     item_id: Option<String>,
     item_name: Option<String>,
-    quantity_change: Option<i32>,
+    quantity_change: Option<f64>,
     value: Option<f64>,
 ) -> Result<(), String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
@@ -429,7 +605,7 @@ pub fn export_to_csv(state: State<AppState>, file_path: String) -> Result<(), St
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, sku, name, category, quantity, location, price, cost_price, currency, last_updated FROM inventory_items"
+        "SELECT id, sku, name, category, quantity, location, price, cost_price, currency, last_updated FROM inventory_items WHERE deleted_at IS NULL"
     ).map_err(|e| e.to_string())?;
 
     let rows = stmt.query_map([], |row| {
@@ -438,7 +614,7 @@ pub fn export_to_csv(state: State<AppState>, file_path: String) -> Result<(), St
             row.get::<_, String>(1)?,
             row.get::<_, String>(2)?,
             row.get::<_, String>(3)?,
-            row.get::<_, i32>(4)?,
+            row.get::<_, f64>(4)?,
             row.get::<_, String>(5)?,
             row.get::<_, f64>(6)?,
             row.get::<_, f64>(7)?,
@@ -462,95 +638,95 @@ pub fn export_to_csv(state: State<AppState>, file_path: String) -> Result<(), St
     Ok(())
 }
 
+/// Export stock cards with inventory and lot status as "csv" or "json"
 #[tauri::command]
-pub fn import_from_csv(state: State<AppState>, file_path: String) -> Result<i32, String> {
-    let content = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
-    let lines: Vec<&str> = content.lines().collect();
-    
-    // Check if empty or just header
-    if lines.is_empty() {
-        return Ok(0);
-    }
-
+pub fn export_catalog(state: State<AppState>, file_path: String, format: String) -> Result<usize, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    let now = chrono::Utc::now().to_rfc3339();
-    let mut count = 0;
+    let rows = inventory::get_catalog_rows(&conn).map_err(|e| e.to_string())?;
 
-    // Start transaction
-    conn.execute("BEGIN TRANSACTION", []).map_err(|e| e.to_string())?;
+    let content = match format.to_lowercase().as_str() {
+        "csv" => inventory::catalog_to_csv(&rows).map_err(|e| e.to_string())?,
+        "json" => serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())?,
+        other => return Err(format!("Desteklenmeyen format: {} (csv veya json)", other)),
+    };
 
-    // Skip header line (index 0)
-    for line in lines.iter().skip(1) {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+    std::fs::write(&file_path, content).map_err(|e| e.to_string())?;
+    Ok(rows.len())
+}
 
-        let parts: Vec<&str> = line.split(';').collect();
-        if parts.len() < 3 {
-            continue; // Need at least SKU, Name
-        }
+/// Import a CSV export; `delimiter` defaults to `;` and quoted fields may contain it
+#[tauri::command]
+pub fn import_from_csv(
+    state: State<AppState>,
+    file_path: String,
+    delimiter: Option<String>,
+) -> Result<CsvImportResult, String> {
+    let delimiter = import_staging::parse_csv_delimiter(delimiter.as_deref()).map_err(|e| e.to_string())?;
+    let content = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let result = inventory::import_from_csv(&conn, &content, delimiter).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(result)
+}
 
-        // Expected format: ID;SKU;Name;Category;Quantity;Location;Price;CostPrice;Currency;LastUpdated
-        // We will be flexible. If importing from our own export, we have IDs. 
-        // If importing new data, we might not have IDs.
+/// Export inventory as .xlsx with the CSV export's columns. Returns the number of rows written.
+#[tauri::command]
+pub fn export_inventory_xlsx(state: State<AppState>, file_path: String) -> Result<usize, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    xlsx::export_inventory_xlsx(&conn, &file_path).map_err(|e| e.to_string())
+}
 
-        // Let's try to parse based on standard export format
-        let sku = parts.get(1).unwrap_or(&"").trim();
-        let name = parts.get(2).unwrap_or(&"").trim();
-        
-        if sku.is_empty() || name.is_empty() {
-            continue;
-        }
+/// Import the first sheet of an .xlsx file; columns are matched by header name
+#[tauri::command]
+pub fn import_inventory_xlsx(state: State<AppState>, file_path: String) -> Result<CsvImportResult, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let result = xlsx::import_inventory_xlsx(&conn, &file_path).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(result)
+}
 
-        let category = parts.get(3).unwrap_or(&"Genel").trim();
-        let quantity: i32 = parts.get(4).unwrap_or(&"0").trim().parse().unwrap_or(0);
-        let location = parts.get(5).unwrap_or(&"").trim();
-        let price: f64 = parts.get(6).unwrap_or(&"0.0").trim().replace(',', ".").parse().unwrap_or(0.0);
-        let cost_price: f64 = parts.get(7).unwrap_or(&"0.0").trim().replace(',', ".").parse().unwrap_or(0.0);
-        let currency = parts.get(8).unwrap_or(&"TL").trim();
-
-        // Check if item exists
-        let exists: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM inventory_items WHERE sku = ?1",
-            params![sku],
-            |row| row.get(0),
-        ).unwrap_or(0);
+/// Dry-run CSV import: rows are staged for review, live inventory is not touched
+#[tauri::command]
+pub fn stage_csv_import(
+    state: State<AppState>,
+    file_path: String,
+    delimiter: Option<String>,
+) -> Result<ImportStageSummary, String> {
+    let delimiter = import_staging::parse_csv_delimiter(delimiter.as_deref()).map_err(|e| e.to_string())?;
+    let content = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    import_staging::stage_csv_import(&mut conn, &content, delimiter).map_err(|e| e.to_string())
+}
 
-        if exists > 0 {
-            // Update
-             conn.execute(
-                "UPDATE inventory_items SET name = ?1, category = ?2, quantity = ?3, location = ?4, price = ?5, cost_price = ?6, currency = ?7, last_updated = ?8 WHERE sku = ?9",
-                params![name, category, quantity, location, price, cost_price, currency, &now, sku],
-            ).map_err(|e| e.to_string())?;
-        } else {
-            // Insert
-            let id = uuid::Uuid::new_v4().to_string();
-            conn.execute(
-                "INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-                params![
-                    &id,
-                    sku,
-                    name,
-                    category,
-                    quantity,
-                    location,
-                    price,
-                    cost_price,
-                    "", // No image
-                    "", // No description
-                    "[]", // Empty tags
-                    &now,
-                    currency,
-                ],
-            ).map_err(|e| e.to_string())?;
-        }
-        count += 1;
-    }
+/// Staged prices that moved more than `threshold_percent` from the previous price
+#[tauri::command]
+pub fn review_import_prices(
+    state: State<AppState>,
+    batch_id: String,
+    threshold_percent: f64,
+) -> Result<Vec<PriceOutlier>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    import_staging::review_import_prices(&conn, &batch_id, threshold_percent).map_err(|e| e.to_string())
+}
 
-    conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
+/// Apply a staged import; `excluded_skus` are the rows rejected during review
+#[tauri::command]
+pub fn commit_staged_import(
+    state: State<AppState>,
+    batch_id: String,
+    excluded_skus: Option<Vec<String>>,
+) -> Result<ImportCommitResult, String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let result = import_staging::commit_staged_import(&mut conn, &batch_id, &excluded_skus.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(result)
+}
 
-    Ok(count)
+#[tauri::command]
+pub fn discard_staged_import(state: State<AppState>, batch_id: String) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    import_staging::discard_staged_import(&conn, &batch_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -566,58 +742,79 @@ pub fn save_to_downloads(content: String, filename: String) -> Result<String, St
 
 // ==================== DATABASE MANAGEMENT COMMANDS ====================
 
+/// Takes a safety backup first and returns a message with its path
 #[tauri::command]
-pub fn clear_database(state: State<AppState>) -> Result<(), String> {
+pub fn clear_database(state: State<AppState>) -> Result<String, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let backup_path = backup::create_safety_backup(&conn, &state.db.get_db_path_string(), "clear_database")?;
 
-    conn.execute("DELETE FROM inventory_items", []).map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM transactions", []).map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM activity_log", []).map_err(|e| e.to_string())?;
+    inventory::clear_inventory(&conn).map_err(|e| e.to_string())?;
 
-    Ok(())
+    cache::lock(&state.cache).invalidate_all();
+    Ok(format!("Veritabanı temizlendi. Önceki veriler yedeklendi: {}", backup_path.display()))
 }
 
+/// Plaintext copy of the database: the export is meant to be imported on another install,
+/// which has its own encryption key
 #[tauri::command]
 pub fn export_database(state: State<AppState>, file_path: String) -> Result<(), String> {
-    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
     let db_path = state.db.get_db_path_string();
+    crate::database::encryption::export_plaintext_copy(std::path::Path::new(&db_path), std::path::Path::new(&file_path))
+        .map_err(|e| format!("Veritabani kopyalanamadi: {}", e))
+}
 
-    // Force WAL checkpoint to flush all data to main database file
-    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
-        .map_err(|e| format!("WAL checkpoint basarisiz: {}", e))?;
-
-    // Copy the database file to the specified path
-    std::fs::copy(&db_path, &file_path)
-        .map_err(|e| format!("Veritabani kopyalanamadi: {}", e))?;
-
-    Ok(())
+/// Schema version of a database file before importing it
+#[tauri::command]
+pub fn preview_database_import(file_path: String) -> Result<DatabaseSchemaInfo, String> {
+    if !std::path::Path::new(&file_path).exists() {
+        return Err("Kaynak dosya bulunamadi".to_string());
+    }
+    crate::database::inspect_database_schema(std::path::Path::new(&file_path)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn import_database(state: State<AppState>, file_path: String) -> Result<(), String> {
+pub fn import_database(state: State<AppState>, file_path: String) -> Result<DatabaseSchemaInfo, String> {
     // Verify the source file exists
     if !std::path::Path::new(&file_path).exists() {
         return Err("Kaynak dosya bulunamadi".to_string());
     }
 
     let db_path = state.db.get_db_path_string();
+    let backup_path = {
+        let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+        backup::create_safety_backup(&conn, &db_path, "import_database")?
+    };
 
-    // Open the source database
-    let source_conn = rusqlite::Connection::open(&file_path)
-        .map_err(|e| format!("Kaynak veritabani acilamadi: {}", e))?;
-
-    // Open direct mutable connection to destination (not through pool)
-    let mut dest_conn = rusqlite::Connection::open(&db_path)
-        .map_err(|e| format!("Hedef veritabani acilamadi: {}", e))?;
+    // SQLite backup API into the live file; fails with DatabaseLocked instead of hanging.
+    // Older exports are migrated first, exports from a newer version are refused.
+    let mut schema = crate::database::restore_database_from_file(std::path::Path::new(&file_path), &db_path)
+        .map_err(|e| e.to_string())?;
+    schema.backup_path = Some(backup_path.to_string_lossy().to_string());
+    cache::lock(&state.cache).invalidate_all();
+    Ok(schema)
+}
 
-    // Use SQLite backup API to restore into destination
-    let backup = rusqlite::backup::Backup::new(&source_conn, &mut dest_conn)
-        .map_err(|e| format!("Backup olusturulamadi: {}", e))?;
+/// Business tables as one readable, versioned JSON document (see `services::json_export`)
+#[tauri::command]
+pub fn export_json(state: State<AppState>, file_path: String) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let export = json_export::export_json(&conn).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    std::fs::write(&file_path, json).map_err(|e| format!("JSON dosyasi yazilamadi: {}", e))
+}
 
-    backup.run_to_completion(100, std::time::Duration::from_millis(50), None)
-        .map_err(|e| format!("Backup tamamlanamadi: {}", e))?;
+/// Load a JSON export into a database without business records (safety backup first); returns the rows written per table
+#[tauri::command]
+pub fn import_json(state: State<AppState>, file_path: String) -> Result<BTreeMap<String, usize>, String> {
+    let content = std::fs::read_to_string(&file_path).map_err(|e| format!("JSON dosyasi okunamadi: {}", e))?;
+    let export: json_export::JsonExport =
+        serde_json::from_str(&content).map_err(|e| format!("Gecersiz JSON dosyasi: {}", e))?;
 
-    Ok(())
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    backup::create_safety_backup(&conn, &state.db.get_db_path_string(), "import_json")?;
+    let imported = json_export::import_json(&conn, &export).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(imported)
 }
 
 #[tauri::command]
@@ -642,6 +839,7 @@ pub fn apply_price_change_by_category(
         ).map_err(|e| e.to_string())?;
     }
 
+    cache::lock(&state.cache).invalidate_all();
     Ok(())
 }
 
@@ -709,9 +907,12 @@ pub fn seed_database(_state: State<AppState>) -> Result<String, String> {
     Ok("Test verisi oluşturma devre dışı bırakıldı.".to_string())
 }
 
+/// Requires a valid admin session
 #[tauri::command]
-pub fn factory_reset(state: State<AppState>, admin_password: Option<String>) -> Result<String, String> {
+pub fn factory_reset(state: State<AppState>, admin_password: Option<String>, session_token: String) -> Result<String, String> {
     let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    session::require_admin_session(&conn, &session_token, chrono::Utc::now()).map_err(|e| e.to_string())?;
+    let backup_path = backup::create_safety_backup(&conn, &state.db.get_db_path_string(), "factory_reset")?;
     
     // Start transaction
     let tx = conn.transaction().map_err(|e| e.to_string())?;
@@ -720,16 +921,23 @@ pub fn factory_reset(state: State<AppState>, admin_password: Option<String>) ->
     tx.execute("DELETE FROM transactions", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM inventory_items", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM inventory_lots", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM sale_lot_consumptions", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM product_components", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM item_attributes", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM current_accounts", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM stock_cards", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM sku_counters", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM categories", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM expense_categories", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM recurring_expenses", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM finance_records", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM activity_log", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM access_codes", []).map_err(|e| e.to_string())?;
     
     // Clear Users
     tx.execute("DELETE FROM users", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM sessions", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM password_history", []).map_err(|e| e.to_string())?;
     
     // Re-create Admin User
     let admin_id = uuid::Uuid::new_v4().to_string();
@@ -750,88 +958,59 @@ pub fn factory_reset(state: State<AppState>, admin_password: Option<String>) ->
 
     tx.commit().map_err(|e| e.to_string())?;
 
-    Ok("Sistem başarıyla sıfırlandı.".to_string())
+    cache::lock(&state.cache).invalidate_all();
+    Ok(format!("Sistem başarıyla sıfırlandı. Önceki veriler yedeklendi: {}", backup_path.display()))
 }
 
 // ==================== USER AUTHENTICATION COMMANDS ====================
 
-// Rate limiting sabitleri
-#[allow(dead_code)]
-const MAX_LOGIN_ATTEMPTS: i32 = 5;
-#[allow(dead_code)]
-const LOCKOUT_DURATION_MINUTES: i64 = 15;
-
-#[tauri::command]
-pub fn login(state: State<AppState>, username: String, password: String) -> Result<User, String> {
-    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-
-    // Kullanıcıyı ara (rate limiting alanları dahil)
-    let user_result: Result<(String, String, String, String, String, String, Option<String>, i32, Option<String>, bool), _> = conn.query_row(
-        "SELECT id, username, password_hash, display_name, role, created_at, last_login, failed_login_attempts, locked_until, must_change_password FROM users WHERE username = ?1",
-        params![&username],
-        |row| {
-            Ok((
-                row.get(0)?,
-                row.get(1)?,
-                row.get(2)?,
-                row.get(3)?,
-                row.get(4)?,
-                row.get(5)?,
-                row.get(6)?,
-                row.get::<_, i32>(7).unwrap_or(0),
-                row.get(8)?,
-                row.get::<_, bool>(9).unwrap_or(false),
-            ))
-        },
-    );
+/// Returns the user with a session token for later commands
+#[tauri::command]
+pub fn login(state: State<AppState>, username: String, password: String) -> Result<AuthSession, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now();
 
-    match user_result {
-        Ok((id, uname, password_hash, display_name, role, created_at, _, _failed_attempts, _locked_until, must_change_pwd)) => {
-            let now = chrono::Utc::now();
-            
-            // Hesap kilitleme kaldirildi (sonsuz deneme hakki)
-
-            // Şifreyi doğrula
-            // Admin için şifre ZORUNLU, diğer kullanıcılar şifresiz girebilir
-            let is_valid = if role == "admin" {
-                // Admin için şifre zorunlu - boş şifre kabul edilmez
-                if password.is_empty() {
-                    return Err("Yönetici hesabı için şifre zorunludur".to_string());
-                }
-                verify_password(&password, &password_hash).unwrap_or(false)
-            } else {
-                // Diğer kullanıcılar şifresiz girebilir
-                if password.is_empty() {
-                    true
-                } else {
-                    verify_password(&password, &password_hash).unwrap_or(false)
-                }
-            };
+    let user = login::authenticate(&conn, &username, &password, now).map_err(|e| match e {
+        // Login screen messages are shown as they are
+        crate::error::AppError::Auth(message) => message,
+        e => e.to_string(),
+    })?;
+    session::create_session(&conn, user, now).map_err(|e| e.to_string())
+}
 
-            if !is_valid {
-                // Return generic error without locking
-                return Err("Hatali sifre".to_string());
-            }
+/// User of a live session (the idle timeout restarts), None when it expired or was revoked
+#[tauri::command]
+pub fn validate_session(state: State<AppState>, token: String) -> Result<Option<User>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    session::validate_session(&conn, &token, chrono::Utc::now()).map_err(|e| e.to_string())
+}
 
-            // Basarili giris - sayaçları sıfırla
-            conn.execute(
-                "UPDATE users SET failed_login_attempts = 0, locked_until = NULL, last_login = ?1 WHERE id = ?2",
-                params![now.to_rfc3339(), &id],
-            ).map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn logout(state: State<AppState>, token: String) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let user = session::validate_session(&conn, &token, chrono::Utc::now()).map_err(|e| e.to_string())?;
+    session::revoke_session(&conn, &token).map_err(|e| e.to_string())?;
+    auth_events::record_auth_event(
+        &conn,
+        auth_events::ACTION_LOGOUT,
+        user.as_ref().map_or("", |u| u.username.as_str()),
+        user.as_ref().map(|u| u.id.as_str()),
+        user.is_some(),
+        if user.is_some() { None } else { Some("Oturum bulunamadı") },
+    )
+    .map_err(|e| e.to_string())
+}
 
-            Ok(User {
-                id,
-                username: uname,
-                display_name,
-                role,
-                created_at,
-                last_login: Some(now.to_rfc3339()),
-                must_change_password: must_change_pwd,
-            })
-        }
-        Err(rusqlite::Error::QueryReturnedNoRows) => Err("Kullanıcı bulunamadı".to_string()),
-        Err(e) => Err(format!("Veritabanı hatası: {}", e)),
-    }
+/// Logins, logouts and admin password checks, newest first (dates YYYY-MM-DD, inclusive)
+#[tauri::command]
+pub fn get_auth_events(
+    state: State<AppState>,
+    limit: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<AuthEvent>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    auth_events::get_auth_events(&conn, limit, start_date.as_deref(), end_date.as_deref()).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -848,10 +1027,64 @@ pub fn get_users_for_login(state: State<AppState>) -> Result<Vec<crate::models::
             username: row.get(1)?,
             display_name: row.get(2)?,
             role: row.get(3)?,
+            password_required: false,
         })
     }).map_err(|e| e.to_string())?;
 
-    users.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    let mut users = users.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
+    for user in users.iter_mut() {
+        user.password_required = login_policy::is_password_required(&conn, &user.role).map_err(|e| e.to_string())?;
+    }
+
+    Ok(users)
+}
+
+/// Roles that must enter a password on username/password login
+#[tauri::command]
+pub fn get_login_policy(state: State<AppState>) -> Result<Vec<String>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    login_policy::get_password_required_roles(&conn).map_err(|e| e.to_string())
+}
+
+/// Change require_password_for_roles (admin credentials required, logged to activity_log)
+#[tauri::command]
+pub fn set_login_policy(
+    state: State<AppState>,
+    admin_username: String,
+    admin_password: String,
+    roles: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+
+    // Same attempt counting and lock as the login screen
+    let admin_id = login::verify_admin(
+        &conn,
+        &admin_username,
+        &admin_password,
+        auth_events::ACTION_SET_LOGIN_POLICY,
+        chrono::Utc::now(),
+    )
+    .map_err(|e| e.to_string())?;
+
+    let previous = login_policy::get_password_required_roles(&conn).map_err(|e| e.to_string())?;
+    let stored = login_policy::set_password_required_roles(&conn, &roles).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO activity_log (action_type, description, user_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            "SECURITY_POLICY",
+            format!(
+                "{}: şifre zorunlu roller [{}] -> [{}]",
+                admin_username,
+                previous.join(", "),
+                stored.join(", ")
+            ),
+            &admin_id,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(stored)
 }
 
 #[tauri::command]
@@ -967,9 +1200,11 @@ pub fn update_user(state: State<AppState>, id: String, updates: UpdateUserReques
     Ok(())
 }
 
+/// Requires a valid admin session; the deleted user's sessions end with it
 #[tauri::command]
-pub fn delete_user(state: State<AppState>, id: String) -> Result<(), String> {
+pub fn delete_user(state: State<AppState>, id: String, session_token: String) -> Result<(), String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    session::require_admin_session(&conn, &session_token, chrono::Utc::now()).map_err(|e| e.to_string())?;
 
     let admin_count: i32 = conn.query_row(
         "SELECT COUNT(*) FROM users WHERE role = 'admin'",
@@ -989,6 +1224,9 @@ pub fn delete_user(state: State<AppState>, id: String) -> Result<(), String> {
 
     conn.execute("DELETE FROM users WHERE id = ?1", params![&id])
         .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM password_history WHERE user_id = ?1", params![&id])
+        .map_err(|e| e.to_string())?;
+    session::revoke_user_sessions(&conn, &id).map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -999,14 +1237,8 @@ pub fn change_password(state: State<AppState>, id: String, new_password: String)
     validate_password_strength(&new_password)?;
 
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    let password_hash = hash_password(&new_password).map_err(|e| e.to_string())?;
-
-    conn.execute(
-        "UPDATE users SET password_hash = ?1 WHERE id = ?2",
-        params![&password_hash, &id],
-    ).map_err(|e| e.to_string())?;
-
-    Ok(())
+    // Rejects the current and recently used passwords
+    password_history::change_password(&conn, &id, &new_password).map_err(|e| e.to_string())
 }
 
 // ==================== PAGINATION COMMANDS ====================
@@ -1018,96 +1250,20 @@ pub fn get_items_paginated(
     per_page: i32,
     search: Option<String>,
     category: Option<String>,
+    attributes: Option<HashMap<String, String>>,
+    sort_by: Option<String>,
+    sort_dir: Option<String>,
 ) -> Result<PaginatedItemsResponse, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
-    let page = if page < 1 { 1 } else { page };
-    let per_page = if per_page < 1 { 20 } else { per_page.min(100) };
-    let offset = (page - 1) * per_page;
-
-    // Build WHERE clause
-    let mut conditions = Vec::new();
-    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-    if let Some(ref s) = search {
-        if !s.is_empty() {
-            conditions.push("(name LIKE ? OR sku LIKE ? OR description LIKE ?)");
-            let search_pattern = format!("%{}%", s);
-            params_vec.push(Box::new(search_pattern.clone()));
-            params_vec.push(Box::new(search_pattern.clone()));
-            params_vec.push(Box::new(search_pattern));
-        }
-    }
-
-    if let Some(ref c) = category {
-        if !c.is_empty() && c != "Tümü" && c != "HEPSİ" {
-            conditions.push("category = ?");
-            params_vec.push(Box::new(c.clone()));
-        }
-    }
-
-    let where_clause = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", conditions.join(" AND "))
-    };
-
-    // Get total count
-    let count_sql = format!("SELECT COUNT(*) FROM inventory_items {}", where_clause);
-    let total: i32 = {
-        let mut stmt = conn.prepare(&count_sql).map_err(|e| e.to_string())?;
-        // Use params_from_iter for cleaner code if possible, or manual slice
-        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
-        stmt.query_row(params_refs.as_slice(), |row| row.get(0)).unwrap_or(0)
+    let filter = inventory::ItemFilter {
+        search,
+        category,
+        attributes,
+        sort_by,
+        sort_dir,
     };
-
-    // Get paginated items
-    let select_sql = format!(
-        "SELECT id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency, supplier_id, brand
-         FROM inventory_items {} ORDER BY name LIMIT ? OFFSET ?",
-        where_clause
-    );
-
-    let mut params_with_pagination = params_vec;
-    params_with_pagination.push(Box::new(per_page));
-    params_with_pagination.push(Box::new(offset));
-
-    let mut stmt = conn.prepare(&select_sql).map_err(|e| e.to_string())?;
-    let params_refs: Vec<&dyn rusqlite::ToSql> = params_with_pagination.iter().map(|p| p.as_ref()).collect();
-
-    let items = stmt.query_map(params_refs.as_slice(), |row| {
-        let ai_tags_str: Option<String> = row.get(10)?;
-        let ai_tags: Option<Vec<String>> = ai_tags_str.and_then(|s| serde_json::from_str(&s).ok());
-
-        Ok(InventoryItem {
-            id: row.get(0)?,
-            sku: row.get(1)?,
-            name: row.get(2)?,
-            category: row.get(3)?,
-            quantity: row.get(4)?,
-            location: row.get(5)?,
-            price: row.get(6)?,
-            cost_price: row.get(7)?,
-            image: row.get(8)?,
-            description: row.get(9)?,
-            ai_tags,
-            last_updated: row.get(11)?,
-            currency: row.get(12)?,
-            supplier_id: row.get(13)?,
-            brand: row.get(14).unwrap_or(None),
-        })
-    }).map_err(|e| e.to_string())?;
-
-    let data: Vec<InventoryItem> = items.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
-    let total_pages = ((total as f64) / (per_page as f64)).ceil() as i32;
-
-    Ok(PaginatedItemsResponse {
-        data,
-        total,
-        page,
-        per_page,
-        total_pages,
-    })
+    inventory::get_items_paginated(&conn, page, per_page, &filter).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -1119,103 +1275,25 @@ pub fn get_transactions_with_pagination(
     end_date: Option<String>,
     transaction_type: Option<String>,
     customer_id: Option<String>,
+    item_sku: Option<String>,
+    sort_by: Option<String>,
+    sort_dir: Option<String>,
 ) -> Result<PaginatedTransactionsResponse, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     // Debug print
     println!("Fetching transactions: page={}, type={:?}, customer={:?}", page, transaction_type, customer_id);
 
-    let page = if page < 1 { 1 } else { page };
-    let per_page = if per_page < 1 { 20 } else { per_page.min(100) };
-    let offset = (page - 1) * per_page;
-
-    // Build WHERE clause
-    let mut conditions = Vec::new();
-    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-    if let Some(ref sd) = start_date {
-        if !sd.is_empty() {
-            conditions.push("substr(created_at, 1, 10) >= ?");
-            params_vec.push(Box::new(sd.clone()));
-        }
-    }
-
-    if let Some(ref ed) = end_date {
-        if !ed.is_empty() {
-            conditions.push("substr(created_at, 1, 10) <= ?");
-            params_vec.push(Box::new(ed.clone()));
-        }
-    }
-
-    if let Some(ref tt) = transaction_type {
-        if !tt.is_empty() && tt != "ALL" {
-            conditions.push("transaction_type = ?");
-            params_vec.push(Box::new(tt.clone()));
-        }
-    }
-
-    if let Some(ref cid) = customer_id {
-        if !cid.is_empty() {
-             conditions.push("customer_id = ?");
-             params_vec.push(Box::new(cid.clone()));
-        }
-    }
-
-    let where_clause = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", conditions.join(" AND "))
-    };
-
-    // Get total count
-    let count_sql = format!("SELECT COUNT(*) FROM transactions {}", where_clause);
-    let total: i32 = {
-        let mut stmt = conn.prepare(&count_sql).map_err(|e| e.to_string())?;
-        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
-        stmt.query_row(params_refs.as_slice(), |row| row.get(0)).unwrap_or(0)
+    let filter = inventory::TransactionFilter {
+        start_date,
+        end_date,
+        transaction_type,
+        customer_id,
+        item_sku,
+        sort_by,
+        sort_dir,
     };
-
-    // Get paginated transactions
-    let select_sql = format!(
-        "SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id
-         FROM transactions {} ORDER BY created_at DESC LIMIT ? OFFSET ?",
-        where_clause
-    );
-
-    let mut params_with_pagination = params_vec;
-    params_with_pagination.push(Box::new(per_page));
-    params_with_pagination.push(Box::new(offset));
-
-    let mut stmt = conn.prepare(&select_sql).map_err(|e| e.to_string())?;
-    let params_refs: Vec<&dyn rusqlite::ToSql> = params_with_pagination.iter().map(|p| p.as_ref()).collect();
-
-    let transactions = stmt.query_map(params_refs.as_slice(), |row| {
-        let items_json: String = row.get(1)?;
-        let items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
-
-        Ok(Transaction {
-            id: row.get(0)?,
-            items,
-            total: row.get(2)?,
-            payment_method: row.get(3)?,
-            transaction_type: row.get(4)?,
-            status: "completed".to_string(),
-            note: row.get(5)?,
-            created_at: row.get(6)?,
-            customer_id: row.get(7).unwrap_or(None),
-        })
-    }).map_err(|e| e.to_string())?;
-
-    let data: Vec<Transaction> = transactions.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
-    let total_pages = ((total as f64) / (per_page as f64)).ceil() as i32;
-
-    Ok(PaginatedTransactionsResponse {
-        data,
-        total,
-        page,
-        per_page,
-        total_pages,
-    })
+    inventory::get_transactions_paginated(&conn, page, per_page, &filter).map_err(|e| e.to_string())
 }
 
 // ==================== LICENSE COMMANDS ====================
@@ -1280,28 +1358,15 @@ pub fn activate_license(
         license::save_license(&conn, &license_data).map_err(|e| e.to_string())?;
 
         // Create default admin user for this dealer if no users exist
-        let user_count: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM users",
-            [],
-            |row| row.get(0),
-        ).unwrap_or(0);
+        let dealer_name = final_response.dealer_name.clone().unwrap_or("Admin".to_string());
+        let dealer_id = final_response.dealer_id.clone().unwrap_or_default();
+        let temp_password = admin::create_initial_admin(&conn, &dealer_name, Some(&dealer_id))
+            .map_err(|e| e.to_string())?;
 
-        if user_count == 0 {
-            let admin_id = uuid::Uuid::new_v4().to_string();
-            // Generate random password (first 8 chars of a UUID)
-            let random_pwd = uuid::Uuid::new_v4().to_string().chars().take(8).collect::<String>();
-            let password_hash = hash_password(&random_pwd).map_err(|e| e.to_string())?;
-            let dealer_name = final_response.dealer_name.clone().unwrap_or("Admin".to_string());
-            let dealer_id = final_response.dealer_id.clone().unwrap_or_default();
-
-            conn.execute(
-                "INSERT INTO users (id, username, password_hash, display_name, role, created_at, dealer_id, must_change_password) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1)",
-                params![&admin_id, "admin", &password_hash, &dealer_name, "admin", &now, &dealer_id],
-            ).map_err(|e| e.to_string())?;
-            
+        if let Some(temp_password) = temp_password {
             final_response.message = Some(format!(
                 "Lisans başarıyla aktifleştirildi. Geçici Yönetici Şifreniz: {} (Lütfen hemen değiştirin)", 
-                random_pwd
+                temp_password
             ));
         }
     }
@@ -1309,52 +1374,62 @@ pub fn activate_license(
     Ok(final_response)
 }
 
+/// Local license check: expiry, periodic online revalidation and the offline grace window
 #[tauri::command]
-pub fn check_license_validity(state: State<AppState>) -> Result<bool, String> {
+pub fn check_license_validity(state: State<AppState>) -> Result<LicenseCheckResult, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    license::check_license(&conn, chrono::Utc::now(), |lic| {
+        license::validate_license_api(&lic.api_base_url, &lic.license_key, &lic.mac_address)
+    })
+    .map_err(|e| e.to_string())
+}
 
-    match license::get_local_license(&conn).map_err(|e| e.to_string())? {
-        Some(lic) => {
-            // Check if expired
-            if license::is_license_expired(&lic) {
-                return Ok(false);
-            }
+#[tauri::command]
+pub fn get_license_policy(state: State<AppState>) -> Result<LicensePolicy, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    license::load_license_policy(&conn).map_err(|e| e.to_string())
+}
 
-            // Check if needs revalidation (every 7 days)
-            if license::needs_revalidation(&lic) {
-                // Try to revalidate with API
-                match license::validate_license_api(&lic.api_base_url, &lic.license_key, &lic.mac_address) {
-                    Ok(response) => {
-                        if response.valid {
-                            let _ = license::update_last_validated(&conn);
-                            Ok(true)
-                        } else {
-                            Ok(false)
-                        }
-                    }
-                    Err(_) => {
-                        // API unreachable - allow offline usage if not expired
-                        Ok(lic.is_active && !license::is_license_expired(&lic))
-                    }
-                }
-            } else {
-                Ok(lic.is_active)
-            }
-        }
-        None => Ok(false), // No license found
-    }
+#[tauri::command]
+pub fn set_license_policy(state: State<AppState>, policy: LicensePolicy) -> Result<LicensePolicy, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    license::save_license_policy(&conn, &policy).map_err(|e| e.to_string())
 }
 
+/// Free the server activation seat, then delete the local license.
+/// The local license is deleted even when the server cannot release the seat; the returned
+/// warning then tells the user to have the dealer free it.
 #[tauri::command]
-pub fn deactivate_license(state: State<AppState>) -> Result<(), String> {
+pub fn deactivate_license(state: State<AppState>) -> Result<Option<String>, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    license::delete_license(&conn).map_err(|e| e.to_string())
+    let local = license::get_local_license(&conn).map_err(|e| e.to_string())?;
+
+    let warning = local.and_then(|lic| {
+        match license::deactivate_license_api(&lic.api_base_url, &lic.license_key, &lic.mac_address) {
+            Ok(response) if response.success => None,
+            // Never activated on the server: no seat to free
+            Ok(response) if response.error.as_deref() == Some("NOT_ACTIVATED") => None,
+            Ok(response) => Some(format!(
+                "Lisans sunucuda serbest birakilamadi ({}). Yeni cihaz icin bayinizle iletisime gecin.",
+                response.message.or(response.error).unwrap_or_default()
+            )),
+            Err(e) => Some(format!(
+                "Lisans sunucusuna ulasilamadi ({}). Yeni cihaz icin bayinizle iletisime gecin.",
+                e
+            )),
+        }
+    });
+
+    license::delete_license(&conn).map_err(|e| e.to_string())?;
+    Ok(warning)
 }
 
 // ==================== CLOUD SYNC COMMANDS ====================
 
+/// Start a cloud backup in the background and return its job id.
+/// Progress and the result are sent as `cloud-backup-progress` / `-complete` / `-error` events.
 #[tauri::command]
-pub fn cloud_backup(state: State<AppState>) -> Result<CloudBackupResponse, String> {
+pub fn cloud_backup(app: tauri::AppHandle, state: State<AppState>) -> Result<String, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     // Get license info for API auth
@@ -1364,25 +1439,26 @@ pub fn cloud_backup(state: State<AppState>) -> Result<CloudBackupResponse, Strin
 
     let db_path = state.db.get_db_path_string();
 
-    // Perform backup (with WAL checkpoint)
-    let response = cloud::backup_to_cloud(
+    // WAL checkpoint runs here; the upload and last backup update run on the job thread
+    cloud::start_cloud_backup_job(
         &conn,
-        &license_data.api_base_url,
-        &license_data.dealer_id,
-        &license_data.license_key,
-        &db_path,
-    ).map_err(|e| e.to_string())?;
-
-    if response.success {
-        // Update local sync status
-        cloud::update_last_backup(&conn).map_err(|e| e.to_string())?;
-    }
-
-    Ok(response)
+        db_path,
+        license_data.api_base_url,
+        license_data.dealer_id,
+        license_data.license_key,
+        move |event| {
+            let _ = match event {
+                cloud::CloudBackupEvent::Progress(p) => app.emit(cloud::backup_job::CLOUD_BACKUP_PROGRESS_EVENT, p),
+                cloud::CloudBackupEvent::Complete(c) => app.emit(cloud::backup_job::CLOUD_BACKUP_COMPLETE_EVENT, c),
+                cloud::CloudBackupEvent::Error(e) => app.emit(cloud::backup_job::CLOUD_BACKUP_ERROR_EVENT, e),
+            };
+        },
+    )
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn cloud_restore(state: State<AppState>) -> Result<(), String> {
+pub fn cloud_restore(state: State<AppState>, force: Option<bool>) -> Result<cloud::CloudRestoreResult, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     // Get license info for API auth
@@ -1392,18 +1468,23 @@ pub fn cloud_restore(state: State<AppState>) -> Result<(), String> {
 
     let db_path = state.db.get_db_path_string();
 
-    // Perform restore using SQLite backup API (no restart needed)
-    cloud::restore_from_cloud_with_conn(
+    // Perform restore using SQLite backup API (no restart needed).
+    // Older or smaller backups are only restored with force=true.
+    let result = cloud::restore_from_cloud_with_conn(
         &db_path,
         &license_data.api_base_url,
         &license_data.dealer_id,
         &license_data.license_key,
+        force.unwrap_or(false),
     ).map_err(|e| e.to_string())?;
 
     // Update last restore timestamp
-    cloud::update_last_restore(&conn).map_err(|e| e.to_string())?;
+    if result.restored {
+        cloud::update_last_restore(&conn).map_err(|e| e.to_string())?;
+        cache::lock(&state.cache).invalidate_all();
+    }
 
-    Ok(())
+    Ok(result)
 }
 
 #[tauri::command]
@@ -1413,30 +1494,120 @@ pub fn get_sync_status(state: State<AppState>) -> Result<SyncStatus, String> {
 }
 
 #[tauri::command]
-pub fn get_cloud_status(state: State<AppState>) -> Result<CloudStatusResponse, String> {
+pub fn get_cloud_status(state: State<AppState>) -> Result<CloudStatusResponse, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+
+    let license_data = license::get_local_license(&conn)
+        .map_err(|e| e.to_string())?
+        .ok_or("Lisans bulunamadi")?;
+
+    cloud::get_cloud_status(
+        &license_data.api_base_url,
+        &license_data.dealer_id,
+        &license_data.license_key,
+    ).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_auto_sync(
+    state: State<AppState>,
+    enabled: bool,
+    interval_minutes: i32,
+    check_server: Option<bool>,
+) -> Result<AutoSyncSettingsResult, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let interval = cloud::clamp_auto_sync_interval(interval_minutes); // 5 min to 24 hours
+    let mut warnings = Vec::new();
+
+    if interval != interval_minutes {
+        warnings.push(format!(
+            "Senkron araligi {} dakika olarak ayarlandi (izin verilen: 5-1440 dakika)",
+            interval
+        ));
+    }
+
+    // Reachability is only a warning: the scheduler retries on its own
+    let mut server_reachable = None;
+    if enabled && check_server.unwrap_or(true) {
+        match license::get_local_license(&conn).map_err(|e| e.to_string())? {
+            Some(license_data) => {
+                let result = cloud::check_server_health(&license_data.api_base_url);
+                if let Err(e) = &result {
+                    warnings.push(format!("Senkron sunucusuna ulasilamadi, ilk yedek basarisiz olabilir: {}", e));
+                }
+                server_reachable = Some(result.is_ok());
+            }
+            None => warnings.push("Lisans bulunamadi, otomatik yedekleme lisans aktivasyonundan sonra baslar".to_string()),
+        }
+    }
+
+    let was_enabled = cloud::get_local_sync_status(&conn)
+        .map(|s| s.auto_sync_enabled)
+        .unwrap_or(false);
+    cloud::set_auto_sync_settings(&conn, enabled, interval).map_err(|e| e.to_string())?;
+
+    // Enabling runs the first backup right away instead of after a full interval
+    let first_sync_scheduled = enabled && !was_enabled;
+    if first_sync_scheduled {
+        cloud::start_auto_sync_scheduler(state.db.get_db_path_string());
+        cloud::request_immediate_sync();
+    }
+
+    let status = cloud::get_local_sync_status(&conn).map_err(|e| e.to_string())?;
+
+    Ok(AutoSyncSettingsResult {
+        status,
+        requested_interval_minutes: interval_minutes,
+        effective_interval_minutes: interval,
+        interval_clamped: interval != interval_minutes,
+        server_reachable,
+        first_sync_scheduled,
+        warnings,
+    })
+}
+
+/// Retries after the first attempt for license/cloud HTTP calls
+#[tauri::command]
+pub fn get_http_retry_count() -> u32 {
+    http_service::get_http_max_retries()
+}
+
+#[tauri::command]
+pub fn set_http_retry_count(state: State<AppState>, retries: u32) -> Result<u32, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    http_service::save_http_max_retries(&conn, retries).map_err(|e| e.to_string())
+}
+
+/// Wait before the first retry in milliseconds (doubled each retry, with jitter)
+#[tauri::command]
+pub fn get_http_retry_backoff_ms() -> u64 {
+    http_service::get_http_retry_backoff_ms()
+}
+
+#[tauri::command]
+pub fn set_http_retry_backoff_ms(state: State<AppState>, backoff_ms: u64) -> Result<u64, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    http_service::save_http_retry_backoff_ms(&conn, backoff_ms).map_err(|e| e.to_string())
+}
+
+// ==================== SETTINGS COMMANDS ====================
+
+#[tauri::command]
+pub fn get_setting(state: State<AppState>, key: String) -> Result<Option<String>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    settings::get_setting(&conn, &key).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_setting(state: State<AppState>, key: String, value: String) -> Result<(), String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-
-    let license_data = license::get_local_license(&conn)
-        .map_err(|e| e.to_string())?
-        .ok_or("Lisans bulunamadi")?;
-
-    cloud::get_cloud_status(
-        &license_data.api_base_url,
-        &license_data.dealer_id,
-        &license_data.license_key,
-    ).map_err(|e| e.to_string())
+    settings::set_setting(&conn, &key, &value).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn set_auto_sync(
-    state: State<AppState>,
-    enabled: bool,
-    interval_minutes: i32,
-) -> Result<(), String> {
+pub fn get_all_settings(state: State<AppState>) -> Result<HashMap<String, String>, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    let interval = if interval_minutes < 5 { 5 } else { interval_minutes.min(1440) }; // 5 min to 24 hours
-
-    cloud::set_auto_sync_settings(&conn, enabled, interval).map_err(|e| e.to_string())
+    settings::get_all_settings(&conn).map_err(|e| e.to_string())
 }
 
 // ==================== LOCAL BACKUP COMMANDS ====================
@@ -1457,15 +1628,21 @@ pub fn create_local_backup(state: State<AppState>) -> Result<BackupInfo, String>
     })
 }
 
+/// `safety_only` lists just the backups taken before destructive operations
 #[tauri::command]
-pub fn list_local_backups() -> Result<Vec<BackupInfo>, String> {
+pub fn list_local_backups(safety_only: Option<bool>) -> Result<Vec<BackupInfo>, String> {
+    if safety_only.unwrap_or(false) {
+        return backup::list_safety_backups();
+    }
     backup::list_backups()
 }
 
 #[tauri::command]
 pub fn restore_local_backup(state: State<AppState>, backup_path: String) -> Result<(), String> {
     let db_path = state.db.get_db_path_string();
-    backup::restore_backup(&backup_path, &db_path)
+    backup::restore_backup(&backup_path, &db_path)?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(())
 }
 
 #[tauri::command]
@@ -1487,6 +1664,98 @@ pub fn stop_auto_backup() -> Result<(), String> {
     Ok(())
 }
 
+/// Write the inventory and the day's transactions as CSV into `dir` every day at `hour:minute`.
+/// The schedule is saved and resumed on the next start.
+#[tauri::command]
+pub fn start_scheduled_export(state: State<AppState>, dir: String, hour: u32, minute: u32) -> Result<(), String> {
+    let at = scheduled_export::validate_export_time(hour, minute).map_err(|e| e.to_string())?;
+    let dir = dir.trim().to_string();
+    if dir.is_empty() {
+        return Err("Dışa aktarım klasörü seçilmedi".to_string());
+    }
+    // A folder that cannot be created now is logged; the scheduler retries it at every run
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        eprintln!("[Export] Klasör oluşturulamadı ({}): {}", dir, e);
+    }
+
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    settings::set_setting(&conn, scheduled_export::EXPORT_DIR_SETTING, &dir).map_err(|e| e.to_string())?;
+    settings::set_setting(&conn, scheduled_export::EXPORT_TIME_SETTING, &at.format("%H:%M").to_string())
+        .map_err(|e| e.to_string())?;
+    scheduled_export::start_export_scheduler(state.db.get_db_path_string(), std::path::PathBuf::from(dir), at);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_scheduled_export(state: State<AppState>) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    settings::set_setting(&conn, scheduled_export::EXPORT_DIR_SETTING, "").map_err(|e| e.to_string())?;
+    scheduled_export::stop_export_scheduler();
+    Ok(())
+}
+
+/// Scheduled database health checks, newest first
+#[tauri::command]
+pub fn get_health_history(state: State<AppState>, limit: Option<i64>) -> Result<Vec<DbHealthSnapshot>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    health::get_health_history(&conn, limit.unwrap_or(50)).map_err(|e| e.to_string())
+}
+
+/// Run a health check now (same checks as the backup schedule)
+#[tauri::command]
+pub fn run_health_check(state: State<AppState>) -> Result<DbHealthSnapshot, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    health::run_health_check(&conn, &state.db.get_db_path_string()).map_err(|e| e.to_string())
+}
+
+/// Integrity and foreign key check on demand, e.g. after a crash
+#[tauri::command]
+pub fn check_database_integrity(state: State<AppState>) -> Result<IntegrityReport, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    health::check_database_integrity(&conn).map_err(|e| e.to_string())
+}
+
+/// Reclaim free space with VACUUM and report the file size before and after
+#[tauri::command]
+pub fn vacuum_database(state: State<AppState>) -> Result<VacuumReport, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    health::vacuum_database(&conn, &state.db.get_db_path_string()).map_err(|e| e.to_string())
+}
+
+/// Where scheduled backups are written (secret keys are never returned)
+#[tauri::command]
+pub fn get_backup_target(state: State<AppState>) -> Result<BackupTargetConfig, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    backup_target::get_backup_target_config(&conn)
+        .map(|config| config.masked())
+        .map_err(|e| e.to_string())
+}
+
+/// Set the backup destination; the target is checked for access before it is saved
+#[tauri::command]
+pub fn configure_backup_target(state: State<AppState>, config: BackupTargetConfig) -> Result<BackupTargetConfig, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    backup_target::configure_backup_target(&conn, config).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_target_backups(state: State<AppState>) -> Result<Vec<BackupInfo>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let config = backup_target::get_backup_target_config(&conn).map_err(|e| e.to_string())?;
+    backup_target::open_backup_target(&conn, &config)
+        .and_then(|target| target.list())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_target_backup(state: State<AppState>, backup_id: String) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let db_path = state.db.get_db_path_string();
+    backup_target::restore_from_configured_target(&conn, &db_path, &backup_id).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(())
+}
+
 // ==================== STARTUP COMMANDS ====================
 
 #[tauri::command]
@@ -1501,14 +1770,92 @@ pub fn get_windows_startup_status() -> bool {
 
 // ==================== PRINT COMMANDS ====================
 
+/// Empty currency / store name are taken from settings
+#[tauri::command]
+pub fn generate_receipt(state: State<AppState>, transaction: Transaction, currency: String, store_name: String, config: Option<print_service::PrintConfig>) -> Result<String, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let currency = settings::value_or_setting(&conn, currency, settings::CURRENCY_SETTING).map_err(|e| e.to_string())?;
+    let store_name = settings::value_or_setting(&conn, store_name, settings::STORE_NAME_SETTING).map_err(|e| e.to_string())?;
+    let mut config = config.unwrap_or_default();
+    config.receipt_qr = print_service::receipt_qr_for(&conn, &transaction.id).map_err(|e| e.to_string())?;
+    Ok(print_service::generate_receipt_text(&transaction, &currency, &store_name, &config))
+}
+
+/// Empty currency / store name are taken from settings
+#[tauri::command]
+pub fn generate_invoice(state: State<AppState>, transaction: Transaction, currency: String, store_name: String, config: Option<print_service::PrintConfig>) -> Result<String, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let currency = settings::value_or_setting(&conn, currency, settings::CURRENCY_SETTING).map_err(|e| e.to_string())?;
+    let store_name = settings::value_or_setting(&conn, store_name, settings::STORE_NAME_SETTING).map_err(|e| e.to_string())?;
+    let mut config = config.unwrap_or_default();
+    config.receipt_qr = print_service::receipt_qr_for(&conn, &transaction.id).map_err(|e| e.to_string())?;
+    Ok(print_service::generate_invoice_html(&transaction, &currency, &store_name, &config))
+}
+
+/// Printable stock-count sheet, ordered by location then category so staff can count aisle by aisle
 #[tauri::command]
-pub fn generate_receipt(transaction: Transaction, currency: String, store_name: String) -> String {
-    print_service::generate_receipt_text(&transaction, &currency, &store_name)
+pub fn generate_count_sheet(
+    state: State<AppState>,
+    location: Option<String>,
+    category: Option<String>,
+    hide_quantities: Option<bool>,
+) -> Result<String, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+
+    let location = location.filter(|l| !l.is_empty());
+    let category = category.filter(|c| !c.is_empty());
+
+    // Set ürünlerin kendi stoğu yok, bileşenleri sayılır
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(NULLIF(TRIM(i.location), ''), 'Konumsuz'), COALESCE(NULLIF(i.category, ''), 'Genel'), i.sku, i.name, i.quantity
+         FROM inventory_items i
+         WHERE i.deleted_at IS NULL
+           AND (?1 IS NULL OR i.location = ?1)
+           AND (?2 IS NULL OR i.category = ?2)
+           AND NOT EXISTS (SELECT 1 FROM product_components pc WHERE pc.parent_sku = i.sku)
+         ORDER BY (NULLIF(TRIM(i.location), '') IS NULL), i.location COLLATE NOCASE, i.category COLLATE NOCASE, i.name COLLATE NOCASE"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![&location, &category], |row| {
+        Ok(print_service::CountSheetRow {
+            location: row.get(0)?,
+            category: row.get(1)?,
+            sku: row.get(2)?,
+            name: row.get(3)?,
+            quantity: row.get(4)?,
+        })
+    }).map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
+
+    let store_name = license::get_local_license(&conn)
+        .ok()
+        .flatten()
+        .map(|l| l.dealer_name)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "Nexus Inventory Hub".to_string());
+
+    let filter_label = match (&location, &category) {
+        (Some(l), Some(c)) => format!("Konum: {} / Kategori: {}", l, c),
+        (Some(l), None) => format!("Konum: {}", l),
+        (None, Some(c)) => format!("Kategori: {}", c),
+        (None, None) => "Tüm konumlar".to_string(),
+    };
+    let date = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+
+    Ok(print_service::generate_count_sheet_html(
+        &rows,
+        &store_name,
+        &filter_label,
+        &date,
+        hide_quantities.unwrap_or(false),
+    ))
 }
 
+/// 40x30mm shelf label (PNG bytes) with the Code128 barcode, name and price of an item
 #[tauri::command]
-pub fn generate_invoice(transaction: Transaction, currency: String, store_name: String) -> String {
-    print_service::generate_invoice_html(&transaction, &currency, &store_name)
+pub fn generate_label_png(state: State<AppState>, sku: String) -> Result<Vec<u8>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    print_service::generate_label_for_sku(&conn, &sku).map_err(|e| e.to_string())
 }
 
 // ==================== UPDATER COMMANDS ====================
@@ -1520,20 +1867,90 @@ pub fn get_app_version() -> String {
 
 // ==================== SCANNER COMMANDS ====================
 
+/// Parse a barcode, dropping the configured scanner prefix/suffix first
+#[tauri::command]
+pub fn parse_barcode(state: State<AppState>, barcode: String) -> Result<scanner::BarcodeInfo, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let config = scanner::load_scanner_config(&conn).map_err(|e| e.to_string())?;
+    Ok(scanner::parse_barcode(scanner::strip_scanner_affixes(&barcode, &config)))
+}
+
+/// Inventory item of a scanned code; GS1 codes are matched by their GTIN
+#[tauri::command]
+pub fn find_item_by_barcode(state: State<AppState>, barcode: String) -> Result<Option<InventoryItem>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let config = scanner::load_scanner_config(&conn).map_err(|e| e.to_string())?;
+    let info = scanner::parse_barcode(scanner::strip_scanner_affixes(&barcode, &config));
+    scanner::find_item_for_barcode(&conn, &info).map_err(|e| e.to_string())
+}
+
+/// Item of a scanned code and which lookup found it (SKU, stock card or scale label item code).
+/// Exact SKU hits are served from the item cache; whatever is found is cached by its SKU.
+#[tauri::command]
+pub fn lookup_by_barcode(state: State<AppState>, barcode: String) -> Result<Option<scanner::BarcodeLookup>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let config = scanner::load_scanner_config(&conn).map_err(|e| e.to_string())?;
+    let info = scanner::parse_barcode(scanner::strip_scanner_affixes(&barcode, &config));
+    cache::read_through(
+        &state.cache,
+        |c| {
+            scanner::sku_candidates(&info).iter().find_map(|sku| c.get_item(sku)).map(|item| {
+                Some(scanner::BarcodeLookup {
+                    item,
+                    matched_by: scanner::BarcodeMatch::Sku,
+                    embedded_price: info.embedded_price,
+                })
+            })
+        },
+        |c, found| {
+            if let Some(found) = found {
+                c.set_item(found.item.sku.clone(), found.item);
+            }
+        },
+        || scanner::lookup_barcode(&conn, &info).map_err(|e| e.to_string()),
+    )
+}
+
+/// Buffered keystrokes as a parsed scan; None when they were typed by hand
+#[tauri::command]
+pub fn parse_scanned_input(
+    state: State<AppState>,
+    input: String,
+    key_intervals_ms: Vec<u32>,
+) -> Result<Option<scanner::BarcodeInfo>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let config = scanner::load_scanner_config(&conn).map_err(|e| e.to_string())?;
+    Ok(scanner::parse_scanned_input(&input, &key_intervals_ms, &config))
+}
+
+#[tauri::command]
+pub fn validate_barcode(state: State<AppState>, barcode: String) -> Result<scanner::BarcodeValidation, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let config = scanner::load_scanner_config(&conn).map_err(|e| e.to_string())?;
+    Ok(scanner::validate_barcode(&barcode, &config))
+}
+
 #[tauri::command]
-pub fn parse_barcode(barcode: String) -> scanner::BarcodeInfo {
-    scanner::parse_barcode(&barcode)
+pub fn get_scanner_config(state: State<AppState>) -> Result<scanner::ScannerConfig, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    scanner::load_scanner_config(&conn).map_err(|e| e.to_string())
 }
 
+/// Store the scanner settings; disabling the scanner also stops a running listener
 #[tauri::command]
-pub fn validate_barcode(barcode: String) -> bool {
-    let config = scanner::ScannerConfig::default();
-    scanner::is_valid_barcode(&barcode, &config)
+pub fn set_scanner_config(state: State<AppState>, config: scanner::ScannerConfig) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    scanner::save_scanner_config(&conn, &config).map_err(|e| e.to_string())?;
+    if scanner::is_scanner_active() && !config.enabled {
+        scanner::stop_scanner_listener();
+    }
+    Ok(())
 }
 
 #[tauri::command]
-pub fn start_scanner() {
-    scanner::start_scanner_listener();
+pub fn start_scanner(state: State<AppState>) -> Result<scanner::ScannerConfig, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    scanner::start_scanner_listener(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -1559,7 +1976,7 @@ pub fn add_finance_record(state: State<AppState>, record: FinanceRecord) -> Resu
             &record.record_type,
             &record.category,
             &record.amount,
-            &record.payment_method,
+            finance::canonical_payment_method(&record.payment_method),
             &record.description,
             &record.date,
             &record.created_at
@@ -1593,6 +2010,13 @@ pub fn get_finance_records(state: State<AppState>, start_date: String, end_date:
     records.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
+/// Fix a record in place (id and created_at are kept); a goods receipt's amount needs `force`
+#[tauri::command]
+pub fn update_finance_record(state: State<AppState>, id: i64, record: FinanceRecord, force: Option<bool>) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    finance::update_finance_record(&conn, id, &record, force.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn delete_finance_record(state: State<AppState>, id: i64) -> Result<(), String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
@@ -1611,88 +2035,73 @@ pub fn get_finance_summary(state: State<AppState>, date: String) -> Result<Finan
 
     // --- DAILY TOTALS (Specific Date) ---
     // SQL does the heavy lifting
+    // Account payments are counted through their COLLECTION transaction
     let (d_man_inc, d_man_exp): (f64, f64) = conn.query_row(
-        "SELECT 
+        &format!("SELECT 
             COALESCE(SUM(CASE WHEN record_type = 'INCOME' THEN amount ELSE 0 END), 0),
             COALESCE(SUM(CASE WHEN record_type = 'EXPENSE' THEN amount ELSE 0 END), 0)
-         FROM finance_records WHERE date = ?1",
+         FROM finance_records WHERE date = ?1 AND {}", finance::not_account_payment_sql()),
         params![&date],
         |row| Ok((row.get(0)?, row.get(1)?))
     ).unwrap_or((0.0, 0.0));
 
     // Daily Transactions Aggregation
-    // We sum absolute values based on logic
-    let (d_tx_inc, d_tx_exp): (f64, f64) = conn.query_row(
-        "SELECT 
-            COALESCE(SUM(CASE WHEN transaction_type IN ('SALE', 'COLLECTION') THEN ABS(total) ELSE 0 END), 0),
-            COALESCE(SUM(CASE WHEN transaction_type IN ('RETURN', 'EXPENSE') THEN ABS(total) ELSE 0 END), 0)
-         FROM transactions WHERE substr(created_at, 1, 10) = ?1",
-        params![&date],
-        |row| Ok((row.get(0)?, row.get(1)?))
-    ).unwrap_or((0.0, 0.0));
+    // We sum absolute values based on logic; lines of foreign-priced items are converted to TRY
+    let mut rates = currency::RateTable::load(&conn, chrono::Utc::now()).map_err(|e| e.to_string())?;
+    let item_currencies = currency::foreign_item_currencies(&conn).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(
+        &format!("SELECT t.total, t.items,
+            CASE WHEN {payment} THEN 0 WHEN t.transaction_type IN ('SALE', 'COLLECTION') THEN 1 ELSE 0 END,
+            CASE WHEN {payment} OR t.transaction_type IN ('RETURN', 'EXPENSE') THEN 1 ELSE 0 END
+         FROM transactions t LEFT JOIN current_accounts a ON a.id = t.customer_id
+         WHERE substr(t.created_at, 1, 10) = ?1 AND t.status != 'voided'", payment = finance::SUPPLIER_PAYMENT_SQL)
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![&date], |row| {
+        Ok((row.get::<_, f64>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?, row.get::<_, bool>(3)?))
+    }).map_err(|e| e.to_string())?;
+    let (mut d_tx_inc, mut d_tx_exp) = (0.0, 0.0);
+    for row in rows {
+        let (total, items_json, income, expense) = row.map_err(|e| e.to_string())?;
+        let total = if item_currencies.is_empty() {
+            total
+        } else {
+            let items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
+            rates.transaction_total_to_base(total, &items, &item_currencies)
+        };
+        if income {
+            d_tx_inc += total.abs();
+        }
+        if expense {
+            d_tx_exp += total.abs();
+        }
+    }
 
     let total_income = d_man_inc + d_tx_inc;
     let total_expense = d_man_exp + d_tx_exp;
     let daily_net = total_income - total_expense; 
     
     // --- ALL TIME BALANCES (Asset Status) ---
-    
-    // 1. Manual Records Balance
-    // Income is positive, Expense is negative
-    let (man_cash, man_card, man_bank): (f64, f64, f64) = conn.query_row(
-        "SELECT 
-            COALESCE(SUM(CASE 
-                WHEN payment_method IN ('NAKIT', 'CASH') THEN (CASE WHEN record_type='INCOME' THEN amount ELSE -amount END)
-                ELSE 0 END), 0),
-            COALESCE(SUM(CASE 
-                WHEN payment_method IN ('KREDI_KARTI', 'CREDIT_CARD', 'MAIL_ORDER') THEN (CASE WHEN record_type='INCOME' THEN amount ELSE -amount END)
-                ELSE 0 END), 0),
-            COALESCE(SUM(CASE 
-                WHEN payment_method IN ('HAVALE', 'EFT', 'BANKA_KARTI') THEN (CASE WHEN record_type='INCOME' THEN amount ELSE -amount END)
-                ELSE 0 END), 0)
-         FROM finance_records",
-        [],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
-    ).unwrap_or((0.0, 0.0, 0.0));
-
-    // 2. Transactions Balance
-    // SALE/COLLECTION adds to balance (as cash/asset)
-    // RETURN/EXPENSE/PURCHASE reduces it (cash out)
-    let (tx_cash, tx_card, tx_bank): (f64, f64, f64) = conn.query_row(
-        "SELECT
-            COALESCE(SUM(CASE
-                WHEN payment_method IN ('NAKIT', 'CASH', 'Nakit') THEN
-                    (CASE WHEN transaction_type IN ('SALE', 'COLLECTION') THEN ABS(total)
-                          WHEN transaction_type IN ('RETURN', 'EXPENSE', 'PURCHASE') THEN -ABS(total)
-                          ELSE 0 END)
-                ELSE 0 END), 0),
-            COALESCE(SUM(CASE
-                WHEN payment_method IN ('KREDI_KARTI', 'Kredi Kartı', 'CREDIT_CARD', 'MAIL_ORDER', 'MAIL ORDER') THEN
-                    (CASE WHEN transaction_type IN ('SALE', 'COLLECTION') THEN ABS(total)
-                          WHEN transaction_type IN ('RETURN', 'EXPENSE', 'PURCHASE') THEN -ABS(total)
-                          ELSE 0 END)
-                ELSE 0 END), 0),
-            COALESCE(SUM(CASE
-                WHEN payment_method IN ('HAVALE', 'EFT', 'Havale', 'BANKA_KARTI', 'Banka Kartı') THEN
-                    (CASE WHEN transaction_type IN ('SALE', 'COLLECTION') THEN ABS(total)
-                          WHEN transaction_type IN ('RETURN', 'EXPENSE', 'PURCHASE') THEN -ABS(total)
-                          ELSE 0 END)
-                ELSE 0 END), 0)
-         FROM transactions",
-        [],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
-    ).unwrap_or((0.0, 0.0, 0.0));
+    // Manual records and transactions grouped by payment method (any spelling)
+    let balances = finance::payment_balances(&conn).unwrap_or_default();
 
     Ok(FinanceSummary {
         total_income,
         total_expense,
         net_balance: daily_net,
-        cash_balance: man_cash + tx_cash,
-        card_balance: man_card + tx_card,
-        bank_balance: man_bank + tx_bank,
+        cash_balance: balances.cash,
+        card_balance: balances.card,
+        bank_balance: balances.bank,
+        currency_warnings: rates.warnings(),
     })
 }
 
+/// End-of-day close (Z report) for `date` (YYYY-MM-DD); each day can be closed once
+#[tauri::command]
+pub fn generate_z_report(state: State<AppState>, date: String) -> Result<ZReport, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    finance::generate_z_report(&conn, &date).map_err(|e| e.to_string())
+}
+
 // ==================== ACCESS CODE COMMANDS ====================
 
 #[tauri::command]
@@ -1735,7 +2144,7 @@ pub fn delete_access_code(state: State<AppState>, id: i64) -> Result<(), String>
 }
 
 #[tauri::command]
-pub fn login_with_code(state: State<AppState>, code: String) -> Result<Option<User>, String> {
+pub fn login_with_code(state: State<AppState>, code: String) -> Result<Option<AuthSession>, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     let access_code: Option<AccessCode> = conn.query_row(
@@ -1752,43 +2161,98 @@ pub fn login_with_code(state: State<AppState>, code: String) -> Result<Option<Us
 
     match access_code {
         Some(ac) => {
-            Ok(Some(User {
-                id: format!("code_{}", ac.id),
+            let now = chrono::Utc::now();
+            let user_id = format!("{}{}", session::ACCESS_CODE_USER_PREFIX, ac.id);
+            auth_events::record_auth_event(&conn, auth_events::ACTION_LOGIN_CODE, &ac.name, Some(&user_id), true, None)
+                .map_err(|e| e.to_string())?;
+            let user = User {
+                id: user_id,
                 username: ac.name.clone(),
                 display_name: ac.name,
                 role: ac.role,
                 created_at: ac.created_at,
-                last_login: Some(chrono::Utc::now().to_rfc3339()),
+                last_login: Some(now.to_rfc3339()),
                 must_change_password: false,
-            }))
+            };
+            session::create_session(&conn, user, now).map(Some).map_err(|e| e.to_string())
         },
-        None => Ok(None)
+        None => {
+            // The wrong code itself is not stored
+            auth_events::record_auth_event(&conn, auth_events::ACTION_LOGIN_CODE, "", None, false, Some("Geçersiz erişim kodu"))
+                .map_err(|e| e.to_string())?;
+            Ok(None)
+        }
     }
 }
 
 // ==================== GOODS RECEIPT COMMANDS ====================
 
+/// Returns warnings when a receipt against a purchase order differs from the ordered quantities
 #[tauri::command]
-pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem>, total_amount: f64, payment_method: String, description: String, date: String, supplier_id: Option<String>, invoice_no: Option<String>) -> Result<(), String> {
+pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem>, total_amount: f64, payment_method: String, description: String, date: String, supplier_id: Option<String>, invoice_no: Option<String>, purchase_order_id: Option<String>) -> Result<Vec<String>, String> {
+    // Blank expiry means the goods do not expire
+    let mut items = items;
+    for item in &mut items {
+        item.expiry_date = item.expiry_date.take().map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+        if let Some(expiry) = &item.expiry_date {
+            reports::parse_report_date(expiry).map_err(|e| e.to_string())?;
+        }
+    }
+
     let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
     // Enable foreign key support just in case, though mostly handled by logic
     conn.execute("PRAGMA foreign_keys = ON", []).map_err(|e| e.to_string())?;
     
     let tx = conn.transaction().map_err(|e| e.to_string())?;
 
+    // Receiving against a purchase order: count the items on its lines; the supplier defaults to the order's
+    let purchase_order_id = purchase_order_id.filter(|id| !id.is_empty());
+    let (supplier_id, warnings) = match &purchase_order_id {
+        Some(order_id) => {
+            let (order, warnings) = purchase_orders::receive_purchase_order(&tx, order_id, supplier_id.as_deref(), &items)
+                .map_err(|e| e.to_string())?;
+            (Some(order.supplier_id), warnings)
+        }
+        None => (supplier_id, Vec::new()),
+    };
+
+    let payment_method = finance::canonical_payment_method(&payment_method);
+    let on_account = finance::PaymentMethod::parse(&payment_method) == finance::PaymentMethod::Credit;
     let created_at = chrono::Utc::now().to_rfc3339();
+    let receipt_id = uuid::Uuid::new_v4().to_string();
+
+    // 0. Receipt header - lots below are linked to it
+    tx.execute(
+        "INSERT INTO goods_receipts (id, invoice_no, supplier_id, total_amount, payment_method, description, date, created_at, purchase_order_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![&receipt_id, &invoice_no, &supplier_id, total_amount, &payment_method, &description, &date, &created_at, &purchase_order_id],
+    ).map_err(|e| e.to_string())?;
 
     // 1. Add Finance Record
     // ONLY if NOT VADELI (Credit). If VADELI, no cash moves out yet.
-    if payment_method != "VADELI" {
+    if !on_account {
         tx.execute(
             "INSERT INTO finance_records (record_type, category, amount, payment_method, description, date, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params!["EXPENSE", "MAL_KABUL", total_amount, &payment_method, &description, &date, &created_at],
         ).map_err(|e| e.to_string())?;
+
+        tx.execute(
+            "UPDATE goods_receipts SET finance_record_id = ?1 WHERE id = ?2",
+            params![tx.last_insert_rowid(), &receipt_id],
+        ).map_err(|e| e.to_string())?;
     }
 
     // 2. Update Inventory and Create Lots
     for item in &items {
+        // Kesirli miktar yalnızca ölçülen birimlerde (KG, LT, MT...) kabul edilir
+        let unit: Option<String> = tx.query_row(
+            "SELECT COALESCE((SELECT unit FROM inventory_items WHERE id = ?1), (SELECT unit FROM stock_cards WHERE id = ?1))",
+            params![item.product_id],
+            |row| row.get(0),
+        ).map_err(|e| e.to_string())?;
+        let unit = units::normalize_unit(unit.as_deref().unwrap_or_default());
+        validate_quantity(item.quantity, &unit).map_err(|e| e.to_string())?;
+
         // First, get the stock card barcode for SKU matching
         let stock_card_barcode: Option<String> = tx.query_row(
             "SELECT barcode FROM stock_cards WHERE id = ?1",
@@ -1828,6 +2292,12 @@ pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem
             } else { by_id }
         };
 
+        // Existing item without an explicit sell price: reprice from the category's target margin
+        if updated > 0 && item.sell_price.is_none() {
+            pricing::reprice_received_item(&tx, &item.product_id, stock_card_barcode.as_deref(), item.buy_price)
+                .map_err(|e| e.to_string())?;
+        }
+
         // If item doesn't exist in inventory, INSERT it from Stock Card data
         if updated == 0 {
             // Fetch Stock Card details
@@ -1858,12 +2328,21 @@ pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem
                     if let Some(n) = cat_n { category_name = n; }
                 }
 
-                // Use sell_price from item, or calculate 30% margin
-                let initial_price = item.sell_price.unwrap_or(item.buy_price * 1.3);
+                // Use sell_price from item, or calculate from the category / default margin
+                let initial_price = match item.sell_price {
+                    Some(price) => price,
+                    None => {
+                        let margin = pricing::resolve_margin(&tx, &category_name).map_err(|e| e.to_string())?;
+                        let price = pricing::price_from_margin(item.buy_price, margin);
+                        pricing::record_price_change(&tx, &item.product_id, Some(&barcode), None, price, Some(item.buy_price), pricing::REASON_AUTO_MARGIN)
+                            .map_err(|e| e.to_string())?;
+                        price
+                    }
+                };
 
                 tx.execute(
-                    "INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency, supplier_id) 
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, 'TL', ?13)",
+                    "INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency, supplier_id, unit) 
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, 'TL', ?13, ?14)",
                     params![
                         item.product_id, // id (same as stock card id)
                         barcode,         // sku (using barcode)
@@ -1877,7 +2356,8 @@ pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem
                         card_desc,       // description
                         Option::<String>::None, // ai_tags
                         created_at,      // last_updated
-                        supplier_id      // supplier_id
+                        supplier_id,     // supplier_id
+                        unit             // unit
                     ],
                 ).map_err(|e| e.to_string())?;
             }
@@ -1886,8 +2366,8 @@ pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem
         // 3. Create Inventory Lot
         let lot_id = uuid::Uuid::new_v4().to_string();
         tx.execute(
-            "INSERT INTO inventory_lots (id, product_id, supplier_id, quantity, initial_quantity, buy_price, sell_price, receipt_date, invoice_no, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "INSERT INTO inventory_lots (id, product_id, supplier_id, quantity, initial_quantity, buy_price, sell_price, receipt_date, invoice_no, created_at, goods_receipt_id, expiry_date)
+             VALUES (?1, ?2, ?3, ?4, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 lot_id,
                 item.product_id,
@@ -1897,14 +2377,16 @@ pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem
                 item.sell_price,
                 &date,
                 &invoice_no,
-                created_at
+                created_at,
+                &receipt_id,
+                &item.expiry_date
             ],
         ).map_err(|e| e.to_string())?;
 
         // 4. Log Activity
         tx.execute(
             "INSERT INTO activity_log (action_type, description, item_id, quantity_change, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params!["STOCK_IN", format!("Mal Kabul: {} adet - {}", item.quantity, description), item.product_id, item.quantity, created_at],
+            params!["STOCK_IN", format!("Mal Kabul: {} - {}", units::format_quantity(item.quantity, &unit), description), item.product_id, item.quantity, created_at],
         ).map_err(|e| e.to_string())?;
     }
 
@@ -1947,8 +2429,13 @@ pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem
             ],
         ).map_err(|e| e.to_string())?;
 
+        tx.execute(
+            "UPDATE goods_receipts SET transaction_id = ?1 WHERE id = ?2",
+            params![&tx_id, &receipt_id],
+        ).map_err(|e| e.to_string())?;
+
         // Update Balance ONLY if VADELI
-        if payment_method == "VADELI" {
+        if on_account {
              // Basic check if supplier exists
             let supplier_exists: i32 = tx.query_row(
                 "SELECT COUNT(*) FROM current_accounts WHERE id = ?1",
@@ -1966,21 +2453,24 @@ pub fn process_goods_receipt(state: State<AppState>, items: Vec<GoodsReceiptItem
     }
 
     tx.commit().map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
 
-    Ok(())
+    Ok(warnings)
 }
 
 #[tauri::command]
 pub fn get_product_lots(state: State<AppState>, product_id: String) -> Result<Vec<InventoryLot>, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
-    let mut stmt = conn.prepare(
-        "SELECT l.id, l.product_id, l.supplier_id, ca.name, l.quantity, l.initial_quantity, l.buy_price, l.sell_price, l.receipt_date, l.invoice_no, l.created_at
+    // In the order sales consume them
+    let mut stmt = conn.prepare(&format!(
+        "SELECT l.id, l.product_id, l.supplier_id, ca.name, l.quantity, l.initial_quantity, l.buy_price, l.sell_price, l.receipt_date, l.invoice_no, l.created_at, l.expiry_date
          FROM inventory_lots l
          LEFT JOIN current_accounts ca ON l.supplier_id = ca.id
          WHERE l.product_id = ?1
-         ORDER BY l.created_at ASC"
-    ).map_err(|e| e.to_string())?;
+         ORDER BY {}",
+        inventory::LOT_CONSUMPTION_ORDER
+    )).map_err(|e| e.to_string())?;
 
     let lots = stmt.query_map(params![&product_id], |row| {
         Ok(InventoryLot {
@@ -1995,12 +2485,39 @@ pub fn get_product_lots(state: State<AppState>, product_id: String) -> Result<Ve
             receipt_date: row.get(8)?,
             invoice_no: row.get(9)?,
             created_at: row.get(10)?,
+            expiry_date: row.get(11)?,
         })
     }).map_err(|e| e.to_string())?;
 
     lots.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
+// ==================== CURRENCY COMMANDS ====================
+
+#[tauri::command]
+pub fn get_exchange_rates(state: State<AppState>) -> Result<Vec<ExchangeRate>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    currency::get_exchange_rates(&conn, chrono::Utc::now()).map_err(|e| e.to_string())
+}
+
+/// Set the TRY value of one unit of `currency` (USD, EUR…); reports use it right away
+#[tauri::command]
+pub fn set_exchange_rate(state: State<AppState>, currency: String, rate_to_try: f64) -> Result<ExchangeRate, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let rate = currency::set_exchange_rate(&conn, &currency, rate_to_try, chrono::Utc::now()).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(rate)
+}
+
+/// Lots with stock left expiring within `within_days` of today (shop time), expired ones included
+#[tauri::command]
+pub fn get_expiring_lots(state: State<AppState>, within_days: i64) -> Result<Vec<ExpiringLot>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let tz = reports::get_timezone_offset(&conn).map_err(|e| e.to_string())?;
+    let today = chrono::Utc::now().with_timezone(&tz).date_naive();
+    goods_receipts::get_expiring_lots(&conn, within_days, today).map_err(|e| e.to_string())
+}
+
 // ==================== CURRENT ACCOUNT (CARİ) COMMANDS ====================
 
 #[tauri::command]
@@ -2009,9 +2526,13 @@ pub fn create_current_account(state: State<AppState>, data: CreateCurrentAccount
     let now = chrono::Utc::now().to_rfc3339();
     let id = uuid::Uuid::new_v4().to_string();
 
+    if let Some(ref parent_id) = data.parent_account_id {
+        accounts::validate_account_parent(&conn, None, parent_id).map_err(|e| e.to_string())?;
+    }
+
     conn.execute(
-        "INSERT INTO current_accounts (id, name, account_type, tax_number, phone, email, address, note, payment_term, balance, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 0.0, ?10, ?10)",
+        "INSERT INTO current_accounts (id, name, account_type, tax_number, phone, email, address, note, payment_term, balance, created_at, updated_at, parent_account_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 0.0, ?10, ?10, ?11)",
         params![
             &id,
             &data.name,
@@ -2022,7 +2543,8 @@ pub fn create_current_account(state: State<AppState>, data: CreateCurrentAccount
             &data.address,
             &data.note,
             &data.payment_term,
-            &now
+            &now,
+            &data.parent_account_id
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -2039,6 +2561,7 @@ pub fn create_current_account(state: State<AppState>, data: CreateCurrentAccount
         balance: 0.0,
         created_at: now.clone(),
         updated_at: now,
+        parent_account_id: data.parent_account_id,
     })
 }
 
@@ -2047,36 +2570,33 @@ pub fn get_current_accounts(state: State<AppState>) -> Result<Vec<CurrentAccount
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, name, account_type, tax_number, phone, email, address, note, payment_term, balance, created_at, updated_at FROM current_accounts ORDER BY name"
+        &format!("SELECT {} FROM current_accounts ORDER BY name", accounts::ACCOUNT_COLUMNS)
     ).map_err(|e| e.to_string())?;
 
-    let accounts = stmt.query_map([], |row| {
-        Ok(CurrentAccount {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            account_type: row.get(2)?,
-            tax_number: row.get(3)?,
-            phone: row.get(4)?,
-            email: row.get(5)?,
-            address: row.get(6)?,
-            note: row.get(7)?,
-            payment_term: row.get(8)?,
-            balance: row.get(9)?,
-            created_at: row.get(10)?,
-            updated_at: row.get(11)?,
-        })
-    }).map_err(|e| e.to_string())?;
+    let accounts = stmt.query_map([], accounts::map_account_row).map_err(|e| e.to_string())?;
 
     accounts.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
+/// Update an account. With `apply_to_sub_accounts`, the payment term is copied to all sub-accounts.
 #[tauri::command]
-pub fn update_current_account(state: State<AppState>, id: String, data: CreateCurrentAccountRequest) -> Result<(), String> {
-    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+pub fn update_current_account(
+    state: State<AppState>,
+    id: String,
+    data: CreateCurrentAccountRequest,
+    apply_to_sub_accounts: Option<bool>,
+) -> Result<(), String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
     let now = chrono::Utc::now().to_rfc3339();
 
-    conn.execute(
-        "UPDATE current_accounts SET name = ?1, account_type = ?2, tax_number = ?3, phone = ?4, email = ?5, address = ?6, note = ?7, payment_term = ?8, updated_at = ?9 WHERE id = ?10",
+    if let Some(ref parent_id) = data.parent_account_id {
+        accounts::validate_account_parent(&conn, Some(&id), parent_id).map_err(|e| e.to_string())?;
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "UPDATE current_accounts SET name = ?1, account_type = ?2, tax_number = ?3, phone = ?4, email = ?5, address = ?6, note = ?7, payment_term = ?8, updated_at = ?9, parent_account_id = ?10 WHERE id = ?11",
         params![
             &data.name,
             &data.account_type,
@@ -2087,10 +2607,22 @@ pub fn update_current_account(state: State<AppState>, id: String, data: CreateCu
             &data.note,
             &data.payment_term,
             &now,
+            &data.parent_account_id,
             &id
         ],
     ).map_err(|e| e.to_string())?;
 
+    if apply_to_sub_accounts.unwrap_or(false) {
+        let group_ids = accounts::get_group_account_ids(&tx, &id).map_err(|e| e.to_string())?;
+        for sub_id in group_ids.iter().filter(|sub_id| **sub_id != id) {
+            tx.execute(
+                "UPDATE current_accounts SET payment_term = ?1, updated_at = ?2 WHERE id = ?3",
+                params![&data.payment_term, &now, sub_id],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
@@ -2108,49 +2640,109 @@ pub fn delete_current_account(state: State<AppState>, id: String) -> Result<(),
     if balance.abs() > 0.01 {
         return Err(format!("Bu cari hesabın bakiyesi var (₺{:.2}). Silmek için önce bakiyeyi sıfırlayın.", balance));
     }
+
+    // Alt hesap kontrolü - grubu dağıtmadan ana hesap silinemez
+    let sub_accounts: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM current_accounts WHERE parent_account_id = ?1",
+        params![&id],
+        |row| row.get(0),
+    ).unwrap_or(0);
+
+    if sub_accounts > 0 {
+        return Err(format!("Bu cari hesabın {} alt hesabı var. Silmek için önce alt hesapları ayırın.", sub_accounts));
+    }
     
     conn.execute("DELETE FROM current_accounts WHERE id = ?1", params![&id]).map_err(|e| e.to_string())?;
     Ok(())
 }
 
-// ==================== STOCK CARD COMMANDS ====================
+/// Set or clear the parent (group) account of an account
+#[tauri::command]
+pub fn set_account_parent(state: State<AppState>, account_id: String, parent_account_id: Option<String>) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    accounts::set_account_parent(&conn, &account_id, parent_account_id.as_deref()).map_err(|e| e.to_string())
+}
 
+/// Balance of an account plus all of its sub-accounts
 #[tauri::command]
-pub fn create_stock_card(state: State<AppState>, data: CreateStockCardRequest) -> Result<StockCard, String> {
+pub fn get_consolidated_balance(state: State<AppState>, parent_id: String) -> Result<ConsolidatedBalance, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    let now = chrono::Utc::now().to_rfc3339();
+    accounts::get_consolidated_balance(&conn, &parent_id).map_err(|e| e.to_string())
+}
 
-    // Check for duplicate barcode
-    let existing: Option<String> = conn.query_row(
-        "SELECT id FROM stock_cards WHERE barcode = ?1",
-        params![&data.barcode],
-        |row| row.get(0)
-    ).optional().map_err(|e| e.to_string())?;
+/// Compare stored balances with the balances derived from transactions; fixes them unless dry_run (default true)
+#[tauri::command]
+pub fn recompute_account_balances(state: State<AppState>, dry_run: Option<bool>) -> Result<Vec<BalanceDiscrepancy>, String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    accounts::recompute_account_balances(&mut conn, dry_run.unwrap_or(true)).map_err(|e| e.to_string())
+}
 
-    if existing.is_some() {
-        return Err("Bu barkod numarası zaten kullanılıyor!".to_string());
-    }
+#[tauri::command]
+pub fn get_account_tree(state: State<AppState>) -> Result<Vec<AccountTreeNode>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    accounts::get_account_tree(&conn).map_err(|e| e.to_string())
+}
 
-    conn.execute(
-        "INSERT INTO stock_cards (id, barcode, name, brand, unit, category_id, description, image, supplier_id, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)",
-        params![
-            &data.id,
-            &data.barcode,
-            &data.name,
-            &data.brand,
-            &data.unit,
-            &data.category_id,
-            &data.description,
-            &data.image,
-            &data.supplier_id,
-            &now
-        ],
-    ).map_err(|e| e.to_string())?;
+/// Transactions for an account statement; `consolidated` includes all sub-accounts
+#[tauri::command]
+pub fn get_account_transactions(state: State<AppState>, account_id: String, consolidated: Option<bool>) -> Result<Vec<Transaction>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    accounts::get_account_transactions(&conn, &account_id, consolidated.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// Payment against an account (customer tahsilat or supplier ödeme); refuses overpaying unless allow_overpay
+#[tauri::command]
+pub fn record_account_payment(
+    state: State<AppState>,
+    account_id: String,
+    amount: f64,
+    payment_method: String,
+    date: String,
+    note: Option<String>,
+    allow_overpay: Option<bool>,
+) -> Result<Transaction, String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let transaction = accounts::record_account_payment(
+        &mut conn,
+        &account_id,
+        amount,
+        &payment_method,
+        &date,
+        note,
+        allow_overpay.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_aggregates();
+    Ok(transaction)
+}
+
+/// Statement with opening balance, balance-changing movements with running balance and closing balance
+#[tauri::command]
+pub fn get_account_statement(
+    state: State<AppState>,
+    account_id: String,
+    start_date: String,
+    end_date: String,
+) -> Result<AccountStatement, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    accounts::get_account_statement(&conn, &account_id, &start_date, &end_date).map_err(|e| e.to_string())
+}
+
+// ==================== STOCK CARD COMMANDS ====================
+
+#[tauri::command]
+pub fn create_stock_card(state: State<AppState>, data: CreateStockCardRequest) -> Result<StockCard, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now().to_rfc3339();
 
-    Ok(StockCard {
+    let barcode = if data.generate_sku && data.barcode.trim().is_empty() {
+        inventory::generate_internal_sku(&conn, "").map_err(|e| e.to_string())?
+    } else {
+        data.barcode
+    };
+    let card = StockCard {
         id: data.id,
-        barcode: data.barcode,
+        barcode,
         name: data.name,
         brand: data.brand,
         unit: data.unit,
@@ -2160,13 +2752,25 @@ pub fn create_stock_card(state: State<AppState>, data: CreateStockCardRequest) -
         supplier_id: data.supplier_id,
         created_at: now.clone(),
         updated_at: now,
-    })
+    };
+    inventory::create_stock_card(&conn, &card).map_err(|e| e.to_string())?;
+
+    Ok(card)
+}
+
+/// Next 8-character internal SKU for `prefix` (empty: digits only, like 00001037)
+#[tauri::command]
+pub fn generate_internal_sku(state: State<AppState>, prefix: String) -> Result<String, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    inventory::generate_internal_sku(&conn, &prefix).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn delete_stock_card(state: State<AppState>, id: String) -> Result<(), String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    inventory::delete_stock_card_safe(&conn, &id).map_err(|e| e.to_string())
+    inventory::delete_stock_card_safe(&conn, &id).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
+    Ok(())
 }
 
 #[tauri::command]
@@ -2217,6 +2821,13 @@ pub fn update_stock_card(state: State<AppState>, data: CreateStockCardRequest) -
         ],
     ).map_err(|e| e.to_string())?;
 
+    // The item stocked from this card is sold in the card's unit
+    conn.execute(
+        "UPDATE inventory_items SET unit = ?1 WHERE id = ?2 OR sku = ?3",
+        params![units::normalize_unit(&data.unit), &data.id, &data.barcode],
+    ).map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_item(&data.barcode);
+
     // Fetch the updated card
     let mut stmt = conn.prepare(
         "SELECT id, barcode, name, brand, unit, category_id, description, image, supplier_id, created_at, updated_at FROM stock_cards WHERE id = ?1"
@@ -2286,11 +2897,25 @@ pub fn get_categories(state: State<AppState>) -> Result<Vec<Category>, String> {
     cats.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
+/// Categories nested under their parents, with full paths and stock card counts
 #[tauri::command]
-pub fn delete_category(state: State<AppState>, id: String) -> Result<(), String> {
+pub fn get_category_tree(state: State<AppState>) -> Result<Vec<CategoryNode>, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM categories WHERE id = ?1", params![&id]).map_err(|e| e.to_string())?;
-    Ok(())
+    categories::get_category_tree(&conn).map_err(|e| e.to_string())
+}
+
+/// Move a category under another one (None: top level); moves that would make a cycle are rejected
+#[tauri::command]
+pub fn move_category(state: State<AppState>, id: String, new_parent_id: Option<String>) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    categories::move_category(&conn, &id, new_parent_id.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Delete a category; one with subcategories or stock cards needs `reassign_to` to move them to
+#[tauri::command]
+pub fn delete_category(state: State<AppState>, id: String, reassign_to: Option<String>) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    categories::delete_category(&conn, &id, reassign_to.as_deref()).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -2299,7 +2924,7 @@ pub fn sync_inventory_categories(state: State<AppState>) -> Result<i32, String>
     let now = chrono::Utc::now().to_rfc3339();
     
     // Get all unique categories from inventory items
-    let mut stmt = conn.prepare("SELECT DISTINCT category FROM inventory_items WHERE category IS NOT NULL AND category != ''").map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare("SELECT DISTINCT category FROM inventory_items WHERE category IS NOT NULL AND category != '' AND deleted_at IS NULL").map_err(|e| e.to_string())?;
     let existing_cats: Vec<String> = stmt.query_map([], |row| row.get(0))
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
@@ -2328,11 +2953,99 @@ pub fn sync_inventory_categories(state: State<AppState>) -> Result<i32, String>
     Ok(count)
 }
 
+// ==================== CATEGORY MARGIN COMMANDS ====================
+
+#[tauri::command]
+pub fn get_category_margins(state: State<AppState>) -> Result<Vec<CategoryMargin>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    pricing::get_category_margins(&conn).map_err(|e| e.to_string())
+}
+
+/// Set a category's target margin (% over cost); `None` removes it so the default applies
+#[tauri::command]
+pub fn set_category_margin(state: State<AppState>, category: String, target_margin_percent: Option<f64>) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    pricing::set_category_margin(&conn, &category, target_margin_percent).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_default_margin(state: State<AppState>) -> Result<f64, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    pricing::get_default_margin(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_default_margin(state: State<AppState>, percent: f64) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    pricing::set_default_margin(&conn, percent).map_err(|e| e.to_string())
+}
+
+// ==================== BUNDLE COMMANDS ====================
+
+#[tauri::command]
+pub fn get_bundles(state: State<AppState>) -> Result<Vec<BundleSummary>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    bundles::get_bundles(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_bundle_components(state: State<AppState>, parent_sku: String) -> Result<Vec<ProductComponent>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    bundles::get_bundle_components(&conn, &parent_sku).map_err(|e| e.to_string())
+}
+
+/// Replace a bundle's components; an empty list makes the SKU a normal item again
+#[tauri::command]
+pub fn set_bundle_components(
+    state: State<AppState>,
+    parent_sku: String,
+    components: Vec<BundleComponentInput>,
+) -> Result<(), String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    bundles::set_bundle_components(&mut conn, &parent_sku, &components).map_err(|e| e.to_string())
+}
+
+/// Stock for a normal item, buildable kits for a bundle
+#[tauri::command]
+pub fn get_available_quantity(state: State<AppState>, sku: String) -> Result<f64, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    bundles::get_available_quantity(&conn, &sku).map_err(|e| e.to_string())
+}
+
+// ==================== ITEM ATTRIBUTE COMMANDS ====================
+
+#[tauri::command]
+pub fn get_item_attributes(state: State<AppState>, item_id: String) -> Result<BTreeMap<String, String>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    attributes_service::get_item_attributes(&conn, &item_id).map_err(|e| e.to_string())
+}
+
+/// Set variant attributes of an item; empty values remove keys, `replace` drops keys not listed
+#[tauri::command]
+pub fn set_item_attributes(
+    state: State<AppState>,
+    item_id: String,
+    attributes: HashMap<String, String>,
+    replace: Option<bool>,
+) -> Result<BTreeMap<String, String>, String> {
+    let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    attributes_service::set_item_attributes(&mut conn, &item_id, &attributes, replace.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Attribute keys and their values, for building filters
+#[tauri::command]
+pub fn get_attribute_values(state: State<AppState>) -> Result<Vec<AttributeValues>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    attributes_service::get_attribute_values(&conn).map_err(|e| e.to_string())
+}
+
 // ==================== SEED DATA COMMAND ====================
 
 #[tauri::command]
 pub fn seed_data(state: State<AppState>) -> Result<String, String> {
     let mut conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let backup_path = backup::create_safety_backup(&conn, &state.db.get_db_path_string(), "seed_data")?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
     let now = chrono::Utc::now().to_rfc3339();
 
@@ -2340,6 +3053,9 @@ pub fn seed_data(state: State<AppState>) -> Result<String, String> {
     tx.execute("DELETE FROM transactions", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM inventory_items", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM inventory_lots", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM sale_lot_consumptions", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM product_components", []).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM item_attributes", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM stock_cards", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM categories", []).map_err(|e| e.to_string())?;
     tx.execute("DELETE FROM current_accounts", []).map_err(|e| e.to_string())?;
@@ -2617,6 +3333,7 @@ pub fn seed_data(state: State<AppState>) -> Result<String, String> {
     }
 
     tx.commit().map_err(|e| e.to_string())?;
+    cache::lock(&state.cache).invalidate_all();
 
     // Seed Expense Categories
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
@@ -2629,8 +3346,8 @@ pub fn seed_data(state: State<AppState>) -> Result<String, String> {
         ).map_err(|e| e.to_string())?;
     }
 
-    Ok(format!("Seed data created: {} stock cards, {} inventory items, {} categories",
-        sample_items.len(), sample_items.len(), product_categories.len()))
+    Ok(format!("Seed data created: {} stock cards, {} inventory items, {} categories. Backup: {}",
+        sample_items.len(), sample_items.len(), product_categories.len(), backup_path.display()))
 }
 
 // ==================== EXPENSE CATEGORY COMMANDS ====================
@@ -2640,18 +3357,18 @@ pub fn verify_admin_password(state: State<AppState>, password: String) -> Result
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
     
     // 'admin' rolüne sahip ilk kullanıcının şifresi ile eşleştirme yapıyoruz.
-    let mut stmt = conn.prepare("SELECT password_hash FROM users WHERE role = 'admin' LIMIT 1").map_err(|e| e.to_string())?;
-    let admin_hash_iter = stmt.query_map([], |row| row.get::<_, String>(0)).map_err(|e| e.to_string())?;
+    let username: String = conn.query_row(
+        "SELECT username FROM users WHERE role = 'admin' LIMIT 1",
+        [],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?.unwrap_or_default();
 
-    for hash_result in admin_hash_iter {
-        if let Ok(hash) = hash_result {
-            if verify_password(&password, &hash).unwrap_or(false) {
-                return Ok(true);
-            }
-        }
+    // Wrong passwords count toward the login lock; a locked account is an error, not `false`
+    match login::verify_admin(&conn, &username, &password, auth_events::ACTION_VERIFY_ADMIN_PASSWORD, chrono::Utc::now()) {
+        Ok(_) => Ok(true),
+        Err(crate::error::AppError::Auth(_)) => Ok(false),
+        Err(e) => Err(e.to_string()),
     }
-
-    Ok(false)
 }
 
 
@@ -2668,45 +3385,33 @@ pub fn process_expense(
     date: String, // Front-end sends ISO date string
 ) -> Result<Transaction, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    
-    let transaction_id = uuid::Uuid::new_v4().to_string();
-    // Use user provided date for the record, but created_at is system time? 
-    // Usually 'created_at' is database record time. We might need 'transaction_date' if different.
-    // For now, let's use the provided date as created_at or assume user enters 'today'.
-    // If user enters past date, we should probably respect it for the record.
-    // Let's use the provided date.
-    
-    // Expenses are negative in financial calculations
-    let total = -amount.abs(); 
-    
-    // Create a dummy item for the expense to verify structure
-    let expense_item = serde_json::json!([{
-        "id": "EXPENSE",
-        "sku": "EXPENSE",
-        "name": description,
-        "category": category,
-        "quantity": 1,
-        "price": total,
-        "cartQuantity": 1
-    }]);
+    // The user's date is kept as the record date, so past expenses land on their day
+    inventory::record_expense(&conn, &description, amount, &category, &payment_method, &date).map_err(|e| e.to_string())
+}
 
-    conn.execute(
-        "INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at) 
-         VALUES (?1, ?2, ?3, ?4, 'EXPENSE', ?5, ?6)",
-        params![transaction_id, expense_item.to_string(), total, payment_method, description, date],
-    ).map_err(|e| e.to_string())?;
+/// Monthly expenses (rent, utilities) written automatically on their day
+#[tauri::command]
+pub fn get_recurring_expenses(state: State<AppState>) -> Result<Vec<RecurringExpense>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    recurring_expenses::list_recurring_expenses(&conn).map_err(|e| e.to_string())
+}
 
-    Ok(Transaction {
-        id: transaction_id,
-        items: serde_json::from_value(expense_item).unwrap(),
-        total,
-        payment_method,
-        transaction_type: "EXPENSE".to_string(),
-        status: "completed".to_string(),
-        note: Some(description),
-        created_at: date,
-        customer_id: None,
-    })
+#[tauri::command]
+pub fn create_recurring_expense(state: State<AppState>, expense: RecurringExpense) -> Result<RecurringExpense, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    recurring_expenses::create_recurring_expense(&conn, expense).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_recurring_expense(state: State<AppState>, expense: RecurringExpense) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    recurring_expenses::update_recurring_expense(&conn, &expense).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_recurring_expense(state: State<AppState>, id: String) -> Result<(), String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    recurring_expenses::delete_recurring_expense(&conn, &id).map_err(|e| e.to_string())
 }
 
 // ==================== EXPENSE CATEGORY COMMANDS ====================
@@ -2764,37 +3469,15 @@ pub fn delete_expense_category(state: State<AppState>, id: String) -> Result<(),
 
 // ==================== INVOICE NUMBER GENERATION ====================
 
+/// Next `PREFIX-YEAR-00001` number for the current year (prefix defaults to `MK`)
 #[tauri::command]
 pub fn generate_invoice_number(state: State<AppState>, prefix: Option<String>) -> Result<String, String> {
+    use chrono::Datelike;
+
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    
-    // Get current year
-    let year = chrono::Utc::now().format("%Y").to_string();
-    let prefix_str = prefix.unwrap_or_else(|| "MK".to_string());
-    
-    // Get the last invoice number for this year/prefix from finance_records
-    let pattern = format!("{}-{}-%", prefix_str, year);
-    let last_number: Option<String> = conn.query_row(
-        "SELECT description FROM finance_records WHERE description LIKE ?1 ORDER BY created_at DESC LIMIT 1",
-        params![&pattern],
-        |row| row.get(0)
-    ).optional().map_err(|e| e.to_string())?;
-    
-    let next_seq = if let Some(last) = last_number {
-        // Extract sequence number from last invoice (MK-2026-00001)
-        if let Some(seq_str) = last.split('-').last() {
-            seq_str.parse::<u32>().unwrap_or(0) + 1
-        } else {
-            1
-        }
-    } else {
-        1
-    };
-    
-    // Format: MK-2026-00001
-    let invoice_number = format!("{}-{}-{:05}", prefix_str, year, next_seq);
-    
-    Ok(invoice_number)
+    let year = chrono::Local::now().year();
+    let prefix = prefix.filter(|p| !p.trim().is_empty()).unwrap_or_else(|| "MK".to_string());
+    finance::next_invoice_number(&conn, &prefix, year).map_err(|e| e.to_string())
 }
 
 // ==================== GOODS RECEIPT HISTORY ====================
@@ -2817,40 +3500,62 @@ pub fn get_goods_receipt_history(state: State<AppState>) -> Result<Vec<GoodsRece
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn.prepare(
-        "SELECT fr.id, fr.description, fr.amount, fr.payment_method, fr.date, fr.created_at,
-                ca.id as supplier_id, ca.name as supplier_name
-         FROM finance_records fr
-         LEFT JOIN current_accounts ca ON fr.description LIKE '%' || ca.name || '%'
-         WHERE fr.category = 'MAL_KABUL'
-         ORDER BY fr.created_at DESC"
+        "SELECT g.id, g.invoice_no, g.supplier_id, ca.name, g.total_amount, g.payment_method, g.description, g.date, g.created_at
+         FROM goods_receipts g
+         LEFT JOIN current_accounts ca ON ca.id = g.supplier_id
+         ORDER BY g.created_at DESC"
     ).map_err(|e| e.to_string())?;
     
     let records = stmt.query_map([], |row| {
-        let description: String = row.get(1)?;
-        // Extract invoice number from description if present
-        let invoice_no = if description.contains("Fatura:") {
-            description.split("Fatura:").nth(1).map(|s| s.trim().split_whitespace().next().unwrap_or("").to_string())
-        } else {
-            None
-        };
-        
         Ok(GoodsReceiptRecord {
             id: row.get(0)?,
-            invoice_no,
-            supplier_id: row.get::<_, Option<String>>(6)?,
-            supplier_name: row.get::<_, Option<String>>(7)?,
-            total_amount: row.get(2)?,
-            payment_method: row.get(3)?,
-            description: row.get(1)?,
-            date: row.get(4)?,
-            created_at: row.get(5)?,
+            invoice_no: row.get(1)?,
+            supplier_id: row.get(2)?,
+            supplier_name: row.get(3)?,
+            total_amount: row.get(4)?,
+            payment_method: row.get(5)?,
+            description: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+            date: row.get(7)?,
+            created_at: row.get(8)?,
         })
     }).map_err(|e| e.to_string())?;
-    
 
     records.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
+/// Goods receipts with their lines, filtered by supplier and date range
+#[tauri::command]
+pub fn get_goods_receipts_paginated(
+    state: State<AppState>,
+    page: i32,
+    per_page: i32,
+    supplier_id: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<PaginatedGoodsReceiptsResponse, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    goods_receipts::get_goods_receipts_paginated(&conn, page, per_page, supplier_id, start_date, end_date)
+        .map_err(|e| e.to_string())
+}
+
+// ==================== PURCHASE ORDER COMMANDS ====================
+
+/// Save a purchase order for the supplier under the next `PO-YEAR-00001` number and return it as HTML
+#[tauri::command]
+pub fn generate_purchase_order(state: State<AppState>, supplier_id: String, lines: Vec<POLine>) -> Result<String, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let order = purchase_orders::create_purchase_order(&conn, &supplier_id, lines).map_err(|e| e.to_string())?;
+    purchase_orders::purchase_order_html(&conn, &order).map_err(|e| e.to_string())
+}
+
+/// Purchase orders still waiting for goods (open or partial), optionally for one supplier
+#[tauri::command]
+pub fn get_open_purchase_orders(state: State<AppState>, supplier_id: Option<String>) -> Result<Vec<PurchaseOrder>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let supplier_id = supplier_id.filter(|s| !s.is_empty());
+    purchase_orders::get_open_purchase_orders(&conn, supplier_id.as_deref()).map_err(|e| e.to_string())
+}
+
 // ==================== CHECK COMMANDS ====================
 
 #[tauri::command]
@@ -2869,27 +3574,15 @@ pub fn check_sku_exists(state: State<AppState>, sku: String) -> Result<bool, Str
     }
 
     // Also check inventory_items just in case
-    let inv_count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM inventory_items WHERE sku = ?1",
-        [&sku],
-        |row| row.get(0),
-    ).map_err(|e| e.to_string())?;
-
-    Ok(inv_count > 0)
+    inventory::item_sku_exists(&conn, &sku).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn check_category_usage(state: State<AppState>, category_id: String) -> Result<i32, String> {
     let conn = state.db.get_conn().map_err(|e| e.to_string())?;
-    
-    // Check stock_cards using this category
-    let card_count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM stock_cards WHERE category_id = ?1",
-        [&category_id],
-        |row| row.get(0),
-    ).unwrap_or(0);
-    
-    Ok(card_count)
+    // Stock cards using this category
+    let card_count = categories::stock_card_count(&conn, &category_id).map_err(|e| e.to_string())?;
+    Ok(card_count as i32)
 }
 
 #[tauri::command]
@@ -2907,7 +3600,9 @@ pub fn check_current_account_exists(state: State<AppState>, name: String) -> Res
 
 // ==================== MULTI-DEVICE SYNC COMMANDS ====================
 
-use crate::services::sync::{self as sync_service, ActionType, SyncState as DeviceSyncState};
+use crate::services::sync::{
+    self as sync_service, ActionType, DeviceSyncEvent, SyncConflict, SyncResult as DeviceSyncResult, SyncState as DeviceSyncState,
+};
 
 /// Perform manual sync (push and pull)
 #[tauri::command]
@@ -2917,12 +3612,6 @@ pub fn perform_device_sync(state: State<AppState>) -> Result<DeviceSyncResult, S
     Ok(DeviceSyncResult { pushed, pulled })
 }
 
-#[derive(serde::Serialize)]
-pub struct DeviceSyncResult {
-    pub pushed: i32,
-    pub pulled: i32,
-}
-
 /// Get device sync status
 #[tauri::command]
 pub fn get_device_sync_state(state: State<AppState>) -> Result<DeviceSyncState, String> {
@@ -2930,12 +3619,37 @@ pub fn get_device_sync_state(state: State<AppState>) -> Result<DeviceSyncState,
     sync_service::get_sync_state(&conn).map_err(|e| e.to_string())
 }
 
+/// Message of the last failed device sync, `None` after a successful one
+#[tauri::command]
+pub fn get_last_sync_error(state: State<AppState>) -> Result<Option<String>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    sync_service::get_last_sync_error(&conn).map_err(|e| e.to_string())
+}
+
+/// Forward background sync events to the frontend; emitting without an open window is a no-op.
+/// Rows pulled from other devices make the cached lists stale, so the cache is dropped first.
+pub fn emit_device_sync_event(app: &tauri::AppHandle, event: DeviceSyncEvent) {
+    let _ = match event {
+        DeviceSyncEvent::Started => app.emit(sync_service::DEVICE_SYNC_STARTED_EVENT, ()),
+        DeviceSyncEvent::Done(result) => {
+            if result.pulled > 0 {
+                if let Some(state) = app.try_state::<AppState>() {
+                    cache::lock(&state.cache).invalidate_all();
+                }
+            }
+            app.emit(sync_service::DEVICE_SYNC_DONE_EVENT, result)
+        }
+        DeviceSyncEvent::Error(failed) => app.emit(sync_service::DEVICE_SYNC_ERROR_EVENT, failed),
+    };
+}
+
 /// Start background sync service
 #[tauri::command]
-pub fn start_device_sync(state: State<AppState>, interval_seconds: Option<u64>) -> Result<bool, String> {
+pub fn start_device_sync(app: tauri::AppHandle, state: State<AppState>, interval_seconds: Option<u64>) -> Result<bool, String> {
     let interval = interval_seconds.unwrap_or(300); // Default 5 minutes
     let db_path = state.db.get_db_path_string();
-    sync_service::start_background_sync(db_path, interval).map_err(|e| e.to_string())?;
+    sync_service::start_background_sync(db_path, interval, move |event| emit_device_sync_event(&app, event))
+        .map_err(|e| e.to_string())?;
     Ok(true)
 }
 
@@ -2959,7 +3673,7 @@ pub fn queue_sync_transaction(
     action_type: String,
     item_sku: Option<String>,
     item_name: Option<String>,
-    quantity_change: i32,
+    quantity_change: f64,
     old_value: Option<f64>,
     new_value: Option<f64>,
     metadata: Option<String>,
@@ -2988,3 +3702,158 @@ pub fn queue_sync_transaction(
         metadata.as_deref(),
     ).map_err(|e| e.to_string())
 }
+
+/// Advertise this device on the LAN and accept sync exchanges from peers; returns the listener port.
+/// Rows a peer pushes make the cached lists stale, so the cache is dropped after each apply.
+#[tauri::command]
+pub fn start_lan_sync(app: tauri::AppHandle, state: State<AppState>) -> Result<u16, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let identity = sync_service::lan::local_identity(&conn).map_err(|e| e.to_string())?;
+    let on_applied = move |_rows| {
+        if let Some(state) = app.try_state::<AppState>() {
+            cache::lock(&state.cache).invalidate_all();
+        }
+    };
+    sync_service::lan::start_lan_sync(state.db.get_db_path_string(), identity, on_applied).map_err(|e| e.to_string())
+}
+
+/// Stop LAN sync
+#[tauri::command]
+pub fn stop_lan_sync() -> Result<bool, String> {
+    sync_service::lan::stop_lan_sync();
+    Ok(true)
+}
+
+/// Devices found on the LAN
+#[tauri::command]
+pub fn get_lan_peers() -> Result<Vec<sync_service::lan::Peer>, String> {
+    Ok(sync_service::lan::get_lan_peers())
+}
+
+/// Exchange sync rows directly with the device at `addr` (`ip:port`)
+#[tauri::command]
+pub fn sync_with_peer(state: State<AppState>, addr: String) -> Result<DeviceSyncResult, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let identity = sync_service::lan::local_identity(&conn).map_err(|e| e.to_string())?;
+    let (pushed, pulled) = sync_service::lan::sync_with_peer(&conn, &identity, &addr).map_err(|e| e.to_string())?;
+    if pulled > 0 {
+        cache::lock(&state.cache).invalidate_all();
+    }
+    Ok(DeviceSyncResult { pushed, pulled })
+}
+
+/// Delete synced outbox rows older than the given number of days; returns how many were removed
+#[tauri::command]
+pub fn purge_synced_outbox(state: State<AppState>, older_than_days: i64) -> Result<usize, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    sync_service::purge_synced_outbox(&conn, older_than_days).map_err(|e| e.to_string())
+}
+
+/// Pulled changes that met a local change of the same item, newest first
+#[tauri::command]
+pub fn get_sync_conflicts(state: State<AppState>, limit: Option<i64>) -> Result<Vec<SyncConflict>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    sync_service::get_sync_conflicts(&conn, limit.unwrap_or(100)).map_err(|e| e.to_string())
+}
+
+// ==================== REPORT COMMANDS ====================
+
+use crate::services::reports;
+use crate::models::{HeatCell, HourlySales, ItemVelocity, ProductSales, ProfitReport, ReorderSuggestion, TurnoverReport};
+
+/// Inventory turnover and days-of-inventory per category
+#[tauri::command]
+pub fn get_turnover_report(
+    state: State<AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<TurnoverReport, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    reports::get_turnover_report(&conn, &start_date, &end_date).map_err(|e| e.to_string())
+}
+
+/// Revenue, cost of goods and gross profit of the sales in a date range, per category
+#[tauri::command]
+pub fn get_profit_report(
+    state: State<AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<ProfitReport, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    reports::get_profit_report(&conn, &start_date, &end_date).map_err(|e| e.to_string())
+}
+
+/// Best sellers of a date range by quantity (then revenue)
+#[tauri::command]
+pub fn get_top_products(
+    state: State<AppState>,
+    start_date: String,
+    end_date: String,
+    limit: i64,
+) -> Result<Vec<ProductSales>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    reports::get_top_products(&conn, &start_date, &end_date, limit).map_err(|e| e.to_string())
+}
+
+/// Dense 7×24 grid of sales count and revenue by weekday and hour (shop time)
+#[tauri::command]
+pub fn get_sales_heatmap(
+    state: State<AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<HeatCell>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    reports::get_sales_heatmap(&conn, &start_date, &end_date).map_err(|e| e.to_string())
+}
+
+/// 24 hourly buckets of sales count and total (shop time)
+#[tauri::command]
+pub fn get_sales_by_hour(
+    state: State<AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<HourlySales>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    reports::get_sales_by_hour(&conn, &start_date, &end_date).map_err(|e| e.to_string())
+}
+
+/// Average daily sales, days of stock left and reorder-by date for one item
+#[tauri::command]
+pub fn get_item_velocity(
+    state: State<AppState>,
+    sku: String,
+    window_days: i64,
+    lead_time_days: Option<i64>,
+) -> Result<ItemVelocity, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    reports::get_item_velocity(&conn, &sku, window_days, lead_time_days).map_err(|e| e.to_string())
+}
+
+/// Items below their minimum stock level with a suggested order quantity, grouped by supplier
+#[tauri::command]
+pub fn get_reorder_report(state: State<AppState>) -> Result<Vec<ReorderSuggestion>, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    reports::get_reorder_report(&conn).map_err(|e| e.to_string())
+}
+
+/// Export one row per day (sales, returns, expenses, payment methods) as csv or json
+#[tauri::command]
+pub fn export_daily_summary(
+    state: State<AppState>,
+    file_path: String,
+    start_date: String,
+    end_date: String,
+    format: String,
+) -> Result<usize, String> {
+    let conn = state.db.get_conn().map_err(|e| e.to_string())?;
+    let rows = reports::get_daily_summary(&conn, &start_date, &end_date).map_err(|e| e.to_string())?;
+
+    let content = match format.to_lowercase().as_str() {
+        "csv" => reports::daily_summary_to_csv(&rows),
+        "json" => serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())?,
+        other => return Err(format!("Desteklenmeyen format: {} (csv veya json)", other)),
+    };
+
+    std::fs::write(&file_path, content).map_err(|e| e.to_string())?;
+    Ok(rows.len())
+}
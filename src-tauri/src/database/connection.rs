@@ -2,20 +2,34 @@ use chrono::Utc;
 use directories::ProjectDirs;
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::Connection;
-use std::path::PathBuf;
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::error::AppError;
+use crate::models::DatabaseSchemaInfo;
+
+use super::encryption;
+use super::migrations::{self, SCHEMA_VERSION};
 
 pub type DbPool = Pool<SqliteConnectionManager>;
 pub type DbConn = PooledConnection<SqliteConnectionManager>;
 
+/// Backup steps that may hit a busy/locked destination before giving up (~10 sn)
+const LOCK_RETRY_LIMIT: u32 = 200;
+
 pub struct Database {
     pool: DbPool,
     db_path: PathBuf,
 }
 
 impl Database {
+    /// Open the pool and bring the schema up to date.
+    ///
+    /// With a key in the OS keyring every connection is opened with `PRAGMA key`. An existing
+    /// unencrypted database is converted once, before `init_schema` runs on it; if the
+    /// conversion fails it is retried on the next start and this session stays unencrypted.
     pub fn new() -> Result<Self, AppError> {
         let db_path = Self::get_db_path()?;
 
@@ -24,7 +38,22 @@ impl Database {
             std::fs::create_dir_all(parent)?;
         }
 
-        let manager = SqliteConnectionManager::file(&db_path);
+        let mut key = encryption::load_or_create_key();
+        if let Some(k) = &key {
+            if encryption::is_plaintext_database(&db_path) {
+                println!("[Encryption] Sifresiz veritabani bir kereye mahsus sifreleniyor: {}", db_path.display());
+                if let Err(e) = encryption::encrypt_plaintext_database(&db_path, k) {
+                    eprintln!("[Encryption] Veritabani sifrelenemedi, sifresiz devam ediliyor: {}", e);
+                    key = None;
+                }
+            }
+        }
+        encryption::set_database_key(key.clone());
+
+        let manager = SqliteConnectionManager::file(&db_path).with_init(move |conn| match &key {
+            Some(key) => encryption::apply_key(conn, key),
+            None => Ok(()),
+        });
 
         let pool = Pool::builder()
             .max_size(10)
@@ -37,13 +66,41 @@ impl Database {
             let conn = pool
                 .get()
                 .map_err(|e| AppError::Internal(format!("Baglanti alinamadi: {}", e)))?;
+            encryption::verify_key(&conn)?;
             Self::init_pragmas(&conn)?;
             Self::init_schema(&conn)?;
+            Self::seed_if_empty(&conn)?;
+            Self::extract_inline_images(&conn, &db_path);
         }
 
         Ok(Self { pool, db_path })
     }
 
+    /// App data directory the database lives in (image files are kept next to it)
+    pub fn data_dir(&self) -> PathBuf {
+        self.db_path.parent().map(Path::to_path_buf).unwrap_or_default()
+    }
+
+    /// Base64 item images written by older builds are moved to files; the database is compacted
+    /// afterwards so the space is actually given back. A failure only leaves them inline.
+    fn extract_inline_images(conn: &Connection, db_path: &Path) {
+        let data_dir = db_path.parent().unwrap_or(Path::new("."));
+        match crate::services::images::extract_inline_images(conn, data_dir) {
+            Ok(0) => {}
+            Ok(count) => {
+                println!("[Images] {} urun gorseli dosyaya tasindi", count);
+                let vacuumed = conn
+                    .execute_batch("VACUUM;")
+                    .map_err(AppError::from)
+                    .and_then(|_| crate::services::inventory::rebuild_search_index(conn));
+                if let Err(e) = vacuumed {
+                    eprintln!("[Images] VACUUM basarisiz: {}", e);
+                }
+            }
+            Err(e) => eprintln!("[Images] Gorseller dosyaya tasinamadi: {}", e),
+        }
+    }
+
     fn get_db_path() -> Result<PathBuf, AppError> {
         let proj_dirs = ProjectDirs::from("com", "nexus", "inventory").ok_or_else(|| {
             AppError::Internal("Uygulama veri dizini belirlenemedi".to_string())
@@ -72,358 +129,13 @@ impl Database {
         Ok(())
     }
 
-    fn init_schema(conn: &Connection) -> Result<(), AppError> {
-        conn.execute_batch(
-            "
-            -- Inventory Items Table
-            CREATE TABLE IF NOT EXISTS inventory_items (
-                id TEXT PRIMARY KEY NOT NULL,
-                sku TEXT UNIQUE NOT NULL,
-                name TEXT NOT NULL,
-                category TEXT DEFAULT 'Genel',
-                quantity INTEGER DEFAULT 0 CHECK (quantity >= 0),
-                location TEXT DEFAULT '',
-                price REAL DEFAULT 0.00 CHECK (price >= 0),
-                cost_price REAL DEFAULT 0.00,
-                image TEXT,
-                description TEXT,
-                ai_tags TEXT,
-                last_updated TEXT NOT NULL,
-                currency TEXT DEFAULT 'TL',
-                supplier_id TEXT
-            );
-
-            -- Transactions Table
-            CREATE TABLE IF NOT EXISTS transactions (
-                id TEXT PRIMARY KEY NOT NULL,
-                items TEXT NOT NULL,
-                total REAL NOT NULL,
-                payment_method TEXT DEFAULT 'Nakit',
-                transaction_type TEXT DEFAULT 'SALE' CHECK (transaction_type IN ('SALE', 'RETURN', 'EXPENSE', 'COLLECTION', 'PURCHASE')),
-                note TEXT,
-                created_at TEXT NOT NULL,
-                customer_id TEXT
-            );
-
-            -- Users Table
-            CREATE TABLE IF NOT EXISTS users (
-                id TEXT PRIMARY KEY NOT NULL,
-                username TEXT UNIQUE NOT NULL,
-                password_hash TEXT NOT NULL,
-                display_name TEXT NOT NULL,
-                role TEXT NOT NULL DEFAULT 'user' CHECK (role IN ('admin', 'user')),
-                created_at TEXT NOT NULL,
-                last_login TEXT,
-                failed_login_attempts INTEGER DEFAULT 0,
-                locked_until TEXT,
-                dealer_id TEXT,
-                must_change_password BOOLEAN DEFAULT 0
-            );
-
-            -- License Table (tek kayıt tutacak)
-            CREATE TABLE IF NOT EXISTS license (
-                id INTEGER PRIMARY KEY CHECK (id = 1),
-                license_key TEXT NOT NULL,
-                dealer_id TEXT NOT NULL,
-                dealer_name TEXT NOT NULL,
-                mac_address TEXT NOT NULL,
-                activated_at TEXT NOT NULL,
-                expires_at TEXT,
-                is_active INTEGER DEFAULT 1,
-                last_validated TEXT,
-                api_base_url TEXT NOT NULL
-            );
-
-            -- Cloud Sync Status Table
-            CREATE TABLE IF NOT EXISTS sync_status (
-                id INTEGER PRIMARY KEY CHECK (id = 1),
-                last_backup_at TEXT,
-                last_restore_at TEXT,
-                auto_sync_enabled INTEGER DEFAULT 0,
-                auto_sync_interval_minutes INTEGER DEFAULT 30
-            );
-
-            -- Settings Table
-            CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY NOT NULL,
-                value TEXT
-            );
-
-            -- Finance Records Table
-            CREATE TABLE IF NOT EXISTS finance_records (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                record_type TEXT NOT NULL CHECK (record_type IN ('INCOME', 'EXPENSE')),
-                category TEXT NOT NULL,
-                amount REAL NOT NULL,
-                payment_method TEXT NOT NULL DEFAULT 'NAKIT',
-                description TEXT DEFAULT '',
-                date TEXT NOT NULL,
-                created_at TEXT NOT NULL
-            );
-
-            -- Access Codes Table
-            CREATE TABLE IF NOT EXISTS access_codes (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                code TEXT UNIQUE NOT NULL,
-                name TEXT NOT NULL,
-                role TEXT DEFAULT 'user',
-                created_at TEXT NOT NULL
-            );
-
-            -- Activity Log Table
-            CREATE TABLE IF NOT EXISTS activity_log (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                action_type TEXT NOT NULL,
-                description TEXT NOT NULL,
-                item_id TEXT,
-                item_name TEXT,
-                quantity_change INTEGER,
-                value REAL,
-                user_id TEXT,
-                created_at TEXT NOT NULL
-            );
-
-            -- Current Accounts (Cari) Table
-            CREATE TABLE IF NOT EXISTS current_accounts (
-                id TEXT PRIMARY KEY NOT NULL,
-                name TEXT NOT NULL,
-                account_type TEXT NOT NULL DEFAULT 'CUSTOMER' CHECK (account_type IN ('CUSTOMER', 'SUPPLIER', 'BOTH')),
-                tax_number TEXT,
-                phone TEXT,
-                email TEXT,
-                address TEXT,
-                note TEXT,
-                payment_term INTEGER DEFAULT 0,
-                balance REAL DEFAULT 0.00,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-
-            -- Stock Cards Table
-            CREATE TABLE IF NOT EXISTS stock_cards (
-                id TEXT PRIMARY KEY NOT NULL,
-                barcode TEXT UNIQUE NOT NULL,
-                name TEXT NOT NULL,
-                brand TEXT,
-                unit TEXT NOT NULL DEFAULT 'ADET',
-                category_id TEXT,
-                description TEXT,
-                image TEXT,
-                supplier_id TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-
-            -- Categories Table
-            CREATE TABLE IF NOT EXISTS categories (
-                id TEXT PRIMARY KEY NOT NULL,
-                name TEXT NOT NULL,
-                parent_id TEXT,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (parent_id) REFERENCES categories(id) ON DELETE CASCADE
-            );
-
-            -- Inventory Lots Table (for lot/batch tracking)
-            CREATE TABLE IF NOT EXISTS inventory_lots (
-                id TEXT PRIMARY KEY NOT NULL,
-                product_id TEXT NOT NULL,
-                supplier_id TEXT,
-                quantity INTEGER NOT NULL,
-                initial_quantity INTEGER NOT NULL,
-                buy_price REAL NOT NULL,
-                sell_price REAL,
-                receipt_date TEXT NOT NULL,
-                invoice_no TEXT,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (product_id) REFERENCES inventory_items(id) ON DELETE CASCADE,
-                FOREIGN KEY (supplier_id) REFERENCES current_accounts(id)
-            );
-
-            -- Sync Transactions Table (Local outbox for pending sync)
-            CREATE TABLE IF NOT EXISTS sync_outbox (
-                id TEXT PRIMARY KEY NOT NULL,
-                action_type TEXT NOT NULL CHECK (action_type IN ('SALE', 'STOCK_IN', 'STOCK_OUT', 'PRICE_CHANGE', 'ITEM_CREATE', 'ITEM_UPDATE', 'ITEM_DELETE')),
-                item_sku TEXT,
-                item_name TEXT,
-                quantity_change INTEGER DEFAULT 0,
-                old_value REAL,
-                new_value REAL,
-                metadata TEXT,
-                transaction_time TEXT NOT NULL,
-                synced INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL
-            );
-
-            -- Sync State Table (Track last sync position)
-            CREATE TABLE IF NOT EXISTS sync_state (
-                id INTEGER PRIMARY KEY CHECK (id = 1),
-                last_push_at TEXT,
-                last_pull_at TEXT,
-                last_received_id TEXT,
-                sync_in_progress INTEGER DEFAULT 0
-            );
-
-            -- Performance Indexes
-            CREATE INDEX IF NOT EXISTS idx_sync_outbox_synced ON sync_outbox(synced);
-            CREATE INDEX IF NOT EXISTS idx_sync_outbox_time ON sync_outbox(transaction_time);
-            CREATE INDEX IF NOT EXISTS idx_inventory_sku ON inventory_items(sku);
-            CREATE INDEX IF NOT EXISTS idx_inventory_category ON inventory_items(category);
-            CREATE INDEX IF NOT EXISTS idx_inventory_quantity ON inventory_items(quantity);
-            CREATE INDEX IF NOT EXISTS idx_transactions_created_at ON transactions(created_at);
-            CREATE INDEX IF NOT EXISTS idx_transactions_type ON transactions(transaction_type);
-            CREATE INDEX IF NOT EXISTS idx_transactions_payment ON transactions(payment_method);
-            CREATE INDEX IF NOT EXISTS idx_activity_created_at ON activity_log(created_at);
-            CREATE INDEX IF NOT EXISTS idx_users_username ON users(username);
-            CREATE INDEX IF NOT EXISTS idx_current_accounts_name ON current_accounts(name);
-            CREATE INDEX IF NOT EXISTS idx_stock_cards_barcode ON stock_cards(barcode);
-            CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id);
-            CREATE INDEX IF NOT EXISTS idx_inventory_lots_product ON inventory_lots(product_id);
-            CREATE INDEX IF NOT EXISTS idx_inventory_lots_created ON inventory_lots(created_at);
-            CREATE INDEX IF NOT EXISTS idx_inventory_items_supplier ON inventory_items(supplier_id);
-            CREATE INDEX IF NOT EXISTS idx_finance_records_date ON finance_records(date);
-            CREATE INDEX IF NOT EXISTS idx_finance_records_payment ON finance_records(payment_method);
-
-            -- Expense Categories Table
-            CREATE TABLE IF NOT EXISTS expense_categories (
-                id TEXT PRIMARY KEY NOT NULL,
-                name TEXT UNIQUE NOT NULL
-            );
-            ",
-        )?;
-
-        // Migration: Ensure supplier_id column exists in inventory_items (for existing databases)
-        // Check if column exists
-        let has_supplier_id: bool = conn
-            .query_row(
-                "SELECT COUNT(*) FROM pragma_table_info('inventory_items') WHERE name='supplier_id'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0) > 0;
-
-        if !has_supplier_id {
-            // Add the column if it doesn't exist
-            // Ignore error if column already exists (race condition or check failed)
-            let _ = conn.execute("ALTER TABLE inventory_items ADD COLUMN supplier_id TEXT", []);
-        }
-
-        // Migration: Fix transaction_type check constraint by recreating the table if needed.
-        // We check if we can insert an EXPENSE type dummy transaction (in a transaction that we rollback)
-        // actually, simpler: Just check sql definition in sqlite_master
-        let sql: String = conn.query_row(
-            "SELECT sql FROM sqlite_master WHERE type='table' AND name='transactions'", 
-            [], 
-            |row| row.get(0)
-        ).unwrap_or_default();
-
-        if !sql.contains("'EXPENSE'") || !sql.contains("'PURCHASE'") {
-            // Need migration - add EXPENSE and/or PURCHASE types
-            conn.execute_batch("
-                BEGIN TRANSACTION;
-                ALTER TABLE transactions RENAME TO transactions_old;
-
-                CREATE TABLE transactions (
-                    id TEXT PRIMARY KEY NOT NULL,
-                    items TEXT NOT NULL,
-                    total REAL NOT NULL,
-                    payment_method TEXT DEFAULT 'Nakit',
-                    transaction_type TEXT DEFAULT 'SALE' CHECK (transaction_type IN ('SALE', 'RETURN', 'EXPENSE', 'COLLECTION', 'PURCHASE')),
-                    note TEXT,
-                    created_at TEXT NOT NULL,
-                    customer_id TEXT
-                );
-
-                INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id)
-                SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id
-                FROM transactions_old;
-
-                DROP TABLE transactions_old;
-
-                CREATE INDEX idx_transactions_created_at ON transactions(created_at);
-                CREATE INDEX idx_transactions_type ON transactions(transaction_type);
-
-                COMMIT;
-            ").map_err(|e| AppError::Internal(format!("Migration failed: {}", e)))?;
-        }
-
-
-
-        // Migration: Ensure customer_id column exists in transactions
-        let has_customer_id: bool = conn
-            .query_row(
-                "SELECT COUNT(*) FROM pragma_table_info('transactions') WHERE name='customer_id'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0) > 0;
-
-        if !has_customer_id {
-            let _ = conn.execute("ALTER TABLE transactions ADD COLUMN customer_id TEXT", []);
-        }
-
-        // Migration: Ensure supplier_id column exists in stock_cards (for existing databases)
-        let has_stock_supplier_id: bool = conn
-            .query_row(
-                "SELECT COUNT(*) FROM pragma_table_info('stock_cards') WHERE name='supplier_id'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0) > 0;
-
-        if !has_stock_supplier_id {
-            let _ = conn.execute("ALTER TABLE stock_cards ADD COLUMN supplier_id TEXT", []);
-        }
-
-        // Migration: Ensure must_change_password column exists in users
-        let has_must_change_pwd: bool = conn
-            .query_row(
-                "SELECT COUNT(*) FROM pragma_table_info('users') WHERE name='must_change_password'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0) > 0;
-
-        if !has_must_change_pwd {
-            let _ = conn.execute("ALTER TABLE users ADD COLUMN must_change_password BOOLEAN DEFAULT 0", []);
-        }
-
-        // Migration: Create inventory_lots table if it doesn't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS inventory_lots (
-                id TEXT PRIMARY KEY NOT NULL,
-                product_id TEXT NOT NULL,
-                supplier_id TEXT,
-                quantity INTEGER NOT NULL,
-                initial_quantity INTEGER NOT NULL,
-                buy_price REAL NOT NULL,
-                sell_price REAL,
-                receipt_date TEXT NOT NULL,
-                invoice_no TEXT,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (product_id) REFERENCES inventory_items(id) ON DELETE CASCADE,
-                FOREIGN KEY (supplier_id) REFERENCES current_accounts(id)
-            )",
-            [],
-        ).map_err(|e| AppError::Internal(format!("Create inventory_lots failed: {}", e)))?;
-
-        // Create indexes for inventory_lots if they don't exist
-        let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_inventory_lots_product ON inventory_lots(product_id)", []);
-        let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_inventory_lots_created ON inventory_lots(created_at)", []);
-
-        // Migration: Ensure brand column exists in inventory_items
-        let has_brand: bool = conn
-            .query_row(
-                "SELECT COUNT(*) FROM pragma_table_info('inventory_items') WHERE name='brand'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0) > 0;
-
-        if !has_brand {
-            let _ = conn.execute("ALTER TABLE inventory_items ADD COLUMN brand TEXT", []);
-        }
+    /// Bring the schema up to `SCHEMA_VERSION` (see `migrations`)
+    pub(crate) fn init_schema(conn: &Connection) -> Result<(), AppError> {
+        migrations::run_migrations(conn)
+    }
 
-        // Auto-seed: Veritabanı boşsa otomatik olarak ürünleri ekle
+    /// Auto-seed: Veritabanı boşsa otomatik olarak ürünleri ekle
+    pub(crate) fn seed_if_empty(conn: &Connection) -> Result<(), AppError> {
         let stock_count: i32 = conn
             .query_row("SELECT COUNT(*) FROM stock_cards", [], |row| row.get(0))
             .unwrap_or(0);
@@ -712,3 +424,227 @@ impl Database {
 pub fn init_database() -> Result<Database, AppError> {
     Database::new()
 }
+
+/// Schema version of a database file, checked before it replaces the live database
+pub fn inspect_database_schema(path: &Path) -> Result<DatabaseSchemaInfo, AppError> {
+    inspect_schema_with_key(path, encryption::database_key())
+}
+
+fn inspect_schema_with_key(path: &Path, key: Option<&str>) -> Result<DatabaseSchemaInfo, AppError> {
+    let conn = encryption::open_database_with_key(path, OpenFlags::SQLITE_OPEN_READ_ONLY, key)
+        .map_err(|e| AppError::sqlite("Kaynak veritabani acilamadi", e))?;
+
+    let has_inventory: i32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'inventory_items'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::Validation("Dosya gecerli bir veritabani degil".to_string()))?;
+    if has_inventory == 0 {
+        return Err(AppError::Validation("Dosya bir Nexus envanter veritabani degil".to_string()));
+    }
+
+    let detected_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(DatabaseSchemaInfo {
+        detected_version,
+        current_version: SCHEMA_VERSION,
+        needs_migration: detected_version < SCHEMA_VERSION,
+        compatible: detected_version <= SCHEMA_VERSION,
+        backup_path: None,
+    })
+}
+
+/// Run the backup API from `source_conn` into `dest_conn`, giving up after `LOCK_RETRY_LIMIT` busy steps
+fn copy_database(source_conn: &Connection, dest_conn: &mut Connection) -> Result<(), AppError> {
+    let backup = Backup::new(source_conn, dest_conn)
+        .map_err(|e| AppError::sqlite("Backup olusturulamadi", e))?;
+
+    let mut busy_steps = 0;
+    loop {
+        match backup
+            .step(100)
+            .map_err(|e| AppError::sqlite("Backup tamamlanamadi", e))?
+        {
+            StepResult::Done => return Ok(()),
+            StepResult::More => busy_steps = 0,
+            _ => {
+                busy_steps += 1;
+                if busy_steps >= LOCK_RETRY_LIMIT {
+                    return Err(AppError::DatabaseLocked(
+                        "Veritabani baska bir uygulama tarafindan kullaniliyor, geri yukleme yapilamadi".to_string(),
+                    ));
+                }
+            }
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Copy the database at `source_path` over `db_path` using the SQLite backup API.
+///
+/// Unlike `Backup::run_to_completion`, this does not retry forever when another
+/// process (ikinci uygulama, yedekleme aracı) holds the file: it returns
+/// `AppError::DatabaseLocked` after `LOCK_RETRY_LIMIT` busy steps.
+///
+/// Yedeğin şema sürümü kontrol edilir: daha yeni bir sürümden gelen yedek reddedilir,
+/// eski yedekler önce geçici bir kopyada güncellenir, kaynak dosyaya dokunulmaz.
+pub fn restore_database_from_file(source_path: &Path, db_path: &str) -> Result<DatabaseSchemaInfo, AppError> {
+    restore_database_with_key(source_path, db_path, encryption::database_key())
+}
+
+/// `restore_database_from_file` into a live database encrypted with `key`.
+/// SQLCipher only runs the backup API between databases with the same key, so the source
+/// (plaintext or keyed) is first exported into a temp copy keyed like the live database.
+fn restore_database_with_key(source_path: &Path, db_path: &str, key: Option<&str>) -> Result<DatabaseSchemaInfo, AppError> {
+    let schema = inspect_schema_with_key(source_path, key)?;
+    if !schema.compatible {
+        return Err(AppError::Validation(format!(
+            "Bu yedek daha yeni bir uygulama surumune ait (sema v{}, bu surum v{}). Lutfen uygulamayi guncelleyin.",
+            schema.detected_version, schema.current_version
+        )));
+    }
+
+    let temp_path = std::env::temp_dir().join(format!("nexus_import_{}.db", uuid::Uuid::new_v4()));
+    let result = (|| {
+        let source_conn = encryption::open_database_with_key(source_path, OpenFlags::default(), key)
+            .map_err(|e| AppError::sqlite("Kaynak veritabani acilamadi", e))?;
+        encryption::export_database_to(&source_conn, &temp_path, key)?;
+        drop(source_conn);
+
+        let temp_conn = encryption::open_database_with_key(&temp_path, OpenFlags::default(), key)
+            .map_err(|e| AppError::sqlite("Gecici veritabani acilamadi", e))?;
+        if schema.needs_migration {
+            Database::init_schema(&temp_conn)?;
+        }
+
+        // Direct mutable connection to destination (not through pool)
+        let mut dest_conn = encryption::open_database_with_key(db_path, OpenFlags::default(), key)
+            .map_err(|e| AppError::sqlite("Hedef veritabani acilamadi", e))?;
+        copy_database(&temp_conn, &mut dest_conn)
+    })();
+    let _ = std::fs::remove_file(&temp_path);
+
+    result.map(|_| schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("nexus_schema_{}_{}.db", label, uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_restore_migrates_old_and_rejects_newer_backups() {
+        let live = temp_db("live");
+        Database::init_schema(&Connection::open(&live).unwrap()).unwrap();
+
+        // Pre-versioning export that lacks a migrated column
+        let old = temp_db("old");
+        {
+            let conn = Connection::open(&old).unwrap();
+            Database::init_schema(&conn).unwrap();
+            conn.execute_batch(
+                "DROP TRIGGER inventory_fts_insert; DROP TRIGGER inventory_fts_update; DROP TRIGGER inventory_fts_delete;
+                 DROP TABLE inventory_fts;
+                 ALTER TABLE inventory_items DROP COLUMN brand; PRAGMA user_version = 0;",
+            )
+            .unwrap();
+        }
+
+        let schema = restore_database_from_file(&old, &live.to_string_lossy()).unwrap();
+        assert_eq!(schema.detected_version, 0);
+        assert!(schema.needs_migration);
+
+        let conn = Connection::open(&live).unwrap();
+        let has_brand: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('inventory_items') WHERE name='brand'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(has_brand, 1);
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        // The source file itself is left untouched
+        assert!(inspect_database_schema(&old).unwrap().needs_migration);
+
+        let newer = temp_db("newer");
+        {
+            let conn = Connection::open(&newer).unwrap();
+            Database::init_schema(&conn).unwrap();
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+        }
+        let err = restore_database_from_file(&newer, &live.to_string_lossy()).unwrap_err();
+        assert!(err.to_string().contains("daha yeni"));
+
+        for path in [live, old, newer] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_plaintext_backup_restores_into_encrypted_database() {
+        let key = "5a".repeat(32);
+        let live = temp_db("keyed");
+        {
+            let conn = Connection::open(&live).unwrap();
+            encryption::apply_key(&conn, &key).unwrap();
+            Database::init_schema(&conn).unwrap();
+            conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;").unwrap();
+            conn.execute(
+                "INSERT INTO inventory_items (id, sku, name, quantity, last_updated) VALUES ('live', 'LIVE', 'Yerel', 1, 'now')",
+                [],
+            )
+            .unwrap();
+        }
+        assert!(!encryption::is_plaintext_database(&live));
+
+        // Plaintext export from before encryption, one schema version behind
+        let backup = temp_db("plain");
+        {
+            let conn = Connection::open(&backup).unwrap();
+            Database::init_schema(&conn).unwrap();
+            conn.execute_batch(
+                "INSERT INTO categories (id, name, created_at) VALUES ('cat', 'Boya', 'now');
+                 INSERT INTO stock_cards (id, barcode, name, category_id, created_at, updated_at)
+                    VALUES ('card', 'RP1', 'Plastik Boya', 'cat', 'now', 'now');
+                 INSERT INTO inventory_items (id, sku, name, quantity, last_updated) VALUES ('i1', 'RP1', 'Plastik Boya', 7, 'now');",
+            )
+            .unwrap();
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION - 1).unwrap();
+        }
+
+        let schema = restore_database_with_key(&backup, &live.to_string_lossy(), Some(&key)).unwrap();
+        assert!(schema.needs_migration);
+        assert!(!encryption::is_plaintext_database(&live));
+
+        let conn = encryption::open_database_with_key(&live, OpenFlags::default(), Some(&key)).unwrap();
+        encryption::verify_key(&conn).unwrap();
+        let skus: Vec<String> = conn
+            .prepare("SELECT sku FROM inventory_items ORDER BY sku")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(skus, ["RP1"]);
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        // A file that leaves the machine is plaintext and restores back into the keyed database
+        let export = temp_db("export");
+        encryption::export_database_to(&conn, &export, None).unwrap();
+        drop(conn);
+        assert!(encryption::is_plaintext_database(&export));
+        let schema = restore_database_with_key(&export, &live.to_string_lossy(), Some(&key)).unwrap();
+        assert!(!schema.needs_migration);
+
+        for path in [live, backup, export] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
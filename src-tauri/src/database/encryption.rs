@@ -0,0 +1,259 @@
+//! Database Encryption Module
+//!
+//! Yerel veritabanı SQLCipher ile şifrelenir. Anahtar ilk açılışta üretilir ve işletim
+//! sisteminin anahtar deposunda (Windows Credential Manager, macOS Keychain, Secret Service)
+//! tutulur; veritabanı dosyasının yanına yazılmaz. Anahtar deposu kullanılamıyorsa
+//! veritabanı eskisi gibi şifresiz açılır.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use rand::RngCore;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::error::AppError;
+
+const KEYRING_SERVICE: &str = "com.nexus.inventory";
+const KEYRING_ACCOUNT: &str = "database-key";
+
+/// Raw key length in bytes (SQLCipher uses a 256-bit key)
+const KEY_BYTES: usize = 32;
+
+/// Header of every unencrypted SQLite file
+const PLAINTEXT_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Key of the live database for connections opened outside the pool (set once at startup)
+static DATABASE_KEY: OnceLock<Option<String>> = OnceLock::new();
+
+fn is_valid_key(key: &str) -> bool {
+    key.len() == KEY_BYTES * 2 && key.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn generate_key() -> String {
+    let mut bytes = [0u8; KEY_BYTES];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hex key from the OS keyring, created and stored on first run.
+/// None when the keyring is unavailable; the database then stays unencrypted.
+pub fn load_or_create_key() -> Option<String> {
+    let entry = match keyring::Entry::new(KEYRING_SERVICE, KEYRING_ACCOUNT) {
+        Ok(entry) => entry,
+        Err(e) => {
+            eprintln!("[Encryption] Anahtar deposu kullanilamiyor, veritabani sifresiz: {}", e);
+            return None;
+        }
+    };
+
+    match entry.get_password() {
+        Ok(key) if is_valid_key(&key) => Some(key),
+        Ok(_) => {
+            eprintln!("[Encryption] Anahtar deposundaki veritabani anahtari gecersiz, veritabani sifresiz");
+            None
+        }
+        Err(keyring::Error::NoEntry) => {
+            let key = generate_key();
+            match entry.set_password(&key) {
+                Ok(()) => Some(key),
+                Err(e) => {
+                    eprintln!("[Encryption] Veritabani anahtari kaydedilemedi, veritabani sifresiz: {}", e);
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("[Encryption] Veritabani anahtari okunamadi, veritabani sifresiz: {}", e);
+            None
+        }
+    }
+}
+
+/// Remember the key of the live database for `open_database`
+pub fn set_database_key(key: Option<String>) {
+    let _ = DATABASE_KEY.set(key);
+}
+
+/// Key of the live database; None while it is unencrypted
+pub fn database_key() -> Option<&'static str> {
+    DATABASE_KEY.get().and_then(|key| key.as_deref())
+}
+
+/// Issue `PRAGMA key` with a raw hex key; must run before anything else on the connection
+pub fn apply_key(conn: &Connection, key: &str) -> rusqlite::Result<()> {
+    if !is_valid_key(key) {
+        return Err(rusqlite::Error::InvalidParameterName("key".to_string()));
+    }
+    conn.execute_batch(&format!("PRAGMA key = \"x'{}'\";", key))
+}
+
+/// Fail early with a clear message when the key does not decrypt the file
+pub fn verify_key(conn: &Connection) -> Result<(), AppError> {
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+        .map(|_| ())
+        .map_err(|e| {
+            AppError::Internal(format!(
+                "Veritabani acilamadi, sifreleme anahtari bu dosyaya ait degil: {}",
+                e
+            ))
+        })
+}
+
+/// Whether `path` is an existing, unencrypted SQLite file.
+/// Missing or empty files are not: they are created encrypted once the key is set.
+pub fn is_plaintext_database(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    match std::fs::File::open(path) {
+        Ok(mut file) => std::io::Read::read_exact(&mut file, &mut header).is_ok() && &header == PLAINTEXT_HEADER,
+        Err(_) => false,
+    }
+}
+
+/// Open a database file with `flags`, applying the live database key unless the file is
+/// a plaintext SQLite file (e.g. a backup taken before encryption was enabled).
+pub fn open_database_with_flags(path: impl AsRef<Path>, flags: OpenFlags) -> rusqlite::Result<Connection> {
+    open_database_with_key(path, flags, database_key())
+}
+
+/// `open_database_with_flags` with an explicit key instead of the live database's
+pub fn open_database_with_key(path: impl AsRef<Path>, flags: OpenFlags, key: Option<&str>) -> rusqlite::Result<Connection> {
+    let path = path.as_ref();
+    let conn = Connection::open_with_flags(path, flags)?;
+    if let Some(key) = key {
+        if !is_plaintext_database(path) {
+            apply_key(&conn, key)?;
+        }
+    }
+    Ok(conn)
+}
+
+/// `Connection::open` for database files; see `open_database_with_flags`
+pub fn open_database(path: impl AsRef<Path>) -> rusqlite::Result<Connection> {
+    open_database_with_flags(path, OpenFlags::default())
+}
+
+/// Write everything in `conn` into a new database file at `target`, encrypted with `key` or
+/// plaintext without one, keeping the schema version. Uses `ATTACH ... KEY` + `sqlcipher_export`
+/// because SQLCipher refuses the backup API between encrypted and plaintext databases.
+pub fn export_database_to(conn: &Connection, target: &Path, key: Option<&str>) -> Result<(), AppError> {
+    let key_clause = match key {
+        Some(key) if is_valid_key(key) => format!("\"x'{}'\"", key),
+        Some(_) => return Err(AppError::Validation("Gecersiz sifreleme anahtari".to_string())),
+        None => "''".to_string(),
+    };
+    for path in [target.to_path_buf(), sidecar_path(target, "-wal"), sidecar_path(target, "-shm")] {
+        let _ = std::fs::remove_file(path);
+    }
+
+    let user_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    conn.execute(
+        &format!("ATTACH DATABASE ?1 AS export_target KEY {}", key_clause),
+        [target.to_string_lossy().to_string()],
+    )?;
+    let exported = (|| {
+        conn.query_row("SELECT sqlcipher_export('export_target')", [], |_| Ok(()))
+            .map_err(|e| AppError::sqlite("Veritabani disa aktarilamadi", e))?;
+        conn.execute_batch(&format!("PRAGMA export_target.user_version = {};", user_version))?;
+        Ok(())
+    })();
+    let detached = conn.execute_batch("DETACH DATABASE export_target;").map_err(AppError::from);
+    if let Err(e) = exported.and(detached) {
+        let _ = std::fs::remove_file(target);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Plaintext copy of the database at `source` for files that leave this machine (exports,
+/// off-site backups): another install has its own keyring key and could not open a keyed copy.
+pub fn export_plaintext_copy(source: &Path, target: &Path) -> Result<(), AppError> {
+    let conn = open_database(source).map_err(|e| AppError::sqlite("Veritabani acilamadi", e))?;
+    export_database_to(&conn, target, None)
+}
+
+fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// One-time conversion of an unencrypted database in place.
+///
+/// The plaintext file is exported into an encrypted copy (`export_database_to`), which then
+/// replaces the original (WAL and shared memory files of the old file are removed).
+pub fn encrypt_plaintext_database(path: &Path, key: &str) -> Result<(), AppError> {
+    if !is_valid_key(key) {
+        return Err(AppError::Validation("Gecersiz sifreleme anahtari".to_string()));
+    }
+    let encrypted_path = sidecar_path(path, ".encrypting");
+    let _ = std::fs::remove_file(&encrypted_path);
+
+    let result = (|| {
+        let conn = Connection::open(path).map_err(|e| AppError::sqlite("Veritabani acilamadi", e))?;
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+            .map_err(|e| AppError::sqlite("WAL checkpoint basarisiz", e))?;
+        export_database_to(&conn, &encrypted_path, Some(key))
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&encrypted_path);
+        return Err(e);
+    }
+
+    std::fs::rename(&encrypted_path, path)?;
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(sidecar_path(path, suffix));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_database_opens_only_with_right_key() {
+        let path = std::env::temp_dir().join(format!("nexus_encrypt_{}.db", uuid::Uuid::new_v4()));
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "PRAGMA journal_mode = WAL;
+                CREATE TABLE current_accounts (id TEXT PRIMARY KEY, phone TEXT, tax_number TEXT);
+                INSERT INTO current_accounts VALUES ('c1', '05321234567', '1234567890');
+                PRAGMA user_version = 7;",
+            )
+            .unwrap();
+        }
+        assert!(is_plaintext_database(&path));
+
+        let key = generate_key();
+        encrypt_plaintext_database(&path, &key).unwrap();
+        assert!(!is_plaintext_database(&path));
+        assert!(!sidecar_path(&path, ".encrypting").exists());
+        // Customer data is no longer readable from the raw file
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!raw.windows(11).any(|w| w == b"05321234567"));
+
+        let conn = Connection::open(&path).unwrap();
+        apply_key(&conn, &key).unwrap();
+        verify_key(&conn).unwrap();
+        let phone: String = conn
+            .query_row("SELECT phone FROM current_accounts WHERE id = 'c1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(phone, "05321234567");
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, 7);
+        drop(conn);
+
+        let conn = Connection::open(&path).unwrap();
+        apply_key(&conn, &generate_key()).unwrap();
+        assert!(verify_key(&conn).is_err());
+        drop(conn);
+
+        let conn = Connection::open(&path).unwrap();
+        assert!(verify_key(&conn).is_err());
+        assert!(apply_key(&conn, "not-a-key").is_err());
+        drop(conn);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,1269 @@
+//! Schema Migrations Module
+//!
+//! Şema sürümü `PRAGMA user_version` içinde tutulur. Her migration kendi sürüm numarasıyla
+//! sırayla ve kendi transaction'ı içinde uygulanır; başarılı olunca `user_version` o sürüme
+//! yükseltilir. Yeni bir şema değişikliği listeye yeni bir migration olarak eklenir.
+
+use rusqlite::{params, Connection};
+
+use crate::error::AppError;
+use crate::services::{finance, inventory};
+
+/// Schema version stored in `PRAGMA user_version`, the version of the last migration
+pub const SCHEMA_VERSION: i32 = 30;
+
+/// One schema step; `up` runs inside a transaction together with the `user_version` bump
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> Result<(), AppError>,
+}
+
+/// All migrations in the order they are applied
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration { version: 1, description: "baseline tables", up: baseline },
+        Migration { version: 2, description: "item_attributes", up: item_attributes },
+        Migration { version: 3, description: "import_staging", up: import_staging },
+        Migration { version: 4, description: "health_snapshots", up: health_snapshots },
+        Migration { version: 5, description: "inventory_items.min_stock_level", up: min_stock_level },
+        Migration { version: 6, description: "sale_lot_consumptions, transactions.cost_of_goods", up: sale_lot_consumptions },
+        Migration { version: 7, description: "transactions.status", up: transaction_status },
+        Migration { version: 8, description: "transactions.tax_total", up: transaction_tax_total },
+        Migration { version: 9, description: "z_reports", up: z_reports },
+        Migration { version: 10, description: "canonical payment methods", up: canonical_payment_methods },
+        Migration { version: 11, description: "inventory_items.deleted_at", up: item_soft_delete },
+        Migration { version: 12, description: "sync_conflicts", up: sync_conflicts },
+        Migration { version: 13, description: "sync_received, lan_sync_peers", up: lan_sync },
+        Migration { version: 14, description: "invoice_counters", up: invoice_counters },
+        Migration { version: 15, description: "transactions.original_transaction_id", up: original_transaction_id },
+        Migration { version: 16, description: "transaction_payments", up: transaction_payments },
+        Migration { version: 17, description: "purchase_orders", up: purchase_orders },
+        Migration { version: 18, description: "goods_receipts.purchase_order_id", up: goods_receipt_purchase_order },
+        Migration { version: 19, description: "sessions", up: sessions },
+        Migration { version: 20, description: "auth_events", up: auth_events },
+        Migration { version: 21, description: "password_history", up: password_history },
+        Migration { version: 22, description: "inventory_lots.expiry_date", up: lot_expiry },
+        Migration { version: 23, description: "exchange_rates", up: exchange_rates },
+        Migration { version: 24, description: "inventory_fts", up: inventory_fts },
+        Migration { version: 25, description: "inventory_items.unit, sync_outbox REAL quantity", up: item_unit },
+        Migration { version: 26, description: "transactions discounts", up: transaction_discounts },
+        Migration { version: 27, description: "recurring_expenses", up: recurring_expenses },
+        Migration { version: 28, description: "inventory_items negative quantity", up: negative_stock },
+        Migration { version: 29, description: "sku_counters", up: sku_counters },
+        Migration { version: 30, description: "sync_state.last_error", up: sync_last_error },
+    ]
+}
+
+/// Apply every migration newer than the database's `user_version`.
+/// Databases created before versioning report 0 and start from the baseline.
+pub fn run_migrations(conn: &Connection) -> Result<(), AppError> {
+    let current: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if current > SCHEMA_VERSION {
+        // Written by a newer build; leave it as it is rather than stamping it down
+        eprintln!(
+            "[Schema] Veritabani daha yeni bir surume ait (v{}, bu surum v{}), migration atlandi",
+            current, SCHEMA_VERSION
+        );
+        return Ok(());
+    }
+
+    let pending: Vec<Migration> = migrations().into_iter().filter(|m| m.version > current).collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    // Table rebuilds drop the old table, which would cascade to its children with foreign keys on.
+    // The pragma has no effect inside a transaction, so it is switched around the whole run.
+    let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let result = pending.into_iter().try_for_each(|migration| {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx).map_err(|e| {
+            AppError::Internal(format!(
+                "Migration v{} ({}) basarisiz: {}",
+                migration.version, migration.description, e
+            ))
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        Ok(())
+    });
+    conn.pragma_update(None, "foreign_keys", foreign_keys)?;
+    result
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, AppError> {
+    let count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// `ALTER TABLE ... ADD COLUMN` unless the column is already there; returns whether it was added
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<bool, AppError> {
+    if has_column(conn, table, column)? {
+        return Ok(false);
+    }
+    conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, definition))?;
+    Ok(true)
+}
+
+const BASELINE_TABLES: &str = "
+    -- Inventory Items Table
+    CREATE TABLE IF NOT EXISTS inventory_items (
+        id TEXT PRIMARY KEY NOT NULL,
+        sku TEXT UNIQUE NOT NULL,
+        name TEXT NOT NULL,
+        category TEXT DEFAULT 'Genel',
+        quantity INTEGER DEFAULT 0 CHECK (quantity >= 0),
+        location TEXT DEFAULT '',
+        price REAL DEFAULT 0.00 CHECK (price >= 0),
+        cost_price REAL DEFAULT 0.00,
+        image TEXT,
+        description TEXT,
+        ai_tags TEXT,
+        last_updated TEXT NOT NULL,
+        currency TEXT DEFAULT 'TL',
+        supplier_id TEXT
+    );
+
+    -- Transactions Table
+    CREATE TABLE IF NOT EXISTS transactions (
+        id TEXT PRIMARY KEY NOT NULL,
+        items TEXT NOT NULL,
+        total REAL NOT NULL,
+        payment_method TEXT DEFAULT 'Nakit',
+        transaction_type TEXT DEFAULT 'SALE' CHECK (transaction_type IN ('SALE', 'RETURN', 'EXPENSE', 'COLLECTION', 'PURCHASE')),
+        note TEXT,
+        created_at TEXT NOT NULL,
+        customer_id TEXT
+    );
+
+    -- Users Table
+    CREATE TABLE IF NOT EXISTS users (
+        id TEXT PRIMARY KEY NOT NULL,
+        username TEXT UNIQUE NOT NULL,
+        password_hash TEXT NOT NULL,
+        display_name TEXT NOT NULL,
+        role TEXT NOT NULL DEFAULT 'user' CHECK (role IN ('admin', 'user')),
+        created_at TEXT NOT NULL,
+        last_login TEXT,
+        failed_login_attempts INTEGER DEFAULT 0,
+        locked_until TEXT,
+        dealer_id TEXT,
+        must_change_password BOOLEAN DEFAULT 0
+    );
+
+    -- License Table (tek kayıt tutacak)
+    CREATE TABLE IF NOT EXISTS license (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        license_key TEXT NOT NULL,
+        dealer_id TEXT NOT NULL,
+        dealer_name TEXT NOT NULL,
+        mac_address TEXT NOT NULL,
+        activated_at TEXT NOT NULL,
+        expires_at TEXT,
+        is_active INTEGER DEFAULT 1,
+        last_validated TEXT,
+        api_base_url TEXT NOT NULL
+    );
+
+    -- Cloud Sync Status Table
+    CREATE TABLE IF NOT EXISTS sync_status (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        last_backup_at TEXT,
+        last_restore_at TEXT,
+        auto_sync_enabled INTEGER DEFAULT 0,
+        auto_sync_interval_minutes INTEGER DEFAULT 30
+    );
+
+    -- Settings Table
+    CREATE TABLE IF NOT EXISTS settings (
+        key TEXT PRIMARY KEY NOT NULL,
+        value TEXT
+    );
+
+    -- Finance Records Table
+    CREATE TABLE IF NOT EXISTS finance_records (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        record_type TEXT NOT NULL CHECK (record_type IN ('INCOME', 'EXPENSE')),
+        category TEXT NOT NULL,
+        amount REAL NOT NULL,
+        payment_method TEXT NOT NULL DEFAULT 'NAKIT',
+        description TEXT DEFAULT '',
+        date TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+
+    -- Access Codes Table
+    CREATE TABLE IF NOT EXISTS access_codes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        code TEXT UNIQUE NOT NULL,
+        name TEXT NOT NULL,
+        role TEXT DEFAULT 'user',
+        created_at TEXT NOT NULL
+    );
+
+    -- Activity Log Table
+    CREATE TABLE IF NOT EXISTS activity_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        action_type TEXT NOT NULL,
+        description TEXT NOT NULL,
+        item_id TEXT,
+        item_name TEXT,
+        quantity_change INTEGER,
+        value REAL,
+        user_id TEXT,
+        created_at TEXT NOT NULL
+    );
+
+    -- Current Accounts (Cari) Table
+    CREATE TABLE IF NOT EXISTS current_accounts (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        account_type TEXT NOT NULL DEFAULT 'CUSTOMER' CHECK (account_type IN ('CUSTOMER', 'SUPPLIER', 'BOTH')),
+        tax_number TEXT,
+        phone TEXT,
+        email TEXT,
+        address TEXT,
+        note TEXT,
+        payment_term INTEGER DEFAULT 0,
+        balance REAL DEFAULT 0.00,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+    -- Stock Cards Table
+    CREATE TABLE IF NOT EXISTS stock_cards (
+        id TEXT PRIMARY KEY NOT NULL,
+        barcode TEXT UNIQUE NOT NULL,
+        name TEXT NOT NULL,
+        brand TEXT,
+        unit TEXT NOT NULL DEFAULT 'ADET',
+        category_id TEXT,
+        description TEXT,
+        image TEXT,
+        supplier_id TEXT,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+    -- Categories Table
+    CREATE TABLE IF NOT EXISTS categories (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        parent_id TEXT,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (parent_id) REFERENCES categories(id) ON DELETE CASCADE
+    );
+
+    -- Inventory Lots Table (for lot/batch tracking)
+    CREATE TABLE IF NOT EXISTS inventory_lots (
+        id TEXT PRIMARY KEY NOT NULL,
+        product_id TEXT NOT NULL,
+        supplier_id TEXT,
+        quantity INTEGER NOT NULL,
+        initial_quantity INTEGER NOT NULL,
+        buy_price REAL NOT NULL,
+        sell_price REAL,
+        receipt_date TEXT NOT NULL,
+        invoice_no TEXT,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (product_id) REFERENCES inventory_items(id) ON DELETE CASCADE,
+        FOREIGN KEY (supplier_id) REFERENCES current_accounts(id)
+    );
+
+    -- Sync Transactions Table (Local outbox for pending sync)
+    CREATE TABLE IF NOT EXISTS sync_outbox (
+        id TEXT PRIMARY KEY NOT NULL,
+        action_type TEXT NOT NULL CHECK (action_type IN ('SALE', 'STOCK_IN', 'STOCK_OUT', 'PRICE_CHANGE', 'ITEM_CREATE', 'ITEM_UPDATE', 'ITEM_DELETE')),
+        item_sku TEXT,
+        item_name TEXT,
+        quantity_change REAL DEFAULT 0,
+        old_value REAL,
+        new_value REAL,
+        metadata TEXT,
+        transaction_time TEXT NOT NULL,
+        synced INTEGER DEFAULT 0,
+        created_at TEXT NOT NULL
+    );
+
+    -- Sync State Table (Track last sync position)
+    CREATE TABLE IF NOT EXISTS sync_state (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        last_push_at TEXT,
+        last_pull_at TEXT,
+        last_received_id TEXT,
+        sync_in_progress INTEGER DEFAULT 0
+    );
+
+    -- Expense Categories Table
+    CREATE TABLE IF NOT EXISTS expense_categories (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT UNIQUE NOT NULL
+    );
+
+    -- Category Margins Table (target markup over cost per category)
+    CREATE TABLE IF NOT EXISTS category_margins (
+        category TEXT PRIMARY KEY NOT NULL,
+        target_margin_percent REAL NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+    -- Price History Table
+    CREATE TABLE IF NOT EXISTS price_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        item_id TEXT NOT NULL,
+        sku TEXT,
+        old_price REAL,
+        new_price REAL NOT NULL,
+        cost_price REAL,
+        reason TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+
+    -- Goods Receipts Table (Mal Kabul header; lines are inventory_lots.goods_receipt_id)
+    CREATE TABLE IF NOT EXISTS goods_receipts (
+        id TEXT PRIMARY KEY NOT NULL,
+        invoice_no TEXT,
+        supplier_id TEXT,
+        total_amount REAL NOT NULL DEFAULT 0,
+        payment_method TEXT NOT NULL,
+        description TEXT DEFAULT '',
+        date TEXT NOT NULL,
+        transaction_id TEXT,
+        finance_record_id INTEGER,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (supplier_id) REFERENCES current_accounts(id)
+    );
+
+    -- Product Components Table (bundle/kit bill of materials)
+    CREATE TABLE IF NOT EXISTS product_components (
+        parent_sku TEXT NOT NULL,
+        component_sku TEXT NOT NULL,
+        quantity INTEGER NOT NULL CHECK (quantity > 0),
+        PRIMARY KEY (parent_sku, component_sku)
+    );
+";
+
+const BASELINE_INDEXES: &str = "
+    -- Performance Indexes
+    CREATE INDEX IF NOT EXISTS idx_sync_outbox_synced ON sync_outbox(synced);
+    CREATE INDEX IF NOT EXISTS idx_sync_outbox_time ON sync_outbox(transaction_time);
+    CREATE INDEX IF NOT EXISTS idx_inventory_sku ON inventory_items(sku);
+    CREATE INDEX IF NOT EXISTS idx_inventory_category ON inventory_items(category);
+    CREATE INDEX IF NOT EXISTS idx_inventory_quantity ON inventory_items(quantity);
+    CREATE INDEX IF NOT EXISTS idx_transactions_created_at ON transactions(created_at);
+    CREATE INDEX IF NOT EXISTS idx_transactions_type ON transactions(transaction_type);
+    CREATE INDEX IF NOT EXISTS idx_transactions_payment ON transactions(payment_method);
+    CREATE INDEX IF NOT EXISTS idx_activity_created_at ON activity_log(created_at);
+    CREATE INDEX IF NOT EXISTS idx_users_username ON users(username);
+    CREATE INDEX IF NOT EXISTS idx_current_accounts_name ON current_accounts(name);
+    CREATE INDEX IF NOT EXISTS idx_stock_cards_barcode ON stock_cards(barcode);
+    CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id);
+    CREATE INDEX IF NOT EXISTS idx_inventory_lots_product ON inventory_lots(product_id);
+    CREATE INDEX IF NOT EXISTS idx_inventory_lots_created ON inventory_lots(created_at);
+    CREATE INDEX IF NOT EXISTS idx_inventory_items_supplier ON inventory_items(supplier_id);
+    CREATE INDEX IF NOT EXISTS idx_finance_records_date ON finance_records(date);
+    CREATE INDEX IF NOT EXISTS idx_finance_records_payment ON finance_records(payment_method);
+    CREATE INDEX IF NOT EXISTS idx_price_history_item ON price_history(item_id);
+    CREATE INDEX IF NOT EXISTS idx_goods_receipts_supplier ON goods_receipts(supplier_id);
+    CREATE INDEX IF NOT EXISTS idx_goods_receipts_date ON goods_receipts(date);
+    CREATE INDEX IF NOT EXISTS idx_product_components_component ON product_components(component_sku);
+";
+
+/// v1: the schema as it was when versioning started. Databases from before versioning
+/// may lack columns and constraints that were added ad hoc back then; they are brought
+/// up to the same baseline here.
+fn baseline(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(BASELINE_TABLES)?;
+
+    add_column(conn, "inventory_items", "supplier_id", "TEXT")?;
+    add_column(conn, "inventory_items", "brand", "TEXT")?;
+    add_column(conn, "transactions", "customer_id", "TEXT")?;
+    add_column(conn, "stock_cards", "supplier_id", "TEXT")?;
+    add_column(conn, "users", "must_change_password", "BOOLEAN DEFAULT 0")?;
+    add_column(conn, "current_accounts", "parent_account_id", "TEXT REFERENCES current_accounts(id)")?;
+
+    // EXPENSE and PURCHASE were added to the transaction_type CHECK constraint later
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type='table' AND name='transactions'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !sql.contains("'EXPENSE'") || !sql.contains("'PURCHASE'") {
+        conn.execute_batch(
+            "
+            ALTER TABLE transactions RENAME TO transactions_old;
+
+            CREATE TABLE transactions (
+                id TEXT PRIMARY KEY NOT NULL,
+                items TEXT NOT NULL,
+                total REAL NOT NULL,
+                payment_method TEXT DEFAULT 'Nakit',
+                transaction_type TEXT DEFAULT 'SALE' CHECK (transaction_type IN ('SALE', 'RETURN', 'EXPENSE', 'COLLECTION', 'PURCHASE')),
+                note TEXT,
+                created_at TEXT NOT NULL,
+                customer_id TEXT
+            );
+
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id)
+            SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id
+            FROM transactions_old;
+
+            DROP TABLE transactions_old;
+            ",
+        )?;
+    }
+
+    conn.execute_batch(BASELINE_INDEXES)?;
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_current_accounts_parent ON current_accounts(parent_account_id);")?;
+
+    // Link inventory_lots to goods_receipts and backfill headers from existing history
+    if add_column(conn, "inventory_lots", "goods_receipt_id", "TEXT")? {
+        backfill_goods_receipts(conn)?;
+    }
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_inventory_lots_receipt ON inventory_lots(goods_receipt_id);")?;
+    Ok(())
+}
+
+/// Headers for goods receipts saved before there was a goods_receipts table. Each receipt left
+/// its lots, a MAL_KABUL finance record (unless VADELI) and, with a supplier, a PURCHASE
+/// transaction noted "Mal Kabul Fatura: <no>", all with the same created_at. Receipts from the
+/// same second are told apart by amount and payment method, their lots by supplier and invoice.
+fn backfill_goods_receipts(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        INSERT INTO goods_receipts (id, invoice_no, supplier_id, total_amount, payment_method, description, date, transaction_id, created_at)
+        SELECT p.id, p.invoice_no, p.customer_id, p.total, COALESCE(p.payment_method, ''), COALESCE(p.note, ''),
+               COALESCE((SELECT l.receipt_date FROM inventory_lots l
+                         WHERE l.created_at = p.created_at AND l.supplier_id IS p.customer_id AND l.invoice_no IS p.invoice_no
+                         LIMIT 1), substr(p.created_at, 1, 10)),
+               p.id, p.created_at
+        FROM (
+            SELECT t.id, t.customer_id, t.total, t.payment_method, t.note, t.created_at,
+                   CASE WHEN t.note LIKE 'Mal Kabul Fatura: %' THEN NULLIF(substr(t.note, 19), '-') END AS invoice_no
+            FROM transactions t
+            WHERE t.transaction_type = 'PURCHASE'
+              AND NOT EXISTS (SELECT 1 FROM goods_receipts g WHERE g.transaction_id = t.id)
+            ORDER BY t.rowid
+        ) p;
+        ",
+    )?;
+
+    let records: Vec<(i64, String, f64, String, Option<String>, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, created_at, amount, COALESCE(payment_method, ''), description, date FROM finance_records fr
+             WHERE category = 'MAL_KABUL'
+               AND NOT EXISTS (SELECT 1 FROM goods_receipts g WHERE g.finance_record_id = fr.id)
+             ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    for (id, created_at, amount, payment_method, description, date) in records {
+        // Paid receipts with a supplier: the first header of that second still without a record
+        let linked = conn.execute(
+            "UPDATE goods_receipts SET finance_record_id = ?1, description = COALESCE(?2, description)
+             WHERE rowid = (SELECT rowid FROM goods_receipts
+                            WHERE finance_record_id IS NULL AND transaction_id IS NOT NULL
+                              AND created_at = ?3 AND total_amount = ?4 AND payment_method = ?5
+                            ORDER BY rowid LIMIT 1)",
+            params![id, description, created_at, amount, payment_method],
+        )?;
+        if linked > 0 {
+            continue;
+        }
+        // Receipts without a supplier only left the finance record; the invoice comes from
+        // their lots, skipping invoices another such receipt of that second already took
+        conn.execute(
+            "INSERT INTO goods_receipts (id, invoice_no, supplier_id, total_amount, payment_method, description, date, finance_record_id, created_at)
+             VALUES ('fr-' || ?1,
+                     (SELECT l.invoice_no FROM inventory_lots l
+                      WHERE l.created_at = ?6 AND l.supplier_id IS NULL
+                        AND NOT EXISTS (SELECT 1 FROM goods_receipts g
+                                        WHERE g.created_at = l.created_at AND g.supplier_id IS NULL AND g.invoice_no IS l.invoice_no)
+                      ORDER BY l.rowid LIMIT 1),
+                     NULL, ?2, ?3, COALESCE(?4, ''), ?5, ?1, ?6)",
+            params![id, amount, payment_method, description, date, created_at],
+        )?;
+    }
+
+    conn.execute_batch(
+        "
+        UPDATE inventory_lots SET goods_receipt_id = (
+            SELECT g.id FROM goods_receipts g
+            WHERE g.created_at = inventory_lots.created_at AND g.supplier_id IS inventory_lots.supplier_id
+              AND g.invoice_no IS inventory_lots.invoice_no
+            ORDER BY g.rowid
+            LIMIT 1
+        )
+        WHERE goods_receipt_id IS NULL;
+        ",
+    )?;
+    Ok(())
+}
+
+/// v2
+fn item_attributes(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        -- Item Attributes Table (variant fields: size, color, base type...)
+        CREATE TABLE IF NOT EXISTS item_attributes (
+            item_id TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (item_id, key)
+        );
+        CREATE INDEX IF NOT EXISTS idx_item_attributes_key_value ON item_attributes(key, value COLLATE NOCASE);
+        ",
+    )?;
+    Ok(())
+}
+
+/// v3
+fn import_staging(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        -- Import Staging Table (dry-run CSV import, reviewed before it goes live)
+        CREATE TABLE IF NOT EXISTS import_staging (
+            batch_id TEXT NOT NULL,
+            sku TEXT NOT NULL,
+            name TEXT NOT NULL,
+            category TEXT,
+            quantity INTEGER DEFAULT 0,
+            location TEXT,
+            price REAL DEFAULT 0.0,
+            cost_price REAL DEFAULT 0.0,
+            currency TEXT,
+            item_id TEXT,
+            current_price REAL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (batch_id, sku)
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+/// v4
+fn health_snapshots(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        -- Health Snapshots Table (scheduled integrity checks)
+        CREATE TABLE IF NOT EXISTS health_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            checked_at TEXT NOT NULL,
+            integrity_ok INTEGER NOT NULL,
+            integrity_messages TEXT NOT NULL DEFAULT '[]',
+            foreign_key_violations INTEGER NOT NULL DEFAULT 0,
+            file_sha256 TEXT,
+            file_size_bytes INTEGER,
+            duration_ms INTEGER
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+/// v5: per-item low stock threshold (existing rows get the old hardcoded 10)
+fn min_stock_level(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "inventory_items", "min_stock_level", "INTEGER NOT NULL DEFAULT 10")?;
+    Ok(())
+}
+
+/// v6: cost of goods consumed by a sale (NULL for sales recorded before lot tracking)
+fn sale_lot_consumptions(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        -- Sale Lot Consumptions Table (which purchase lots a sale used, at their buy price)
+        CREATE TABLE IF NOT EXISTS sale_lot_consumptions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            transaction_id TEXT NOT NULL,
+            lot_id TEXT NOT NULL,
+            product_id TEXT NOT NULL,
+            quantity INTEGER NOT NULL,
+            unit_cost REAL NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_sale_lot_consumptions_tx ON sale_lot_consumptions(transaction_id);
+        CREATE INDEX IF NOT EXISTS idx_sale_lot_consumptions_lot ON sale_lot_consumptions(lot_id);
+        ",
+    )?;
+    add_column(conn, "transactions", "cost_of_goods", "REAL")?;
+    Ok(())
+}
+
+/// v7: transaction status ('completed' | 'voided')
+fn transaction_status(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "transactions", "status", "TEXT NOT NULL DEFAULT 'completed'")?;
+    Ok(())
+}
+
+/// v8: KDV included in `total` (0 for sales recorded before tax was tracked)
+fn transaction_tax_total(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "transactions", "tax_total", "REAL NOT NULL DEFAULT 0")?;
+    Ok(())
+}
+
+/// v9: end-of-day closes; one per day
+fn z_reports(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        -- Z Reports Table (daily close, full report kept as JSON)
+        CREATE TABLE IF NOT EXISTS z_reports (
+            id TEXT PRIMARY KEY NOT NULL,
+            report_date TEXT NOT NULL UNIQUE,
+            gross_sales REAL NOT NULL,
+            expected_cash REAL NOT NULL,
+            report TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+/// v10: payment methods stored as `Nakit`, `CASH`, `Kredi Kartı`... rewritten to one spelling each
+fn canonical_payment_methods(conn: &Connection) -> Result<(), AppError> {
+    for table in ["transactions", "finance_records", "goods_receipts"] {
+        finance::canonicalize_payment_methods(conn, table, "payment_method")?;
+    }
+    Ok(())
+}
+
+/// v11: items are moved to the trash instead of being deleted
+fn item_soft_delete(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "inventory_items", "deleted_at", "TEXT")?;
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_inventory_deleted_at ON inventory_items(deleted_at);")?;
+    Ok(())
+}
+
+/// v12: audit of how pulled sync changes were reconciled with local ones
+fn sync_conflicts(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS sync_conflicts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            remote_id TEXT NOT NULL,
+            device_identifier TEXT,
+            action_type TEXT NOT NULL,
+            item_sku TEXT,
+            resolution TEXT NOT NULL CHECK (resolution IN ('REMOTE_WINS', 'LOCAL_WINS', 'MERGED')),
+            remote_time TEXT NOT NULL,
+            local_time TEXT,
+            detail TEXT,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_sync_conflicts_sku ON sync_conflicts(item_sku);
+        ",
+    )?;
+    Ok(())
+}
+
+/// v13: ids of applied remote sync rows (cloud and LAN deliver the same row) and LAN peer cursors
+fn lan_sync(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS sync_received (
+            id TEXT PRIMARY KEY NOT NULL,
+            device_identifier TEXT,
+            received_at TEXT NOT NULL
+        );
+
+        -- Newest outbox times exchanged with each LAN peer (each side's own clock)
+        CREATE TABLE IF NOT EXISTS lan_sync_peers (
+            device_identifier TEXT PRIMARY KEY NOT NULL,
+            address TEXT,
+            last_sent_time TEXT,
+            last_received_time TEXT,
+            last_sync_at TEXT
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+/// v14: last issued invoice sequence per prefix and year
+fn invoice_counters(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS invoice_counters (
+            prefix TEXT NOT NULL,
+            year INTEGER NOT NULL,
+            last_seq INTEGER NOT NULL,
+            PRIMARY KEY (prefix, year)
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+/// v15: returns point at the sale they give goods back from
+fn original_transaction_id(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "transactions", "original_transaction_id", "TEXT")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_transactions_original ON transactions(original_transaction_id);",
+    )?;
+    Ok(())
+}
+
+/// v16: portions of split-paid transactions (`payment_method = 'SPLIT'`)
+fn transaction_payments(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS transaction_payments (
+            transaction_id TEXT NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            method TEXT NOT NULL,
+            amount REAL NOT NULL,
+            PRIMARY KEY (transaction_id, position)
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+/// v17: purchase orders sent to suppliers, matched against goods receipts by `po_number`
+fn purchase_orders(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS purchase_orders (
+            id TEXT PRIMARY KEY NOT NULL,
+            po_number TEXT UNIQUE NOT NULL,
+            supplier_id TEXT NOT NULL REFERENCES current_accounts(id),
+            lines TEXT NOT NULL,
+            total REAL NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open',
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_purchase_orders_supplier ON purchase_orders(supplier_id);
+        ",
+    )?;
+    Ok(())
+}
+
+/// v18: goods receipts point at the purchase order they were received against
+fn goods_receipt_purchase_order(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "goods_receipts", "purchase_order_id", "TEXT")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_goods_receipts_purchase_order ON goods_receipts(purchase_order_id);",
+    )?;
+    Ok(())
+}
+
+/// v19: login sessions; `expires_at` is pushed forward on each use
+fn sessions(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS sessions (
+            token TEXT PRIMARY KEY NOT NULL,
+            user_id TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id);
+        ",
+    )?;
+    Ok(())
+}
+
+/// v20: audit log of logins, logouts and admin password checks
+fn auth_events(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS auth_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            action TEXT NOT NULL,
+            username TEXT NOT NULL,
+            user_id TEXT,
+            success INTEGER NOT NULL,
+            detail TEXT,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_auth_events_created ON auth_events(created_at);
+        ",
+    )?;
+    Ok(())
+}
+
+/// v21: replaced password hashes, so recent passwords cannot be reused
+fn password_history(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS password_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            password_hash TEXT NOT NULL,
+            changed_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_password_history_user ON password_history(user_id);
+        ",
+    )?;
+    Ok(())
+}
+
+/// v22: expiry date of a lot (YYYY-MM-DD, NULL when the goods do not expire)
+fn lot_expiry(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "inventory_lots", "expiry_date", "TEXT")?;
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_inventory_lots_expiry ON inventory_lots(expiry_date);")?;
+    Ok(())
+}
+
+/// v23: TRY value of the currencies items are priced in
+fn exchange_rates(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS exchange_rates (
+            currency TEXT PRIMARY KEY NOT NULL,
+            rate_to_try REAL NOT NULL CHECK (rate_to_try > 0),
+            updated_at TEXT NOT NULL
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+/// Full-text index of item names, SKUs, descriptions and brands, keyed by the item's rowid. The
+/// triggers store the Turkish-folded text; the insert trigger also clears a leftover entry for
+/// the rowid (INSERT OR REPLACE does not fire the delete trigger).
+fn inventory_fts(conn: &Connection) -> Result<(), AppError> {
+    let values = |row: &str| {
+        format!(
+            "{}.rowid, {}, {}, {}, {}",
+            row,
+            inventory::fold_search_sql(&format!("{}.sku", row)),
+            inventory::fold_search_sql(&format!("{}.name", row)),
+            inventory::fold_search_sql(&format!("{}.description", row)),
+            inventory::fold_search_sql(&format!("{}.brand", row)),
+        )
+    };
+    conn.execute_batch(&format!(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS inventory_fts USING fts5(
+            sku, name, description, brand,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS inventory_fts_insert AFTER INSERT ON inventory_items BEGIN
+            DELETE FROM inventory_fts WHERE rowid = new.rowid;
+            INSERT INTO inventory_fts (rowid, sku, name, description, brand) VALUES ({new});
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS inventory_fts_delete AFTER DELETE ON inventory_items BEGIN
+            DELETE FROM inventory_fts WHERE rowid = old.rowid;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS inventory_fts_update AFTER UPDATE OF sku, name, description, brand ON inventory_items
+        WHEN old.sku IS NOT new.sku OR old.name IS NOT new.name
+          OR old.description IS NOT new.description OR old.brand IS NOT new.brand
+        BEGIN
+            DELETE FROM inventory_fts WHERE rowid = old.rowid;
+            INSERT INTO inventory_fts (rowid, sku, name, description, brand) VALUES ({new});
+        END;
+        ",
+        new = values("new"),
+    ))?;
+    inventory::rebuild_search_index(conn)
+}
+
+/// v25: unit of measure on the item, copied from its stock card (same id or barcode = SKU).
+/// Quantities of measured units (KG, MT...) are stored fractional in the existing columns.
+/// The outbox column is declared INTEGER in older databases and is rebuilt as REAL.
+fn item_unit(conn: &Connection) -> Result<(), AppError> {
+    if add_column(conn, "inventory_items", "unit", "TEXT NOT NULL DEFAULT 'ADET'")? {
+        conn.execute_batch(
+            "UPDATE inventory_items SET unit = coalesce((
+                 SELECT upper(trim(s.unit)) FROM stock_cards s
+                 WHERE (s.id = inventory_items.id OR s.barcode = inventory_items.sku) AND trim(s.unit) != ''
+                 LIMIT 1
+             ), 'ADET');",
+        )?;
+    }
+    outbox_real_quantity(conn)
+}
+
+/// Outbox tables created before v25 declare `quantity_change` INTEGER; rebuild them with a REAL
+/// column so deltas of measured units (0.4 KG) keep their fraction
+fn outbox_real_quantity(conn: &Connection) -> Result<(), AppError> {
+    let declared: String = conn.query_row(
+        "SELECT type FROM pragma_table_info('sync_outbox') WHERE name = 'quantity_change'",
+        [],
+        |row| row.get(0),
+    )?;
+    if declared.eq_ignore_ascii_case("REAL") {
+        return Ok(());
+    }
+
+    conn.execute_batch(
+        "
+        ALTER TABLE sync_outbox RENAME TO sync_outbox_old;
+
+        CREATE TABLE sync_outbox (
+            id TEXT PRIMARY KEY NOT NULL,
+            action_type TEXT NOT NULL CHECK (action_type IN ('SALE', 'STOCK_IN', 'STOCK_OUT', 'PRICE_CHANGE', 'ITEM_CREATE', 'ITEM_UPDATE', 'ITEM_DELETE')),
+            item_sku TEXT,
+            item_name TEXT,
+            quantity_change REAL DEFAULT 0,
+            old_value REAL,
+            new_value REAL,
+            metadata TEXT,
+            transaction_time TEXT NOT NULL,
+            synced INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL
+        );
+
+        INSERT INTO sync_outbox (id, action_type, item_sku, item_name, quantity_change, old_value, new_value, metadata, transaction_time, synced, created_at)
+        SELECT id, action_type, item_sku, item_name, quantity_change, old_value, new_value, metadata, transaction_time, synced, created_at
+        FROM sync_outbox_old;
+
+        DROP TABLE sync_outbox_old;
+
+        CREATE INDEX IF NOT EXISTS idx_sync_outbox_synced ON sync_outbox(synced);
+        CREATE INDEX IF NOT EXISTS idx_sync_outbox_time ON sync_outbox(transaction_time);
+        ",
+    )?;
+    Ok(())
+}
+
+/// v26: discount breakdown of a sale; per-line discounts stay in the `items` JSON
+fn transaction_discounts(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "transactions", "line_discount", "REAL NOT NULL DEFAULT 0")?;
+    add_column(conn, "transactions", "ticket_discount", "REAL NOT NULL DEFAULT 0")?;
+    Ok(())
+}
+
+/// v27: monthly expenses (rent, utilities) generated automatically once their day has come.
+/// `last_generated_period` ("YYYY-MM") is the month whose expense was already written.
+fn recurring_expenses(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS recurring_expenses (
+            id TEXT PRIMARY KEY NOT NULL,
+            description TEXT NOT NULL,
+            amount REAL NOT NULL CHECK (amount > 0),
+            category TEXT NOT NULL,
+            payment_method TEXT NOT NULL,
+            day_of_month INTEGER NOT NULL CHECK (day_of_month BETWEEN 1 AND 31),
+            active INTEGER NOT NULL DEFAULT 1,
+            last_generated_period TEXT,
+            created_at TEXT NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// Quantity constraint of the baseline `inventory_items` table
+const QUANTITY_CHECK: &str = " CHECK (quantity >= 0)";
+
+/// v28: the quantity may go negative when oversold sales are allowed (backorder). SQLite cannot drop
+/// a CHECK in place, so the table is rebuilt from its own schema text without it: create, copy with
+/// the rowids (the search index is keyed by them), drop, rename and recreate the indexes and triggers.
+/// `run_migrations` turns foreign keys off around this, otherwise dropping the table would
+/// cascade-delete its lots.
+fn negative_stock(conn: &Connection) -> Result<(), AppError> {
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type='table' AND name='inventory_items'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !sql.contains(QUANTITY_CHECK) {
+        return Ok(());
+    }
+    let Some(body_start) = sql.find('(') else {
+        return Err(AppError::Internal("inventory_items semasi okunamadi".to_string()));
+    };
+
+    let dependents: Vec<String> = conn
+        .prepare(
+            "SELECT sql FROM sqlite_master
+             WHERE tbl_name = 'inventory_items' AND type IN ('index', 'trigger') AND sql IS NOT NULL",
+        )?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let columns: Vec<String> = conn
+        .prepare("SELECT name FROM pragma_table_info('inventory_items')")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let columns = columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ");
+
+    conn.execute_batch(&format!(
+        "CREATE TABLE inventory_items_new {};
+         INSERT INTO inventory_items_new (rowid, {columns}) SELECT rowid, {columns} FROM inventory_items;
+         DROP TABLE inventory_items;
+         ALTER TABLE inventory_items_new RENAME TO inventory_items;",
+        sql[body_start..].replace(QUANTITY_CHECK, ""),
+        columns = columns,
+    ))?;
+    for statement in dependents {
+        conn.execute_batch(&statement)?;
+    }
+    Ok(())
+}
+
+/// v29: last generated internal SKU sequence per prefix (`inventory::generate_internal_sku`)
+fn sku_counters(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sku_counters (
+            prefix TEXT PRIMARY KEY NOT NULL,
+            last_seq INTEGER NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// v30: message of the last failed device sync, cleared by the next successful one
+fn sync_last_error(conn: &Connection) -> Result<(), AppError> {
+    add_column(conn, "sync_state", "last_error", "TEXT")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)").unwrap();
+        let names = stmt.query_map([table], |row| row.get(0)).unwrap();
+        names.collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn test_migrations_upgrade_version_zero_database() {
+        let versions: Vec<i32> = migrations().iter().map(|m| m.version).collect();
+        assert_eq!(versions, (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+
+        // Pre-versioning database: old transaction_type constraint, columns that were added ad hoc missing
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (
+                id TEXT PRIMARY KEY NOT NULL, sku TEXT UNIQUE NOT NULL, name TEXT NOT NULL, category TEXT DEFAULT 'Genel',
+                quantity INTEGER DEFAULT 0, location TEXT DEFAULT '', price REAL DEFAULT 0.00, cost_price REAL DEFAULT 0.00,
+                image TEXT, description TEXT, ai_tags TEXT, last_updated TEXT NOT NULL, currency TEXT DEFAULT 'TL'
+            );
+            CREATE TABLE transactions (
+                id TEXT PRIMARY KEY NOT NULL, items TEXT NOT NULL, total REAL NOT NULL, payment_method TEXT DEFAULT 'Nakit',
+                transaction_type TEXT DEFAULT 'SALE' CHECK (transaction_type IN ('SALE', 'RETURN')), note TEXT,
+                created_at TEXT NOT NULL, customer_id TEXT
+            );
+            CREATE TABLE users (
+                id TEXT PRIMARY KEY NOT NULL, username TEXT UNIQUE NOT NULL, password_hash TEXT NOT NULL,
+                display_name TEXT NOT NULL, role TEXT NOT NULL DEFAULT 'user', created_at TEXT NOT NULL
+            );
+            CREATE TABLE sync_outbox (
+                id TEXT PRIMARY KEY NOT NULL, action_type TEXT NOT NULL, item_sku TEXT, item_name TEXT,
+                quantity_change INTEGER DEFAULT 0, old_value REAL, new_value REAL, metadata TEXT,
+                transaction_time TEXT NOT NULL, synced INTEGER DEFAULT 0, created_at TEXT NOT NULL
+            );
+            INSERT INTO inventory_items (id, sku, name, last_updated) VALUES ('i1', 'RP1-25', 'Plastik Boya', 'now');
+            INSERT INTO transactions (id, items, total, created_at) VALUES ('t1', '[]', 100.0, '2024-01-01T10:00:00Z');
+            INSERT INTO sync_outbox (id, action_type, item_sku, quantity_change, transaction_time, created_at)
+            VALUES ('o1', 'SALE', 'RP1-25', -2, 'now', 'now');",
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        let expected = [
+            ("inventory_items", &["supplier_id", "brand", "min_stock_level", "deleted_at", "unit"][..]),
+            ("transactions", &["customer_id", "cost_of_goods", "status", "tax_total", "original_transaction_id", "line_discount", "ticket_discount"][..]),
+            ("users", &["must_change_password"][..]),
+            ("stock_cards", &["supplier_id"][..]),
+            ("current_accounts", &["parent_account_id"][..]),
+            ("inventory_lots", &["goods_receipt_id", "expiry_date"][..]),
+            ("item_attributes", &["item_id", "key", "value"][..]),
+            ("import_staging", &["batch_id"][..]),
+            ("health_snapshots", &["integrity_ok"][..]),
+            ("sale_lot_consumptions", &["lot_id", "unit_cost"][..]),
+            ("z_reports", &["report_date", "report"][..]),
+            ("sync_conflicts", &["remote_id", "resolution"][..]),
+            ("sync_received", &["id", "received_at"][..]),
+            ("lan_sync_peers", &["address", "last_sent_time", "last_received_time"][..]),
+            ("invoice_counters", &["prefix", "year", "last_seq"][..]),
+            ("transaction_payments", &["transaction_id", "position", "method", "amount"][..]),
+            ("purchase_orders", &["po_number", "supplier_id", "lines", "total", "status"][..]),
+            ("goods_receipts", &["purchase_order_id"][..]),
+            ("sessions", &["token", "user_id", "created_at", "expires_at"][..]),
+            ("auth_events", &["action", "username", "user_id", "success", "detail", "created_at"][..]),
+            ("password_history", &["user_id", "password_hash", "changed_at"][..]),
+            ("exchange_rates", &["currency", "rate_to_try", "updated_at"][..]),
+            ("inventory_fts", &["sku", "name", "description", "brand"][..]),
+            ("recurring_expenses", &["day_of_month", "active", "last_generated_period"][..]),
+            ("sku_counters", &["prefix", "last_seq"][..]),
+            ("sync_state", &["last_error"][..]),
+        ];
+        for (table, expected_columns) in expected {
+            let actual = columns(&conn, table);
+            for column in expected_columns {
+                assert!(actual.iter().any(|c| c == column), "{}.{} missing", table, column);
+            }
+        }
+
+        let outbox_type: String = conn
+            .query_row("SELECT type FROM pragma_table_info('sync_outbox') WHERE name = 'quantity_change'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(outbox_type, "REAL");
+        let outbox_change: f64 = conn
+            .query_row("SELECT quantity_change FROM sync_outbox WHERE id = 'o1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(outbox_change, -2.0);
+
+        // Existing rows survive and get the column defaults
+        let (status, min_stock, payment_method): (String, i32, String) = conn
+            .query_row(
+                "SELECT t.status, i.min_stock_level, t.payment_method FROM transactions t, inventory_items i WHERE t.id = 't1' AND i.id = 'i1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((status.as_str(), min_stock), ("completed", 10));
+        // The old 'Nakit' default is rewritten to the canonical code
+        assert_eq!(payment_method, "NAKIT");
+        // Existing items are indexed for search
+        assert_eq!(inventory::search_items(&conn, "boya", 10).unwrap().len(), 1);
+        conn.execute(
+            "INSERT INTO transactions (id, items, total, transaction_type, created_at) VALUES ('t2', '[]', 5.0, 'PURCHASE', 'now')",
+            [],
+        )
+        .unwrap();
+
+        // Up-to-date databases are left alone
+        run_migrations(&conn).unwrap();
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_goods_receipt_backfill_from_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(BASELINE_TABLES).unwrap();
+        // Saved by the old process_goods_receipt; the first three in the same second, in this order:
+        // 1. supplier s1, NAKIT, invoice F-1: finance record, lot, PURCHASE
+        // 2. supplier s2, VADELI, invoice F-2: lot, PURCHASE (no cash moved, no finance record)
+        // 3. no supplier, NAKIT, invoice F-3, same amount as the first: finance record, lot
+        // 4. later, no supplier, HAVALE, no invoice: finance record, lot
+        conn.execute_batch(
+            "INSERT INTO current_accounts (id, name, account_type, created_at, updated_at) VALUES
+                 ('s1', 'Boyaci', 'SUPPLIER', 'now', 'now'),
+                 ('s2', 'Nalbur', 'SUPPLIER', 'now', 'now');
+             INSERT INTO inventory_items (id, sku, name, last_updated) VALUES
+                 ('i1', 'BOYA-1', 'Boya', 'now'), ('i2', 'FIRCA-2', 'Firca', 'now'), ('i3', 'VIDA-3', 'Vida', 'now');
+             INSERT INTO finance_records (id, record_type, category, amount, payment_method, description, date, created_at) VALUES
+                 (1, 'EXPENSE', 'MAL_KABUL', 100.0, 'NAKIT', 'Boya alimi', '2024-03-01', '2024-03-01T10:00:00Z'),
+                 (2, 'EXPENSE', 'MAL_KABUL', 100.0, 'NAKIT', 'Nalbur', '2024-03-01', '2024-03-01T10:00:00Z'),
+                 (3, 'EXPENSE', 'MAL_KABUL', 30.0, 'HAVALE', 'Vida', '2024-03-02', '2024-03-02T08:00:00Z');
+             INSERT INTO inventory_lots (id, product_id, supplier_id, quantity, initial_quantity, buy_price, receipt_date, invoice_no, created_at) VALUES
+                 ('l1', 'i1', 's1', 10, 10, 10.0, '2024-03-01', 'F-1', '2024-03-01T10:00:00Z'),
+                 ('l2', 'i2', 's2', 5, 5, 10.0, '2024-03-01', 'F-2', '2024-03-01T10:00:00Z'),
+                 ('l3', 'i1', NULL, 4, 4, 25.0, '2024-03-01', 'F-3', '2024-03-01T10:00:00Z'),
+                 ('l4', 'i3', NULL, 30, 30, 1.0, '2024-03-02', NULL, '2024-03-02T08:00:00Z');
+             INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES
+                 ('p1', '[]', 100.0, 'NAKIT', 'PURCHASE', 'Mal Kabul Fatura: F-1', '2024-03-01T10:00:00Z', 's1'),
+                 ('p2', '[]', 50.0, 'VADELI', 'PURCHASE', 'Mal Kabul Fatura: F-2', '2024-03-01T10:00:00Z', 's2');",
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        // (id, invoice_no, supplier_id, total_amount, payment_method, description, finance_record_id)
+        type ReceiptRow = (String, Option<String>, Option<String>, f64, String, String, Option<i64>);
+        let receipts: Vec<ReceiptRow> = conn
+            .prepare(
+                "SELECT id, invoice_no, supplier_id, total_amount, payment_method, description, finance_record_id
+                 FROM goods_receipts ORDER BY id",
+            )
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let s = |v: &str| v.to_string();
+        assert_eq!(
+            receipts,
+            [
+                (s("fr-2"), Some(s("F-3")), None, 100.0, s("NAKIT"), s("Nalbur"), Some(2)),
+                (s("fr-3"), None, None, 30.0, s("HAVALE"), s("Vida"), Some(3)),
+                (s("p1"), Some(s("F-1")), Some(s("s1")), 100.0, s("NAKIT"), s("Boya alimi"), Some(1)),
+                // The later payment method migration writes the canonical code
+                (s("p2"), Some(s("F-2")), Some(s("s2")), 50.0, s("VERESIYE"), s("Mal Kabul Fatura: F-2"), None),
+            ]
+        );
+
+        let lots: Vec<(String, Option<String>)> = conn
+            .prepare("SELECT id, goods_receipt_id FROM inventory_lots ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            lots,
+            [
+                (s("l1"), Some(s("p1"))),
+                (s("l2"), Some(s("p2"))),
+                (s("l3"), Some(s("fr-2"))),
+                (s("l4"), Some(s("fr-3"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_negative_stock_migration_keeps_rows_and_lots() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        for migration in migrations().iter().filter(|m| m.version < 28) {
+            (migration.up)(&conn).unwrap();
+        }
+        conn.pragma_update(None, "user_version", 27).unwrap();
+        conn.execute_batch(
+            "INSERT INTO inventory_items (id, sku, name, quantity, last_updated) VALUES ('i1', 'FIRCA-2', 'Firca', 3, 'now');
+             INSERT INTO inventory_items (id, sku, name, quantity, last_updated) VALUES ('i2', 'RULO-9', 'Boya Rulosu', 1, 'now');
+             DELETE FROM inventory_items WHERE id = 'i2';
+             INSERT INTO inventory_items (id, sku, name, quantity, last_updated) VALUES ('i3', 'MALA-4', 'Celik Mala', 2, 'now');
+             INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, created_at)
+             VALUES ('lot-1', 'i1', 3, 3, 20.0, '2024-01-01', 'now');",
+        )
+        .unwrap();
+        assert!(conn.execute("UPDATE inventory_items SET quantity = -2 WHERE id = 'i1'", []).is_err());
+
+        run_migrations(&conn).unwrap();
+        let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+        assert!(foreign_keys);
+        let sql: String = conn
+            .query_row("SELECT sql FROM sqlite_master WHERE type='table' AND name='inventory_items'", [], |row| row.get(0))
+            .unwrap();
+        assert!(!sql.contains(QUANTITY_CHECK));
+
+        conn.execute("UPDATE inventory_items SET quantity = -2 WHERE id = 'i1'", []).unwrap();
+        let quantity: f64 = conn.query_row("SELECT quantity FROM inventory_items WHERE id = 'i1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(quantity, -2.0);
+        let lots: i64 = conn.query_row("SELECT COUNT(*) FROM inventory_lots", [], |row| row.get(0)).unwrap();
+        assert_eq!(lots, 1);
+        // The price constraint and the indexes stay
+        assert!(conn.execute("UPDATE inventory_items SET price = -1 WHERE id = 'i1'", []).is_err());
+        let sku_index: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type='index' AND name='idx_inventory_sku'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sku_index, 1);
+
+        // Rowids are kept for the search index and its triggers are back
+        assert_eq!(inventory::search_items(&conn, "mala", 10).unwrap().len(), 1);
+        conn.execute("UPDATE inventory_items SET name = 'Genis Firca' WHERE id = 'i1'", []).unwrap();
+        assert_eq!(inventory::search_items(&conn, "genis", 10).unwrap().len(), 1);
+
+        // The lot still references its item
+        conn.execute("DELETE FROM inventory_items WHERE id = 'i1'", []).unwrap();
+        let lots: i64 = conn.query_row("SELECT COUNT(*) FROM inventory_lots", [], |row| row.get(0)).unwrap();
+        assert_eq!(lots, 0);
+        let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0)).unwrap();
+        assert_eq!(integrity, "ok");
+        negative_stock(&conn).unwrap();
+    }
+}
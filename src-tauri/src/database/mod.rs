@@ -1,3 +1,6 @@
 pub mod connection;
+pub mod encryption;
+pub mod migrations;
 
-pub use connection::{Database, DbPool, DbConn, init_database};
+pub use connection::{Database, DbPool, DbConn, init_database, inspect_database_schema, restore_database_from_file};
+pub use encryption::open_database;
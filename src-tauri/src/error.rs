@@ -39,6 +39,43 @@ pub enum AppError {
 
     #[error("Dahili hata: {0}")]
     Internal(String),
+
+    #[error("Veritabani kilitli: {0}")]
+    DatabaseLocked(String),
+
+    #[error("{}", conflict_message(.field, .value))]
+    Conflict { field: String, value: String },
+}
+
+/// User-facing text for `AppError::Conflict`; SKU and barcode clashes get their own wording
+fn conflict_message(field: &str, value: &str) -> String {
+    match field {
+        "sku" => format!("Bu SKU zaten kayıtlı: {}", value),
+        "barcode" => format!("Bu barkod zaten kayıtlı: {}", value),
+        _ => format!("Kayit zaten mevcut - {}: {}", field, value),
+    }
+}
+
+impl AppError {
+    /// Add context to a rusqlite error; SQLITE_BUSY / SQLITE_LOCKED become `DatabaseLocked`
+    pub fn sqlite(context: &str, e: rusqlite::Error) -> Self {
+        match e.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::DatabaseBusy) | Some(rusqlite::ErrorCode::DatabaseLocked) => {
+                AppError::DatabaseLocked(format!("{}: {}", context, e))
+            }
+            _ => AppError::Internal(format!("{}: {}", context, e)),
+        }
+    }
+
+    /// True for a UNIQUE or PRIMARY KEY violation (extended result codes), not other constraint failures
+    pub fn is_unique_violation(e: &rusqlite::Error) -> bool {
+        matches!(
+            e,
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+                    || err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_PRIMARYKEY
+        )
+    }
 }
 
 impl Serialize for AppError {
@@ -15,7 +15,7 @@ pub use database::{Database, DbPool, DbConn, init_database};
 pub use models::*;
 pub use error::*;
 
-use tauri::{Manager, tray::{TrayIconBuilder, MouseButton, MouseButtonState}, menu::{Menu, MenuItem}};
+use tauri::{Emitter, Manager, tray::{TrayIconBuilder, MouseButton, MouseButtonState}, menu::{Menu, MenuItem}};
 use std::sync::Mutex;
 use cache::AppCache;
 
@@ -28,6 +28,15 @@ pub struct AppState {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        // Must be registered first: a second launch focuses the running window
+        // instead of opening a competing connection to the database
+        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+        }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
@@ -39,10 +48,26 @@ pub fn run() {
             // Auto-seed if empty
             if let Ok(conn) = db.get_conn() {
                 let _ = services::inventory::check_and_seed_initial_data(&conn);
+
+                // Apply the configured HTTP retry count for license/cloud calls
+                if let Err(e) = services::http::load_http_settings(&conn) {
+                    eprintln!("HTTP ayarlari okunamadi: {}", e);
+                }
+
+                // Ensure an admin exists (temporary password goes to the support log)
+                if let Err(e) = security::admin::ensure_admin_exists(&conn) {
+                    eprintln!("Yonetici hesabi kontrol edilemedi: {}", e);
+                }
             }
 
 
 
+            // Forward the first failed health check to the frontend
+            let handle = app.handle().clone();
+            services::health::set_health_warning_listener(move |snapshot| {
+                let _ = handle.emit(services::health::DB_HEALTH_WARNING_EVENT, snapshot.clone());
+            });
+
             // Get db_path before moving db into AppState
             let db_path = db.get_db_path_string();
 
@@ -51,8 +76,23 @@ pub fn run() {
                 cache: Mutex::new(AppCache::new()),
             });
 
+            // Resume the nightly CSV export if one was configured
+            if let Ok(conn) = crate::database::open_database(&db_path) {
+                if let Err(e) = services::scheduled_export::resume_export_scheduler(&conn, &db_path) {
+                    eprintln!("Zamanlanmis disa aktarim baslatilamadi: {}", e);
+                }
+            }
+
+            // Write recurring expenses (rent, utilities) whose day has come
+            services::recurring_expenses::start_recurring_expense_job(db_path.clone());
+
+            // Start cloud auto-sync scheduler (idle while auto-sync is disabled)
+            cloud::start_auto_sync_scheduler(db_path.clone());
+
             // Start background sync service (5 minute interval = 300 seconds)
-            if let Err(e) = services::sync::start_background_sync(db_path, 300) {
+            let handle = app.handle().clone();
+            let on_sync_event = move |event| commands::emit_device_sync_event(&handle, event);
+            if let Err(e) = services::sync::start_background_sync(db_path, 300, on_sync_event) {
                 eprintln!("Background sync could not start: {}", e);
             }
 
@@ -115,32 +155,59 @@ pub fn run() {
             // Inventory commands
             get_all_items,
             get_item_by_sku,
+            search_items,
             add_item,
             update_item,
             delete_item,
+            save_item_image,
+            get_item_image,
+            get_item_thumbnail,
+            restore_item,
+            list_deleted_items,
+            purge_deleted_items,
             update_quantity,
+            adjust_stock,
             // Transaction commands
             process_sale,
+            process_partial_return,
             get_transactions,
             update_transaction,
+            get_transaction_by_id,
+            void_transaction,
             // Dashboard & Analytics
             get_dashboard_stats,
+            get_low_stock_items,
             get_recent_activities,
             log_activity,
             get_category_stats,
+            get_cache_stats,
+            reset_cache_stats,
             get_sales_by_date_range,
             get_transactions_by_date_range,
             // Import/Export
             export_to_csv,
+            export_catalog,
             import_from_csv,
+            export_inventory_xlsx,
+            import_inventory_xlsx,
+            stage_csv_import,
+            review_import_prices,
+            commit_staged_import,
+            discard_staged_import,
             export_database,
             import_database,
+            preview_database_import,
+            export_json,
+            import_json,
             // Database management
             clear_database,
             apply_price_change_by_category,
             seed_database,
             // User authentication commands
             login,
+            validate_session,
+            logout,
+            get_auth_events,
             check_users_exist,
             get_all_users,
             create_user,
@@ -148,6 +215,8 @@ pub fn run() {
             delete_user,
             change_password,
             get_users_for_login, // Yeni - Login grid icin
+            get_login_policy,
+            set_login_policy,
             // Pagination commands
             get_items_paginated,
             get_transactions_with_pagination,
@@ -157,6 +226,8 @@ pub fn run() {
             validate_license,
             activate_license,
             check_license_validity,
+            get_license_policy,
+            set_license_policy,
             deactivate_license,
             // Cloud sync commands
             cloud_backup,
@@ -164,6 +235,10 @@ pub fn run() {
             get_sync_status,
             get_cloud_status,
             set_auto_sync,
+            get_http_retry_count,
+            set_http_retry_count,
+            get_http_retry_backoff_ms,
+            set_http_retry_backoff_ms,
             save_to_downloads,
             import_from_csv,
             // Local backup commands
@@ -173,17 +248,38 @@ pub fn run() {
             delete_local_backup,
             start_auto_backup,
             stop_auto_backup,
+            start_scheduled_export,
+            stop_scheduled_export,
+            get_health_history,
+            run_health_check,
+            check_database_integrity,
+            vacuum_database,
+            get_backup_target,
+            configure_backup_target,
+            list_target_backups,
+            restore_target_backup,
+            // Settings commands
+            get_setting,
+            set_setting,
+            get_all_settings,
             // Startup commands
             set_windows_startup,
             get_windows_startup_status,
             // Print commands
             generate_receipt,
             generate_invoice,
+            generate_count_sheet,
+            generate_label_png,
             // Updater commands
             get_app_version,
             // Scanner commands
             parse_barcode,
             validate_barcode,
+            parse_scanned_input,
+            find_item_by_barcode,
+            lookup_by_barcode,
+            get_scanner_config,
+            set_scanner_config,
             start_scanner,
             stop_scanner,
             update_current_account,
@@ -196,8 +292,10 @@ pub fn run() {
             // Finance commands
             add_finance_record,
             get_finance_records,
+            update_finance_record,
             delete_finance_record,
             get_finance_summary,
+            generate_z_report,
             // Access Code commands
             create_access_code,
             get_access_codes,
@@ -205,26 +303,61 @@ pub fn run() {
             login_with_code,
             process_goods_receipt,
             get_product_lots,
+            get_expiring_lots,
+            get_exchange_rates,
+            set_exchange_rate,
             // Current Account (Cari) commands
             create_current_account,
             get_current_accounts,
+            set_account_parent,
+            get_consolidated_balance,
+            get_account_tree,
+            recompute_account_balances,
+            get_account_transactions,
+            get_account_statement,
+            record_account_payment,
             // Stock Card commands
             create_stock_card,
+            generate_internal_sku,
             get_stock_cards,
             update_stock_card,
             // Category commands
             create_category,
             get_categories,
+            get_category_tree,
+            move_category,
             delete_category,
             sync_inventory_categories,
+            // Category margin commands
+            get_category_margins,
+            set_category_margin,
+            get_default_margin,
+            set_default_margin,
+            // Bundle (kit) commands
+            get_bundles,
+            get_bundle_components,
+            set_bundle_components,
+            get_available_quantity,
+            // Item attribute (variant) commands
+            get_item_attributes,
+            set_item_attributes,
+            get_attribute_values,
             // Seed data command
             seed_data,
             factory_reset,
             process_expense,
+            get_recurring_expenses,
+            create_recurring_expense,
+            update_recurring_expense,
+            delete_recurring_expense,
             // Invoice number generation
             generate_invoice_number,
             // Goods receipt history
             get_goods_receipt_history,
+            get_goods_receipts_paginated,
+            // Purchase orders
+            generate_purchase_order,
+            get_open_purchase_orders,
             // Stock card deletion
             delete_stock_card,
             // Check commands
@@ -234,10 +367,26 @@ pub fn run() {
             // Multi-device sync commands
             perform_device_sync,
             get_device_sync_state,
+            get_last_sync_error,
             start_device_sync,
             stop_device_sync,
             is_device_sync_running,
             queue_sync_transaction,
+            get_sync_conflicts,
+            purge_synced_outbox,
+            start_lan_sync,
+            stop_lan_sync,
+            get_lan_peers,
+            sync_with_peer,
+            // Report commands
+            get_turnover_report,
+            get_profit_report,
+            get_top_products,
+            get_sales_heatmap,
+            get_sales_by_hour,
+            get_item_velocity,
+            get_reorder_report,
+            export_daily_summary,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,7 +1,30 @@
 use crate::error::AppError;
-use crate::models::{License, LicenseActivateRequest, LicenseActivateResponse, LicenseValidateRequest, LicenseValidateResponse};
+use crate::services::http;
+use crate::models::{
+    License, LicenseActivateRequest, LicenseActivateResponse, LicenseCheckResult, LicenseDeactivateRequest,
+    LicenseDeactivateResponse, LicensePolicy, LicenseValidateRequest, LicenseValidateResponse,
+};
+use chrono::{DateTime, Duration, Utc};
 use mac_address::get_mac_address;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub const LICENSE_REVALIDATION_DAYS_SETTING: &str = "license_revalidation_days";
+pub const LICENSE_OFFLINE_GRACE_DAYS_SETTING: &str = "license_offline_grace_days";
+pub const DEFAULT_REVALIDATION_DAYS: i64 = 7;
+pub const DEFAULT_OFFLINE_GRACE_DAYS: i64 = 14;
+const MAX_POLICY_DAYS: i64 = 365;
+
+/// `needs_online_soon` is set when this many grace days or fewer are left
+const GRACE_WARNING_DAYS: i64 = 3;
+
+impl Default for LicensePolicy {
+    fn default() -> Self {
+        LicensePolicy {
+            revalidation_days: DEFAULT_REVALIDATION_DAYS,
+            offline_grace_days: DEFAULT_OFFLINE_GRACE_DAYS,
+        }
+    }
+}
 
 /// Get the MAC address of the primary network interface
 pub fn get_device_mac_address() -> Result<String, AppError> {
@@ -33,12 +56,13 @@ pub fn validate_license_api(
         mac_address: mac_address.to_string(),
     };
 
-    let response = client
-        .post(&url)
-        .json(&request)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
+    let response = http::send_with_retry(&url, || {
+        client
+            .post(&url)
+            .json(&request)
+            .timeout(std::time::Duration::from_secs(30))
+    })
+    .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
 
     if response.status().is_success() {
         let result: LicenseValidateResponse = response
@@ -80,12 +104,14 @@ pub fn activate_license_api(
         device_name: Some(get_device_name()),
     };
 
-    let response = client
-        .post(&url)
-        .json(&request)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
+    // Not safe to send twice: only retried when the server was never reached
+    let response = http::send_non_idempotent(&url, || {
+        client
+            .post(&url)
+            .json(&request)
+            .timeout(std::time::Duration::from_secs(30))
+    })
+    .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
 
     if response.status().is_success() {
         let result: LicenseActivateResponse = response
@@ -112,6 +138,50 @@ pub fn activate_license_api(
     }
 }
 
+/// Release this device's activation on the server so the license can be activated on another PC
+pub fn deactivate_license_api(
+    api_base_url: &str,
+    license_key: &str,
+    mac_address: &str,
+) -> Result<LicenseDeactivateResponse, AppError> {
+    let client = reqwest::blocking::Client::new();
+    let url = format!("{}/api/license/deactivate", api_base_url);
+
+    let request = LicenseDeactivateRequest {
+        license_key: license_key.to_string(),
+        mac_address: mac_address.to_string(),
+    };
+
+    // Not safe to send twice: only retried when the server was never reached
+    let response = http::send_non_idempotent(&url, || {
+        client
+            .post(&url)
+            .json(&request)
+            .timeout(std::time::Duration::from_secs(30))
+    })
+    .map_err(|e| AppError::Internal(format!("API istegi basarisiz: {}", e)))?;
+
+    if response.status().is_success() {
+        let result: LicenseDeactivateResponse = response
+            .json()
+            .map_err(|e| AppError::Internal(format!("API yaniti ayriştirilamadi: {}", e)))?;
+        Ok(result)
+    } else {
+        let status = response.status();
+        let body = response.text().unwrap_or_default();
+
+        if let Ok(error_response) = serde_json::from_str::<LicenseDeactivateResponse>(&body) {
+            Ok(error_response)
+        } else {
+            Ok(LicenseDeactivateResponse {
+                success: false,
+                error: Some(format!("HTTP_{}", status.as_u16())),
+                message: Some(format!("Sunucu hatasi: {}", status)),
+            })
+        }
+    }
+}
+
 /// Get license from local database
 pub fn get_local_license(conn: &Connection) -> Result<Option<License>, AppError> {
     let result = conn.query_row(
@@ -161,39 +231,246 @@ pub fn save_license(conn: &Connection, license: &License) -> Result<(), AppError
 
 /// Update last validated timestamp
 pub fn update_last_validated(conn: &Connection) -> Result<(), AppError> {
-    let now = chrono::Utc::now().to_rfc3339();
+    set_last_validated(conn, Utc::now())
+}
+
+fn set_last_validated(conn: &Connection, at: DateTime<Utc>) -> Result<(), AppError> {
     conn.execute(
         "UPDATE license SET last_validated = ?1 WHERE id = 1",
-        params![&now],
+        params![at.to_rfc3339()],
     )?;
     Ok(())
 }
 
-/// Check if license needs revalidation (every 7 days)
-pub fn needs_revalidation(license: &License) -> bool {
-    if let Some(last_validated) = &license.last_validated {
-        if let Ok(last_date) = chrono::DateTime::parse_from_rfc3339(last_validated) {
-            let now = chrono::Utc::now();
-            let diff = now.signed_duration_since(last_date);
-            return diff.num_days() >= 7;
-        }
+fn read_days_setting(conn: &Connection, key: &str) -> Result<Option<i64>, AppError> {
+    let value: Option<Option<String>> = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()?;
+    Ok(value.flatten().and_then(|v| v.trim().parse::<i64>().ok()))
+}
+
+/// Revalidation interval and offline grace window from settings, defaults when missing
+pub fn load_license_policy(conn: &Connection) -> Result<LicensePolicy, AppError> {
+    let defaults = LicensePolicy::default();
+    Ok(LicensePolicy {
+        revalidation_days: read_days_setting(conn, LICENSE_REVALIDATION_DAYS_SETTING)?
+            .unwrap_or(defaults.revalidation_days),
+        offline_grace_days: read_days_setting(conn, LICENSE_OFFLINE_GRACE_DAYS_SETTING)?
+            .unwrap_or(defaults.offline_grace_days),
+    })
+}
+
+/// Store the policy. The grace window must cover the revalidation interval, otherwise the
+/// license would lock before an online check is even attempted.
+pub fn save_license_policy(conn: &Connection, policy: &LicensePolicy) -> Result<LicensePolicy, AppError> {
+    if !(1..=MAX_POLICY_DAYS).contains(&policy.revalidation_days) {
+        return Err(AppError::Validation(format!(
+            "Dogrulama araligi 1-{} gun olmali",
+            MAX_POLICY_DAYS
+        )));
+    }
+    if policy.offline_grace_days < policy.revalidation_days || policy.offline_grace_days > MAX_POLICY_DAYS {
+        return Err(AppError::Validation(format!(
+            "Cevrimdisi sure dogrulama araligindan kisa ya da {} gunden uzun olamaz",
+            MAX_POLICY_DAYS
+        )));
+    }
+
+    for (key, days) in [
+        (LICENSE_REVALIDATION_DAYS_SETTING, policy.revalidation_days),
+        (LICENSE_OFFLINE_GRACE_DAYS_SETTING, policy.offline_grace_days),
+    ] {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![key, days.to_string()],
+        )?;
+    }
+    Ok(*policy)
+}
+
+/// Last successful online validation (activation time when it was never revalidated)
+fn last_validated_at(license: &License) -> Option<DateTime<Utc>> {
+    license
+        .last_validated
+        .as_deref()
+        .or(Some(license.activated_at.as_str()))
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Check if license needs revalidation (every `revalidation_days`)
+pub fn needs_revalidation(license: &License, revalidation_days: i64, now: DateTime<Utc>) -> bool {
+    match license.last_validated.as_deref().map(DateTime::parse_from_rfc3339) {
+        Some(Ok(last_date)) => now.signed_duration_since(last_date).num_days() >= revalidation_days,
+        _ => true, // If no last_validated, needs revalidation
     }
-    true // If no last_validated, needs revalidation
 }
 
 /// Check if license is expired
 pub fn is_license_expired(license: &License) -> bool {
+    is_license_expired_at(license, Utc::now())
+}
+
+fn is_license_expired_at(license: &License, now: DateTime<Utc>) -> bool {
     if let Some(expires_at) = &license.expires_at {
-        if let Ok(expiry_date) = chrono::DateTime::parse_from_rfc3339(expires_at) {
-            let now = chrono::Utc::now();
+        if let Ok(expiry_date) = DateTime::parse_from_rfc3339(expires_at) {
             return now > expiry_date;
         }
     }
     false // No expiry means never expires
 }
 
+/// Check the local license at `now`: expiry, revalidation through `validate_online` when due,
+/// and the offline grace window counted from the last successful online validation.
+/// An unreachable API keeps the license valid until the grace window runs out.
+pub fn check_license(
+    conn: &Connection,
+    now: DateTime<Utc>,
+    validate_online: impl FnOnce(&License) -> Result<LicenseValidateResponse, AppError>,
+) -> Result<LicenseCheckResult, AppError> {
+    let invalid = |remaining: Option<i64>| LicenseCheckResult {
+        valid: false,
+        offline_grace_remaining_days: remaining,
+        needs_online_soon: false,
+    };
+
+    let Some(license) = get_local_license(conn)? else {
+        return Ok(invalid(None));
+    };
+    if !license.is_active || is_license_expired_at(&license, now) {
+        return Ok(invalid(None));
+    }
+
+    let policy = load_license_policy(conn)?;
+    let mut last_validated = last_validated_at(&license);
+    if needs_revalidation(&license, policy.revalidation_days, now) {
+        match validate_online(&license) {
+            Ok(response) if response.valid => {
+                set_last_validated(conn, now)?;
+                last_validated = Some(now);
+            }
+            Ok(_) => return Ok(invalid(None)),
+            Err(e) => eprintln!("[License] Cevrimici dogrulama yapilamadi: {}", e),
+        }
+    }
+
+    let Some(last_validated) = last_validated else {
+        return Ok(invalid(Some(0)));
+    };
+    let remaining = last_validated + Duration::days(policy.offline_grace_days) - now;
+    if remaining <= Duration::zero() {
+        return Ok(LicenseCheckResult {
+            needs_online_soon: true,
+            ..invalid(Some(0))
+        });
+    }
+
+    let remaining_days = remaining.num_days();
+    Ok(LicenseCheckResult {
+        valid: true,
+        offline_grace_remaining_days: Some(remaining_days),
+        needs_online_soon: remaining_days <= GRACE_WARNING_DAYS,
+    })
+}
+
 /// Delete license from local database
 pub fn delete_license(conn: &Connection) -> Result<(), AppError> {
     conn.execute("DELETE FROM license WHERE id = 1", [])?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(last_validated: &str) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);
+             CREATE TABLE license (
+                id INTEGER PRIMARY KEY CHECK (id = 1), license_key TEXT NOT NULL, dealer_id TEXT NOT NULL,
+                dealer_name TEXT NOT NULL, mac_address TEXT NOT NULL, activated_at TEXT NOT NULL, expires_at TEXT,
+                is_active INTEGER DEFAULT 1, last_validated TEXT, api_base_url TEXT NOT NULL
+             );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO license VALUES (1, 'KEY', 'd1', 'Bayi', 'aa:bb', '2024-01-01T00:00:00+00:00', NULL, 1, ?1, 'http://api')",
+            params![last_validated],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    fn offline(_: &License) -> Result<LicenseValidateResponse, AppError> {
+        Err(AppError::Internal("API erisilemedi".to_string()))
+    }
+
+    fn online(valid: bool) -> impl FnOnce(&License) -> Result<LicenseValidateResponse, AppError> {
+        move |_| {
+            Ok(LicenseValidateResponse {
+                valid,
+                dealer_id: None,
+                dealer_name: None,
+                expires_at: None,
+                error: None,
+                message: None,
+            })
+        }
+    }
+
+    #[test]
+    fn test_offline_within_grace_window() {
+        let conn = setup("2024-03-01T12:00:00+00:00");
+
+        // Revalidation due after 7 days, API unreachable: 14 - 10 days of grace left
+        let result = check_license(&conn, at("2024-03-11T12:00:00+00:00"), offline).unwrap();
+        assert_eq!(
+            result,
+            LicenseCheckResult { valid: true, offline_grace_remaining_days: Some(4), needs_online_soon: false }
+        );
+
+        let result = check_license(&conn, at("2024-03-13T12:00:00+00:00"), offline).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.offline_grace_remaining_days, Some(2));
+        assert!(result.needs_online_soon);
+
+        // Not due yet: the API is not called at all
+        let result = check_license(&conn, at("2024-03-03T12:00:00+00:00"), |_| panic!("not due")).unwrap();
+        assert_eq!(result.offline_grace_remaining_days, Some(12));
+    }
+
+    #[test]
+    fn test_offline_beyond_grace_window_is_invalid() {
+        let conn = setup("2024-03-01T12:00:00+00:00");
+        let result = check_license(&conn, at("2024-03-15T12:00:01+00:00"), offline).unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.offline_grace_remaining_days, Some(0));
+        assert!(result.needs_online_soon);
+
+        // A longer configured window keeps it working
+        save_license_policy(&conn, &LicensePolicy { revalidation_days: 7, offline_grace_days: 30 }).unwrap();
+        assert!(check_license(&conn, at("2024-03-15T12:00:01+00:00"), offline).unwrap().valid);
+        assert!(save_license_policy(&conn, &LicensePolicy { revalidation_days: 10, offline_grace_days: 5 }).is_err());
+    }
+
+    #[test]
+    fn test_successful_revalidation_restarts_grace_window() {
+        let conn = setup("2024-03-01T12:00:00+00:00");
+        let now = at("2024-03-14T12:00:00+00:00");
+
+        let result = check_license(&conn, now, online(true)).unwrap();
+        assert_eq!(result.offline_grace_remaining_days, Some(14));
+        assert!(!result.needs_online_soon);
+        let stored = get_local_license(&conn).unwrap().unwrap().last_validated.unwrap();
+        assert_eq!(at(&stored), now);
+
+        // Revoked on the server
+        let later = at("2024-03-22T12:00:00+00:00");
+        assert!(!check_license(&conn, later, online(false)).unwrap().valid);
+    }
+}
@@ -7,7 +7,8 @@ pub struct InventoryItem {
     pub sku: String,
     pub name: String,
     pub category: String,
-    pub quantity: i32,
+    /// Stock in `unit`; fractional only for measured units (see `services::units`)
+    pub quantity: f64,
     pub location: String,
     pub price: f64,
     #[serde(rename = "costPrice")]
@@ -22,6 +23,32 @@ pub struct InventoryItem {
     #[serde(rename = "supplierId")]
     pub supplier_id: Option<String>,
     pub brand: Option<String>,
+    /// Low stock threshold: the item is low when `quantity < min_stock_level`
+    #[serde(rename = "minStockLevel", default = "default_min_stock_level")]
+    pub min_stock_level: i32,
+    /// Unit of measure, from the stock card ('ADET', 'KG', 'MT'...)
+    #[serde(default = "default_unit")]
+    pub unit: String,
+}
+
+/// Item in the trash (soft-deleted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedItem {
+    #[serde(flatten)]
+    pub item: InventoryItem,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: String,
+}
+
+/// Default low stock threshold for items that do not set their own
+pub const DEFAULT_MIN_STOCK_LEVEL: i32 = 10;
+
+fn default_min_stock_level() -> i32 {
+    DEFAULT_MIN_STOCK_LEVEL
+}
+
+fn default_unit() -> String {
+    crate::services::units::DEFAULT_UNIT.to_string()
 }
 
 /// Cart item for processing sales
@@ -31,10 +58,33 @@ pub struct CartItem {
     pub sku: String,
     pub name: String,
     #[serde(rename = "cartQuantity")]
-    pub cart_quantity: i32,
+    pub cart_quantity: f64,
     pub price: f64,
     #[serde(rename = "costPrice")]
     pub cost_price: Option<f64>,
+    /// KDV rate in percent; prices include it
+    #[serde(rename = "taxRate", default = "default_tax_rate")]
+    pub tax_rate: f64,
+    /// Discount on the whole line in money; the price stays the list price
+    #[serde(rename = "discountAmount", default, skip_serializing_if = "Option::is_none")]
+    pub discount_amount: Option<f64>,
+    /// Discount on the whole line in percent; a line has this or `discount_amount`, not both
+    #[serde(rename = "discountPercent", default, skip_serializing_if = "Option::is_none")]
+    pub discount_percent: Option<f64>,
+}
+
+/// Whole-ticket discount of a sale, an amount or a percent of the total after line discounts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SaleDiscount {
+    pub amount: Option<f64>,
+    pub percent: Option<f64>,
+}
+
+/// KDV rate of cart lines that do not carry one
+pub const DEFAULT_TAX_RATE: f64 = 20.0;
+
+fn default_tax_rate() -> f64 {
+    DEFAULT_TAX_RATE
 }
 
 /// Transaction record
@@ -47,25 +97,67 @@ pub struct Transaction {
     pub payment_method: String, // 'CASH', 'CARD', 'CREDIT'
     #[serde(rename = "transactionType")]
     pub transaction_type: String, // 'SALE', 'RETURN'
-    pub status: String,
+    pub status: String, // 'completed', 'voided'
     pub note: Option<String>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
     #[serde(rename = "customerId")]
     pub customer_id: Option<String>,
+    /// KDV included in `total`
+    #[serde(rename = "taxTotal", default)]
+    pub tax_total: f64,
+    /// Sum of the line discounts, already taken off `total`
+    #[serde(rename = "lineDiscount", default)]
+    pub line_discount: f64,
+    /// Whole-ticket discount taken off after the line discounts
+    #[serde(rename = "ticketDiscount", default)]
+    pub ticket_discount: f64,
+}
+
+/// One portion of a sale paid with more than one method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Payment {
+    pub method: String,
+    pub amount: f64,
 }
 
+/// One line of a partial return: how many of a SKU from the original sale come back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnItem {
+    pub sku: String,
+    pub quantity: f64,
+}
+
+pub const TRANSACTION_STATUS_COMPLETED: &str = "completed";
+/// Cancelled transaction: kept for history, left out of totals and balances
+pub const TRANSACTION_STATUS_VOIDED: &str = "voided";
+
 /// Dashboard statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DashboardStats {
     #[serde(rename = "totalItems")]
     pub total_items: i32,
     #[serde(rename = "totalQuantity")]
-    pub total_quantity: i32,
+    pub total_quantity: f64,
     #[serde(rename = "lowStockCount")]
     pub low_stock_count: i32,
+    /// Sales minus returns within the period
     #[serde(rename = "totalRevenue")]
     pub total_revenue: f64,
+    #[serde(rename = "saleCount")]
+    pub sale_count: i32,
+    #[serde(rename = "returnCount")]
+    pub return_count: i32,
+    /// Sales total divided by the number of sales
+    #[serde(rename = "averageBasket")]
+    pub average_basket: f64,
+    #[serde(rename = "periodStart")]
+    pub period_start: String,
+    #[serde(rename = "periodEnd")]
+    pub period_end: String,
+    /// Missing or stale exchange rates used for the TRY figures
+    #[serde(rename = "currencyWarnings", default)]
+    pub currency_warnings: Vec<String>,
 }
 
 /// Sales data point for charts
@@ -76,13 +168,13 @@ pub struct SalesDataPoint {
     pub count: i32,
 }
 
-/// Category statistics
+/// Category statistics; `total_value` is in TRY
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CategoryStats {
     pub category: String,
     pub count: i32,
     #[serde(rename = "totalQuantity")]
-    pub total_quantity: i32,
+    pub total_quantity: f64,
     #[serde(rename = "totalValue")]
     pub total_value: f64,
 }
@@ -99,7 +191,7 @@ pub struct ActivityLog {
     #[serde(rename = "itemName")]
     pub item_name: Option<String>,
     #[serde(rename = "quantityChange")]
-    pub quantity_change: Option<i32>,
+    pub quantity_change: Option<f64>,
     pub value: Option<f64>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
@@ -121,6 +213,34 @@ pub struct User {
     pub must_change_password: bool,
 }
 
+/// Logged-in user with the session token that authorizes further commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthSession {
+    #[serde(flatten)]
+    pub user: User,
+    pub token: String,
+    /// Moves forward on every use (idle timeout)
+    #[serde(rename = "expiresAt")]
+    pub expires_at: String,
+}
+
+/// One login, logout or admin password check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthEvent {
+    pub id: i64,
+    /// LOGIN, LOGIN_CODE, LOGOUT or VERIFY_ADMIN_PASSWORD
+    pub action: String,
+    /// Username as typed (or the access code's name)
+    pub username: String,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+    pub success: bool,
+    /// Why an attempt failed
+    pub detail: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
 /// User model for display in login grid (safe)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserLoginDisplay {
@@ -129,6 +249,9 @@ pub struct UserLoginDisplay {
     #[serde(rename = "displayName")]
     pub display_name: String,
     pub role: String,
+    /// Login policy requires a password for this user's role
+    #[serde(rename = "passwordRequired", default)]
+    pub password_required: bool,
 }
 
 /// Create user request
@@ -175,6 +298,28 @@ pub struct License {
     pub api_base_url: String,
 }
 
+/// Result of the local license check, with the offline grace left before lockout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LicenseCheckResult {
+    pub valid: bool,
+    /// Whole days left until the license stops working without a successful online validation
+    #[serde(rename = "offlineGraceRemainingDays")]
+    pub offline_grace_remaining_days: Option<i64>,
+    /// The grace window is nearly used up; the UI should ask the user to go online
+    #[serde(rename = "needsOnlineSoon")]
+    pub needs_online_soon: bool,
+}
+
+/// License revalidation interval and offline grace window, from settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LicensePolicy {
+    #[serde(rename = "revalidationDays")]
+    pub revalidation_days: i64,
+    /// Days after the last successful online validation the app keeps working offline
+    #[serde(rename = "offlineGraceDays")]
+    pub offline_grace_days: i64,
+}
+
 /// License validation request to API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LicenseValidateRequest {
@@ -225,6 +370,23 @@ pub struct LicenseActivateResponse {
     pub message: Option<String>,
 }
 
+/// License deactivation request to API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseDeactivateRequest {
+    #[serde(rename = "license_key")]
+    pub license_key: String,
+    #[serde(rename = "mac_address")]
+    pub mac_address: String,
+}
+
+/// License deactivation response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseDeactivateResponse {
+    pub success: bool,
+    pub error: Option<String>,
+    pub message: Option<String>,
+}
+
 // ==================== CLOUD SYNC MODELS ====================
 
 /// Cloud sync status
@@ -240,6 +402,25 @@ pub struct SyncStatus {
     pub auto_sync_interval_minutes: i32,
 }
 
+/// Result of changing auto-sync settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoSyncSettingsResult {
+    /// Stored settings after the change
+    pub status: SyncStatus,
+    #[serde(rename = "requestedIntervalMinutes")]
+    pub requested_interval_minutes: i32,
+    #[serde(rename = "effectiveIntervalMinutes")]
+    pub effective_interval_minutes: i32,
+    #[serde(rename = "intervalClamped")]
+    pub interval_clamped: bool,
+    /// None when the health check was skipped
+    #[serde(rename = "serverReachable")]
+    pub server_reachable: Option<bool>,
+    #[serde(rename = "firstSyncScheduled")]
+    pub first_sync_scheduled: bool,
+    pub warnings: Vec<String>,
+}
+
 /// Cloud backup response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CloudBackupResponse {
@@ -253,6 +434,33 @@ pub struct CloudBackupResponse {
     pub error: Option<String>,
 }
 
+/// `cloud-backup-progress` event payload: request body bytes sent so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudBackupProgress {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    #[serde(rename = "bytesSent")]
+    pub bytes_sent: u64,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+}
+
+/// `cloud-backup-complete` event payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudBackupComplete {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    pub response: CloudBackupResponse,
+}
+
+/// `cloud-backup-error` event payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudBackupFailed {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    pub error: String,
+}
+
 /// Cloud status response from API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CloudStatusResponse {
@@ -266,6 +474,143 @@ pub struct CloudStatusResponse {
     pub backup_count: Option<i32>,
 }
 
+/// Schema version of a database file being imported or restored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseSchemaInfo {
+    #[serde(rename = "detectedVersion")]
+    pub detected_version: i32,
+    #[serde(rename = "currentVersion")]
+    pub current_version: i32,
+    /// Older file: migrations are applied to a temp copy before it is restored
+    #[serde(rename = "needsMigration")]
+    pub needs_migration: bool,
+    /// False when the file comes from a newer app version
+    pub compatible: bool,
+    /// Safety backup of the replaced database, set by `import_database`
+    #[serde(rename = "backupPath", default, skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+}
+
+/// Distinct values of one item attribute key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributeValues {
+    pub key: String,
+    pub values: Vec<String>,
+    /// Items that have this attribute
+    #[serde(rename = "itemCount")]
+    pub item_count: i32,
+}
+
+/// Result of a direct CSV import; `errors` name the rejected lines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvImportResult {
+    pub imported: i32,
+    pub skipped: i32,
+    pub errors: Vec<String>,
+}
+
+/// Result of staging a CSV import (nothing is applied yet)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportStageSummary {
+    #[serde(rename = "batchId")]
+    pub batch_id: String,
+    #[serde(rename = "totalRows")]
+    pub total_rows: i32,
+    #[serde(rename = "newItems")]
+    pub new_items: i32,
+    #[serde(rename = "updatedItems")]
+    pub updated_items: i32,
+    /// Rows that could not be parsed (missing SKU/name, bad numbers)
+    #[serde(rename = "skippedLines")]
+    pub skipped_lines: i32,
+}
+
+/// Staged price that changed more than the review threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceOutlier {
+    pub sku: String,
+    pub name: String,
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    #[serde(rename = "oldPrice")]
+    pub old_price: f64,
+    #[serde(rename = "newPrice")]
+    pub new_price: f64,
+    #[serde(rename = "percentChange")]
+    pub percent_change: f64,
+    /// "history" (last price_history entry) or "current" (live price)
+    #[serde(rename = "oldPriceSource")]
+    pub old_price_source: String,
+}
+
+/// Result of applying a staged import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportCommitResult {
+    pub applied: i32,
+    pub excluded: i32,
+    #[serde(rename = "priceChanges")]
+    pub price_changes: i32,
+}
+
+/// Result of one scheduled database health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbHealthSnapshot {
+    pub id: i64,
+    #[serde(rename = "checkedAt")]
+    pub checked_at: String,
+    #[serde(rename = "integrityOk")]
+    pub integrity_ok: bool,
+    /// integrity_check output when it is not "ok"
+    #[serde(rename = "integrityMessages")]
+    pub integrity_messages: Vec<String>,
+    #[serde(rename = "foreignKeyViolations")]
+    pub foreign_key_violations: i64,
+    #[serde(rename = "fileSha256")]
+    pub file_sha256: Option<String>,
+    #[serde(rename = "fileSizeBytes")]
+    pub file_size_bytes: Option<i64>,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: i64,
+}
+
+impl DbHealthSnapshot {
+    pub fn is_healthy(&self) -> bool {
+        self.integrity_ok && self.foreign_key_violations == 0
+    }
+}
+
+/// One row of `PRAGMA foreign_key_check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    /// None for WITHOUT ROWID tables
+    pub rowid: Option<i64>,
+    /// Table the broken reference points to
+    pub parent: String,
+    #[serde(rename = "fkId")]
+    pub fk_id: i64,
+}
+
+/// On-demand integrity check result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    /// integrity_check output when it is not "ok"
+    #[serde(rename = "integrityMessages")]
+    pub integrity_messages: Vec<String>,
+    #[serde(rename = "foreignKeyViolations")]
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+}
+
+/// Database file size around a VACUUM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacuumReport {
+    #[serde(rename = "sizeBeforeBytes")]
+    pub size_before_bytes: i64,
+    #[serde(rename = "sizeAfterBytes")]
+    pub size_after_bytes: i64,
+}
+
 // ==================== PAGINATION MODELS ====================
 
 /// Pagination parameters
@@ -347,6 +692,66 @@ pub struct FinanceSummary {
     pub card_balance: f64,
     #[serde(rename = "bankBalance")]
     pub bank_balance: f64,
+    /// Missing or stale exchange rates used for the TRY figures
+    #[serde(rename = "currencyWarnings", default)]
+    pub currency_warnings: Vec<String>,
+}
+
+/// TRY value of one unit of a foreign currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub currency: String,
+    #[serde(rename = "rateToTry")]
+    pub rate_to_try: f64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+    /// Older than `currency::STALE_RATE_HOURS`
+    pub stale: bool,
+}
+
+/// Amounts per payment channel (same grouping as the finance summary)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaymentBreakdown {
+    pub cash: f64,
+    pub card: f64,
+    pub bank: f64,
+    /// Veresiye / vadeli
+    pub credit: f64,
+    pub other: f64,
+}
+
+/// End-of-day close (Z report) of one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZReport {
+    pub id: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    #[serde(rename = "saleCount")]
+    pub sale_count: i64,
+    #[serde(rename = "grossSales")]
+    pub gross_sales: f64,
+    #[serde(rename = "salesByPayment")]
+    pub sales_by_payment: PaymentBreakdown,
+    #[serde(rename = "returnCount")]
+    pub return_count: i64,
+    #[serde(rename = "returnsTotal")]
+    pub returns_total: f64,
+    /// Expense transactions plus manual expense records
+    #[serde(rename = "expenseTotal")]
+    pub expense_total: f64,
+    /// Collections plus manual income records
+    #[serde(rename = "otherIncome")]
+    pub other_income: f64,
+    #[serde(rename = "purchasesTotal")]
+    pub purchases_total: f64,
+    /// Payments made to suppliers' accounts
+    #[serde(rename = "supplierPayments", default)]
+    pub supplier_payments: f64,
+    /// Cash in (sales, collections, income) minus cash out (returns, expenses, purchases, payments) of the day
+    #[serde(rename = "expectedCash")]
+    pub expected_cash: f64,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
 }
 
 /// Access Code for simplified login
@@ -364,11 +769,96 @@ pub struct AccessCode {
 pub struct GoodsReceiptItem {
     #[serde(alias = "productId")]
     pub product_id: String,
-    pub quantity: i32,
+    pub quantity: f64,
     #[serde(alias = "buyPrice")]
     pub buy_price: f64,
     #[serde(alias = "sellPrice")]
     pub sell_price: Option<f64>,
+    /// YYYY-MM-DD, for goods that go off
+    #[serde(default, alias = "expiryDate")]
+    pub expiry_date: Option<String>,
+}
+
+/// Goods receipt line (one inventory lot)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoodsReceiptLine {
+    #[serde(rename = "lotId")]
+    pub lot_id: String,
+    #[serde(rename = "productId")]
+    pub product_id: String,
+    #[serde(rename = "productName")]
+    pub product_name: Option<String>,
+    pub sku: Option<String>,
+    pub quantity: f64,
+    #[serde(rename = "remainingQuantity")]
+    pub remaining_quantity: f64,
+    #[serde(rename = "buyPrice")]
+    pub buy_price: f64,
+    #[serde(rename = "sellPrice")]
+    pub sell_price: Option<f64>,
+}
+
+/// Goods receipt (Mal Kabul) header with its lines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoodsReceipt {
+    pub id: String,
+    #[serde(rename = "invoiceNo")]
+    pub invoice_no: Option<String>,
+    #[serde(rename = "supplierId")]
+    pub supplier_id: Option<String>,
+    #[serde(rename = "supplierName")]
+    pub supplier_name: Option<String>,
+    #[serde(rename = "totalAmount")]
+    pub total_amount: f64,
+    #[serde(rename = "paymentMethod")]
+    pub payment_method: String,
+    pub description: String,
+    pub date: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    pub items: Vec<GoodsReceiptLine>,
+}
+
+/// Paginated goods receipts response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaginatedGoodsReceiptsResponse {
+    pub data: Vec<GoodsReceipt>,
+    pub total: i32,
+    pub page: i32,
+    #[serde(rename = "perPage")]
+    pub per_page: i32,
+    #[serde(rename = "totalPages")]
+    pub total_pages: i32,
+}
+
+/// Purchase order line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct POLine {
+    pub sku: String,
+    pub name: String,
+    pub quantity: f64,
+    #[serde(rename = "buyPrice")]
+    pub buy_price: f64,
+    /// Units received so far through goods receipts
+    #[serde(rename = "receivedQuantity", default)]
+    pub received_quantity: f64,
+}
+
+/// Purchase order (Satın Alma Siparişi) sent to a supplier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrder {
+    pub id: String,
+    /// `PO-YEAR-00001`
+    #[serde(rename = "poNumber")]
+    pub po_number: String,
+    #[serde(rename = "supplierId")]
+    pub supplier_id: String,
+    pub lines: Vec<POLine>,
+    pub total: f64,
+    /// open, partial (some lines short) or received
+    pub status: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
 }
 
 // ==================== LOT/BATCH TRACKING MODELS ====================
@@ -383,9 +873,9 @@ pub struct InventoryLot {
     pub supplier_id: Option<String>,
     #[serde(rename = "supplierName")]
     pub supplier_name: Option<String>, // Joined from current_accounts
-    pub quantity: i32,
+    pub quantity: f64,
     #[serde(rename = "initialQuantity")]
-    pub initial_quantity: i32,
+    pub initial_quantity: f64,
     #[serde(rename = "buyPrice")]
     pub buy_price: f64,
     #[serde(rename = "sellPrice")]
@@ -394,10 +884,31 @@ pub struct InventoryLot {
     pub receipt_date: String,
     #[serde(rename = "invoiceNo")]
     pub invoice_no: Option<String>,
+    #[serde(rename = "expiryDate")]
+    pub expiry_date: Option<String>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
 }
 
+/// Lot with stock left that expires within the requested window (or already has)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpiringLot {
+    #[serde(rename = "lotId")]
+    pub lot_id: String,
+    #[serde(rename = "productId")]
+    pub product_id: String,
+    #[serde(rename = "productName")]
+    pub product_name: String,
+    pub sku: String,
+    #[serde(rename = "remainingQuantity")]
+    pub remaining_quantity: f64,
+    #[serde(rename = "expiryDate")]
+    pub expiry_date: String,
+    /// Negative when already expired
+    #[serde(rename = "daysLeft")]
+    pub days_left: i64,
+}
+
 // ==================== CARİ (CURRENT ACCOUNT) MODELS ====================
 
 /// Current Account (Cari) - Customer or Supplier
@@ -420,6 +931,9 @@ pub struct CurrentAccount {
     pub created_at: String,
     #[serde(rename = "updatedAt")]
     pub updated_at: String,
+    /// Parent account for branch/group accounts
+    #[serde(rename = "parentAccountId")]
+    pub parent_account_id: Option<String>,
 }
 
 /// Create Current Account Request
@@ -436,6 +950,93 @@ pub struct CreateCurrentAccountRequest {
     pub note: Option<String>,
     #[serde(rename = "paymentTerm")]
     pub payment_term: i32,
+    #[serde(rename = "parentAccountId", default)]
+    pub parent_account_id: Option<String>,
+}
+
+/// Account hierarchy node (account + its sub-accounts)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountTreeNode {
+    #[serde(flatten)]
+    pub account: CurrentAccount,
+    /// Own balance plus all sub-account balances
+    #[serde(rename = "consolidatedBalance")]
+    pub consolidated_balance: f64,
+    pub children: Vec<AccountTreeNode>,
+}
+
+/// Stored account balance that does not match the balance derived from its transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceDiscrepancy {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "accountName")]
+    pub account_name: String,
+    #[serde(rename = "accountType")]
+    pub account_type: String,
+    #[serde(rename = "storedBalance")]
+    pub stored_balance: f64,
+    #[serde(rename = "computedBalance")]
+    pub computed_balance: f64,
+    /// computed - stored
+    pub difference: f64,
+    #[serde(rename = "transactionCount")]
+    pub transaction_count: i32,
+    /// True when the stored balance was overwritten (dry_run = false)
+    pub corrected: bool,
+}
+
+/// One balance-changing transaction on an account statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountStatementLine {
+    #[serde(rename = "transactionId")]
+    pub transaction_id: String,
+    pub date: String,
+    #[serde(rename = "transactionType")]
+    pub transaction_type: String,
+    #[serde(rename = "paymentMethod")]
+    pub payment_method: String,
+    pub description: String,
+    /// Change of the balance; positive increases the debt
+    pub amount: f64,
+    /// Balance after this line
+    pub balance: f64,
+}
+
+/// Account statement (cari ekstre) for a date range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountStatement {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "accountName")]
+    pub account_name: String,
+    #[serde(rename = "accountType")]
+    pub account_type: String,
+    #[serde(rename = "startDate")]
+    pub start_date: String,
+    #[serde(rename = "endDate")]
+    pub end_date: String,
+    #[serde(rename = "openingBalance")]
+    pub opening_balance: f64,
+    pub lines: Vec<AccountStatementLine>,
+    #[serde(rename = "closingBalance")]
+    pub closing_balance: f64,
+}
+
+/// Consolidated balance of an account group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidatedBalance {
+    #[serde(rename = "parentId")]
+    pub parent_id: String,
+    #[serde(rename = "parentName")]
+    pub parent_name: String,
+    #[serde(rename = "ownBalance")]
+    pub own_balance: f64,
+    #[serde(rename = "totalBalance")]
+    pub total_balance: f64,
+    #[serde(rename = "accountCount")]
+    pub account_count: i32,
+    pub members: Vec<CurrentAccount>,
 }
 
 // ==================== STOK KARTI (STOCK CARD) MODELS ====================
@@ -474,6 +1075,9 @@ pub struct CreateStockCardRequest {
     pub image: Option<String>,
     #[serde(rename = "supplierId")]
     pub supplier_id: Option<String>,
+    /// With an empty barcode, give the card the next internal SKU (`generate_internal_sku`)
+    #[serde(rename = "generateSku", default)]
+    pub generate_sku: bool,
 }
 
 // ==================== KATEGORİ (CATEGORY) MODELS ====================
@@ -498,3 +1102,299 @@ pub struct CreateCategoryRequest {
     pub parent_id: Option<String>,
 }
 
+/// Category with its subcategories, see `services::categories::get_category_tree`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryNode {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<String>,
+    /// Names from the top level down, e.g. "HIRDAVAT > Tornavida"
+    pub path: String,
+    /// Stock cards filed directly under this category
+    #[serde(rename = "itemCount")]
+    pub item_count: i64,
+    pub children: Vec<CategoryNode>,
+}
+
+/// Target margin (markup % over cost) used to price goods receipts in a category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryMargin {
+    pub category: String,
+    #[serde(rename = "targetMarginPercent")]
+    pub target_margin_percent: f64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+// ==================== KATALOG (CATALOG EXPORT) MODELS ====================
+
+/// Combined stock card + inventory row for the master catalog export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogRow {
+    /// None for inventory items that have no stock card
+    #[serde(rename = "stockCardId")]
+    pub stock_card_id: Option<String>,
+    pub barcode: String,
+    pub name: String,
+    pub brand: Option<String>,
+    pub unit: Option<String>,
+    pub category: String,
+    /// None for stock cards that have no inventory record (quantity is 0)
+    #[serde(rename = "inventoryId")]
+    pub inventory_id: Option<String>,
+    pub quantity: f64,
+    pub location: Option<String>,
+    pub price: Option<f64>,
+    #[serde(rename = "costPrice")]
+    pub cost_price: Option<f64>,
+    #[serde(rename = "lotCount")]
+    pub lot_count: i32,
+    /// Lots that still have remaining quantity
+    #[serde(rename = "openLotCount")]
+    pub open_lot_count: i32,
+}
+
+// ==================== SET ÜRÜN (BUNDLE) MODELS ====================
+
+/// One component line of a bundle (kit) product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductComponent {
+    #[serde(rename = "parentSku")]
+    pub parent_sku: String,
+    #[serde(rename = "componentSku")]
+    pub component_sku: String,
+    #[serde(rename = "componentName")]
+    pub component_name: Option<String>,
+    /// Units of the component per bundle
+    pub quantity: f64,
+    #[serde(rename = "componentStock")]
+    pub component_stock: f64,
+}
+
+/// Component definition sent when saving a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleComponentInput {
+    #[serde(rename = "componentSku")]
+    pub component_sku: String,
+    pub quantity: f64,
+}
+
+/// Bundle with the number of complete kits that can be built from stock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSummary {
+    pub sku: String,
+    pub name: Option<String>,
+    #[serde(rename = "componentCount")]
+    pub component_count: i64,
+    #[serde(rename = "availableQuantity")]
+    pub available_quantity: f64,
+}
+
+// ==================== RAPOR (REPORT) MODELS ====================
+
+/// Inventory turnover for a single category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnoverRow {
+    pub category: String,
+    /// Cost of goods sold in the period (returns deducted)
+    pub cogs: f64,
+    #[serde(rename = "beginningValue")]
+    pub beginning_value: f64,
+    #[serde(rename = "endingValue")]
+    pub ending_value: f64,
+    #[serde(rename = "averageInventoryValue")]
+    pub average_inventory_value: f64,
+    pub turnover: f64,
+    /// None when nothing was sold in the period
+    #[serde(rename = "daysOfInventory")]
+    pub days_of_inventory: Option<f64>,
+}
+
+/// Turnover report with the assumptions used to approximate inventory values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnoverReport {
+    #[serde(rename = "startDate")]
+    pub start_date: String,
+    #[serde(rename = "endDate")]
+    pub end_date: String,
+    pub rows: Vec<TurnoverRow>,
+    pub assumptions: Vec<String>,
+}
+
+/// Sales in one weekday/hour slot of the heatmap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatCell {
+    /// 0 = Pazartesi … 6 = Pazar
+    pub weekday: u8,
+    /// 0-23, shop time
+    pub hour: u8,
+    pub count: i64,
+    pub revenue: f64,
+}
+
+/// Sales in one hour of the day, summed over all days of the range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourlySales {
+    /// 0-23, shop time
+    pub hour: u8,
+    pub count: i64,
+    pub total: f64,
+}
+
+/// One day of the daily sales summary export
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailySummaryRow {
+    pub date: String,
+    #[serde(rename = "saleCount")]
+    pub sale_count: i64,
+    #[serde(rename = "grossSales")]
+    pub gross_sales: f64,
+    pub returns: f64,
+    /// Gross sales minus returns
+    #[serde(rename = "netSales")]
+    pub net_sales: f64,
+    pub expenses: f64,
+    /// Net sales (sales - returns) per canonical payment method
+    pub cash: f64,
+    pub card: f64,
+    pub bank: f64,
+    pub credit: f64,
+    pub other: f64,
+}
+
+/// Revenue, cost and gross profit of one category in the profit report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CategoryProfit {
+    pub category: String,
+    pub revenue: f64,
+    pub cost: f64,
+    #[serde(rename = "grossProfit")]
+    pub gross_profit: f64,
+    /// Gross profit / revenue, percent
+    #[serde(rename = "marginPercent")]
+    pub margin_percent: f64,
+    /// Sold lines costed at 0 because no cost was known
+    #[serde(rename = "missingCostLines")]
+    pub missing_cost_lines: i64,
+}
+
+/// Profit of the sales in a date range (returns subtracted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfitReport {
+    #[serde(rename = "startDate")]
+    pub start_date: String,
+    #[serde(rename = "endDate")]
+    pub end_date: String,
+    pub revenue: f64,
+    #[serde(rename = "totalCost")]
+    pub total_cost: f64,
+    #[serde(rename = "grossProfit")]
+    pub gross_profit: f64,
+    #[serde(rename = "marginPercent")]
+    pub margin_percent: f64,
+    #[serde(rename = "missingCostLines")]
+    pub missing_cost_lines: i64,
+    pub categories: Vec<CategoryProfit>,
+}
+
+/// Sales velocity and stock projection for one item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemVelocity {
+    pub sku: String,
+    pub name: String,
+    #[serde(rename = "windowDays")]
+    pub window_days: i64,
+    /// Days actually averaged over (shorter when history is shorter than the window)
+    #[serde(rename = "effectiveDays")]
+    pub effective_days: i64,
+    /// Net units (sales minus returns)
+    #[serde(rename = "unitsSold")]
+    pub units_sold: f64,
+    #[serde(rename = "saleDays")]
+    pub sale_days: i64,
+    #[serde(rename = "avgDailyUnits")]
+    pub avg_daily_units: f64,
+    #[serde(rename = "onHand")]
+    pub on_hand: f64,
+    /// None when the item did not sell in the window
+    #[serde(rename = "daysOfStockRemaining")]
+    pub days_of_stock_remaining: Option<f64>,
+    #[serde(rename = "stockoutDate")]
+    pub stockout_date: Option<String>,
+    #[serde(rename = "leadTimeDays")]
+    pub lead_time_days: i64,
+    #[serde(rename = "reorderBy")]
+    pub reorder_by: Option<String>,
+    #[serde(rename = "reorderNow")]
+    pub reorder_now: bool,
+    #[serde(rename = "insufficientHistory")]
+    pub insufficient_history: bool,
+    #[serde(rename = "tooFewSales")]
+    pub too_few_sales: bool,
+    #[serde(rename = "insufficientData")]
+    pub insufficient_data: bool,
+}
+
+/// Units sold and revenue of one product in a date range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductSales {
+    pub sku: String,
+    pub name: String,
+    #[serde(rename = "quantitySold")]
+    pub quantity_sold: f64,
+    pub revenue: f64,
+}
+
+/// One item below its minimum stock level and how much to order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderLine {
+    pub sku: String,
+    pub name: String,
+    pub quantity: f64,
+    #[serde(rename = "minStockLevel")]
+    pub min_stock_level: i64,
+    /// Net units (sales minus returns) over the last 30 days
+    #[serde(rename = "unitsSold30d")]
+    pub units_sold_30d: f64,
+    #[serde(rename = "avgDailyUnits")]
+    pub avg_daily_units: f64,
+    #[serde(rename = "suggestedQuantity")]
+    pub suggested_quantity: f64,
+}
+
+/// Reorder lines of one supplier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderSuggestion {
+    /// None for items without a supplier
+    #[serde(rename = "supplierId")]
+    pub supplier_id: Option<String>,
+    #[serde(rename = "supplierName")]
+    pub supplier_name: String,
+    pub items: Vec<ReorderLine>,
+}
+
+/// Hit/miss/eviction counts of one cache segment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheSegmentStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped for expiry or LRU capacity (invalidations are not counted)
+    pub evictions: u64,
+}
+
+/// Cache counters since startup or the last reset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    #[serde(rename = "itemsBySku")]
+    pub items_by_sku: CacheSegmentStats,
+    #[serde(rename = "allItems")]
+    pub all_items: CacheSegmentStats,
+    #[serde(rename = "dashboardStats")]
+    pub dashboard_stats: CacheSegmentStats,
+    #[serde(rename = "categoryStats")]
+    pub category_stats: CacheSegmentStats,
+    /// Items currently cached by SKU
+    #[serde(rename = "itemsBySkuLen")]
+    pub items_by_sku_len: usize,
+}
@@ -0,0 +1,82 @@
+//! Initial admin bootstrap
+//!
+//! Kullanıcı tablosu boşsa ilk girişte değiştirilmesi zorunlu geçici şifreli yönetici oluşturur.
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rusqlite::{params, Connection};
+
+use crate::error::AppError;
+use crate::license;
+use crate::security::password::hash_password;
+use crate::services::support_log;
+
+pub const ADMIN_USERNAME: &str = "admin";
+
+const TEMP_PASSWORD_LENGTH: usize = 10;
+
+fn generate_temp_password() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TEMP_PASSWORD_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Create the first admin (must_change_password = 1) if the users table is empty.
+///
+/// Returns the temporary password when an admin was created; it is also written to the
+/// support log so it can be recovered. Idempotent.
+pub fn create_initial_admin(
+    conn: &Connection,
+    display_name: &str,
+    dealer_id: Option<&str>,
+) -> Result<Option<String>, AppError> {
+    let user_count: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
+    if user_count > 0 {
+        return Ok(None);
+    }
+
+    let temp_password = generate_temp_password();
+    let password_hash = hash_password(&temp_password)?;
+
+    conn.execute(
+        "INSERT INTO users (id, username, password_hash, display_name, role, created_at, dealer_id, must_change_password) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1)",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            ADMIN_USERNAME,
+            &password_hash,
+            display_name,
+            "admin",
+            chrono::Utc::now().to_rfc3339(),
+            dealer_id
+        ],
+    )?;
+
+    // The account already exists at this point; a log failure must not undo the bootstrap
+    if let Err(e) = support_log::append_support_log(&format!(
+        "'{}' yonetici hesabi olusturuldu. Gecici sifre: {} (ilk giriste degistirilmeli)",
+        ADMIN_USERNAME, temp_password
+    )) {
+        eprintln!("Destek kaydi yazilamadi: {}", e);
+    }
+
+    Ok(Some(temp_password))
+}
+
+/// Startup path: make sure an activated install always has an admin.
+///
+/// Without a license nothing is created — `activate_license` creates the admin and shows
+/// the temporary password. With a license but no users (ör. fabrika ayarlarına dönüş),
+/// an admin is created here; the temporary password is only in the support log.
+/// Returns true when an admin was created.
+pub fn ensure_admin_exists(conn: &Connection) -> Result<bool, AppError> {
+    let Some(lic) = license::get_local_license(conn)? else {
+        return Ok(false);
+    };
+
+    let display_name = if lic.dealer_name.is_empty() { "Yönetici" } else { lic.dealer_name.as_str() };
+    let dealer_id = Some(lic.dealer_id.as_str()).filter(|id| !id.is_empty());
+
+    Ok(create_initial_admin(conn, display_name, dealer_id)?.is_some())
+}
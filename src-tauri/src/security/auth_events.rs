@@ -0,0 +1,172 @@
+//! Authentication audit log
+//!
+//! Girişler, erişim kodu girişleri, hatalı şifre denemeleri, çıkışlar ve yönetici şifresi
+//! doğrulamaları `auth_events` tablosuna yazılır (kim, ne zaman, başarılı mı).
+
+use rusqlite::{params, Connection};
+
+use crate::error::AppError;
+use crate::models::AuthEvent;
+use crate::services::reports::parse_report_date;
+
+pub const ACTION_LOGIN: &str = "LOGIN";
+pub const ACTION_LOGIN_CODE: &str = "LOGIN_CODE";
+pub const ACTION_LOGOUT: &str = "LOGOUT";
+pub const ACTION_VERIFY_ADMIN_PASSWORD: &str = "VERIFY_ADMIN_PASSWORD";
+pub const ACTION_SET_LOGIN_POLICY: &str = "SET_LOGIN_POLICY";
+
+/// Events returned when no limit is given
+pub const DEFAULT_EVENT_LIMIT: i64 = 200;
+const MAX_EVENT_LIMIT: i64 = 5000;
+
+/// Append one event. `username` is what was typed (or the access code's name), even when no such user exists.
+pub fn record_auth_event(
+    conn: &Connection,
+    action: &str,
+    username: &str,
+    user_id: Option<&str>,
+    success: bool,
+    detail: Option<&str>,
+) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO auth_events (action, username, user_id, success, detail, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![action, username, user_id, success, detail, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Events newest first, optionally between two `YYYY-MM-DD` dates (inclusive, UTC)
+pub fn get_auth_events(
+    conn: &Connection,
+    limit: Option<i64>,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<Vec<AuthEvent>, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_EVENT_LIMIT).clamp(1, MAX_EVENT_LIMIT);
+    let start = start_date.filter(|d| !d.is_empty()).map(parse_report_date).transpose()?;
+    let end = end_date.filter(|d| !d.is_empty()).map(parse_report_date).transpose()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, action, username, user_id, success, detail, created_at FROM auth_events
+         WHERE (?1 IS NULL OR substr(created_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) <= ?2)
+         ORDER BY created_at DESC, id DESC
+         LIMIT ?3",
+    )?;
+    let events = stmt
+        .query_map(
+            params![start.map(|d| d.to_string()), end.map(|d| d.to_string()), limit],
+            |row| {
+                Ok(AuthEvent {
+                    id: row.get(0)?,
+                    action: row.get(1)?,
+                    username: row.get(2)?,
+                    user_id: row.get(3)?,
+                    success: row.get(4)?,
+                    detail: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::login::{authenticate, verify_admin};
+    use crate::security::password::hash_password;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);
+             CREATE TABLE users (
+                 id TEXT PRIMARY KEY NOT NULL, username TEXT UNIQUE, password_hash TEXT, display_name TEXT, role TEXT,
+                 created_at TEXT, last_login TEXT, failed_login_attempts INTEGER DEFAULT 0, locked_until TEXT,
+                 must_change_password BOOLEAN DEFAULT 0
+             );
+             CREATE TABLE auth_events (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT, action TEXT NOT NULL, username TEXT NOT NULL, user_id TEXT,
+                 success INTEGER NOT NULL, detail TEXT, created_at TEXT NOT NULL
+             );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role, created_at) VALUES ('u1', 'admin', ?1, 'Yonetici', 'admin', '2024-01-01')",
+            params![hash_password("Dogru.Sifre1").unwrap()],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_successful_login_is_recorded() {
+        let conn = setup_db();
+        authenticate(&conn, "admin", "Dogru.Sifre1", chrono::Utc::now()).unwrap();
+
+        let events = get_auth_events(&conn, None, None, None).unwrap();
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!((event.action.as_str(), event.username.as_str()), (ACTION_LOGIN, "admin"));
+        assert_eq!(event.user_id.as_deref(), Some("u1"));
+        assert!(event.success);
+        assert_eq!(event.detail, None);
+
+        let today = chrono::Utc::now().date_naive().to_string();
+        assert_eq!(get_auth_events(&conn, Some(10), Some(&today), Some(&today)).unwrap().len(), 1);
+        assert!(get_auth_events(&conn, None, Some("2000-01-01"), Some("2000-01-02")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_failed_logins_are_recorded_newest_first() {
+        let conn = setup_db();
+        let now = chrono::Utc::now();
+        assert!(matches!(authenticate(&conn, "admin", "yanlis", now), Err(AppError::Auth(_))));
+        assert!(matches!(authenticate(&conn, "hayalet", "x", now), Err(AppError::Auth(_))));
+
+        let events = get_auth_events(&conn, None, None, None).unwrap();
+        assert_eq!(events.len(), 2);
+
+        // Unknown user: the typed username is kept
+        assert_eq!((events[0].username.as_str(), events[0].user_id.as_deref()), ("hayalet", None));
+        assert!(!events[0].success);
+
+        assert_eq!((events[1].action.as_str(), events[1].user_id.as_deref()), (ACTION_LOGIN, Some("u1")));
+        assert!(!events[1].success);
+        assert!(events[1].detail.as_deref().unwrap().starts_with("Hatalı şifre"));
+
+        assert_eq!(get_auth_events(&conn, Some(1), None, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_admin_recheck_shares_the_login_lockout() {
+        let conn = setup_db();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role, created_at) VALUES ('u2', 'kasa', ?1, 'Kasa', 'cashier', '2024-01-01')",
+            params![hash_password("Dogru.Sifre1").unwrap()],
+        )
+        .unwrap();
+        let now = chrono::Utc::now();
+        let check = |username: &str, password: &str| verify_admin(&conn, username, password, ACTION_VERIFY_ADMIN_PASSWORD, now);
+
+        assert!(matches!(check("kasa", "Dogru.Sifre1"), Err(AppError::Auth(_))));
+        assert_eq!(check("admin", "Dogru.Sifre1").unwrap(), "u1");
+
+        // Wrong re-checks and wrong logins count together toward the lock (5 by default)
+        for _ in 0..2 {
+            assert!(matches!(check("admin", "yanlis"), Err(AppError::Auth(_))));
+            assert!(authenticate(&conn, "admin", "yanlis", now).is_err());
+        }
+        assert!(matches!(check("admin", "yanlis"), Err(AppError::Auth(m)) if m.contains("kilitlendi")));
+        assert!(matches!(check("admin", "Dogru.Sifre1"), Err(AppError::UserLocked { .. })));
+        assert!(authenticate(&conn, "admin", "Dogru.Sifre1", now).is_err());
+
+        let events = get_auth_events(&conn, None, None, None).unwrap();
+        let rechecks: Vec<bool> =
+            events.iter().rev().filter(|e| e.action == ACTION_VERIFY_ADMIN_PASSWORD).map(|e| e.success).collect();
+        assert_eq!(rechecks, [false, true, false, false, false, false]);
+    }
+}
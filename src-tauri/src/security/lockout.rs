@@ -0,0 +1,180 @@
+//! Login lockout
+//!
+//! Şifre zorunlu hesaplarda (varsayılan: admin) art arda `max_login_attempts` hatalı şifre
+//! hesabı `lockout_duration_minutes` dakika kilitler. Sayaç `users.failed_login_attempts`,
+//! kilit bitişi `users.locked_until` içinde tutulur; başarılı giriş ikisini de sıfırlar.
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+
+use crate::error::AppError;
+use crate::services::settings;
+
+pub const MAX_LOGIN_ATTEMPTS_SETTING: &str = "max_login_attempts";
+pub const LOCKOUT_DURATION_SETTING: &str = "lockout_duration_minutes";
+
+pub const DEFAULT_MAX_LOGIN_ATTEMPTS: i32 = 5;
+pub const DEFAULT_LOCKOUT_DURATION_MINUTES: i64 = 15;
+
+/// Failed attempts allowed before the lock and how long it lasts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    pub max_attempts: i32,
+    pub duration_minutes: i64,
+}
+
+/// What a wrong password did to the account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailedLogin {
+    /// Attempts left before the lock
+    Remaining(i32),
+    /// The account is now locked for this many minutes
+    Locked { minutes: i64 },
+}
+
+/// Policy from settings; missing or out-of-range values fall back to the defaults
+pub fn get_lockout_policy(conn: &Connection) -> Result<LockoutPolicy, AppError> {
+    let max_attempts = settings::get_setting(conn, MAX_LOGIN_ATTEMPTS_SETTING)?
+        .and_then(|v| v.trim().parse::<i32>().ok())
+        .filter(|n| (1..=100).contains(n))
+        .unwrap_or(DEFAULT_MAX_LOGIN_ATTEMPTS);
+    let duration_minutes = settings::get_setting(conn, LOCKOUT_DURATION_SETTING)?
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|n| (1..=1440).contains(n))
+        .unwrap_or(DEFAULT_LOCKOUT_DURATION_MINUTES);
+    Ok(LockoutPolicy { max_attempts, duration_minutes })
+}
+
+/// `AppError::UserLocked` while `locked_until` is still ahead of `now`
+pub fn ensure_not_locked(locked_until: Option<&str>, now: DateTime<Utc>) -> Result<(), AppError> {
+    let Some(until) = locked_until.and_then(|t| DateTime::parse_from_rfc3339(t).ok()) else {
+        return Ok(());
+    };
+    let until = until.with_timezone(&Utc);
+    if now < until {
+        return Err(AppError::UserLocked { minutes: (until - now).num_minutes() + 1 });
+    }
+    Ok(())
+}
+
+/// Count a wrong password and lock the account once the limit is reached.
+/// A lock that has already run out starts the count again.
+pub fn record_failed_login(conn: &Connection, user_id: &str, now: DateTime<Utc>) -> Result<FailedLogin, AppError> {
+    let policy = get_lockout_policy(conn)?;
+    let attempts: i32 = conn.query_row(
+        "UPDATE users SET
+             failed_login_attempts = CASE WHEN locked_until IS NULL THEN COALESCE(failed_login_attempts, 0) + 1 ELSE 1 END,
+             locked_until = NULL
+         WHERE id = ?1
+         RETURNING failed_login_attempts",
+        params![user_id],
+        |row| row.get(0),
+    )?;
+
+    if attempts < policy.max_attempts {
+        return Ok(FailedLogin::Remaining(policy.max_attempts - attempts));
+    }
+    conn.execute(
+        "UPDATE users SET locked_until = ?1 WHERE id = ?2",
+        params![(now + Duration::minutes(policy.duration_minutes)).to_rfc3339(), user_id],
+    )?;
+    Ok(FailedLogin::Locked { minutes: policy.duration_minutes })
+}
+
+/// Clear the failed-attempt counter and lock, and stamp the login time
+pub fn record_successful_login(conn: &Connection, user_id: &str, now: DateTime<Utc>) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE users SET failed_login_attempts = 0, locked_until = NULL, last_login = ?1 WHERE id = ?2",
+        params![now.to_rfc3339(), user_id],
+    )?;
+    Ok(())
+}
+
+/// Clear the failed-attempt counter and lock without stamping a login (admin re-checks)
+pub fn reset_failed_logins(conn: &Connection, user_id: &str) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE id = ?1",
+        params![user_id],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::OptionalExtension;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);
+             CREATE TABLE users (
+                 id TEXT PRIMARY KEY NOT NULL, failed_login_attempts INTEGER DEFAULT 0, locked_until TEXT, last_login TEXT
+             );
+             INSERT INTO users (id) VALUES ('admin');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn locked_until(conn: &Connection) -> Option<String> {
+        conn.query_row("SELECT locked_until FROM users WHERE id = 'admin'", [], |row| row.get(0))
+            .optional()
+            .unwrap()
+            .flatten()
+    }
+
+    #[test]
+    fn test_failures_lock_account_until_duration_passes() {
+        let conn = setup_db();
+        settings::set_setting(&conn, MAX_LOGIN_ATTEMPTS_SETTING, "3").unwrap();
+        settings::set_setting(&conn, LOCKOUT_DURATION_SETTING, "10").unwrap();
+        let now = Utc::now();
+
+        assert_eq!(record_failed_login(&conn, "admin", now).unwrap(), FailedLogin::Remaining(2));
+        assert_eq!(record_failed_login(&conn, "admin", now).unwrap(), FailedLogin::Remaining(1));
+        assert_eq!(record_failed_login(&conn, "admin", now).unwrap(), FailedLogin::Locked { minutes: 10 });
+
+        let until = locked_until(&conn);
+        assert!(matches!(
+            ensure_not_locked(until.as_deref(), now + Duration::minutes(5)),
+            Err(AppError::UserLocked { minutes: 6 })
+        ));
+        assert!(ensure_not_locked(until.as_deref(), now + Duration::minutes(11)).is_ok());
+
+        // After the lock runs out the count starts again
+        let later = now + Duration::minutes(11);
+        assert_eq!(record_failed_login(&conn, "admin", later).unwrap(), FailedLogin::Remaining(2));
+        assert_eq!(locked_until(&conn), None);
+    }
+
+    #[test]
+    fn test_successful_login_resets_counter() {
+        let conn = setup_db();
+        let now = Utc::now();
+        assert_eq!(get_lockout_policy(&conn).unwrap().max_attempts, DEFAULT_MAX_LOGIN_ATTEMPTS);
+
+        for _ in 0..DEFAULT_MAX_LOGIN_ATTEMPTS - 1 {
+            record_failed_login(&conn, "admin", now).unwrap();
+        }
+        record_successful_login(&conn, "admin", now).unwrap();
+        let attempts: i32 = conn
+            .query_row("SELECT failed_login_attempts FROM users WHERE id = 'admin'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(attempts, 0);
+
+        // A fresh set of attempts is needed to lock again
+        assert_eq!(
+            record_failed_login(&conn, "admin", now).unwrap(),
+            FailedLogin::Remaining(DEFAULT_MAX_LOGIN_ATTEMPTS - 1)
+        );
+
+        // Out-of-range settings fall back to the defaults
+        settings::set_setting(&conn, MAX_LOGIN_ATTEMPTS_SETTING, "0").unwrap();
+        settings::set_setting(&conn, LOCKOUT_DURATION_SETTING, "abc").unwrap();
+        assert_eq!(
+            get_lockout_policy(&conn).unwrap(),
+            LockoutPolicy { max_attempts: DEFAULT_MAX_LOGIN_ATTEMPTS, duration_minutes: DEFAULT_LOCKOUT_DURATION_MINUTES }
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Username/password login
+//!
+//! Şifre politikası (`login_policy`), hesap kilitleme (`lockout`) ve giriş kaydı (`auth_events`)
+//! burada birleşir. Başarılı ya da başarısız her deneme `auth_events` tablosuna yazılır.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::models::User;
+use crate::security::auth_events::{self, ACTION_LOGIN};
+use crate::security::password::verify_and_maybe_rehash;
+use crate::security::{lockout, login_policy};
+
+/// Fields of the user row a login needs
+struct LoginRow {
+    id: String,
+    username: String,
+    password_hash: String,
+    display_name: String,
+    role: String,
+    created_at: String,
+    locked_until: Option<String>,
+    must_change_password: bool,
+}
+
+/// Record the failed attempt and return its message as `AppError::Auth`
+fn reject<T>(conn: &Connection, action: &str, username: &str, user_id: Option<&str>, message: String) -> Result<T, AppError> {
+    auth_events::record_auth_event(conn, action, username, user_id, false, Some(&message))?;
+    Err(AppError::Auth(message))
+}
+
+/// Count a wrong password toward the lock and word the rejection
+fn wrong_password(conn: &Connection, user_id: &str, now: DateTime<Utc>) -> Result<String, AppError> {
+    Ok(match lockout::record_failed_login(conn, user_id, now)? {
+        lockout::FailedLogin::Remaining(remaining) => format!("Hatalı şifre. {} deneme hakkınız kaldı.", remaining),
+        lockout::FailedLogin::Locked { minutes } => {
+            format!("Çok fazla başarısız deneme. Hesabınız {} dakika kilitlendi.", minutes)
+        }
+    })
+}
+
+/// Check `username` / `password` and return the user.
+///
+/// Rejections come back as `AppError::Auth` carrying the message to show on the login screen.
+pub fn authenticate(conn: &Connection, username: &str, password: &str, now: DateTime<Utc>) -> Result<User, AppError> {
+    let row = conn
+        .query_row(
+            "SELECT id, username, password_hash, display_name, role, created_at, locked_until, must_change_password
+             FROM users WHERE username = ?1",
+            params![username],
+            |row| {
+                Ok(LoginRow {
+                    id: row.get(0)?,
+                    username: row.get(1)?,
+                    password_hash: row.get(2)?,
+                    display_name: row.get(3)?,
+                    role: row.get(4)?,
+                    created_at: row.get(5)?,
+                    locked_until: row.get(6)?,
+                    must_change_password: row.get::<_, bool>(7).unwrap_or(false),
+                })
+            },
+        )
+        .optional()?;
+    let Some(row) = row else {
+        return reject(conn, ACTION_LOGIN, username, None, "Kullanıcı bulunamadı".to_string());
+    };
+    let user_id = Some(row.id.as_str());
+
+    // Şifre zorunluluğu require_password_for_roles politikasına göre (varsayılan: yalnızca admin)
+    let password_required = login_policy::is_password_required(conn, &row.role)?;
+
+    // Hesap kilitleme yalnızca şifre zorunlu hesaplarda (şifresiz personel girişleri etkilenmez)
+    if password_required {
+        if let Err(AppError::UserLocked { minutes }) = lockout::ensure_not_locked(row.locked_until.as_deref(), now) {
+            return reject(conn, ACTION_LOGIN, username, user_id, format!("Hesabınız kilitli. {} dakika sonra tekrar deneyin.", minutes));
+        }
+    }
+
+    // Şifreyi doğrula
+    let is_valid = if password.is_empty() {
+        if password_required {
+            let message = if row.role == "admin" {
+                "Yönetici hesabı için şifre zorunludur"
+            } else {
+                "Bu hesap için şifre zorunludur"
+            };
+            return reject(conn, ACTION_LOGIN, username, user_id, message.to_string());
+        }
+        true
+    } else {
+        let (valid, rehashed) = verify_and_maybe_rehash(password, &row.password_hash).unwrap_or((false, None));
+        // Hash made with older, cheaper Argon2 parameters: store the upgraded one
+        if let Some(hash) = rehashed {
+            conn.execute("UPDATE users SET password_hash = ?1 WHERE id = ?2", params![hash, &row.id])?;
+        }
+        valid
+    };
+
+    if !is_valid {
+        if !password_required {
+            return reject(conn, ACTION_LOGIN, username, user_id, "Hatali sifre".to_string());
+        }
+        let message = wrong_password(conn, &row.id, now)?;
+        return reject(conn, ACTION_LOGIN, username, user_id, message);
+    }
+
+    // Basarili giris - sayaçları sıfırla
+    lockout::record_successful_login(conn, &row.id, now)?;
+    auth_events::record_auth_event(conn, ACTION_LOGIN, username, user_id, true, None)?;
+
+    Ok(User {
+        id: row.id,
+        username: row.username,
+        display_name: row.display_name,
+        role: row.role,
+        created_at: row.created_at,
+        last_login: Some(now.to_rfc3339()),
+        must_change_password: row.must_change_password,
+    })
+}
+
+/// Re-check an admin's password before a protected action (giriş politikası, yönetici onayı).
+///
+/// Wrong passwords count toward the same lock as `authenticate`; while the account is locked even
+/// the right password is refused with `AppError::UserLocked`. Other rejections are `AppError::Auth`.
+/// Every attempt is recorded under `action`. Returns the admin's user id.
+pub fn verify_admin(
+    conn: &Connection,
+    username: &str,
+    password: &str,
+    action: &str,
+    now: DateTime<Utc>,
+) -> Result<String, AppError> {
+    let row: Option<(String, String, String, Option<String>)> = conn
+        .query_row(
+            "SELECT id, password_hash, role, locked_until FROM users WHERE username = ?1",
+            params![username],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?;
+    let Some((id, password_hash, role, locked_until)) = row else {
+        return reject(conn, action, username, None, "Bu işlem için yönetici yetkisi gereklidir".to_string());
+    };
+    if role != "admin" {
+        return reject(conn, action, username, Some(&id), "Bu işlem için yönetici yetkisi gereklidir".to_string());
+    }
+
+    if let Err(locked) = lockout::ensure_not_locked(locked_until.as_deref(), now) {
+        auth_events::record_auth_event(conn, action, username, Some(&id), false, Some(&locked.to_string()))?;
+        return Err(locked);
+    }
+
+    let (valid, rehashed) = verify_and_maybe_rehash(password, &password_hash).unwrap_or((false, None));
+    if let Some(hash) = rehashed {
+        conn.execute("UPDATE users SET password_hash = ?1 WHERE id = ?2", params![hash, &id])?;
+    }
+    if !valid {
+        let message = wrong_password(conn, &id, now)?;
+        return reject(conn, action, username, Some(&id), message);
+    }
+
+    lockout::reset_failed_logins(conn, &id)?;
+    auth_events::record_auth_event(conn, action, username, Some(&id), true, None)?;
+    Ok(id)
+}
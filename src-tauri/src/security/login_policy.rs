@@ -0,0 +1,93 @@
+//! Login password policy
+//!
+//! `require_password_for_roles` ayarı hangi rollerin kullanıcı adı/şifre girişinde
+//! şifre girmek zorunda olduğunu belirler. Varsayılan: yalnızca `admin`
+//! (diğer roller boş şifreyle girebilir). `*` tüm rollerde şifre ister.
+//! Erişim kodu ile giriş (`login_with_code`) bu politikadan etkilenmez.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+
+pub const REQUIRE_PASSWORD_SETTING: &str = "require_password_for_roles";
+
+/// Wildcard value: every role must enter a password
+pub const ALL_ROLES: &str = "*";
+
+/// Default policy: only admins must enter a password
+pub const DEFAULT_REQUIRED_ROLES: &str = "admin";
+
+/// Parse the comma separated setting value; admin is always included
+fn parse_roles(value: &str) -> Vec<String> {
+    let mut roles: Vec<String> = value
+        .split(',')
+        .map(|r| r.trim().to_lowercase())
+        .filter(|r| !r.is_empty())
+        .collect();
+    if !roles.iter().any(|r| r == "admin" || r == ALL_ROLES) {
+        roles.insert(0, "admin".to_string());
+    }
+    roles.dedup();
+    roles
+}
+
+/// Roles that must enter a password (`["*"]` = all roles)
+pub fn get_password_required_roles(conn: &Connection) -> Result<Vec<String>, AppError> {
+    let value: Option<Option<String>> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![REQUIRE_PASSWORD_SETTING],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(parse_roles(&value.flatten().unwrap_or_else(|| DEFAULT_REQUIRED_ROLES.to_string())))
+}
+
+/// Store the policy. Admin cannot be removed from the list.
+pub fn set_password_required_roles(conn: &Connection, roles: &[String]) -> Result<Vec<String>, AppError> {
+    let roles = parse_roles(&roles.join(","));
+    let roles = if roles.iter().any(|r| r == ALL_ROLES) {
+        vec![ALL_ROLES.to_string()]
+    } else {
+        roles
+    };
+
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![REQUIRE_PASSWORD_SETTING, roles.join(",")],
+    )?;
+    Ok(roles)
+}
+
+/// Whether a user with this role must enter a password to log in
+pub fn is_password_required(conn: &Connection, role: &str) -> Result<bool, AppError> {
+    let role = role.to_lowercase();
+    Ok(get_password_required_roles(conn)?
+        .iter()
+        .any(|r| r == ALL_ROLES || *r == role))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_default_and_all_roles() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);")
+            .unwrap();
+
+        assert!(is_password_required(&conn, "admin").unwrap());
+        assert!(!is_password_required(&conn, "user").unwrap());
+
+        // Admin cannot be dropped from the policy
+        let stored = set_password_required_roles(&conn, &["user".to_string()]).unwrap();
+        assert_eq!(stored, vec!["admin", "user"]);
+        assert!(is_password_required(&conn, "admin").unwrap());
+
+        set_password_required_roles(&conn, &[ALL_ROLES.to_string()]).unwrap();
+        assert!(is_password_required(&conn, "user").unwrap());
+        assert!(is_password_required(&conn, "cashier").unwrap());
+    }
+}
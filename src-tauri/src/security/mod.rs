@@ -1,6 +1,13 @@
 #![allow(unused_imports)]
 
+pub mod admin;
+pub mod auth_events;
+pub mod lockout;
+pub mod login;
+pub mod login_policy;
 pub mod password;
+pub mod password_history;
+pub mod session;
 pub mod validation;
 
 pub use password::{hash_password, verify_password};
@@ -1,17 +1,51 @@
 use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
 use rand::rngs::OsRng;
 
 use crate::error::AppError;
 
-/// Hash a password using Argon2id
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashParams {
+    /// Memory in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+/// Parameters new hashes are made with. Raising them upgrades older hashes on the next login.
+pub const CURRENT_HASH_PARAMS: HashParams = HashParams {
+    memory_kib: Params::DEFAULT_M_COST,
+    iterations: Params::DEFAULT_T_COST,
+    parallelism: Params::DEFAULT_P_COST,
+};
+
+impl HashParams {
+    fn argon2(&self) -> Result<Argon2<'static>, AppError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| AppError::PasswordHash(format!("Gecersiz Argon2 parametreleri: {}", e)))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    /// Whether a hash made with these parameters is cheaper than `other` in any dimension
+    fn weaker_than(&self, other: &HashParams) -> bool {
+        self.memory_kib < other.memory_kib || self.iterations < other.iterations || self.parallelism < other.parallelism
+    }
+}
+
+/// Hash a password using Argon2id with the current parameters
 pub fn hash_password(password: &str) -> Result<String, AppError> {
+    hash_password_with(password, &CURRENT_HASH_PARAMS)
+}
+
+/// Hash a password using Argon2id with the given parameters
+pub fn hash_password_with(password: &str, params: &HashParams) -> Result<String, AppError> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
 
-    let password_hash = argon2
+    let password_hash = params
+        .argon2()?
         .hash_password(password.as_bytes(), &salt)
         .map_err(|e| AppError::PasswordHash(e.to_string()))?
         .to_string();
@@ -36,6 +70,33 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, AppError> {
         .is_ok())
 }
 
+/// Whether a stored hash is not Argon2id or was made with weaker parameters than the current ones
+pub fn needs_rehash(hash: &str) -> Result<bool, AppError> {
+    let parsed_hash = PasswordHash::new(hash)
+        .map_err(|e| AppError::PasswordHash(format!("Hash parse hatasi: {}", e)))?;
+    if parsed_hash.algorithm.as_str() != "argon2id" {
+        return Ok(true);
+    }
+    let params = Params::try_from(&parsed_hash)
+        .map_err(|e| AppError::PasswordHash(format!("Hash parse hatasi: {}", e)))?;
+    let stored = HashParams {
+        memory_kib: params.m_cost(),
+        iterations: params.t_cost(),
+        parallelism: params.p_cost(),
+    };
+    Ok(stored.weaker_than(&CURRENT_HASH_PARAMS))
+}
+
+/// Verify a password and, when it matches a hash made with weaker parameters, return a fresh hash
+/// with the current ones so the caller can store it
+pub fn verify_and_maybe_rehash(password: &str, stored_hash: &str) -> Result<(bool, Option<String>), AppError> {
+    if !verify_password(password, stored_hash)? {
+        return Ok((false, None));
+    }
+    let rehashed = if needs_rehash(stored_hash)? { Some(hash_password(password)?) } else { None };
+    Ok((true, rehashed))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -54,4 +115,27 @@ mod tests {
         assert!(verify_password("admin123", "admin123_hashed").is_err());
         assert!(verify_password("test", "plaintext").is_err());
     }
+
+    #[test]
+    fn test_weak_hash_is_rehashed() {
+        let weak = HashParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+        let weak_hash = hash_password_with("test123456", &weak).unwrap();
+        assert!(needs_rehash(&weak_hash).unwrap());
+
+        let (valid, rehashed) = verify_and_maybe_rehash("test123456", &weak_hash).unwrap();
+        assert!(valid);
+        let rehashed = rehashed.unwrap();
+        assert!(verify_password("test123456", &rehashed).unwrap());
+        assert!(!needs_rehash(&rehashed).unwrap());
+
+        // A wrong password never produces a new hash
+        assert_eq!(verify_and_maybe_rehash("wrong", &weak_hash).unwrap(), (false, None));
+    }
+
+    #[test]
+    fn test_current_hash_is_not_rehashed() {
+        let hash = hash_password("test123456").unwrap();
+        assert!(!needs_rehash(&hash).unwrap());
+        assert_eq!(verify_and_maybe_rehash("test123456", &hash).unwrap(), (true, None));
+    }
 }
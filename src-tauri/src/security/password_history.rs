@@ -0,0 +1,125 @@
+//! Password history
+//!
+//! Şifre değiştirilirken eski hash `password_history` tablosuna yazılır. Yeni şifre mevcut şifreyle
+//! ya da son `password_history_size` (varsayılan 3) eski şifreden biriyle aynıysa reddedilir.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::security::password::{hash_password, verify_password};
+use crate::services::settings;
+
+pub const PASSWORD_HISTORY_SETTING: &str = "password_history_size";
+
+pub const DEFAULT_PASSWORD_HISTORY_SIZE: i64 = 3;
+
+/// Previous passwords kept per user; missing or out-of-range settings fall back to the default
+pub fn get_history_size(conn: &Connection) -> Result<i64, AppError> {
+    Ok(settings::get_setting(conn, PASSWORD_HISTORY_SETTING)?
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|n| (1..=24).contains(n))
+        .unwrap_or(DEFAULT_PASSWORD_HISTORY_SIZE))
+}
+
+/// Set a new password for `user_id`, rejecting the current one and the last N previous ones.
+/// The replaced hash goes into the history, which is trimmed to N entries.
+pub fn change_password(conn: &Connection, user_id: &str, new_password: &str) -> Result<(), AppError> {
+    let size = get_history_size(conn)?;
+    let current: String = conn
+        .query_row("SELECT password_hash FROM users WHERE id = ?1", params![user_id], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Kullanici bulunamadi: {}", user_id)))?;
+
+    let previous: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT password_hash FROM password_history WHERE user_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![user_id, size], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    // Hashes from before Argon2 cannot be checked and do not block the change
+    if std::iter::once(&current)
+        .chain(&previous)
+        .any(|hash| verify_password(new_password, hash).unwrap_or(false))
+    {
+        return Err(AppError::Validation(format!(
+            "Yeni sifre mevcut sifre veya son {} sifreden biriyle ayni olamaz",
+            size
+        )));
+    }
+
+    let password_hash = hash_password(new_password)?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE users SET password_hash = ?1, must_change_password = 0 WHERE id = ?2",
+        params![&password_hash, user_id],
+    )?;
+    tx.execute(
+        "INSERT INTO password_history (user_id, password_hash, changed_at) VALUES (?1, ?2, ?3)",
+        params![user_id, &current, chrono::Utc::now().to_rfc3339()],
+    )?;
+    tx.execute(
+        "DELETE FROM password_history WHERE user_id = ?1 AND id NOT IN (
+             SELECT id FROM password_history WHERE user_id = ?1 ORDER BY id DESC LIMIT ?2
+         )",
+        params![user_id, size],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);
+             CREATE TABLE users (id TEXT PRIMARY KEY NOT NULL, password_hash TEXT NOT NULL, must_change_password BOOLEAN DEFAULT 0);
+             CREATE TABLE password_history (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT, user_id TEXT NOT NULL, password_hash TEXT NOT NULL, changed_at TEXT NOT NULL
+             );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO users (id, password_hash, must_change_password) VALUES ('u1', ?1, 1)",
+            params![hash_password("Sifre-1").unwrap()],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn history_len(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM password_history WHERE user_id = 'u1'", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_recent_passwords_cannot_be_reused() {
+        let conn = setup_db();
+        for password in ["Sifre-2", "Sifre-3", "Sifre-4"] {
+            change_password(&conn, "u1", password).unwrap();
+        }
+        assert_eq!(history_len(&conn), 3);
+
+        // Current password
+        assert!(matches!(change_password(&conn, "u1", "Sifre-4"), Err(AppError::Validation(_))));
+        // Third-previous password
+        assert!(matches!(change_password(&conn, "u1", "Sifre-1"), Err(AppError::Validation(_))));
+
+        // A new one is accepted and the oldest entry drops out of the history
+        change_password(&conn, "u1", "Yepyeni-5").unwrap();
+        assert_eq!(history_len(&conn), 3);
+        let (hash, must_change): (String, bool) = conn
+            .query_row("SELECT password_hash, must_change_password FROM users WHERE id = 'u1'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert!(verify_password("Yepyeni-5", &hash).unwrap());
+        assert!(!must_change);
+        change_password(&conn, "u1", "Sifre-1").unwrap();
+
+        assert!(matches!(change_password(&conn, "nope", "Baska-1"), Err(AppError::NotFound(_))));
+    }
+}
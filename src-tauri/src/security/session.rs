@@ -0,0 +1,218 @@
+//! Login sessions
+//!
+//! `login` / `login_with_code` bir oturum anahtarı (token) üretir ve `sessions` tablosuna yazar.
+//! Oturum `session_idle_timeout_minutes` (varsayılan 120) dakika kullanılmazsa sona erer; her
+//! başarılı doğrulama süreyi yeniden başlatır. Hassas komutlar geçerli bir yönetici oturumu ister.
+
+use chrono::{DateTime, Duration, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::models::{AuthSession, User};
+use crate::services::settings;
+
+pub const SESSION_IDLE_TIMEOUT_SETTING: &str = "session_idle_timeout_minutes";
+
+pub const DEFAULT_SESSION_IDLE_TIMEOUT_MINUTES: i64 = 120;
+
+/// User id prefix of access-code logins (`code_<access_codes.id>`)
+pub const ACCESS_CODE_USER_PREFIX: &str = "code_";
+
+const TOKEN_LENGTH: usize = 48;
+
+fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Idle timeout from settings; missing or out-of-range values fall back to the default
+pub fn get_idle_timeout_minutes(conn: &Connection) -> Result<i64, AppError> {
+    Ok(settings::get_setting(conn, SESSION_IDLE_TIMEOUT_SETTING)?
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|n| (1..=10080).contains(n))
+        .unwrap_or(DEFAULT_SESSION_IDLE_TIMEOUT_MINUTES))
+}
+
+/// The user behind a session: a `users` row or an access code
+fn load_user(conn: &Connection, user_id: &str) -> Result<Option<User>, AppError> {
+    if let Some(code_id) = user_id.strip_prefix(ACCESS_CODE_USER_PREFIX) {
+        let user = conn
+            .query_row(
+                "SELECT name, role, created_at FROM access_codes WHERE id = ?1",
+                params![code_id],
+                |row| {
+                    let name: String = row.get(0)?;
+                    Ok(User {
+                        id: user_id.to_string(),
+                        username: name.clone(),
+                        display_name: name,
+                        role: row.get(1)?,
+                        created_at: row.get(2)?,
+                        last_login: None,
+                        must_change_password: false,
+                    })
+                },
+            )
+            .optional()?;
+        return Ok(user);
+    }
+
+    let user = conn
+        .query_row(
+            "SELECT id, username, display_name, role, created_at, last_login, must_change_password FROM users WHERE id = ?1",
+            params![user_id],
+            |row| {
+                Ok(User {
+                    id: row.get(0)?,
+                    username: row.get(1)?,
+                    display_name: row.get(2)?,
+                    role: row.get(3)?,
+                    created_at: row.get(4)?,
+                    last_login: row.get(5)?,
+                    must_change_password: row.get::<_, Option<bool>>(6)?.unwrap_or(false),
+                })
+            },
+        )
+        .optional()?;
+    Ok(user)
+}
+
+/// Start a session for a user who just logged in. Expired sessions are cleaned up on the way.
+pub fn create_session(conn: &Connection, user: User, now: DateTime<Utc>) -> Result<AuthSession, AppError> {
+    let expires_at = (now + Duration::minutes(get_idle_timeout_minutes(conn)?)).to_rfc3339();
+    let token = generate_token();
+
+    conn.execute("DELETE FROM sessions WHERE expires_at <= ?1", params![now.to_rfc3339()])?;
+    conn.execute(
+        "INSERT INTO sessions (token, user_id, created_at, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        params![&token, &user.id, now.to_rfc3339(), &expires_at],
+    )?;
+    Ok(AuthSession { user, token, expires_at })
+}
+
+/// User of a live session, pushing its expiry forward; None for unknown, expired or orphaned tokens
+pub fn validate_session(conn: &Connection, token: &str, now: DateTime<Utc>) -> Result<Option<User>, AppError> {
+    let session: Option<(String, String)> = conn
+        .query_row(
+            "SELECT user_id, expires_at FROM sessions WHERE token = ?1",
+            params![token],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((user_id, expires_at)) = session else {
+        return Ok(None);
+    };
+
+    let expired = DateTime::parse_from_rfc3339(&expires_at).map_or(true, |t| t.with_timezone(&Utc) <= now);
+    let user = if expired { None } else { load_user(conn, &user_id)? };
+    let Some(user) = user else {
+        revoke_session(conn, token)?;
+        return Ok(None);
+    };
+
+    conn.execute(
+        "UPDATE sessions SET expires_at = ?1 WHERE token = ?2",
+        params![(now + Duration::minutes(get_idle_timeout_minutes(conn)?)).to_rfc3339(), token],
+    )?;
+    Ok(Some(user))
+}
+
+/// End one session (logout)
+pub fn revoke_session(conn: &Connection, token: &str) -> Result<(), AppError> {
+    conn.execute("DELETE FROM sessions WHERE token = ?1", params![token])?;
+    Ok(())
+}
+
+/// End every session of a user (deleted user, revoked cashier)
+pub fn revoke_user_sessions(conn: &Connection, user_id: &str) -> Result<(), AppError> {
+    conn.execute("DELETE FROM sessions WHERE user_id = ?1", params![user_id])?;
+    Ok(())
+}
+
+/// The admin behind `token`, or `AppError::Auth` when the session is invalid or not an admin's
+pub fn require_admin_session(conn: &Connection, token: &str, now: DateTime<Utc>) -> Result<User, AppError> {
+    match validate_session(conn, token, now)? {
+        Some(user) if user.role == "admin" => Ok(user),
+        Some(_) => Err(AppError::Auth("Bu işlem için yönetici yetkisi gereklidir".to_string())),
+        None => Err(AppError::Auth("Oturum süresi dolmuş, lütfen yeniden giriş yapın".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);
+             CREATE TABLE users (
+                 id TEXT PRIMARY KEY NOT NULL, username TEXT, display_name TEXT, role TEXT, created_at TEXT,
+                 last_login TEXT, must_change_password BOOLEAN DEFAULT 0
+             );
+             CREATE TABLE access_codes (id INTEGER PRIMARY KEY, code TEXT, name TEXT, role TEXT, created_at TEXT);
+             CREATE TABLE sessions (token TEXT PRIMARY KEY NOT NULL, user_id TEXT NOT NULL, created_at TEXT NOT NULL, expires_at TEXT NOT NULL);
+             INSERT INTO users (id, username, display_name, role, created_at) VALUES
+                 ('u-admin', 'admin', 'Yonetici', 'admin', '2024-01-01'),
+                 ('u-kasa', 'kasa', 'Kasiyer', 'user', '2024-01-01');
+             INSERT INTO access_codes VALUES (7, '1234', 'Vardiya', 'user', '2024-01-01');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn user(conn: &Connection, id: &str) -> User {
+        load_user(conn, id).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_session_expires_after_idle_timeout() {
+        let conn = setup_db();
+        settings::set_setting(&conn, SESSION_IDLE_TIMEOUT_SETTING, "30").unwrap();
+        let now = Utc::now();
+
+        let session = create_session(&conn, user(&conn, "u-admin"), now).unwrap();
+        assert_eq!(session.token.len(), TOKEN_LENGTH);
+
+        // Use within the timeout slides the expiry forward
+        let later = now + Duration::minutes(20);
+        assert_eq!(validate_session(&conn, &session.token, later).unwrap().unwrap().id, "u-admin");
+        assert!(validate_session(&conn, &session.token, later + Duration::minutes(20)).unwrap().is_some());
+
+        // Idle longer than the timeout: gone for good
+        let idle = later + Duration::minutes(51);
+        assert!(validate_session(&conn, &session.token, idle).unwrap().is_none());
+        assert!(validate_session(&conn, &session.token, later).unwrap().is_none());
+        assert!(require_admin_session(&conn, &session.token, later).is_err());
+    }
+
+    #[test]
+    fn test_revoked_sessions_are_rejected() {
+        let conn = setup_db();
+        let now = Utc::now();
+
+        let admin = create_session(&conn, user(&conn, "u-admin"), now).unwrap();
+        let cashier = create_session(&conn, user(&conn, "u-kasa"), now).unwrap();
+        let code = create_session(&conn, user(&conn, "code_7"), now).unwrap();
+        assert_eq!(validate_session(&conn, &code.token, now).unwrap().unwrap().display_name, "Vardiya");
+
+        assert_eq!(require_admin_session(&conn, &admin.token, now).unwrap().id, "u-admin");
+        assert!(matches!(require_admin_session(&conn, &cashier.token, now), Err(AppError::Auth(_))));
+
+        revoke_session(&conn, &admin.token).unwrap();
+        assert!(validate_session(&conn, &admin.token, now).unwrap().is_none());
+
+        revoke_user_sessions(&conn, "u-kasa").unwrap();
+        assert!(validate_session(&conn, &cashier.token, now).unwrap().is_none());
+
+        // Deleting the access code ends its sessions too
+        conn.execute("DELETE FROM access_codes WHERE id = 7", []).unwrap();
+        assert!(validate_session(&conn, &code.token, now).unwrap().is_none());
+        assert!(validate_session(&conn, "unknown", now).unwrap().is_none());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::services::units;
+
 /// Validate SKU format: alphanumeric with hyphens and underscores, max 50 chars
 pub fn validate_sku(sku: &str) -> Result<(), String> {
     if sku.is_empty() {
@@ -59,12 +61,29 @@ pub fn validate_price(price: f64) -> Result<(), String> {
     Ok(())
 }
 
-/// Validate quantity is non-negative
-pub fn validate_quantity(quantity: i32) -> Result<(), String> {
-    if quantity < 0 {
+/// Validate quantity is non-negative, and whole for counted units (ADET, PAKET...)
+pub fn validate_quantity(quantity: f64, unit: &str) -> Result<(), String> {
+    if quantity.is_nan() || quantity.is_infinite() {
+        return Err("Gecersiz miktar degeri".to_string());
+    }
+
+    if quantity < 0.0 {
         return Err("Miktar negatif olamaz".to_string());
     }
 
+    if quantity.fract() != 0.0 && !units::allows_fraction(unit) {
+        return Err(format!("{} birimli urunlerde miktar tam sayi olmali", units::normalize_unit(unit)));
+    }
+
+    Ok(())
+}
+
+/// Validate low stock threshold is non-negative
+pub fn validate_min_stock_level(level: i32) -> Result<(), String> {
+    if level < 0 {
+        return Err("Minimum stok seviyesi negatif olamaz".to_string());
+    }
+
     Ok(())
 }
 
@@ -81,6 +100,17 @@ mod tests {
         assert!(validate_sku("invalid sku").is_err());
     }
 
+    #[test]
+    fn test_validate_quantity_by_unit() {
+        assert!(validate_quantity(3.0, "ADET").is_ok());
+        assert!(validate_quantity(2.5, "MT").is_ok());
+        assert!(validate_quantity(0.75, "kg").is_ok());
+        assert!(validate_quantity(2.5, "ADET").is_err());
+        assert!(validate_quantity(2.5, "").is_err());
+        assert!(validate_quantity(-1.0, "KG").is_err());
+        assert!(validate_quantity(f64::NAN, "KG").is_err());
+    }
+
     #[test]
     fn test_validate_password_strength() {
         assert!(validate_password_strength("password123").is_ok());
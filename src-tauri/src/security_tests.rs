@@ -2,20 +2,24 @@
 #[cfg(test)]
 mod tests {
     use rusqlite::{params, Connection};
-    use crate::models::{CartItem, InventoryItem};
-    use crate::services::inventory;
+    use crate::models::{CartItem, InventoryItem, Payment, ReturnItem, SaleDiscount, StockCard, Transaction};
+    use crate::error::AppError;
+    use crate::services::{finance, inventory, print, reports, settings};
 
     fn setup_db() -> Connection {
         let conn = Connection::open_in_memory().unwrap();
         
         conn.execute_batch(
             "
+            CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);
+            CREATE TABLE exchange_rates (currency TEXT PRIMARY KEY NOT NULL, rate_to_try REAL NOT NULL, updated_at TEXT NOT NULL);
+
             CREATE TABLE inventory_items (
                 id TEXT PRIMARY KEY NOT NULL,
                 sku TEXT UNIQUE NOT NULL,
                 name TEXT NOT NULL,
                 category TEXT DEFAULT 'Genel',
-                quantity INTEGER DEFAULT 0 CHECK (quantity >= 0),
+                quantity INTEGER DEFAULT 0,
                 location TEXT DEFAULT '',
                 price REAL DEFAULT 0.00 CHECK (price >= 0),
                 cost_price REAL DEFAULT 0.00,
@@ -24,7 +28,11 @@ mod tests {
                 ai_tags TEXT,
                 last_updated TEXT NOT NULL,
                 currency TEXT DEFAULT 'TL',
-                supplier_id TEXT
+                supplier_id TEXT,
+                brand TEXT,
+                min_stock_level INTEGER NOT NULL DEFAULT 10,
+                deleted_at TEXT,
+                unit TEXT NOT NULL DEFAULT 'ADET'
             );
 
             CREATE TABLE transactions (
@@ -35,7 +43,54 @@ mod tests {
                 transaction_type TEXT DEFAULT 'SALE',
                 note TEXT,
                 created_at TEXT NOT NULL,
-                customer_id TEXT
+                customer_id TEXT,
+                cost_of_goods REAL,
+                status TEXT NOT NULL DEFAULT 'completed',
+                tax_total REAL NOT NULL DEFAULT 0,
+                original_transaction_id TEXT,
+                line_discount REAL NOT NULL DEFAULT 0,
+                ticket_discount REAL NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE transaction_payments (
+                transaction_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                method TEXT NOT NULL,
+                amount REAL NOT NULL,
+                PRIMARY KEY (transaction_id, position)
+            );
+
+            CREATE TABLE finance_records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                record_type TEXT NOT NULL,
+                category TEXT NOT NULL,
+                amount REAL NOT NULL,
+                payment_method TEXT NOT NULL DEFAULT 'NAKIT',
+                description TEXT DEFAULT '',
+                date TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE activity_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                action_type TEXT NOT NULL,
+                description TEXT NOT NULL,
+                item_id TEXT,
+                item_name TEXT,
+                quantity_change INTEGER,
+                value REAL,
+                user_id TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE sale_lot_consumptions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                transaction_id TEXT NOT NULL,
+                lot_id TEXT NOT NULL,
+                product_id TEXT NOT NULL,
+                quantity INTEGER NOT NULL,
+                unit_cost REAL NOT NULL,
+                created_at TEXT NOT NULL
             );
 
             CREATE TABLE current_accounts (
@@ -63,7 +118,15 @@ mod tests {
                 sell_price REAL,
                 receipt_date TEXT NOT NULL,
                 invoice_no TEXT,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                expiry_date TEXT
+            );
+
+            CREATE TABLE product_components (
+                parent_sku TEXT NOT NULL,
+                component_sku TEXT NOT NULL,
+                quantity INTEGER NOT NULL CHECK (quantity > 0),
+                PRIMARY KEY (parent_sku, component_sku)
             );
 
             CREATE TABLE stock_cards (
@@ -100,9 +163,12 @@ mod tests {
                 id: "item-1".to_string(),
                 sku: "SKU123".to_string(),
                 name: "Test Item".to_string(),
-                cart_quantity: 2,
+                cart_quantity: 2.0,
                 price: 100.0,
                 cost_price: None,
+                tax_rate: 20.0,
+                discount_amount: None,
+                discount_percent: None,
             }
         ];
 
@@ -128,6 +194,271 @@ mod tests {
         assert_eq!(new_qty, 8);
     }
 
+    fn sell_one_line(conn: &mut Connection, sku: &str, quantity: f64) -> Result<Transaction, AppError> {
+        let cart_items = vec![CartItem {
+            id: sku.to_string(),
+            sku: sku.to_string(),
+            name: sku.to_string(),
+            cart_quantity: quantity,
+            price: 40.0,
+            cost_price: None,
+            tax_rate: 20.0,
+            discount_amount: None,
+            discount_percent: None,
+        }];
+        inventory::process_sale_transaction(conn, cart_items, "NAKIT".to_string(), "SALE".to_string(), None, None)
+    }
+
+    #[test]
+    fn test_process_sale_fractional_quantity_for_meter_unit() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated, unit) VALUES ('cable', 'KABLO', 'Kablo 3x1.5', 10, 40.0, '2024-01-01', 'MT')",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, created_at) VALUES ('lot-1', 'cable', 10, 10, 25.0, '2024-01-01', '2024-01-01')",
+            [],
+        ).unwrap();
+
+        let tx = sell_one_line(&mut conn, "KABLO", 2.5).unwrap();
+        assert_eq!(tx.total, 100.0);
+
+        let (stock, lot): (f64, f64) = conn.query_row(
+            "SELECT i.quantity, l.quantity FROM inventory_items i, inventory_lots l WHERE i.sku = 'KABLO' AND l.id = 'lot-1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!((stock, lot), (7.5, 7.5));
+
+        // Repeated fractional sales do not drift
+        for _ in 0..3 {
+            sell_one_line(&mut conn, "KABLO", 0.1).unwrap();
+        }
+        let stock: f64 = conn.query_row("SELECT quantity FROM inventory_items WHERE sku = 'KABLO'", [], |row| row.get(0)).unwrap();
+        assert_eq!(stock, 7.2);
+    }
+
+    #[test]
+    fn test_process_sale_rejects_fractional_quantity_for_adet_unit() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('brush', 'FIRCA', 'Firca', 10, 40.0, '2024-01-01')",
+            [],
+        ).unwrap();
+
+        assert!(matches!(sell_one_line(&mut conn, "FIRCA", 2.5), Err(AppError::Validation(_))));
+        let stock: i32 = conn.query_row("SELECT quantity FROM inventory_items WHERE sku = 'FIRCA'", [], |row| row.get(0)).unwrap();
+        assert_eq!(stock, 10);
+    }
+
+    #[test]
+    fn test_process_sale_stores_kdv_by_rate() {
+        let mut conn = setup_db();
+        conn.execute_batch(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES
+             ('bread', 'EKMEK', 'Ekmek', 50, 10.1, '2024-01-01'),
+             ('book', 'KITAP', 'Kitap', 10, 55.0, '2024-01-01'),
+             ('paint', 'BOYA', 'Boya', 10, 120.0, '2024-01-01');",
+        ).unwrap();
+
+        let line = |id: &str, sku: &str, quantity: i32, price: f64, tax_rate: f64| CartItem {
+            id: id.to_string(),
+            sku: sku.to_string(),
+            name: sku.to_string(),
+            cart_quantity: quantity.into(),
+            price,
+            cost_price: None,
+            tax_rate,
+            discount_amount: None,
+            discount_percent: None,
+        };
+        let cart_items = vec![
+            line("bread", "EKMEK", 5, 10.1, 1.0),
+            line("book", "KITAP", 2, 55.0, 10.0),
+            line("paint", "BOYA", 1, 120.0, 20.0),
+        ];
+        let tx = inventory::process_sale_transaction(&mut conn, cart_items, "NAKIT".to_string(), "SALE".to_string(), None, None).unwrap();
+
+        let stored: f64 = conn.query_row(
+            "SELECT tax_total FROM transactions WHERE id = ?1",
+            params![&tx.id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(stored, tx.tax_total);
+
+        let groups = print::calculate_totals(&tx, &print::PrintConfig::default()).groups;
+        assert_eq!(groups.iter().map(|g| g.rate).collect::<Vec<_>>(), vec![1.0, 10.0, 20.0]);
+        assert_eq!(groups.iter().map(|g| g.tax).collect::<Vec<_>>(), vec![0.5, 10.0, 20.0]);
+        assert_eq!(groups.iter().map(|g| g.tax).sum::<f64>(), stored);
+        assert_eq!(stored, 30.5);
+
+        // Lines without a rate (older clients) are taxed at 20%
+        let legacy: CartItem = serde_json::from_str(
+            r#"{"id":"paint","sku":"BOYA","name":"Boya","cartQuantity":1,"price":120.0,"costPrice":null}"#,
+        ).unwrap();
+        assert_eq!(legacy.tax_rate, 20.0);
+    }
+
+    #[test]
+    fn test_process_sale_consumes_lots_fifo() {
+        let mut conn = setup_db();
+
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, cost_price, last_updated) VALUES ('item-1', 'SKU123', 'Test Item', 20, 100.0, 70.0, '2024-01-01')",
+            [],
+        ).unwrap();
+        // Two receipts at different buy prices; the newer lot was entered first
+        conn.execute_batch(
+            "INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, created_at) VALUES
+             ('lot-new', 'item-1', 10, 10, 60.0, '2024-02-01', '2024-01-01T00:00:00Z'),
+             ('lot-old', 'item-1', 5, 5, 50.0, '2024-01-15', '2024-02-01T00:00:00Z');",
+        ).unwrap();
+
+        let sell = |conn: &mut Connection, quantity: i32| {
+            let cart_items = vec![CartItem {
+                id: "item-1".to_string(),
+                sku: "SKU123".to_string(),
+                name: "Test Item".to_string(),
+                cart_quantity: quantity.into(),
+                price: 100.0,
+                cost_price: None,
+                tax_rate: 20.0,
+                discount_amount: None,
+                discount_percent: None,
+            }];
+            let tx = inventory::process_sale_transaction(conn, cart_items, "NAKIT".to_string(), "SALE".to_string(), None, None).unwrap();
+            let cost: f64 = conn.query_row(
+                "SELECT cost_of_goods FROM transactions WHERE id = ?1",
+                params![&tx.id],
+                |row| row.get(0),
+            ).unwrap();
+            (tx.id, cost)
+        };
+        let lot_qty = |conn: &Connection, id: &str| -> i32 {
+            conn.query_row("SELECT quantity FROM inventory_lots WHERE id = ?1", params![id], |row| row.get(0)).unwrap()
+        };
+
+        // 5 from the older lot at 50 + 3 from the newer at 60
+        let (first_id, first_cost) = sell(&mut conn, 8);
+        assert_eq!(first_cost, 5.0 * 50.0 + 3.0 * 60.0);
+        assert_eq!(lot_qty(&conn, "lot-old"), 0);
+        assert_eq!(lot_qty(&conn, "lot-new"), 7);
+        let splits: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM sale_lot_consumptions WHERE transaction_id = ?1",
+            params![&first_id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(splits, 2);
+
+        // 7 left in lots; the other 2 fall back to the item's cost price with a warning
+        let (_, second_cost) = sell(&mut conn, 9);
+        assert_eq!(second_cost, 7.0 * 60.0 + 2.0 * 70.0);
+        assert_eq!(lot_qty(&conn, "lot-new"), 0);
+        let warnings: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM activity_log WHERE action_type = ?1 AND quantity_change = -2",
+            params![inventory::ACTIVITY_LOT_SHORTFALL],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(warnings, 1);
+    }
+
+    #[test]
+    fn test_process_sale_consumes_earliest_expiry_first() {
+        let mut conn = setup_db();
+
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, cost_price, last_updated) VALUES ('item-1', 'SKU123', 'Silikon', 18, 100.0, 70.0, '2024-01-01')",
+            [],
+        ).unwrap();
+        // Plain FIFO would take lot-old first; the later receipt expires sooner
+        conn.execute_batch(
+            "INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, created_at, expiry_date) VALUES
+             ('lot-old', 'item-1', 5, 5, 50.0, '2024-01-15', '2024-01-15T00:00:00Z', '2025-06-30'),
+             ('lot-new', 'item-1', 5, 5, 60.0, '2024-02-01', '2024-02-01T00:00:00Z', '2024-12-31'),
+             ('lot-plain', 'item-1', 8, 8, 40.0, '2024-01-01', '2024-01-01T00:00:00Z', NULL);",
+        ).unwrap();
+        let lot_qty = |conn: &Connection, id: &str| -> i32 {
+            conn.query_row("SELECT quantity FROM inventory_lots WHERE id = ?1", params![id], |row| row.get(0)).unwrap()
+        };
+
+        let cart_items = vec![CartItem {
+            id: "item-1".to_string(),
+            sku: "SKU123".to_string(),
+            name: "Silikon".to_string(),
+            cart_quantity: 12.0,
+            price: 100.0,
+            cost_price: None,
+            tax_rate: 20.0,
+            discount_amount: None,
+            discount_percent: None,
+        }];
+        let tx = inventory::process_sale_transaction(&mut conn, cart_items, "NAKIT".to_string(), "SALE".to_string(), None, None).unwrap();
+        let cost: f64 = conn.query_row(
+            "SELECT cost_of_goods FROM transactions WHERE id = ?1",
+            params![&tx.id],
+            |row| row.get(0),
+        ).unwrap();
+
+        // Earliest expiry, then the next one, then lots without expiry
+        assert_eq!(lot_qty(&conn, "lot-new"), 0);
+        assert_eq!(lot_qty(&conn, "lot-old"), 0);
+        assert_eq!(lot_qty(&conn, "lot-plain"), 6);
+        assert_eq!(cost, 5.0 * 60.0 + 5.0 * 50.0 + 2.0 * 40.0);
+    }
+
+    #[test]
+    fn test_void_transaction_restores_stock_once() {
+        let mut conn = setup_db();
+
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('item-1', 'SKU123', 'Test Item', 10, 100.0, '2024-01-01')",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, created_at) VALUES ('lot-1', 'item-1', 10, 10, 60.0, '2024-01-01', '2024-01-01')",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO current_accounts (id, name, created_at, updated_at) VALUES ('cust', 'Musteri', 'now', 'now')",
+            [],
+        ).unwrap();
+
+        let cart_items = vec![CartItem {
+            id: "item-1".to_string(),
+            sku: "SKU123".to_string(),
+            name: "Test Item".to_string(),
+            cart_quantity: 3.0,
+            price: 100.0,
+            cost_price: None,
+            tax_rate: 20.0,
+            discount_amount: None,
+            discount_percent: None,
+        }];
+        let sale = inventory::process_sale_transaction(
+            &mut conn, cart_items, "VERESIYE".to_string(), "SALE".to_string(), None, Some("cust".to_string())
+        ).unwrap();
+
+        let state = |conn: &Connection| -> (i32, i32, f64) {
+            conn.query_row(
+                "SELECT i.quantity, l.quantity, a.balance FROM inventory_items i, inventory_lots l, current_accounts a
+                 WHERE i.id = 'item-1' AND l.id = 'lot-1' AND a.id = 'cust'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            ).unwrap()
+        };
+        assert_eq!(state(&conn), (7, 7, 300.0));
+
+        let voided = inventory::void_transaction(&mut conn, &sale.id).unwrap();
+        assert_eq!(voided.status, "voided");
+        assert_eq!(state(&conn), (10, 10, 0.0));
+
+        // Second void must not add the stock back again
+        assert!(matches!(inventory::void_transaction(&mut conn, &sale.id), Err(AppError::Validation(_))));
+        assert_eq!(state(&conn), (10, 10, 0.0));
+        assert!(matches!(inventory::void_transaction(&mut conn, "missing"), Err(AppError::NotFound(_))));
+    }
+
     #[test]
     fn test_process_sale_price_manipulation() {
         let mut conn = setup_db();
@@ -143,9 +474,12 @@ mod tests {
                 id: "item-1".to_string(),
                 sku: "SKU123".to_string(),
                 name: "Test Item".to_string(),
-                cart_quantity: 1,
+                cart_quantity: 1.0,
                 price: 50.0, // MANIPULATED PRICE (Should be 100.0)
                 cost_price: None,
+                tax_rate: 20.0,
+                discount_amount: None,
+                discount_percent: None,
             }
         ];
 
@@ -163,6 +497,49 @@ mod tests {
         assert!(err.contains("Fiyat uyuşmazlığı"));
     }
 
+    fn stock_of(conn: &Connection, sku: &str) -> f64 {
+        conn.query_row("SELECT quantity FROM inventory_items WHERE sku = ?1", params![sku], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_process_sale_rejects_oversell_by_default() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('brush', 'FIRCA-2', 'Firca', 3, 40.0, '2024-01-01')",
+            [],
+        ).unwrap();
+
+        let err = sell_one_line(&mut conn, "FIRCA-2", 5.0).unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+        let message = err.to_string();
+        assert!(message.contains("FIRCA-2") && message.contains("mevcut: 3 ADET"), "{}", message);
+        assert_eq!(stock_of(&conn, "FIRCA-2"), 3.0);
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 0);
+
+        // Explicitly off behaves the same; the whole stock can still be sold
+        settings::set_setting(&conn, settings::ALLOW_NEGATIVE_STOCK_SETTING, "false").unwrap();
+        assert!(sell_one_line(&mut conn, "FIRCA-2", 4.0).is_err());
+        sell_one_line(&mut conn, "FIRCA-2", 3.0).unwrap();
+        assert_eq!(stock_of(&conn, "FIRCA-2"), 0.0);
+    }
+
+    #[test]
+    fn test_process_sale_records_negative_stock_when_allowed() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('brush', 'FIRCA-2', 'Firca', 3, 40.0, '2024-01-01')",
+            [],
+        ).unwrap();
+        settings::set_setting(&conn, settings::ALLOW_NEGATIVE_STOCK_SETTING, "true").unwrap();
+
+        sell_one_line(&mut conn, "FIRCA-2", 5.0).unwrap();
+        // The oversold 2 show up as backorder instead of being clamped away
+        assert_eq!(stock_of(&conn, "FIRCA-2"), -2.0);
+        sell_one_line(&mut conn, "FIRCA-2", 1.0).unwrap();
+        assert_eq!(stock_of(&conn, "FIRCA-2"), -3.0);
+    }
+
     #[test]
     fn test_process_sale_negative_quantity() {
         let mut conn = setup_db();
@@ -178,9 +555,12 @@ mod tests {
                 id: "item-1".to_string(),
                 sku: "SKU123".to_string(),
                 name: "Test Item".to_string(),
-                cart_quantity: -5, // NEGATIVE QUANTITY
+                cart_quantity: -5.0, // NEGATIVE QUANTITY
                 price: 100.0,
                 cost_price: None,
+                tax_rate: 20.0,
+                discount_amount: None,
+                discount_percent: None,
             }
         ];
 
@@ -226,4 +606,575 @@ mod tests {
         let result = inventory::delete_stock_card_safe(&conn, "sc-1");
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_add_item_duplicate_sku_conflict() {
+        let conn = setup_db();
+
+        let item = |id: &str, price: f64| InventoryItem {
+            id: id.to_string(),
+            sku: "SKU123".to_string(),
+            name: "Test Item".to_string(),
+            category: "Genel".to_string(),
+            quantity: 5.0,
+            location: String::new(),
+            price,
+            cost_price: None,
+            image: None,
+            description: None,
+            ai_tags: None,
+            last_updated: "2024-01-01".to_string(),
+            currency: None,
+            supplier_id: None,
+            brand: None,
+            min_stock_level: 10,
+            unit: "ADET".to_string(),
+        };
+
+        inventory::add_item(&conn, &item("item-1", 100.0)).unwrap();
+
+        // Duplicate SKU -> typed conflict instead of a raw UNIQUE error
+        match inventory::add_item(&conn, &item("item-2", 120.0)) {
+            Err(e @ AppError::Conflict { .. }) => {
+                assert_eq!(e.to_string(), "Bu SKU zaten kayıtlı: SKU123");
+                assert!(matches!(e, AppError::Conflict { ref field, ref value } if field == "sku" && value == "SKU123"));
+            }
+            other => panic!("expected conflict, got {:?}", other),
+        }
+        let policy = inventory::SkuConflictPolicy::Error;
+        assert!(matches!(
+            inventory::add_item_with_policy(&conn, &item("item-2", 120.0), policy),
+            Err(AppError::Conflict { .. })
+        ));
+
+        // Skip keeps the existing row, update overwrites it
+        let skipped = inventory::add_item_with_policy(&conn, &item("item-2", 120.0), inventory::SkuConflictPolicy::Skip);
+        assert_eq!(skipped.unwrap(), inventory::AddItemOutcome::Skipped);
+        let updated = inventory::add_item_with_policy(&conn, &item("item-2", 150.0), inventory::SkuConflictPolicy::Update);
+        assert_eq!(updated.unwrap(), inventory::AddItemOutcome::Updated);
+
+        let (count, price): (i32, f64) = conn.query_row(
+            "SELECT COUNT(*), MAX(price) FROM inventory_items WHERE sku = 'SKU123'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?))
+        ).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(price, 150.0);
+
+        // Any other failure is still reported as a database error, not as a duplicate
+        conn.execute_batch(
+            "CREATE TRIGGER reject_insert BEFORE INSERT ON inventory_items BEGIN SELECT RAISE(ABORT, 'disk dolu'); END;",
+        ).unwrap();
+        match inventory::add_item(&conn, &InventoryItem { sku: "SKU124".to_string(), ..item("item-3", 100.0) }) {
+            Err(AppError::Conflict { .. }) => panic!("trigger failure reported as duplicate"),
+            Err(AppError::Database(e)) => {
+                assert!(!AppError::is_unique_violation(&e));
+                assert!(e.to_string().contains("disk dolu"));
+            }
+            other => panic!("expected database error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_create_stock_card_duplicate_barcode() {
+        let conn = setup_db();
+        let card = |id: &str| StockCard {
+            id: id.to_string(),
+            barcode: "8690000000011".to_string(),
+            name: "Silikon".to_string(),
+            brand: None,
+            unit: "ADET".to_string(),
+            category_id: None,
+            description: None,
+            image: None,
+            supplier_id: None,
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-01".to_string(),
+        };
+
+        inventory::create_stock_card(&conn, &card("card-1")).unwrap();
+        let err = inventory::create_stock_card(&conn, &card("card-2")).unwrap_err();
+        assert_eq!(err.to_string(), "Bu barkod zaten kayıtlı: 8690000000011");
+
+        // The UNIQUE constraint itself is matched by its extended result code
+        let raw = conn.execute(
+            "INSERT INTO stock_cards (id, barcode, name, unit, created_at, updated_at) VALUES ('card-3', '8690000000011', 'x', 'ADET', 'now', 'now')",
+            [],
+        ).unwrap_err();
+        assert!(AppError::is_unique_violation(&raw));
+    }
+
+    #[test]
+    fn test_low_stock_uses_per_item_threshold() {
+        let conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated, min_stock_level) VALUES
+             ('screw', 'VIDA-4', 'Vida 4mm', 50, 1.0, '2024-01-01', 100),
+             ('paint', 'BOYA-15', 'Boya 15L', 2, 900.0, '2024-01-01', 3),
+             ('brush', 'FIRCA-2', 'Firca', 5, 40.0, '2024-01-01', 2)",
+            [],
+        ).unwrap();
+        // Rows inserted without a threshold keep the old default of 10
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('tape', 'BANT', 'Bant', 9, 15.0, '2024-01-01')",
+            [],
+        ).unwrap();
+
+        let low: Vec<String> = inventory::get_low_stock_items(&conn).unwrap().into_iter().map(|i| i.sku).collect();
+        assert_eq!(low, vec!["VIDA-4", "BANT", "BOYA-15"]);
+
+        let item = inventory::get_item_by_sku(&conn, "BOYA-15").unwrap().unwrap();
+        assert_eq!(item.min_stock_level, 3);
+        assert!(inventory::update_item(&conn, &InventoryItem { min_stock_level: -1, ..item }).is_err());
+    }
+
+    #[test]
+    fn test_transactions_filter_by_item_sku() {
+        let conn = setup_db();
+        conn.execute_batch(
+            r#"INSERT INTO transactions (id, items, total, transaction_type, created_at) VALUES
+                ('t1', '[{"sku": "8696071025282", "name": "Boya"}, {"sku": "FIRCA-2", "name": "Firca"}]', 940.0, 'SALE', '2024-03-01T10:00:00Z'),
+                ('t2', '[{"sku": "FIRCA-2", "name": "Firca"}]', 40.0, 'SALE', '2024-03-02T10:00:00Z'),
+                ('t3', 'not json', 10.0, 'SALE', '2024-03-03T10:00:00Z');"#,
+        ).unwrap();
+
+        let filter = inventory::TransactionFilter {
+            item_sku: Some("8696071025282".to_string()),
+            ..Default::default()
+        };
+        let page = inventory::get_transactions_paginated(&conn, 1, 20, &filter).unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.data.len(), 1);
+        assert_eq!(page.data[0].id, "t1");
+
+        let filter = inventory::TransactionFilter { item_sku: Some("FIRCA-2".to_string()), ..Default::default() };
+        let first = inventory::get_transactions_paginated(&conn, 1, 1, &filter).unwrap();
+        assert_eq!((first.total, first.total_pages), (2, 2));
+        assert_eq!(first.data[0].id, "t2");
+
+        let all = inventory::get_transactions_paginated(&conn, 1, 20, &inventory::TransactionFilter::default()).unwrap();
+        assert_eq!(all.total, 3);
+    }
+
+    #[test]
+    fn test_paginated_sort_order() {
+        let conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES
+             ('a', 'BOYA-15', 'Boya 15L', 12, 900.0, '2024-01-03'),
+             ('b', 'VIDA-4', 'Vida 4mm', 3, 1.0, '2024-01-01'),
+             ('c', 'FIRCA-2', 'Firca', 7, 40.0, '2024-01-02')",
+            [],
+        ).unwrap();
+        let skus = |filter: &inventory::ItemFilter| -> Vec<String> {
+            inventory::get_items_paginated(&conn, 1, 20, filter).unwrap().data.into_iter().map(|i| i.sku).collect()
+        };
+
+        // Default stays by name
+        assert_eq!(skus(&inventory::ItemFilter::default()), vec!["BOYA-15", "FIRCA-2", "VIDA-4"]);
+        let by_quantity = inventory::ItemFilter { sort_by: Some("quantity".to_string()), sort_dir: Some("asc".to_string()), ..Default::default() };
+        assert_eq!(skus(&by_quantity), vec!["VIDA-4", "FIRCA-2", "BOYA-15"]);
+        let newest = inventory::ItemFilter { sort_by: Some("last_updated".to_string()), sort_dir: Some("DESC".to_string()), ..Default::default() };
+        assert_eq!(skus(&newest), vec!["BOYA-15", "FIRCA-2", "VIDA-4"]);
+
+        conn.execute_batch(
+            "INSERT INTO transactions (id, items, total, transaction_type, created_at) VALUES
+                ('t1', '[]', 50.0, 'SALE', '2024-03-01T10:00:00Z'),
+                ('t2', '[]', 10.0, 'SALE', '2024-03-02T10:00:00Z');",
+        ).unwrap();
+        let ids = |filter: &inventory::TransactionFilter| -> Vec<String> {
+            inventory::get_transactions_paginated(&conn, 1, 20, filter).unwrap().data.into_iter().map(|t| t.id).collect()
+        };
+        assert_eq!(ids(&inventory::TransactionFilter::default()), vec!["t2", "t1"]);
+        let by_total = inventory::TransactionFilter { sort_by: Some("total".to_string()), sort_dir: Some("desc".to_string()), ..Default::default() };
+        assert_eq!(ids(&by_total), vec!["t1", "t2"]);
+    }
+
+    #[test]
+    fn test_paginated_sort_rejects_unknown_column() {
+        let conn = setup_db();
+        let injected = inventory::ItemFilter { sort_by: Some("name; DROP TABLE inventory_items".to_string()), ..Default::default() };
+        assert!(matches!(inventory::get_items_paginated(&conn, 1, 20, &injected), Err(AppError::Validation(_))));
+        // Real columns outside the list are rejected too
+        let cost = inventory::ItemFilter { sort_by: Some("cost_price".to_string()), ..Default::default() };
+        assert!(inventory::get_items_paginated(&conn, 1, 20, &cost).is_err());
+        let direction = inventory::ItemFilter { sort_dir: Some("sideways".to_string()), ..Default::default() };
+        assert!(inventory::get_items_paginated(&conn, 1, 20, &direction).is_err());
+
+        let tx = inventory::TransactionFilter { sort_by: Some("quantity".to_string()), ..Default::default() };
+        assert!(matches!(inventory::get_transactions_paginated(&conn, 1, 20, &tx), Err(AppError::Validation(_))));
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM inventory_items", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_adjust_stock_logs_signed_delta() {
+        let mut conn = setup_db();
+        conn.execute_batch(
+            "CREATE TABLE sync_outbox (
+                id TEXT PRIMARY KEY NOT NULL, action_type TEXT NOT NULL, item_sku TEXT, item_name TEXT,
+                quantity_change INTEGER DEFAULT 0, old_value REAL, new_value REAL, metadata TEXT,
+                transaction_time TEXT NOT NULL, synced INTEGER DEFAULT 0, created_at TEXT NOT NULL
+            );
+            INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('paint', 'BOYA-15', 'Boya 15L', 10, 900.0, '2024-01-01');",
+        ).unwrap();
+
+        assert_eq!(inventory::adjust_stock(&mut conn, "BOYA-15", 13.0, "Sayim fazlasi").unwrap(), 3.0);
+        assert_eq!(inventory::adjust_stock(&mut conn, "BOYA-15", 8.0, "Kirik kutu").unwrap(), -5.0);
+        assert_eq!(inventory::adjust_stock(&mut conn, "BOYA-15", 8.0, "Sayim").unwrap(), 0.0);
+        assert!(inventory::adjust_stock(&mut conn, "BOYA-15", -1.0, "Sayim").is_err());
+        assert!(inventory::adjust_stock(&mut conn, "BOYA-15", 5.0, "  ").is_err());
+        assert_eq!(inventory::get_item_by_sku(&conn, "BOYA-15").unwrap().unwrap().quantity, 8.0);
+
+        let logged: Vec<(i32, String)> = conn
+            .prepare("SELECT quantity_change, description FROM activity_log WHERE action_type = 'ADJUSTMENT' ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(logged.len(), 2);
+        assert_eq!(logged[0].0, 3);
+        assert_eq!(logged[1], (-5, "Stok duzeltmesi (13 -> 8): Kirik kutu".to_string()));
+
+        let queued: Vec<(String, i32)> = conn
+            .prepare("SELECT action_type, quantity_change FROM sync_outbox ORDER BY transaction_time, rowid")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(queued, vec![("STOCK_IN".to_string(), 3), ("STOCK_OUT".to_string(), -5)]);
+    }
+
+    #[test]
+    fn test_deleted_item_goes_to_trash_and_restores() {
+        let mut conn = setup_db();
+        conn.execute_batch(
+            "CREATE TABLE item_attributes (item_id TEXT NOT NULL, key TEXT NOT NULL, value TEXT NOT NULL, PRIMARY KEY (item_id, key));
+             INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES
+                ('paint', 'BOYA-15', 'Boya 15L', 4, 900.0, '2024-01-01'),
+                ('brush', 'FIRCA-2', 'Firca', 5, 40.0, '2024-01-01');
+             INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, created_at)
+                VALUES ('lot-1', 'paint', 4, 4, 700.0, '2024-01-01', '2024-01-01');
+             INSERT INTO item_attributes VALUES ('paint', 'ambalaj', '15L');",
+        ).unwrap();
+        let skus = |conn: &Connection| -> Vec<String> {
+            let mut skus: Vec<String> = inventory::get_all_items(conn).unwrap().into_iter().map(|i| i.sku).collect();
+            skus.sort();
+            skus
+        };
+
+        inventory::delete_item(&conn, "BOYA-15").unwrap();
+        assert_eq!(skus(&conn), vec!["FIRCA-2"]);
+        assert!(inventory::get_item_by_sku(&conn, "BOYA-15").unwrap().is_none());
+        assert_eq!(inventory::get_dashboard_stats(&conn, None, None).unwrap().total_items, 1);
+        let trash = inventory::list_deleted_items(&conn).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].item.sku, "BOYA-15");
+        // Already in the trash
+        assert!(inventory::delete_item(&conn, "BOYA-15").is_err());
+
+        let restored = inventory::restore_item(&conn, "BOYA-15").unwrap();
+        assert_eq!(restored.quantity, 4.0);
+        assert_eq!(skus(&conn), vec!["BOYA-15", "FIRCA-2"]);
+        assert!(inventory::list_deleted_items(&conn).unwrap().is_empty());
+        assert!(inventory::restore_item(&conn, "BOYA-15").is_err());
+
+        // Purging only removes items that have been in the trash long enough, with their lots
+        inventory::delete_item(&conn, "BOYA-15").unwrap();
+        assert_eq!(inventory::purge_deleted_items(&mut conn, 30).unwrap(), 0);
+        assert_eq!(inventory::purge_deleted_items(&mut conn, 0).unwrap(), 1);
+        let leftovers: i32 = conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM inventory_items WHERE id = 'paint')
+                  + (SELECT COUNT(*) FROM inventory_lots WHERE product_id = 'paint')
+                  + (SELECT COUNT(*) FROM item_attributes WHERE item_id = 'paint')",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(leftovers, 0);
+        assert_eq!(skus(&conn), vec!["FIRCA-2"]);
+    }
+
+    /// Cash sale of 3 x SKU123 (100) and 2 x SKU456 (50) with a 40 discount line
+    fn sale_for_return(conn: &mut Connection) -> String {
+        conn.execute_batch(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES
+                ('item-1', 'SKU123', 'Test Item', 10, 100.0, '2024-01-01'),
+                ('item-2', 'SKU456', 'Other Item', 10, 50.0, '2024-01-01');",
+        ).unwrap();
+        let line = |id: &str, sku: &str, quantity: i32, price: f64| CartItem {
+            id: id.to_string(),
+            sku: sku.to_string(),
+            name: sku.to_string(),
+            cart_quantity: quantity.into(),
+            price,
+            cost_price: None,
+            tax_rate: 20.0,
+            discount_amount: None,
+            discount_percent: None,
+        };
+        let cart_items = vec![
+            line("item-1", "SKU123", 3, 100.0),
+            line("item-2", "SKU456", 2, 50.0),
+            line("disc", "IND", 1, -40.0),
+        ];
+        inventory::process_sale_transaction(conn, cart_items, "NAKIT".to_string(), "SALE".to_string(), None, None)
+            .unwrap()
+            .id
+    }
+
+    fn quantity(conn: &Connection, sku: &str) -> i32 {
+        conn.query_row("SELECT quantity FROM inventory_items WHERE sku = ?1", params![sku], |row| row.get(0)).unwrap()
+    }
+
+    fn return_item(sku: &str, quantity: i32) -> ReturnItem {
+        ReturnItem { sku: sku.to_string(), quantity: quantity.into() }
+    }
+
+    #[test]
+    fn test_partial_return_restocks_and_refunds() {
+        let mut conn = setup_db();
+        let sale_id = sale_for_return(&mut conn);
+        assert_eq!(quantity(&conn, "SKU123"), 7);
+
+        let returned = inventory::process_partial_return(&mut conn, &sale_id, vec![return_item("SKU123", 1), return_item("SKU456", 2)]).unwrap();
+        assert_eq!(returned.transaction_type, "RETURN");
+        // 200 of 400 gross comes back, less half of the 40 discount
+        assert_eq!(returned.total, -180.0);
+        assert_eq!((quantity(&conn, "SKU123"), quantity(&conn, "SKU456")), (8, 10));
+
+        let original: String = conn.query_row(
+            "SELECT original_transaction_id FROM transactions WHERE id = ?1", params![&returned.id], |row| row.get(0)
+        ).unwrap();
+        assert_eq!(original, sale_id);
+        let (category, amount): (String, f64) = conn.query_row(
+            "SELECT category, amount FROM finance_records WHERE record_type = 'EXPENSE'", [], |row| Ok((row.get(0)?, row.get(1)?))
+        ).unwrap();
+        assert_eq!((category.as_str(), amount), ("SATIS_IADE", 180.0));
+    }
+
+    #[test]
+    fn test_partial_return_cannot_exceed_sold_quantity() {
+        let mut conn = setup_db();
+        let sale_id = sale_for_return(&mut conn);
+
+        inventory::process_partial_return(&mut conn, &sale_id, vec![return_item("SKU123", 2)]).unwrap();
+        let err = inventory::process_partial_return(&mut conn, &sale_id, vec![return_item("SKU123", 2)])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("satılanı aşıyor"), "{}", err);
+        // Listing the SKU twice counts both lines
+        assert!(inventory::process_partial_return(&mut conn, &sale_id, vec![return_item("SKU123", 1), return_item("SKU123", 1)]).is_err());
+        assert_eq!(quantity(&conn, "SKU123"), 9);
+
+        // The last one can still come back
+        inventory::process_partial_return(&mut conn, &sale_id, vec![return_item("SKU123", 1)]).unwrap();
+        assert_eq!(quantity(&conn, "SKU123"), 10);
+    }
+
+    #[test]
+    fn test_partial_return_rejects_sku_not_in_sale() {
+        let mut conn = setup_db();
+        let sale_id = sale_for_return(&mut conn);
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('item-3', 'SKU789', 'Unsold', 5, 10.0, 'now')",
+            [],
+        ).unwrap();
+
+        let result = inventory::process_partial_return(&mut conn, &sale_id, vec![return_item("SKU789", 1)]);
+        assert!(matches!(result, Err(AppError::Validation(ref msg)) if msg.contains("SKU789")));
+        assert_eq!(quantity(&conn, "SKU789"), 5);
+        let returns: i32 = conn.query_row("SELECT COUNT(*) FROM transactions WHERE transaction_type = 'RETURN'", [], |row| row.get(0)).unwrap();
+        assert_eq!(returns, 0);
+        assert!(matches!(inventory::process_partial_return(&mut conn, "missing", vec![return_item("SKU123", 1)]), Err(AppError::NotFound(_))));
+    }
+
+    fn paint_cart(quantity: i32) -> Vec<CartItem> {
+        vec![CartItem {
+            id: "item-1".to_string(),
+            sku: "SKU123".to_string(),
+            name: "Test Item".to_string(),
+            cart_quantity: quantity.into(),
+            price: 100.0,
+            cost_price: None,
+            tax_rate: 20.0,
+            discount_amount: None,
+            discount_percent: None,
+        }]
+    }
+
+    fn payment(method: &str, amount: f64) -> Payment {
+        Payment { method: method.to_string(), amount }
+    }
+
+    #[test]
+    fn test_split_payment_counts_each_portion_in_its_bucket() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('item-1', 'SKU123', 'Test Item', 10, 100.0, 'now')",
+            [],
+        ).unwrap();
+
+        let sale = inventory::process_sale_with_payments(
+            &mut conn,
+            paint_cart(3),
+            String::new(),
+            Some(vec![payment("Nakit", 120.0), payment("Kredi Kartı", 180.0)]),
+            None,
+            "SALE".to_string(),
+            None,
+            None,
+        ).unwrap();
+        assert_eq!((sale.payment_method.as_str(), sale.total), ("SPLIT", 300.0));
+        assert_eq!(
+            finance::get_transaction_payments(&conn, &sale.id).unwrap(),
+            vec![payment("NAKIT", 120.0), payment("KREDI_KARTI", 180.0)]
+        );
+        inventory::process_sale_transaction(&mut conn, paint_cart(1), "NAKIT".to_string(), "SALE".to_string(), None, None).unwrap();
+
+        let balances = finance::payment_balances(&conn).unwrap();
+        assert_eq!((balances.cash, balances.card, balances.other), (220.0, 180.0, 0.0));
+
+        // Returning one of three gives back a third of each portion
+        let returned = inventory::process_partial_return(&mut conn, &sale.id, vec![ReturnItem { sku: "SKU123".to_string(), quantity: 1.0 }]).unwrap();
+        assert_eq!(finance::get_transaction_payments(&conn, &returned.id).unwrap(), vec![payment("NAKIT", 40.0), payment("KREDI_KARTI", 60.0)]);
+        let balances = finance::payment_balances(&conn).unwrap();
+        assert_eq!((balances.cash, balances.card), (180.0, 120.0));
+    }
+
+    #[test]
+    fn test_split_payment_must_add_up_to_total() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('item-1', 'SKU123', 'Test Item', 10, 100.0, 'now')",
+            [],
+        ).unwrap();
+
+        let result = inventory::process_sale_with_payments(
+            &mut conn,
+            paint_cart(2),
+            String::new(),
+            Some(vec![payment("NAKIT", 100.0), payment("KREDI_KARTI", 50.0)]),
+            None,
+            "SALE".to_string(),
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(AppError::Validation(ref msg)) if msg.contains("eşit değil")));
+
+        let on_credit = inventory::process_sale_with_payments(
+            &mut conn,
+            paint_cart(2),
+            String::new(),
+            Some(vec![payment("NAKIT", 100.0), payment("VERESIYE", 100.0)]),
+            None,
+            "SALE".to_string(),
+            None,
+            None,
+        );
+        assert!(on_credit.is_err());
+
+        let count: i32 = conn.query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 0);
+        let stock: i32 = conn.query_row("SELECT quantity FROM inventory_items WHERE sku = 'SKU123'", [], |row| row.get(0)).unwrap();
+        assert_eq!(stock, 10);
+    }
+
+    fn discounted_sale(
+        conn: &mut Connection,
+        cart_items: Vec<CartItem>,
+        discount: Option<SaleDiscount>,
+    ) -> Result<Transaction, AppError> {
+        inventory::process_sale_with_payments(conn, cart_items, "NAKIT".to_string(), None, discount, "SALE".to_string(), None, None)
+    }
+
+    fn stored_discounts(conn: &Connection, id: &str) -> (f64, f64, f64) {
+        conn.query_row(
+            "SELECT total, line_discount, ticket_discount FROM transactions WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_process_sale_line_discount() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('item-1', 'SKU123', 'Test Item', 10, 100.0, 'now')",
+            [],
+        ).unwrap();
+
+        let mut cart = paint_cart(3);
+        cart[0].discount_amount = Some(30.0);
+        let sale = discounted_sale(&mut conn, cart, None).unwrap();
+        assert_eq!((sale.total, sale.line_discount, sale.ticket_discount, sale.tax_total), (270.0, 30.0, 0.0, 45.0));
+        assert_eq!(stored_discounts(&conn, &sale.id), (270.0, 30.0, 0.0));
+        // The list price is kept on the line, the discount next to it
+        assert_eq!((sale.items[0].price, sale.items[0].discount_amount), (100.0, Some(30.0)));
+
+        let receipt = print::generate_receipt_text(&sale, "TL", "Nexus", &print::PrintConfig::default());
+        assert!(receipt.contains("-30,00TL"), "{}", receipt);
+        assert!(receipt.contains("270,00TL"), "{}", receipt);
+
+        // A returned unit gives back its share of the line discount
+        let returned = inventory::process_partial_return(&mut conn, &sale.id, vec![return_item("SKU123", 1)]).unwrap();
+        assert_eq!((returned.total, returned.line_discount), (-90.0, -10.0));
+    }
+
+    #[test]
+    fn test_process_sale_ticket_percent_discount() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('item-1', 'SKU123', 'Test Item', 10, 100.0, 'now')",
+            [],
+        ).unwrap();
+
+        let discount = SaleDiscount { amount: None, percent: Some(10.0) };
+        let sale = discounted_sale(&mut conn, paint_cart(2), Some(discount)).unwrap();
+        assert_eq!((sale.total, sale.line_discount, sale.ticket_discount, sale.tax_total), (180.0, 0.0, 20.0, 30.0));
+        assert_eq!(stored_discounts(&conn, &sale.id), (180.0, 0.0, 20.0));
+
+        let stored = inventory::get_transaction(&conn, &sale.id).unwrap().unwrap();
+        let totals = print::calculate_totals(&stored, &print::PrintConfig::default());
+        assert_eq!((totals.subtotal, totals.tax, totals.grand_total), (150.0, 30.0, 180.0));
+        let receipt = print::generate_receipt_text(&stored, "TL", "Nexus", &print::PrintConfig::default());
+        assert!(receipt.contains("FİŞ İNDİRİMİ:") && receipt.contains("-20,00TL"), "{}", receipt);
+
+        // Reports count what was charged
+        let day = &sale.created_at[..10];
+        assert_eq!(reports::get_profit_report(&conn, day, day).unwrap().revenue, 180.0);
+        let refund = inventory::process_partial_return(&mut conn, &sale.id, vec![return_item("SKU123", 1)]).unwrap();
+        assert_eq!((refund.total, refund.ticket_discount), (-90.0, -10.0));
+    }
+
+    #[test]
+    fn test_line_and_ticket_discounts_do_not_stack_on_list_price() {
+        let mut conn = setup_db();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('item-1', 'SKU123', 'Test Item', 10, 100.0, 'now')",
+            [],
+        ).unwrap();
+
+        // 10% off the line, then 10% off what is left: 300 -> 270 -> 243, not 300 - 60
+        let mut cart = paint_cart(3);
+        cart[0].discount_percent = Some(10.0);
+        let discount = SaleDiscount { amount: None, percent: Some(10.0) };
+        let sale = discounted_sale(&mut conn, cart, Some(discount)).unwrap();
+        assert_eq!((sale.total, sale.line_discount, sale.ticket_discount), (243.0, 30.0, 27.0));
+
+        // A line takes an amount or a percent, and no discount may exceed what it is taken from
+        let mut both = paint_cart(1);
+        both[0].discount_amount = Some(5.0);
+        both[0].discount_percent = Some(5.0);
+        assert!(matches!(discounted_sale(&mut conn, both, None), Err(AppError::Validation(_))));
+        let mut too_much = paint_cart(1);
+        too_much[0].discount_amount = Some(150.0);
+        assert!(matches!(discounted_sale(&mut conn, too_much, None), Err(AppError::Validation(_))));
+        let over_ticket = SaleDiscount { amount: Some(250.0), percent: None };
+        assert!(matches!(discounted_sale(&mut conn, paint_cart(2), Some(over_ticket)), Err(AppError::Validation(_))));
+
+        let count: i32 = conn.query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
 }
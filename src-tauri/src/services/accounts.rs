@@ -0,0 +1,712 @@
+//! Current Account Service Module
+//!
+//! Cari hesap grupları (şube / ana firma) ve konsolide bakiye hesaplamaları
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::error::AppError;
+use crate::models::{
+    AccountStatement, AccountStatementLine, AccountTreeNode, BalanceDiscrepancy, CartItem, ConsolidatedBalance,
+    CurrentAccount, Transaction, TRANSACTION_STATUS_COMPLETED,
+};
+use crate::services::finance::{self, PaymentMethod};
+use crate::services::reports::parse_report_date;
+use crate::services::tax::round_money;
+
+/// Column list matching `map_account_row`
+pub const ACCOUNT_COLUMNS: &str =
+    "id, name, account_type, tax_number, phone, email, address, note, payment_term, balance, created_at, updated_at, parent_account_id";
+
+/// Map a row selected with `ACCOUNT_COLUMNS` to a `CurrentAccount`
+pub fn map_account_row(row: &Row) -> rusqlite::Result<CurrentAccount> {
+    Ok(CurrentAccount {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        account_type: row.get(2)?,
+        tax_number: row.get(3)?,
+        phone: row.get(4)?,
+        email: row.get(5)?,
+        address: row.get(6)?,
+        note: row.get(7)?,
+        payment_term: row.get(8)?,
+        balance: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+        parent_account_id: row.get(12)?,
+    })
+}
+
+/// Get a single account by id
+pub fn get_account(conn: &Connection, id: &str) -> Result<CurrentAccount, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM current_accounts WHERE id = ?1", ACCOUNT_COLUMNS),
+        params![id],
+        map_account_row,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("Cari hesap bulunamadı: {}", id)))
+}
+
+/// Ids of the account and all of its sub-accounts (recursive)
+pub fn get_group_account_ids(conn: &Connection, parent_id: &str) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE account_group(id) AS (
+             SELECT id FROM current_accounts WHERE id = ?1
+             UNION
+             SELECT c.id FROM current_accounts c JOIN account_group g ON c.parent_account_id = g.id
+         )
+         SELECT id FROM account_group",
+    )?;
+
+    let ids = stmt
+        .query_map(params![parent_id], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(ids)
+}
+
+/// Validate that `parent_id` can become the parent of `account_id` (exists, no cycle)
+pub fn validate_account_parent(
+    conn: &Connection,
+    account_id: Option<&str>,
+    parent_id: &str,
+) -> Result<(), AppError> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM current_accounts WHERE id = ?1",
+            params![parent_id],
+            |row| row.get::<_, i32>(0),
+        )? > 0;
+    if !exists {
+        return Err(AppError::NotFound(format!("Üst cari hesap bulunamadı: {}", parent_id)));
+    }
+
+    if let Some(account_id) = account_id {
+        // Walk up from the new parent; reaching the account itself means a cycle. A chain that
+        // already loops without it (older data) is reported instead of walked forever.
+        let mut visited = HashSet::new();
+        let mut current = Some(parent_id.to_string());
+        while let Some(id) = current {
+            if id == account_id {
+                return Err(AppError::Validation(
+                    "Döngüsel hesap grubu oluşturulamaz: üst hesap, bu hesabın alt hesabı olamaz".to_string(),
+                ));
+            }
+            if !visited.insert(id.clone()) {
+                return Err(AppError::Validation(format!(
+                    "Üst hesap zincirinde döngü var ({} hesabında); önce mevcut üst hesap bağlantısını kaldırın",
+                    id
+                )));
+            }
+            current = conn
+                .query_row(
+                    "SELECT parent_account_id FROM current_accounts WHERE id = ?1",
+                    params![&id],
+                    |row| row.get::<_, Option<String>>(0),
+                )
+                .optional()?
+                .flatten();
+        }
+    }
+
+    Ok(())
+}
+
+/// Set or clear the parent of an account, guarding against cycles
+pub fn set_account_parent(
+    conn: &Connection,
+    account_id: &str,
+    parent_id: Option<&str>,
+) -> Result<(), AppError> {
+    if let Some(parent_id) = parent_id {
+        validate_account_parent(conn, Some(account_id), parent_id)?;
+    }
+
+    let updated = conn.execute(
+        "UPDATE current_accounts SET parent_account_id = ?1, updated_at = ?2 WHERE id = ?3",
+        params![parent_id, chrono::Utc::now().to_rfc3339(), account_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("Cari hesap bulunamadı: {}", account_id)));
+    }
+
+    Ok(())
+}
+
+/// Sum of the parent's and all sub-accounts' balances
+pub fn get_consolidated_balance(conn: &Connection, parent_id: &str) -> Result<ConsolidatedBalance, AppError> {
+    let parent = get_account(conn, parent_id)?;
+    let ids = get_group_account_ids(conn, parent_id)?;
+
+    let mut members = Vec::with_capacity(ids.len());
+    for id in &ids {
+        members.push(get_account(conn, id)?);
+    }
+    let total_balance = members.iter().map(|a| a.balance).sum();
+
+    Ok(ConsolidatedBalance {
+        parent_id: parent.id,
+        parent_name: parent.name,
+        own_balance: parent.balance,
+        total_balance,
+        account_count: members.len() as i32,
+        members,
+    })
+}
+
+/// All accounts as a hierarchy (roots sorted by name)
+pub fn get_account_tree(conn: &Connection) -> Result<Vec<AccountTreeNode>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM current_accounts ORDER BY name",
+        ACCOUNT_COLUMNS
+    ))?;
+    let accounts = stmt
+        .query_map([], map_account_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let known: std::collections::HashSet<String> = accounts.iter().map(|a| a.id.clone()).collect();
+    let mut children: HashMap<Option<String>, Vec<CurrentAccount>> = HashMap::new();
+    for account in accounts {
+        // Orphans (parent deleted) are shown as roots
+        let parent = account.parent_account_id.clone().filter(|p| known.contains(p));
+        children.entry(parent).or_default().push(account);
+    }
+
+    fn build(
+        parent: Option<String>,
+        children: &mut HashMap<Option<String>, Vec<CurrentAccount>>,
+    ) -> Vec<AccountTreeNode> {
+        let accounts = children.remove(&parent).unwrap_or_default();
+        accounts
+            .into_iter()
+            .map(|account| {
+                let sub_accounts = build(Some(account.id.clone()), children);
+                let consolidated_balance =
+                    account.balance + sub_accounts.iter().map(|c| c.consolidated_balance).sum::<f64>();
+                AccountTreeNode {
+                    account,
+                    consolidated_balance,
+                    children: sub_accounts,
+                }
+            })
+            .collect()
+    }
+
+    Ok(build(None, &mut children))
+}
+
+/// Transactions linked to an account, optionally across the whole account group
+pub fn get_account_transactions(
+    conn: &Connection,
+    account_id: &str,
+    consolidated: bool,
+) -> Result<Vec<Transaction>, AppError> {
+    let ids = if consolidated {
+        get_group_account_ids(conn, account_id)?
+    } else {
+        vec![account_id.to_string()]
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id, status, tax_total,
+                line_discount, ticket_discount
+         FROM transactions WHERE customer_id = ?1 ORDER BY created_at ASC",
+    )?;
+
+    let mut transactions = Vec::new();
+    for id in &ids {
+        let rows = stmt.query_map(params![id], |row| {
+            let items_json: String = row.get(1)?;
+            let items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
+            Ok(Transaction {
+                id: row.get(0)?,
+                items,
+                total: row.get(2)?,
+                payment_method: row.get(3)?,
+                transaction_type: row.get(4)?,
+                status: row.get(8)?,
+                tax_total: row.get(9)?,
+                line_discount: row.get(10)?,
+                ticket_discount: row.get(11)?,
+                note: row.get(5)?,
+                created_at: row.get(6)?,
+                customer_id: row.get(7)?,
+            })
+        })?;
+        for row in rows {
+            transactions.push(row?);
+        }
+    }
+    transactions.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    Ok(transactions)
+}
+
+/// Differences below this are rounding noise, not discrepancies
+const BALANCE_TOLERANCE: f64 = 0.005;
+
+/// Balance change of one linked transaction, using the same rules as the write paths
+/// (process_sale_transaction and process_goods_receipt)
+pub fn transaction_balance_effect(transaction_type: &str, payment_method: &str, total: f64, account_type: &str) -> f64 {
+    let on_credit = PaymentMethod::parse(payment_method) == PaymentMethod::Credit;
+    match transaction_type {
+        // Mal kabul: only credit (VADELI) purchases are owed to the supplier
+        "PURCHASE" if on_credit => total,
+        "PURCHASE" => 0.0,
+        // Returns are stored with a negative total
+        "RETURN" => total,
+        "COLLECTION" => -total.abs(),
+        _ if on_credit => {
+            if account_type == "SUPPLIER" { -total } else { total }
+        }
+        _ => 0.0,
+    }
+}
+
+/// Derive every account's balance from its linked transactions and compare it to the stored one.
+///
+/// Hesap kapatma/tahsilatlar COLLECTION işlemi olarak tutulur; ayrı bir ödeme eşleştirme tablosu yoktur.
+/// `dry_run` false ise farklı olan bakiyeler tek bir veritabanı işleminde düzeltilir.
+pub fn recompute_account_balances(conn: &mut Connection, dry_run: bool) -> Result<Vec<BalanceDiscrepancy>, AppError> {
+    let mut computed: HashMap<String, (f64, i32)> = HashMap::new();
+    let mut discrepancies = Vec::new();
+
+    {
+        let mut stmt = conn.prepare(
+            "SELECT t.customer_id, t.transaction_type, t.payment_method, t.total, a.account_type
+             FROM transactions t JOIN current_accounts a ON a.id = t.customer_id
+             WHERE t.status != 'voided'",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                row.get::<_, f64>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+        for row in rows {
+            let (account_id, transaction_type, payment_method, total, account_type) = row?;
+            let entry = computed.entry(account_id).or_insert((0.0, 0));
+            entry.0 += transaction_balance_effect(&transaction_type, &payment_method, total, &account_type);
+            entry.1 += 1;
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, account_type, balance FROM current_accounts ORDER BY name ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, f64>(3)?,
+            ))
+        })?;
+        for row in rows {
+            let (account_id, account_name, account_type, stored_balance) = row?;
+            let (computed_balance, transaction_count) = computed.get(&account_id).copied().unwrap_or((0.0, 0));
+            let computed_balance = (computed_balance * 100.0).round() / 100.0;
+            let difference = computed_balance - stored_balance;
+            if difference.abs() > BALANCE_TOLERANCE {
+                discrepancies.push(BalanceDiscrepancy {
+                    account_id,
+                    account_name,
+                    account_type,
+                    stored_balance,
+                    computed_balance,
+                    difference,
+                    transaction_count,
+                    corrected: false,
+                });
+            }
+        }
+    }
+
+    if !dry_run && !discrepancies.is_empty() {
+        let now = chrono::Local::now().to_rfc3339();
+        let tx = conn.transaction()?;
+        for d in discrepancies.iter_mut() {
+            tx.execute(
+                "UPDATE current_accounts SET balance = ?1, updated_at = ?2 WHERE id = ?3",
+                params![d.computed_balance, &now, &d.account_id],
+            )?;
+            d.corrected = true;
+        }
+        tx.commit()?;
+    }
+
+    Ok(discrepancies)
+}
+
+/// Record a payment against an account: a customer paying their debt (tahsilat) or us paying a
+/// supplier (ödeme). Writes a COLLECTION transaction, the matching finance record and the lower
+/// balance in one database transaction.
+///
+/// Ödenen tutar açık bakiyeyi aşamaz; `allow_overpay` ile fazlası alacak/avans olarak kalır.
+pub fn record_account_payment(
+    conn: &mut Connection,
+    account_id: &str,
+    amount: f64,
+    payment_method: &str,
+    date: &str,
+    note: Option<String>,
+    allow_overpay: bool,
+) -> Result<Transaction, AppError> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(AppError::Validation("Ödeme tutarı 0'dan büyük olmalı".to_string()));
+    }
+    let amount = round_money(amount);
+    let day = parse_report_date(date)?;
+    let payment_method = finance::canonical_payment_method(payment_method);
+    if payment_method.is_empty() || PaymentMethod::parse(&payment_method) == PaymentMethod::Credit {
+        return Err(AppError::Validation(format!("Geçersiz ödeme yöntemi: {}", payment_method)));
+    }
+
+    let tx = conn.transaction()?;
+    let account = get_account(&tx, account_id)?;
+    if !allow_overpay && amount > account.balance + BALANCE_TOLERANCE {
+        return Err(AppError::Validation(format!(
+            "Ödeme tutarı ({:.2}) açık bakiyeyi ({:.2}) aşıyor",
+            amount, account.balance
+        )));
+    }
+
+    let is_supplier = account.account_type == "SUPPLIER";
+    let (record_type, category, label) = if is_supplier {
+        ("EXPENSE", finance::ACCOUNT_PAYMENT_CATEGORIES[1], "Ödeme")
+    } else {
+        ("INCOME", finance::ACCOUNT_PAYMENT_CATEGORIES[0], "Tahsilat")
+    };
+    let note = note
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("{}: {}", label, account.name));
+
+    let now = chrono::Local::now();
+    // Back-dated payments are stored on their day, like expenses
+    let created_at = if day == now.date_naive() { now.to_rfc3339() } else { day.to_string() };
+    let transaction_id = uuid::Uuid::new_v4().to_string();
+    // Stored negative, like collections entered at the register
+    let total = -amount;
+
+    tx.execute(
+        "INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id)
+         VALUES (?1, '[]', ?2, ?3, 'COLLECTION', ?4, ?5, ?6)",
+        params![&transaction_id, total, &payment_method, &note, &created_at, account_id],
+    )?;
+    tx.execute(
+        "INSERT INTO finance_records (record_type, category, amount, payment_method, description, date, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![record_type, category, amount, &payment_method, &note, day.to_string(), now.to_rfc3339()],
+    )?;
+    tx.execute(
+        "UPDATE current_accounts SET balance = balance - ?1, updated_at = ?2 WHERE id = ?3",
+        params![amount, now.to_rfc3339(), account_id],
+    )?;
+    tx.commit()?;
+
+    Ok(Transaction {
+        id: transaction_id,
+        items: Vec::new(),
+        total,
+        payment_method,
+        transaction_type: "COLLECTION".to_string(),
+        status: TRANSACTION_STATUS_COMPLETED.to_string(),
+        note: Some(note),
+        created_at,
+        customer_id: Some(account_id.to_string()),
+        tax_total: 0.0,
+        line_discount: 0.0,
+        ticket_discount: 0.0,
+    })
+}
+
+/// Statement text for a transaction without a note
+fn movement_label(transaction_type: &str, account_type: &str) -> &'static str {
+    match transaction_type {
+        "PURCHASE" => "Mal kabul",
+        "RETURN" => "İade",
+        "COLLECTION" if account_type == "SUPPLIER" => "Ödeme",
+        "COLLECTION" => "Tahsilat",
+        _ => "Satış",
+    }
+}
+
+/// Statement (ekstre) of an account between two dates (YYYY-MM-DD, inclusive).
+///
+/// Bakiye etkisi `transaction_balance_effect` ile hesaplanır; etkisi olmayan (peşin) işlemler listelenmez.
+/// Açılış bakiyesi kayıtlı bakiyeden aralık başından sonraki hareketler düşülerek bulunur, böylece
+/// bugüne kadar uzanan bir ekstre her zaman `current_accounts.balance` ile kapanır (elle girilen
+/// açılış bakiyeleri dahil).
+pub fn get_account_statement(
+    conn: &Connection,
+    account_id: &str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<AccountStatement, AppError> {
+    let start = parse_report_date(start_date)?.to_string();
+    let end = parse_report_date(end_date)?.to_string();
+    if end < start {
+        return Err(AppError::Validation("Bitiş tarihi başlangıç tarihinden önce olamaz".to_string()));
+    }
+    let account = get_account(conn, account_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.created_at, COALESCE(t.transaction_type, 'SALE'), COALESCE(t.payment_method, ''), t.total,
+                COALESCE(NULLIF(t.note, ''), NULLIF(g.description, ''), NULLIF(g.invoice_no, ''))
+         FROM transactions t
+         LEFT JOIN goods_receipts g ON g.transaction_id = t.id
+         WHERE t.customer_id = ?1 AND t.status != 'voided'
+         ORDER BY t.created_at ASC, t.rowid ASC",
+    )?;
+    let rows = stmt.query_map(params![account_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, f64>(4)?,
+            row.get::<_, Option<String>>(5)?,
+        ))
+    })?;
+
+    let mut since_start = 0.0;
+    let mut lines = Vec::new();
+    for row in rows {
+        let (transaction_id, created_at, transaction_type, payment_method, total, note) = row?;
+        let amount = transaction_balance_effect(&transaction_type, &payment_method, total, &account.account_type);
+        let day = created_at.get(..10).unwrap_or(&created_at);
+        if amount.abs() <= BALANCE_TOLERANCE || day < start.as_str() {
+            continue;
+        }
+        since_start += amount;
+        if day <= end.as_str() {
+            lines.push(AccountStatementLine {
+                transaction_id,
+                date: created_at.clone(),
+                description: note.unwrap_or_else(|| movement_label(&transaction_type, &account.account_type).to_string()),
+                transaction_type,
+                payment_method,
+                amount,
+                balance: 0.0,
+            });
+        }
+    }
+
+    let opening_balance = round_money(account.balance - since_start);
+    let mut balance = opening_balance;
+    for line in lines.iter_mut() {
+        balance = round_money(balance + line.amount);
+        line.balance = balance;
+    }
+
+    Ok(AccountStatement {
+        account_id: account.id,
+        account_name: account.name,
+        account_type: account.account_type,
+        start_date: start,
+        end_date: end,
+        opening_balance,
+        lines,
+        closing_balance: balance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE current_accounts (
+                id TEXT PRIMARY KEY, name TEXT NOT NULL, account_type TEXT NOT NULL DEFAULT 'CUSTOMER',
+                tax_number TEXT, phone TEXT, email TEXT, address TEXT, note TEXT,
+                payment_term INTEGER DEFAULT 0, balance REAL DEFAULT 0.0,
+                created_at TEXT NOT NULL, updated_at TEXT NOT NULL,
+                parent_account_id TEXT REFERENCES current_accounts(id)
+            );
+            INSERT INTO current_accounts (id, name, balance, created_at, updated_at) VALUES ('hq', 'Merkez', 100.0, 'now', 'now');
+            INSERT INTO current_accounts (id, name, balance, created_at, updated_at, parent_account_id) VALUES ('b1', 'Şube 1', 50.0, 'now', 'now', 'hq');
+            INSERT INTO current_accounts (id, name, balance, created_at, updated_at, parent_account_id) VALUES ('b2', 'Şube 2', -20.0, 'now', 'now', 'b1');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_consolidated_balance_includes_all_descendants() {
+        let conn = setup_db();
+        let group = get_consolidated_balance(&conn, "hq").unwrap();
+        assert_eq!(group.account_count, 3);
+        assert!((group.total_balance - 130.0).abs() < 0.001);
+
+        let tree = get_account_tree(&conn).unwrap();
+        assert_eq!(tree.len(), 1);
+        assert!((tree[0].consolidated_balance - 130.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_set_parent_rejects_cycles() {
+        let conn = setup_db();
+        assert!(set_account_parent(&conn, "hq", Some("b2")).is_err());
+        assert!(set_account_parent(&conn, "b1", Some("b1")).is_err());
+        assert!(set_account_parent(&conn, "b2", Some("hq")).is_ok());
+    }
+
+    #[test]
+    fn test_set_parent_stops_on_existing_cycle() {
+        let conn = setup_db();
+        // Loop saved before cycles were checked: hq -> b2 -> b1 -> hq
+        conn.execute_batch(
+            "UPDATE current_accounts SET parent_account_id = 'b2' WHERE id = 'hq';
+             INSERT INTO current_accounts (id, name, created_at, updated_at) VALUES ('b3', 'Şube 3', 'now', 'now');",
+        )
+        .unwrap();
+
+        let err = set_account_parent(&conn, "b3", Some("b1")).unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)), "{:?}", err);
+        let parent: Option<String> = conn
+            .query_row("SELECT parent_account_id FROM current_accounts WHERE id = 'b3'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(parent, None);
+
+        // The loop can still be broken by clearing a parent
+        set_account_parent(&conn, "hq", None).unwrap();
+        assert!(set_account_parent(&conn, "b3", Some("b1")).is_ok());
+    }
+
+    #[test]
+    fn test_recompute_balances_reports_and_fixes_drift() {
+        let mut conn = setup_db();
+        conn.execute_batch(
+            "CREATE TABLE transactions (
+                id TEXT PRIMARY KEY, items TEXT NOT NULL, total REAL NOT NULL, payment_method TEXT NOT NULL,
+                transaction_type TEXT NOT NULL DEFAULT 'SALE', note TEXT, created_at TEXT NOT NULL, customer_id TEXT,
+                status TEXT NOT NULL DEFAULT 'completed', tax_total REAL NOT NULL DEFAULT 0
+            );
+            INSERT INTO current_accounts (id, name, account_type, balance, created_at, updated_at) VALUES ('sup', 'Tedarikçi', 'SUPPLIER', 500.0, 'now', 'now');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('t1', '[]', 150.0, 'VERESIYE', 'SALE', NULL, 'now', 'hq');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('t2', '[]', 40.0, 'NAKIT', 'COLLECTION', NULL, 'now', 'hq');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('t3', '[]', -10.0, 'NAKIT', 'RETURN', NULL, 'now', 'hq');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('t4', '[]', 75.0, 'NAKIT', 'SALE', NULL, 'now', 'hq');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('t5', '[]', 50.0, 'VERESIYE', 'SALE', NULL, 'now', 'b1');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('t6', '[]', 300.0, 'VADELI', 'PURCHASE', NULL, 'now', 'sup');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('t7', '[]', 200.0, 'NAKIT', 'PURCHASE', NULL, 'now', 'sup');",
+        )
+        .unwrap();
+
+        // hq: 150 - 40 - 10 = 100 (matches); b1: 50 (matches); b2: 0 vs -20; sup: 300 vs 500
+        let report = recompute_account_balances(&mut conn, true).unwrap();
+        assert_eq!(report.len(), 2);
+        let sup = report.iter().find(|d| d.account_id == "sup").unwrap();
+        assert!((sup.computed_balance - 300.0).abs() < 0.001);
+        assert!((sup.difference + 200.0).abs() < 0.001);
+        assert!(!sup.corrected);
+        assert!((get_account(&conn, "sup").unwrap().balance - 500.0).abs() < 0.001);
+
+        let fixed = recompute_account_balances(&mut conn, false).unwrap();
+        assert!(fixed.iter().all(|d| d.corrected));
+        assert!((get_account(&conn, "sup").unwrap().balance - 300.0).abs() < 0.001);
+        assert!(get_account(&conn, "b2").unwrap().balance.abs() < 0.001);
+        assert!(recompute_account_balances(&mut conn, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_statement_supplier_credit_purchase_and_partial_payment() {
+        let conn = setup_db();
+        conn.execute_batch(
+            "CREATE TABLE transactions (
+                id TEXT PRIMARY KEY, items TEXT NOT NULL, total REAL NOT NULL, payment_method TEXT NOT NULL,
+                transaction_type TEXT NOT NULL DEFAULT 'SALE', note TEXT, created_at TEXT NOT NULL, customer_id TEXT,
+                status TEXT NOT NULL DEFAULT 'completed', tax_total REAL NOT NULL DEFAULT 0
+            );
+            CREATE TABLE goods_receipts (
+                id TEXT PRIMARY KEY, invoice_no TEXT, supplier_id TEXT, total_amount REAL NOT NULL DEFAULT 0,
+                payment_method TEXT NOT NULL, description TEXT DEFAULT '', date TEXT NOT NULL, transaction_id TEXT,
+                created_at TEXT NOT NULL
+            );
+            INSERT INTO current_accounts (id, name, account_type, balance, created_at, updated_at) VALUES ('sup', 'Tedarikçi', 'SUPPLIER', 300.0, 'now', 'now');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('p1', '[]', 500.0, 'VERESIYE', 'PURCHASE', NULL, '2024-03-01T10:00:00Z', 'sup');
+            INSERT INTO goods_receipts (id, invoice_no, supplier_id, payment_method, date, transaction_id, created_at) VALUES ('g1', 'FT-001', 'sup', 'VERESIYE', '2024-03-01', 'p1', 'now');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('p2', '[]', 80.0, 'NAKIT', 'PURCHASE', NULL, '2024-03-02T10:00:00Z', 'sup');
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES ('c1', '[]', -200.0, 'HAVALE', 'COLLECTION', NULL, '2024-03-05T10:00:00Z', 'sup');",
+        )
+        .unwrap();
+
+        let full = get_account_statement(&conn, "sup", "2024-01-01", "2024-12-31").unwrap();
+        assert_eq!(full.opening_balance, 0.0);
+        // The cash purchase does not change the balance
+        assert_eq!(full.lines.len(), 2);
+        assert_eq!((full.lines[0].amount, full.lines[0].balance), (500.0, 500.0));
+        assert_eq!(full.lines[0].description, "FT-001");
+        assert_eq!((full.lines[1].amount, full.lines[1].balance), (-200.0, 300.0));
+        assert_eq!(full.lines[1].description, "Ödeme");
+        assert_eq!(full.closing_balance, get_account(&conn, "sup").unwrap().balance);
+
+        let after_purchase = get_account_statement(&conn, "sup", "2024-03-02", "2024-03-31").unwrap();
+        assert_eq!(after_purchase.opening_balance, 500.0);
+        assert_eq!(after_purchase.lines.len(), 1);
+        assert_eq!(after_purchase.closing_balance, 300.0);
+
+        let before_payment = get_account_statement(&conn, "sup", "2024-03-01", "2024-03-04").unwrap();
+        assert_eq!(before_payment.closing_balance, 500.0);
+
+        assert!(get_account_statement(&conn, "sup", "2024-03-05", "2024-03-01").is_err());
+    }
+
+    #[test]
+    fn test_payment_pays_balance_off_to_zero() {
+        let mut conn = setup_db();
+        conn.execute_batch(
+            "CREATE TABLE transactions (
+                id TEXT PRIMARY KEY, items TEXT NOT NULL, total REAL NOT NULL, payment_method TEXT NOT NULL,
+                transaction_type TEXT NOT NULL DEFAULT 'SALE', note TEXT, created_at TEXT NOT NULL, customer_id TEXT,
+                status TEXT NOT NULL DEFAULT 'completed', tax_total REAL NOT NULL DEFAULT 0
+            );
+            CREATE TABLE transaction_payments (
+                transaction_id TEXT NOT NULL, position INTEGER NOT NULL, method TEXT NOT NULL, amount REAL NOT NULL
+            );
+            CREATE TABLE finance_records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, record_type TEXT NOT NULL, category TEXT NOT NULL,
+                amount REAL NOT NULL, payment_method TEXT NOT NULL DEFAULT 'NAKIT', description TEXT DEFAULT '',
+                date TEXT NOT NULL, created_at TEXT NOT NULL
+            );
+            INSERT INTO current_accounts (id, name, account_type, balance, created_at, updated_at) VALUES ('sup', 'Tedarikçi', 'SUPPLIER', 300.0, 'now', 'now');",
+        )
+        .unwrap();
+
+        // hq owes 100: more than that is refused, a partial and the exact rest pay it off
+        assert!(record_account_payment(&mut conn, "hq", 100.01, "Nakit", "2024-03-01", None, false).is_err());
+        assert!(record_account_payment(&mut conn, "hq", 40.0, "VERESIYE", "2024-03-01", None, false).is_err());
+        record_account_payment(&mut conn, "hq", 60.0, "Nakit", "2024-03-01", None, false).unwrap();
+        let payment = record_account_payment(&mut conn, "hq", 40.0, "Kredi Kartı", "2024-03-02", None, false).unwrap();
+        assert_eq!(payment.payment_method, "KREDI_KARTI");
+        assert_eq!(get_account(&conn, "hq").unwrap().balance, 0.0);
+
+        let (record_type, category, amount, date): (String, String, f64, String) = conn
+            .query_row(
+                "SELECT record_type, category, amount, date FROM finance_records ORDER BY id DESC LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!((record_type.as_str(), category.as_str(), amount, date.as_str()), ("INCOME", "CARI_TAHSILAT", 40.0, "2024-03-02"));
+
+        // Paying a supplier is an expense; overpaying is allowed with the flag
+        assert!(record_account_payment(&mut conn, "sup", 350.0, "HAVALE", "2024-03-02", None, false).is_err());
+        record_account_payment(&mut conn, "sup", 350.0, "HAVALE", "2024-03-02", Some("Avans".to_string()), true).unwrap();
+        assert_eq!(get_account(&conn, "sup").unwrap().balance, -50.0);
+        let record_type: String = conn
+            .query_row("SELECT record_type FROM finance_records WHERE category = 'CARI_ODEME'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(record_type, "EXPENSE");
+
+        let balances = finance::payment_balances(&conn).unwrap();
+        assert_eq!((balances.cash, balances.card, balances.bank), (60.0, 40.0, -350.0));
+    }
+}
@@ -0,0 +1,190 @@
+//! Item Attribute Service Module
+//!
+//! Ürün varyant özellikleri (beden, renk, baz tipi, ambalaj...) anahtar/değer olarak
+//! `item_attributes` tablosunda tutulur. İsteğe bağlıdır; özelliği olmayan ürünler etkilenmez.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
+
+use crate::error::AppError;
+use crate::models::AttributeValues;
+
+const MAX_ATTRIBUTE_KEY_LEN: usize = 50;
+const MAX_ATTRIBUTE_VALUE_LEN: usize = 200;
+
+/// Keys are stored trimmed and lowercase so `Beden` and `beden` are the same attribute
+pub fn normalize_attribute_key(key: &str) -> Result<String, AppError> {
+    let key = key.trim().to_lowercase();
+    if key.is_empty() || key.chars().count() > MAX_ATTRIBUTE_KEY_LEN {
+        return Err(AppError::Validation(format!(
+            "Ozellik adi 1-{} karakter olmali",
+            MAX_ATTRIBUTE_KEY_LEN
+        )));
+    }
+    Ok(key)
+}
+
+/// Attributes of an item, sorted by key
+pub fn get_item_attributes(conn: &Connection, item_id: &str) -> Result<BTreeMap<String, String>, AppError> {
+    let mut stmt = conn.prepare_cached("SELECT key, value FROM item_attributes WHERE item_id = ?1")?;
+    let attributes = stmt
+        .query_map(params![item_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<BTreeMap<String, String>, _>>()?;
+    Ok(attributes)
+}
+
+/// Set attributes of an item. An empty value removes the key; with `replace` all other keys are removed.
+pub fn set_item_attributes(
+    conn: &mut Connection,
+    item_id: &str,
+    attributes: &HashMap<String, String>,
+    replace: bool,
+) -> Result<BTreeMap<String, String>, AppError> {
+    let exists: Option<String> = conn
+        .query_row("SELECT id FROM inventory_items WHERE id = ?1", params![item_id], |row| row.get(0))
+        .optional()?;
+    if exists.is_none() {
+        return Err(AppError::NotFound(format!("Urun bulunamadi: {}", item_id)));
+    }
+
+    let mut normalized = Vec::with_capacity(attributes.len());
+    for (key, value) in attributes {
+        let value = value.trim();
+        if value.chars().count() > MAX_ATTRIBUTE_VALUE_LEN {
+            return Err(AppError::Validation(format!(
+                "Ozellik degeri en fazla {} karakter olabilir: {}",
+                MAX_ATTRIBUTE_VALUE_LEN, key
+            )));
+        }
+        normalized.push((normalize_attribute_key(key)?, value.to_string()));
+    }
+
+    let tx = conn.transaction()?;
+    if replace {
+        tx.execute("DELETE FROM item_attributes WHERE item_id = ?1", params![item_id])?;
+    }
+    for (key, value) in &normalized {
+        if value.is_empty() {
+            tx.execute(
+                "DELETE FROM item_attributes WHERE item_id = ?1 AND key = ?2",
+                params![item_id, key],
+            )?;
+        } else {
+            tx.execute(
+                "INSERT OR REPLACE INTO item_attributes (item_id, key, value) VALUES (?1, ?2, ?3)",
+                params![item_id, key, value],
+            )?;
+        }
+    }
+    tx.commit()?;
+
+    get_item_attributes(conn, item_id)
+}
+
+/// Every attribute key with its distinct values (filter options)
+pub fn get_attribute_values(conn: &Connection) -> Result<Vec<AttributeValues>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT key, value, COUNT(*) FROM item_attributes GROUP BY key, value ORDER BY key, value COLLATE NOCASE",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i32>(2)?))
+    })?;
+
+    let mut result: Vec<AttributeValues> = Vec::new();
+    for row in rows {
+        let (key, value, count) = row?;
+        match result.last_mut() {
+            Some(last) if last.key == key => {
+                last.values.push(value);
+                last.item_count += count;
+            }
+            _ => result.push(AttributeValues { key, values: vec![value], item_count: count }),
+        }
+    }
+    Ok(result)
+}
+
+/// Add one `id IN (...)` condition per attribute filter (all must match, values case-insensitive)
+pub fn push_attribute_conditions(
+    filters: &HashMap<String, String>,
+    id_column: &str,
+    conditions: &mut Vec<String>,
+    params: &mut Vec<Box<dyn ToSql>>,
+) -> Result<(), AppError> {
+    let mut filters: Vec<(String, String)> = filters
+        .iter()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(key, value)| Ok((normalize_attribute_key(key)?, value.trim().to_string())))
+        .collect::<Result<_, AppError>>()?;
+    // Stable SQL text for the statement cache
+    filters.sort();
+
+    for (key, value) in filters {
+        conditions.push(format!(
+            "{} IN (SELECT item_id FROM item_attributes WHERE key = ? AND value = ? COLLATE NOCASE)",
+            id_column
+        ));
+        params.push(Box::new(key));
+        params.push(Box::new(value));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (id TEXT PRIMARY KEY, sku TEXT, name TEXT);
+            CREATE TABLE item_attributes (
+                item_id TEXT NOT NULL, key TEXT NOT NULL, value TEXT NOT NULL,
+                PRIMARY KEY (item_id, key)
+            );
+            INSERT INTO inventory_items VALUES ('p1', 'RP1-25', 'Plastik Boya');
+            INSERT INTO inventory_items VALUES ('p2', 'RP1-75', 'Plastik Boya');
+            INSERT INTO inventory_items VALUES ('p3', 'RP3-25', 'Plastik Boya');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn attrs(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_set_and_filter_attributes() {
+        let mut conn = setup_db();
+        set_item_attributes(&mut conn, "p1", &attrs(&[("Baz", "RP1"), ("Ambalaj", "2.5L")]), false).unwrap();
+        set_item_attributes(&mut conn, "p2", &attrs(&[("baz", "RP1"), ("ambalaj", "7.5L")]), false).unwrap();
+        set_item_attributes(&mut conn, "p3", &attrs(&[("baz", "RP3"), ("ambalaj", "2.5L")]), false).unwrap();
+        assert!(set_item_attributes(&mut conn, "missing", &attrs(&[("baz", "RP1")]), false).is_err());
+
+        // Empty value removes a key
+        let p3 = set_item_attributes(&mut conn, "p3", &attrs(&[("ambalaj", "")]), false).unwrap();
+        assert_eq!(p3.len(), 1);
+
+        let mut conditions = Vec::new();
+        let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+        push_attribute_conditions(&attrs(&[("BAZ", "rp1"), ("ambalaj", "2.5l")]), "id", &mut conditions, &mut params)
+            .unwrap();
+        let sql = format!("SELECT id FROM inventory_items WHERE {}", conditions.join(" AND "));
+        let refs: Vec<&dyn ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let ids: Vec<String> = conn
+            .prepare(&sql)
+            .unwrap()
+            .query_map(refs.as_slice(), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, vec!["p1"]);
+
+        let values = get_attribute_values(&conn).unwrap();
+        let baz = values.iter().find(|v| v.key == "baz").unwrap();
+        assert_eq!(baz.values, vec!["RP1", "RP3"]);
+        assert_eq!(baz.item_count, 3);
+    }
+}
@@ -1,12 +1,16 @@
 //! Backup Service Module
 //! 
 //! Otomatik ve manuel veritabanı yedekleme sistemi
+//!
+//! Veri silen işlemler (fabrika ayarları, veritabanını temizleme, örnek veri, içe aktarma) önce
+//! işlem adıyla etiketlenmiş bir güvenlik yedeği alır; yedek alınamazsa işlem yapılmaz.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use directories::ProjectDirs;
 use chrono::{Local, Datelike, Timelike};
+use rusqlite::Connection;
 
 static BACKUP_RUNNING: AtomicBool = AtomicBool::new(false);
 
@@ -37,37 +41,77 @@ pub fn get_backup_directory() -> Option<PathBuf> {
         .map(|proj| proj.data_dir().join("backups"))
 }
 
-/// Create backup of the database
-pub fn create_backup(db_path: &str) -> Result<PathBuf, String> {
-    let backup_dir = get_backup_directory()
-        .ok_or("Yedek dizini belirlenemedi")?;
-    
-    // Ensure backup directory exists
-    std::fs::create_dir_all(&backup_dir)
-        .map_err(|e| format!("Yedek dizini oluşturulamadı: {}", e))?;
-    
-    // Generate backup filename with timestamp
-    let now = Local::now();
-    let filename = format!(
+/// Timestamped backup file name (nexus_backup_YYYYMMDD_HHMMSS.db)
+pub fn backup_file_name(now: chrono::DateTime<Local>) -> String {
+    format!(
         "nexus_backup_{:04}{:02}{:02}_{:02}{:02}{:02}.db",
         now.year(), now.month(), now.day(),
         now.hour(), now.minute(), now.second()
-    );
-    let backup_path = backup_dir.join(&filename);
-    
+    )
+}
+
+/// Marker between the timestamp and the operation name of a safety backup
+const SAFETY_BACKUP_MARKER: &str = "_before_";
+
+/// Safety backup file name (nexus_backup_YYYYMMDD_HHMMSS_before_<operation>.db)
+pub fn safety_backup_file_name(now: chrono::DateTime<Local>, operation: &str) -> String {
+    let name = backup_file_name(now);
+    format!("{}{}{}.db", name.trim_end_matches(".db"), SAFETY_BACKUP_MARKER, operation)
+}
+
+/// Operation a safety backup was taken before, None for regular backups
+pub fn safety_backup_operation(filename: &str) -> Option<&str> {
+    let (_, operation) = filename.strip_suffix(".db")?.split_once(SAFETY_BACKUP_MARKER)?;
+    Some(operation)
+}
+
+/// Copy `db_path` into `backup_dir` under `filename`
+fn copy_backup(backup_dir: &Path, db_path: &str, filename: &str) -> Result<PathBuf, String> {
+    // Ensure backup directory exists
+    std::fs::create_dir_all(backup_dir)
+        .map_err(|e| format!("Yedek dizini oluşturulamadı: {}", e))?;
+
+    let backup_path = backup_dir.join(filename);
+
     // Copy database file
     std::fs::copy(db_path, &backup_path)
         .map_err(|e| format!("Yedek kopyalanamadı: {}", e))?;
+
+    println!("[Backup] Created: {}", backup_path.display());
+    Ok(backup_path)
+}
+
+/// Create backup of the database
+pub fn create_backup(db_path: &str) -> Result<PathBuf, String> {
+    let backup_dir = get_backup_directory()
+        .ok_or("Yedek dizini belirlenemedi")?;
+    
+    // Generate backup filename with timestamp
+    let backup_path = copy_backup(&backup_dir, db_path, &backup_file_name(Local::now()))?;
     
     // Clean old backups
     if let Err(e) = cleanup_old_backups(&backup_dir, 10) {
         eprintln!("Eski yedekler temizlenemedi: {}", e);
     }
     
-    println!("[Backup] Created: {}", backup_path.display());
     Ok(backup_path)
 }
 
+/// Back up the database before a destructive `operation` (e.g. "factory_reset").
+/// The WAL is checkpointed first so the copy holds every committed row.
+pub fn create_safety_backup(conn: &Connection, db_path: &str, operation: &str) -> Result<PathBuf, String> {
+    let backup_dir = get_backup_directory()
+        .ok_or("Yedek dizini belirlenemedi")?;
+    create_safety_backup_in(&backup_dir, conn, db_path, operation)
+}
+
+fn create_safety_backup_in(backup_dir: &Path, conn: &Connection, db_path: &str, operation: &str) -> Result<PathBuf, String> {
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+        .map_err(|e| format!("WAL checkpoint basarisiz: {}", e))?;
+    copy_backup(backup_dir, db_path, &safety_backup_file_name(Local::now(), operation))
+        .map_err(|e| format!("Güvenlik yedeği alınamadı, işlem iptal edildi: {}", e))
+}
+
 /// List all available backups
 pub fn list_backups() -> Result<Vec<BackupInfo>, String> {
     let backup_dir = get_backup_directory()
@@ -104,6 +148,14 @@ pub fn list_backups() -> Result<Vec<BackupInfo>, String> {
     Ok(backups)
 }
 
+/// Backups taken automatically before destructive operations, newest first
+pub fn list_safety_backups() -> Result<Vec<BackupInfo>, String> {
+    Ok(list_backups()?
+        .into_iter()
+        .filter(|b| safety_backup_operation(&b.filename).is_some())
+        .collect())
+}
+
 /// Restore database from backup
 pub fn restore_backup(backup_path: &str, db_path: &str) -> Result<(), String> {
     // Verify backup file exists
@@ -173,8 +225,19 @@ pub fn start_backup_scheduler(db_path: String, interval_hours: u64) {
                 break;
             }
             
-            match create_backup(&db_path) {
-                Ok(path) => println!("[Backup] Scheduled backup created: {}", path.display()),
+            // Check first: rotating in a corrupt copy would push out the last good backups
+            match super::health::run_scheduled_health_check(&db_path) {
+                Ok(true) => {}
+                Ok(false) => {
+                    eprintln!("[Backup] Skipped: database health check failed");
+                    continue;
+                }
+                Err(e) => eprintln!("[Backup] Health check could not run: {}", e),
+            }
+
+            // Written to the configured target (local folder, network share, cloud or S3)
+            match super::backup_target::backup_to_configured_target(&db_path, 10) {
+                Ok(info) => println!("[Backup] Scheduled backup created: {}", info.filename),
                 Err(e) => eprintln!("[Backup] Scheduled backup failed: {}", e),
             }
         }
@@ -189,3 +252,47 @@ pub fn start_backup_scheduler(db_path: String, interval_hours: u64) {
 pub fn stop_backup_scheduler() {
     BACKUP_RUNNING.store(false, Ordering::SeqCst);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_database_leaves_restorable_safety_backup() {
+        let dir = std::env::temp_dir().join(format!("nexus_safety_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("inventory.db").to_string_lossy().to_string();
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE inventory_items (id TEXT PRIMARY KEY, sku TEXT);
+             CREATE TABLE item_attributes (item_id TEXT, key TEXT, value TEXT);
+             CREATE TABLE transactions (id TEXT PRIMARY KEY);
+             CREATE TABLE sale_lot_consumptions (id TEXT PRIMARY KEY);
+             CREATE TABLE activity_log (id TEXT PRIMARY KEY);
+             INSERT INTO inventory_items VALUES ('1', 'A-1'), ('2', 'A-2');
+             INSERT INTO transactions VALUES ('t1');",
+        )
+        .unwrap();
+
+        let backup_path = create_safety_backup_in(&dir.join("backups"), &conn, &db_path, "clear_database").unwrap();
+        crate::services::inventory::clear_inventory(&conn).unwrap();
+        let count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM inventory_items", [], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(count(&conn), 0);
+
+        let filename = backup_path.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(safety_backup_operation(&filename), Some("clear_database"));
+        assert_eq!(safety_backup_operation("nexus_backup_20240101_120000.db"), None);
+
+        // Restoring the backup brings the rows back
+        let restored_path = dir.join("restored.db").to_string_lossy().to_string();
+        restore_backup(&backup_path.to_string_lossy(), &restored_path).unwrap();
+        let restored = Connection::open(&restored_path).unwrap();
+        assert_eq!(count(&restored), 2);
+
+        drop((conn, restored));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,587 @@
+//! Backup Target Service Module
+//!
+//! Yedeklerin nereye yazılacağını soyutlar: yerel klasör, ağ paylaşımı (SMB/UNC),
+//! bayinin bulut API'si veya S3 uyumlu depolama (`s3-backup` özelliği ile).
+//! Seçili hedef `backup_target` ayarında JSON olarak tutulur; zamanlayıcı hedeften bağımsızdır.
+
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::cloud;
+use crate::error::AppError;
+use crate::license;
+
+use super::backup::{self, BackupInfo};
+
+pub const BACKUP_TARGET_SETTING: &str = "backup_target";
+
+/// Destination for database backups. `BackupInfo.path` holds the target specific backup id.
+pub trait BackupTarget: Send {
+    /// Human readable destination, used in logs
+    fn describe(&self) -> String;
+    /// Store a backup under `name` and return its entry
+    fn store(&self, bytes: &[u8], name: &str) -> Result<BackupInfo, AppError>;
+    /// Stored backups, newest first
+    fn list(&self) -> Result<Vec<BackupInfo>, AppError>;
+    fn fetch(&self, id: &str) -> Result<Vec<u8>, AppError>;
+    fn delete(&self, id: &str) -> Result<(), AppError>;
+}
+
+/// Stored backup target configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BackupTargetConfig {
+    /// App data folder, or a custom local folder
+    Local {
+        #[serde(default)]
+        path: Option<String>,
+    },
+    /// Mounted network share or UNC path (\\sunucu\paylasim\yedek)
+    NetworkShare { path: String },
+    /// Dealer cloud API, using the activated license
+    Cloud,
+    /// S3-compatible bucket (AWS, MinIO, ...)
+    S3 {
+        endpoint: String,
+        region: String,
+        bucket: String,
+        #[serde(rename = "accessKey")]
+        access_key: String,
+        /// Never returned to the frontend; an empty value keeps the stored secret
+        #[serde(rename = "secretKey", default)]
+        secret_key: String,
+        #[serde(default)]
+        prefix: Option<String>,
+    },
+}
+
+impl Default for BackupTargetConfig {
+    fn default() -> Self {
+        BackupTargetConfig::Local { path: None }
+    }
+}
+
+impl BackupTargetConfig {
+    /// Copy safe to send to the frontend
+    pub fn masked(&self) -> Self {
+        match self {
+            BackupTargetConfig::S3 { endpoint, region, bucket, access_key, prefix, .. } => BackupTargetConfig::S3 {
+                endpoint: endpoint.clone(),
+                region: region.clone(),
+                bucket: bucket.clone(),
+                access_key: access_key.clone(),
+                secret_key: String::new(),
+                prefix: prefix.clone(),
+            },
+            other => other.clone(),
+        }
+    }
+}
+
+/// Backup ids must be plain file names so they cannot point outside the target
+fn validate_backup_name(name: &str) -> Result<(), AppError> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(AppError::Validation(format!("Gecersiz yedek adi: {}", name)));
+    }
+    Ok(())
+}
+
+// ==================== LOCAL FOLDER / NETWORK SHARE ====================
+
+/// Backups as `.db` files in a folder (local disk or mounted network share)
+pub struct LocalFolderTarget {
+    dir: PathBuf,
+}
+
+impl LocalFolderTarget {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn backup_info(&self, path: &std::path::Path) -> Option<BackupInfo> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let filename = path.file_name()?.to_string_lossy().to_string();
+        Some(BackupInfo {
+            path: filename.clone(),
+            filename,
+            size_bytes: metadata.len(),
+            created_at: metadata
+                .modified()
+                .map(|t| chrono::DateTime::<chrono::Local>::from(t).to_rfc3339())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl BackupTarget for LocalFolderTarget {
+    fn describe(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn store(&self, bytes: &[u8], name: &str) -> Result<BackupInfo, AppError> {
+        validate_backup_name(name)?;
+        std::fs::create_dir_all(&self.dir)?;
+
+        // Write then rename so a half-written file is never listed as a backup
+        let path = self.dir.join(name);
+        let partial = self.dir.join(format!("{}.partial", name));
+        std::fs::write(&partial, bytes)?;
+        std::fs::rename(&partial, &path)?;
+
+        self.backup_info(&path)
+            .ok_or_else(|| AppError::Internal(format!("Yedek yazildi ama okunamadi: {}", path.display())))
+    }
+
+    fn list(&self) -> Result<Vec<BackupInfo>, AppError> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut backups: Vec<BackupInfo> = std::fs::read_dir(&self.dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|e| e == "db").unwrap_or(false))
+            .filter_map(|path| self.backup_info(&path))
+            .collect();
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(backups)
+    }
+
+    fn fetch(&self, id: &str) -> Result<Vec<u8>, AppError> {
+        validate_backup_name(id)?;
+        let path = self.dir.join(id);
+        if !path.exists() {
+            return Err(AppError::NotFound(format!("Yedek bulunamadi: {}", id)));
+        }
+        Ok(std::fs::read(path)?)
+    }
+
+    fn delete(&self, id: &str) -> Result<(), AppError> {
+        validate_backup_name(id)?;
+        Ok(std::fs::remove_file(self.dir.join(id))?)
+    }
+}
+
+// ==================== CLOUD API ====================
+
+/// The dealer cloud API keeps a single (latest) backup per dealer
+pub struct CloudApiTarget {
+    api_base_url: String,
+    dealer_id: String,
+    license_key: String,
+}
+
+/// Id of the only backup the cloud API serves
+pub const CLOUD_LATEST_BACKUP_ID: &str = "latest";
+
+impl CloudApiTarget {
+    pub fn new(api_base_url: &str, dealer_id: &str, license_key: &str) -> Self {
+        Self {
+            api_base_url: api_base_url.to_string(),
+            dealer_id: dealer_id.to_string(),
+            license_key: license_key.to_string(),
+        }
+    }
+}
+
+impl BackupTarget for CloudApiTarget {
+    fn describe(&self) -> String {
+        format!("bulut ({})", self.api_base_url)
+    }
+
+    fn store(&self, bytes: &[u8], name: &str) -> Result<BackupInfo, AppError> {
+        // The upload streams from a file; stage the bytes in a temp file
+        let temp_path = std::env::temp_dir().join(format!("nexus_cloud_upload_{}.db", uuid::Uuid::new_v4()));
+        std::fs::write(&temp_path, bytes)?;
+        let result = cloud::upload_backup_file(
+            &self.api_base_url,
+            &self.dealer_id,
+            &self.license_key,
+            &temp_path.to_string_lossy(),
+        );
+        let _ = std::fs::remove_file(&temp_path);
+
+        let response = result?;
+        if !response.success {
+            return Err(AppError::Internal(
+                response.error.unwrap_or_else(|| "Bulut yedeklemesi basarisiz".to_string()),
+            ));
+        }
+        Ok(BackupInfo {
+            filename: name.to_string(),
+            path: CLOUD_LATEST_BACKUP_ID.to_string(),
+            size_bytes: bytes.len() as u64,
+            created_at: response.timestamp.unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+        })
+    }
+
+    fn list(&self) -> Result<Vec<BackupInfo>, AppError> {
+        let status = cloud::get_cloud_status(&self.api_base_url, &self.dealer_id, &self.license_key)?;
+        if !status.has_backup {
+            return Ok(vec![]);
+        }
+        Ok(vec![BackupInfo {
+            filename: "bulut_yedegi.db".to_string(),
+            path: CLOUD_LATEST_BACKUP_ID.to_string(),
+            size_bytes: status.backup_size_bytes.unwrap_or(0).max(0) as u64,
+            created_at: status.last_backup_at.unwrap_or_default(),
+        }])
+    }
+
+    fn fetch(&self, id: &str) -> Result<Vec<u8>, AppError> {
+        if id != CLOUD_LATEST_BACKUP_ID {
+            return Err(AppError::NotFound(format!("Yedek bulunamadi: {}", id)));
+        }
+        let temp_path = cloud::download_cloud_backup(&self.api_base_url, &self.dealer_id, &self.license_key)?;
+        let bytes = std::fs::read(&temp_path);
+        let _ = std::fs::remove_file(&temp_path);
+        Ok(bytes?)
+    }
+
+    fn delete(&self, _id: &str) -> Result<(), AppError> {
+        Err(AppError::Validation("Bulut yedekleri uygulamadan silinemez".to_string()))
+    }
+}
+
+// ==================== S3 ====================
+
+#[cfg(feature = "s3-backup")]
+pub struct S3Target {
+    bucket: s3::Bucket,
+    prefix: String,
+}
+
+#[cfg(feature = "s3-backup")]
+impl S3Target {
+    pub fn new(
+        endpoint: &str,
+        region: &str,
+        bucket: &str,
+        access_key: &str,
+        secret_key: &str,
+        prefix: Option<&str>,
+    ) -> Result<Self, AppError> {
+        let region = s3::Region::Custom {
+            region: region.to_string(),
+            endpoint: endpoint.to_string(),
+        };
+        let credentials = s3::creds::Credentials::new(Some(access_key), Some(secret_key), None, None, None)
+            .map_err(|e| AppError::Validation(format!("S3 kimlik bilgileri gecersiz: {}", e)))?;
+        // Path-style addressing works with MinIO and other S3-compatible servers
+        let bucket = s3::Bucket::new(bucket, region, credentials)
+            .map_err(|e| AppError::Validation(format!("S3 bucket tanimlanamadi: {}", e)))?
+            .with_path_style();
+
+        let prefix = prefix.map(|p| p.trim_matches('/')).filter(|p| !p.is_empty());
+        Ok(Self {
+            bucket,
+            prefix: prefix.map(|p| format!("{}/", p)).unwrap_or_default(),
+        })
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    fn check_status(code: u16, action: &str) -> Result<(), AppError> {
+        if (200..300).contains(&code) {
+            Ok(())
+        } else {
+            Err(AppError::Internal(format!("S3 {} basarisiz: HTTP {}", action, code)))
+        }
+    }
+}
+
+#[cfg(feature = "s3-backup")]
+impl BackupTarget for S3Target {
+    fn describe(&self) -> String {
+        format!("s3://{}/{}", self.bucket.name(), self.prefix)
+    }
+
+    fn store(&self, bytes: &[u8], name: &str) -> Result<BackupInfo, AppError> {
+        validate_backup_name(name)?;
+        let response = self
+            .bucket
+            .put_object(self.key(name), bytes)
+            .map_err(|e| AppError::Internal(format!("S3 yukleme basarisiz: {}", e)))?;
+        Self::check_status(response.status_code(), "yukleme")?;
+
+        Ok(BackupInfo {
+            filename: name.to_string(),
+            path: name.to_string(),
+            size_bytes: bytes.len() as u64,
+            created_at: chrono::Local::now().to_rfc3339(),
+        })
+    }
+
+    fn list(&self) -> Result<Vec<BackupInfo>, AppError> {
+        let pages = self
+            .bucket
+            .list(self.prefix.clone(), Some("/".to_string()))
+            .map_err(|e| AppError::Internal(format!("S3 listeleme basarisiz: {}", e)))?;
+
+        let mut backups: Vec<BackupInfo> = pages
+            .into_iter()
+            .flat_map(|page| page.contents)
+            .filter_map(|object| {
+                let name = object.key.strip_prefix(&self.prefix)?.to_string();
+                name.ends_with(".db").then(|| BackupInfo {
+                    filename: name.clone(),
+                    path: name,
+                    size_bytes: object.size,
+                    created_at: object.last_modified,
+                })
+            })
+            .collect();
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(backups)
+    }
+
+    fn fetch(&self, id: &str) -> Result<Vec<u8>, AppError> {
+        validate_backup_name(id)?;
+        let response = self
+            .bucket
+            .get_object(self.key(id))
+            .map_err(|e| AppError::Internal(format!("S3 indirme basarisiz: {}", e)))?;
+        if response.status_code() == 404 {
+            return Err(AppError::NotFound(format!("Yedek bulunamadi: {}", id)));
+        }
+        Self::check_status(response.status_code(), "indirme")?;
+        Ok(response.bytes().to_vec())
+    }
+
+    fn delete(&self, id: &str) -> Result<(), AppError> {
+        validate_backup_name(id)?;
+        let response = self
+            .bucket
+            .delete_object(self.key(id))
+            .map_err(|e| AppError::Internal(format!("S3 silme basarisiz: {}", e)))?;
+        Self::check_status(response.status_code(), "silme")
+    }
+}
+
+// ==================== CONFIGURATION ====================
+
+/// Folder of a local or network share target
+fn target_folder(config: &BackupTargetConfig) -> Result<Option<PathBuf>, AppError> {
+    match config {
+        BackupTargetConfig::Local { path: Some(path) } | BackupTargetConfig::NetworkShare { path } => {
+            Ok(Some(PathBuf::from(path)))
+        }
+        BackupTargetConfig::Local { path: None } => backup::get_backup_directory()
+            .map(Some)
+            .ok_or_else(|| AppError::Internal("Yedek dizini belirlenemedi".to_string())),
+        _ => Ok(None),
+    }
+}
+
+/// Build the target described by `config`
+pub fn open_backup_target(conn: &Connection, config: &BackupTargetConfig) -> Result<Box<dyn BackupTarget>, AppError> {
+    if let Some(dir) = target_folder(config)? {
+        return Ok(Box::new(LocalFolderTarget::new(dir)));
+    }
+
+    match config {
+        BackupTargetConfig::Local { .. } | BackupTargetConfig::NetworkShare { .. } => {
+            unreachable!("folder targets are handled above")
+        }
+        BackupTargetConfig::Cloud => {
+            let license_data = license::get_local_license(conn)?
+                .ok_or_else(|| AppError::NotFound("Lisans bulunamadi".to_string()))?;
+            Ok(Box::new(CloudApiTarget::new(
+                &license_data.api_base_url,
+                &license_data.dealer_id,
+                &license_data.license_key,
+            )))
+        }
+        #[cfg(feature = "s3-backup")]
+        BackupTargetConfig::S3 { endpoint, region, bucket, access_key, secret_key, prefix } => Ok(Box::new(
+            S3Target::new(endpoint, region, bucket, access_key, secret_key, prefix.as_deref())?,
+        )),
+        #[cfg(not(feature = "s3-backup"))]
+        BackupTargetConfig::S3 { .. } => Err(AppError::Validation(
+            "Bu surum S3 destegi olmadan derlendi (s3-backup ozelligi)".to_string(),
+        )),
+    }
+}
+
+/// Configured target (local app data folder when nothing is stored)
+pub fn get_backup_target_config(conn: &Connection) -> Result<BackupTargetConfig, AppError> {
+    let value: Option<Option<String>> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![BACKUP_TARGET_SETTING],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match value.flatten() {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(BackupTargetConfig::default()),
+    }
+}
+
+/// Validate, check that the target is reachable and store it. Returns the masked config.
+pub fn configure_backup_target(conn: &Connection, config: BackupTargetConfig) -> Result<BackupTargetConfig, AppError> {
+    let config = match config {
+        BackupTargetConfig::Local { path } => BackupTargetConfig::Local {
+            path: path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()),
+        },
+        BackupTargetConfig::NetworkShare { path } => {
+            let path = path.trim().to_string();
+            if path.is_empty() {
+                return Err(AppError::Validation("Ag paylasimi yolu bos olamaz".to_string()));
+            }
+            BackupTargetConfig::NetworkShare { path }
+        }
+        BackupTargetConfig::S3 { endpoint, region, bucket, access_key, secret_key, prefix } => {
+            let secret_key = if secret_key.is_empty() {
+                match get_backup_target_config(conn)? {
+                    BackupTargetConfig::S3 { secret_key, .. } => secret_key,
+                    _ => String::new(),
+                }
+            } else {
+                secret_key
+            };
+            if [&endpoint, &region, &bucket, &access_key, &secret_key].iter().any(|v| v.trim().is_empty()) {
+                return Err(AppError::Validation(
+                    "S3 icin endpoint, bolge, bucket ve erisim anahtarlari gerekli".to_string(),
+                ));
+            }
+            BackupTargetConfig::S3 { endpoint, region, bucket, access_key, secret_key, prefix }
+        }
+        BackupTargetConfig::Cloud => BackupTargetConfig::Cloud,
+    };
+
+    // Fail now rather than on the next scheduled backup
+    if let Some(dir) = target_folder(&config)? {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| AppError::Validation(format!("Yedek klasorune erisilemedi ({}): {}", dir.display(), e)))?;
+    }
+    let target = open_backup_target(conn, &config)?;
+    target
+        .list()
+        .map_err(|e| AppError::Validation(format!("Yedek hedefine erisilemedi ({}): {}", target.describe(), e)))?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![BACKUP_TARGET_SETTING, serde_json::to_string(&config)?],
+    )?;
+    Ok(config.masked())
+}
+
+/// Remove all but the newest `keep` backups; failures are logged, not returned
+pub fn prune_backups(target: &dyn BackupTarget, keep: usize) -> Result<(), AppError> {
+    for old in target.list()?.into_iter().skip(keep) {
+        match target.delete(&old.path) {
+            Ok(()) => println!("[Backup] Cleaned: {}", old.filename),
+            Err(e) => eprintln!("Eski yedek silinemedi {}: {}", old.filename, e),
+        }
+    }
+    Ok(())
+}
+
+/// Snapshot the database into the configured target, keeping the newest `keep` backups
+pub fn backup_to_configured_target(db_path: &str, keep: usize) -> Result<BackupInfo, AppError> {
+    let conn = crate::database::open_database(db_path)?;
+    let config = get_backup_target_config(&conn)?;
+    let target = open_backup_target(&conn, &config)?;
+
+    let bytes = if matches!(config, BackupTargetConfig::Local { .. }) {
+        // Flush the WAL so the file on disk holds all committed data
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+            .map_err(|e| AppError::sqlite("WAL checkpoint basarisiz", e))?;
+        std::fs::read(db_path)?
+    } else {
+        // Off-site copies are plaintext so a new install (with its own key) can restore them
+        let temp_path = std::env::temp_dir().join(format!("nexus_target_backup_{}.db", uuid::Uuid::new_v4()));
+        let read = crate::database::encryption::export_plaintext_copy(std::path::Path::new(db_path), &temp_path)
+            .and_then(|()| std::fs::read(&temp_path).map_err(AppError::from));
+        let _ = std::fs::remove_file(&temp_path);
+        read?
+    };
+
+    let info = target.store(&bytes, &backup::backup_file_name(chrono::Local::now()))?;
+    if let Err(e) = prune_backups(target.as_ref(), keep) {
+        eprintln!("Eski yedekler temizlenemedi ({}): {}", target.describe(), e);
+    }
+    Ok(info)
+}
+
+/// Restore the live database from a backup stored in the configured target
+pub fn restore_from_configured_target(conn: &Connection, db_path: &str, id: &str) -> Result<(), AppError> {
+    let target = open_backup_target(conn, &get_backup_target_config(conn)?)?;
+    let bytes = target.fetch(id)?;
+
+    let temp_path = std::env::temp_dir().join(format!("nexus_target_restore_{}.db", uuid::Uuid::new_v4()));
+    std::fs::write(&temp_path, &bytes)?;
+    let result = crate::database::restore_database_from_file(&temp_path, db_path);
+    let _ = std::fs::remove_file(&temp_path);
+
+    result?;
+    println!("[Backup] Restored {} from {}", id, target.describe());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_folder_target_roundtrip_and_prune() {
+        let dir = std::env::temp_dir().join(format!("nexus_backup_target_{}", std::process::id()));
+        let target = LocalFolderTarget::new(&dir);
+
+        for i in 0..3 {
+            target.store(&[i as u8; 16], &format!("nexus_backup_{}.db", i)).unwrap();
+            // Distinct modification times for newest-first ordering
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert!(target.store(b"x", "../escape.db").is_err());
+        assert_eq!(target.fetch("nexus_backup_1.db").unwrap(), vec![1u8; 16]);
+
+        prune_backups(&target, 2).unwrap();
+        let names: Vec<String> = target.list().unwrap().into_iter().map(|b| b.path).collect();
+        assert_eq!(names, vec!["nexus_backup_2.db", "nexus_backup_1.db"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_config_storage_keeps_secret() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);")
+            .unwrap();
+        assert_eq!(get_backup_target_config(&conn).unwrap(), BackupTargetConfig::default());
+
+        let dir = std::env::temp_dir().join(format!("nexus_backup_share_{}", std::process::id()));
+        let share = BackupTargetConfig::NetworkShare { path: dir.to_string_lossy().to_string() };
+        assert_eq!(configure_backup_target(&conn, share.clone()).unwrap(), share);
+        assert_eq!(get_backup_target_config(&conn).unwrap(), share);
+        assert!(configure_backup_target(&conn, BackupTargetConfig::NetworkShare { path: " ".to_string() }).is_err());
+
+        let s3 = BackupTargetConfig::S3 {
+            endpoint: "http://127.0.0.1:9".to_string(),
+            region: "us-east-1".to_string(),
+            bucket: "yedek".to_string(),
+            access_key: "AK".to_string(),
+            secret_key: "SECRET".to_string(),
+            prefix: None,
+        };
+        assert_eq!(s3.masked(), BackupTargetConfig::S3 {
+            endpoint: "http://127.0.0.1:9".to_string(),
+            region: "us-east-1".to_string(),
+            bucket: "yedek".to_string(),
+            access_key: "AK".to_string(),
+            secret_key: String::new(),
+            prefix: None,
+        });
+        // Unreachable (or not compiled in): rejected and the previous target is kept
+        assert!(configure_backup_target(&conn, s3).is_err());
+        assert_eq!(get_backup_target_config(&conn).unwrap(), share);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,263 @@
+//! Bundle Service Module
+//!
+//! Set/kit ürünler: satıldığında bileşen stokları düşülür
+
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::models::{BundleComponentInput, BundleSummary, ProductComponent};
+use crate::security::validation::validate_quantity;
+
+/// Nested bundles deeper than this are rejected (döngü koruması)
+const MAX_BUNDLE_DEPTH: usize = 8;
+
+/// Direct components of a bundle as (component_sku, quantity); empty when the SKU is not a bundle
+fn direct_components(conn: &Connection, parent_sku: &str) -> Result<Vec<(String, f64)>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT component_sku, quantity FROM product_components WHERE parent_sku = ?1 ORDER BY component_sku",
+    )?;
+    let components = stmt
+        .query_map(params![parent_sku], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(components)
+}
+
+/// Whether `target` appears anywhere below `root` in the bundle tree
+fn bundle_contains(conn: &Connection, root: &str, target: &str) -> Result<bool, AppError> {
+    let mut stack = vec![root.to_string()];
+    let mut visited = HashSet::new();
+    while let Some(sku) = stack.pop() {
+        if !visited.insert(sku.clone()) {
+            continue;
+        }
+        for (component, _) in direct_components(conn, &sku)? {
+            if component == target {
+                return Ok(true);
+            }
+            stack.push(component);
+        }
+    }
+    Ok(false)
+}
+
+fn expand_into(
+    conn: &Connection,
+    sku: &str,
+    quantity: f64,
+    depth: usize,
+    out: &mut Vec<(String, f64)>,
+) -> Result<(), AppError> {
+    if depth > MAX_BUNDLE_DEPTH {
+        return Err(AppError::Validation(format!("Set ürün çok derin veya döngüsel: {}", sku)));
+    }
+    let components = direct_components(conn, sku)?;
+    if components.is_empty() {
+        match out.iter_mut().find(|(s, _)| s == sku) {
+            Some((_, q)) => *q += quantity,
+            None => out.push((sku.to_string(), quantity)),
+        }
+        return Ok(());
+    }
+    for (component, per_bundle) in components {
+        expand_into(conn, &component, quantity * per_bundle, depth + 1, out)?;
+    }
+    Ok(())
+}
+
+/// Stock-carrying SKUs and quantities moved when `quantity` of `sku` is sold or returned.
+///
+/// A normal item expands to itself; a bundle expands (recursively) to its leaf components.
+pub fn expand_stock_movements(conn: &Connection, sku: &str, quantity: f64) -> Result<Vec<(String, f64)>, AppError> {
+    let mut out = Vec::new();
+    expand_into(conn, sku, quantity, 0, &mut out)?;
+    Ok(out)
+}
+
+/// Sellable quantity: stock for a normal item, the number of complete kits buildable for a bundle
+pub fn get_available_quantity(conn: &Connection, sku: &str) -> Result<f64, AppError> {
+    let per_bundle = expand_stock_movements(conn, sku, 1.0)?;
+    let is_single = per_bundle.len() == 1 && per_bundle[0].0 == sku;
+
+    let mut available: Option<f64> = None;
+    for (component, needed) in per_bundle {
+        let on_hand: f64 = conn
+            .query_row(
+                "SELECT quantity FROM inventory_items WHERE sku = ?1",
+                params![&component],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(0.0);
+        let buildable = if is_single { on_hand } else { (on_hand.max(0.0) / needed).floor() };
+        available = Some(available.map_or(buildable, |a| a.min(buildable)));
+    }
+    Ok(available.unwrap_or(0.0))
+}
+
+/// Components of a bundle with names and current stock
+pub fn get_bundle_components(conn: &Connection, parent_sku: &str) -> Result<Vec<ProductComponent>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT pc.parent_sku, pc.component_sku, i.name, pc.quantity, COALESCE(i.quantity, 0)
+         FROM product_components pc
+         LEFT JOIN inventory_items i ON i.sku = pc.component_sku
+         WHERE pc.parent_sku = ?1
+         ORDER BY pc.component_sku",
+    )?;
+    let components = stmt
+        .query_map(params![parent_sku], |row| {
+            Ok(ProductComponent {
+                parent_sku: row.get(0)?,
+                component_sku: row.get(1)?,
+                component_name: row.get(2)?,
+                quantity: row.get(3)?,
+                component_stock: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(components)
+}
+
+/// All bundles with their buildable quantity
+pub fn get_bundles(conn: &Connection) -> Result<Vec<BundleSummary>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT pc.parent_sku, i.name, COUNT(*)
+         FROM product_components pc
+         LEFT JOIN inventory_items i ON i.sku = pc.parent_sku
+         GROUP BY pc.parent_sku
+         ORDER BY i.name",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut bundles = Vec::with_capacity(rows.len());
+    for (sku, name, component_count) in rows {
+        let available_quantity = get_available_quantity(conn, &sku)?;
+        bundles.push(BundleSummary {
+            sku,
+            name,
+            component_count,
+            available_quantity,
+        });
+    }
+    Ok(bundles)
+}
+
+/// Replace a bundle's components; an empty list turns the SKU back into a normal item
+pub fn set_bundle_components(
+    conn: &mut Connection,
+    parent_sku: &str,
+    components: &[BundleComponentInput],
+) -> Result<(), AppError> {
+    let unit = |conn: &Connection, sku: &str| -> Result<Option<String>, AppError> {
+        Ok(conn
+            .query_row("SELECT unit FROM inventory_items WHERE sku = ?1", params![sku], |row| row.get(0))
+            .optional()?)
+    };
+
+    if unit(conn, parent_sku)?.is_none() {
+        return Err(AppError::NotFound(format!("Set ürün bulunamadı: {}", parent_sku)));
+    }
+
+    let mut seen = HashSet::new();
+    for component in components {
+        if component.quantity.is_nan() || component.quantity <= 0.0 {
+            return Err(AppError::Validation(format!("Geçersiz bileşen miktarı: {}", component.component_sku)));
+        }
+        if !seen.insert(component.component_sku.as_str()) {
+            return Err(AppError::Validation(format!("Bileşen birden fazla kez eklendi: {}", component.component_sku)));
+        }
+        if component.component_sku == parent_sku || bundle_contains(conn, &component.component_sku, parent_sku)? {
+            return Err(AppError::Validation(format!(
+                "Döngüsel set tanımı: {} kendi bileşeni olamaz ({})",
+                parent_sku, component.component_sku
+            )));
+        }
+        let Some(component_unit) = unit(conn, &component.component_sku)? else {
+            return Err(AppError::NotFound(format!("Bileşen ürün bulunamadı: {}", component.component_sku)));
+        };
+        validate_quantity(component.quantity, &component_unit)
+            .map_err(|e| AppError::Validation(format!("{}: {}", component.component_sku, e)))?;
+    }
+
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM product_components WHERE parent_sku = ?1", params![parent_sku])?;
+    for component in components {
+        tx.execute(
+            "INSERT INTO product_components (parent_sku, component_sku, quantity) VALUES (?1, ?2, ?3)",
+            params![parent_sku, &component.component_sku, component.quantity],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (sku TEXT PRIMARY KEY, name TEXT, quantity INTEGER, unit TEXT NOT NULL DEFAULT 'ADET');
+             CREATE TABLE product_components (parent_sku TEXT NOT NULL, component_sku TEXT NOT NULL, quantity INTEGER NOT NULL, PRIMARY KEY (parent_sku, component_sku));
+             INSERT INTO inventory_items (sku, name, quantity) VALUES ('KIT', 'Tamir Seti', 0), ('CONTA', 'Conta', 10), ('BANT', 'Bant', 3), ('BIG', 'Büyük Set', 0);",
+        )
+        .unwrap();
+        let components = [
+            BundleComponentInput { component_sku: "CONTA".into(), quantity: 4.0 },
+            BundleComponentInput { component_sku: "BANT".into(), quantity: 1.0 },
+        ];
+        set_bundle_components(&mut conn, "KIT", &components).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_bundle_availability_and_expansion() {
+        let mut conn = setup();
+        // 10 / 4 = 2 kits (bant would allow 3)
+        assert_eq!(get_available_quantity(&conn, "KIT").unwrap(), 2.0);
+        assert_eq!(get_available_quantity(&conn, "BANT").unwrap(), 3.0);
+
+        let nested = [
+            BundleComponentInput { component_sku: "KIT".into(), quantity: 2.0 },
+            BundleComponentInput { component_sku: "CONTA".into(), quantity: 1.0 },
+        ];
+        set_bundle_components(&mut conn, "BIG", &nested).unwrap();
+        let moves = expand_stock_movements(&conn, "BIG", 1.0).unwrap();
+        assert_eq!(moves, vec![("CONTA".to_string(), 9.0), ("BANT".to_string(), 2.0)]);
+    }
+
+    #[test]
+    fn test_circular_bundle_rejected() {
+        let mut conn = setup();
+        let self_ref = [BundleComponentInput { component_sku: "KIT".into(), quantity: 1.0 }];
+        assert!(set_bundle_components(&mut conn, "KIT", &self_ref).is_err());
+
+        set_bundle_components(&mut conn, "BIG", &self_ref).unwrap();
+        let cycle = [BundleComponentInput { component_sku: "BIG".into(), quantity: 1.0 }];
+        assert!(set_bundle_components(&mut conn, "KIT", &cycle).is_err());
+    }
+
+    #[test]
+    fn test_fractional_component_needs_measured_unit() {
+        let mut conn = setup();
+        conn.execute("INSERT INTO inventory_items VALUES ('KABLO', 'Kablo', 5, 'MT')", []).unwrap();
+        let half = [BundleComponentInput { component_sku: "CONTA".into(), quantity: 0.5 }];
+        assert!(set_bundle_components(&mut conn, "BIG", &half).is_err());
+
+        let cable = [BundleComponentInput { component_sku: "KABLO".into(), quantity: 1.5 }];
+        set_bundle_components(&mut conn, "BIG", &cable).unwrap();
+        assert_eq!(get_bundle_components(&conn, "BIG").unwrap()[0].quantity, 1.5);
+        assert_eq!(get_available_quantity(&conn, "BIG").unwrap(), 3.0);
+    }
+}
@@ -0,0 +1,304 @@
+//! Categories Module
+//!
+//! `categories` tablosu `parent_id` ile ağaç kurar (HIRDAVAT > Tornavida). Ağaç burada
+//! kurulur; bozuk kayıtlardan kalan döngüler ağacı kilitlemesin diye döngüdeki ilk kategori
+//! kök kabul edilir, taşıma ise döngü oluşturacaksa reddedilir. Dolu bir kategori ancak alt
+//! kategorileri ve stok kartları başka bir kategoriye aktarılarak silinir.
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::models::{Category, CategoryNode};
+
+/// Separator of the names in a category's full path
+pub const PATH_SEPARATOR: &str = " > ";
+
+fn load_categories(conn: &Connection) -> Result<Vec<Category>, AppError> {
+    let mut stmt = conn.prepare("SELECT id, name, parent_id, created_at FROM categories ORDER BY name, id")?;
+    let categories = stmt
+        .query_map([], |row| {
+            Ok(Category {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(categories)
+}
+
+/// Nested categories by name, each with its full path and the number of stock cards filed
+/// directly under it. A parent that no longer exists makes the category a root; so does being
+/// the first (by name) of a cycle, which breaks the cycle there.
+pub fn get_category_tree(conn: &Connection) -> Result<Vec<CategoryNode>, AppError> {
+    let categories = load_categories(conn)?;
+    let counts: HashMap<String, i64> = {
+        let mut stmt = conn.prepare(
+            "SELECT category_id, COUNT(*) FROM stock_cards WHERE category_id IS NOT NULL GROUP BY category_id",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let ids: HashSet<&str> = categories.iter().map(|c| c.id.as_str()).collect();
+    let mut children: HashMap<&str, Vec<&Category>> = HashMap::new();
+    for category in &categories {
+        if let Some(parent) = category.parent_id.as_deref().filter(|p| ids.contains(p)) {
+            children.entry(parent).or_default().push(category);
+        }
+    }
+
+    let mut placed = HashSet::new();
+    let mut roots = Vec::new();
+    let is_root = |c: &Category| c.parent_id.as_deref().is_none_or(|p| !ids.contains(p));
+    for category in categories.iter().filter(|c| is_root(c)) {
+        roots.push(build_node(category, None, &children, &counts, &mut placed));
+    }
+    // Whatever is left hangs off a cycle; start each one at its first category
+    for category in &categories {
+        if !placed.contains(category.id.as_str()) {
+            roots.push(build_node(category, None, &children, &counts, &mut placed));
+        }
+    }
+    roots.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+    Ok(roots)
+}
+
+fn build_node<'a>(
+    category: &'a Category,
+    parent_path: Option<&str>,
+    children: &HashMap<&str, Vec<&'a Category>>,
+    counts: &HashMap<String, i64>,
+    placed: &mut HashSet<&'a str>,
+) -> CategoryNode {
+    placed.insert(category.id.as_str());
+    let path = match parent_path {
+        Some(parent_path) => format!("{}{}{}", parent_path, PATH_SEPARATOR, category.name),
+        None => category.name.clone(),
+    };
+
+    let mut nodes = Vec::new();
+    for child in children.get(category.id.as_str()).into_iter().flatten() {
+        // A child already placed closes a cycle back to this branch
+        if !placed.contains(child.id.as_str()) {
+            nodes.push(build_node(child, Some(&path), children, counts, placed));
+        }
+    }
+
+    CategoryNode {
+        id: category.id.clone(),
+        name: category.name.clone(),
+        parent_id: category.parent_id.clone(),
+        path,
+        item_count: counts.get(&category.id).copied().unwrap_or(0),
+        children: nodes,
+    }
+}
+
+fn category_exists(conn: &Connection, id: &str) -> Result<bool, AppError> {
+    Ok(conn
+        .query_row("SELECT 1 FROM categories WHERE id = ?1", params![id], |_| Ok(()))
+        .optional()?
+        .is_some())
+}
+
+/// Whether `id` is `ancestor` or lies somewhere below it. Walks up the parents; the visited set
+/// stops the walk on a cycle already in the data.
+fn is_within(conn: &Connection, id: &str, ancestor: &str) -> Result<bool, AppError> {
+    let mut visited = HashSet::new();
+    let mut current = Some(id.to_string());
+    while let Some(category) = current {
+        if category == ancestor {
+            return Ok(true);
+        }
+        if !visited.insert(category.clone()) {
+            break;
+        }
+        current = conn
+            .query_row("SELECT parent_id FROM categories WHERE id = ?1", params![&category], |row| row.get(0))
+            .optional()?
+            .flatten();
+    }
+    Ok(false)
+}
+
+/// Put a category under `new_parent_id`, or at the top level when None. Moving it under itself
+/// or one of its own descendants would make a cycle and is rejected.
+pub fn move_category(conn: &Connection, id: &str, new_parent_id: Option<&str>) -> Result<(), AppError> {
+    if !category_exists(conn, id)? {
+        return Err(AppError::NotFound(format!("Kategori bulunamadı: {}", id)));
+    }
+    let new_parent_id = new_parent_id.filter(|p| !p.trim().is_empty());
+
+    if let Some(parent) = new_parent_id {
+        if !category_exists(conn, parent)? {
+            return Err(AppError::NotFound(format!("Üst kategori bulunamadı: {}", parent)));
+        }
+        if is_within(conn, parent, id)? {
+            return Err(AppError::Validation(
+                "Kategori kendisinin veya bir alt kategorisinin altına taşınamaz".to_string(),
+            ));
+        }
+    }
+
+    conn.execute(
+        "UPDATE categories SET parent_id = ?1 WHERE id = ?2",
+        params![new_parent_id, id],
+    )?;
+    Ok(())
+}
+
+/// Stock cards filed directly under the category
+pub fn stock_card_count(conn: &Connection, id: &str) -> Result<i64, AppError> {
+    Ok(conn.query_row("SELECT COUNT(*) FROM stock_cards WHERE category_id = ?1", params![id], |row| row.get(0))?)
+}
+
+/// Delete a category. One with subcategories or stock cards needs `reassign_to`: its direct
+/// subcategories and stock cards move there first, so nothing is orphaned or cascade-deleted.
+/// The target may not be the category itself or lie below it.
+pub fn delete_category(conn: &Connection, id: &str, reassign_to: Option<&str>) -> Result<(), AppError> {
+    if !category_exists(conn, id)? {
+        return Err(AppError::NotFound(format!("Kategori bulunamadı: {}", id)));
+    }
+    let tx = conn.unchecked_transaction()?;
+    let cards = stock_card_count(&tx, id)?;
+    let children: i64 = tx.query_row("SELECT COUNT(*) FROM categories WHERE parent_id = ?1", params![id], |row| row.get(0))?;
+    if cards > 0 || children > 0 {
+        let Some(target) = reassign_to.filter(|t| !t.trim().is_empty()) else {
+            return Err(AppError::Validation(format!(
+                "Kategori boş değil: {} stok kartı, {} alt kategori. Silmek için aktarılacak kategoriyi seçin",
+                cards, children
+            )));
+        };
+        if !category_exists(&tx, target)? {
+            return Err(AppError::NotFound(format!("Aktarılacak kategori bulunamadı: {}", target)));
+        }
+        if is_within(&tx, target, id)? {
+            return Err(AppError::Validation(
+                "Kategori kendisine veya bir alt kategorisine aktarılamaz".to_string(),
+            ));
+        }
+
+        tx.execute("UPDATE categories SET parent_id = ?1 WHERE parent_id = ?2", params![target, id])?;
+        tx.execute(
+            "UPDATE stock_cards SET category_id = ?1, updated_at = ?2 WHERE category_id = ?3",
+            params![target, chrono::Utc::now().to_rfc3339(), id],
+        )?;
+    }
+    tx.execute("DELETE FROM categories WHERE id = ?1", params![id])?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO categories (id, name, parent_id, created_at) VALUES
+                ('hirdavat', 'HIRDAVAT', NULL, 'now'),
+                ('tornavida', 'Tornavida', 'hirdavat', 'now'),
+                ('yildiz', 'Yıldız', 'tornavida', 'now'),
+                ('boya', 'BOYA', NULL, 'now');
+             INSERT INTO stock_cards (id, barcode, name, category_id, created_at, updated_at) VALUES
+                ('s1', 'B1', 'Düz tornavida', 'tornavida', 'now', 'now'),
+                ('s2', 'B2', 'Yıldız tornavida', 'yildiz', 'now', 'now'),
+                ('s3', 'B3', 'Tornavida seti', 'tornavida', 'now', 'now'),
+                ('s4', 'B4', 'Plastik boya', 'boya', 'now', 'now');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_category_tree_paths_and_counts() {
+        let conn = setup();
+        let tree = get_category_tree(&conn).unwrap();
+
+        assert_eq!(tree.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), vec!["BOYA", "HIRDAVAT"]);
+        let hirdavat = &tree[1];
+        // Counts are direct: the HIRDAVAT node itself has no cards
+        assert_eq!((hirdavat.path.as_str(), hirdavat.item_count), ("HIRDAVAT", 0));
+        let tornavida = &hirdavat.children[0];
+        assert_eq!((tornavida.path.as_str(), tornavida.item_count), ("HIRDAVAT > Tornavida", 2));
+        let yildiz = &tornavida.children[0];
+        assert_eq!((yildiz.path.as_str(), yildiz.item_count), ("HIRDAVAT > Tornavida > Yıldız", 1));
+        assert_eq!(tree[0].item_count, 1);
+    }
+
+    #[test]
+    fn test_move_category_rejects_cycle() {
+        let conn = setup();
+        assert!(matches!(move_category(&conn, "hirdavat", Some("yildiz")), Err(AppError::Validation(_))));
+        assert!(move_category(&conn, "tornavida", Some("tornavida")).is_err());
+        assert!(matches!(move_category(&conn, "tornavida", Some("yok")), Err(AppError::NotFound(_))));
+
+        move_category(&conn, "tornavida", Some("boya")).unwrap();
+        let tree = get_category_tree(&conn).unwrap();
+        assert_eq!(tree[0].children[0].children[0].path, "BOYA > Tornavida > Yıldız");
+        move_category(&conn, "tornavida", None).unwrap();
+        assert_eq!(get_category_tree(&conn).unwrap().len(), 3);
+
+        // A cycle already in the data is broken at its first category instead of hiding both
+        conn.execute_batch(
+            "UPDATE categories SET parent_id = 'yildiz' WHERE id = 'tornavida';",
+        )
+        .unwrap();
+        let tree = get_category_tree(&conn).unwrap();
+        let tornavida = tree.iter().find(|n| n.id == "tornavida").unwrap();
+        assert_eq!(tornavida.children[0].path, "Tornavida > Yıldız");
+        assert!(tornavida.children[0].children.is_empty());
+    }
+
+    fn card_category(conn: &Connection, id: &str) -> Option<String> {
+        conn.query_row("SELECT category_id FROM stock_cards WHERE id = ?1", params![id], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_delete_empty_category() {
+        let conn = setup();
+        conn.execute("INSERT INTO categories (id, name, parent_id, created_at) VALUES ('bos', 'Boş', 'hirdavat', 'now')", []).unwrap();
+
+        delete_category(&conn, "bos", None).unwrap();
+        assert!(!category_exists(&conn, "bos").unwrap());
+        assert!(matches!(delete_category(&conn, "bos", None), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_delete_category_with_items_needs_target() {
+        let conn = setup();
+        let err = delete_category(&conn, "tornavida", None).unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+        assert!(err.to_string().contains("2 stok kartı, 1 alt kategori"), "{}", err);
+        // Nothing moved or cascaded away
+        assert!(category_exists(&conn, "yildiz").unwrap());
+        assert_eq!(card_category(&conn, "s1").as_deref(), Some("tornavida"));
+
+        // The target may not be inside the deleted branch
+        assert!(delete_category(&conn, "tornavida", Some("yildiz")).is_err());
+        assert!(delete_category(&conn, "tornavida", Some("tornavida")).is_err());
+        assert!(matches!(delete_category(&conn, "tornavida", Some("yok")), Err(AppError::NotFound(_))));
+        assert!(category_exists(&conn, "tornavida").unwrap());
+    }
+
+    #[test]
+    fn test_delete_category_reassigns_to_target() {
+        let conn = setup();
+        delete_category(&conn, "tornavida", Some("hirdavat")).unwrap();
+
+        assert!(!category_exists(&conn, "tornavida").unwrap());
+        assert_eq!(card_category(&conn, "s1").as_deref(), Some("hirdavat"));
+        assert_eq!(card_category(&conn, "s3").as_deref(), Some("hirdavat"));
+        assert_eq!(stock_card_count(&conn, "hirdavat").unwrap(), 2);
+        let tree = get_category_tree(&conn).unwrap();
+        let hirdavat = tree.iter().find(|n| n.id == "hirdavat").unwrap();
+        assert_eq!(hirdavat.children[0].path, "HIRDAVAT > Yıldız");
+        assert_eq!(hirdavat.children[0].item_count, 1);
+    }
+}
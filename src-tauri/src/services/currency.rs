@@ -0,0 +1,264 @@
+//! Currency Service Module
+//!
+//! Ürün fiyatları kendi para biriminde tutulur (çoğunlukla TL, içe aktarılanlarda USD/EUR).
+//! Raporlar tutarları `exchange_rates` tablosundaki kurla TRY'ye çevirir. Kuru girilmemiş
+//! para birimi 1.0 ile çevrilir; eksik ya da 24 saatten eski kurlar uyarı olarak döner.
+
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+
+use crate::error::AppError;
+use crate::models::{CartItem, ExchangeRate};
+use crate::services::discounts;
+
+/// Currency every report is in
+pub const BASE_CURRENCY: &str = "TRY";
+
+/// Rates older than this are flagged as stale
+pub const STALE_RATE_HOURS: i64 = 24;
+
+/// Canonical code of a stored currency; "TL", "₺" and blank all mean TRY
+pub fn normalize_currency(currency: Option<&str>) -> String {
+    let code = currency.unwrap_or("").trim().to_uppercase();
+    match code.as_str() {
+        "" | "TL" | "₺" | "YTL" => BASE_CURRENCY.to_string(),
+        _ => code,
+    }
+}
+
+fn is_stale(updated_at: &str, now: DateTime<Utc>) -> bool {
+    DateTime::parse_from_rfc3339(updated_at)
+        .map(|t| now - t.with_timezone(&Utc) > Duration::hours(STALE_RATE_HOURS))
+        .unwrap_or(true)
+}
+
+/// Store the TRY value of one unit of `currency`
+pub fn set_exchange_rate(
+    conn: &Connection,
+    currency: &str,
+    rate_to_try: f64,
+    now: DateTime<Utc>,
+) -> Result<ExchangeRate, AppError> {
+    let currency = normalize_currency(Some(currency));
+    if currency == BASE_CURRENCY {
+        return Err(AppError::Validation("TRY kuru her zaman 1'dir".to_string()));
+    }
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(AppError::Validation(format!("Gecersiz para birimi: {}", currency)));
+    }
+    if !rate_to_try.is_finite() || rate_to_try <= 0.0 {
+        return Err(AppError::Validation(format!("Gecersiz kur: {}", rate_to_try)));
+    }
+
+    let updated_at = now.to_rfc3339();
+    conn.execute(
+        "INSERT OR REPLACE INTO exchange_rates (currency, rate_to_try, updated_at) VALUES (?1, ?2, ?3)",
+        params![&currency, rate_to_try, &updated_at],
+    )?;
+    Ok(ExchangeRate {
+        currency,
+        rate_to_try,
+        updated_at,
+        stale: false,
+    })
+}
+
+/// Every stored rate, alphabetically
+pub fn get_exchange_rates(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<ExchangeRate>, AppError> {
+    let mut stmt = conn.prepare_cached("SELECT currency, rate_to_try, updated_at FROM exchange_rates ORDER BY currency")?;
+    let rates = stmt
+        .query_map([], |row| {
+            let updated_at: String = row.get(2)?;
+            Ok(ExchangeRate {
+                currency: row.get(0)?,
+                rate_to_try: row.get(1)?,
+                stale: is_stale(&updated_at, now),
+                updated_at,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rates)
+}
+
+/// Stored rates, loaded once per report. Remembers which currencies it converted so the report
+/// can warn about missing or stale rates.
+pub struct RateTable {
+    rates: HashMap<String, ExchangeRate>,
+    used: BTreeSet<String>,
+}
+
+impl RateTable {
+    pub fn load(conn: &Connection, now: DateTime<Utc>) -> Result<Self, AppError> {
+        let rates = get_exchange_rates(conn, now)?
+            .into_iter()
+            .map(|rate| (rate.currency.clone(), rate))
+            .collect();
+        Ok(Self { rates, used: BTreeSet::new() })
+    }
+
+    /// TRY value of one unit of `currency`; 1.0 when no rate was entered
+    pub fn rate(&mut self, currency: Option<&str>) -> f64 {
+        let currency = normalize_currency(currency);
+        if currency == BASE_CURRENCY {
+            return 1.0;
+        }
+        let rate = self.rates.get(&currency).map_or(1.0, |r| r.rate_to_try);
+        self.used.insert(currency);
+        rate
+    }
+
+    pub fn convert_to_base(&mut self, amount: f64, currency: Option<&str>) -> f64 {
+        amount * self.rate(currency)
+    }
+
+    /// TRY value of a sale or return total whose lines may be priced in other currencies.
+    ///
+    /// `item_currencies` maps the SKUs of foreign-priced items to their currency; lines of other
+    /// items are already in TRY. Uses today's rate, not the one on the day of the sale.
+    pub fn transaction_total_to_base(
+        &mut self,
+        total: f64,
+        items: &[CartItem],
+        item_currencies: &HashMap<String, String>,
+    ) -> f64 {
+        let mut extra = 0.0;
+        for line in items {
+            if let Some(currency) = item_currencies.get(&line.sku) {
+                let amount = discounts::line_net_total(line);
+                extra += self.convert_to_base(amount, Some(currency)) - amount;
+            }
+        }
+        // Returns are stored with a negative total
+        if total < 0.0 {
+            total - extra
+        } else {
+            total + extra
+        }
+    }
+
+    /// Missing or stale rates of the currencies converted so far
+    pub fn warnings(&self) -> Vec<String> {
+        self.used
+            .iter()
+            .filter_map(|currency| match self.rates.get(currency) {
+                None => Some(format!("{} kuru girilmemis, 1.0 kabul edildi", currency)),
+                Some(rate) if rate.stale => Some(format!(
+                    "{} kuru {} saatten eski ({})",
+                    currency, STALE_RATE_HOURS, rate.updated_at
+                )),
+                Some(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Currency of every item not priced in TRY, by SKU (deleted items too, for old sales)
+pub fn foreign_item_currencies(conn: &Connection) -> Result<HashMap<String, String>, AppError> {
+    let mut stmt = conn.prepare_cached("SELECT sku, currency FROM inventory_items WHERE currency IS NOT NULL")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+    let mut currencies = HashMap::new();
+    for row in rows {
+        let (sku, currency) = row?;
+        let currency = normalize_currency(Some(&currency));
+        if currency != BASE_CURRENCY {
+            currencies.insert(sku, currency);
+        }
+    }
+    Ok(currencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::inventory;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE exchange_rates (currency TEXT PRIMARY KEY, rate_to_try REAL NOT NULL, updated_at TEXT NOT NULL);
+             CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT);
+             INSERT INTO settings VALUES ('timezone_offset_minutes', '0');
+             CREATE TABLE inventory_items (
+                 sku TEXT PRIMARY KEY, category TEXT, quantity INTEGER, price REAL, currency TEXT,
+                 min_stock_level INTEGER DEFAULT 5, deleted_at TEXT
+             );
+             INSERT INTO inventory_items (sku, category, quantity, price, currency) VALUES
+                 ('MATKAP', 'Elektrikli', 2, 100.0, 'USD'),
+                 ('TORNAVIDA', 'El Aleti', 10, 50.0, 'TL'),
+                 ('PENSE', 'El Aleti', 4, 25.0, NULL);
+             CREATE TABLE transactions (
+                 id TEXT PRIMARY KEY, items TEXT, total REAL, transaction_type TEXT, status TEXT, created_at TEXT
+             );",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn line(sku: &str, qty: i32, price: f64) -> CartItem {
+        CartItem {
+            id: sku.to_string(),
+            sku: sku.to_string(),
+            name: sku.to_string(),
+            cart_quantity: qty.into(),
+            price,
+            cost_price: None,
+            tax_rate: 20.0,
+            discount_amount: None,
+            discount_percent: None,
+        }
+    }
+
+    #[test]
+    fn test_usd_item_converted_into_try_totals() {
+        let conn = setup_db();
+        let now = Utc::now();
+        set_exchange_rate(&conn, "usd", 32.5, now).unwrap();
+        let mut rates = RateTable::load(&conn, now).unwrap();
+        assert_eq!(rates.convert_to_base(10.0, Some("USD")), 325.0);
+        assert_eq!(rates.convert_to_base(10.0, Some("TL")), 10.0);
+
+        let stats = inventory::get_category_stats(&conn).unwrap();
+        let value = |category: &str| stats.iter().find(|s| s.category == category).unwrap().total_value;
+        assert_eq!(value("Elektrikli"), 2.0 * 100.0 * 32.5);
+        assert_eq!(value("El Aleti"), 10.0 * 50.0 + 4.0 * 25.0);
+        assert_eq!(stats[0].category, "Elektrikli");
+
+        // One drill (100 USD) and one screwdriver (50 TL) sold, the drill returned
+        let today = now.format("%Y-%m-%d").to_string();
+        for (id, items, total, transaction_type) in [
+            ("t1", vec![line("MATKAP", 1, 100.0), line("TORNAVIDA", 1, 50.0)], 150.0, "SALE"),
+            ("t2", vec![line("MATKAP", 1, 100.0)], -100.0, "RETURN"),
+        ] {
+            conn.execute(
+                "INSERT INTO transactions VALUES (?1, ?2, ?3, ?4, 'completed', ?5)",
+                params![id, serde_json::to_string(&items).unwrap(), total, transaction_type, now.to_rfc3339()],
+            )
+            .unwrap();
+        }
+        let dashboard = inventory::get_dashboard_stats(&conn, Some(&today), Some(&today)).unwrap();
+        assert_eq!(dashboard.average_basket, 3250.0 + 50.0);
+        assert_eq!(dashboard.total_revenue, 50.0);
+        assert!(dashboard.currency_warnings.is_empty());
+    }
+
+    #[test]
+    fn test_missing_and_stale_rates_warn() {
+        let conn = setup_db();
+        let now = Utc::now();
+        set_exchange_rate(&conn, "EUR", 35.0, now - Duration::hours(30)).unwrap();
+        assert!(set_exchange_rate(&conn, "TL", 2.0, now).is_err());
+        assert!(set_exchange_rate(&conn, "USD", 0.0, now).is_err());
+
+        let mut rates = RateTable::load(&conn, now).unwrap();
+        assert_eq!(rates.convert_to_base(10.0, Some("EUR")), 350.0);
+        assert_eq!(rates.convert_to_base(10.0, Some("USD")), 10.0);
+        assert_eq!(rates.convert_to_base(10.0, Some("TL")), 10.0);
+        let warnings = rates.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("EUR kuru 24 saatten eski"));
+        assert!(warnings[1].starts_with("USD kuru girilmemis"));
+    }
+}
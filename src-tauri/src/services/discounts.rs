@@ -0,0 +1,89 @@
+//! Discounts Module
+//!
+//! Pazarlıkla verilen indirimler. Satır indirimi (tutar veya yüzde) satırın kendi tutarından,
+//! fiş indirimi ise satır indirimlerinden sonra kalan toplamdan düşülür: %10 fiş indirimi
+//! zaten indirilmiş satırlara uygulanır, iki indirim liste fiyatı üzerinden toplanmaz.
+//! Orijinal birim fiyat satırda kalır, indirim ayrı tutulur.
+
+use crate::error::AppError;
+use crate::models::{CartItem, SaleDiscount};
+use crate::services::tax::round_money;
+
+/// Discount in money on `base`, from either a fixed amount or a percent of `base`.
+/// Zero counts as not given; both at once, a negative amount, a percent outside 0-100 or
+/// more than `base` is rejected.
+fn discount_on(amount: Option<f64>, percent: Option<f64>, base: f64, label: &str) -> Result<f64, AppError> {
+    let amount = amount.filter(|a| *a != 0.0);
+    let percent = percent.filter(|p| *p != 0.0);
+
+    let discount = match (amount, percent) {
+        (None, None) => return Ok(0.0),
+        (Some(_), Some(_)) => {
+            return Err(AppError::Validation(format!(
+                "{}: tutar ve yüzde indirimi birlikte girilemez",
+                label
+            )))
+        }
+        (Some(amount), None) => {
+            if !amount.is_finite() || amount < 0.0 {
+                return Err(AppError::Validation(format!("{}: geçersiz indirim tutarı {}", label, amount)));
+            }
+            round_money(amount)
+        }
+        (None, Some(percent)) => {
+            if !percent.is_finite() || !(0.0..=100.0).contains(&percent) {
+                return Err(AppError::Validation(format!("{}: geçersiz indirim yüzdesi {}", label, percent)));
+            }
+            round_money(base * percent / 100.0)
+        }
+    };
+
+    if discount > round_money(base) {
+        return Err(AppError::Validation(format!(
+            "{}: indirim ({:.2}) tutarı ({:.2}) aşamaz",
+            label, discount, base
+        )));
+    }
+    Ok(discount)
+}
+
+/// Check a cart line's discount against its own price
+pub fn validate_line_discount(item: &CartItem) -> Result<(), AppError> {
+    discount_on(item.discount_amount, item.discount_percent, item.price * item.cart_quantity, &item.name).map(|_| ())
+}
+
+/// Discount of a line in money. Stored lines were validated at sale time; anything out of
+/// range is clamped so reports of older or hand-edited rows stay sane.
+pub fn line_discount(item: &CartItem) -> f64 {
+    let gross = item.price * item.cart_quantity;
+    if gross <= 0.0 {
+        return 0.0;
+    }
+    let discount = match (item.discount_amount, item.discount_percent) {
+        (Some(amount), _) if amount != 0.0 => amount,
+        (_, Some(percent)) => gross * percent / 100.0,
+        _ => 0.0,
+    };
+    round_money(discount.clamp(0.0, gross))
+}
+
+/// Line amount after its own discount, before the ticket discount
+pub fn line_net_total(item: &CartItem) -> f64 {
+    item.price * item.cart_quantity - line_discount(item)
+}
+
+/// Ticket discount in money on `subtotal`, the lines' total after their own discounts
+pub fn ticket_discount(discount: &SaleDiscount, subtotal: f64) -> Result<f64, AppError> {
+    discount_on(discount.amount, discount.percent, subtotal.max(0.0), "Fiş indirimi")
+}
+
+/// Spread a ticket discount over `(rate, amount)` lines in proportion to their amounts, so the
+/// KDV of each rate is worked out on what was actually charged. Returns pass both negative.
+pub fn spread_ticket_discount(lines: Vec<(f64, f64)>, discount: f64) -> Vec<(f64, f64)> {
+    let subtotal: f64 = lines.iter().map(|(_, amount)| amount).sum();
+    if discount == 0.0 || subtotal == 0.0 {
+        return lines;
+    }
+    let factor = ((subtotal - discount) / subtotal).clamp(0.0, 1.0);
+    lines.into_iter().map(|(rate, amount)| (rate, amount * factor)).collect()
+}
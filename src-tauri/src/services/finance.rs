@@ -0,0 +1,719 @@
+//! Finance Service Module
+//!
+//! Ödeme yöntemlerinin tek bir yazıma indirgenmesi ve gün sonu (Z raporu).
+//! Eski kayıtlarda aynı yöntem farklı yazımlarla bulunur (`NAKIT`, `Nakit`, `CASH`...);
+//! yazarken kanonik koda çevrilir, okurken `PaymentMethod` ile gruplanır.
+//! Fatura numaraları `invoice_counters` sayacından atomik olarak verilir.
+
+use std::convert::Infallible;
+use std::str::FromStr;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+
+use crate::error::AppError;
+use crate::models::{FinanceRecord, Payment, PaymentBreakdown, ZReport, TRANSACTION_STATUS_VOIDED};
+
+/// Finance record categories written next to a COLLECTION transaction by `record_account_payment`.
+/// Totals count the transaction, so these records are left out of them.
+pub const ACCOUNT_PAYMENT_CATEGORIES: [&str; 2] = ["CARI_TAHSILAT", "CARI_ODEME"];
+
+/// SQL condition on a transactions row aliased `t` joined to current_accounts `a`: a COLLECTION on a
+/// supplier account is a payment we made, i.e. money out
+pub const SUPPLIER_PAYMENT_SQL: &str = "t.transaction_type = 'COLLECTION' AND a.account_type = 'SUPPLIER'";
+
+/// Finance record category of the refund written by `process_partial_return`.
+/// Totals count the RETURN transaction, so these records are left out of them too.
+pub const SALE_RETURN_CATEGORY: &str = "SATIS_IADE";
+
+/// Finance record category of a goods receipt paid to a supplier (`process_goods_receipt`)
+pub const GOODS_RECEIPT_CATEGORY: &str = "MAL_KABUL";
+
+/// `category NOT IN (...)` for `ACCOUNT_PAYMENT_CATEGORIES` and `SALE_RETURN_CATEGORY`
+pub fn not_account_payment_sql() -> String {
+    format!(
+        "category NOT IN ({})",
+        ACCOUNT_PAYMENT_CATEGORIES
+            .iter()
+            .chain([&SALE_RETURN_CATEGORY])
+            .map(|c| format!("'{}'", c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// `payment_method` of a transaction paid with more than one method; the portions are in
+/// `transaction_payments`
+pub const SPLIT_PAYMENT_METHOD: &str = "SPLIT";
+
+/// `transactions` with split-paid rows expanded to one row per portion, for grouping by payment
+/// method. Same column names as `transactions` (`total` is the portion, signed like the total) plus
+/// `counted`: 1 on one row per transaction, so `SUM(counted)` counts transactions.
+pub const PAYMENT_LINES_SQL: &str = "(
+    SELECT id, transaction_type, status, created_at, customer_id, payment_method, total, 1 AS counted
+    FROM transactions WHERE COALESCE(payment_method, '') != 'SPLIT'
+    UNION ALL
+    SELECT t.id, t.transaction_type, t.status, t.created_at, t.customer_id, p.method,
+           CASE WHEN t.total < 0 THEN -p.amount ELSE p.amount END, p.position = 0
+    FROM transactions t JOIN transaction_payments p ON p.transaction_id = t.id
+    WHERE t.payment_method = 'SPLIT'
+)";
+
+/// How a payment settles, for balances and reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentMethod {
+    Cash,
+    /// Credit card and mail order
+    Card,
+    /// Bank transfer (havale/EFT) and debit card
+    Bank,
+    /// Veresiye / vadeli: on the customer's or supplier's account
+    Credit,
+    Other,
+}
+
+/// Canonical code for a spelling key, see `spelling_key`
+fn canonical_code(key: &str) -> Option<&'static str> {
+    Some(match key {
+        "NAKIT" | "CASH" | "PESIN" => "NAKIT",
+        "KREDI_KARTI" | "KREDI_KART" | "KREDIKARTI" | "KART" | "CARD" | "CREDIT_CARD" | "POS" => "KREDI_KARTI",
+        "MAIL_ORDER" | "MAILORDER" => "MAIL_ORDER",
+        "HAVALE" | "EFT" | "HAVALE_EFT" | "BANKA" | "BANK" | "BANK_TRANSFER" | "TRANSFER" => "HAVALE",
+        "BANKA_KARTI" | "DEBIT_CARD" => "BANKA_KARTI",
+        "VERESIYE" | "VADELI" | "CREDIT" | "ACIK_HESAP" => "VERESIYE",
+        _ => return None,
+    })
+}
+
+/// Upper-case ASCII form: Turkish letters folded, spaces, hyphens and slashes as `_`
+fn spelling_key(method: &str) -> String {
+    method
+        .trim()
+        .chars()
+        .map(|c| match c {
+            'ı' | 'i' | 'İ' => 'I',
+            'ş' | 'Ş' => 'S',
+            'ç' | 'Ç' => 'C',
+            'ğ' | 'Ğ' => 'G',
+            'ö' | 'Ö' => 'O',
+            'ü' | 'Ü' => 'U',
+            ' ' | '-' | '/' => '_',
+            c => c.to_ascii_uppercase(),
+        })
+        .collect()
+}
+
+/// Method and optional detail of `Kredi Kartı (Ziraat)`-style values
+fn split_detail(method: &str) -> (&str, Option<&str>) {
+    let method = method.trim();
+    match method.find('(') {
+        Some(open) if method.ends_with(')') => {
+            (method[..open].trim(), Some(method[open + 1..method.len() - 1].trim()))
+        }
+        _ => (method, None),
+    }
+}
+
+/// Canonical spelling to store: `NAKIT`, `KREDI_KARTI`, `MAIL_ORDER`, `HAVALE`, `BANKA_KARTI` or
+/// `VERESIYE`, keeping a bank detail (`KREDI_KARTI (Ziraat)`). Unknown methods are only trimmed.
+pub fn canonical_payment_method(method: &str) -> String {
+    let (base, detail) = split_detail(method);
+    match (canonical_code(&spelling_key(base)), detail) {
+        (Some(code), Some(detail)) if !detail.is_empty() => format!("{} ({})", code, detail),
+        (Some(code), _) => code.to_string(),
+        (None, _) => method.trim().to_string(),
+    }
+}
+
+impl FromStr for PaymentMethod {
+    type Err = Infallible;
+
+    /// Any known spelling, canonical or not; never fails (unknown methods are `Other`)
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        let (base, _) = split_detail(method);
+        Ok(match canonical_code(&spelling_key(base)) {
+            Some("NAKIT") => PaymentMethod::Cash,
+            Some("KREDI_KARTI") | Some("MAIL_ORDER") => PaymentMethod::Card,
+            Some("HAVALE") | Some("BANKA_KARTI") => PaymentMethod::Bank,
+            Some("VERESIYE") => PaymentMethod::Credit,
+            _ => PaymentMethod::Other,
+        })
+    }
+}
+
+impl PaymentMethod {
+    pub fn parse(method: &str) -> Self {
+        match method.parse() {
+            Ok(method) => method,
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl PaymentBreakdown {
+    pub fn add(&mut self, method: PaymentMethod, amount: f64) {
+        match method {
+            PaymentMethod::Cash => self.cash += amount,
+            PaymentMethod::Card => self.card += amount,
+            PaymentMethod::Bank => self.bank += amount,
+            PaymentMethod::Credit => self.credit += amount,
+            PaymentMethod::Other => self.other += amount,
+        }
+    }
+}
+
+/// Check the portions of a split payment against the transaction total (positive) and return them
+/// with canonical method spellings. Veresiye cannot be part of a split: such a sale goes on the
+/// account as a whole.
+pub fn validate_split_payments(payments: &[Payment], total: f64) -> Result<Vec<Payment>, AppError> {
+    let mut validated = Vec::with_capacity(payments.len());
+    for payment in payments {
+        let method = canonical_payment_method(&payment.method);
+        if method.is_empty() || method == SPLIT_PAYMENT_METHOD {
+            return Err(AppError::Validation(format!("Geçersiz ödeme yöntemi: {}", payment.method)));
+        }
+        if PaymentMethod::parse(&method) == PaymentMethod::Credit {
+            return Err(AppError::Validation("Veresiye bölünmüş ödemede kullanılamaz".to_string()));
+        }
+        if !payment.amount.is_finite() || payment.amount <= 0.0 {
+            return Err(AppError::Validation(format!("Geçersiz ödeme tutarı: {} {}", method, payment.amount)));
+        }
+        validated.push(Payment { method, amount: round_money(payment.amount) });
+    }
+
+    let paid = round_money(validated.iter().map(|p| p.amount).sum());
+    if (paid - round_money(total)).abs() > 0.005 {
+        return Err(AppError::Validation(format!(
+            "Ödemelerin toplamı ({:.2}) işlem tutarına ({:.2}) eşit değil",
+            paid, total
+        )));
+    }
+    Ok(validated)
+}
+
+/// Store the portions of a split-paid transaction, in entry order
+pub fn save_transaction_payments(conn: &Connection, transaction_id: &str, payments: &[Payment]) -> Result<(), AppError> {
+    for (position, payment) in payments.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO transaction_payments (transaction_id, position, method, amount) VALUES (?1, ?2, ?3, ?4)",
+            params![transaction_id, position as i64, &payment.method, payment.amount],
+        )?;
+    }
+    Ok(())
+}
+
+/// Portions of a split-paid transaction in entry order (empty for single-method transactions)
+pub fn get_transaction_payments(conn: &Connection, transaction_id: &str) -> Result<Vec<Payment>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT method, amount FROM transaction_payments WHERE transaction_id = ?1 ORDER BY position",
+    )?;
+    let payments = stmt
+        .query_map(params![transaction_id], |row| Ok(Payment { method: row.get(0)?, amount: row.get(1)? }))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(payments)
+}
+
+/// Rewrite `column` of `table` to canonical spellings; returns the number of rows changed
+pub fn canonicalize_payment_methods(conn: &Connection, table: &str, column: &str) -> Result<usize, AppError> {
+    let methods: Vec<String> = {
+        let mut stmt = conn.prepare(&format!("SELECT DISTINCT {0} FROM {1} WHERE {0} IS NOT NULL", column, table))?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut changed = 0;
+    for method in methods {
+        let canonical = canonical_payment_method(&method);
+        if canonical != method {
+            changed += conn.execute(
+                &format!("UPDATE {0} SET {1} = ?1 WHERE {1} = ?2", table, column),
+                params![canonical, method],
+            )?;
+        }
+    }
+    Ok(changed)
+}
+
+/// All-time balance per payment method: income, sales and collections in; expenses, returns,
+/// purchases and supplier payments out (voided transactions are left out, split payments count per portion)
+pub fn payment_balances(conn: &Connection) -> Result<PaymentBreakdown, AppError> {
+    let mut balances = PaymentBreakdown::default();
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT payment_method, COALESCE(SUM(CASE WHEN record_type = 'INCOME' THEN amount ELSE -amount END), 0)
+         FROM finance_records WHERE {} GROUP BY payment_method",
+        not_account_payment_sql()
+    ))?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))?;
+    for row in rows {
+        let (method, amount) = row?;
+        balances.add(PaymentMethod::parse(&method), amount);
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT COALESCE(t.payment_method, ''), COALESCE(SUM(CASE
+             WHEN {} THEN -ABS(t.total)
+             WHEN t.transaction_type IN ('SALE', 'COLLECTION') THEN ABS(t.total)
+             WHEN t.transaction_type IN ('RETURN', 'EXPENSE', 'PURCHASE') THEN -ABS(t.total)
+             ELSE 0 END), 0)
+         FROM {} t LEFT JOIN current_accounts a ON a.id = t.customer_id
+         WHERE t.status != ?1 GROUP BY t.payment_method",
+        SUPPLIER_PAYMENT_SQL, PAYMENT_LINES_SQL
+    ))?;
+    let rows = stmt.query_map(params![TRANSACTION_STATUS_VOIDED], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+    })?;
+    for row in rows {
+        let (method, amount) = row?;
+        balances.add(PaymentMethod::parse(&method), amount);
+    }
+
+    for amount in [&mut balances.cash, &mut balances.card, &mut balances.bank, &mut balances.credit, &mut balances.other] {
+        *amount = round_money(*amount);
+    }
+    Ok(balances)
+}
+
+fn round_money(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// Aggregate one day's transactions and finance records (voided transactions are left out)
+pub fn build_z_report(conn: &Connection, date: &str) -> Result<ZReport, AppError> {
+    let mut report = ZReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        date: date.to_string(),
+        sale_count: 0,
+        gross_sales: 0.0,
+        sales_by_payment: PaymentBreakdown::default(),
+        return_count: 0,
+        returns_total: 0.0,
+        expense_total: 0.0,
+        other_income: 0.0,
+        purchases_total: 0.0,
+        supplier_payments: 0.0,
+        expected_cash: 0.0,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let mut cash_in = 0.0;
+    let mut cash_out = 0.0;
+
+    // Supplier payments are reported as PAYMENT
+    let mut stmt = conn.prepare(&format!(
+        "SELECT CASE WHEN {} THEN 'PAYMENT' ELSE t.transaction_type END AS kind,
+                COALESCE(t.payment_method, ''), SUM(t.counted), COALESCE(SUM(ABS(t.total)), 0)
+         FROM {} t LEFT JOIN current_accounts a ON a.id = t.customer_id
+         WHERE substr(t.created_at, 1, 10) = ?1 AND t.status != ?2
+         GROUP BY kind, t.payment_method",
+        SUPPLIER_PAYMENT_SQL, PAYMENT_LINES_SQL
+    ))?;
+    let rows = stmt.query_map(params![date, TRANSACTION_STATUS_VOIDED], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, f64>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (transaction_type, method, count, amount) = row?;
+        let method = PaymentMethod::parse(&method);
+        let is_cash = method == PaymentMethod::Cash;
+        match transaction_type.as_str() {
+            "SALE" => {
+                report.sale_count += count;
+                report.gross_sales += amount;
+                report.sales_by_payment.add(method, amount);
+            }
+            "RETURN" => {
+                report.return_count += count;
+                report.returns_total += amount;
+            }
+            "EXPENSE" => report.expense_total += amount,
+            "COLLECTION" => report.other_income += amount,
+            "PURCHASE" => report.purchases_total += amount,
+            "PAYMENT" => report.supplier_payments += amount,
+            _ => continue,
+        }
+        if is_cash {
+            match transaction_type.as_str() {
+                "SALE" | "COLLECTION" => cash_in += amount,
+                _ => cash_out += amount,
+            }
+        }
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT record_type, payment_method, COALESCE(SUM(amount), 0)
+         FROM finance_records WHERE date = ?1 AND {}
+         GROUP BY record_type, payment_method",
+        not_account_payment_sql()
+    ))?;
+    let rows = stmt.query_map(params![date], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?))
+    })?;
+    for row in rows {
+        let (record_type, method, amount) = row?;
+        let is_cash = PaymentMethod::parse(&method) == PaymentMethod::Cash;
+        if record_type == "INCOME" {
+            report.other_income += amount;
+            if is_cash {
+                cash_in += amount;
+            }
+        } else {
+            report.expense_total += amount;
+            if is_cash {
+                cash_out += amount;
+            }
+        }
+    }
+
+    report.gross_sales = round_money(report.gross_sales);
+    report.returns_total = round_money(report.returns_total);
+    report.expense_total = round_money(report.expense_total);
+    report.other_income = round_money(report.other_income);
+    report.purchases_total = round_money(report.purchases_total);
+    report.supplier_payments = round_money(report.supplier_payments);
+    report.expected_cash = round_money(cash_in - cash_out);
+    let sales = &mut report.sales_by_payment;
+    for amount in [&mut sales.cash, &mut sales.card, &mut sales.bank, &mut sales.credit, &mut sales.other] {
+        *amount = round_money(*amount);
+    }
+    Ok(report)
+}
+
+/// Close a day: build its Z report and store it. A day can only be closed once.
+pub fn generate_z_report(conn: &Connection, date: &str) -> Result<ZReport, AppError> {
+    let day = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| AppError::Validation(format!("Gecersiz tarih: {} (YYYY-AA-GG)", date)))?;
+    if day > chrono::Local::now().date_naive() {
+        return Err(AppError::Validation("Gelecek bir gun icin Z raporu alinamaz".to_string()));
+    }
+
+    let existing: Option<String> = conn
+        .query_row("SELECT id FROM z_reports WHERE report_date = ?1", params![date], |row| row.get(0))
+        .optional()?;
+    if existing.is_some() {
+        return Err(AppError::Conflict { field: "Z raporu".to_string(), value: date.to_string() });
+    }
+
+    let report = build_z_report(conn, date)?;
+    conn.execute(
+        "INSERT INTO z_reports (id, report_date, gross_sales, expected_cash, report, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            &report.id,
+            &report.date,
+            report.gross_sales,
+            report.expected_cash,
+            serde_json::to_string(&report)?,
+            &report.created_at,
+        ],
+    )?;
+    Ok(report)
+}
+
+/// Next invoice number `PREFIX-YEAR-00001` for `prefix` in `year`.
+/// The counter is bumped in a write transaction with one upsert, so concurrent callers always get
+/// distinct, increasing numbers. A new prefix/year continues after the highest number already used
+/// in finance record descriptions (numbers issued before the counter existed).
+pub fn next_invoice_number(conn: &Connection, prefix: &str, year: i32) -> Result<String, AppError> {
+    let prefix = prefix.trim();
+    if prefix.is_empty() || prefix.contains('-') || prefix.contains('%') || prefix.contains('_') {
+        return Err(AppError::Validation(format!("Gecersiz fatura oneki: {}", prefix)));
+    }
+
+    let number_start = format!("{}-{}-", prefix, year);
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    let seq: i64 = tx.query_row(
+        "INSERT INTO invoice_counters (prefix, year, last_seq)
+         VALUES (?1, ?2, COALESCE((
+             SELECT MAX(CAST(substr(description, ?3) AS INTEGER)) FROM finance_records WHERE description LIKE ?4
+         ), 0) + 1)
+         ON CONFLICT(prefix, year) DO UPDATE SET last_seq = last_seq + 1
+         RETURNING last_seq",
+        params![prefix, year, number_start.chars().count() as i64 + 1, format!("{}%", number_start)],
+        |row| row.get(0),
+    )?;
+    tx.commit()?;
+
+    Ok(format!("{}{:05}", number_start, seq))
+}
+
+/// Correct a finance record in place, keeping its id and `created_at`.
+/// The amount of a goods receipt record is what the supplier was paid, so it only changes with
+/// `force`; otherwise the supplier balance and the receipt would no longer match.
+pub fn update_finance_record(conn: &Connection, id: i64, record: &FinanceRecord, force: bool) -> Result<(), AppError> {
+    if record.record_type != "INCOME" && record.record_type != "EXPENSE" {
+        return Err(AppError::Validation(format!("Gecersiz kayit turu: {} (INCOME veya EXPENSE)", record.record_type)));
+    }
+    if !record.amount.is_finite() || record.amount < 0.0 {
+        return Err(AppError::Validation(format!("Gecersiz tutar: {}", record.amount)));
+    }
+
+    let existing: Option<(String, f64)> = conn
+        .query_row(
+            "SELECT category, amount FROM finance_records WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((category, amount)) = existing else {
+        return Err(AppError::NotFound(format!("Finans kaydi bulunamadi: {}", id)));
+    };
+    if category == GOODS_RECEIPT_CATEGORY && (record.amount - amount).abs() >= 0.005 && !force {
+        return Err(AppError::Validation(
+            "Mal kabul kaydinin tutari degistirilemez: tedarikci bakiyesi ile uyusmaz. Yine de degistirmek icin zorla secenegini kullanin".to_string(),
+        ));
+    }
+
+    conn.execute(
+        "UPDATE finance_records
+         SET record_type = ?1, category = ?2, amount = ?3, payment_method = ?4, description = ?5, date = ?6
+         WHERE id = ?7",
+        params![
+            record.record_type,
+            record.category,
+            record.amount,
+            canonical_payment_method(&record.payment_method),
+            record.description,
+            record.date,
+            id
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE transactions (
+                id TEXT PRIMARY KEY, items TEXT NOT NULL, total REAL NOT NULL, payment_method TEXT,
+                transaction_type TEXT, note TEXT, created_at TEXT NOT NULL, customer_id TEXT,
+                status TEXT NOT NULL DEFAULT 'completed'
+            );
+            CREATE TABLE transaction_payments (
+                transaction_id TEXT NOT NULL, position INTEGER NOT NULL, method TEXT NOT NULL, amount REAL NOT NULL,
+                PRIMARY KEY (transaction_id, position)
+            );
+            CREATE TABLE finance_records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, record_type TEXT NOT NULL, category TEXT NOT NULL,
+                amount REAL NOT NULL, payment_method TEXT NOT NULL DEFAULT 'NAKIT', description TEXT DEFAULT '',
+                date TEXT NOT NULL, created_at TEXT NOT NULL
+            );
+            CREATE TABLE current_accounts (id TEXT PRIMARY KEY, account_type TEXT NOT NULL DEFAULT 'CUSTOMER');
+            CREATE TABLE z_reports (
+                id TEXT PRIMARY KEY NOT NULL, report_date TEXT NOT NULL UNIQUE, gross_sales REAL NOT NULL,
+                expected_cash REAL NOT NULL, report TEXT NOT NULL, created_at TEXT NOT NULL
+            );
+            INSERT INTO transactions (id, items, total, payment_method, transaction_type, created_at, status) VALUES
+                ('s1', '[]', 150.0, 'NAKIT', 'SALE', '2024-03-01T09:00:00Z', 'completed'),
+                ('s2', '[]', 250.0, 'Kredi Kartı', 'SALE', '2024-03-01T11:00:00Z', 'completed'),
+                ('s3', '[]', 80.0, 'Nakit', 'SALE', '2024-03-01T15:00:00Z', 'completed'),
+                ('s4', '[]', 999.0, 'NAKIT', 'SALE', '2024-03-01T16:00:00Z', 'voided'),
+                ('s5', '[]', 500.0, 'NAKIT', 'SALE', '2024-03-02T09:00:00Z', 'completed'),
+                ('r1', '[]', -30.0, 'NAKIT', 'RETURN', '2024-03-01T17:00:00Z', 'completed'),
+                ('e1', '[]', -20.0, 'NAKIT', 'EXPENSE', '2024-03-01T18:00:00Z', 'completed');
+            INSERT INTO finance_records (record_type, category, amount, payment_method, date, created_at) VALUES
+                ('EXPENSE', 'Kira', 45.5, 'NAKIT', '2024-03-01', 'now'),
+                ('EXPENSE', 'Elektrik', 100.0, 'HAVALE', '2024-03-01', 'now');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_payment_method_spellings() {
+        let cases = [
+            ("NAKIT", "NAKIT", PaymentMethod::Cash),
+            ("Nakit", "NAKIT", PaymentMethod::Cash),
+            ("nakit", "NAKIT", PaymentMethod::Cash),
+            ("NAKİT", "NAKIT", PaymentMethod::Cash),
+            ("CASH", "NAKIT", PaymentMethod::Cash),
+            ("Peşin", "NAKIT", PaymentMethod::Cash),
+            ("KREDI_KARTI", "KREDI_KARTI", PaymentMethod::Card),
+            ("Kredi Kartı", "KREDI_KARTI", PaymentMethod::Card),
+            ("KREDİ KARTI", "KREDI_KARTI", PaymentMethod::Card),
+            ("Kredi Kartı (Ziraat)", "KREDI_KARTI (Ziraat)", PaymentMethod::Card),
+            ("CREDIT_CARD", "KREDI_KARTI", PaymentMethod::Card),
+            ("KART", "KREDI_KARTI", PaymentMethod::Card),
+            ("POS", "KREDI_KARTI", PaymentMethod::Card),
+            ("MAIL_ORDER", "MAIL_ORDER", PaymentMethod::Card),
+            ("MAIL ORDER", "MAIL_ORDER", PaymentMethod::Card),
+            ("Mail Order", "MAIL_ORDER", PaymentMethod::Card),
+            ("HAVALE", "HAVALE", PaymentMethod::Bank),
+            ("Havale", "HAVALE", PaymentMethod::Bank),
+            ("Havale (Garanti)", "HAVALE (Garanti)", PaymentMethod::Bank),
+            ("Havale/EFT", "HAVALE", PaymentMethod::Bank),
+            ("EFT", "HAVALE", PaymentMethod::Bank),
+            ("BANKA_KARTI", "BANKA_KARTI", PaymentMethod::Bank),
+            ("Banka Kartı", "BANKA_KARTI", PaymentMethod::Bank),
+            ("VERESIYE", "VERESIYE", PaymentMethod::Credit),
+            ("Veresiye", "VERESIYE", PaymentMethod::Credit),
+            ("VADELI", "VERESIYE", PaymentMethod::Credit),
+            ("Vadeli", "VERESIYE", PaymentMethod::Credit),
+            ("CREDIT", "VERESIYE", PaymentMethod::Credit),
+            ("Açık Hesap", "VERESIYE", PaymentMethod::Credit),
+            ("  Çek ", "Çek", PaymentMethod::Other),
+            ("", "", PaymentMethod::Other),
+        ];
+        for (raw, canonical, method) in cases {
+            assert_eq!(canonical_payment_method(raw), canonical, "{}", raw);
+            assert_eq!(PaymentMethod::parse(raw), method, "{}", raw);
+            // Canonical values are stable and parse the same way
+            assert_eq!(canonical_payment_method(canonical), canonical);
+            assert_eq!(PaymentMethod::parse(canonical), method);
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_existing_rows() {
+        let conn = setup_db();
+        let changed = canonicalize_payment_methods(&conn, "transactions", "payment_method").unwrap();
+        assert_eq!(changed, 2);
+        let kart: String = conn
+            .query_row("SELECT payment_method FROM transactions WHERE id = 's2'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kart, "KREDI_KARTI");
+        assert_eq!(canonicalize_payment_methods(&conn, "transactions", "payment_method").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_payment_balances_group_spellings() {
+        let conn = setup_db();
+        let balances = payment_balances(&conn).unwrap();
+        // 150 + 80 ('Nakit') + 500 - 30 return - 20 expense - 45.50 manual expense
+        assert_eq!(balances.cash, 634.5);
+        assert_eq!(balances.card, 250.0);
+        assert_eq!(balances.bank, -100.0);
+        assert_eq!(balances.credit, 0.0);
+    }
+
+    #[test]
+    fn test_z_report_sales_return_and_expense() {
+        let conn = setup_db();
+        let report = generate_z_report(&conn, "2024-03-01").unwrap();
+
+        assert_eq!(report.sale_count, 3);
+        assert_eq!(report.gross_sales, 480.0);
+        assert_eq!(report.sales_by_payment.cash, 230.0);
+        assert_eq!(report.sales_by_payment.card, 250.0);
+        assert_eq!((report.return_count, report.returns_total), (1, 30.0));
+        assert_eq!(report.expense_total, 165.5);
+        // 230 cash sales - 30 return - 20 expense - 45.50 manual expense
+        assert_eq!(report.expected_cash, 134.5);
+
+        let stored: f64 = conn
+            .query_row("SELECT expected_cash FROM z_reports WHERE report_date = '2024-03-01'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, 134.5);
+
+        // A closed day cannot be closed again
+        assert!(matches!(generate_z_report(&conn, "2024-03-01"), Err(AppError::Conflict { .. })));
+        assert!(generate_z_report(&conn, "01.03.2024").is_err());
+    }
+
+    #[test]
+    fn test_invoice_numbers_unique_under_concurrency() {
+        let path = std::env::temp_dir().join(format!("nexus_invoice_{}.db", uuid::Uuid::new_v4()));
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+            CREATE TABLE finance_records (id INTEGER PRIMARY KEY AUTOINCREMENT, description TEXT DEFAULT '');
+            CREATE TABLE invoice_counters (prefix TEXT NOT NULL, year INTEGER NOT NULL, last_seq INTEGER NOT NULL, PRIMARY KEY (prefix, year));
+            INSERT INTO finance_records (description) VALUES ('MK-2026-00007'), ('MK-2025-00099');",
+        )
+        .unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let conn = Connection::open(&path).unwrap();
+                    conn.busy_timeout(std::time::Duration::from_secs(10)).unwrap();
+                    (0..10).map(|_| next_invoice_number(&conn, "MK", 2026).unwrap()).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut numbers: Vec<String> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        numbers.sort();
+        numbers.dedup();
+
+        // Continues after the number already used before the counter existed
+        assert_eq!(numbers.len(), 80);
+        assert_eq!(numbers.first().map(String::as_str), Some("MK-2026-00008"));
+        assert_eq!(numbers.last().map(String::as_str), Some("MK-2026-00087"));
+
+        assert_eq!(next_invoice_number(&conn, "FT", 2026).unwrap(), "FT-2026-00001");
+        assert!(next_invoice_number(&conn, "M-K", 2026).is_err());
+
+        drop(conn);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    fn finance_record(conn: &Connection, id: i64) -> FinanceRecord {
+        conn.query_row(
+            "SELECT id, record_type, category, amount, payment_method, description, date, created_at FROM finance_records WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(FinanceRecord {
+                    id: row.get(0)?,
+                    record_type: row.get(1)?,
+                    category: row.get(2)?,
+                    amount: row.get(3)?,
+                    payment_method: row.get(4)?,
+                    description: row.get(5)?,
+                    date: row.get(6)?,
+                    created_at: row.get(7)?,
+                })
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_update_finance_record_in_place() {
+        let conn = setup_db();
+        let original = finance_record(&conn, 1);
+
+        let edited = FinanceRecord { amount: 4550.0, payment_method: "Havale".to_string(), description: "Mart kirasi".to_string(), ..original.clone() };
+        update_finance_record(&conn, 1, &edited, false).unwrap();
+
+        let stored = finance_record(&conn, 1);
+        assert_eq!((stored.amount, stored.payment_method.as_str(), stored.description.as_str()), (4550.0, "HAVALE", "Mart kirasi"));
+        // Same row: id and created_at are kept
+        assert_eq!((stored.id, stored.created_at), (original.id, original.created_at));
+
+        let invalid = |record: FinanceRecord| matches!(update_finance_record(&conn, 1, &record, false), Err(AppError::Validation(_)));
+        assert!(invalid(FinanceRecord { amount: -1.0, ..edited.clone() }));
+        assert!(invalid(FinanceRecord { record_type: "TRANSFER".to_string(), ..edited.clone() }));
+        assert!(matches!(update_finance_record(&conn, 99, &edited, false), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_update_goods_receipt_record_amount_needs_force() {
+        let conn = setup_db();
+        conn.execute(
+            "INSERT INTO finance_records (record_type, category, amount, payment_method, description, date, created_at)
+             VALUES ('EXPENSE', 'MAL_KABUL', 1200.0, 'VERESIYE', 'Fatura 88', '2024-03-01', 'now')",
+            [],
+        )
+        .unwrap();
+        let id = conn.last_insert_rowid();
+        let receipt = finance_record(&conn, id);
+
+        // The description may be fixed, the amount may not
+        update_finance_record(&conn, id, &FinanceRecord { description: "Fatura 88A".to_string(), ..receipt.clone() }, false).unwrap();
+        let bigger = FinanceRecord { amount: 1300.0, ..receipt.clone() };
+        assert!(matches!(update_finance_record(&conn, id, &bigger, false), Err(AppError::Validation(_))));
+        // Moving it to another category in the same edit does not get around the check
+        let recategorized = FinanceRecord { category: "Genel".to_string(), ..bigger.clone() };
+        assert!(update_finance_record(&conn, id, &recategorized, false).is_err());
+        assert_eq!(finance_record(&conn, id).amount, 1200.0);
+
+        update_finance_record(&conn, id, &bigger, true).unwrap();
+        assert_eq!(finance_record(&conn, id).amount, 1300.0);
+    }
+}
@@ -0,0 +1,261 @@
+//! Goods Receipt Service Module
+//!
+//! Mal kabul fişleri (başlık + lot satırları) ve filtreli geçmiş
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection};
+
+use crate::error::AppError;
+use crate::models::{ExpiringLot, GoodsReceipt, GoodsReceiptLine, PaginatedGoodsReceiptsResponse};
+
+/// Lines (lots) of a receipt
+pub fn get_receipt_lines(conn: &Connection, receipt_id: &str) -> Result<Vec<GoodsReceiptLine>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT l.id, l.product_id, i.name, i.sku, l.initial_quantity, l.quantity, l.buy_price, l.sell_price
+         FROM inventory_lots l
+         LEFT JOIN inventory_items i ON i.id = l.product_id
+         WHERE l.goods_receipt_id = ?1
+         ORDER BY i.name",
+    )?;
+
+    let lines = stmt
+        .query_map(params![receipt_id], |row| {
+            Ok(GoodsReceiptLine {
+                lot_id: row.get(0)?,
+                product_id: row.get(1)?,
+                product_name: row.get(2)?,
+                sku: row.get(3)?,
+                quantity: row.get(4)?,
+                remaining_quantity: row.get(5)?,
+                buy_price: row.get(6)?,
+                sell_price: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(lines)
+}
+
+/// Lots with stock left whose expiry date is at most `within_days` after `today`, soonest first.
+/// Lots that already expired are listed too, with negative `days_left`.
+pub fn get_expiring_lots(conn: &Connection, within_days: i64, today: NaiveDate) -> Result<Vec<ExpiringLot>, AppError> {
+    if !(0..=3650).contains(&within_days) {
+        return Err(AppError::Validation("Gun sayisi 0-3650 arasinda olmali".to_string()));
+    }
+    let until = today + Duration::days(within_days);
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT l.id, l.product_id, i.name, i.sku, l.quantity, l.expiry_date
+         FROM inventory_lots l
+         JOIN inventory_items i ON i.id = l.product_id AND i.deleted_at IS NULL
+         WHERE l.quantity > 0 AND l.expiry_date IS NOT NULL AND l.expiry_date <= ?1
+         ORDER BY l.expiry_date ASC, i.name",
+    )?;
+    let rows = stmt.query_map(params![until.to_string()], |row| {
+        Ok(ExpiringLot {
+            lot_id: row.get(0)?,
+            product_id: row.get(1)?,
+            product_name: row.get(2)?,
+            sku: row.get(3)?,
+            remaining_quantity: row.get(4)?,
+            expiry_date: row.get(5)?,
+            days_left: 0,
+        })
+    })?;
+
+    let mut lots = Vec::new();
+    for lot in rows {
+        let mut lot = lot?;
+        let Ok(expiry) = NaiveDate::parse_from_str(&lot.expiry_date, "%Y-%m-%d") else {
+            continue;
+        };
+        lot.days_left = (expiry - today).num_days();
+        lots.push(lot);
+    }
+    Ok(lots)
+}
+
+/// Goods receipts, newest first (saved order breaks ties), filtered by supplier and receipt date (YYYY-MM-DD, inclusive)
+pub fn get_goods_receipts_paginated(
+    conn: &Connection,
+    page: i32,
+    per_page: i32,
+    supplier_id: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<PaginatedGoodsReceiptsResponse, AppError> {
+    let page = if page < 1 { 1 } else { page };
+    let per_page = if per_page < 1 { 20 } else { per_page.min(100) };
+    let offset = (page - 1) * per_page;
+
+    let mut conditions = Vec::new();
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(s) = supplier_id.filter(|s| !s.is_empty()) {
+        conditions.push("g.supplier_id = ?");
+        params_vec.push(Box::new(s));
+    }
+    if let Some(d) = start_date.filter(|d| !d.is_empty()) {
+        conditions.push("substr(g.date, 1, 10) >= ?");
+        params_vec.push(Box::new(d));
+    }
+    if let Some(d) = end_date.filter(|d| !d.is_empty()) {
+        conditions.push("substr(g.date, 1, 10) <= ?");
+        params_vec.push(Box::new(d));
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let total: i32 = {
+        let mut stmt = conn.prepare(&format!("SELECT COUNT(*) FROM goods_receipts g {}", where_clause))?;
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        stmt.query_row(params_refs.as_slice(), |row| row.get(0))?
+    };
+
+    let select_sql = format!(
+        "SELECT g.id, g.invoice_no, g.supplier_id, ca.name, g.total_amount, g.payment_method, g.description, g.date, g.created_at
+         FROM goods_receipts g
+         LEFT JOIN current_accounts ca ON ca.id = g.supplier_id
+         {} ORDER BY g.date DESC, g.created_at DESC, g.rowid DESC LIMIT ? OFFSET ?",
+        where_clause
+    );
+    params_vec.push(Box::new(per_page));
+    params_vec.push(Box::new(offset));
+
+    let mut stmt = conn.prepare(&select_sql)?;
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+    let headers = stmt
+        .query_map(params_refs.as_slice(), |row| {
+            Ok(GoodsReceipt {
+                id: row.get(0)?,
+                invoice_no: row.get(1)?,
+                supplier_id: row.get(2)?,
+                supplier_name: row.get(3)?,
+                total_amount: row.get(4)?,
+                payment_method: row.get(5)?,
+                description: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                date: row.get(7)?,
+                created_at: row.get(8)?,
+                items: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut data = Vec::with_capacity(headers.len());
+    for mut receipt in headers {
+        receipt.items = get_receipt_lines(conn, &receipt.id)?;
+        data.push(receipt);
+    }
+
+    let total_pages = (total as f64 / per_page as f64).ceil() as i32;
+
+    Ok(PaginatedGoodsReceiptsResponse {
+        data,
+        total,
+        page,
+        per_page,
+        total_pages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiring_lots_window() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (id TEXT PRIMARY KEY, sku TEXT, name TEXT, deleted_at TEXT);
+             CREATE TABLE inventory_lots (id TEXT PRIMARY KEY, product_id TEXT, quantity INTEGER, expiry_date TEXT);
+             INSERT INTO inventory_items VALUES
+                 ('sil', 'SIL-01', 'Silikon Seffaf', NULL),
+                 ('yap', 'YAP-02', 'Hizli Yapistirici', NULL),
+                 ('eski', 'ESK-03', 'Silinmis Urun', '2024-01-01');
+             INSERT INTO inventory_lots VALUES
+                 ('expired', 'sil', 3, '2024-05-28'),
+                 ('soon', 'yap', 12, '2024-06-10'),
+                 ('edge', 'sil', 4, '2024-07-01'),
+                 ('later', 'sil', 8, '2024-07-02'),
+                 ('used-up', 'yap', 0, '2024-06-05'),
+                 ('no-expiry', 'yap', 20, NULL),
+                 ('deleted-item', 'eski', 5, '2024-06-03');",
+        )
+        .unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+
+        let lots = get_expiring_lots(&conn, 30, today).unwrap();
+        let summary: Vec<(&str, &str, f64, i64)> = lots
+            .iter()
+            .map(|l| (l.lot_id.as_str(), l.product_name.as_str(), l.remaining_quantity, l.days_left))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("expired", "Silikon Seffaf", 3.0, -4),
+                ("soon", "Hizli Yapistirici", 12.0, 9),
+                ("edge", "Silikon Seffaf", 4.0, 30),
+            ]
+        );
+        assert_eq!(lots[1].sku, "YAP-02");
+
+        assert_eq!(get_expiring_lots(&conn, 0, today).unwrap().len(), 1);
+        assert!(get_expiring_lots(&conn, -1, today).is_err());
+    }
+
+    #[test]
+    fn test_goods_receipts_paginated_filters_and_ties() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO current_accounts (id, name, account_type, created_at, updated_at)
+                 VALUES ('s1', 'Boya Tedarik', 'SUPPLIER', 'now', 'now');
+             INSERT INTO inventory_items (id, sku, name, last_updated) VALUES
+                 ('fir', 'FIR-01', 'Firca', 'now'), ('rul', 'RUL-01', 'Rulo', 'now');
+             -- r2, r3 and r4 were saved in the same second; r3 has no supplier
+             INSERT INTO goods_receipts (id, invoice_no, supplier_id, total_amount, payment_method, description, date, created_at) VALUES
+                 ('r1', 'F-1', 's1', 100, 'NAKIT', 'Mart', '2024-03-01', '2024-03-01T09:00:00Z'),
+                 ('r2', 'F-2', 's1', 40, 'VADELI', '', '2024-03-05', '2024-03-05T10:00:00Z'),
+                 ('r3', NULL, NULL, 25, 'NAKIT', 'Nalbur', '2024-03-05', '2024-03-05T10:00:00Z'),
+                 ('r4', 'F-4', 's1', 60, 'HAVALE', '', '2024-03-05', '2024-03-05T10:00:00Z');
+             INSERT INTO inventory_lots (id, product_id, supplier_id, quantity, initial_quantity, buy_price, receipt_date, created_at, goods_receipt_id) VALUES
+                 ('l1', 'fir', 's1', 4, 10, 10, '2024-03-01', '2024-03-01T09:00:00Z', 'r1'),
+                 ('l3a', 'rul', NULL, 5, 5, 3, '2024-03-05', '2024-03-05T10:00:00Z', 'r3'),
+                 ('l3b', 'fir', NULL, 2, 2, 5, '2024-03-05', '2024-03-05T10:00:00Z', 'r3');",
+        )
+        .unwrap();
+        let ids = |page: &PaginatedGoodsReceiptsResponse| page.data.iter().map(|r| r.id.clone()).collect::<Vec<_>>();
+
+        // Ties keep a fixed order, so paging neither repeats nor skips a receipt
+        let first = get_goods_receipts_paginated(&conn, 1, 2, None, None, None).unwrap();
+        let second = get_goods_receipts_paginated(&conn, 2, 2, None, None, None).unwrap();
+        assert_eq!((first.total, first.total_pages), (4, 2));
+        assert_eq!(ids(&first), ["r4", "r3"]);
+        assert_eq!(ids(&second), ["r2", "r1"]);
+
+        // No supplier: no name, and its lines are still listed
+        let no_supplier = &first.data[1];
+        assert_eq!((no_supplier.supplier_id.as_deref(), no_supplier.supplier_name.as_deref()), (None, None));
+        let lines: Vec<(&str, Option<&str>, f64)> = no_supplier
+            .items
+            .iter()
+            .map(|l| (l.lot_id.as_str(), l.product_name.as_deref(), l.quantity))
+            .collect();
+        assert_eq!(lines, [("l3b", Some("Firca"), 2.0), ("l3a", Some("Rulo"), 5.0)]);
+        assert_eq!(second.data[1].supplier_name.as_deref(), Some("Boya Tedarik"));
+        assert_eq!(second.data[1].items[0].remaining_quantity, 4.0);
+
+        let by_supplier = get_goods_receipts_paginated(&conn, 1, 20, Some("s1".to_string()), None, None).unwrap();
+        assert_eq!(ids(&by_supplier), ["r4", "r2", "r1"]);
+        let in_range =
+            get_goods_receipts_paginated(&conn, 1, 20, None, Some("2024-03-02".to_string()), Some("2024-03-05".to_string()))
+                .unwrap();
+        assert_eq!(ids(&in_range), ["r4", "r3", "r2"]);
+        // Empty filters are ignored
+        assert_eq!(get_goods_receipts_paginated(&conn, 1, 20, Some(String::new()), None, None).unwrap().total, 4);
+    }
+}
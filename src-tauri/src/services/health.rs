@@ -0,0 +1,280 @@
+//! Database Health Service Module
+//!
+//! Yedekleme zamanlamasında `PRAGMA integrity_check` ve `PRAGMA foreign_key_check` çalıştırır,
+//! sonucu ve veritabanı dosyasının SHA256 özetini `health_snapshots` tablosuna yazar.
+//! Bozulma ilk görüldüğünde uyarı dinleyicisi (arayüzde `db-health-warning` olayı) tetiklenir.
+//! Aynı kontroller çökme sonrası elle de çalıştırılabilir (`check_database_integrity`); büyük
+//! silmelerden sonra `vacuum_database` dosyayı küçültür.
+
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::cloud;
+use crate::error::AppError;
+use crate::models::{DbHealthSnapshot, ForeignKeyViolation, IntegrityReport, VacuumReport};
+use crate::services::inventory;
+
+/// Event emitted to the frontend when a check first fails
+pub const DB_HEALTH_WARNING_EVENT: &str = "db-health-warning";
+
+/// integrity_check messages kept per snapshot
+const MAX_INTEGRITY_MESSAGES: usize = 20;
+
+/// Snapshots older than the newest this many are removed
+const MAX_HEALTH_SNAPSHOTS: i64 = 500;
+
+type WarningListener = Box<dyn Fn(&DbHealthSnapshot) + Send + Sync>;
+
+static WARNING_LISTENER: OnceLock<WarningListener> = OnceLock::new();
+
+/// Register the callback run when a check first fails (set once at startup)
+pub fn set_health_warning_listener(listener: impl Fn(&DbHealthSnapshot) + Send + Sync + 'static) {
+    let _ = WARNING_LISTENER.set(Box::new(listener));
+}
+
+fn snapshot_from_row(row: &rusqlite::Row) -> rusqlite::Result<DbHealthSnapshot> {
+    let messages: String = row.get(3)?;
+    Ok(DbHealthSnapshot {
+        id: row.get(0)?,
+        checked_at: row.get(1)?,
+        integrity_ok: row.get::<_, i32>(2)? == 1,
+        integrity_messages: serde_json::from_str(&messages).unwrap_or_default(),
+        foreign_key_violations: row.get(4)?,
+        file_sha256: row.get(5)?,
+        file_size_bytes: row.get(6)?,
+        duration_ms: row.get(7)?,
+    })
+}
+
+const SNAPSHOT_COLUMNS: &str =
+    "id, checked_at, integrity_ok, integrity_messages, foreign_key_violations, file_sha256, file_size_bytes, duration_ms";
+
+/// `PRAGMA integrity_check`: whether it passed and its messages when it did not
+fn integrity_check(conn: &Connection) -> Result<(bool, Vec<String>), AppError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA integrity_check({})", MAX_INTEGRITY_MESSAGES))?;
+    let mut messages = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let ok = messages.len() == 1 && messages[0] == "ok";
+    if ok {
+        messages.clear();
+    }
+    Ok((ok, messages))
+}
+
+/// `PRAGMA foreign_key_check` rows
+fn foreign_key_check(conn: &Connection) -> Result<Vec<ForeignKeyViolation>, AppError> {
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let violations = stmt
+        .query_map([], |row| {
+            Ok(ForeignKeyViolation {
+                table: row.get(0)?,
+                rowid: row.get(1)?,
+                parent: row.get(2)?,
+                fk_id: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(violations)
+}
+
+fn file_size(db_path: &str) -> Result<i64, AppError> {
+    Ok(std::fs::metadata(db_path)
+        .map_err(|e| AppError::Internal(format!("Veritabani dosyasi okunamadi: {}", e)))?
+        .len() as i64)
+}
+
+/// Run integrity and foreign key checks on demand (nothing is stored). The WAL is checkpointed first.
+pub fn check_database_integrity(conn: &Connection) -> Result<IntegrityReport, AppError> {
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    let (integrity_ok, integrity_messages) = integrity_check(conn)?;
+    let foreign_key_violations = foreign_key_check(conn)?;
+    Ok(IntegrityReport {
+        ok: integrity_ok && foreign_key_violations.is_empty(),
+        integrity_messages,
+        foreign_key_violations,
+    })
+}
+
+/// Checkpoint the WAL and `VACUUM` the database at `db_path` to reclaim space left by deletions
+pub fn vacuum_database(conn: &Connection, db_path: &str) -> Result<VacuumReport, AppError> {
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    let size_before_bytes = file_size(db_path)?;
+    conn.execute_batch("VACUUM;")?;
+    // VACUUM may renumber the item rowids the search index is keyed on
+    inventory::rebuild_search_index(conn)?;
+    // VACUUM goes through the WAL as well
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    Ok(VacuumReport { size_before_bytes, size_after_bytes: file_size(db_path)? })
+}
+
+/// Run the checks, store a snapshot and return it. `db_path` is hashed after a WAL checkpoint.
+pub fn run_health_check(conn: &Connection, db_path: &str) -> Result<DbHealthSnapshot, AppError> {
+    let started = Instant::now();
+
+    let (integrity_ok, integrity_messages) = integrity_check(conn)?;
+    let foreign_key_violations = foreign_key_check(conn)?.len() as i64;
+
+    // Hash what is on disk, including committed WAL pages
+    let _ = conn.execute_batch("PRAGMA wal_checkpoint(PASSIVE);");
+    let file_sha256 = cloud::calculate_checksum(db_path).ok();
+    let file_size_bytes = std::fs::metadata(db_path).map(|m| m.len() as i64).ok();
+
+    let checked_at = chrono::Utc::now().to_rfc3339();
+    let duration_ms = started.elapsed().as_millis() as i64;
+    conn.execute(
+        "INSERT INTO health_snapshots (checked_at, integrity_ok, integrity_messages, foreign_key_violations, file_sha256, file_size_bytes, duration_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            &checked_at,
+            integrity_ok as i32,
+            serde_json::to_string(&integrity_messages)?,
+            foreign_key_violations,
+            &file_sha256,
+            file_size_bytes,
+            duration_ms,
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "DELETE FROM health_snapshots WHERE id <= ?1",
+        params![id - MAX_HEALTH_SNAPSHOTS],
+    )?;
+
+    Ok(DbHealthSnapshot {
+        id,
+        checked_at,
+        integrity_ok,
+        integrity_messages,
+        foreign_key_violations,
+        file_sha256,
+        file_size_bytes,
+        duration_ms,
+    })
+}
+
+/// Whether the snapshot before `snapshot` was healthy (or there was none)
+fn previous_was_healthy(conn: &Connection, snapshot: &DbHealthSnapshot) -> Result<bool, AppError> {
+    let previous: Option<(i32, i64)> = conn
+        .query_row(
+            "SELECT integrity_ok, foreign_key_violations FROM health_snapshots WHERE id < ?1 ORDER BY id DESC LIMIT 1",
+            params![snapshot.id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    Ok(match previous {
+        Some((ok, violations)) => ok == 1 && violations == 0,
+        None => true,
+    })
+}
+
+/// Scheduled check: store a snapshot and notify the listener on the first failed check.
+/// Returns false when the database is unhealthy (the caller should not rotate backups then).
+pub fn run_scheduled_health_check(db_path: &str) -> Result<bool, AppError> {
+    let conn = crate::database::open_database(db_path)?;
+    let snapshot = run_health_check(&conn, db_path)?;
+    if snapshot.is_healthy() {
+        return Ok(true);
+    }
+
+    eprintln!(
+        "[Health] Veritabani kontrolu basarisiz: {} butunluk hatasi, {} yabanci anahtar ihlali",
+        snapshot.integrity_messages.len(),
+        snapshot.foreign_key_violations
+    );
+    if previous_was_healthy(&conn, &snapshot)? {
+        if let Some(listener) = WARNING_LISTENER.get() {
+            listener(&snapshot);
+        }
+    }
+    Ok(false)
+}
+
+/// Stored snapshots, newest first
+pub fn get_health_history(conn: &Connection, limit: i64) -> Result<Vec<DbHealthSnapshot>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM health_snapshots ORDER BY id DESC LIMIT ?1",
+        SNAPSHOT_COLUMNS
+    ))?;
+    let snapshots = stmt
+        .query_map(params![limit.clamp(1, MAX_HEALTH_SNAPSHOTS)], snapshot_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(snapshots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_check_records_snapshot_and_fk_violations() {
+        let path = std::env::temp_dir().join(format!("nexus_health_{}.db", uuid::Uuid::new_v4()));
+        let db_path = path.to_string_lossy().to_string();
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE health_snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, checked_at TEXT NOT NULL, integrity_ok INTEGER NOT NULL,
+                integrity_messages TEXT NOT NULL DEFAULT '[]', foreign_key_violations INTEGER NOT NULL DEFAULT 0,
+                file_sha256 TEXT, file_size_bytes INTEGER, duration_ms INTEGER
+            );
+            CREATE TABLE current_accounts (id TEXT PRIMARY KEY, parent_account_id TEXT REFERENCES current_accounts(id));
+            INSERT INTO current_accounts VALUES ('a', NULL);",
+        )
+        .unwrap();
+
+        let healthy = run_health_check(&conn, &db_path).unwrap();
+        assert!(healthy.is_healthy());
+        assert_eq!(healthy.file_sha256.as_ref().map(|h| h.len()), Some(64));
+
+        // Dangling parent reference, e.g. written by an external tool with enforcement off
+        conn.execute_batch("PRAGMA foreign_keys = OFF; INSERT INTO current_accounts VALUES ('b', 'missing');")
+            .unwrap();
+        let broken = run_health_check(&conn, &db_path).unwrap();
+        assert!(broken.integrity_ok);
+        assert_eq!(broken.foreign_key_violations, 1);
+        assert!(previous_was_healthy(&conn, &broken).unwrap());
+
+        let again = run_health_check(&conn, &db_path).unwrap();
+        assert!(!previous_was_healthy(&conn, &again).unwrap());
+
+        let history = get_health_history(&conn, 10).unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].id, again.id);
+
+        drop(conn);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_integrity_check_on_healthy_database() {
+        let path = std::env::temp_dir().join(format!("nexus_integrity_{}.db", uuid::Uuid::new_v4()));
+        let db_path = path.to_string_lossy().to_string();
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE current_accounts (id TEXT PRIMARY KEY, parent_account_id TEXT REFERENCES current_accounts(id));
+             INSERT INTO current_accounts VALUES ('a', NULL), ('b', 'a');
+             CREATE TABLE inventory_items (sku TEXT PRIMARY KEY, name TEXT, description TEXT, brand TEXT);
+             CREATE VIRTUAL TABLE inventory_fts USING fts5(sku, name, description, brand);
+             CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT);
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 2000)
+             INSERT INTO notes (body) SELECT hex(randomblob(200)) FROM n;",
+        )
+        .unwrap();
+
+        let report = check_database_integrity(&conn).unwrap();
+        assert!(report.ok);
+        assert!(report.integrity_messages.is_empty());
+        assert!(report.foreign_key_violations.is_empty());
+
+        conn.execute("DELETE FROM notes", []).unwrap();
+        let vacuum = vacuum_database(&conn, &db_path).unwrap();
+        assert!(vacuum.size_after_bytes < vacuum.size_before_bytes);
+        assert!(check_database_integrity(&conn).unwrap().ok);
+
+        drop(conn);
+        let _ = std::fs::remove_file(&path);
+    }
+}
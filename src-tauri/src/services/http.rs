@@ -0,0 +1,378 @@
+//! HTTP Client Service Module
+//!
+//! Lisans ve bulut API çağrıları için tekrar deneme (üstel bekleme + rastgele sapma) ve devre kesici.
+//! Art arda başarısız olan sunucuya bekleme süresi boyunca istek gönderilmez.
+//! Tekrarlanması güvenli olmayan istekler (yedek yükleme, lisans etkinleştirme) yalnızca sunucuya
+//! hiç ulaşmadıklarında (bağlantı kurulamadı) yeniden denenir. 4xx yanıtları hiç tekrarlanmaz.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use reqwest::blocking::{RequestBuilder, Response};
+use rusqlite::{params, Connection};
+
+use crate::error::AppError;
+use crate::services::settings;
+
+pub const HTTP_MAX_RETRIES_SETTING: &str = "http_max_retries";
+pub const DEFAULT_HTTP_MAX_RETRIES: u32 = 2;
+pub const MAX_HTTP_RETRIES_LIMIT: u32 = 5;
+
+pub const HTTP_RETRY_BACKOFF_SETTING: &str = "http_retry_backoff_ms";
+/// First retry waits about this long, doubling each attempt up to MAX_BACKOFF
+pub const DEFAULT_HTTP_RETRY_BACKOFF_MS: u64 = 500;
+const MIN_HTTP_RETRY_BACKOFF_MS: u64 = 50;
+const MAX_HTTP_RETRY_BACKOFF_MS: u64 = 10_000;
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Consecutive failed requests before the circuit opens
+const CIRCUIT_FAILURE_THRESHOLD: u32 = 3;
+/// How long an open circuit rejects requests
+const CIRCUIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+static MAX_RETRIES: AtomicU32 = AtomicU32::new(DEFAULT_HTTP_MAX_RETRIES);
+static RETRY_BACKOFF_MS: AtomicU64 = AtomicU64::new(DEFAULT_HTTP_RETRY_BACKOFF_MS);
+
+/// How a request is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Wait before the first retry; doubled each retry (before jitter)
+    pub initial_backoff: Duration,
+    /// Retry only when the connection could not be made, i.e. the server never saw the request.
+    /// For calls that must not run twice (backup upload, license activation).
+    pub connect_errors_only: bool,
+}
+
+impl RetryPolicy {
+    /// Configured retry count and backoff, for idempotent requests
+    pub fn configured() -> Self {
+        Self {
+            max_retries: get_http_max_retries(),
+            initial_backoff: Duration::from_millis(get_http_retry_backoff_ms()),
+            connect_errors_only: false,
+        }
+    }
+
+    /// Configured retry count and backoff, for requests that are not safe to send twice
+    pub fn non_idempotent() -> Self {
+        Self { connect_errors_only: true, ..Self::configured() }
+    }
+}
+
+/// Failure of a request after retries, or a rejected request while the circuit is open
+#[derive(Debug)]
+pub enum HttpError {
+    CircuitOpen { host: String, retry_in_secs: u64 },
+    Request { attempts: u32, source: reqwest::Error },
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpError::CircuitOpen { host, retry_in_secs } => write!(
+                f,
+                "{} sunucusuna art arda ulasilamadi, {} sn sonra tekrar denenecek",
+                host, retry_in_secs
+            ),
+            HttpError::Request { attempts, source } => {
+                write!(f, "{} ({} deneme)", source, attempts)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct CircuitState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitState {
+    /// Remaining cooldown if the circuit is open
+    fn blocked_for(&self, now: Instant) -> Option<Duration> {
+        self.open_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    /// After the cooldown a single trial request is let through; failing it reopens the circuit
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= CIRCUIT_FAILURE_THRESHOLD {
+            self.open_until = Some(now + CIRCUIT_COOLDOWN);
+        }
+    }
+}
+
+fn circuits() -> &'static Mutex<HashMap<String, CircuitState>> {
+    static CIRCUITS: OnceLock<Mutex<HashMap<String, CircuitState>>> = OnceLock::new();
+    CIRCUITS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn with_circuit<T>(host: &str, f: impl FnOnce(&mut CircuitState) -> T) -> T {
+    let mut map = circuits().lock().unwrap_or_else(|e| e.into_inner());
+    f(map.entry(host.to_string()).or_default())
+}
+
+fn host_key(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| match u.port() {
+            Some(port) => format!("{}:{}", h, port),
+            None => h.to_string(),
+        }))
+        .unwrap_or_else(|| url.to_string())
+}
+
+fn backoff_for(initial: Duration, retry: u32) -> Duration {
+    initial
+        .saturating_mul(2u32.saturating_pow(retry))
+        .min(MAX_BACKOFF)
+}
+
+/// Wait somewhere between half and all of `backoff`, so clients that failed together do not retry together
+fn with_jitter(backoff: Duration) -> Duration {
+    let half = backoff / 2;
+    half + half.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+}
+
+/// Server errors worth retrying (the request itself was fine)
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+}
+
+/// Number of retries after the first attempt
+pub fn get_http_max_retries() -> u32 {
+    MAX_RETRIES.load(Ordering::SeqCst)
+}
+
+/// Set the retry count (0 disables retries), clamped to MAX_HTTP_RETRIES_LIMIT
+pub fn set_http_max_retries(retries: u32) -> u32 {
+    let retries = retries.min(MAX_HTTP_RETRIES_LIMIT);
+    MAX_RETRIES.store(retries, Ordering::SeqCst);
+    retries
+}
+
+/// Initial retry backoff in milliseconds
+pub fn get_http_retry_backoff_ms() -> u64 {
+    RETRY_BACKOFF_MS.load(Ordering::SeqCst)
+}
+
+/// Set the initial retry backoff, clamped to 50 ms - 10 s
+pub fn set_http_retry_backoff_ms(backoff_ms: u64) -> u64 {
+    let backoff_ms = backoff_ms.clamp(MIN_HTTP_RETRY_BACKOFF_MS, MAX_HTTP_RETRY_BACKOFF_MS);
+    RETRY_BACKOFF_MS.store(backoff_ms, Ordering::SeqCst);
+    backoff_ms
+}
+
+/// Apply the retry count and backoff stored in settings
+pub fn load_http_settings(conn: &Connection) -> Result<u32, AppError> {
+    let backoff_ms = settings::get_setting(conn, HTTP_RETRY_BACKOFF_SETTING)?
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_HTTP_RETRY_BACKOFF_MS);
+    set_http_retry_backoff_ms(backoff_ms);
+
+    let retries = settings::get_setting(conn, HTTP_MAX_RETRIES_SETTING)?
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_HTTP_MAX_RETRIES);
+    Ok(set_http_max_retries(retries))
+}
+
+/// Store and apply the retry count
+pub fn save_http_max_retries(conn: &Connection, retries: u32) -> Result<u32, AppError> {
+    let retries = set_http_max_retries(retries);
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![HTTP_MAX_RETRIES_SETTING, retries.to_string()],
+    )?;
+    Ok(retries)
+}
+
+/// Store and apply the initial retry backoff
+pub fn save_http_retry_backoff_ms(conn: &Connection, backoff_ms: u64) -> Result<u64, AppError> {
+    let backoff_ms = set_http_retry_backoff_ms(backoff_ms);
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![HTTP_RETRY_BACKOFF_SETTING, backoff_ms.to_string()],
+    )?;
+    Ok(backoff_ms)
+}
+
+/// Send an idempotent request with the configured retry count and backoff
+pub fn send_with_retry<F>(url: &str, build: F) -> Result<Response, HttpError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    send_with_policy(url, RetryPolicy::configured(), build)
+}
+
+/// Send a request that must not reach the server twice; only connection failures are retried
+pub fn send_non_idempotent<F>(url: &str, build: F) -> Result<Response, HttpError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    send_with_policy(url, RetryPolicy::non_idempotent(), build)
+}
+
+/// Send an idempotent request with `max_retries` and the configured backoff
+pub fn send_with_retries<F>(url: &str, max_retries: u32, build: F) -> Result<Response, HttpError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    send_with_policy(url, RetryPolicy { max_retries, ..RetryPolicy::configured() }, build)
+}
+
+/// Send a request, retrying network errors and 5xx/408/429 with jittered exponential backoff.
+/// 4xx responses are returned at once.
+///
+/// `build` is called once per attempt, so a timeout set on the builder applies per attempt.
+/// If retries run out on a server error the last response is returned for the caller to handle.
+pub fn send_with_policy<F>(url: &str, policy: RetryPolicy, build: F) -> Result<Response, HttpError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let host = host_key(url);
+
+    if let Some(remaining) = with_circuit(&host, |c| c.blocked_for(Instant::now())) {
+        return Err(HttpError::CircuitOpen {
+            host,
+            retry_in_secs: remaining.as_secs().max(1),
+        });
+    }
+
+    let mut attempt = 0;
+    loop {
+        let last_attempt = attempt >= policy.max_retries;
+        match build().send() {
+            Ok(response) if is_retryable_status(response.status()) => {
+                // The server may have acted on the request before failing
+                if last_attempt || policy.connect_errors_only {
+                    with_circuit(&host, |c| c.record_failure(Instant::now()));
+                    return Ok(response);
+                }
+            }
+            Ok(response) => {
+                with_circuit(&host, |c| c.record_success());
+                return Ok(response);
+            }
+            Err(e) => {
+                if last_attempt || (policy.connect_errors_only && !e.is_connect()) {
+                    with_circuit(&host, |c| c.record_failure(Instant::now()));
+                    return Err(HttpError::Request { attempts: attempt + 1, source: e });
+                }
+            }
+        }
+
+        std::thread::sleep(with_jitter(backoff_for(policy.initial_backoff, attempt)));
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_circuit_state() {
+        let initial = Duration::from_millis(500);
+        assert_eq!(backoff_for(initial, 0), Duration::from_millis(500));
+        assert_eq!(backoff_for(initial, 2), Duration::from_secs(2));
+        assert_eq!(backoff_for(initial, 10), MAX_BACKOFF);
+        for _ in 0..20 {
+            let jittered = with_jitter(Duration::from_secs(2));
+            assert!(jittered >= Duration::from_secs(1) && jittered <= Duration::from_secs(2));
+        }
+
+        let now = Instant::now();
+        let mut circuit = CircuitState::default();
+        for _ in 0..CIRCUIT_FAILURE_THRESHOLD - 1 {
+            circuit.record_failure(now);
+        }
+        assert!(circuit.blocked_for(now).is_none());
+
+        circuit.record_failure(now);
+        assert!(circuit.blocked_for(now).is_some());
+        // Cooldown over: one trial request is allowed
+        assert!(circuit.blocked_for(now + CIRCUIT_COOLDOWN).is_none());
+
+        circuit.record_success();
+        assert!(circuit.blocked_for(now).is_none());
+        assert_eq!(circuit.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_unreachable_server_opens_circuit() {
+        // Nothing listens on port 9; connections are refused immediately
+        let url = "http://127.0.0.1:9/api/health";
+        let client = reqwest::blocking::Client::new();
+        for _ in 0..CIRCUIT_FAILURE_THRESHOLD {
+            let result = send_with_retries(url, 0, || client.get(url).timeout(Duration::from_secs(2)));
+            assert!(matches!(result, Err(HttpError::Request { attempts: 1, .. })));
+        }
+
+        let result = send_with_retries(url, 0, || client.get(url));
+        assert!(matches!(result, Err(HttpError::CircuitOpen { .. })));
+    }
+
+    /// Local server answering requests with `statuses` in order; returns its URL and a request counter
+    fn mock_server(statuses: &'static [u16]) -> (String, std::sync::Arc<AtomicU32>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/cloud/status", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+        std::thread::spawn(move || {
+            for (stream, status) in listener.incoming().zip(statuses) {
+                let mut stream = stream.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                    status
+                );
+            }
+        });
+        (url, requests)
+    }
+
+    fn fast_policy(connect_errors_only: bool) -> RetryPolicy {
+        RetryPolicy { max_retries: 3, initial_backoff: Duration::from_millis(10), connect_errors_only }
+    }
+
+    #[test]
+    fn test_transient_failures_are_retried_until_success() {
+        let client = reqwest::blocking::Client::new();
+
+        let (url, requests) = mock_server(&[503, 503, 200]);
+        let response = send_with_policy(&url, fast_policy(false), || client.get(&url)).unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // Client errors are final
+        let (url, requests) = mock_server(&[404, 200]);
+        let response = send_with_policy(&url, fast_policy(false), || client.get(&url)).unwrap();
+        assert_eq!(response.status(), 404);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // A request that reached the server is not sent again when it is not idempotent
+        let (url, requests) = mock_server(&[503, 200]);
+        let response = send_with_policy(&url, fast_policy(true), || client.post(&url)).unwrap();
+        assert_eq!(response.status(), 503);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,359 @@
+//! Item Images Module
+//!
+//! Ürün görselleri veritabanında base64 olarak değil, uygulama veri dizinindeki `images/`
+//! klasöründe dosya olarak tutulur. Dosya adı içeriğin SHA256 özetidir; aynı görsel tek dosya
+//! olarak saklanır. `inventory_items.image` sütununda yalnızca göreli yol (`images/<özet>.<uzantı>`)
+//! bulunur. Her görselin yanında listelerde kullanılan küçük bir WebP önizlemesi
+//! (`<özet>.thumb.webp`) tutulur; eksikse ilk istendiğinde üretilir.
+
+use std::path::{Path, PathBuf};
+
+use base64::Engine as _;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+
+/// Folder under the app data directory the image files are kept in
+pub const IMAGES_DIR: &str = "images";
+
+/// Largest image accepted
+pub const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Longest edge of a thumbnail, in pixels
+pub const THUMBNAIL_MAX_EDGE: u32 = 128;
+
+/// Suffix replacing the extension of an image file in its thumbnail's name
+const THUMBNAIL_SUFFIX: &str = ".thumb.webp";
+
+/// File extension of a supported image format, by its magic bytes
+fn image_extension(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("png"),
+        [0xff, 0xd8, 0xff, ..] => Some("jpg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("webp"),
+        [b'B', b'M', ..] => Some("bmp"),
+        _ => None,
+    }
+}
+
+/// Whether a stored `image` value is an inline `data:` URL rather than a file path
+pub fn is_inline_image(image: &str) -> bool {
+    image.starts_with("data:")
+}
+
+/// Bytes of an inline `data:<mime>;base64,<data>` image
+fn decode_data_url(image: &str) -> Result<Vec<u8>, AppError> {
+    let (_, data) = image
+        .split_once(";base64,")
+        .ok_or_else(|| AppError::Validation("Gorsel base64 data URL degil".to_string()))?;
+    base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| AppError::Validation(format!("Gorsel cozulemedi: {}", e)))
+}
+
+/// Absolute path of a stored relative image path; anything outside `images/` is refused
+fn resolve_image_path(data_dir: &Path, relative: &str) -> Option<PathBuf> {
+    let name = relative.strip_prefix(IMAGES_DIR)?.strip_prefix('/')?;
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') && !name.starts_with('.');
+    valid.then(|| data_dir.join(IMAGES_DIR).join(name))
+}
+
+/// WebP thumbnail of an image, at most `THUMBNAIL_MAX_EDGE` pixels on the long edge (never enlarged)
+pub fn make_thumbnail(bytes: &[u8]) -> Result<Vec<u8>, AppError> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| AppError::Validation(format!("Gorsel okunamadi: {}", e)))?;
+    let thumbnail = if image.width() > THUMBNAIL_MAX_EDGE || image.height() > THUMBNAIL_MAX_EDGE {
+        image.thumbnail(THUMBNAIL_MAX_EDGE, THUMBNAIL_MAX_EDGE)
+    } else {
+        image
+    };
+
+    // The WebP encoder only takes 8-bit RGB(A)
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgba8(thumbnail.to_rgba8())
+        .write_to(&mut encoded, image::ImageFormat::WebP)
+        .map_err(|e| AppError::Internal(format!("Onizleme olusturulamadi: {}", e)))?;
+    Ok(encoded.into_inner())
+}
+
+/// Relative path of the thumbnail of a stored image (`images/<sha256>.thumb.webp`)
+fn thumbnail_path(relative: &str) -> String {
+    let stem = relative.rsplit_once('.').map_or(relative, |(stem, _)| stem);
+    format!("{}{}", stem, THUMBNAIL_SUFFIX)
+}
+
+/// Write a file under a temporary name first, so a half-written file never takes the final name
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), AppError> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    let temp = dir.join(format!(".{}.tmp", uuid::Uuid::new_v4()));
+    std::fs::write(&temp, bytes)?;
+    if let Err(e) = std::fs::rename(&temp, path) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Write `bytes` to `<data_dir>/images/<sha256>.<ext>` unless that file already exists, and
+/// return the relative path stored in the `image` column. The thumbnail is made alongside; if
+/// that fails it is made on the first request instead.
+pub fn store_image(data_dir: &Path, bytes: &[u8]) -> Result<String, AppError> {
+    if bytes.is_empty() {
+        return Err(AppError::Validation("Gorsel bos".to_string()));
+    }
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(AppError::Validation(format!(
+            "Gorsel cok buyuk ({} KB, en fazla {} KB)",
+            bytes.len() / 1024,
+            MAX_IMAGE_BYTES / 1024
+        )));
+    }
+    let extension = image_extension(bytes)
+        .ok_or_else(|| AppError::Validation("Desteklenmeyen gorsel bicimi (PNG, JPEG, GIF, WEBP, BMP)".to_string()))?;
+
+    let relative = format!("{}/{:x}.{}", IMAGES_DIR, Sha256::digest(bytes), extension);
+    let path = data_dir.join(&relative);
+    if !path.exists() {
+        write_atomically(&path, bytes)?;
+    }
+
+    let thumbnail = data_dir.join(thumbnail_path(&relative));
+    if !thumbnail.exists() {
+        if let Err(e) = make_thumbnail(bytes).and_then(|thumb| write_atomically(&thumbnail, &thumb)) {
+            eprintln!("[Images] {} icin onizleme olusturulamadi: {}", relative, e);
+        }
+    }
+    Ok(relative)
+}
+
+/// The `image` value to store for an item: inline `data:` images are moved to a file, paths and
+/// empty values are kept as they are
+pub fn externalize_image(data_dir: &Path, image: Option<String>) -> Result<Option<String>, AppError> {
+    match image {
+        Some(image) if is_inline_image(&image) => Ok(Some(store_image(data_dir, &decode_data_url(&image)?)?)),
+        other => Ok(other),
+    }
+}
+
+/// Store `bytes` as the image of item `sku`; returns the relative path
+pub fn save_item_image(conn: &Connection, data_dir: &Path, sku: &str, bytes: &[u8]) -> Result<String, AppError> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM inventory_items WHERE sku = ?1 AND deleted_at IS NULL)",
+        params![sku],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Err(AppError::NotFound(format!("Urun bulunamadi: {}", sku)));
+    }
+
+    let relative = store_image(data_dir, bytes)?;
+    conn.execute(
+        "UPDATE inventory_items SET image = ?1, last_updated = ?2 WHERE sku = ?3 AND deleted_at IS NULL",
+        params![&relative, chrono::Utc::now().to_rfc3339(), sku],
+    )?;
+    Ok(relative)
+}
+
+/// Stored `image` value of item `sku`, if it has one
+fn item_image_value(conn: &Connection, sku: &str) -> Result<Option<String>, AppError> {
+    let image: Option<String> = conn
+        .query_row(
+            "SELECT image FROM inventory_items WHERE sku = ?1 AND deleted_at IS NULL",
+            params![sku],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    Ok(image.filter(|i| !i.trim().is_empty()))
+}
+
+/// Contents of a file, `None` when it does not exist
+fn read_if_exists(path: &Path) -> Result<Option<Vec<u8>>, AppError> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Image bytes of item `sku`; `None` when it has no image or the file is gone
+pub fn get_item_image(conn: &Connection, data_dir: &Path, sku: &str) -> Result<Option<Vec<u8>>, AppError> {
+    let Some(image) = item_image_value(conn, sku)? else {
+        return Ok(None);
+    };
+
+    // Not extracted yet (written by an import or an older build)
+    if is_inline_image(&image) {
+        return decode_data_url(&image).map(Some);
+    }
+    match resolve_image_path(data_dir, &image) {
+        Some(path) => read_if_exists(&path),
+        None => Ok(None),
+    }
+}
+
+/// Thumbnail bytes of item `sku`. A missing thumbnail is made from the image and kept.
+pub fn get_item_thumbnail(conn: &Connection, data_dir: &Path, sku: &str) -> Result<Option<Vec<u8>>, AppError> {
+    let Some(image) = item_image_value(conn, sku)? else {
+        return Ok(None);
+    };
+    if is_inline_image(&image) {
+        return make_thumbnail(&decode_data_url(&image)?).map(Some);
+    }
+    let (Some(original), Some(thumbnail)) = (
+        resolve_image_path(data_dir, &image),
+        resolve_image_path(data_dir, &thumbnail_path(&image)),
+    ) else {
+        return Ok(None);
+    };
+
+    if let Some(bytes) = read_if_exists(&thumbnail)? {
+        return Ok(Some(bytes));
+    }
+    let Some(bytes) = read_if_exists(&original)? else {
+        return Ok(None);
+    };
+    let thumb = make_thumbnail(&bytes)?;
+    write_atomically(&thumbnail, &thumb)?;
+    Ok(Some(thumb))
+}
+
+/// Move every inline base64 item image into a file. Runs at startup; once extracted there is
+/// nothing left to match, so in practice it works once. Returns the number of items changed.
+pub fn extract_inline_images(conn: &Connection, data_dir: &Path) -> Result<usize, AppError> {
+    let inline: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT sku, image FROM inventory_items WHERE image LIKE 'data:%'")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut extracted = 0;
+    for (sku, image) in inline {
+        // A broken image is left in place rather than losing it
+        match decode_data_url(&image).and_then(|bytes| store_image(data_dir, &bytes)) {
+            Ok(relative) => {
+                conn.execute("UPDATE inventory_items SET image = ?1 WHERE sku = ?2", params![&relative, &sku])?;
+                extracted += 1;
+            }
+            Err(e) => eprintln!("[Images] {} urununun gorseli dosyaya tasinamadi: {}", sku, e),
+        }
+    }
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 1, 2, 3, 4];
+
+    fn setup() -> (Connection, PathBuf) {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (sku TEXT PRIMARY KEY, image TEXT, last_updated TEXT, deleted_at TEXT);
+             INSERT INTO inventory_items (sku) VALUES ('VIDA'), ('SOMUN'), ('PUL');",
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("nexus_images_{}", uuid::Uuid::new_v4()));
+        (conn, dir)
+    }
+
+    /// Stored originals (thumbnails not counted)
+    fn image_files(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir.join(IMAGES_DIR))
+            .map(|entries| {
+                entries
+                    .map(|e| e.unwrap().path())
+                    .filter(|p| !p.to_string_lossy().ends_with(THUMBNAIL_SUFFIX))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// A real PNG with some detail so it does not compress to nothing
+    fn photo_png(width: u32, height: u32) -> Vec<u8> {
+        let photo = image::RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x * 7 % 256) as u8, (y * 13 % 256) as u8, ((x ^ y) % 256) as u8])
+        });
+        let mut png = std::io::Cursor::new(Vec::new());
+        photo.write_to(&mut png, image::ImageFormat::Png).unwrap();
+        png.into_inner()
+    }
+
+    #[test]
+    fn test_item_image_round_trip_and_shared_file() {
+        let (conn, dir) = setup();
+
+        let path = save_item_image(&conn, &dir, "VIDA", PNG).unwrap();
+        assert!(path.starts_with("images/") && path.ends_with(".png"));
+        let stored: String = conn.query_row("SELECT image FROM inventory_items WHERE sku = 'VIDA'", [], |r| r.get(0)).unwrap();
+        assert_eq!(stored, path);
+        assert_eq!(get_item_image(&conn, &dir, "VIDA").unwrap().as_deref(), Some(PNG));
+        assert_eq!(get_item_image(&conn, &dir, "SOMUN").unwrap(), None);
+
+        // Same picture on another item: one file
+        assert_eq!(save_item_image(&conn, &dir, "SOMUN", PNG).unwrap(), path);
+        assert_eq!(image_files(&dir).len(), 1);
+
+        assert!(matches!(save_item_image(&conn, &dir, "YOK", PNG), Err(AppError::NotFound(_))));
+        assert!(matches!(save_item_image(&conn, &dir, "PUL", b"not an image"), Err(AppError::Validation(_))));
+
+        // A path outside the images folder is never read
+        conn.execute("UPDATE inventory_items SET image = 'images/../../secret.db' WHERE sku = 'PUL'", []).unwrap();
+        assert_eq!(get_item_image(&conn, &dir, "PUL").unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inline_images_extracted_to_files() {
+        let (conn, dir) = setup();
+        let data_url = format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(PNG));
+        conn.execute("UPDATE inventory_items SET image = ?1 WHERE sku IN ('VIDA', 'SOMUN')", params![&data_url]).unwrap();
+        conn.execute("UPDATE inventory_items SET image = 'data:image/png;base64,%%%' WHERE sku = 'PUL'", []).unwrap();
+
+        // Readable before extraction too
+        assert_eq!(get_item_image(&conn, &dir, "VIDA").unwrap().as_deref(), Some(PNG));
+
+        assert_eq!(extract_inline_images(&conn, &dir).unwrap(), 2);
+        assert_eq!(image_files(&dir).len(), 1);
+        assert_eq!(get_item_image(&conn, &dir, "SOMUN").unwrap().as_deref(), Some(PNG));
+        let broken: String = conn.query_row("SELECT image FROM inventory_items WHERE sku = 'PUL'", [], |r| r.get(0)).unwrap();
+        assert!(broken.starts_with("data:"));
+
+        // Nothing left to do on the next start
+        assert_eq!(extract_inline_images(&conn, &dir).unwrap(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_thumbnail_smaller_than_original() {
+        let (conn, dir) = setup();
+        let photo = photo_png(1600, 1200);
+        let path = save_item_image(&conn, &dir, "VIDA", &photo).unwrap();
+        let thumb_file = dir.join(thumbnail_path(&path));
+        assert!(thumb_file.exists());
+
+        let thumb = get_item_thumbnail(&conn, &dir, "VIDA").unwrap().unwrap();
+        assert!(thumb.len() < photo.len());
+        let decoded = image::load_from_memory_with_format(&thumb, image::ImageFormat::WebP).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (THUMBNAIL_MAX_EDGE, 96));
+
+        // Made again on request when missing
+        std::fs::remove_file(&thumb_file).unwrap();
+        assert_eq!(get_item_thumbnail(&conn, &dir, "VIDA").unwrap().unwrap(), thumb);
+        assert!(thumb_file.exists());
+
+        // Small images are not enlarged
+        save_item_image(&conn, &dir, "SOMUN", &photo_png(40, 20)).unwrap();
+        let small = get_item_thumbnail(&conn, &dir, "SOMUN").unwrap().unwrap();
+        assert_eq!(image::load_from_memory(&small).unwrap().width(), 40);
+        assert_eq!(get_item_thumbnail(&conn, &dir, "PUL").unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,478 @@
+//! Import Staging Service Module
+//!
+//! CSV ürün/fiyat listeleri önce `import_staging` tablosuna alınır (deneme içe aktarma).
+//! Fiyat sapmaları burada incelenir; onaylanan satırlar canlı stoğa sonradan uygulanır.
+
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::models::{ImportCommitResult, ImportStageSummary, PriceOutlier};
+use crate::security::validation::validate_quantity;
+
+use super::pricing;
+
+/// One parsed line of the export format `ID;SKU;Name;Category;Quantity;Location;Price;CostPrice;Currency;...`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvImportRow {
+    pub sku: String,
+    pub name: String,
+    pub category: String,
+    pub quantity: f64,
+    pub location: String,
+    pub price: f64,
+    pub cost_price: f64,
+    pub currency: String,
+}
+
+/// Parsed import rows: file line number and the row or why it was rejected
+pub type ImportRows = Vec<(u64, Result<CsvImportRow, String>)>;
+
+/// Field delimiter of the export format
+pub const DEFAULT_CSV_DELIMITER: u8 = b';';
+
+/// Delimiter argument of the import commands: one ASCII character, `\t` for tab (default `;`)
+pub fn parse_csv_delimiter(value: Option<&str>) -> Result<u8, AppError> {
+    match value {
+        None | Some("") => Ok(DEFAULT_CSV_DELIMITER),
+        Some("\\t") | Some("\t") => Ok(b'\t'),
+        Some(v) if v.len() == 1 && v.is_ascii() && v != "\"" => Ok(v.as_bytes()[0]),
+        Some(v) => Err(AppError::Validation(format!("Gecersiz ayirici: {}", v))),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(record: &csv::StringRecord, index: usize, label: &str, default: T) -> Result<T, String> {
+    match record.get(index).map(|v| v.trim().replace(',', ".")) {
+        None => Ok(default),
+        Some(v) if v.is_empty() => Ok(default),
+        Some(v) => v.parse().map_err(|_| format!("Gecersiz {}: {}", label, v)),
+    }
+}
+
+fn csv_import_row(record: &csv::StringRecord) -> Result<CsvImportRow, String> {
+    if record.len() < 3 {
+        return Err(format!("Eksik sutun ({} alan, en az ID;SKU;Ad gerekli)", record.len()));
+    }
+
+    let sku = record[1].trim();
+    let name = record[2].trim();
+    if sku.is_empty() {
+        return Err("SKU bos".to_string());
+    }
+    if name.is_empty() {
+        return Err(format!("Urun adi bos: {}", sku));
+    }
+
+    Ok(CsvImportRow {
+        sku: sku.to_string(),
+        name: name.to_string(),
+        category: record.get(3).unwrap_or("Genel").trim().to_string(),
+        quantity: parse_number(record, 4, "miktar", 0.0)?,
+        location: record.get(5).unwrap_or("").trim().to_string(),
+        price: parse_number(record, 6, "fiyat", 0.0)?,
+        cost_price: parse_number(record, 7, "maliyet", 0.0)?,
+        currency: record.get(8).unwrap_or("TL").trim().to_string(),
+    })
+}
+
+/// Parse the data rows of a CSV export (BOM and header line skipped). Quoted fields may contain
+/// the delimiter, `""` escapes and line breaks. Each entry has the file line number and either
+/// the row or why it was rejected.
+pub fn read_csv_import_rows(csv_content: &str, delimiter: u8) -> ImportRows {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(csv_content.trim_start_matches('\u{feff}').as_bytes());
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        match record {
+            Ok(record) => {
+                let line = record.position().map(|p| p.line()).unwrap_or(0);
+                // Lines of only delimiters (spreadsheet padding) are not rows
+                if record.iter().all(|field| field.trim().is_empty()) {
+                    continue;
+                }
+                rows.push((line, csv_import_row(&record)));
+            }
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                rows.push((line, Err(e.to_string())));
+            }
+        }
+    }
+    rows
+}
+
+/// Stage a CSV file (header line skipped) without touching live inventory
+pub fn stage_csv_import(
+    conn: &mut Connection,
+    csv_content: &str,
+    delimiter: u8,
+) -> Result<ImportStageSummary, AppError> {
+    let batch_id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut summary = ImportStageSummary {
+        batch_id: batch_id.clone(),
+        total_rows: 0,
+        new_items: 0,
+        updated_items: 0,
+        skipped_lines: 0,
+    };
+
+    let tx = conn.transaction()?;
+    for (_, row) in read_csv_import_rows(csv_content, delimiter) {
+        let Ok(row) = row else {
+            summary.skipped_lines += 1;
+            continue;
+        };
+
+        let existing: Option<(String, f64, String)> = tx
+            .query_row(
+                "SELECT id, price, unit FROM inventory_items WHERE sku = ?1",
+                params![&row.sku],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .optional()?;
+        // The export has no unit column: the quantity must fit the unit of the item it updates
+        let unit = existing.as_ref().map_or("ADET", |(_, _, unit)| unit.as_str());
+        if validate_quantity(row.quantity, unit).is_err() {
+            summary.skipped_lines += 1;
+            continue;
+        }
+        match existing {
+            Some(_) => summary.updated_items += 1,
+            None => summary.new_items += 1,
+        }
+
+        // A SKU listed twice keeps its last line, like the direct import
+        tx.execute(
+            "INSERT OR REPLACE INTO import_staging
+             (batch_id, sku, name, category, quantity, location, price, cost_price, currency, item_id, current_price, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                &batch_id,
+                &row.sku,
+                &row.name,
+                &row.category,
+                row.quantity,
+                &row.location,
+                row.price,
+                row.cost_price,
+                &row.currency,
+                existing.as_ref().map(|(id, _, _)| id.clone()),
+                existing.as_ref().map(|(_, price, _)| *price),
+                &now,
+            ],
+        )?;
+    }
+    tx.commit()?;
+
+    summary.total_rows = staged_row_count(conn, &batch_id)?;
+    Ok(summary)
+}
+
+fn staged_row_count(conn: &Connection, batch_id: &str) -> Result<i32, AppError> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM import_staging WHERE batch_id = ?1",
+        params![batch_id],
+        |row| row.get(0),
+    )?)
+}
+
+fn ensure_batch_exists(conn: &Connection, batch_id: &str) -> Result<(), AppError> {
+    if staged_row_count(conn, batch_id)? == 0 {
+        return Err(AppError::NotFound(format!("Bekleyen ice aktarma bulunamadi: {}", batch_id)));
+    }
+    Ok(())
+}
+
+/// Staged prices that differ from the previous price by more than `threshold_percent`.
+///
+/// Önceki fiyat: ürünün son fiyat geçmişi kaydı, yoksa mevcut satış fiyatı.
+/// Yeni ürünler ve önceki fiyatı 0 olanlar karşılaştırılamaz, listelenmez.
+pub fn review_import_prices(
+    conn: &Connection,
+    batch_id: &str,
+    threshold_percent: f64,
+) -> Result<Vec<PriceOutlier>, AppError> {
+    if !threshold_percent.is_finite() || threshold_percent < 0.0 {
+        return Err(AppError::Validation(format!("Gecersiz esik degeri: {}", threshold_percent)));
+    }
+    ensure_batch_exists(conn, batch_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT s.sku, s.name, s.item_id, s.price, s.current_price,
+                (SELECT h.new_price FROM price_history h WHERE h.item_id = s.item_id
+                 ORDER BY h.created_at DESC, h.id DESC LIMIT 1)
+         FROM import_staging s
+         WHERE s.batch_id = ?1 AND s.item_id IS NOT NULL
+         ORDER BY s.sku",
+    )?;
+    let rows = stmt.query_map(params![batch_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, f64>(3)?,
+            row.get::<_, Option<f64>>(4)?,
+            row.get::<_, Option<f64>>(5)?,
+        ))
+    })?;
+
+    let mut outliers = Vec::new();
+    for row in rows {
+        let (sku, name, item_id, new_price, current_price, history_price) = row?;
+        let (old_price, source) = match (history_price, current_price) {
+            (Some(price), _) => (price, "history"),
+            (None, Some(price)) => (price, "current"),
+            (None, None) => continue,
+        };
+        if old_price <= 0.0 {
+            continue;
+        }
+
+        let percent_change = (new_price - old_price) / old_price * 100.0;
+        if percent_change.abs() > threshold_percent {
+            outliers.push(PriceOutlier {
+                sku,
+                name,
+                item_id,
+                old_price,
+                new_price,
+                percent_change: (percent_change * 100.0).round() / 100.0,
+                old_price_source: source.to_string(),
+            });
+        }
+    }
+
+    // Largest deviations first
+    outliers.sort_by(|a, b| b.percent_change.abs().total_cmp(&a.percent_change.abs()));
+    Ok(outliers)
+}
+
+/// Apply a staged batch to live inventory, leaving out `excluded_skus` (rejected in review)
+pub fn commit_staged_import(
+    conn: &mut Connection,
+    batch_id: &str,
+    excluded_skus: &[String],
+) -> Result<ImportCommitResult, AppError> {
+    ensure_batch_exists(conn, batch_id)?;
+    let excluded: HashSet<&str> = excluded_skus.iter().map(|s| s.as_str()).collect();
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut result = ImportCommitResult { applied: 0, excluded: 0, price_changes: 0 };
+
+    let tx = conn.transaction()?;
+    let rows: Vec<CsvImportRow> = {
+        let mut stmt = tx.prepare(
+            "SELECT sku, name, category, quantity, location, price, cost_price, currency
+             FROM import_staging WHERE batch_id = ?1 ORDER BY sku",
+        )?;
+        let rows = stmt
+            .query_map(params![batch_id], |row| {
+                Ok(CsvImportRow {
+                    sku: row.get(0)?,
+                    name: row.get(1)?,
+                    category: row.get(2)?,
+                    quantity: row.get(3)?,
+                    location: row.get(4)?,
+                    price: row.get(5)?,
+                    cost_price: row.get(6)?,
+                    currency: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    for row in rows {
+        if excluded.contains(row.sku.as_str()) {
+            result.excluded += 1;
+            continue;
+        }
+
+        // Live state at commit time, not at staging time
+        let existing: Option<(String, f64)> = tx
+            .query_row(
+                "SELECT id, price FROM inventory_items WHERE sku = ?1",
+                params![&row.sku],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?;
+
+        match existing {
+            Some((item_id, old_price)) => {
+                tx.execute(
+                    "UPDATE inventory_items SET name = ?1, category = ?2, quantity = ?3, location = ?4, price = ?5, cost_price = ?6, currency = ?7, last_updated = ?8 WHERE id = ?9",
+                    params![&row.name, &row.category, row.quantity, &row.location, row.price, row.cost_price, &row.currency, &now, &item_id],
+                )
+                .map_err(|e| AppError::Validation(format!("{}: {}", row.sku, e)))?;
+                if (old_price - row.price).abs() > f64::EPSILON {
+                    pricing::record_price_change(&tx, &item_id, Some(&row.sku), Some(old_price), row.price, Some(row.cost_price), pricing::REASON_IMPORT)?;
+                    result.price_changes += 1;
+                }
+            }
+            None => {
+                let id = uuid::Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, cost_price, image, description, ai_tags, last_updated, currency) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, '', '', '[]', ?9, ?10)",
+                    params![&id, &row.sku, &row.name, &row.category, row.quantity, &row.location, row.price, row.cost_price, &now, &row.currency],
+                )
+                .map_err(|e| AppError::Validation(format!("{}: {}", row.sku, e)))?;
+                pricing::record_price_change(&tx, &id, Some(&row.sku), None, row.price, Some(row.cost_price), pricing::REASON_IMPORT)?;
+            }
+        }
+        result.applied += 1;
+    }
+
+    tx.execute("DELETE FROM import_staging WHERE batch_id = ?1", params![batch_id])?;
+    tx.commit()?;
+    Ok(result)
+}
+
+/// Drop a staged batch without applying it
+pub fn discard_staged_import(conn: &Connection, batch_id: &str) -> Result<(), AppError> {
+    conn.execute("DELETE FROM import_staging WHERE batch_id = ?1", params![batch_id])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (
+                id TEXT PRIMARY KEY, sku TEXT UNIQUE NOT NULL, name TEXT NOT NULL, category TEXT, quantity INTEGER,
+                location TEXT, price REAL CHECK (price >= 0), cost_price REAL, image TEXT, description TEXT,
+                ai_tags TEXT, last_updated TEXT, currency TEXT, unit TEXT NOT NULL DEFAULT 'ADET'
+            );
+            CREATE TABLE price_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, item_id TEXT NOT NULL, sku TEXT, old_price REAL,
+                new_price REAL NOT NULL, cost_price REAL, reason TEXT NOT NULL, created_at TEXT NOT NULL
+            );
+            CREATE TABLE import_staging (
+                batch_id TEXT NOT NULL, sku TEXT NOT NULL, name TEXT NOT NULL, category TEXT, quantity INTEGER,
+                location TEXT, price REAL, cost_price REAL, currency TEXT, item_id TEXT, current_price REAL,
+                created_at TEXT NOT NULL, PRIMARY KEY (batch_id, sku)
+            );
+            INSERT INTO inventory_items VALUES ('i1', 'BOYA-25', 'Boya 2.5L', 'Boya', 5, '', 250.0, 180.0, '', '', '[]', 'now', 'TL', 'ADET');
+            INSERT INTO inventory_items VALUES ('i2', 'BOYA-75', 'Boya 7.5L', 'Boya', 5, '', 600.0, 450.0, '', '', '[]', 'now', 'TL', 'ADET');
+            INSERT INTO price_history (item_id, sku, old_price, new_price, reason, created_at)
+                VALUES ('i2', 'BOYA-75', 550.0, 600.0, 'manual', '2024-01-01');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_staged_import_flags_outliers_and_commits_approved_rows() {
+        let mut conn = setup_db();
+        let csv = "ID;SKU;Ad;Kategori;Adet;Konum;Fiyat;Maliyet;ParaBirimi\n\
+                   ;BOYA-25;Boya 2.5L;Boya;5;;2650,00;180;TL\n\
+                   ;BOYA-75;Boya 7.5L;Boya;5;;630;450;TL\n\
+                   ;BOYA-15;Boya 15L;Boya;2;;1100;800;TL\n\
+                   bozuk satir\n";
+
+        let summary = stage_csv_import(&mut conn, csv, DEFAULT_CSV_DELIMITER).unwrap();
+        assert_eq!((summary.total_rows, summary.new_items, summary.updated_items, summary.skipped_lines), (3, 1, 2, 1));
+        // Nothing is live yet
+        let price: f64 = conn.query_row("SELECT price FROM inventory_items WHERE id = 'i1'", [], |r| r.get(0)).unwrap();
+        assert_eq!(price, 250.0);
+
+        let outliers = review_import_prices(&conn, &summary.batch_id, 20.0).unwrap();
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].sku, "BOYA-25");
+        assert_eq!(outliers[0].old_price_source, "current");
+        assert!((outliers[0].percent_change - 960.0).abs() < 0.01);
+
+        // 630 vs last history price 600 = %5
+        assert!(review_import_prices(&conn, &summary.batch_id, 4.0).unwrap().iter().any(|o| o.sku == "BOYA-75" && o.old_price_source == "history"));
+
+        let result = commit_staged_import(&mut conn, &summary.batch_id, &["BOYA-25".to_string()]).unwrap();
+        assert_eq!((result.applied, result.excluded, result.price_changes), (2, 1, 1));
+        let price: f64 = conn.query_row("SELECT price FROM inventory_items WHERE id = 'i1'", [], |r| r.get(0)).unwrap();
+        assert_eq!(price, 250.0);
+        let price: f64 = conn.query_row("SELECT price FROM inventory_items WHERE id = 'i2'", [], |r| r.get(0)).unwrap();
+        assert_eq!(price, 630.0);
+        assert!(review_import_prices(&conn, &summary.batch_id, 20.0).is_err());
+    }
+
+    #[test]
+    fn test_read_csv_rows_with_quotes_and_malformed_lines() {
+        let csv = "\u{feff}ID;SKU;Ad;Kategori;Adet;Konum;Fiyat\n\
+                   ;VALF-12;\"VALF 1/2; 3/4 UYUMLU\";Tesisat;4;Raf 2;85,50\n\
+                   ;SLK-01;\"Silikon \"\"Seffaf\"\" 280ml\";Yapi;12;;95\n\
+                   ;BOZUK\n\
+                   ;;;;\n\
+                   ;VIDA-4;Vida;Hirdavat;on;;1\n";
+
+        let rows = read_csv_import_rows(csv, DEFAULT_CSV_DELIMITER);
+        assert_eq!(rows.len(), 4);
+
+        let valve = rows[0].1.as_ref().unwrap();
+        assert_eq!(rows[0].0, 2);
+        assert_eq!(valve.name, "VALF 1/2; 3/4 UYUMLU");
+        assert_eq!((valve.category.as_str(), valve.quantity, valve.price), ("Tesisat", 4.0, 85.5));
+        assert_eq!(rows[1].1.as_ref().unwrap().name, "Silikon \"Seffaf\" 280ml");
+
+        assert_eq!(rows[2].0, 4);
+        assert!(rows[2].1.is_err());
+        assert!(rows[3].1.as_ref().unwrap_err().contains("miktar"));
+
+        assert_eq!(parse_csv_delimiter(Some(",")).unwrap(), b',');
+        assert_eq!(parse_csv_delimiter(None).unwrap(), b';');
+        assert!(parse_csv_delimiter(Some(";;")).is_err());
+    }
+
+    #[test]
+    fn test_import_from_csv_upserts_and_reports_errors() {
+        let conn = setup_db();
+        let csv = "ID,SKU,Ad,Kategori,Adet,Konum,Fiyat,Maliyet,ParaBirimi\n\
+                   ,BOYA-25,\"Boya, Ic Cephe 2.5L\",Boya,7,,\"275,00\",190,TL\n\
+                   ,YENI-1,Rulo,Boya,3,,45,30,TL\n\
+                   ,,Adsiz,Boya,1,,10,5,TL\n";
+
+        let result = crate::services::inventory::import_from_csv(&conn, csv, b',').unwrap();
+        assert_eq!((result.imported, result.skipped), (2, 1));
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].starts_with("Satir 4"));
+
+        let (name, price, quantity): (String, f64, i32) = conn
+            .query_row("SELECT name, price, quantity FROM inventory_items WHERE id = 'i1'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((name.as_str(), price, quantity), ("Boya, Ic Cephe 2.5L", 275.0, 7));
+        let count: i32 = conn.query_row("SELECT COUNT(*) FROM inventory_items", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_fractional_quantities_follow_item_unit() {
+        let mut conn = setup_db();
+        conn.execute_batch(
+            "INSERT INTO inventory_items VALUES ('i3', 'KABLO-3', 'Kablo 3x1.5', 'Elektrik', 10, '', 12.0, 8.0, '', '', '[]', 'now', 'TL', 'MT');",
+        )
+        .unwrap();
+        let csv = "ID;SKU;Ad;Kategori;Adet;Konum;Fiyat;Maliyet;ParaBirimi\n\
+                   ;KABLO-3;Kablo 3x1.5;Elektrik;12,5;;12;8;TL\n\
+                   ;BOYA-25;Boya 2.5L;Boya;2,5;;250;180;TL\n\
+                   ;YENI-1;Rulo;Boya;0,5;;45;30;TL\n";
+
+        let summary = stage_csv_import(&mut conn, csv, DEFAULT_CSV_DELIMITER).unwrap();
+        assert_eq!((summary.total_rows, summary.skipped_lines), (1, 2));
+        commit_staged_import(&mut conn, &summary.batch_id, &[]).unwrap();
+        let quantity: f64 = conn.query_row("SELECT quantity FROM inventory_items WHERE id = 'i3'", [], |r| r.get(0)).unwrap();
+        assert_eq!(quantity, 12.5);
+
+        let result = crate::services::inventory::import_from_csv(&conn, &csv.replace("12,5", "13,25"), DEFAULT_CSV_DELIMITER).unwrap();
+        assert_eq!((result.imported, result.skipped), (1, 2));
+        assert!(result.errors[0].contains("ADET"));
+        let quantity: f64 = conn.query_row("SELECT quantity FROM inventory_items WHERE id = 'i3'", [], |r| r.get(0)).unwrap();
+        assert_eq!(quantity, 13.25);
+    }
+}
@@ -1,92 +1,369 @@
 #![allow(dead_code)]
 
-use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
 
 use crate::error::AppError;
-use crate::models::{CategoryStats, DashboardStats, InventoryItem, Transaction, CartItem};
-use crate::security::validation::{validate_price, validate_quantity, validate_sku};
+use crate::models::{
+    CategoryStats, DashboardStats, DeletedItem, InventoryItem, Transaction, CartItem, CatalogRow, CsvImportResult,
+    PaginatedItemsResponse, PaginatedTransactionsResponse, Payment, ReturnItem, SaleDiscount, StockCard,
+    TRANSACTION_STATUS_COMPLETED, TRANSACTION_STATUS_VOIDED,
+};
+use crate::security::validation::{validate_min_stock_level, validate_price, validate_quantity, validate_sku};
+use crate::services::accounts;
+use crate::services::attributes;
+use crate::services::bundles;
+use crate::services::currency;
+use crate::services::discounts;
+use crate::services::finance::{self, PaymentMethod};
+use crate::services::import_staging::{self, ImportRows};
+use crate::services::reports::{get_timezone_offset, parse_report_date};
+use crate::services::settings;
+use crate::services::sync::{self, ActionType};
+use crate::services::tax;
+use crate::services::units;
+
+const ITEM_COLUMNS: &str = "id, sku, name, category, quantity, location, price, cost_price,
+     image, description, ai_tags, last_updated, currency, supplier_id, brand, min_stock_level, unit";
+
+fn item_from_row(row: &rusqlite::Row) -> rusqlite::Result<InventoryItem> {
+    let ai_tags_str: Option<String> = row.get(10)?;
+    let ai_tags: Option<Vec<String>> = ai_tags_str.and_then(|s| serde_json::from_str(&s).ok());
+
+    Ok(InventoryItem {
+        id: row.get(0)?,
+        sku: row.get(1)?,
+        name: row.get(2)?,
+        category: row.get(3)?,
+        quantity: row.get(4)?,
+        location: row.get(5)?,
+        price: row.get(6)?,
+        cost_price: row.get(7)?,
+        image: row.get(8)?,
+        description: row.get(9)?,
+        ai_tags,
+        last_updated: row.get(11)?,
+        currency: row.get(12)?,
+        supplier_id: row.get(13)?,
+        brand: row.get(14)?,
+        min_stock_level: row.get(15)?,
+        unit: row.get(16)?,
+    })
+}
 
 /// Get all inventory items
 pub fn get_all_items(conn: &Connection) -> Result<Vec<InventoryItem>, AppError> {
-    let mut stmt = conn.prepare_cached(
-        "SELECT id, sku, name, category, quantity, location, price, cost_price,
-                image, description, ai_tags, last_updated, currency, supplier_id, brand
-         FROM inventory_items
-         ORDER BY last_updated DESC",
-    )?;
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM inventory_items WHERE deleted_at IS NULL ORDER BY last_updated DESC",
+        ITEM_COLUMNS
+    ))?;
 
     let items = stmt
-        .query_map([], |row| {
-            let ai_tags_str: Option<String> = row.get(10)?;
-            let ai_tags: Option<Vec<String>> =
-                ai_tags_str.and_then(|s| serde_json::from_str(&s).ok());
-
-            Ok(InventoryItem {
-                id: row.get(0)?,
-                sku: row.get(1)?,
-                name: row.get(2)?,
-                category: row.get(3)?,
-                quantity: row.get(4)?,
-                location: row.get(5)?,
-                price: row.get(6)?,
-                cost_price: row.get(7)?,
-                image: row.get(8)?,
-                description: row.get(9)?,
-                ai_tags,
-                last_updated: row.get(11)?,
-                currency: row.get(12)?,
-                supplier_id: row.get(13)?,
-                brand: row.get(14)?,
-            })
-        })?
+        .query_map([], item_from_row)?
         .collect::<Result<Vec<_>, _>>()?;
 
     Ok(items)
 }
 
+/// Unit of measure of the item with `sku`, deleted items too
+pub fn item_unit(conn: &Connection, sku: &str) -> Result<Option<String>, AppError> {
+    let unit = conn
+        .query_row("SELECT unit FROM inventory_items WHERE sku = ?1", [sku], |row| row.get(0))
+        .optional()?;
+    Ok(unit)
+}
+
 /// Get item by SKU
 pub fn get_item_by_sku(conn: &Connection, sku: &str) -> Result<Option<InventoryItem>, AppError> {
-    let mut stmt = conn.prepare_cached(
-        "SELECT id, sku, name, category, quantity, location, price, cost_price,
-                image, description, ai_tags, last_updated, currency, supplier_id, brand
-         FROM inventory_items WHERE sku = ?1",
-    )?;
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM inventory_items WHERE sku = ?1 AND deleted_at IS NULL",
+        ITEM_COLUMNS
+    ))?;
 
-    let item = stmt
-        .query_row([sku], |row| {
-            let ai_tags_str: Option<String> = row.get(10)?;
-            let ai_tags: Option<Vec<String>> =
-                ai_tags_str.and_then(|s| serde_json::from_str(&s).ok());
+    let item = stmt.query_row([sku], item_from_row).optional()?;
 
-            Ok(InventoryItem {
-                id: row.get(0)?,
-                sku: row.get(1)?,
-                name: row.get(2)?,
-                category: row.get(3)?,
-                quantity: row.get(4)?,
-                location: row.get(5)?,
-                price: row.get(6)?,
-                cost_price: row.get(7)?,
-                image: row.get(8)?,
-                description: row.get(9)?,
-                ai_tags,
-                last_updated: row.get(11)?,
-                currency: row.get(12)?,
-                supplier_id: row.get(13)?,
-                brand: row.get(14)?,
-            })
-        })
-        .optional()?;
+    Ok(item)
+}
+
+/// Item of the stock card with `barcode`: the item created from the card (same id) or stocked
+/// under the card's barcode
+pub fn get_item_by_stock_card_barcode(conn: &Connection, barcode: &str) -> Result<Option<InventoryItem>, AppError> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM inventory_items
+         WHERE deleted_at IS NULL AND EXISTS (
+             SELECT 1 FROM stock_cards s
+             WHERE s.barcode = ?1 AND (s.id = inventory_items.id OR s.barcode = inventory_items.sku)
+         )
+         LIMIT 1",
+        ITEM_COLUMNS
+    ))?;
+
+    let item = stmt.query_row([barcode], item_from_row).optional()?;
 
     Ok(item)
 }
 
+/// Default and largest number of `search_items` results
+pub const DEFAULT_SEARCH_LIMIT: i64 = 50;
+pub const MAX_SEARCH_LIMIT: i64 = 200;
+
+/// Turkish letters folded for search, so "IŞIK", "ışık" and "isik" all match each other.
+/// The tokenizer lowercases the rest and strips the remaining diacritics.
+const SEARCH_FOLDS: &[(char, char)] = &[
+    ('İ', 'i'), ('I', 'i'), ('ı', 'i'),
+    ('Ş', 's'), ('ş', 's'),
+    ('Ğ', 'g'), ('ğ', 'g'),
+    ('Ü', 'u'), ('ü', 'u'),
+    ('Ö', 'o'), ('ö', 'o'),
+    ('Ç', 'c'), ('ç', 'c'),
+];
+
+/// Search form of `text`, the same folding `fold_search_sql` does in SQL
+pub fn fold_search_text(text: &str) -> String {
+    text.chars()
+        .map(|c| SEARCH_FOLDS.iter().find(|(from, _)| *from == c).map_or(c, |(_, to)| *to))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// SQL expression folding the text column `expr` like `fold_search_text`
+pub fn fold_search_sql(expr: &str) -> String {
+    let folded = SEARCH_FOLDS.iter().fold(format!("coalesce({}, '')", expr), |sql, (from, to)| {
+        format!("replace({}, '{}', '{}')", sql, from, to)
+    });
+    format!("lower({})", folded)
+}
+
+/// Refill `inventory_fts` from `inventory_items`. Needed after a VACUUM, which may renumber the
+/// rowids the index is keyed on.
+pub fn rebuild_search_index(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(&format!(
+        "DELETE FROM inventory_fts;
+         INSERT INTO inventory_fts (rowid, sku, name, description, brand)
+         SELECT rowid, {}, {}, {}, {} FROM inventory_items;",
+        fold_search_sql("sku"),
+        fold_search_sql("name"),
+        fold_search_sql("description"),
+        fold_search_sql("brand"),
+    ))?;
+    Ok(())
+}
+
+/// Full-text item search over name, SKU, description and brand, best match first (BM25; name
+/// hits weigh most). Every word is a prefix and any of them may match. A query with anything
+/// but letters and digits (e.g. "RP1-25") falls back to a substring match instead.
+pub fn search_items(conn: &Connection, query: &str, limit: i64) -> Result<Vec<InventoryItem>, AppError> {
+    let folded = fold_search_text(query.trim());
+    if folded.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.clamp(1, MAX_SEARCH_LIMIT);
+
+    if !folded.chars().all(|c| c.is_alphanumeric() || c.is_whitespace()) {
+        return search_items_like(conn, &folded, limit);
+    }
+
+    let match_query = folded
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    // bm25 weights follow the column order: sku, name, description, brand
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM inventory_items
+         JOIN (SELECT rowid AS fts_rowid, bm25(inventory_fts, 2.0, 3.0, 0.5, 1.0) AS score
+               FROM inventory_fts WHERE inventory_fts MATCH ?1) f ON inventory_items.rowid = f.fts_rowid
+         WHERE deleted_at IS NULL
+         ORDER BY f.score
+         LIMIT ?2",
+        ITEM_COLUMNS
+    ))?;
+    let items = stmt
+        .query_map(params![match_query, limit], item_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(items)
+}
+
+fn search_items_like(conn: &Connection, folded: &str, limit: i64) -> Result<Vec<InventoryItem>, AppError> {
+    let escaped = folded.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let pattern = format!("%{}%", escaped);
+    let condition = ["sku", "name", "description", "brand"]
+        .iter()
+        .map(|column| format!("{} LIKE ?1 ESCAPE '\\'", fold_search_sql(column)))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM inventory_items WHERE deleted_at IS NULL AND ({}) ORDER BY name LIMIT ?2",
+        ITEM_COLUMNS, condition
+    ))?;
+    let items = stmt
+        .query_map(params![pattern, limit], item_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(items)
+}
+
+/// Items below their own low stock threshold, lowest stock first
+pub fn get_low_stock_items(conn: &Connection) -> Result<Vec<InventoryItem>, AppError> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM inventory_items
+         WHERE quantity < min_stock_level AND deleted_at IS NULL
+         ORDER BY quantity - min_stock_level, name",
+        ITEM_COLUMNS
+    ))?;
+
+    let items = stmt
+        .query_map([], item_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(items)
+}
+
+/// What add_item does when the SKU already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkuConflictPolicy {
+    /// Return `AppError::Conflict` (default)
+    Error,
+    /// Overwrite the existing item (upsert)
+    Update,
+    /// Keep the existing item and do nothing
+    Skip,
+}
+
+impl SkuConflictPolicy {
+    pub fn parse(value: Option<&str>) -> Result<Self, AppError> {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("error") => Ok(Self::Error),
+            Some("update") => Ok(Self::Update),
+            Some("skip") => Ok(Self::Skip),
+            Some(other) => Err(AppError::Validation(format!(
+                "Gecersiz on_conflict degeri: {} (error, update veya skip)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Result of add_item_with_policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddItemOutcome {
+    Inserted,
+    Updated,
+    Skipped,
+}
+
+/// Map UNIQUE violations on inventory_items to `AppError::Conflict`
+fn classify_insert_error(e: rusqlite::Error, item: &InventoryItem) -> AppError {
+    if AppError::is_unique_violation(&e) {
+        let message = e.to_string();
+        if message.contains("inventory_items.sku") {
+            return AppError::Conflict { field: "sku".to_string(), value: item.sku.clone() };
+        }
+        if message.contains("inventory_items.id") {
+            return AppError::Conflict { field: "id".to_string(), value: item.id.clone() };
+        }
+    }
+    AppError::Database(e)
+}
+
+/// Add an item, handling an existing SKU according to `policy`
+pub fn add_item_with_policy(
+    conn: &Connection,
+    item: &InventoryItem,
+    policy: SkuConflictPolicy,
+) -> Result<AddItemOutcome, AppError> {
+    validate_sku(&item.sku).map_err(AppError::Validation)?;
+
+    if item_sku_exists(conn, &item.sku)? {
+        return match policy {
+            SkuConflictPolicy::Error => Err(AppError::Conflict {
+                field: "sku".to_string(),
+                value: item.sku.clone(),
+            }),
+            SkuConflictPolicy::Update => {
+                update_item(conn, item)?;
+                Ok(AddItemOutcome::Updated)
+            }
+            SkuConflictPolicy::Skip => Ok(AddItemOutcome::Skipped),
+        };
+    }
+
+    add_item(conn, item)?;
+    Ok(AddItemOutcome::Inserted)
+}
+
+/// Whether an inventory item with this SKU exists
+pub fn item_sku_exists(conn: &Connection, sku: &str) -> Result<bool, AppError> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM inventory_items WHERE sku = ?1)",
+        params![sku],
+        |row| row.get(0),
+    )?)
+}
+
+/// Length of generated internal SKUs, prefix included; existing hand-made codes look like 00001036
+pub const INTERNAL_SKU_LENGTH: usize = 8;
+
+/// Next internal SKU: `prefix` followed by a zero-padded sequence, 8 characters in all
+/// (e.g. 00001037). The `sku_counters` row of the prefix is bumped in a write transaction, so
+/// concurrent callers get distinct, increasing codes. A new prefix continues after the highest
+/// code of that shape already used by a stock card or item, and codes someone has since typed in
+/// by hand are skipped.
+pub fn generate_internal_sku(conn: &Connection, prefix: &str) -> Result<String, AppError> {
+    let prefix = prefix.trim().to_uppercase();
+    if prefix.len() >= INTERNAL_SKU_LENGTH || !prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::Validation(format!("Geçersiz SKU öneki: {}", prefix)));
+    }
+    let width = INTERNAL_SKU_LENGTH - prefix.len();
+    let shape = format!("{}{}", prefix, "[0-9]".repeat(width));
+
+    let tx = rusqlite::Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    loop {
+        let seq: i64 = tx.query_row(
+            "INSERT INTO sku_counters (prefix, last_seq)
+             VALUES (?1, COALESCE((
+                 SELECT MAX(CAST(substr(code, ?2) AS INTEGER))
+                 FROM (SELECT barcode AS code FROM stock_cards UNION ALL SELECT sku FROM inventory_items)
+                 WHERE code GLOB ?3
+             ), 0) + 1)
+             ON CONFLICT(prefix) DO UPDATE SET last_seq = last_seq + 1
+             RETURNING last_seq",
+            params![prefix, prefix.len() as i64 + 1, shape],
+            |row| row.get(0),
+        )?;
+        if seq >= 10i64.pow(width as u32) {
+            return Err(AppError::Validation(format!("{} önekli SKU numaraları tükendi", prefix)));
+        }
+
+        let sku = format!("{}{:0width$}", prefix, seq, width = width);
+        let taken: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM stock_cards WHERE barcode = ?1) OR EXISTS(SELECT 1 FROM inventory_items WHERE sku = ?1)",
+            params![sku],
+            |row| row.get(0),
+        )?;
+        if !taken {
+            tx.commit()?;
+            return Ok(sku);
+        }
+    }
+}
+
 /// Add a new inventory item with validation
+///
+/// A duplicate SKU (or id) returns `AppError::Conflict`.
 pub fn add_item(conn: &Connection, item: &InventoryItem) -> Result<(), AppError> {
     // Validate inputs
     validate_sku(&item.sku).map_err(AppError::Validation)?;
     validate_price(item.price).map_err(AppError::Validation)?;
-    validate_quantity(item.quantity).map_err(AppError::Validation)?;
+    validate_quantity(item.quantity, &item.unit).map_err(AppError::Validation)?;
+    validate_min_stock_level(item.min_stock_level).map_err(AppError::Validation)?;
+
+    // Cheap check first; the UNIQUE constraint below still catches a concurrent insert
+    if item_sku_exists(conn, &item.sku)? {
+        return Err(AppError::Conflict { field: "sku".to_string(), value: item.sku.clone() });
+    }
 
     let ai_tags_json = item
         .ai_tags
@@ -96,8 +373,8 @@ pub fn add_item(conn: &Connection, item: &InventoryItem) -> Result<(), AppError>
     conn.execute(
         "INSERT INTO inventory_items
          (id, sku, name, category, quantity, location, price, cost_price,
-          image, description, ai_tags, last_updated, currency, supplier_id, brand)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+          image, description, ai_tags, last_updated, currency, supplier_id, brand, min_stock_level, unit)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
         params![
             item.id,
             item.sku,
@@ -113,17 +390,22 @@ pub fn add_item(conn: &Connection, item: &InventoryItem) -> Result<(), AppError>
             item.last_updated,
             item.currency,
             item.supplier_id,
-            item.brand
+            item.brand,
+            item.min_stock_level,
+            units::normalize_unit(&item.unit)
         ],
-    )?;
+    )
+    .map_err(|e| classify_insert_error(e, item))?;
 
     Ok(())
 }
 
-/// Update existing inventory item
+/// Update existing inventory item. The unit is kept; it follows the stock card.
 pub fn update_item(conn: &Connection, item: &InventoryItem) -> Result<(), AppError> {
     validate_price(item.price).map_err(AppError::Validation)?;
-    validate_quantity(item.quantity).map_err(AppError::Validation)?;
+    let unit = item_unit(conn, &item.sku)?.unwrap_or_else(|| item.unit.clone());
+    validate_quantity(item.quantity, &unit).map_err(AppError::Validation)?;
+    validate_min_stock_level(item.min_stock_level).map_err(AppError::Validation)?;
 
     let ai_tags_json = item
         .ai_tags
@@ -134,8 +416,9 @@ pub fn update_item(conn: &Connection, item: &InventoryItem) -> Result<(), AppErr
         "UPDATE inventory_items
          SET name = ?1, category = ?2, quantity = ?3, location = ?4,
              price = ?5, cost_price = ?6, image = ?7, description = ?8,
-             ai_tags = ?9, last_updated = ?10, currency = ?11, supplier_id = ?12, brand = ?13
-         WHERE sku = ?14",
+             ai_tags = ?9, last_updated = ?10, currency = ?11, supplier_id = ?12, brand = ?13,
+             min_stock_level = ?14
+         WHERE sku = ?15",
         params![
             item.name,
             item.category,
@@ -150,6 +433,7 @@ pub fn update_item(conn: &Connection, item: &InventoryItem) -> Result<(), AppErr
             item.currency,
             item.supplier_id,
             item.brand,
+            item.min_stock_level,
             item.sku
         ],
     )?;
@@ -161,9 +445,12 @@ pub fn update_item(conn: &Connection, item: &InventoryItem) -> Result<(), AppErr
     Ok(())
 }
 
-/// Delete item by SKU
+/// Move an item to the trash; it is hidden from lists until restored or purged
 pub fn delete_item(conn: &Connection, sku: &str) -> Result<(), AppError> {
-    let affected = conn.execute("DELETE FROM inventory_items WHERE sku = ?1", [sku])?;
+    let affected = conn.execute(
+        "UPDATE inventory_items SET deleted_at = ?1 WHERE sku = ?2 AND deleted_at IS NULL",
+        params![chrono::Utc::now().to_rfc3339(), sku],
+    )?;
 
     if affected == 0 {
         return Err(AppError::NotFound(format!("Urun bulunamadi: {}", sku)));
@@ -172,16 +459,128 @@ pub fn delete_item(conn: &Connection, sku: &str) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Take an item back out of the trash
+pub fn restore_item(conn: &Connection, sku: &str) -> Result<InventoryItem, AppError> {
+    let affected = conn.execute(
+        "UPDATE inventory_items SET deleted_at = NULL WHERE sku = ?1 AND deleted_at IS NOT NULL",
+        [sku],
+    )?;
+
+    if affected == 0 {
+        return Err(AppError::NotFound(format!("Cop kutusunda urun bulunamadi: {}", sku)));
+    }
+
+    get_item_by_sku(conn, sku)?.ok_or_else(|| AppError::NotFound(format!("Urun bulunamadi: {}", sku)))
+}
+
+/// Items in the trash, most recently deleted first
+pub fn list_deleted_items(conn: &Connection) -> Result<Vec<DeletedItem>, AppError> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}, deleted_at FROM inventory_items WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+        ITEM_COLUMNS
+    ))?;
+
+    let items = stmt
+        .query_map([], |row| Ok(DeletedItem { item: item_from_row(row)?, deleted_at: row.get(16)? }))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(items)
+}
+
+/// Permanently remove items that have been in the trash for more than `older_than_days` days,
+/// together with their lots, attributes and bundle rows. Returns the number of items removed.
+pub fn purge_deleted_items(conn: &mut Connection, older_than_days: i64) -> Result<usize, AppError> {
+    if older_than_days < 0 {
+        return Err(AppError::Validation("Gun sayisi negatif olamaz".to_string()));
+    }
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(older_than_days)).to_rfc3339();
+
+    let tx = conn.transaction()?;
+    let purged: Vec<(String, String)> = {
+        let mut stmt = tx.prepare(
+            "SELECT id, sku FROM inventory_items WHERE deleted_at IS NOT NULL AND deleted_at <= ?1",
+        )?;
+        let rows = stmt.query_map([&cutoff], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    for (id, sku) in &purged {
+        tx.execute("DELETE FROM inventory_lots WHERE product_id = ?1", [id])?;
+        tx.execute("DELETE FROM item_attributes WHERE item_id = ?1", [id])?;
+        tx.execute(
+            "DELETE FROM product_components WHERE parent_sku = ?1 OR component_sku = ?1",
+            [sku],
+        )?;
+        tx.execute("DELETE FROM inventory_items WHERE id = ?1", [id])?;
+    }
+    tx.commit()?;
+
+    Ok(purged.len())
+}
+
+/// Activity logged for a stock count correction
+pub const ACTIVITY_ADJUSTMENT: &str = "ADJUSTMENT";
+
+/// Set an item's stock to a counted quantity, logging the signed difference and the reason and
+/// queueing it for the other devices. Returns the difference (0 when nothing changed).
+pub fn adjust_stock(conn: &mut Connection, sku: &str, new_quantity: f64, reason: &str) -> Result<f64, AppError> {
+    if new_quantity < 0.0 {
+        return Err(AppError::Validation(format!("Stok miktari negatif olamaz: {}", new_quantity)));
+    }
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::Validation("Stok duzeltmesi icin bir neden girin".to_string()));
+    }
+
+    let tx = conn.transaction()?;
+    let (id, name, current, unit): (String, String, f64, String) = tx
+        .query_row(
+            "SELECT id, name, quantity, unit FROM inventory_items WHERE sku = ?1 AND deleted_at IS NULL",
+            [sku],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Urun bulunamadi: {}", sku)))?;
+    validate_quantity(new_quantity, &unit).map_err(AppError::Validation)?;
+
+    let delta = units::round_quantity(new_quantity - current);
+    if delta == 0.0 {
+        return Ok(0.0);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    tx.execute(
+        "UPDATE inventory_items SET quantity = ?1, last_updated = ?2 WHERE id = ?3",
+        params![new_quantity, &now, &id],
+    )?;
+    tx.execute(
+        "INSERT INTO activity_log (action_type, description, item_id, item_name, quantity_change, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            ACTIVITY_ADJUSTMENT,
+            format!("Stok duzeltmesi ({} -> {}): {}", current, new_quantity, reason),
+            &id,
+            &name,
+            delta,
+            &now
+        ],
+    )?;
+    let action = if delta > 0.0 { ActionType::STOCK_IN } else { ActionType::STOCK_OUT };
+    sync::queue_transaction(&tx, action, Some(sku), Some(&name), delta, Some(current), Some(new_quantity), Some(reason))?;
+    tx.commit()?;
+
+    Ok(delta)
+}
+
 /// Update quantity by delta (can be negative for sales)
 pub fn update_quantity(
     conn: &Connection,
     sku: &str,
-    delta: i32,
+    delta: f64,
 ) -> Result<Option<InventoryItem>, AppError> {
     let now = chrono::Utc::now().to_rfc3339();
 
     // First get current quantity to check bounds
-    let current_qty: Option<i32> = conn
+    let current_qty: Option<f64> = conn
         .query_row(
             "SELECT quantity FROM inventory_items WHERE sku = ?1",
             [sku],
@@ -194,7 +593,7 @@ pub fn update_quantity(
         None => return Ok(None), // Item not found
     };
 
-    let new_qty = (current_qty + delta).max(0); // Prevent negative
+    let new_qty = units::round_quantity((current_qty + delta).max(0.0)); // Prevent negative
 
     conn.execute(
         "UPDATE inventory_items SET quantity = ?1, last_updated = ?2 WHERE sku = ?3",
@@ -205,61 +604,160 @@ pub fn update_quantity(
     get_item_by_sku(conn, sku)
 }
 
-/// Get dashboard statistics
-pub fn get_dashboard_stats(conn: &Connection) -> Result<DashboardStats, AppError> {
+/// First and last day of the current month in shop time
+fn current_month(conn: &Connection) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let today = chrono::Utc::now().with_timezone(&get_timezone_offset(conn)?).date_naive();
+    let start = today.with_day(1).unwrap_or(today);
+    let end = start
+        .checked_add_months(chrono::Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(today);
+    Ok((start, end))
+}
+
+/// Get dashboard statistics.
+///
+/// Item, quantity and low-stock counts are current snapshots. Revenue (sales minus returns),
+/// sale/return counts and the average basket cover `start_date`..=`end_date` (`YYYY-MM-DD`,
+/// shop time, default: this month); voided transactions are left out. Lines of items priced in
+/// another currency are converted to TRY (`currency`).
+pub fn get_dashboard_stats(
+    conn: &Connection,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<DashboardStats, AppError> {
+    let (month_start, month_end) = current_month(conn)?;
+    let start = start_date.filter(|d| !d.is_empty()).map(parse_report_date).transpose()?.unwrap_or(month_start);
+    let end = end_date.filter(|d| !d.is_empty()).map(parse_report_date).transpose()?.unwrap_or(month_end);
+    if end < start {
+        return Err(AppError::Validation("Bitiş tarihi başlangıç tarihinden önce olamaz".to_string()));
+    }
+
     let total_items: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM inventory_items",
+        "SELECT COUNT(*) FROM inventory_items WHERE deleted_at IS NULL",
         [],
         |row| row.get(0),
     )?;
 
-    let total_quantity: i32 = conn.query_row(
-        "SELECT COALESCE(SUM(quantity), 0) FROM inventory_items",
+    let total_quantity: f64 = conn.query_row(
+        "SELECT COALESCE(SUM(quantity), 0) FROM inventory_items WHERE deleted_at IS NULL",
         [],
         |row| row.get(0),
     )?;
 
     let low_stock_count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM inventory_items WHERE quantity < 10",
+        "SELECT COUNT(*) FROM inventory_items WHERE quantity < min_stock_level AND deleted_at IS NULL",
         [],
         |row| row.get(0),
     )?;
 
-    let total_revenue: f64 = conn.query_row(
-        "SELECT COALESCE(SUM(total), 0) FROM transactions WHERE transaction_type = 'SALE'",
-        [],
-        |row| row.get(0),
-    )?;
+    let tz = get_timezone_offset(conn)?;
+    let mut rates = currency::RateTable::load(conn, chrono::Utc::now())?;
+    let item_currencies = currency::foreign_item_currencies(conn)?;
+    let mut sales_total = 0.0;
+    let mut returns_total = 0.0;
+    let mut sale_count = 0;
+    let mut return_count = 0;
+    {
+        // UTC timestamps can fall one day either side of the local date
+        let mut stmt = conn.prepare(
+            "SELECT created_at, transaction_type, total, items FROM transactions
+             WHERE transaction_type IN ('SALE', 'RETURN') AND status != ?1 AND created_at >= ?2 AND created_at < ?3",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                TRANSACTION_STATUS_VOIDED,
+                (start - chrono::Duration::days(1)).to_string(),
+                (end + chrono::Duration::days(2)).to_string()
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            },
+        )?;
+        for row in rows {
+            let (created_at, transaction_type, total, items_json) = row?;
+            let Ok(created) = chrono::DateTime::parse_from_rfc3339(&created_at) else {
+                continue;
+            };
+            let date = created.with_timezone(&tz).date_naive();
+            if date < start || date > end {
+                continue;
+            }
+            let total = if item_currencies.is_empty() {
+                total
+            } else {
+                let items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
+                rates.transaction_total_to_base(total, &items, &item_currencies)
+            };
+            // Returns are stored with a negative total
+            if transaction_type == "RETURN" {
+                returns_total += total.abs();
+                return_count += 1;
+            } else {
+                sales_total += total;
+                sale_count += 1;
+            }
+        }
+    }
 
     Ok(DashboardStats {
         total_items,
         total_quantity,
         low_stock_count,
-        total_revenue,
+        total_revenue: tax::round_money(sales_total - returns_total),
+        sale_count,
+        return_count,
+        average_basket: if sale_count > 0 { tax::round_money(sales_total / sale_count as f64) } else { 0.0 },
+        period_start: start.to_string(),
+        period_end: end.to_string(),
+        currency_warnings: rates.warnings(),
     })
 }
 
-/// Get category statistics
+/// Item count, quantity and stock value (in TRY) per category, highest value first
 pub fn get_category_stats(conn: &Connection) -> Result<Vec<CategoryStats>, AppError> {
     let mut stmt = conn.prepare_cached(
-        "SELECT category, COUNT(*) as count, SUM(quantity) as total_qty,
-                SUM(price * quantity) as total_value
+        "SELECT category, currency, COUNT(*), COALESCE(SUM(quantity), 0), COALESCE(SUM(price * quantity), 0)
          FROM inventory_items
-         GROUP BY category
-         ORDER BY total_value DESC",
+         WHERE deleted_at IS NULL
+         GROUP BY category, currency",
     )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, i32>(2)?,
+            row.get::<_, f64>(3)?,
+            row.get::<_, f64>(4)?,
+        ))
+    })?;
+
+    let mut rates = currency::RateTable::load(conn, chrono::Utc::now())?;
+    let mut by_category: HashMap<String, CategoryStats> = HashMap::new();
+    for row in rows {
+        let (category, item_currency, count, quantity, value) = row?;
+        let category = category.unwrap_or_default();
+        let stats = by_category.entry(category.clone()).or_insert_with(|| CategoryStats {
+            category,
+            count: 0,
+            total_quantity: 0.0,
+            total_value: 0.0,
+        });
+        stats.count += count;
+        stats.total_quantity += quantity;
+        stats.total_value += rates.convert_to_base(value, item_currency.as_deref());
+    }
 
-    let stats = stmt
-        .query_map([], |row| {
-            Ok(CategoryStats {
-                category: row.get(0)?,
-                count: row.get(1)?,
-                total_quantity: row.get::<_, Option<i32>>(2)?.unwrap_or(0),
-                total_value: row.get::<_, Option<f64>>(3)?.unwrap_or(0.0),
-            })
-        })?
-        .collect::<Result<Vec<_>, _>>()?;
-
+    let mut stats: Vec<CategoryStats> = by_category.into_values().collect();
+    for s in &mut stats {
+        s.total_value = tax::round_money(s.total_value);
+    }
+    stats.sort_by(|a, b| b.total_value.total_cmp(&a.total_value).then_with(|| a.category.cmp(&b.category)));
     Ok(stats)
 }
 
@@ -311,84 +809,187 @@ pub fn export_to_csv(conn: &Connection) -> Result<String, AppError> {
     Ok(csv)
 }
 
-/// Import from CSV content
-pub fn import_from_csv(conn: &Connection, csv_content: &str) -> Result<String, AppError> {
-    let now = chrono::Utc::now().to_rfc3339();
-    let mut imported = 0;
-    let mut updated = 0;
-    let mut errors = 0;
+/// Stock cards joined with their inventory record, category name and lot status.
+/// Inventory items without a stock card are appended so the export covers everything.
+pub fn get_catalog_rows(conn: &Connection) -> Result<Vec<CatalogRow>, AppError> {
+    let lot_columns = "(SELECT COUNT(*) FROM inventory_lots l WHERE l.product_id = i.id),
+                (SELECT COUNT(*) FROM inventory_lots l WHERE l.product_id = i.id AND l.quantity > 0)";
+
+    let map_row = |row: &rusqlite::Row| -> rusqlite::Result<CatalogRow> {
+        Ok(CatalogRow {
+            stock_card_id: row.get(0)?,
+            barcode: row.get(1)?,
+            name: row.get(2)?,
+            brand: row.get(3)?,
+            unit: row.get(4)?,
+            category: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            inventory_id: row.get(6)?,
+            quantity: row.get::<_, Option<f64>>(7)?.unwrap_or(0.0),
+            location: row.get(8)?,
+            price: row.get(9)?,
+            cost_price: row.get(10)?,
+            lot_count: row.get::<_, Option<i32>>(11)?.unwrap_or(0),
+            open_lot_count: row.get::<_, Option<i32>>(12)?.unwrap_or(0),
+        })
+    };
 
-    for (line_num, line) in csv_content.lines().enumerate() {
-        // Skip header line
-        if line_num == 0 {
-            continue;
-        }
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.id, s.barcode, s.name, s.brand, s.unit,
+            COALESCE(c.name, i.category),
+            i.id, i.quantity, i.location, i.price, i.cost_price,
+            {}
+         FROM stock_cards s
+         LEFT JOIN inventory_items i ON i.sku = s.barcode AND i.deleted_at IS NULL
+         LEFT JOIN categories c ON c.id = s.category_id
+         ORDER BY s.name",
+        lot_columns
+    ))?;
+    let mut rows = stmt.query_map([], map_row)?.collect::<Result<Vec<_>, _>>()?;
+
+    // Inventory items that have no stock card
+    let mut orphan_stmt = conn.prepare(&format!(
+        "SELECT NULL, i.sku, i.name, i.brand, i.unit,
+            i.category,
+            i.id, i.quantity, i.location, i.price, i.cost_price,
+            {}
+         FROM inventory_items i
+         WHERE i.sku NOT IN (SELECT barcode FROM stock_cards) AND i.deleted_at IS NULL
+         ORDER BY i.name",
+        lot_columns
+    ))?;
+    rows.extend(orphan_stmt.query_map([], map_row)?.collect::<Result<Vec<_>, _>>()?);
+
+    Ok(rows)
+}
 
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+/// Catalog rows as CSV (Excel uyumlu: BOM + noktalı virgül). Fields containing the delimiter,
+/// quotes or line breaks are quoted, so `read_csv_import_rows` reads them back unchanged.
+pub fn catalog_to_csv(rows: &[CatalogRow]) -> Result<String, AppError> {
+    let csv_error = |e: csv::Error| AppError::Internal(format!("CSV yazilamadi: {}", e));
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(import_staging::DEFAULT_CSV_DELIMITER)
+        .from_writer("\u{FEFF}".as_bytes().to_vec()); // UTF-8 BOM for Excel
+    writer
+        .write_record([
+            "Stok Kart ID", "Barkod", "Ürün Adı", "Marka", "Birim", "Kategori", "Envanter ID", "Miktar", "Konum",
+            "Satış Fiyatı", "Alış Fiyatı", "Lot Sayısı", "Açık Lot Sayısı",
+        ])
+        .map_err(csv_error)?;
+
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let money = |value: Option<f64>| value.map(|v| format!("{:.2}", v)).unwrap_or_default();
+
+    for row in rows {
+        writer
+            .write_record([
+                text(&row.stock_card_id),
+                row.barcode.clone(),
+                row.name.clone(),
+                text(&row.brand),
+                text(&row.unit),
+                row.category.clone(),
+                text(&row.inventory_id),
+                row.quantity.to_string(),
+                text(&row.location),
+                money(row.price),
+                money(row.cost_price),
+                row.lot_count.to_string(),
+                row.open_lot_count.to_string(),
+            ])
+            .map_err(csv_error)?;
+    }
 
-        // Try semicolon first, then comma
-        let parts: Vec<&str> = if line.contains(';') {
-            line.split(';').collect()
-        } else {
-            line.split(',').collect()
-        };
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| AppError::Internal(format!("CSV yazilamadi: {}", e)))?;
+    String::from_utf8(bytes).map_err(|e| AppError::Internal(format!("CSV yazilamadi: {}", e)))
+}
 
-        if parts.len() < 6 {
-            errors += 1;
-            continue;
-        }
+/// Import from CSV content: rows are upserted by SKU, rejected lines are reported in `errors`
+pub fn import_from_csv(conn: &Connection, csv_content: &str, delimiter: u8) -> Result<CsvImportResult, AppError> {
+    upsert_import_rows(conn, import_staging::read_csv_import_rows(csv_content, delimiter))
+}
 
-        let sku = parts[1].trim();
-        let name = parts[2].trim();
-        let category = parts[3].trim();
-        let quantity: i32 = parts[4].trim().parse().unwrap_or(0);
-        let location = parts[5].trim();
-        let price: f64 = if parts.len() > 6 {
-            parts[6].trim().replace(',', ".").parse().unwrap_or(0.0)
-        } else {
-            0.0
+/// Upsert parsed import rows by SKU in one transaction; rejected rows are counted as skipped
+/// and reported with their line number
+pub fn upsert_import_rows(conn: &Connection, rows: ImportRows) -> Result<CsvImportResult, AppError> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut result = CsvImportResult::default();
+
+    let tx = conn.unchecked_transaction()?;
+    for (line, row) in rows {
+        let row = match row {
+            Ok(row) => row,
+            Err(reason) => {
+                result.skipped += 1;
+                result.errors.push(format!("Satir {}: {}", line, reason));
+                continue;
+            }
         };
 
-        // Check if item exists
-        let exists: bool = conn
+        let unit: Option<String> = tx
             .query_row(
-                "SELECT 1 FROM inventory_items WHERE sku = ?1",
-                [sku],
-                |_| Ok(true),
+                "SELECT unit FROM inventory_items WHERE sku = ?1",
+                [&row.sku],
+                |r| r.get(0),
             )
-            .optional()?
-            .unwrap_or(false);
-
-        if exists {
-            conn.execute(
-                "UPDATE inventory_items SET name = ?1, category = ?2, quantity = ?3,
-                 location = ?4, price = ?5, last_updated = ?6 WHERE sku = ?7",
-                params![name, category, quantity, location, price, now, sku],
+            .optional()?;
+        if let Err(reason) = validate_quantity(row.quantity, unit.as_deref().unwrap_or("ADET")) {
+            result.skipped += 1;
+            result.errors.push(format!("Satir {}: {} ({})", line, reason, row.sku));
+            continue;
+        }
+
+        if unit.is_some() {
+            tx.execute(
+                "UPDATE inventory_items SET name = ?1, category = ?2, quantity = ?3, location = ?4,
+                 price = ?5, cost_price = ?6, currency = ?7, last_updated = ?8 WHERE sku = ?9",
+                params![
+                    row.name,
+                    row.category,
+                    row.quantity,
+                    row.location,
+                    row.price,
+                    row.cost_price,
+                    row.currency,
+                    now,
+                    row.sku
+                ],
             )?;
-            updated += 1;
         } else {
             let id = uuid::Uuid::new_v4().to_string();
-            conn.execute(
-                "INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, last_updated)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                params![id, sku, name, category, quantity, location, price, now],
+            tx.execute(
+                "INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, cost_price,
+                 image, description, ai_tags, last_updated, currency)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, '', '', '[]', ?9, ?10)",
+                params![
+                    id,
+                    row.sku,
+                    row.name,
+                    row.category,
+                    row.quantity,
+                    row.location,
+                    row.price,
+                    row.cost_price,
+                    now,
+                    row.currency
+                ],
             )?;
-            imported += 1;
         }
+        result.imported += 1;
     }
+    tx.commit()?;
 
-    Ok(format!(
-        "Import tamamlandi: {} yeni, {} guncellendi, {} hata",
-        imported, updated, errors
-    ))
+    Ok(result)
 }
 
-/// Clear all inventory data
+/// Clear all inventory data: items, their attributes, sales and the activity log
 pub fn clear_inventory(conn: &Connection) -> Result<(), AppError> {
     conn.execute("DELETE FROM inventory_items", [])?;
+    conn.execute("DELETE FROM item_attributes", [])?;
+    conn.execute("DELETE FROM transactions", [])?;
+    conn.execute("DELETE FROM sale_lot_consumptions", [])?;
+    conn.execute("DELETE FROM activity_log", [])?;
     Ok(())
 }
 
@@ -447,8 +1048,11 @@ pub fn check_and_seed_initial_data(conn: &Connection) -> Result<(), AppError> {
     // 3. Read and Import
     match std::fs::read_to_string(csv_path) {
         Ok(content) => {
-            match import_from_csv(conn, &content) {
-                Ok(msg) => println!("[AutoSeed] Success: {}", msg),
+            match import_from_csv(conn, &content, import_staging::DEFAULT_CSV_DELIMITER) {
+                Ok(result) => println!(
+                    "[AutoSeed] Success: {} urun aktarildi, {} satir atlandi",
+                    result.imported, result.skipped
+                ),
                 Err(e) => eprintln!("[AutoSeed] Import failed: {}", e),
             }
         },
@@ -458,6 +1062,155 @@ pub fn check_and_seed_initial_data(conn: &Connection) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Activity logged when a sale needs more than the product's lots hold
+pub const ACTIVITY_LOT_SHORTFALL: &str = "LOT_SHORTFALL";
+
+/// Order sales take lots in: earliest expiry first (FEFO), then lots without an expiry date
+/// oldest receipt first (FIFO)
+pub const LOT_CONSUMPTION_ORDER: &str =
+    "expiry_date IS NULL, expiry_date ASC, receipt_date ASC, created_at ASC";
+
+/// Reject a sale that takes more of a SKU than is in stock, unless negative stock is allowed
+/// (`settings::ALLOW_NEGATIVE_STOCK_SETTING`). Lines of the same SKU and bundle components are
+/// added up; SKUs without an inventory row (IND, TAHSILAT) carry no stock and are skipped.
+fn check_stock_available(tx: &Connection, cart_items: &[CartItem]) -> Result<(), AppError> {
+    if settings::is_enabled(tx, settings::ALLOW_NEGATIVE_STOCK_SETTING)? {
+        return Ok(());
+    }
+
+    let mut needed: Vec<(String, f64)> = Vec::new();
+    for item in cart_items.iter().filter(|item| item.price >= 0.0) {
+        for (sku, quantity) in bundles::expand_stock_movements(tx, &item.sku, item.cart_quantity)? {
+            match needed.iter_mut().find(|(needed_sku, _)| *needed_sku == sku) {
+                Some((_, total)) => *total += quantity,
+                None => needed.push((sku, quantity)),
+            }
+        }
+    }
+
+    for (sku, quantity) in needed {
+        let stock: Option<(f64, String)> = tx
+            .query_row(
+                "SELECT quantity, unit FROM inventory_items WHERE sku = ?1",
+                params![&sku],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((available, unit)) = stock else {
+            continue;
+        };
+        if units::round_quantity(quantity) > units::round_quantity(available) {
+            return Err(AppError::Validation(format!(
+                "Yetersiz stok: {} (mevcut: {}, istenen: {})",
+                sku,
+                units::format_quantity(available, &unit),
+                units::format_quantity(quantity, &unit)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Change one SKU's stock for a sale or return; sales also consume lots FEFO/FIFO.
+/// A sale may leave the quantity negative only when `check_stock_available` let it through.
+/// Returns the cost of the consumed goods (0 for returns).
+fn apply_stock_movement(
+    tx: &Connection,
+    transaction_id: &str,
+    sku: &str,
+    quantity: f64,
+    transaction_type: &str,
+    now: &str,
+) -> Result<f64, AppError> {
+    let current_qty: f64 = tx.query_row(
+        "SELECT quantity FROM inventory_items WHERE sku = ?1",
+        params![sku],
+        |row| row.get(0),
+    ).unwrap_or(0.0);
+
+    let new_qty = if transaction_type == "RETURN" {
+        current_qty + quantity
+    } else {
+        current_qty - quantity
+    };
+    let final_qty = units::round_quantity(new_qty);
+
+    tx.execute(
+        "UPDATE inventory_items SET quantity = ?1, last_updated = ?2 WHERE sku = ?3",
+        params![final_qty, now, sku],
+    )?;
+
+    if transaction_type != "SALE" {
+        return Ok(0.0);
+    }
+
+    // FEFO/FIFO lot consumption (see LOT_CONSUMPTION_ORDER), each lot at its own buy price
+    let item: Option<(String, String, Option<f64>)> = tx.query_row(
+        "SELECT id, name, cost_price FROM inventory_items WHERE sku = ?1",
+        params![sku],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).optional()?;
+
+    let Some((product_id, name, cost_price)) = item else {
+        return Ok(0.0);
+    };
+
+    let lots: Vec<(String, f64, f64)> = {
+        let mut lot_stmt = tx.prepare_cached(
+            &format!(
+                "SELECT id, quantity, buy_price FROM inventory_lots
+                 WHERE product_id = ?1 AND quantity > 0
+                 ORDER BY {}",
+                LOT_CONSUMPTION_ORDER
+            ),
+        )?;
+        let lots = lot_stmt
+            .query_map(params![&product_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        lots
+    };
+
+    let mut remaining_qty = quantity;
+    let mut consumed_cost = 0.0;
+
+    for (lot_id, lot_qty, buy_price) in lots {
+        if remaining_qty <= 0.0 { break; }
+
+        let consume_qty = units::round_quantity(remaining_qty.min(lot_qty));
+
+        tx.execute(
+            "UPDATE inventory_lots SET quantity = quantity - ?1 WHERE id = ?2",
+            params![consume_qty, &lot_id],
+        )?;
+        tx.execute(
+            "INSERT INTO sale_lot_consumptions (transaction_id, lot_id, product_id, quantity, unit_cost, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![transaction_id, &lot_id, &product_id, consume_qty, buy_price, now],
+        )?;
+
+        consumed_cost += consume_qty * buy_price;
+        remaining_qty = units::round_quantity(remaining_qty - consume_qty);
+    }
+
+    // Not enough lots (legacy stock without receipts): the rest is costed at the item's cost price
+    if remaining_qty > 0.0 {
+        consumed_cost += remaining_qty * cost_price.unwrap_or(0.0);
+        tx.execute(
+            "INSERT INTO activity_log (action_type, description, item_id, item_name, quantity_change, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                ACTIVITY_LOT_SHORTFALL,
+                format!("Parti stoku yetersiz: {} adet parti disi satildi (islem {})", remaining_qty, transaction_id),
+                &product_id,
+                &name,
+                -remaining_qty,
+                now
+            ],
+        )?;
+    }
+
+    Ok(consumed_cost)
+}
+
 /// Process a sale transaction with strict security checks and atomic updates
 pub fn process_sale_transaction(
     conn: &mut Connection,
@@ -467,25 +1220,58 @@ pub fn process_sale_transaction(
     note: Option<String>,
     customer_id: Option<String>,
 ) -> Result<Transaction, AppError> {
+    process_sale_with_payments(conn, cart_items, payment_method, None, None, transaction_type, note, customer_id)
+}
+
+/// `process_sale_transaction` for a sale that may be paid with several methods. When `payments` is
+/// given it replaces `payment_method`: the portions must add up to the total, and a sale with more
+/// than one is stored as `SPLIT` with its portions in `transaction_payments`.
+///
+/// Lines may carry their own discount; `discount` is then taken off what is left of the whole
+/// ticket. The total is the net amount and the breakdown is stored with the transaction.
+pub fn process_sale_with_payments(
+    conn: &mut Connection,
+    cart_items: Vec<CartItem>,
+    payment_method: String,
+    payments: Option<Vec<Payment>>,
+    discount: Option<SaleDiscount>,
+    transaction_type: String,
+    note: Option<String>,
+    customer_id: Option<String>,
+) -> Result<Transaction, AppError> {
+    let payments = payments.filter(|p| !p.is_empty());
+    if payments.is_some() && transaction_type != "SALE" {
+        return Err(AppError::Validation("Bölünmüş ödeme yalnızca satışlarda kullanılabilir".to_string()));
+    }
+    if discount.is_some() && transaction_type != "SALE" {
+        return Err(AppError::Validation("Fiş indirimi yalnızca satışlarda kullanılabilir".to_string()));
+    }
+    let mut payment_method = finance::canonical_payment_method(&payment_method);
+
     // Start atomic transaction
     let tx = conn.transaction()?;
 
-    let mut total = 0.0;
+    let mut subtotal = 0.0;
+    let mut line_discount = 0.0;
+    let mut taxed_lines = Vec::with_capacity(cart_items.len());
     
     // 1. Validate Items and Calculate Total Securely
     for item in &cart_items {
-        if item.cart_quantity <= 0 {
+        if item.cart_quantity <= 0.0 {
              return Err(AppError::Validation(format!("Geçersiz miktar: {} (Miktar > 0 olmalı)", item.name)));
         }
+        tax::validate_tax_rate(item.tax_rate)?;
 
-        // Fetch real price from DB
-        let db_price: Option<f64> = tx.query_row(
-            "SELECT price FROM inventory_items WHERE sku = ?1",
+        // Fetch real price and unit from DB
+        let db_item: Option<(f64, String)> = tx.query_row(
+            "SELECT price, unit FROM inventory_items WHERE sku = ?1",
             params![&item.sku],
-            |row| row.get(0)
+            |row| Ok((row.get(0)?, row.get(1)?))
         ).optional()?;
 
-        let final_price = if let Some(real_price) = db_price {
+        let final_price = if let Some((real_price, unit)) = db_item {
+            validate_quantity(item.cart_quantity, &unit)
+                .map_err(|e| AppError::Validation(format!("{}: {}", item.name, e)))?;
             // Check for price manipulation (allow 0.01 tolerance)
             if (item.price - real_price).abs() > 0.01 {
                 return Err(AppError::Validation(format!("GÜVENLİK UYARISI: Fiyat uyuşmazlığı! {} için sistem fiyatı: {:.2}, gönderilen fiyat: {:.2}", item.name, real_price, item.price)));
@@ -501,85 +1287,73 @@ pub fn process_sale_transaction(
              }
         };
 
-        total += final_price * item.cart_quantity as f64;
+        discounts::validate_line_discount(item)?;
+        let item_discount = discounts::line_discount(item);
+        let amount = final_price * item.cart_quantity - item_discount;
+
+        subtotal += amount;
+        line_discount += item_discount;
+        taxed_lines.push((item.tax_rate, amount));
+    }
+    if transaction_type != "RETURN" {
+        check_stock_available(&tx, &cart_items)?;
     }
 
+    let ticket_discount = match &discount {
+        Some(discount) => discounts::ticket_discount(discount, subtotal)?,
+        None => 0.0,
+    };
+    let mut total = subtotal - ticket_discount;
+    let mut tax_total = tax::total_tax(&tax::tax_breakdown(discounts::spread_ticket_discount(taxed_lines, ticket_discount)));
+    let mut line_discount = tax::round_money(line_discount);
     if transaction_type == "RETURN" {
         total = -total;
+        tax_total = -tax_total;
+        line_discount = -line_discount;
     }
 
+    let split_payments = match payments {
+        Some(payments) => {
+            let mut payments = finance::validate_split_payments(&payments, total)?;
+            if payments.len() == 1 {
+                payment_method = payments.remove(0).method;
+            } else {
+                payment_method = finance::SPLIT_PAYMENT_METHOD.to_string();
+            }
+            payments
+        }
+        None => Vec::new(),
+    };
+
     let transaction_id = uuid::Uuid::new_v4().to_string();
     let created_at = chrono::Utc::now().to_rfc3339();
     let items_json = serde_json::to_string(&cart_items)?;
 
     // 2. Insert Transaction
     tx.execute(
-        "INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        params![&transaction_id, &items_json, total, &payment_method, &transaction_type, &note, &created_at, &customer_id],
+        "INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id, tax_total, line_discount, ticket_discount)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![&transaction_id, &items_json, total, &payment_method, &transaction_type, &note, &created_at, &customer_id, tax_total, line_discount, ticket_discount],
     )?;
+    finance::save_transaction_payments(&tx, &transaction_id, &split_payments)?;
 
-    // 3. Update inventory quantities & Lots
+    // 3. Update inventory quantities & Lots (bundles move their components' stock)
+    let mut cost_of_goods = 0.0;
     for item in &cart_items {
         if item.price < 0.0 {
             continue;
         }
 
-        let current_qty: i32 = tx.query_row(
-            "SELECT quantity FROM inventory_items WHERE sku = ?1",
-            params![&item.sku],
-            |row| row.get(0),
-        ).unwrap_or(0);
-
-        let new_qty = if transaction_type == "RETURN" {
-            current_qty + item.cart_quantity
-        } else {
-            current_qty - item.cart_quantity
-        };
-        let final_qty = if new_qty < 0 { 0 } else { new_qty };
+        for (sku, quantity) in bundles::expand_stock_movements(&tx, &item.sku, item.cart_quantity)? {
+            cost_of_goods += apply_stock_movement(&tx, &transaction_id, &sku, quantity, &transaction_type, &created_at)?;
+        }
+    }
 
+    if transaction_type == "SALE" {
         tx.execute(
-            "UPDATE inventory_items SET quantity = ?1, last_updated = ?2 WHERE sku = ?3",
-            params![final_qty, &created_at, &item.sku],
+            "UPDATE transactions SET cost_of_goods = ?1 WHERE id = ?2",
+            params![cost_of_goods, &transaction_id],
         )?;
-
-        // FIFO Lot Consumption (only for SALE)
-        if transaction_type == "SALE" {
-            let item_id: Option<String> = tx.query_row(
-                "SELECT id FROM inventory_items WHERE sku = ?1",
-                params![&item.sku],
-                |row| row.get(0),
-            ).optional().unwrap_or(None);
-
-            if let Some(product_id) = item_id {
-                let mut remaining_qty = item.cart_quantity;
-                
-                let mut lot_stmt = tx.prepare(
-                    "SELECT id, quantity FROM inventory_lots WHERE product_id = ?1 AND quantity > 0 ORDER BY created_at ASC"
-                )?;
-                
-                let lots: Vec<(String, i32)> = lot_stmt.query_map(params![&product_id], |row| {
-                    Ok((row.get(0)?, row.get(1)?))
-                })?
-                .filter_map(|r| r.ok())
-                .collect();
-                // Drop statement to release borrow
-                drop(lot_stmt);
-
-                for (lot_id, lot_qty) in lots {
-                    if remaining_qty <= 0 { break; }
-
-                    let consume_qty = std::cmp::min(remaining_qty, lot_qty);
-                    let new_lot_qty = lot_qty - consume_qty;
-
-                    tx.execute(
-                        "UPDATE inventory_lots SET quantity = ?1 WHERE id = ?2",
-                        params![new_lot_qty, &lot_id],
-                    )?;
-
-                    remaining_qty -= consume_qty;
-                }
-            }
-        }
     }
 
     // 4. Update Customer Balance
@@ -592,7 +1366,7 @@ pub fn process_sale_transaction(
 
         let mut should_update_balance = false;
         
-        if transaction_type == "RETURN" || payment_method == "VERESIYE" || transaction_type == "COLLECTION" {
+        if transaction_type == "RETURN" || PaymentMethod::parse(&payment_method) == PaymentMethod::Credit || transaction_type == "COLLECTION" {
              should_update_balance = true;
         }
 
@@ -621,13 +1395,667 @@ pub fn process_sale_transaction(
         total,
         payment_method,
         transaction_type,
-        status: "COMPLETED".to_string(),
+        status: TRANSACTION_STATUS_COMPLETED.to_string(),
         note,
         created_at,
         customer_id,
+        tax_total,
+        line_discount,
+        ticket_discount,
+    })
+}
+
+/// Record an expense as an EXPENSE transaction dated `date`; the amount is stored negative
+pub fn record_expense(
+    conn: &Connection,
+    description: &str,
+    amount: f64,
+    category: &str,
+    payment_method: &str,
+    date: &str,
+) -> Result<Transaction, AppError> {
+    let transaction_id = uuid::Uuid::new_v4().to_string();
+    let payment_method = finance::canonical_payment_method(payment_method);
+
+    // Expenses are negative in financial calculations
+    let total = -amount.abs();
+
+    // A single line so the expense lists like any other transaction
+    let expense_item = serde_json::json!([{
+        "id": "EXPENSE",
+        "sku": "EXPENSE",
+        "name": description,
+        "category": category,
+        "quantity": 1,
+        "price": total,
+        "cartQuantity": 1
+    }]);
+
+    conn.execute(
+        "INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at)
+         VALUES (?1, ?2, ?3, ?4, 'EXPENSE', ?5, ?6)",
+        params![transaction_id, expense_item.to_string(), total, &payment_method, description, date],
+    )?;
+
+    Ok(Transaction {
+        id: transaction_id,
+        items: serde_json::from_value(expense_item)?,
+        total,
+        payment_method,
+        transaction_type: "EXPENSE".to_string(),
+        status: TRANSACTION_STATUS_COMPLETED.to_string(),
+        note: Some(description.to_string()),
+        created_at: date.to_string(),
+        customer_id: None,
+        tax_total: 0.0,
+        line_discount: 0.0,
+        ticket_discount: 0.0,
+    })
+}
+
+/// Columns the item grid may be sorted by
+pub const ITEM_SORT_COLUMNS: &[&str] = &["name", "sku", "quantity", "price", "last_updated"];
+
+/// Columns the transaction list may be sorted by
+pub const TRANSACTION_SORT_COLUMNS: &[&str] = &["created_at", "total"];
+
+/// `ORDER BY` terms for a requested sort. The column comes from `allowed` and the direction is
+/// ASC or DESC, never the caller's text, so nothing user supplied reaches the SQL. Missing values
+/// fall back to `default`; `id` breaks ties so pages do not overlap.
+fn order_by(
+    sort_by: Option<&str>,
+    sort_dir: Option<&str>,
+    allowed: &[&str],
+    default: (&str, &str),
+) -> Result<String, AppError> {
+    let column = match sort_by.map(str::trim).filter(|c| !c.is_empty()) {
+        None => default.0,
+        Some(requested) => allowed
+            .iter()
+            .copied()
+            .find(|column| column.eq_ignore_ascii_case(requested))
+            .ok_or_else(|| AppError::Validation(format!("Geçersiz sıralama alanı: {}", requested)))?,
+    };
+    let direction = match sort_dir.map(str::trim).filter(|d| !d.is_empty()) {
+        None => default.1,
+        Some(d) if d.eq_ignore_ascii_case("asc") => "ASC",
+        Some(d) if d.eq_ignore_ascii_case("desc") => "DESC",
+        Some(d) => return Err(AppError::Validation(format!("Geçersiz sıralama yönü: {} (asc veya desc)", d))),
+    };
+    Ok(format!("{} {}, id {}", column, direction, direction))
+}
+
+/// Filters and sort of the item grid; empty values are ignored
+#[derive(Debug, Clone, Default)]
+pub struct ItemFilter {
+    /// Matched against name, SKU and description
+    pub search: Option<String>,
+    /// "Tümü" / "HEPSİ" list every category
+    pub category: Option<String>,
+    /// Variant filters, e.g. {"ambalaj": "2.5L"}
+    pub attributes: Option<HashMap<String, String>>,
+    /// One of `ITEM_SORT_COLUMNS`, name by default
+    pub sort_by: Option<String>,
+    /// "asc" or "desc", ascending by default
+    pub sort_dir: Option<String>,
+}
+
+/// Items not in the trash, one page at a time
+pub fn get_items_paginated(
+    conn: &Connection,
+    page: i32,
+    per_page: i32,
+    filter: &ItemFilter,
+) -> Result<PaginatedItemsResponse, AppError> {
+    let page = if page < 1 { 1 } else { page };
+    let per_page = if per_page < 1 { 20 } else { per_page.min(100) };
+    let offset = (page - 1) * per_page;
+    let order = order_by(filter.sort_by.as_deref(), filter.sort_dir.as_deref(), ITEM_SORT_COLUMNS, ("name", "ASC"))?;
+
+    let mut conditions: Vec<String> = vec!["deleted_at IS NULL".to_string()];
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(s) = filter.search.as_deref().filter(|s| !s.is_empty()) {
+        conditions.push("(name LIKE ? OR sku LIKE ? OR description LIKE ?)".to_string());
+        let search_pattern = format!("%{}%", s);
+        params_vec.push(Box::new(search_pattern.clone()));
+        params_vec.push(Box::new(search_pattern.clone()));
+        params_vec.push(Box::new(search_pattern));
+    }
+    if let Some(c) = filter.category.as_deref().filter(|c| !c.is_empty() && *c != "Tümü" && *c != "HEPSİ") {
+        conditions.push("category = ?".to_string());
+        params_vec.push(Box::new(c.to_string()));
+    }
+    if let Some(ref filters) = filter.attributes {
+        attributes::push_attribute_conditions(filters, "id", &mut conditions, &mut params_vec)?;
+    }
+
+    let where_clause = format!("WHERE {}", conditions.join(" AND "));
+
+    let total: i32 = {
+        let mut stmt = conn.prepare(&format!("SELECT COUNT(*) FROM inventory_items {}", where_clause))?;
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        stmt.query_row(params_refs.as_slice(), |row| row.get(0))?
+    };
+
+    let select_sql = format!(
+        "SELECT {} FROM inventory_items {} ORDER BY {} LIMIT ? OFFSET ?",
+        ITEM_COLUMNS, where_clause, order
+    );
+    params_vec.push(Box::new(per_page));
+    params_vec.push(Box::new(offset));
+
+    let mut stmt = conn.prepare(&select_sql)?;
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+    let data = stmt
+        .query_map(params_refs.as_slice(), item_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let total_pages = ((total as f64) / (per_page as f64)).ceil() as i32;
+
+    Ok(PaginatedItemsResponse {
+        data,
+        total,
+        page,
+        per_page,
+        total_pages,
     })
 }
 
+/// Filters of the transaction list; empty values are ignored
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    /// YYYY-MM-DD, inclusive
+    pub start_date: Option<String>,
+    /// YYYY-MM-DD, inclusive
+    pub end_date: Option<String>,
+    /// "ALL" lists every type
+    pub transaction_type: Option<String>,
+    pub customer_id: Option<String>,
+    /// Only transactions with a line for this SKU
+    pub item_sku: Option<String>,
+    /// One of `TRANSACTION_SORT_COLUMNS`, created_at by default
+    pub sort_by: Option<String>,
+    /// "asc" or "desc", descending by default
+    pub sort_dir: Option<String>,
+}
+
+/// Transactions, newest first unless sorted otherwise, one page at a time
+pub fn get_transactions_paginated(
+    conn: &Connection,
+    page: i32,
+    per_page: i32,
+    filter: &TransactionFilter,
+) -> Result<PaginatedTransactionsResponse, AppError> {
+    let page = if page < 1 { 1 } else { page };
+    let per_page = if per_page < 1 { 20 } else { per_page.min(100) };
+    let offset = (page - 1) * per_page;
+    let order = order_by(
+        filter.sort_by.as_deref(),
+        filter.sort_dir.as_deref(),
+        TRANSACTION_SORT_COLUMNS,
+        ("created_at", "DESC"),
+    )?;
+
+    let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.is_empty());
+    let mut conditions = Vec::new();
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(d) = non_empty(&filter.start_date) {
+        conditions.push("substr(created_at, 1, 10) >= ?");
+        params_vec.push(Box::new(d));
+    }
+    if let Some(d) = non_empty(&filter.end_date) {
+        conditions.push("substr(created_at, 1, 10) <= ?");
+        params_vec.push(Box::new(d));
+    }
+    if let Some(t) = non_empty(&filter.transaction_type).filter(|t| t != "ALL") {
+        conditions.push("transaction_type = ?");
+        params_vec.push(Box::new(t));
+    }
+    if let Some(c) = non_empty(&filter.customer_id) {
+        conditions.push("customer_id = ?");
+        params_vec.push(Box::new(c));
+    }
+    if let Some(sku) = non_empty(&filter.item_sku) {
+        // Lines are stored as a JSON array; rows with broken JSON simply do not match
+        conditions.push(
+            "EXISTS (SELECT 1 FROM json_each(CASE WHEN json_valid(items) THEN items ELSE '[]' END) line
+                     WHERE json_extract(line.value, '$.sku') = ?)",
+        );
+        params_vec.push(Box::new(sku.trim().to_string()));
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let total: i32 = {
+        let mut stmt = conn.prepare(&format!("SELECT COUNT(*) FROM transactions {}", where_clause))?;
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        stmt.query_row(params_refs.as_slice(), |row| row.get(0))?
+    };
+
+    let select_sql = format!(
+        "SELECT id, items, total, payment_method, transaction_type, note, created_at, customer_id, status, tax_total,
+                line_discount, ticket_discount
+         FROM transactions {} ORDER BY {} LIMIT ? OFFSET ?",
+        where_clause, order
+    );
+    params_vec.push(Box::new(per_page));
+    params_vec.push(Box::new(offset));
+
+    let mut stmt = conn.prepare(&select_sql)?;
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+    let data = stmt
+        .query_map(params_refs.as_slice(), |row| {
+            let items_json: String = row.get(1)?;
+            let items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
+
+            Ok(Transaction {
+                id: row.get(0)?,
+                items,
+                total: row.get(2)?,
+                payment_method: row.get(3)?,
+                transaction_type: row.get(4)?,
+                status: row.get(8)?,
+                tax_total: row.get(9)?,
+                line_discount: row.get(10)?,
+                ticket_discount: row.get(11)?,
+                note: row.get(5)?,
+                created_at: row.get(6)?,
+                customer_id: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let total_pages = ((total as f64) / (per_page as f64)).ceil() as i32;
+
+    Ok(PaginatedTransactionsResponse {
+        data,
+        total,
+        page,
+        per_page,
+        total_pages,
+    })
+}
+
+/// Activity logged when a transaction is voided
+pub const ACTIVITY_VOID: &str = "VOID";
+
+/// One transaction by id, or None
+pub fn get_transaction(conn: &Connection, id: &str) -> Result<Option<Transaction>, AppError> {
+    let transaction = conn.query_row(
+        "SELECT items, total, payment_method, transaction_type, note, created_at, customer_id, status, tax_total,
+                line_discount, ticket_discount
+         FROM transactions WHERE id = ?1",
+        params![id],
+        |row| {
+            let items_json: String = row.get(0)?;
+            Ok(Transaction {
+                id: id.to_string(),
+                items: serde_json::from_str(&items_json).unwrap_or_default(),
+                total: row.get(1)?,
+                payment_method: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                transaction_type: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                note: row.get(4)?,
+                created_at: row.get(5)?,
+                customer_id: row.get(6)?,
+                status: row.get(7)?,
+                tax_total: row.get(8)?,
+                line_discount: row.get(9)?,
+                ticket_discount: row.get(10)?,
+            })
+        },
+    ).optional()?;
+    Ok(transaction)
+}
+
+/// Cancel a completed transaction: undo its stock movements (sales also give their lots back),
+/// reverse its effect on the linked account balance and mark it voided.
+pub fn void_transaction(conn: &mut Connection, id: &str) -> Result<Transaction, AppError> {
+    let tx = conn.transaction()?;
+
+    let Some(mut transaction) = get_transaction(&tx, id)? else {
+        return Err(AppError::NotFound(format!("İşlem bulunamadı: {}", id)));
+    };
+    if transaction.status == TRANSACTION_STATUS_VOIDED {
+        return Err(AppError::Validation("Bu işlem zaten iptal edilmiş".to_string()));
+    }
+    if transaction.transaction_type == "PURCHASE" {
+        return Err(AppError::Validation("Mal kabul işlemleri buradan iptal edilemez".to_string()));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+
+    // 1. Stock: a voided sale puts the goods back, a voided return takes them out again
+    let direction = match transaction.transaction_type.as_str() {
+        "SALE" => 1.0,
+        "RETURN" => -1.0,
+        _ => 0.0,
+    };
+    if direction != 0.0 {
+        for item in &transaction.items {
+            if item.price < 0.0 {
+                continue;
+            }
+            for (sku, quantity) in bundles::expand_stock_movements(&tx, &item.sku, item.cart_quantity)? {
+                tx.execute(
+                    "UPDATE inventory_items SET quantity = MAX(0, round(quantity + ?1, 3)), last_updated = ?2 WHERE sku = ?3",
+                    params![direction * quantity, &now, &sku],
+                )?;
+            }
+        }
+    }
+
+    // 2. Lots consumed by the sale become available again
+    tx.execute(
+        "UPDATE inventory_lots SET quantity = quantity + (
+             SELECT COALESCE(SUM(c.quantity), 0) FROM sale_lot_consumptions c
+             WHERE c.lot_id = inventory_lots.id AND c.transaction_id = ?1
+         )
+         WHERE id IN (SELECT lot_id FROM sale_lot_consumptions WHERE transaction_id = ?1)",
+        params![id],
+    )?;
+    tx.execute("DELETE FROM sale_lot_consumptions WHERE transaction_id = ?1", params![id])?;
+
+    // 3. Account balance
+    if let Some(ref c_id) = transaction.customer_id {
+        let account_type: Option<String> = tx.query_row(
+            "SELECT account_type FROM current_accounts WHERE id = ?1",
+            params![c_id],
+            |row| row.get(0),
+        ).optional()?;
+
+        if let Some(account_type) = account_type {
+            let change = accounts::transaction_balance_effect(
+                &transaction.transaction_type,
+                &transaction.payment_method,
+                transaction.total,
+                &account_type,
+            );
+            if change != 0.0 {
+                tx.execute(
+                    "UPDATE current_accounts SET balance = balance - ?1, updated_at = ?2 WHERE id = ?3",
+                    params![change, &now, c_id],
+                )?;
+            }
+        }
+    }
+
+    tx.execute(
+        "UPDATE transactions SET status = ?1 WHERE id = ?2",
+        params![TRANSACTION_STATUS_VOIDED, id],
+    )?;
+
+    tx.execute(
+        "INSERT INTO activity_log (action_type, description, value, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            ACTIVITY_VOID,
+            format!("İşlem iptal edildi: {} ({}, {:.2})", id, transaction.transaction_type, transaction.total),
+            transaction.total,
+            &now
+        ],
+    )?;
+
+    tx.commit()?;
+
+    transaction.status = TRANSACTION_STATUS_VOIDED.to_string();
+    Ok(transaction)
+}
+
+/// Give back part of a completed sale. Each SKU may be returned at most as many times as the sale
+/// contained it, minus what earlier returns of the same sale already took back. The refund uses the
+/// sale's own prices less the line discount and the share of any ticket discount or discount line.
+/// The goods are restocked, the linked account is credited and, unless the sale was on credit,
+/// the refund is written to finance records.
+pub fn process_partial_return(
+    conn: &mut Connection,
+    original_transaction_id: &str,
+    returned_items: Vec<ReturnItem>,
+) -> Result<Transaction, AppError> {
+    if returned_items.is_empty() {
+        return Err(AppError::Validation("İade edilecek ürün seçilmedi".to_string()));
+    }
+
+    let tx = conn.transaction()?;
+
+    let original: Option<(String, String, String, String, Option<String>, f64)> = tx.query_row(
+        "SELECT items, COALESCE(transaction_type, ''), status, COALESCE(payment_method, ''), customer_id, total
+         FROM transactions WHERE id = ?1",
+        params![original_transaction_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+    ).optional()?;
+
+    let Some((items_json, transaction_type, status, payment_method, customer_id, sale_total)) = original else {
+        return Err(AppError::NotFound(format!("Satış bulunamadı: {}", original_transaction_id)));
+    };
+    if transaction_type != "SALE" {
+        return Err(AppError::Validation("Yalnızca satış işlemleri iade edilebilir".to_string()));
+    }
+    if status == TRANSACTION_STATUS_VOIDED {
+        return Err(AppError::Validation("İptal edilmiş bir satış iade edilemez".to_string()));
+    }
+
+    // Sold quantity per SKU; discount lines (negative price) are spread over the goods instead
+    let sale_lines: Vec<CartItem> = serde_json::from_str(&items_json)?;
+    let mut sold: HashMap<String, CartItem> = HashMap::new();
+    let mut gross = 0.0;
+    for line in sale_lines.into_iter().filter(|l| l.price >= 0.0) {
+        gross += discounts::line_net_total(&line);
+        // Line discounts are kept as amounts so lines of the same SKU add up
+        let discount = discounts::line_discount(&line);
+        let line = CartItem { discount_amount: Some(discount), discount_percent: None, ..line };
+        sold.entry(line.sku.clone())
+            .and_modify(|existing| {
+                existing.cart_quantity += line.cart_quantity;
+                existing.discount_amount = Some(existing.discount_amount.unwrap_or(0.0) + discount);
+            })
+            .or_insert(line);
+    }
+    let discount_factor = if gross > 0.0 { (sale_total / gross).clamp(0.0, 1.0) } else { 1.0 };
+
+    let mut already_returned: HashMap<String, f64> = HashMap::new();
+    {
+        let mut stmt = tx.prepare(
+            "SELECT items FROM transactions
+             WHERE original_transaction_id = ?1 AND transaction_type = 'RETURN' AND status != ?2",
+        )?;
+        let rows = stmt.query_map(params![original_transaction_id, TRANSACTION_STATUS_VOIDED], |row| {
+            row.get::<_, String>(0)
+        })?;
+        for items in rows {
+            for line in serde_json::from_str::<Vec<CartItem>>(&items?)? {
+                *already_returned.entry(line.sku).or_insert(0.0) += line.cart_quantity;
+            }
+        }
+    }
+
+    // The same SKU may be listed more than once; it is checked and returned as one line
+    let mut lines: Vec<CartItem> = Vec::new();
+    for item in &returned_items {
+        if item.quantity <= 0.0 {
+            return Err(AppError::Validation(format!("Geçersiz iade miktarı: {} (Miktar > 0 olmalı)", item.sku)));
+        }
+        let Some(sale_line) = sold.get(&item.sku) else {
+            return Err(AppError::Validation(format!("{} bu satışta yok, iade edilemez", item.sku)));
+        };
+        let unit = item_unit(&tx, &item.sku)?.unwrap_or_default();
+        validate_quantity(item.quantity, &unit).map_err(|e| AppError::Validation(format!("{}: {}", item.sku, e)))?;
+        match lines.iter_mut().find(|l| l.sku == item.sku) {
+            Some(line) => line.cart_quantity += item.quantity,
+            None => lines.push(CartItem { cart_quantity: item.quantity, ..sale_line.clone() }),
+        }
+    }
+    for line in &mut lines {
+        // A returned line carries its share of the sold line's discount
+        let sale_line = &sold[&line.sku];
+        line.discount_amount = sale_line
+            .discount_amount
+            .map(|d| tax::round_money(d * line.cart_quantity / sale_line.cart_quantity))
+            .filter(|d| *d > 0.0);
+
+        let sold_quantity = sale_line.cart_quantity;
+        let returned = already_returned.get(&line.sku).copied().unwrap_or(0.0);
+        if units::round_quantity(line.cart_quantity) > units::round_quantity(sold_quantity - returned) {
+            return Err(AppError::Validation(format!(
+                "İade miktarı satılanı aşıyor: {} için satılan {}, önceden iade edilen {}, istenen {}",
+                line.sku, sold_quantity, returned, line.cart_quantity
+            )));
+        }
+    }
+
+    let amounts: Vec<(f64, f64)> = lines
+        .iter()
+        .map(|l| (l.tax_rate, discounts::line_net_total(l) * discount_factor))
+        .collect();
+    let total = -tax::round_money(amounts.iter().map(|(_, amount)| amount).sum());
+    let tax_total = -tax::total_tax(&tax::tax_breakdown(amounts));
+    let line_discount = -tax::round_money(lines.iter().map(discounts::line_discount).sum());
+    let ticket_discount = -tax::round_money(lines.iter().map(discounts::line_net_total).sum::<f64>() + total);
+
+    let transaction_id = uuid::Uuid::new_v4().to_string();
+    let created_at = chrono::Utc::now().to_rfc3339();
+    let note = format!("İade: {}", original_transaction_id);
+
+    tx.execute(
+        "INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at, customer_id, tax_total,
+                                   original_transaction_id, line_discount, ticket_discount)
+         VALUES (?1, ?2, ?3, ?4, 'RETURN', ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            &transaction_id,
+            serde_json::to_string(&lines)?,
+            total,
+            &payment_method,
+            &note,
+            &created_at,
+            &customer_id,
+            tax_total,
+            original_transaction_id,
+            line_discount,
+            ticket_discount
+        ],
+    )?;
+
+    for line in &lines {
+        for (sku, quantity) in bundles::expand_stock_movements(&tx, &line.sku, line.cart_quantity)? {
+            apply_stock_movement(&tx, &transaction_id, &sku, quantity, "RETURN", &created_at)?;
+        }
+    }
+
+    if let Some(ref c_id) = customer_id {
+        let account_type: Option<String> = tx.query_row(
+            "SELECT account_type FROM current_accounts WHERE id = ?1",
+            params![c_id],
+            |row| row.get(0),
+        ).optional()?;
+
+        if let Some(account_type) = account_type {
+            let change = accounts::transaction_balance_effect("RETURN", &payment_method, total, &account_type);
+            if change != 0.0 {
+                tx.execute(
+                    "UPDATE current_accounts SET balance = balance + ?1, updated_at = ?2 WHERE id = ?3",
+                    params![change, &created_at, c_id],
+                )?;
+            }
+        }
+    }
+
+    // A split sale is refunded in the same proportions as it was paid
+    let refunds = if payment_method == finance::SPLIT_PAYMENT_METHOD {
+        let paid = finance::get_transaction_payments(&tx, original_transaction_id)?;
+        let mut refunds = Vec::with_capacity(paid.len());
+        let mut remaining = -total;
+        for (index, portion) in paid.iter().enumerate() {
+            let amount = if index + 1 == paid.len() {
+                tax::round_money(remaining)
+            } else {
+                tax::round_money(-total * portion.amount / sale_total)
+            };
+            remaining -= amount;
+            refunds.push(Payment { method: portion.method.clone(), amount });
+        }
+        finance::save_transaction_payments(&tx, &transaction_id, &refunds)?;
+        refunds
+    } else {
+        vec![Payment { method: payment_method.clone(), amount: -total }]
+    };
+
+    // Credit sales are refunded on the account, not paid out
+    for refund in refunds.iter().filter(|r| PaymentMethod::parse(&r.method) != PaymentMethod::Credit) {
+        tx.execute(
+            "INSERT INTO finance_records (record_type, category, amount, payment_method, description, date, created_at)
+             VALUES ('EXPENSE', ?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                finance::SALE_RETURN_CATEGORY,
+                refund.amount,
+                &refund.method,
+                &note,
+                chrono::Local::now().date_naive().to_string(),
+                &created_at
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(Transaction {
+        id: transaction_id,
+        items: lines,
+        total,
+        payment_method,
+        transaction_type: "RETURN".to_string(),
+        status: TRANSACTION_STATUS_COMPLETED.to_string(),
+        note: Some(note),
+        created_at,
+        customer_id,
+        tax_total,
+        line_discount,
+        ticket_discount,
+    })
+}
+
+/// Insert a stock card; a barcode already in use returns `AppError::Conflict`
+pub fn create_stock_card(conn: &Connection, card: &StockCard) -> Result<(), AppError> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM stock_cards WHERE barcode = ?1)",
+        params![card.barcode],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Err(AppError::Conflict { field: "barcode".to_string(), value: card.barcode.clone() });
+    }
+
+    conn.execute(
+        "INSERT INTO stock_cards (id, barcode, name, brand, unit, category_id, description, image, supplier_id, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            card.id,
+            card.barcode,
+            card.name,
+            card.brand,
+            card.unit,
+            card.category_id,
+            card.description,
+            card.image,
+            card.supplier_id,
+            card.created_at,
+            card.updated_at
+        ],
+    )
+    .map_err(|e| {
+        if AppError::is_unique_violation(&e) && e.to_string().contains("stock_cards.barcode") {
+            AppError::Conflict { field: "barcode".to_string(), value: card.barcode.clone() }
+        } else {
+            AppError::Database(e)
+        }
+    })?;
+
+    Ok(())
+}
+
 /// Delete stock card with safety check
 pub fn delete_stock_card_safe(conn: &Connection, id: &str) -> Result<(), AppError> {
     let count: i32 = conn.query_row(
@@ -651,3 +2079,260 @@ pub fn delete_stock_card_safe(conn: &Connection, id: &str) -> Result<(), AppErro
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);
+             INSERT INTO settings VALUES ('timezone_offset_minutes', '180');
+             CREATE TABLE exchange_rates (currency TEXT PRIMARY KEY, rate_to_try REAL, updated_at TEXT);
+             CREATE TABLE inventory_items (
+                 sku TEXT PRIMARY KEY, quantity INTEGER, currency TEXT DEFAULT 'TL', min_stock_level INTEGER DEFAULT 5, deleted_at TEXT
+             );
+             INSERT INTO inventory_items (sku, quantity) VALUES ('A', 10), ('B', 2);
+             CREATE TABLE transactions (
+                 id TEXT PRIMARY KEY, items TEXT DEFAULT '[]', total REAL, transaction_type TEXT, status TEXT DEFAULT 'completed', created_at TEXT
+             );",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn add(conn: &Connection, id: &str, transaction_type: &str, total: f64, created_at: &str) {
+        conn.execute(
+            "INSERT INTO transactions (id, total, transaction_type, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![id, total, transaction_type, created_at],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_dashboard_window_is_inclusive_and_nets_returns() {
+        let conn = setup_db();
+        // Shop time is UTC+3: 21:30 UTC on the 31st is already 1 March locally
+        add(&conn, "before", "SALE", 1000.0, "2024-02-29T20:59:00+00:00");
+        add(&conn, "first", "SALE", 100.0, "2024-02-29T21:30:00+00:00");
+        add(&conn, "mid", "SALE", 300.0, "2024-03-15T09:00:00+00:00");
+        add(&conn, "ret", "RETURN", -50.0, "2024-03-16T09:00:00+00:00");
+        add(&conn, "last", "SALE", 200.0, "2024-03-31T20:59:00+00:00");
+        add(&conn, "after", "SALE", 1000.0, "2024-03-31T21:00:00+00:00");
+        add(&conn, "void", "SALE", 1000.0, "2024-03-20T09:00:00+00:00");
+        conn.execute("UPDATE transactions SET status = 'voided' WHERE id = 'void'", []).unwrap();
+
+        let stats = get_dashboard_stats(&conn, Some("2024-03-01"), Some("2024-03-31")).unwrap();
+        assert_eq!((stats.total_items, stats.total_quantity, stats.low_stock_count), (2, 12.0, 1));
+        assert_eq!((stats.sale_count, stats.return_count), (3, 1));
+        assert!((stats.total_revenue - 550.0).abs() < 0.001);
+        assert!((stats.average_basket - 200.0).abs() < 0.001);
+        assert_eq!((stats.period_start.as_str(), stats.period_end.as_str()), ("2024-03-01", "2024-03-31"));
+
+        // A single day includes its own sales
+        let day = get_dashboard_stats(&conn, Some("2024-03-31"), Some("2024-03-31")).unwrap();
+        assert_eq!(day.sale_count, 1);
+        assert!((day.total_revenue - 200.0).abs() < 0.001);
+
+        // Without dates: the current month
+        let default = get_dashboard_stats(&conn, None, None).unwrap();
+        assert!(default.period_start.ends_with("-01"));
+        assert_eq!(default.sale_count, 0);
+
+        assert!(get_dashboard_stats(&conn, Some("2024-03-31"), Some("2024-03-01")).is_err());
+    }
+
+    fn search_db(items: &[(&str, &str, Option<&str>)]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        for (sku, name, description) in items {
+            add_item(&conn, &item(sku, name, *description)).unwrap();
+        }
+        conn
+    }
+
+    fn item(sku: &str, name: &str, description: Option<&str>) -> InventoryItem {
+        InventoryItem {
+            id: format!("id-{}", sku),
+            sku: sku.to_string(),
+            name: name.to_string(),
+            category: "Genel".to_string(),
+            quantity: 1.0,
+            location: String::new(),
+            price: 10.0,
+            cost_price: None,
+            image: None,
+            description: description.map(str::to_string),
+            ai_tags: None,
+            last_updated: "2024-01-01T00:00:00Z".to_string(),
+            currency: None,
+            supplier_id: None,
+            brand: None,
+            min_stock_level: 5,
+            unit: units::DEFAULT_UNIT.to_string(),
+        }
+    }
+
+    fn skus(items: Vec<InventoryItem>) -> Vec<String> {
+        items.into_iter().map(|i| i.sku).collect()
+    }
+
+    #[test]
+    fn test_search_ranks_multi_word_match_first() {
+        let conn = search_db(&[
+            ("FIRCA", "Boya Fırçası", Some("Plastik saplı")),
+            ("KOVA", "Plastik Kova", None),
+            ("RP1-25", "Plastik Boya Beyaz", Some("İç cephe plastik boya")),
+            ("MATKAP", "Darbeli Matkap", None),
+        ]);
+
+        let found = skus(search_items(&conn, "plastik boya", 10).unwrap());
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0], "RP1-25");
+        // Words are prefixes
+        assert_eq!(skus(search_items(&conn, "matk", 10).unwrap()), ["MATKAP"]);
+        assert_eq!(search_items(&conn, "plastik", 1).unwrap().len(), 1);
+        assert!(search_items(&conn, "  ", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_folds_turkish_letters() {
+        let conn = search_db(&[("LED-1", "IŞIK ŞERİDİ", None), ("CIVATA", "Çelik Cıvata", Some("Galvaniz"))]);
+
+        for query in ["ışık", "Işık", "isik", "ŞERİDİ", "seridi"] {
+            assert_eq!(skus(search_items(&conn, query, 10).unwrap()), ["LED-1"], "{}", query);
+        }
+        assert_eq!(skus(search_items(&conn, "CIVATA celik", 10).unwrap()), ["CIVATA"]);
+        assert_eq!(skus(search_items(&conn, "çelik cıvata", 10).unwrap()), ["CIVATA"]);
+    }
+
+    #[test]
+    fn test_search_index_follows_updates_and_deletes() {
+        let conn = search_db(&[("VIDA-4", "Ahşap Vida", None)]);
+        assert_eq!(search_items(&conn, "ahsap", 10).unwrap().len(), 1);
+
+        let mut renamed = item("VIDA-4", "Sunta Vidası", Some("4x40"));
+        renamed.quantity = 3.0;
+        update_item(&conn, &renamed).unwrap();
+        assert!(search_items(&conn, "ahsap", 10).unwrap().is_empty());
+        assert_eq!(skus(search_items(&conn, "sunta", 10).unwrap()), ["VIDA-4"]);
+
+        conn.execute("UPDATE inventory_items SET deleted_at = 'now' WHERE sku = 'VIDA-4'", []).unwrap();
+        assert!(search_items(&conn, "sunta", 10).unwrap().is_empty());
+        conn.execute("DELETE FROM inventory_items", []).unwrap();
+        let indexed: i64 = conn.query_row("SELECT COUNT(*) FROM inventory_fts", [], |row| row.get(0)).unwrap();
+        assert_eq!(indexed, 0);
+    }
+
+    #[test]
+    fn test_search_with_special_characters_uses_substring_match() {
+        let conn = search_db(&[
+            ("RP1-25", "Plastik Boya", None),
+            ("RP1-250", "Plastik Boya 2.5L", None),
+            ("IND-50", "%50 İndirimli Boya", None),
+        ]);
+
+        assert_eq!(skus(search_items(&conn, "rp1-25", 10).unwrap()), ["RP1-25", "RP1-250"]);
+        assert_eq!(skus(search_items(&conn, "2.5l", 10).unwrap()), ["RP1-250"]);
+        // LIKE wildcards in the query are literal
+        assert_eq!(skus(search_items(&conn, "%5", 10).unwrap()), ["IND-50"]);
+        assert!(search_items(&conn, "a_b", 10).unwrap().is_empty());
+        // An unbalanced quote is not an FTS syntax error
+        assert!(search_items(&conn, "boya\"", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_internal_skus_unique_and_monotonic_under_concurrency() {
+        let path = std::env::temp_dir().join(format!("nexus_sku_{}.db", uuid::Uuid::new_v4()));
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch("PRAGMA journal_mode = WAL;").unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        // Codes typed in by hand before the counter existed; the barcode-looking one is ignored
+        conn.execute_batch(
+            "INSERT INTO stock_cards (id, barcode, name, created_at, updated_at) VALUES
+                ('c1', '00001036', 'Vida', 'now', 'now'),
+                ('c2', '8690000000001', 'Boya', 'now', 'now');
+             INSERT INTO inventory_items (id, sku, name, last_updated) VALUES ('i1', '00001040', 'Dübel', 'now');",
+        )
+        .unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let conn = Connection::open(&path).unwrap();
+                    conn.busy_timeout(std::time::Duration::from_secs(10)).unwrap();
+                    (0..10).map(|_| generate_internal_sku(&conn, "").unwrap()).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let per_thread: Vec<Vec<String>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        // Each caller sees its codes increase
+        for codes in &per_thread {
+            assert!(codes.windows(2).all(|w| w[0] < w[1]), "{:?}", codes);
+        }
+        let mut codes: Vec<String> = per_thread.into_iter().flatten().collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), 80);
+        assert_eq!(codes.first().map(String::as_str), Some("00001041"));
+        assert_eq!(codes.last().map(String::as_str), Some("00001120"));
+
+        // A code entered by hand after the counter existed is skipped
+        conn.execute(
+            "INSERT INTO stock_cards (id, barcode, name, created_at, updated_at) VALUES ('c3', '00001121', 'Elle', 'now', 'now')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(generate_internal_sku(&conn, "").unwrap(), "00001122");
+        assert_eq!(generate_internal_sku(&conn, "hr").unwrap(), "HR000001");
+        assert!(generate_internal_sku(&conn, "H-R").is_err());
+
+        drop(conn);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_catalog_covers_cards_without_stock_and_orphan_items() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO categories (id, name, created_at) VALUES ('cat-boya', 'Boya', 'now');
+             INSERT INTO stock_cards (id, barcode, name, unit, category_id, created_at, updated_at) VALUES
+                 ('card-1', 'BOYA-25', 'Boya \"Ic\"; 2.5L', 'ADET', 'cat-boya', 'now', 'now'),
+                 ('card-2', 'FIRCA-1', 'Firca', 'ADET', NULL, 'now', 'now');
+             INSERT INTO inventory_items (id, sku, name, category, quantity, price, cost_price, last_updated) VALUES
+                 ('i1', 'BOYA-25', 'Boya \"Ic\"; 2.5L', 'Boya', 4, 250, 180, 'now'),
+                 ('i2', 'KABLO-3', 'Kablo; 3x1.5', 'Elektrik', 12.5, 12, NULL, 'now');
+             UPDATE inventory_items SET unit = 'MT' WHERE id = 'i2';
+             INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, created_at)
+                 VALUES ('l1', 'i1', 0, 5, 180, '2024-01-01', 'now'), ('l2', 'i1', 4, 4, 180, '2024-02-01', 'now');",
+        )
+        .unwrap();
+
+        let rows = get_catalog_rows(&conn).unwrap();
+        assert_eq!(rows.len(), 3);
+        let paint = rows.iter().find(|r| r.barcode == "BOYA-25").unwrap();
+        assert_eq!((paint.category.as_str(), paint.quantity, paint.lot_count, paint.open_lot_count), ("Boya", 4.0, 2, 1));
+        // A stock card with no inventory record
+        let brush = rows.iter().find(|r| r.barcode == "FIRCA-1").unwrap();
+        assert_eq!((brush.inventory_id.as_deref(), brush.quantity, brush.price), (None, 0.0, None));
+        // An inventory item with no stock card comes last
+        let cable = &rows[2];
+        assert_eq!((cable.stock_card_id.as_deref(), cable.barcode.as_str(), cable.quantity), (None, "KABLO-3", 12.5));
+        assert_eq!(cable.unit.as_deref(), Some("MT"));
+
+        let csv = catalog_to_csv(&rows).unwrap();
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(import_staging::DEFAULT_CSV_DELIMITER)
+            .from_reader(csv.trim_start_matches('\u{feff}').as_bytes());
+        let records: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 3);
+        assert!(records.iter().all(|r| r.len() == 13));
+        let paint = records.iter().find(|r| &r[1] == "BOYA-25").unwrap();
+        assert_eq!((&paint[2], &paint[7], &paint[9]), ("Boya \"Ic\"; 2.5L", "4", "250.00"));
+        assert_eq!(&records[2][2], "Kablo; 3x1.5");
+        assert_eq!(&records[2][7], "12.5");
+    }
+}
@@ -0,0 +1,309 @@
+//! JSON Export Module
+//!
+//! İş verisinin (ürünler, stok kartları, kategoriler, işlemler, cari hesaplar, lotlar, finans
+//! kayıtları) okunabilir, sürümlü tek bir JSON belgesi olarak dışa aktarılması ve geri
+//! yüklenmesi. `.db` kopyasının aksine belge başka bir şemaya taşınabilir: içe aktarırken yalnızca
+//! hedef tabloda bulunan sütunlar yazılır, eksik sütunlar varsayılan değerini alır.
+
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::database::migrations::SCHEMA_VERSION;
+use crate::error::AppError;
+
+/// Version of the document layout; documents from a newer layout are refused
+pub const JSON_EXPORT_VERSION: u32 = 1;
+
+/// Exported tables, parents before the rows that point at them
+pub const JSON_EXPORT_TABLES: &[&str] = &[
+    "categories",
+    "current_accounts",
+    "stock_cards",
+    "inventory_items",
+    "inventory_lots",
+    "transactions",
+    "transaction_payments",
+    "finance_records",
+];
+
+/// Tables that must be empty before an import: the exported history plus the tables pointing at
+/// exported rows. A fresh install only has the startup seed in the catalog tables, which is replaced.
+pub const JSON_IMPORT_EMPTY_TABLES: &[&str] = &[
+    "transactions",
+    "transaction_payments",
+    "finance_records",
+    "goods_receipts",
+    "purchase_orders",
+    "sale_lot_consumptions",
+    "price_history",
+    "item_attributes",
+    "product_components",
+];
+
+/// Key of the object a BLOB value is written as (`{"$blob": "<base64>"}`)
+const BLOB_KEY: &str = "$blob";
+
+/// A whole export: every table as a list of `column -> value` rows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonExport {
+    pub version: u32,
+    /// `SCHEMA_VERSION` of the database it was taken from
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: i32,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: String,
+    pub tables: BTreeMap<String, Vec<Map<String, Value>>>,
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let columns = stmt
+        .query_map([table], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(columns)
+}
+
+fn quote(column: &str) -> String {
+    format!("\"{}\"", column.replace('"', "\"\""))
+}
+
+fn to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => {
+            let mut object = Map::new();
+            object.insert(BLOB_KEY.to_string(), Value::String(base64::engine::general_purpose::STANDARD.encode(b)));
+            Value::Object(object)
+        }
+    }
+}
+
+fn to_sql(value: &Value) -> Result<SqlValue, AppError> {
+    Ok(match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Object(object) if object.len() == 1 && object.contains_key(BLOB_KEY) => {
+            let encoded = object[BLOB_KEY].as_str().unwrap_or_default();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|e| AppError::Validation(format!("Geçersiz BLOB değeri: {}", e)))?;
+            SqlValue::Blob(bytes)
+        }
+        other => SqlValue::Text(other.to_string()),
+    })
+}
+
+/// Read every exported table in one read transaction, so the document is a consistent snapshot
+pub fn export_json(conn: &Connection) -> Result<JsonExport, AppError> {
+    let tx = conn.unchecked_transaction()?;
+    let mut tables = BTreeMap::new();
+
+    for table in JSON_EXPORT_TABLES {
+        let columns = table_columns(&tx, table)?;
+        let select = columns.iter().map(|c| quote(c)).collect::<Vec<_>>().join(", ");
+        let mut stmt = tx.prepare(&format!("SELECT {} FROM {} ORDER BY rowid", select, table))?;
+        let rows = stmt
+            .query_map([], |row| {
+                let mut object = Map::new();
+                for (i, column) in columns.iter().enumerate() {
+                    object.insert(column.clone(), to_json(row.get_ref(i)?));
+                }
+                Ok(object)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        tables.insert(table.to_string(), rows);
+    }
+    tx.commit()?;
+
+    Ok(JsonExport {
+        version: JSON_EXPORT_VERSION,
+        schema_version: SCHEMA_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        tables,
+    })
+}
+
+/// Load an export into a database without business records (`JSON_IMPORT_EMPTY_TABLES`), all in one
+/// transaction. Catalog rows already there (the startup seed) are deleted first.
+/// Tables go in `JSON_EXPORT_TABLES` order and foreign keys are checked at commit, so rows that
+/// point at rows of the same table (sub-categories, sub-accounts) load in any order. Columns the
+/// current schema does not have are dropped. Returns the rows written per table.
+pub fn import_json(conn: &Connection, export: &JsonExport) -> Result<BTreeMap<String, usize>, AppError> {
+    if export.version > JSON_EXPORT_VERSION {
+        return Err(AppError::Validation(format!(
+            "Dışa aktarma dosyası daha yeni bir sürüme ait (v{}, desteklenen v{})",
+            export.version, JSON_EXPORT_VERSION
+        )));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for table in JSON_IMPORT_EMPTY_TABLES {
+        let count: i64 = tx.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))?;
+        if count > 0 {
+            return Err(AppError::Validation(format!(
+                "İçe aktarma yalnızca boş bir veritabanına yapılabilir: {} tablosunda {} kayıt var",
+                table, count
+            )));
+        }
+    }
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+    // Children before parents; rows of other tables still pointing here fail the commit
+    for table in JSON_EXPORT_TABLES.iter().rev() {
+        tx.execute(&format!("DELETE FROM {}", table), [])?;
+    }
+
+    let mut imported = BTreeMap::new();
+    for table in JSON_EXPORT_TABLES {
+        let rows = export.tables.get(*table).map(Vec::as_slice).unwrap_or_default();
+        let columns = table_columns(&tx, table)?;
+        for row in rows {
+            // Only columns the table has; the names come from the schema, never from the file
+            let present: Vec<&String> = columns.iter().filter(|c| row.contains_key(c.as_str())).collect();
+            if present.is_empty() {
+                continue;
+            }
+            let values = present.iter().map(|c| to_sql(&row[c.as_str()])).collect::<Result<Vec<_>, _>>()?;
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                present.iter().map(|c| quote(c)).collect::<Vec<_>>().join(", "),
+                vec!["?"; present.len()].join(", ")
+            );
+            tx.execute(&sql, rusqlite::params_from_iter(values))?;
+        }
+        imported.insert(table.to_string(), rows.len());
+    }
+    tx.commit()?;
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrated_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_json_export_round_trip() {
+        let source = migrated_db();
+        // The sub-category's row comes before its parent's, so importing relies on deferred foreign keys
+        source
+            .execute_batch(
+                "INSERT INTO categories (id, name, parent_id, created_at) VALUES
+                    ('tornavida', 'Tornavida', 'hirdavat', 'now'),
+                    ('hirdavat', 'HIRDAVAT', NULL, 'now');
+                 INSERT INTO current_accounts (id, name, account_type, balance, created_at, updated_at)
+                    VALUES ('sup-1', 'Tedarikçi A.Ş.', 'SUPPLIER', -1200.5, 'now', 'now');
+                 INSERT INTO stock_cards (id, barcode, name, category_id, created_at, updated_at)
+                    VALUES ('card-1', '00001036', 'Yıldız tornavida', 'tornavida', 'now', 'now');
+                 INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated, unit)
+                    VALUES ('item-1', '00001036', 'Yıldız tornavida', 12.5, 85.0, 'now', 'MT');
+                 INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, supplier_id, created_at)
+                    VALUES ('lot-1', 'item-1', 12.5, 20, 50.0, '2024-01-01', 'sup-1', 'now');
+                 INSERT INTO transactions (id, items, total, payment_method, transaction_type, created_at)
+                    VALUES ('t1', '[{\"sku\":\"00001036\"}]', 170.0, 'SPLIT', 'SALE', '2024-03-01T10:00:00Z');
+                 INSERT INTO transaction_payments (transaction_id, position, method, amount) VALUES
+                    ('t1', 0, 'NAKIT', 100.0), ('t1', 1, 'KREDI_KARTI', 70.0);
+                 INSERT INTO finance_records (record_type, category, amount, payment_method, description, date, created_at)
+                    VALUES ('EXPENSE', 'Kira', 4500.0, 'HAVALE', 'Mart', '2024-03-01', 'now');",
+            )
+            .unwrap();
+
+        let export = export_json(&source).unwrap();
+        assert_eq!((export.version, export.schema_version), (JSON_EXPORT_VERSION, SCHEMA_VERSION));
+        let text = serde_json::to_string_pretty(&export).unwrap();
+        let parsed: JsonExport = serde_json::from_str(&text).unwrap();
+
+        let target = migrated_db();
+        let imported = import_json(&target, &parsed).unwrap();
+        for table in JSON_EXPORT_TABLES {
+            assert_eq!(count(&target, table), count(&source, table), "{}", table);
+            assert_eq!(imported[*table] as i64, count(&source, table));
+        }
+        let (quantity, unit): (f64, String) = target
+            .query_row("SELECT quantity, unit FROM inventory_items WHERE id = 'item-1'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((quantity, unit.as_str()), (12.5, "MT"));
+        let parent: Option<String> =
+            target.query_row("SELECT parent_id FROM categories WHERE id = 'hirdavat'", [], |row| row.get(0)).unwrap();
+        assert_eq!(parent, None);
+        let integrity: i64 = target.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0)).unwrap();
+        assert_eq!(integrity, 0);
+
+        // Not into a database that already has records, and never from a newer document layout
+        let err = import_json(&target, &parsed).unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("transactions")));
+        for table in JSON_EXPORT_TABLES {
+            assert_eq!(count(&target, table), count(&source, table), "{}", table);
+        }
+        let newer = JsonExport { version: JSON_EXPORT_VERSION + 1, ..parsed };
+        assert!(matches!(import_json(&migrated_db(), &newer), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_json_import_replaces_startup_seed() {
+        let source = migrated_db();
+        source
+            .execute_batch(
+                "INSERT INTO categories (id, name, created_at) VALUES ('boya', 'Boya', 'now');
+                 INSERT INTO inventory_items (id, sku, name, category, quantity, price, last_updated)
+                    VALUES ('item-1', 'BOYA-25', 'Boya 2.5L', 'Boya', 3, 250.0, 'now');",
+            )
+            .unwrap();
+        let export = export_json(&source).unwrap();
+
+        // A fresh install goes through the same seeding as app startup
+        let target = migrated_db();
+        crate::database::Database::seed_if_empty(&target).unwrap();
+        assert!(count(&target, "stock_cards") > 0 && count(&target, "inventory_lots") > 0);
+
+        import_json(&target, &export).unwrap();
+        for table in JSON_EXPORT_TABLES {
+            assert_eq!(count(&target, table), count(&source, table), "{}", table);
+        }
+        let integrity: i64 = target.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0)).unwrap();
+        assert_eq!(integrity, 0);
+    }
+
+    #[test]
+    fn test_json_import_refused_when_history_points_at_catalog() {
+        let export = export_json(&migrated_db()).unwrap();
+        let target = migrated_db();
+        target
+            .execute_batch(
+                "INSERT INTO current_accounts (id, name, account_type, balance, created_at, updated_at)
+                    VALUES ('sup-1', 'Tedarikçi A.Ş.', 'SUPPLIER', 0, 'now', 'now');
+                 INSERT INTO goods_receipts (id, supplier_id, total_amount, payment_method, date, created_at)
+                    VALUES ('gr-1', 'sup-1', 500.0, 'NAKIT', '2024-03-01', 'now');",
+            )
+            .unwrap();
+
+        let err = import_json(&target, &export).unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("goods_receipts")));
+        // Nothing was deleted
+        assert_eq!(count(&target, "current_accounts"), 1);
+        assert_eq!(count(&target, "goods_receipts"), 1);
+    }
+}
@@ -2,16 +2,51 @@
 
 pub mod inventory;
 pub mod backup;
+pub mod backup_target;
 pub mod startup;
 pub mod print;
 pub mod updater;
 pub mod scanner;
 pub mod sync;
+pub mod reports;
+pub mod accounts;
+pub mod pricing;
+pub mod goods_receipts;
+pub mod bundles;
+pub mod attributes;
+pub mod import_staging;
+pub mod health;
+pub mod support_log;
+pub mod http;
+pub mod tax;
+pub mod finance;
+pub mod settings;
+pub mod xlsx;
+pub mod purchase_orders;
+pub mod currency;
+pub mod images;
+pub mod units;
+pub mod scheduled_export;
+pub mod discounts;
+pub mod recurring_expenses;
+pub mod categories;
+pub mod json_export;
 
 pub use inventory::*;
 pub use backup::*;
+pub use backup_target::*;
 pub use startup::*;
 pub use print::*;
 pub use updater::*;
 pub use scanner::*;
 pub use sync::*;
+pub use reports::*;
+pub use accounts::*;
+pub use pricing::*;
+pub use goods_receipts::*;
+pub use bundles::*;
+pub use attributes::*;
+pub use import_staging::*;
+pub use health::*;
+pub use support_log::*;
+pub use http::*;
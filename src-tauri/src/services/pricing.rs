@@ -0,0 +1,220 @@
+//! Pricing Service Module
+//!
+//! Kategori bazlı hedef kâr oranları ve fiyat geçmişi
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::models::CategoryMargin;
+
+/// Settings key for the global default margin
+pub const DEFAULT_MARGIN_SETTING: &str = "default_margin_percent";
+
+/// Used when neither the category nor the settings define a margin (eski sabit %30)
+pub const FALLBACK_MARGIN_PERCENT: f64 = 30.0;
+
+/// Price history reason for prices computed from a category margin
+pub const REASON_AUTO_MARGIN: &str = "auto-margin";
+
+/// Price history reason for prices applied from a reviewed CSV import
+pub const REASON_IMPORT: &str = "import";
+
+fn validate_margin(percent: f64) -> Result<(), AppError> {
+    if !percent.is_finite() || !(0.0..=1000.0).contains(&percent) {
+        return Err(AppError::Validation(format!("Geçersiz kâr oranı: {} (0-1000 arası olmalı)", percent)));
+    }
+    Ok(())
+}
+
+/// Sell price from cost and a markup percentage over cost, rounded to 2 decimals
+pub fn price_from_margin(buy_price: f64, margin_percent: f64) -> f64 {
+    (buy_price * (1.0 + margin_percent / 100.0) * 100.0).round() / 100.0
+}
+
+/// All configured category margins
+pub fn get_category_margins(conn: &Connection) -> Result<Vec<CategoryMargin>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT category, target_margin_percent, updated_at FROM category_margins ORDER BY category",
+    )?;
+
+    let margins = stmt
+        .query_map([], |row| {
+            Ok(CategoryMargin {
+                category: row.get(0)?,
+                target_margin_percent: row.get(1)?,
+                updated_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(margins)
+}
+
+/// Explicit margin for a category, if one is configured
+pub fn get_category_margin(conn: &Connection, category: &str) -> Result<Option<f64>, AppError> {
+    Ok(conn
+        .query_row(
+            "SELECT target_margin_percent FROM category_margins WHERE category = ?1",
+            params![category],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Set (or with `None`, remove) a category's target margin
+pub fn set_category_margin(
+    conn: &Connection,
+    category: &str,
+    target_margin_percent: Option<f64>,
+) -> Result<(), AppError> {
+    match target_margin_percent {
+        Some(percent) => {
+            validate_margin(percent)?;
+            conn.execute(
+                "INSERT INTO category_margins (category, target_margin_percent, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(category) DO UPDATE SET target_margin_percent = excluded.target_margin_percent, updated_at = excluded.updated_at",
+                params![category, percent, chrono::Utc::now().to_rfc3339()],
+            )?;
+        }
+        None => {
+            conn.execute("DELETE FROM category_margins WHERE category = ?1", params![category])?;
+        }
+    }
+    Ok(())
+}
+
+/// Global default margin from settings
+pub fn get_default_margin(conn: &Connection) -> Result<f64, AppError> {
+    let value: Option<Option<String>> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![DEFAULT_MARGIN_SETTING],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(value
+        .flatten()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(FALLBACK_MARGIN_PERCENT))
+}
+
+pub fn set_default_margin(conn: &Connection, percent: f64) -> Result<(), AppError> {
+    validate_margin(percent)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![DEFAULT_MARGIN_SETTING, percent.to_string()],
+    )?;
+    Ok(())
+}
+
+/// Category margin, falling back to the global default
+pub fn resolve_margin(conn: &Connection, category: &str) -> Result<f64, AppError> {
+    match get_category_margin(conn, category)? {
+        Some(percent) => Ok(percent),
+        None => get_default_margin(conn),
+    }
+}
+
+/// Append a row to price_history
+pub fn record_price_change(
+    conn: &Connection,
+    item_id: &str,
+    sku: Option<&str>,
+    old_price: Option<f64>,
+    new_price: f64,
+    cost_price: Option<f64>,
+    reason: &str,
+) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO price_history (item_id, sku, old_price, new_price, cost_price, reason, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![item_id, sku, old_price, new_price, cost_price, reason, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Reprice an existing item received without an explicit sell price: the new buy price plus its
+/// category's target margin. Categories without an explicit margin keep their shelf price; the
+/// global default only prices new items.
+pub fn reprice_received_item(
+    conn: &Connection,
+    product_id: &str,
+    barcode: Option<&str>,
+    buy_price: f64,
+) -> Result<(), AppError> {
+    let existing: Option<(String, String, String, f64)> = conn
+        .query_row(
+            "SELECT id, sku, category, price FROM inventory_items WHERE id = ?1 OR sku = ?2",
+            params![product_id, barcode],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?;
+
+    let Some((item_id, sku, category, old_price)) = existing else {
+        return Ok(());
+    };
+    let Some(margin) = get_category_margin(conn, &category)? else {
+        return Ok(());
+    };
+    let new_price = price_from_margin(buy_price, margin);
+    if (new_price - old_price).abs() > 0.001 {
+        conn.execute("UPDATE inventory_items SET price = ?1 WHERE id = ?2", params![new_price, &item_id])?;
+        record_price_change(conn, &item_id, Some(&sku), Some(old_price), new_price, Some(buy_price), REASON_AUTO_MARGIN)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_from_margin() {
+        assert_eq!(price_from_margin(100.0, 30.0), 130.0);
+        assert_eq!(price_from_margin(9.99, 25.0), 12.49);
+        assert_eq!(price_from_margin(50.0, 0.0), 50.0);
+    }
+
+    #[test]
+    fn test_received_item_reprices_only_with_category_margin() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (id TEXT PRIMARY KEY, sku TEXT, category TEXT, price REAL);
+             CREATE TABLE category_margins (category TEXT PRIMARY KEY, target_margin_percent REAL, updated_at TEXT);
+             CREATE TABLE price_history (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT, item_id TEXT, sku TEXT, old_price REAL, new_price REAL,
+                 cost_price REAL, reason TEXT, created_at TEXT
+             );
+             INSERT INTO inventory_items VALUES
+                 ('vida', 'VID-01', 'Hirdavat', 5.0),
+                 ('boya', 'BOY-01', 'Boya', 150.0);",
+        )
+        .unwrap();
+        set_category_margin(&conn, "Boya", Some(50.0)).unwrap();
+
+        // No category margin: the shelf price stays
+        reprice_received_item(&conn, "stock-card-id", Some("VID-01"), 8.0).unwrap();
+        // Explicit category margin, found by barcode
+        reprice_received_item(&conn, "stock-card-id", Some("BOY-01"), 120.0).unwrap();
+        // Unchanged price: nothing recorded
+        reprice_received_item(&conn, "boya", None, 120.0).unwrap();
+
+        let prices: Vec<(String, f64)> = conn
+            .prepare("SELECT id, price FROM inventory_items ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(prices, [("boya".to_string(), 180.0), ("vida".to_string(), 5.0)]);
+
+        let history: Vec<(String, f64, f64, f64, String)> = conn
+            .prepare("SELECT item_id, old_price, new_price, cost_price, reason FROM price_history")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(history, [("boya".to_string(), 150.0, 180.0, 120.0, REASON_AUTO_MARGIN.to_string())]);
+    }
+}
@@ -2,10 +2,158 @@
 //! 
 //! Fiş ve fatura yazdırma sistemi
 
-use crate::models::Transaction;
+use crate::error::AppError;
+use crate::models::{CartItem, PurchaseOrder, Transaction};
+use crate::services::discounts;
+use crate::services::finance;
+use crate::services::settings;
+use crate::services::tax::{self, TaxGroup};
+use barcoders::sym::code128::Code128;
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use qrcode::{Color, EcLevel, QrCode};
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// Negatif tutarların gösterimi
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NegativeStyle {
+    /// -12,50
+    Minus,
+    /// (12,50)
+    Parentheses,
+}
+
+/// Fiş / fatura biçim ayarları
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrintConfig {
+    /// "tr-TR" → 1.234,56 ; "en-US" → 1,234.56
+    pub locale: String,
+    #[serde(rename = "negativeStyle")]
+    pub negative_style: NegativeStyle,
+    /// Show the unit price column
+    #[serde(rename = "showUnitPrice")]
+    pub show_unit_price: bool,
+    /// Show the line total column
+    #[serde(rename = "showLineTotal")]
+    pub show_line_total: bool,
+    /// KDV oranı (%) - yalnızca kalem içermeyen belgelerde; kalemler kendi oranını taşır
+    #[serde(rename = "taxRate")]
+    pub tax_rate: f64,
+    /// QR payload printed under the totals; filled from settings by the print commands
+    #[serde(skip)]
+    pub receipt_qr: Option<String>,
+}
+
+impl Default for PrintConfig {
+    fn default() -> Self {
+        Self {
+            locale: "tr-TR".to_string(),
+            negative_style: NegativeStyle::Minus,
+            show_unit_price: true,
+            show_line_total: true,
+            tax_rate: 0.0,
+            receipt_qr: None,
+        }
+    }
+}
+
+/// Subtotal / tax / grand total of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentTotals {
+    pub subtotal: f64,
+    pub tax: f64,
+    pub grand_total: f64,
+    /// KDV per rate, lowest rate first
+    pub groups: Vec<TaxGroup>,
+}
+
+/// Para tutarını 2 haneye yuvarlayıp yerel ayraçlarla biçimlendir
+pub fn format_money(amount: f64, config: &PrintConfig) -> String {
+    let (decimal_sep, thousands_sep) = if config.locale.starts_with("tr") {
+        (',', '.')
+    } else {
+        ('.', ',')
+    };
+
+    let cents = (amount * 100.0).round() as i64;
+    let negative = cents < 0;
+    let cents = cents.unsigned_abs();
+
+    let digits = (cents / 100).to_string();
+    let mut whole = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            whole.push(thousands_sep);
+        }
+        whole.push(ch);
+    }
+    let body = format!("{}{}{:02}", whole, decimal_sep, cents % 100);
+
+    match (negative, config.negative_style) {
+        (false, _) => body,
+        (true, NegativeStyle::Minus) => format!("-{}", body),
+        (true, NegativeStyle::Parentheses) => format!("({})", body),
+    }
+}
+
+/// Signed line total after the line's own discount (returns are negative)
+fn line_total(transaction: &Transaction, item: &CartItem) -> f64 {
+    let total = discounts::line_net_total(item);
+    if transaction.transaction_type == "RETURN" { -total } else { total }
+}
+
+/// Line discount as printed under the line, e.g. "İndirim (%10)"
+fn line_discount_label(item: &CartItem) -> String {
+    match item.discount_percent.filter(|p| *p != 0.0 && item.discount_amount.unwrap_or(0.0) == 0.0) {
+        Some(percent) => format!("İndirim (%{})", percent),
+        None => "İndirim".to_string(),
+    }
+}
+
+/// Ara toplam / KDV oranlarına göre KDV / genel toplam (fiyatlar KDV dahil)
+pub fn calculate_totals(transaction: &Transaction, config: &PrintConfig) -> DocumentTotals {
+    let groups = if transaction.items.is_empty() {
+        tax::tax_breakdown([(config.tax_rate, transaction.total)])
+    } else {
+        let lines = transaction.items.iter().map(|item| (item.tax_rate, line_total(transaction, item))).collect();
+        tax::tax_breakdown(discounts::spread_ticket_discount(lines, transaction.ticket_discount))
+    };
+
+    DocumentTotals {
+        subtotal: tax::round_money(groups.iter().map(|g| g.net).sum()),
+        tax: tax::total_tax(&groups),
+        grand_total: tax::round_money(groups.iter().map(|g| g.gross).sum()),
+        groups,
+    }
+}
+
+fn payment_label(payment_method: &str) -> String {
+    let canonical = finance::canonical_payment_method(payment_method);
+    let (code, detail) = match canonical.split_once(" (") {
+        Some((code, detail)) => (code, Some(detail)),
+        None => (canonical.as_str(), None),
+    };
+    let label = match code {
+        "NAKIT" => "Nakit",
+        "KREDI_KARTI" => "Kredi Kartı",
+        "MAIL_ORDER" => "Mail Order",
+        "HAVALE" => "Havale/EFT",
+        "BANKA_KARTI" => "Banka Kartı",
+        "VERESIYE" => "Veresiye",
+        _ => return canonical,
+    };
+    match detail {
+        Some(detail) => format!("{} ({}", label, detail),
+        None => label.to_string(),
+    }
+}
 
 /// Fiş formatı oluştur (text tabanlı - termal yazıcı uyumlu)
-pub fn generate_receipt_text(transaction: &Transaction, currency: &str, store_name: &str) -> String {
+pub fn generate_receipt_text(transaction: &Transaction, currency: &str, store_name: &str, config: &PrintConfig) -> String {
     let width = 40; // Termal yazıcı genişliği
     let separator = "=".repeat(width);
     let thin_separator = "-".repeat(width);
@@ -32,23 +180,43 @@ pub fn generate_receipt_text(transaction: &Transaction, currency: &str, store_na
     lines.push(thin_separator.clone());
     
     for item in &transaction.items {
-        let item_total = item.price * item.cart_quantity as f64;
-        let name = if item.name.len() > 18 {
-            format!("{}...", &item.name[..15])
+        let name = if item.name.chars().count() > 18 {
+            format!("{}...", item.name.chars().take(15).collect::<String>())
         } else {
             item.name.clone()
         };
-        lines.push(format!("{:<20} {:>5} {:>10.2}{}", name, item.cart_quantity, item_total, currency));
+        if config.show_line_total {
+            let amount = format!("{}{}", format_money(line_total(transaction, item), config), currency);
+            lines.push(format!("{:<20} {:>5} {:>12}", name, item.cart_quantity, amount));
+        } else {
+            lines.push(format!("{:<20} {:>5}", name, item.cart_quantity));
+        }
+        if config.show_unit_price {
+            lines.push(format!("  {} x {}{}", item.cart_quantity, format_money(item.price, config), currency));
+        }
+        let discount = discounts::line_discount(item);
+        if discount > 0.0 {
+            lines.push(format!("  {:<25} {:>12}", line_discount_label(item), format!("-{}{}", format_money(discount, config), currency)));
+        }
     }
     
     lines.push(separator.clone());
     
-    // Total
-    lines.push(format!("{:>28} {:>10.2}{}", "TOPLAM:", transaction.total, currency));
-    lines.push(format!("{:>28} {}", "ÖDEME:", match transaction.payment_method.as_str() {
-        "CASH" => "NAKİT",
-        "CARD" => "KART",
-        "CREDIT" => "VERESİYE",
+    // Totals
+    let totals = calculate_totals(transaction, config);
+    if transaction.ticket_discount != 0.0 {
+        lines.push(format!("{:>26} {:>12}", "FİŞ İNDİRİMİ:", format!("-{}{}", format_money(transaction.ticket_discount.abs(), config), currency)));
+    }
+    lines.push(format!("{:>26} {:>12}", "ARA TOPLAM:", format!("{}{}", format_money(totals.subtotal, config), currency)));
+    for group in &totals.groups {
+        lines.push(format!("{:>26} {:>12}", format!("KDV (%{}):", group.rate), format!("{}{}", format_money(group.tax, config), currency)));
+    }
+    lines.push(format!("{:>26} {:>12}", "TOPLAM:", format!("{}{}", format_money(totals.grand_total, config), currency)));
+    let payment_method = finance::canonical_payment_method(&transaction.payment_method);
+    lines.push(format!("{:>26} {}", "ÖDEME:", match payment_method.as_str() {
+        "NAKIT" => "NAKİT",
+        "KREDI_KARTI" => "KART",
+        "VERESIYE" => "VERESİYE",
         _ => &transaction.payment_method,
     }));
     
@@ -58,26 +226,79 @@ pub fn generate_receipt_text(transaction: &Transaction, currency: &str, store_na
     lines.push(center_text("Teşekkürler!", width));
     lines.push(center_text("Bizi tercih ettiğiniz için", width));
     lines.push(center_text("teşekkür ederiz.", width));
+    if let Some(qr) = config.receipt_qr.as_deref().and_then(escpos_qr) {
+        lines.push(qr);
+    }
     lines.push("".to_string());
     
     lines.join("\n")
 }
 
 /// HTML fatura formatı oluştur
-pub fn generate_invoice_html(transaction: &Transaction, currency: &str, store_name: &str) -> String {
+pub fn generate_invoice_html(transaction: &Transaction, currency: &str, store_name: &str, config: &PrintConfig) -> String {
     let date = transaction.created_at.split('T').next().unwrap_or(&transaction.created_at);
-    
+
+    let mut header_cells = String::from("<th>Ürün</th>\n                <th>Adet</th>");
+    if config.show_unit_price {
+        header_cells.push_str("\n                <th style=\"text-align:right\">Birim Fiyat</th>");
+    }
+    if config.show_line_total {
+        header_cells.push_str("\n                <th style=\"text-align:right\">Tutar</th>");
+    }
+
     let items_html: String = transaction.items.iter().map(|item| {
-        let item_total = item.price * item.cart_quantity as f64;
+        let amount = line_total(transaction, item);
+        let discount = discounts::line_discount(item);
+        let name = if discount > 0.0 {
+            format!(
+                "{}<br><small class=\"negative\">{}: -{} {}</small>",
+                item.name, line_discount_label(item), format_money(discount, config), currency
+            )
+        } else {
+            item.name.clone()
+        };
+        let mut cells = format!("<td>{}</td>\n                <td>{}</td>", name, item.cart_quantity);
+        if config.show_unit_price {
+            cells.push_str(&format!(
+                "\n                <td style=\"text-align:right\">{} {}</td>",
+                format_money(item.price, config), currency
+            ));
+        }
+        if config.show_line_total {
+            cells.push_str(&format!(
+                "\n                <td style=\"text-align:right\"{}>{} {}</td>",
+                if amount < 0.0 { " class=\"negative\"" } else { "" },
+                format_money(amount, config), currency
+            ));
+        }
         format!(r#"
             <tr>
-                <td>{}</td>
-                <td>{}</td>
-                <td style="text-align:right">{:.2} {}</td>
-                <td style="text-align:right">{:.2} {}</td>
+                {}
             </tr>
-        "#, item.name, item.cart_quantity, item.price, currency, item_total, currency)
+        "#, cells)
     }).collect();
+
+    // A QR that does not fit is left out rather than failing the whole invoice
+    let qr_html = config
+        .receipt_qr
+        .as_deref()
+        .and_then(|payload| receipt_qr_data_uri(payload).ok())
+        .map(|uri| format!("\n        <img class=\"qr\" src=\"{}\" alt=\"Fiş QR\" width=\"120\" height=\"120\">", uri))
+        .unwrap_or_default();
+
+    let totals = calculate_totals(transaction, config);
+    let discount_row = if transaction.ticket_discount != 0.0 {
+        format!(
+            "\n        <tr><td>Fiş İndirimi:</td><td class=\"negative\">-{} {}</td></tr>",
+            format_money(transaction.ticket_discount.abs(), config), currency
+        )
+    } else {
+        String::new()
+    };
+    let tax_rows: String = totals.groups.iter().map(|group| format!(
+        "\n        <tr><td>KDV (%{}):</td><td>{} {}</td></tr>",
+        group.rate, format_money(group.tax, config), currency
+    )).collect();
     
     format!(r#"
 <!DOCTYPE html>
@@ -93,7 +314,10 @@ pub fn generate_invoice_html(transaction: &Transaction, currency: &str, store_na
         table {{ width: 100%; border-collapse: collapse; margin-bottom: 20px; }}
         th, td {{ padding: 10px; border-bottom: 1px solid #ddd; text-align: left; }}
         th {{ background: #f5f5f5; }}
-        .total {{ font-size: 1.2em; font-weight: bold; text-align: right; }}
+        .negative {{ color: #c0392b; }}
+        .totals {{ margin-left: auto; width: 300px; border-collapse: collapse; }}
+        .totals td {{ padding: 6px 10px; border: none; text-align: right; }}
+        .totals .grand {{ font-size: 1.2em; font-weight: bold; border-top: 2px solid #333; }}
         .footer {{ text-align: center; color: #666; margin-top: 30px; }}
         @media print {{ body {{ padding: 0; }} }}
     </style>
@@ -118,10 +342,7 @@ pub fn generate_invoice_html(transaction: &Transaction, currency: &str, store_na
     <table>
         <thead>
             <tr>
-                <th>Ürün</th>
-                <th>Adet</th>
-                <th style="text-align:right">Birim Fiyat</th>
-                <th style="text-align:right">Tutar</th>
+                {header_cells}
             </tr>
         </thead>
         <tbody>
@@ -129,12 +350,13 @@ pub fn generate_invoice_html(transaction: &Transaction, currency: &str, store_na
         </tbody>
     </table>
     
-    <div class="total">
-        TOPLAM: {total:.2} {currency}
-    </div>
+    <table class="totals">{discount_row}
+        <tr><td>Ara Toplam:</td><td>{subtotal} {currency}</td></tr>{tax_rows}
+        <tr class="grand"><td>TOPLAM:</td><td>{grand_total} {currency}</td></tr>
+    </table>
     
     <div class="footer">
-        <p>Bizi tercih ettiğiniz için teşekkür ederiz.</p>
+        <p>Bizi tercih ettiğiniz için teşekkür ederiz.</p>{qr}
     </div>
 </body>
 </html>
@@ -142,19 +364,270 @@ pub fn generate_invoice_html(transaction: &Transaction, currency: &str, store_na
         id = &transaction.id[..8],
         store = store_name,
         date = date,
-        payment = match transaction.payment_method.as_str() {
-            "CASH" => "Nakit",
-            "CARD" => "Kredi Kartı",
-            "CREDIT" => "Veresiye",
-            _ => &transaction.payment_method,
-        },
+        payment = payment_label(&transaction.payment_method),
         type_name = if transaction.transaction_type == "SALE" { "Satış" } else { "İade" },
+        header_cells = header_cells,
+        items = items_html,
+        discount_row = discount_row,
+        subtotal = format_money(totals.subtotal, config),
+        tax_rows = tax_rows,
+        grand_total = format_money(totals.grand_total, config),
+        currency = currency,
+        qr = qr_html
+    )
+}
+
+/// Name and contact details in a document's address block
+#[derive(Debug, Clone, Default)]
+pub struct DocumentParty {
+    pub name: String,
+    pub address: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub tax_number: Option<String>,
+}
+
+fn party_html(party: &DocumentParty) -> String {
+    let mut lines = vec![format!("<strong>{}</strong>", escape_html(&party.name))];
+    let details = [
+        ("", &party.address),
+        ("Tel: ", &party.phone),
+        ("E-posta: ", &party.email),
+        ("Vergi No: ", &party.tax_number),
+    ];
+    for (label, value) in details {
+        if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            lines.push(format!("{}{}", label, escape_html(value)));
+        }
+    }
+    lines.join("<br>\n            ")
+}
+
+/// HTML satın alma siparişi (fatura ile aynı görünüm): tedarikçi, mağaza, alış fiyatlı kalemler ve toplam
+pub fn generate_purchase_order_html(
+    order: &PurchaseOrder,
+    supplier: &DocumentParty,
+    store: &DocumentParty,
+    currency: &str,
+    config: &PrintConfig,
+) -> String {
+    let date = order.created_at.split('T').next().unwrap_or(&order.created_at);
+
+    let items_html: String = order.lines.iter().map(|line| {
+        format!(r#"
+            <tr>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td style="text-align:right">{} {}</td>
+                <td style="text-align:right">{} {}</td>
+            </tr>
+        "#,
+            escape_html(&line.sku),
+            escape_html(&line.name),
+            line.quantity,
+            format_money(line.buy_price, config), currency,
+            format_money(line.buy_price * line.quantity, config), currency
+        )
+    }).collect();
+
+    format!(r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>Satın Alma Siparişi - {number}</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; padding: 20px; max-width: 800px; margin: 0 auto; }}
+        .header {{ text-align: center; margin-bottom: 30px; }}
+        .header h1 {{ margin: 0; color: #333; }}
+        .info {{ display: flex; justify-content: space-between; margin-bottom: 20px; }}
+        table {{ width: 100%; border-collapse: collapse; margin-bottom: 20px; }}
+        th, td {{ padding: 10px; border-bottom: 1px solid #ddd; text-align: left; }}
+        th {{ background: #f5f5f5; }}
+        .totals {{ margin-left: auto; width: 300px; border-collapse: collapse; }}
+        .totals td {{ padding: 6px 10px; border: none; text-align: right; }}
+        .totals .grand {{ font-size: 1.2em; font-weight: bold; border-top: 2px solid #333; }}
+        .footer {{ text-align: center; color: #666; margin-top: 30px; }}
+        @media print {{ body {{ padding: 0; }} }}
+    </style>
+</head>
+<body>
+    <div class="header">
+        <h1>{store_name}</h1>
+        <p>SATIN ALMA SİPARİŞİ</p>
+    </div>
+
+    <div class="info">
+        <div>
+            {store}
+        </div>
+        <div>
+            <strong>Sipariş No:</strong> {number}<br>
+            <strong>Tarih:</strong> {date}
+        </div>
+    </div>
+
+    <div class="info">
+        <div>
+            <strong>Tedarikçi</strong><br>
+            {supplier}
+        </div>
+    </div>
+
+    <table>
+        <thead>
+            <tr>
+                <th>Stok Kodu</th>
+                <th>Ürün</th>
+                <th>Adet</th>
+                <th style="text-align:right">Birim Alış Fiyatı</th>
+                <th style="text-align:right">Tutar</th>
+            </tr>
+        </thead>
+        <tbody>
+            {items}
+        </tbody>
+    </table>
+
+    <table class="totals">
+        <tr class="grand"><td>TOPLAM:</td><td>{total} {currency}</td></tr>
+    </table>
+
+    <div class="footer">
+        <p>Lütfen irsaliye ve faturada sipariş numarasını belirtiniz.</p>
+    </div>
+</body>
+</html>
+    "#,
+        number = escape_html(&order.po_number),
+        store_name = escape_html(&store.name),
+        store = party_html(store),
+        date = date,
+        supplier = party_html(supplier),
         items = items_html,
-        total = transaction.total,
+        total = format_money(order.total, config),
         currency = currency
     )
 }
 
+/// Sayım föyü satırı (konum → kategori sıralı gelmeli)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountSheetRow {
+    pub location: String,
+    pub category: String,
+    pub sku: String,
+    pub name: String,
+    pub quantity: f64,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// HTML stok sayım föyü: konum, ardından kategori gruplu; sayım için boş sütun.
+/// `hide_quantities` sistem miktarını gizler (sayımı etkilememesi için).
+pub fn generate_count_sheet_html(
+    rows: &[CountSheetRow],
+    store_name: &str,
+    filter_label: &str,
+    date: &str,
+    hide_quantities: bool,
+) -> String {
+    let columns = if hide_quantities { 4 } else { 5 };
+    let mut header_cells = String::from("<th>SKU</th>\n                <th>Ürün</th>");
+    if !hide_quantities {
+        header_cells.push_str("\n                <th style=\"text-align:right\">Sistem</th>");
+    }
+    header_cells.push_str("\n                <th class=\"count\">Sayılan</th>\n                <th class=\"note\">Not</th>");
+
+    let mut body = String::new();
+    let mut current_location: Option<&str> = None;
+    let mut current_category: Option<&str> = None;
+    for row in rows {
+        if current_location != Some(row.location.as_str()) {
+            current_location = Some(row.location.as_str());
+            current_category = None;
+            body.push_str(&format!(
+                "\n            <tr class=\"location\"><td colspan=\"{}\">Konum: {}</td></tr>",
+                columns,
+                escape_html(&row.location)
+            ));
+        }
+        if current_category != Some(row.category.as_str()) {
+            current_category = Some(row.category.as_str());
+            body.push_str(&format!(
+                "\n            <tr class=\"category\"><td colspan=\"{}\">{}</td></tr>",
+                columns,
+                escape_html(&row.category)
+            ));
+        }
+
+        let mut cells = format!("<td>{}</td><td>{}</td>", escape_html(&row.sku), escape_html(&row.name));
+        if !hide_quantities {
+            cells.push_str(&format!("<td style=\"text-align:right\">{}</td>", row.quantity));
+        }
+        cells.push_str("<td class=\"count\"></td><td class=\"note\"></td>");
+        body.push_str(&format!("\n            <tr>{}</tr>", cells));
+    }
+
+    format!(r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>Sayım Föyü - {date}</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; padding: 20px; max-width: 800px; margin: 0 auto; font-size: 12px; }}
+        .header {{ text-align: center; margin-bottom: 20px; }}
+        .header h1 {{ margin: 0; color: #333; }}
+        table {{ width: 100%; border-collapse: collapse; }}
+        th, td {{ padding: 6px 8px; border-bottom: 1px solid #ddd; text-align: left; }}
+        th {{ background: #f5f5f5; }}
+        tr.location td {{ background: #333; color: #fff; font-weight: bold; }}
+        tr.category td {{ background: #eee; font-weight: bold; }}
+        .count {{ width: 90px; border-left: 1px solid #999; }}
+        .note {{ width: 140px; border-left: 1px solid #999; }}
+        .footer {{ margin-top: 30px; display: flex; justify-content: space-between; }}
+        @media print {{ body {{ padding: 0; }} tr {{ page-break-inside: avoid; }} }}
+    </style>
+</head>
+<body>
+    <div class="header">
+        <h1>{store}</h1>
+        <p>STOK SAYIM FÖYÜ</p>
+        <p>{filter} &middot; {date} &middot; {count} ürün</p>
+    </div>
+
+    <table>
+        <thead>
+            <tr>
+                {header_cells}
+            </tr>
+        </thead>
+        <tbody>{body}
+        </tbody>
+    </table>
+
+    <div class="footer">
+        <div>Sayan: ____________________</div>
+        <div>Kontrol eden: ____________________</div>
+    </div>
+</body>
+</html>
+    "#,
+        store = escape_html(store_name),
+        filter = escape_html(filter_label),
+        date = date,
+        count = rows.len(),
+        header_cells = header_cells,
+        body = body
+    )
+}
+
 /// Text ortalama yardımcı fonksiyonu
 fn center_text(text: &str, width: usize) -> String {
     if text.len() >= width {
@@ -163,3 +636,505 @@ fn center_text(text: &str, width: usize) -> String {
     let padding = (width - text.len()) / 2;
     format!("{:>width$}", text, width = padding + text.len())
 }
+
+// ==================== Receipt QR ====================
+
+/// Start of the QR payload: `FIS:<transaction id>.<token>`
+pub const RECEIPT_QR_PREFIX: &str = "FIS:";
+
+/// Hex characters of the HMAC kept as the token; enough to stop guessed ids, short enough for a small code
+const RECEIPT_QR_TOKEN_LEN: usize = 12;
+
+/// Pixels per module and blank modules around the code in the PNG
+const QR_MODULE_PIXELS: usize = 4;
+const QR_QUIET_ZONE: usize = 4;
+
+/// ESC/POS module size (dots) of the printed QR
+const ESCPOS_QR_MODULE_SIZE: u8 = 6;
+
+/// Signed token of a transaction id
+pub fn receipt_qr_token(transaction_id: &str, secret: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(transaction_id.as_bytes());
+    let digest = mac.finalize().into_bytes();
+    digest.iter().map(|b| format!("{:02x}", b)).collect::<String>()[..RECEIPT_QR_TOKEN_LEN].to_string()
+}
+
+/// Text encoded in the receipt QR of a transaction
+pub fn receipt_qr_payload(transaction_id: &str, secret: &str) -> String {
+    format!("{}{}.{}", RECEIPT_QR_PREFIX, transaction_id, receipt_qr_token(transaction_id, secret))
+}
+
+/// Transaction id of a scanned receipt QR. A bare id (typed from the receipt) is taken as is;
+/// a QR whose token does not match this store's secret is rejected.
+pub fn parse_receipt_qr(scanned: &str, secret: &str) -> Result<String, AppError> {
+    let scanned = scanned.trim();
+    let Some(signed) = scanned.strip_prefix(RECEIPT_QR_PREFIX) else {
+        return Ok(scanned.to_string());
+    };
+    let (id, token) = signed
+        .rsplit_once('.')
+        .ok_or_else(|| AppError::Validation("Geçersiz fiş QR kodu".to_string()))?;
+    if !token.eq_ignore_ascii_case(&receipt_qr_token(id, secret)) {
+        return Err(AppError::Validation("Fiş QR kodu bu mağazaya ait değil".to_string()));
+    }
+    Ok(id.to_string())
+}
+
+/// Key the QR tokens are signed with, created on first use
+pub fn receipt_qr_secret(conn: &Connection) -> Result<String, AppError> {
+    if let Some(secret) = settings::get_setting(conn, settings::RECEIPT_QR_SECRET_SETTING)?.filter(|s| !s.is_empty()) {
+        return Ok(secret);
+    }
+    let mut bytes = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let secret: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    settings::set_setting(conn, settings::RECEIPT_QR_SECRET_SETTING, &secret)?;
+    Ok(secret)
+}
+
+/// QR payload for the receipt of `transaction_id`, or None while the receipt QR setting is off
+pub fn receipt_qr_for(conn: &Connection, transaction_id: &str) -> Result<Option<String>, AppError> {
+    let enabled = settings::get_setting(conn, settings::RECEIPT_QR_SETTING)?
+        .is_some_and(|v| matches!(v.trim(), "1" | "true"));
+    if !enabled {
+        return Ok(None);
+    }
+    Ok(Some(receipt_qr_payload(transaction_id, &receipt_qr_secret(conn)?)))
+}
+
+/// PNG of the QR code of `payload`
+pub fn receipt_qr_png(payload: &str) -> Result<Vec<u8>, AppError> {
+    let code = QrCode::with_error_correction_level(payload.as_bytes(), EcLevel::M)
+        .map_err(|e| AppError::Validation(format!("QR kod olusturulamadi: {}", e)))?;
+    let modules = code.width();
+    let size = (modules + 2 * QR_QUIET_ZONE) * QR_MODULE_PIXELS;
+
+    let mut pixels = vec![255u8; size * size];
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != Color::Dark {
+            continue;
+        }
+        let x = (i % modules + QR_QUIET_ZONE) * QR_MODULE_PIXELS;
+        let y = (i / modules + QR_QUIET_ZONE) * QR_MODULE_PIXELS;
+        for row in y..y + QR_MODULE_PIXELS {
+            pixels[row * size + x..row * size + x + QR_MODULE_PIXELS].fill(0);
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| AppError::Internal(format!("QR kod olusturulamadi: {}", e)))?;
+    writer
+        .write_image_data(&pixels)
+        .map_err(|e| AppError::Internal(format!("QR kod olusturulamadi: {}", e)))?;
+    writer
+        .finish()
+        .map_err(|e| AppError::Internal(format!("QR kod olusturulamadi: {}", e)))?;
+    Ok(out)
+}
+
+/// `data:` URI of the QR PNG for the HTML receipt
+pub fn receipt_qr_data_uri(payload: &str) -> Result<String, AppError> {
+    Ok(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(receipt_qr_png(payload)?)
+    ))
+}
+
+/// ESC/POS commands printing `payload` as a centred QR (GS ( k, model 2).
+/// The receipt is text, so every byte must stay ASCII: None for payloads of 125 bytes or more.
+fn escpos_qr(payload: &str) -> Option<String> {
+    let store_len = payload.len() + 3;
+    if !payload.is_ascii() || store_len > 0x7F {
+        return None;
+    }
+    let gs_k = |args: &[u8]| -> String {
+        let mut bytes = vec![0x1D, b'(', b'k'];
+        bytes.extend_from_slice(args);
+        bytes.into_iter().map(char::from).collect()
+    };
+    let mut out = String::from("\x1Ba\x01"); // ESC a 1: centre
+    out.push_str(&gs_k(&[4, 0, 49, 65, 50, 0])); // model 2
+    out.push_str(&gs_k(&[3, 0, 49, 67, ESCPOS_QR_MODULE_SIZE])); // module size
+    out.push_str(&gs_k(&[3, 0, 49, 69, 49])); // error correction M
+    out.push_str(&gs_k(&[store_len as u8, 0, 49, 80, 48])); // store data
+    out.push_str(payload);
+    out.push_str(&gs_k(&[3, 0, 49, 81, 48])); // print
+    out.push_str("\x1Ba\x00"); // ESC a 0: left
+    Some(out)
+}
+
+// ==================== Shelf labels ====================
+
+/// 40x30mm label at 203 dpi (8 dots per mm)
+const LABEL_WIDTH: usize = 320;
+const LABEL_HEIGHT: usize = 240;
+const LABEL_MARGIN: usize = 8;
+
+/// Blank modules on each side of the barcode so scanners find its start and end
+const QUIET_ZONE_MODULES: usize = 10;
+const MAX_MODULE_WIDTH: usize = 3;
+
+const BARCODE_TOP: usize = 48;
+const BARCODE_HEIGHT: usize = 110;
+
+/// Rows of a 5x7 glyph (bit 4 is the leftmost column)
+fn glyph(c: char) -> [u8; 7] {
+    match c {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        ' ' => [0x00; 7],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // ?
+    }
+}
+
+/// Label text in the glyph set: upper case, Turkish letters without accents
+fn label_text(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            'ç' | 'Ç' => vec!['C'],
+            'ğ' | 'Ğ' => vec!['G'],
+            'ı' | 'i' | 'İ' => vec!['I'],
+            'ö' | 'Ö' => vec!['O'],
+            'ş' | 'Ş' => vec!['S'],
+            'ü' | 'Ü' => vec!['U'],
+            _ => c.to_uppercase().collect(),
+        })
+        .collect()
+}
+
+/// 8-bit grayscale canvas, white background
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Self { pixels: vec![255; LABEL_WIDTH * LABEL_HEIGHT] }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        for row in y..(y + height).min(LABEL_HEIGHT) {
+            let start = row * LABEL_WIDTH;
+            for col in x..(x + width).min(LABEL_WIDTH) {
+                self.pixels[start + col] = 0;
+            }
+        }
+    }
+
+    /// Draw one line of text centred horizontally; characters past the width are cut off
+    fn text_centered(&mut self, text: &str, y: usize, scale: usize) {
+        let advance = 6 * scale;
+        let max_chars = (LABEL_WIDTH - 2 * LABEL_MARGIN) / advance;
+        let chars: Vec<char> = text.chars().take(max_chars).collect();
+        let mut x = (LABEL_WIDTH - chars.len() * advance) / 2;
+        for c in chars {
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..5 {
+                    if bits & (0x10 >> col) != 0 {
+                        self.fill(x + col * scale, y + row * scale, scale, scale);
+                    }
+                }
+            }
+            x += advance;
+        }
+    }
+
+    fn to_png(&self) -> Result<Vec<u8>, AppError> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, LABEL_WIDTH as u32, LABEL_HEIGHT as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| AppError::Internal(format!("Etiket olusturulamadi: {}", e)))?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| AppError::Internal(format!("Etiket olusturulamadi: {}", e)))?;
+        writer
+            .finish()
+            .map_err(|e| AppError::Internal(format!("Etiket olusturulamadi: {}", e)))?;
+        Ok(out)
+    }
+}
+
+/// Code128 modules (1 = bar) of a SKU; even-length numeric SKUs use the compact set C
+fn code128_modules(sku: &str) -> Result<Vec<u8>, AppError> {
+    let set = if !sku.is_empty() && sku.len() % 2 == 0 && sku.chars().all(|c| c.is_ascii_digit()) {
+        '\u{0106}' // Code set C
+    } else {
+        '\u{0181}' // Code set B
+    };
+    let barcode = Code128::new(format!("{}{}", set, sku))
+        .map_err(|e| AppError::Validation(format!("Barkod olusturulamadi ({}): {}", sku, e)))?;
+    Ok(barcode.encode())
+}
+
+/// PNG shelf label for a 40x30mm label: name, Code128 barcode of the SKU, SKU and price
+pub fn generate_barcode_label(sku: &str, name: &str, price: f64) -> Result<Vec<u8>, AppError> {
+    let modules = code128_modules(sku)?;
+    let total_modules = modules.len() + 2 * QUIET_ZONE_MODULES;
+    let module_width = (LABEL_WIDTH / total_modules).min(MAX_MODULE_WIDTH);
+    if module_width == 0 {
+        return Err(AppError::Validation(format!("Stok kodu etikete sigmayacak kadar uzun: {}", sku)));
+    }
+
+    let mut canvas = Canvas::new();
+
+    // Name on up to two lines
+    let name = label_text(name.trim());
+    let per_line = (LABEL_WIDTH - 2 * LABEL_MARGIN) / 12;
+    let name_chars: Vec<char> = name.chars().collect();
+    for (i, line) in name_chars.chunks(per_line).take(2).enumerate() {
+        canvas.text_centered(&line.iter().collect::<String>(), LABEL_MARGIN + i * 18, 2);
+    }
+
+    let barcode_x = (LABEL_WIDTH - modules.len() * module_width) / 2;
+    for (i, bar) in modules.iter().enumerate() {
+        if *bar == 1 {
+            canvas.fill(barcode_x + i * module_width, BARCODE_TOP, module_width, BARCODE_HEIGHT);
+        }
+    }
+    canvas.text_centered(&label_text(sku), BARCODE_TOP + BARCODE_HEIGHT + 4, 2);
+
+    let price = format!("{} TL", format_money(price, &PrintConfig::default()));
+    canvas.text_centered(&price, LABEL_HEIGHT - LABEL_MARGIN - 21, 3);
+
+    canvas.to_png()
+}
+
+/// Label of an inventory item looked up by SKU
+pub fn generate_label_for_sku(conn: &Connection, sku: &str) -> Result<Vec<u8>, AppError> {
+    let item: Option<(String, f64)> = conn
+        .query_row(
+            "SELECT name, price FROM inventory_items WHERE sku = ?1",
+            params![sku],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let (name, price) = item.ok_or_else(|| AppError::NotFound(format!("Urun bulunamadi: {}", sku)))?;
+    generate_barcode_label(sku, &name, price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mixed_transaction() -> Transaction {
+        Transaction {
+            id: "abcdef12-0000-0000-0000-000000000000".to_string(),
+            items: vec![
+                CartItem {
+                    id: "1".to_string(),
+                    sku: "8680000000001".to_string(),
+                    name: "Silikon".to_string(),
+                    cart_quantity: 3.0,
+                    price: 94.99999999,
+                    cost_price: None,
+                    tax_rate: 20.0,
+                    discount_amount: None,
+                    discount_percent: None,
+                },
+                CartItem {
+                    id: "2".to_string(),
+                    sku: "8680000000002".to_string(),
+                    name: "Fırça (iade)".to_string(),
+                    cart_quantity: -1.0,
+                    price: 1250.5,
+                    cost_price: None,
+                    tax_rate: 20.0,
+                    discount_amount: None,
+                    discount_percent: None,
+                },
+            ],
+            total: -965.5,
+            payment_method: "CASH".to_string(),
+            transaction_type: "SALE".to_string(),
+            status: "COMPLETED".to_string(),
+            note: None,
+            created_at: "2024-03-01T10:15:00+00:00".to_string(),
+            customer_id: None,
+            tax_total: -160.92,
+            line_discount: 0.0,
+            ticket_discount: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_format_money() {
+        let tr = PrintConfig::default();
+        assert_eq!(format_money(284.9999999, &tr), "285,00");
+        assert_eq!(format_money(1234567.891, &tr), "1.234.567,89");
+        assert_eq!(format_money(-0.001, &tr), "0,00");
+        assert_eq!(format_money(-12.5, &tr), "-12,50");
+
+        let en = PrintConfig {
+            locale: "en-US".to_string(),
+            negative_style: NegativeStyle::Parentheses,
+            ..PrintConfig::default()
+        };
+        assert_eq!(format_money(1234.5, &en), "1,234.50");
+        assert_eq!(format_money(-1234.5, &en), "(1,234.50)");
+    }
+
+    #[test]
+    fn test_invoice_html_snapshot_mixed_sale_return() {
+        let config = PrintConfig {
+            negative_style: NegativeStyle::Parentheses,
+            tax_rate: 20.0,
+            ..PrintConfig::default()
+        };
+        let html = generate_invoice_html(&mixed_transaction(), "TL", "Nexus Yapı", &config);
+        assert_eq!(html, include_str!("snapshots/invoice_mixed_sale_return.html"));
+    }
+
+    #[test]
+    fn test_invoice_html_embeds_receipt_qr() {
+        let transaction = mixed_transaction();
+        let config = PrintConfig {
+            receipt_qr: Some(receipt_qr_payload(&transaction.id, "secret")),
+            ..PrintConfig::default()
+        };
+        let html = generate_invoice_html(&transaction, "TL", "Nexus Yapı", &config);
+
+        let start = html.find("src=\"data:image/png;base64,").expect("QR data URI") + 27;
+        let encoded = &html[start..start + html[start..].find('"').unwrap()];
+        let png_bytes = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        assert!(png_bytes.starts_with(&[0x89, b'P', b'N', b'G']));
+
+        // Setting off: no QR anywhere
+        let plain = generate_invoice_html(&transaction, "TL", "Nexus Yapı", &PrintConfig::default());
+        assert!(!plain.contains("data:image"));
+        assert!(!generate_receipt_text(&transaction, "TL", "Nexus Yapı", &PrintConfig::default()).contains('\x1D'));
+    }
+
+    #[test]
+    fn test_receipt_qr_token_and_escpos() {
+        let id = "abcdef12-0000-0000-0000-000000000000";
+        let payload = receipt_qr_payload(id, "secret");
+        assert_eq!(parse_receipt_qr(&payload, "secret").unwrap(), id);
+        assert_eq!(parse_receipt_qr(&format!(" {} ", id), "secret").unwrap(), id);
+        assert!(parse_receipt_qr(&payload, "other-store").is_err());
+        assert!(parse_receipt_qr(&format!("{}{}.000000000000", RECEIPT_QR_PREFIX, id), "secret").is_err());
+
+        let text = generate_receipt_text(
+            &mixed_transaction(),
+            "TL",
+            "Nexus Yapı",
+            &PrintConfig { receipt_qr: Some(payload.clone()), ..PrintConfig::default() },
+        );
+        let store = format!("\x1D(k{}\x00\x31\x50\x30{}", char::from(payload.len() as u8 + 3), payload);
+        assert!(text.contains(&store));
+        assert!(text.contains("\x1D(k\x03\x00\x31\x51\x30"));
+        assert!(escpos_qr(&"x".repeat(125)).is_none());
+    }
+
+    #[test]
+    fn test_receipt_qr_follows_setting() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT);").unwrap();
+        assert_eq!(receipt_qr_for(&conn, "tx-1").unwrap(), None);
+
+        settings::set_setting(&conn, settings::RECEIPT_QR_SETTING, "true").unwrap();
+        let payload = receipt_qr_for(&conn, "tx-1").unwrap().unwrap();
+        // The secret is created once and reused
+        assert_eq!(receipt_qr_for(&conn, "tx-1").unwrap().unwrap(), payload);
+        assert_eq!(parse_receipt_qr(&payload, &receipt_qr_secret(&conn).unwrap()).unwrap(), "tx-1");
+    }
+
+    #[test]
+    fn test_count_sheet_groups_and_hides_quantities() {
+        let row = |location: &str, category: &str, sku: &str, quantity: i32| CountSheetRow {
+            location: location.to_string(),
+            category: category.to_string(),
+            sku: sku.to_string(),
+            name: format!("Ürün {}", sku),
+            quantity: quantity.into(),
+        };
+        let rows = vec![
+            row("A-01", "Boya", "100", 7),
+            row("A-01", "Boya", "101", 3),
+            row("A-01", "Vida", "200", 150),
+            row("B-02", "Boya", "102", 12),
+        ];
+
+        let html = generate_count_sheet_html(&rows, "Nexus Yapı", "Tüm konumlar", "2024-03-01", false);
+        assert_eq!(html.matches("class=\"location\"").count(), 2);
+        assert_eq!(html.matches("class=\"category\"").count(), 3);
+        assert!(html.contains(">150</td>"));
+
+        let blind = generate_count_sheet_html(&rows, "Nexus Yapı", "Tüm konumlar", "2024-03-01", true);
+        assert!(!blind.contains(">150</td>"));
+        assert!(!blind.contains("Sistem"));
+    }
+
+    #[test]
+    fn test_barcode_label_png() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (sku TEXT PRIMARY KEY, name TEXT NOT NULL, price REAL NOT NULL);
+            INSERT INTO inventory_items VALUES ('00001036', 'Silikon Şeffaf 280ml', 125.5);",
+        )
+        .unwrap();
+
+        let png_bytes = generate_label_for_sku(&conn, "00001036").unwrap();
+        assert!(png_bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]));
+
+        let mut reader = png::Decoder::new(png_bytes.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (320, 240));
+        assert!(pixels.contains(&0));
+
+        assert!(matches!(generate_label_for_sku(&conn, "missing"), Err(AppError::NotFound(_))));
+        assert_eq!(label_text("çiğ Işık"), "CIG ISIK");
+    }
+}
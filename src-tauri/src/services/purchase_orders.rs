@@ -0,0 +1,370 @@
+//! Purchase Order Service Module
+//!
+//! Tedarikçiye gönderilen satın alma siparişleri. Sipariş numarası fatura numarası sayacından
+//! `PO` önekiyle alınır; sipariş sonradan mal kabul fişiyle eşleştirilebilsin diye saklanır.
+
+use chrono::Datelike;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::models::{GoodsReceiptItem, POLine, PurchaseOrder};
+use crate::services::finance;
+use crate::services::print::{self, DocumentParty, PrintConfig};
+use crate::services::settings;
+use crate::services::tax::round_money;
+use crate::services::units;
+
+/// Number prefix of purchase orders (`PO-2026-00001`)
+pub const PURCHASE_ORDER_PREFIX: &str = "PO";
+
+pub const PO_STATUS_OPEN: &str = "open";
+/// Some goods arrived, at least one line is still short
+pub const PO_STATUS_PARTIAL: &str = "partial";
+pub const PO_STATUS_RECEIVED: &str = "received";
+
+const ORDER_COLUMNS: &str = "id, po_number, supplier_id, lines, total, status, created_at";
+
+fn order_from_row(row: &rusqlite::Row) -> rusqlite::Result<PurchaseOrder> {
+    let lines: String = row.get(3)?;
+    Ok(PurchaseOrder {
+        id: row.get(0)?,
+        po_number: row.get(1)?,
+        supplier_id: row.get(2)?,
+        lines: serde_json::from_str(&lines).unwrap_or_default(),
+        total: row.get(4)?,
+        status: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+fn validate_lines(lines: &[POLine]) -> Result<(), AppError> {
+    if lines.is_empty() {
+        return Err(AppError::Validation("Sipariste en az bir kalem olmali".to_string()));
+    }
+    for line in lines {
+        if line.sku.trim().is_empty() {
+            return Err(AppError::Validation("Siparis kaleminde stok kodu bos olamaz".to_string()));
+        }
+        if line.quantity <= 0.0 {
+            return Err(AppError::Validation(format!("Gecersiz siparis adedi: {} ({})", line.quantity, line.sku)));
+        }
+        if !line.buy_price.is_finite() || line.buy_price < 0.0 {
+            return Err(AppError::Validation(format!("Gecersiz alis fiyati: {} ({})", line.buy_price, line.sku)));
+        }
+    }
+    Ok(())
+}
+
+/// Supplier details for the document; customer-only accounts are rejected
+fn supplier_party(conn: &Connection, supplier_id: &str) -> Result<DocumentParty, AppError> {
+    let (party, account_type) = conn
+        .query_row(
+            "SELECT name, address, phone, email, tax_number, account_type FROM current_accounts WHERE id = ?1",
+            params![supplier_id],
+            |row| {
+                Ok((
+                    DocumentParty {
+                        name: row.get(0)?,
+                        address: row.get(1)?,
+                        phone: row.get(2)?,
+                        email: row.get(3)?,
+                        tax_number: row.get(4)?,
+                    },
+                    row.get::<_, String>(5)?,
+                ))
+            },
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Tedarikci bulunamadi: {}", supplier_id)))?;
+
+    if account_type == "CUSTOMER" {
+        return Err(AppError::Validation(format!("{} bir tedarikci hesabi degil", party.name)));
+    }
+    Ok(party)
+}
+
+/// Our store details from settings
+fn store_party(conn: &Connection) -> Result<DocumentParty, AppError> {
+    Ok(DocumentParty {
+        name: settings::get_setting(conn, settings::STORE_NAME_SETTING)?.unwrap_or_default(),
+        address: settings::get_setting(conn, settings::STORE_ADDRESS_SETTING)?,
+        phone: settings::get_setting(conn, settings::STORE_PHONE_SETTING)?,
+        email: None,
+        tax_number: settings::get_setting(conn, settings::STORE_TAX_NUMBER_SETTING)?,
+    })
+}
+
+/// Save a purchase order for `supplier_id` under the next `PO-YEAR-NNNNN` number.
+/// Empty line names fall back to the SKU.
+pub fn create_purchase_order(
+    conn: &Connection,
+    supplier_id: &str,
+    mut lines: Vec<POLine>,
+) -> Result<PurchaseOrder, AppError> {
+    validate_lines(&lines)?;
+    supplier_party(conn, supplier_id)?;
+    for line in &mut lines {
+        if line.name.trim().is_empty() {
+            line.name = line.sku.clone();
+        }
+        line.received_quantity = 0.0;
+    }
+
+    let po_number = finance::next_invoice_number(conn, PURCHASE_ORDER_PREFIX, chrono::Local::now().year())?;
+    let order = PurchaseOrder {
+        id: uuid::Uuid::new_v4().to_string(),
+        po_number,
+        supplier_id: supplier_id.to_string(),
+        total: round_money(lines.iter().map(|l| l.buy_price * l.quantity).sum()),
+        lines,
+        status: PO_STATUS_OPEN.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    conn.execute(
+        "INSERT INTO purchase_orders (id, po_number, supplier_id, lines, total, status, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            &order.id,
+            &order.po_number,
+            &order.supplier_id,
+            serde_json::to_string(&order.lines)?,
+            order.total,
+            &order.status,
+            &order.created_at,
+        ],
+    )?;
+    Ok(order)
+}
+
+/// HTML document of a saved order with supplier and store details
+pub fn purchase_order_html(conn: &Connection, order: &PurchaseOrder) -> Result<String, AppError> {
+    let supplier = supplier_party(conn, &order.supplier_id)?;
+    let store = store_party(conn)?;
+    let currency = settings::get_setting(conn, settings::CURRENCY_SETTING)?
+        .filter(|c| !c.trim().is_empty())
+        .unwrap_or_else(|| "TL".to_string());
+    Ok(print::generate_purchase_order_html(order, &supplier, &store, &currency, &PrintConfig::default()))
+}
+
+pub fn get_purchase_order(conn: &Connection, order_id: &str) -> Result<PurchaseOrder, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM purchase_orders WHERE id = ?1", ORDER_COLUMNS),
+        params![order_id],
+        order_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("Siparis bulunamadi: {}", order_id)))
+}
+
+/// Orders still waiting for goods (open or partial), oldest first, optionally for one supplier
+pub fn get_open_purchase_orders(conn: &Connection, supplier_id: Option<&str>) -> Result<Vec<PurchaseOrder>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM purchase_orders
+         WHERE status IN (?1, ?2) AND (?3 IS NULL OR supplier_id = ?3)
+         ORDER BY created_at, po_number",
+        ORDER_COLUMNS
+    ))?;
+    let orders = stmt
+        .query_map(params![PO_STATUS_OPEN, PO_STATUS_PARTIAL, supplier_id], order_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(orders)
+}
+
+/// Count `items` of a goods receipt as received against the order and update its status.
+///
+/// Items are matched to order lines by SKU (stock card barcode or inventory SKU of the product).
+/// Quantities that differ from the order do not block the receipt; they come back as warnings.
+pub fn receive_purchase_order(
+    conn: &Connection,
+    order_id: &str,
+    supplier_id: Option<&str>,
+    items: &[GoodsReceiptItem],
+) -> Result<(PurchaseOrder, Vec<String>), AppError> {
+    let mut order = get_purchase_order(conn, order_id)?;
+    if order.status == PO_STATUS_RECEIVED {
+        return Err(AppError::Validation(format!("{} siparisi zaten teslim alinmis", order.po_number)));
+    }
+    if let Some(supplier_id) = supplier_id.filter(|id| *id != order.supplier_id) {
+        return Err(AppError::Validation(format!(
+            "{} siparisi baska bir tedarikciye ait ({})",
+            order.po_number, supplier_id
+        )));
+    }
+
+    let mut warnings = Vec::new();
+    for item in items {
+        let sku: Option<String> = conn.query_row(
+            "SELECT COALESCE(
+                 (SELECT barcode FROM stock_cards WHERE id = ?1),
+                 (SELECT sku FROM inventory_items WHERE id = ?1)
+             )",
+            params![&item.product_id],
+            |row| row.get(0),
+        )?;
+        let sku = sku.unwrap_or_else(|| item.product_id.clone());
+
+        match order.lines.iter_mut().find(|line| line.sku == sku) {
+            Some(line) => line.received_quantity = units::round_quantity(line.received_quantity + item.quantity),
+            None => warnings.push(format!("{}: sipariste yok, {} adet teslim alindi", sku, item.quantity)),
+        }
+    }
+
+    for line in &order.lines {
+        if line.received_quantity > line.quantity {
+            warnings.push(format!(
+                "{}: {} adet siparis edildi, {} adet teslim alindi",
+                line.sku, line.quantity, line.received_quantity
+            ));
+        } else if line.received_quantity < line.quantity {
+            warnings.push(format!(
+                "{}: {} adet eksik",
+                line.sku,
+                units::round_quantity(line.quantity - line.received_quantity)
+            ));
+        }
+    }
+
+    order.status = if order.lines.iter().all(|line| line.received_quantity >= line.quantity) {
+        PO_STATUS_RECEIVED
+    } else if order.lines.iter().any(|line| line.received_quantity > 0.0) {
+        PO_STATUS_PARTIAL
+    } else {
+        PO_STATUS_OPEN
+    }
+    .to_string();
+
+    conn.execute(
+        "UPDATE purchase_orders SET lines = ?1, status = ?2 WHERE id = ?3",
+        params![serde_json::to_string(&order.lines)?, &order.status, &order.id],
+    )?;
+    Ok((order, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE current_accounts (
+                id TEXT PRIMARY KEY, name TEXT NOT NULL, account_type TEXT NOT NULL DEFAULT 'CUSTOMER',
+                tax_number TEXT, phone TEXT, email TEXT, address TEXT
+            );
+            CREATE TABLE purchase_orders (
+                id TEXT PRIMARY KEY NOT NULL, po_number TEXT UNIQUE NOT NULL, supplier_id TEXT NOT NULL,
+                lines TEXT NOT NULL, total REAL NOT NULL, status TEXT NOT NULL DEFAULT 'OPEN', created_at TEXT NOT NULL
+            );
+            CREATE TABLE invoice_counters (prefix TEXT NOT NULL, year INTEGER NOT NULL, last_seq INTEGER NOT NULL, PRIMARY KEY (prefix, year));
+            CREATE TABLE finance_records (id INTEGER PRIMARY KEY AUTOINCREMENT, description TEXT);
+            CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);
+            CREATE TABLE stock_cards (id TEXT PRIMARY KEY, barcode TEXT);
+            CREATE TABLE inventory_items (id TEXT PRIMARY KEY, sku TEXT);
+            INSERT INTO stock_cards VALUES ('card-a', 'A'), ('card-b', 'B');
+            INSERT INTO inventory_items VALUES ('item-c', 'C');
+            INSERT INTO current_accounts (id, name, account_type, tax_number, address) VALUES
+                ('s1', 'Boya Tedarik A.S.', 'SUPPLIER', '1234567890', 'Ostim, Ankara'),
+                ('c1', 'Ahmet Usta', 'CUSTOMER', NULL, NULL);
+            INSERT INTO settings VALUES ('store_name', 'Nisa Yapi'), ('currency', 'TL');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn line(sku: &str, quantity: i32, buy_price: f64) -> POLine {
+        POLine { sku: sku.to_string(), name: format!("Urun {}", sku), quantity: quantity.into(), buy_price, received_quantity: 0.0 }
+    }
+
+    fn received(product_id: &str, quantity: i32) -> GoodsReceiptItem {
+        GoodsReceiptItem { product_id: product_id.to_string(), quantity: quantity.into(), buy_price: 10.0, sell_price: None, expiry_date: None }
+    }
+
+    #[test]
+    fn test_purchase_order_number_and_total() {
+        let conn = setup_db();
+        let year = chrono::Local::now().year();
+
+        let order = create_purchase_order(&conn, "s1", vec![line("A", 3, 12.5), line("B", 2, 7.25), line("C", 1, 0.1)]).unwrap();
+        assert_eq!(order.po_number, format!("PO-{}-00001", year));
+        assert_eq!(order.total, 52.1);
+
+        let (lines, total): (String, f64) = conn
+            .query_row("SELECT lines, total FROM purchase_orders WHERE po_number = ?1", params![&order.po_number], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(serde_json::from_str::<Vec<POLine>>(&lines).unwrap().len(), 3);
+        assert_eq!(total, 52.1);
+
+        let second = create_purchase_order(&conn, "s1", vec![line("A", 1, 10.0)]).unwrap();
+        assert_eq!(second.po_number, format!("PO-{}-00002", year));
+
+        let html = purchase_order_html(&conn, &order).unwrap();
+        assert!(html.contains(&order.po_number));
+        assert!(html.contains("Boya Tedarik A.S."));
+        assert!(html.contains("Vergi No: 1234567890"));
+        assert!(html.contains("Nisa Yapi"));
+        assert!(html.contains("52,10 TL"));
+
+        assert!(create_purchase_order(&conn, "s1", vec![]).is_err());
+        assert!(create_purchase_order(&conn, "s1", vec![line("A", 0, 10.0)]).is_err());
+        assert!(create_purchase_order(&conn, "c1", vec![line("A", 1, 10.0)]).is_err());
+        assert!(matches!(create_purchase_order(&conn, "nope", vec![line("A", 1, 10.0)]), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_full_receipt_closes_order() {
+        let conn = setup_db();
+        let order = create_purchase_order(&conn, "s1", vec![line("A", 3, 10.0), line("C", 2, 5.0)]).unwrap();
+        assert_eq!(get_open_purchase_orders(&conn, Some("s1")).unwrap().len(), 1);
+
+        let (order, warnings) =
+            receive_purchase_order(&conn, &order.id, Some("s1"), &[received("card-a", 3), received("item-c", 2)]).unwrap();
+        assert_eq!(order.status, PO_STATUS_RECEIVED);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert!(get_open_purchase_orders(&conn, None).unwrap().is_empty());
+
+        // A closed order cannot be received again
+        assert!(receive_purchase_order(&conn, &order.id, None, &[received("card-a", 1)]).is_err());
+    }
+
+    #[test]
+    fn test_partial_receipt_leaves_order_open() {
+        let conn = setup_db();
+        let order = create_purchase_order(&conn, "s1", vec![line("A", 5, 10.0), line("B", 2, 5.0)]).unwrap();
+
+        let (_, warnings) = receive_purchase_order(&conn, &order.id, None, &[received("card-a", 2)]).unwrap();
+        let stored = get_purchase_order(&conn, &order.id).unwrap();
+        assert_eq!(stored.status, PO_STATUS_PARTIAL);
+        assert_eq!(stored.lines.iter().map(|l| l.received_quantity).collect::<Vec<_>>(), vec![2.0, 0.0]);
+        assert_eq!(warnings, vec!["A: 3 adet eksik".to_string(), "B: 2 adet eksik".to_string()]);
+        assert_eq!(get_open_purchase_orders(&conn, Some("s1")).unwrap().len(), 1);
+        assert!(get_open_purchase_orders(&conn, Some("c1")).unwrap().is_empty());
+
+        // The rest arrives with the next receipt
+        let (order, warnings) =
+            receive_purchase_order(&conn, &order.id, Some("s1"), &[received("card-a", 3), received("card-b", 2)]).unwrap();
+        assert_eq!(order.status, PO_STATUS_RECEIVED);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_over_receipt_warns_but_closes_order() {
+        let conn = setup_db();
+        let order = create_purchase_order(&conn, "s1", vec![line("A", 3, 10.0)]).unwrap();
+        assert!(receive_purchase_order(&conn, &order.id, Some("other"), &[received("card-a", 3)]).is_err());
+
+        let (order, warnings) =
+            receive_purchase_order(&conn, &order.id, Some("s1"), &[received("card-a", 5), received("item-c", 1)]).unwrap();
+        assert_eq!(order.status, PO_STATUS_RECEIVED);
+        assert_eq!(order.lines[0].received_quantity, 5.0);
+        assert_eq!(
+            warnings,
+            vec![
+                "C: sipariste yok, 1 adet teslim alindi".to_string(),
+                "A: 3 adet siparis edildi, 5 adet teslim alindi".to_string(),
+            ]
+        );
+    }
+}
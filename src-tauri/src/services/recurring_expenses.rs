@@ -0,0 +1,278 @@
+//! Recurring Expenses Module
+//!
+//! Kira, elektrik, internet gibi her ay aynı gün ödenen giderler. Günü gelen ve bu ay henüz
+//! yazılmamış her tanım için EXPENSE işlemi oluşturulur; yazılan ay `last_generated_period`
+//! içinde tutulur, böylece uygulama gün içinde kaç kez açılırsa açılsın gider bir kez yazılır.
+
+use std::time::Duration;
+
+use chrono::{Datelike, Local, NaiveDate, NaiveTime};
+use rusqlite::{params, Connection, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::models::Transaction;
+use crate::services::{finance, inventory};
+
+/// How often the background job looks for due expenses; generation is idempotent per month
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// A monthly expense definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringExpense {
+    #[serde(default)]
+    pub id: String,
+    pub description: String,
+    pub amount: f64,
+    pub category: String,
+    #[serde(rename = "paymentMethod")]
+    pub payment_method: String,
+    /// 1-31; in shorter months the expense falls on the last day
+    #[serde(rename = "dayOfMonth")]
+    pub day_of_month: u32,
+    pub active: bool,
+    /// "YYYY-MM" of the last month written
+    #[serde(rename = "lastGeneratedPeriod", default)]
+    pub last_generated_period: Option<String>,
+}
+
+fn validate(expense: &RecurringExpense) -> Result<(), AppError> {
+    if expense.description.trim().is_empty() {
+        return Err(AppError::Validation("Gider açıklaması boş olamaz".to_string()));
+    }
+    if !expense.amount.is_finite() || expense.amount <= 0.0 {
+        return Err(AppError::Validation(format!("Geçersiz gider tutarı: {}", expense.amount)));
+    }
+    if !(1..=31).contains(&expense.day_of_month) {
+        return Err(AppError::Validation(format!("Ayın günü 1-31 arasında olmalı: {}", expense.day_of_month)));
+    }
+    Ok(())
+}
+
+/// Day the expense falls on in the month of `date`
+pub fn due_date(date: NaiveDate, day_of_month: u32) -> NaiveDate {
+    (1..=day_of_month.clamp(1, 31))
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(date.year(), date.month(), day))
+        .unwrap_or(date)
+}
+
+fn period(date: NaiveDate) -> String {
+    date.format("%Y-%m").to_string()
+}
+
+/// All definitions, by day of month
+pub fn list_recurring_expenses(conn: &Connection) -> Result<Vec<RecurringExpense>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, description, amount, category, payment_method, day_of_month, active, last_generated_period
+         FROM recurring_expenses ORDER BY day_of_month, description",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(RecurringExpense {
+            id: row.get(0)?,
+            description: row.get(1)?,
+            amount: row.get(2)?,
+            category: row.get(3)?,
+            payment_method: row.get(4)?,
+            day_of_month: row.get(5)?,
+            active: row.get(6)?,
+            last_generated_period: row.get(7)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Add a definition; a blank id gets a new one
+pub fn create_recurring_expense(conn: &Connection, mut expense: RecurringExpense) -> Result<RecurringExpense, AppError> {
+    validate(&expense)?;
+    if expense.id.trim().is_empty() {
+        expense.id = uuid::Uuid::new_v4().to_string();
+    }
+    expense.payment_method = finance::canonical_payment_method(&expense.payment_method);
+
+    conn.execute(
+        "INSERT INTO recurring_expenses
+         (id, description, amount, category, payment_method, day_of_month, active, last_generated_period, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            expense.id,
+            expense.description.trim(),
+            expense.amount,
+            expense.category,
+            expense.payment_method,
+            expense.day_of_month,
+            expense.active,
+            expense.last_generated_period,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(expense)
+}
+
+/// Change a definition; the month already generated is kept
+pub fn update_recurring_expense(conn: &Connection, expense: &RecurringExpense) -> Result<(), AppError> {
+    validate(expense)?;
+    let affected = conn.execute(
+        "UPDATE recurring_expenses
+         SET description = ?1, amount = ?2, category = ?3, payment_method = ?4, day_of_month = ?5, active = ?6
+         WHERE id = ?7",
+        params![
+            expense.description.trim(),
+            expense.amount,
+            expense.category,
+            finance::canonical_payment_method(&expense.payment_method),
+            expense.day_of_month,
+            expense.active,
+            expense.id
+        ],
+    )?;
+    if affected == 0 {
+        return Err(AppError::NotFound(format!("Tekrarlayan gider bulunamadı: {}", expense.id)));
+    }
+    Ok(())
+}
+
+/// Remove a definition; expenses already written stay
+pub fn delete_recurring_expense(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let affected = conn.execute("DELETE FROM recurring_expenses WHERE id = ?1", params![id])?;
+    if affected == 0 {
+        return Err(AppError::NotFound(format!("Tekrarlayan gider bulunamadı: {}", id)));
+    }
+    Ok(())
+}
+
+/// Write this month's expense of every active definition whose day has come on `today` and that
+/// was not written yet. Each month is claimed with a conditional update inside an immediate
+/// transaction, so several windows or app instances cannot write it twice. Earlier missed months
+/// are not back-filled.
+pub fn generate_due_expenses(conn: &Connection, today: NaiveDate) -> Result<Vec<Transaction>, AppError> {
+    let period = period(today);
+    let tx = rusqlite::Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+
+    let mut created = Vec::new();
+    for expense in list_recurring_expenses(&tx)? {
+        if !expense.active || expense.last_generated_period.as_deref() >= Some(period.as_str()) {
+            continue;
+        }
+        let due = due_date(today, expense.day_of_month);
+        if today < due {
+            continue;
+        }
+
+        let claimed = tx.execute(
+            "UPDATE recurring_expenses SET last_generated_period = ?1
+             WHERE id = ?2 AND active = 1 AND (last_generated_period IS NULL OR last_generated_period < ?1)",
+            params![period, expense.id],
+        )?;
+        if claimed == 0 {
+            continue;
+        }
+
+        let date = due.and_time(NaiveTime::MIN).and_utc().to_rfc3339();
+        created.push(inventory::record_expense(
+            &tx,
+            &format!("{} ({})", expense.description, period),
+            expense.amount,
+            &expense.category,
+            &expense.payment_method,
+            &date,
+        )?);
+    }
+
+    tx.commit()?;
+    Ok(created)
+}
+
+/// Generate due expenses now and then every hour; called once at startup
+pub fn start_recurring_expense_job(db_path: String) {
+    std::thread::spawn(move || loop {
+        let result = crate::database::open_database(&db_path)
+            .map_err(AppError::from)
+            .and_then(|conn| generate_due_expenses(&conn, Local::now().date_naive()));
+        match result {
+            Ok(created) if !created.is_empty() => println!("[Expenses] {} recurring expenses recorded", created.len()),
+            Ok(_) => {}
+            Err(e) => eprintln!("[Expenses] Recurring expenses could not be generated: {}", e),
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn rent(day_of_month: u32) -> RecurringExpense {
+        RecurringExpense {
+            id: String::new(),
+            description: "Dükkan kirası".to_string(),
+            amount: 15000.0,
+            category: "Kira".to_string(),
+            payment_method: "Havale".to_string(),
+            day_of_month,
+            active: true,
+            last_generated_period: None,
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn expense_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM transactions WHERE transaction_type = 'EXPENSE'", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_due_recurrence_generates_exactly_one_expense() {
+        let conn = setup();
+        let expense = create_recurring_expense(&conn, rent(5)).unwrap();
+
+        let created = generate_due_expenses(&conn, date("2024-03-10")).unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!((created[0].total, created[0].payment_method.as_str()), (-15000.0, "HAVALE"));
+        assert_eq!(created[0].created_at, "2024-03-05T00:00:00+00:00");
+        assert_eq!(created[0].note.as_deref(), Some("Dükkan kirası (2024-03)"));
+
+        // Opening the app again the same day or later in the month adds nothing
+        assert!(generate_due_expenses(&conn, date("2024-03-10")).unwrap().is_empty());
+        assert!(generate_due_expenses(&conn, date("2024-03-31")).unwrap().is_empty());
+        assert_eq!(expense_count(&conn), 1);
+        let stored = list_recurring_expenses(&conn).unwrap();
+        assert_eq!(stored[0].last_generated_period.as_deref(), Some("2024-03"));
+
+        // Next month it is due again
+        assert_eq!(generate_due_expenses(&conn, date("2024-04-05")).unwrap().len(), 1);
+        assert_eq!(expense_count(&conn), 2);
+
+        delete_recurring_expense(&conn, &expense.id).unwrap();
+        assert!(delete_recurring_expense(&conn, &expense.id).is_err());
+    }
+
+    #[test]
+    fn test_not_yet_due_or_inactive_recurrence_generates_nothing() {
+        let conn = setup();
+        create_recurring_expense(&conn, rent(20)).unwrap();
+        let mut paused = create_recurring_expense(&conn, RecurringExpense { description: "Internet".to_string(), ..rent(1) }).unwrap();
+        paused.active = false;
+        update_recurring_expense(&conn, &paused).unwrap();
+
+        assert!(generate_due_expenses(&conn, date("2024-03-19")).unwrap().is_empty());
+        assert_eq!(expense_count(&conn), 0);
+
+        // Day 31 falls on the last day of a short month
+        assert_eq!(due_date(date("2024-02-10"), 31), date("2024-02-29"));
+        assert_eq!(due_date(date("2023-02-10"), 30), date("2023-02-28"));
+        assert_eq!(due_date(date("2024-04-10"), 31), date("2024-04-30"));
+        assert_eq!(due_date(date("2024-04-10"), 7), date("2024-04-07"));
+
+        assert!(create_recurring_expense(&conn, rent(0)).is_err());
+        assert!(create_recurring_expense(&conn, RecurringExpense { amount: -1.0, ..rent(1) }).is_err());
+    }
+}
@@ -0,0 +1,1216 @@
+//! Reports Service Module
+//!
+//! Muhasebe ve analiz raporları (stok devir hızı vb.)
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, Offset, Timelike};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::models::{
+    CartItem, CategoryProfit, DailySummaryRow, HeatCell, HourlySales, ItemVelocity, ProductSales, ProfitReport, ReorderLine,
+    ReorderSuggestion, TurnoverReport, TurnoverRow,
+};
+use crate::services::discounts;
+use crate::services::finance::PaymentMethod;
+use crate::services::settings;
+use crate::services::tax::round_money;
+
+/// Settings key for the shop's UTC offset in minutes (ör. Türkiye için 180)
+pub const TIMEZONE_OFFSET_SETTING: &str = "timezone_offset_minutes";
+
+/// Lead time used for the reorder date when none is given
+pub const DEFAULT_LEAD_TIME_DAYS: i64 = 7;
+
+/// Days of sales the reorder report measures velocity over
+pub const REORDER_WINDOW_DAYS: i64 = 30;
+
+/// Settings key for how many days of sales a reorder should cover
+pub const REORDER_COVER_DAYS_SETTING: &str = "reorder_cover_days";
+
+/// Days of cover used when `reorder_cover_days` is unset or invalid
+pub const DEFAULT_REORDER_COVER_DAYS: i64 = 30;
+
+/// Reorder report group of items without a supplier
+const UNASSIGNED_SUPPLIER: &str = "Atanmamış";
+
+/// Fewer distinct sale days than this in the window makes the velocity unreliable
+const MIN_SALE_DAYS: i64 = 3;
+
+/// Profit report category of lines whose SKU is not in the inventory (hizmet vb.)
+const UNCATEGORIZED: &str = "Kategorisiz";
+
+/// Rapor hesaplamaları için ürün başına sabit bilgiler
+struct ItemInfo {
+    category: String,
+    unit_cost: f64,
+}
+
+/// Kategori bazında biriken değerler
+#[derive(Default)]
+struct CategoryTotals {
+    current_value: f64,
+    net_after_end: f64,
+    net_in_period: f64,
+    cogs: f64,
+}
+
+/// Parse a `YYYY-MM-DD` report date
+pub(crate) fn parse_report_date(value: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::Validation(format!("Geçersiz tarih: {} (YYYY-AA-GG bekleniyor)", value)))
+}
+
+/// Load category and unit cost (cost_price, falling back to price when missing or 0) per SKU
+fn load_item_info(conn: &Connection) -> Result<HashMap<String, ItemInfo>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT sku, category, COALESCE(NULLIF(cost_price, 0), price) FROM inventory_items",
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            ItemInfo {
+                category: row.get(1)?,
+                unit_cost: row.get(2)?,
+            },
+        ))
+    })?;
+
+    Ok(rows.collect::<Result<HashMap<_, _>, _>>()?)
+}
+
+/// Inventory turnover per category for the given period.
+///
+/// COGS = satılan satırlar × maliyet (iadeler düşülür).
+/// Ortalama stok = (dönem başı değeri + dönem sonu değeri) / 2.
+/// Dönem sonu/başı değerleri mevcut stok değerinden, sonraki hareketler geri alınarak yaklaşık hesaplanır.
+pub fn get_turnover_report(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+) -> Result<TurnoverReport, AppError> {
+    let start = parse_report_date(start_date)?;
+    let end = parse_report_date(end_date)?;
+    if end < start {
+        return Err(AppError::Validation("Bitiş tarihi başlangıç tarihinden önce olamaz".to_string()));
+    }
+    let period_days = (end - start).num_days() + 1;
+
+    let items = load_item_info(conn)?;
+    let mut totals: HashMap<String, CategoryTotals> = HashMap::new();
+
+    // 1. Current inventory value per category
+    {
+        let mut stmt = conn.prepare(
+            "SELECT category, COALESCE(SUM(quantity * COALESCE(NULLIF(cost_price, 0), price)), 0) FROM inventory_items WHERE deleted_at IS NULL GROUP BY category",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))?;
+        for row in rows {
+            let (category, value) = row?;
+            totals.entry(category).or_default().current_value = value;
+        }
+    }
+
+    // 2. Stock receipts (lots) since the period start
+    {
+        let mut stmt = conn.prepare(
+            "SELECT i.category, COALESCE(NULLIF(i.cost_price, 0), i.price), l.initial_quantity, substr(l.created_at, 1, 10)
+             FROM inventory_lots l
+             JOIN inventory_items i ON i.id = l.product_id
+             WHERE substr(l.created_at, 1, 10) >= ?1",
+        )?;
+        let rows = stmt.query_map(params![start_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+        for row in rows {
+            let (category, unit_cost, quantity, date) = row?;
+            let entry = totals.entry(category).or_default();
+            let value = unit_cost * quantity;
+            if date.as_str() > end_date {
+                entry.net_after_end += value;
+            } else {
+                entry.net_in_period += value;
+            }
+        }
+    }
+
+    // 3. Sales and returns since the period start
+    {
+        let mut stmt = conn.prepare(
+            "SELECT items, transaction_type, substr(created_at, 1, 10)
+             FROM transactions
+             WHERE transaction_type IN ('SALE', 'RETURN') AND status != 'voided' AND substr(created_at, 1, 10) >= ?1",
+        )?;
+        let rows = stmt.query_map(params![start_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (items_json, transaction_type, date) = row?;
+            let cart_items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
+            let direction = if transaction_type == "RETURN" { 1.0 } else { -1.0 };
+
+            for line in cart_items {
+                // Discount lines and service SKUs do not move stock
+                if line.price < 0.0 {
+                    continue;
+                }
+                let Some(info) = items.get(&line.sku) else {
+                    continue;
+                };
+
+                let entry = totals.entry(info.category.clone()).or_default();
+                let qty = line.cart_quantity;
+                if date.as_str() > end_date {
+                    entry.net_after_end += direction * qty * info.unit_cost;
+                } else {
+                    entry.net_in_period += direction * qty * info.unit_cost;
+                    let line_cost = line.cost_price.filter(|c| *c > 0.0).unwrap_or(info.unit_cost);
+                    entry.cogs -= direction * qty * line_cost;
+                }
+            }
+        }
+    }
+
+    let mut rows: Vec<TurnoverRow> = totals
+        .into_iter()
+        .map(|(category, t)| {
+            let ending_value = (t.current_value - t.net_after_end).max(0.0);
+            let beginning_value = (ending_value - t.net_in_period).max(0.0);
+            let average_inventory_value = (beginning_value + ending_value) / 2.0;
+            let turnover = if average_inventory_value > 0.0 {
+                t.cogs / average_inventory_value
+            } else {
+                0.0
+            };
+            let days_of_inventory = if turnover > 0.0 {
+                Some(period_days as f64 / turnover)
+            } else {
+                None
+            };
+
+            TurnoverRow {
+                category,
+                cogs: t.cogs,
+                beginning_value,
+                ending_value,
+                average_inventory_value,
+                turnover,
+                days_of_inventory,
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| a.category.cmp(&b.category));
+
+    let assumptions = vec![
+        "Maliyet: satış satırındaki maliyet fiyatı, yoksa (veya 0 ise) ürünün güncel maliyet fiyatı, o da yoksa satış fiyatı kullanıldı.".to_string(),
+        "Dönem sonu stok değeri, güncel stok değerinden bitiş tarihinden sonraki hareketler geri alınarak yaklaşık hesaplandı.".to_string(),
+        "Dönem başı stok değeri, dönem sonu değerinden dönem içi hareketler (mal kabul, satış, iade) geri alınarak hesaplandı.".to_string(),
+        "Stok hareketleri ürünlerin güncel maliyetiyle değerlendi; geçmiş değerleme anlık görüntüsü bulunmuyor.".to_string(),
+        "İndirim satırları ve stokta olmayan hizmet kalemleri hesaba katılmadı.".to_string(),
+        "Elle yapılan stok düzeltmeleri hareket kaydı tutulmadığı için geri alınamadı.".to_string(),
+        format!("Stokta kalma süresi = dönem gün sayısı ({}) / devir hızı.", period_days),
+    ];
+
+    Ok(TurnoverReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        rows,
+        assumptions,
+    })
+}
+
+fn margin_percent(profit: f64, revenue: f64) -> f64 {
+    if revenue > 0.0 {
+        round_money(profit / revenue * 100.0)
+    } else {
+        0.0
+    }
+}
+
+/// Gross profit of the SALE transactions in the range, with returns subtracted, overall and per category.
+///
+/// Satış maliyeti: FIFO parti tüketimleri (`sale_lot_consumptions`); partiden karşılanmayan adetler
+/// satış satırındaki maliyet fiyatıyla, o yoksa ürünün güncel maliyet fiyatıyla hesaplanır.
+/// Maliyeti bilinmeyen satırlar 0 maliyetle sayılır ve `missing_cost_lines` içinde bildirilir.
+/// Ciro satır indirimleri düşülmüş tutardır; fiş indirimi ve indirim satırları (negatif fiyat)
+/// aynı işlemdeki kategorilere ciroları oranında dağıtılır.
+pub fn get_profit_report(conn: &Connection, start_date: &str, end_date: &str) -> Result<ProfitReport, AppError> {
+    let start = parse_report_date(start_date)?;
+    let end = parse_report_date(end_date)?;
+    if end < start {
+        return Err(AppError::Validation("Bitiş tarihi başlangıç tarihinden önce olamaz".to_string()));
+    }
+
+    // Category and cost price per SKU (deleted items included, they may have been sold in the range)
+    let items: HashMap<String, (String, Option<f64>)> = {
+        let mut stmt = conn.prepare("SELECT sku, category, cost_price FROM inventory_items")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, (row.get::<_, String>(1)?, row.get::<_, Option<f64>>(2)?)))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    // Lot-consumed quantity and cost per (transaction, SKU)
+    let mut consumed: HashMap<(String, String), (f64, f64)> = {
+        let mut stmt = conn.prepare(
+            "SELECT c.transaction_id, i.sku, SUM(c.quantity), SUM(c.quantity * c.unit_cost)
+             FROM sale_lot_consumptions c
+             JOIN inventory_items i ON i.id = c.product_id
+             JOIN transactions t ON t.id = c.transaction_id
+             WHERE substr(t.created_at, 1, 10) >= ?1 AND substr(t.created_at, 1, 10) <= ?2
+             GROUP BY c.transaction_id, i.sku",
+        )?;
+        let rows = stmt.query_map(params![start_date, end_date], |row| {
+            Ok(((row.get::<_, String>(0)?, row.get::<_, String>(1)?), (row.get::<_, f64>(2)?, row.get::<_, f64>(3)?)))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut categories: HashMap<String, CategoryProfit> = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT id, items, transaction_type, ticket_discount FROM transactions
+             WHERE transaction_type IN ('SALE', 'RETURN') AND status != 'voided'
+               AND substr(created_at, 1, 10) >= ?1 AND substr(created_at, 1, 10) <= ?2",
+        )?;
+        let rows = stmt.query_map(params![start_date, end_date], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, f64>(3)?))
+        })?;
+
+        for row in rows {
+            let (transaction_id, items_json, transaction_type, ticket_discount) = row?;
+            let cart_items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
+            let sign = if transaction_type == "RETURN" { -1.0 } else { 1.0 };
+
+            let mut line_revenue: HashMap<String, f64> = HashMap::new();
+            // Stored with the transaction's sign, like its total
+            let mut discount = ticket_discount.abs();
+            for line in &cart_items {
+                let qty = line.cart_quantity;
+                if line.price < 0.0 {
+                    discount += -line.price * qty;
+                    continue;
+                }
+
+                let (category, cost_price) = match items.get(&line.sku) {
+                    Some((category, cost_price)) => (category.clone(), *cost_price),
+                    None => (UNCATEGORIZED.to_string(), None),
+                };
+                let fallback_cost = line.cost_price.filter(|c| *c > 0.0).or(cost_price.filter(|c| *c > 0.0));
+
+                let mut cost = 0.0;
+                let mut uncosted = line.cart_quantity;
+                if transaction_type == "SALE" {
+                    if let Some((lot_qty, lot_cost)) = consumed.get_mut(&(transaction_id.clone(), line.sku.clone())) {
+                        let take = uncosted.min(*lot_qty);
+                        if take > 0.0 {
+                            let taken_cost = *lot_cost * take / *lot_qty;
+                            cost += taken_cost;
+                            *lot_cost -= taken_cost;
+                            *lot_qty -= take;
+                            uncosted -= take;
+                        }
+                    }
+                }
+
+                let entry = categories.entry(category.clone()).or_insert_with(|| CategoryProfit {
+                    category,
+                    ..Default::default()
+                });
+                if uncosted > 0.0 {
+                    match fallback_cost {
+                        Some(unit_cost) => cost += unit_cost * uncosted,
+                        None => entry.missing_cost_lines += 1,
+                    }
+                }
+                entry.cost += sign * cost;
+                *line_revenue.entry(entry.category.clone()).or_insert(0.0) += discounts::line_net_total(line);
+            }
+
+            let gross: f64 = line_revenue.values().sum();
+            for (category, revenue) in line_revenue {
+                let share = if gross > 0.0 { discount * revenue / gross } else { 0.0 };
+                if let Some(entry) = categories.get_mut(&category) {
+                    entry.revenue += sign * (revenue - share);
+                }
+            }
+        }
+    }
+
+    let mut categories: Vec<CategoryProfit> = categories
+        .into_values()
+        .map(|mut c| {
+            c.revenue = round_money(c.revenue);
+            c.cost = round_money(c.cost);
+            c.gross_profit = round_money(c.revenue - c.cost);
+            c.margin_percent = margin_percent(c.gross_profit, c.revenue);
+            c
+        })
+        .collect();
+    categories.sort_by(|a, b| a.category.cmp(&b.category));
+
+    let revenue = round_money(categories.iter().map(|c| c.revenue).sum());
+    let total_cost = round_money(categories.iter().map(|c| c.cost).sum());
+    let gross_profit = round_money(revenue - total_cost);
+
+    Ok(ProfitReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        revenue,
+        total_cost,
+        gross_profit,
+        margin_percent: margin_percent(gross_profit, revenue),
+        missing_cost_lines: categories.iter().map(|c| c.missing_cost_lines).sum(),
+        categories,
+    })
+}
+
+/// Shop timezone: `timezone_offset_minutes` from settings, or the system's local offset
+pub fn get_timezone_offset(conn: &Connection) -> Result<FixedOffset, AppError> {
+    let value: Option<Option<String>> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![TIMEZONE_OFFSET_SETTING],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let configured = value
+        .flatten()
+        .and_then(|v| v.trim().parse::<i32>().ok())
+        .and_then(|minutes| FixedOffset::east_opt(minutes * 60));
+
+    Ok(configured.unwrap_or_else(|| Local::now().offset().fix()))
+}
+
+/// Local date, weekday (0 = Pazartesi) and hour of an RFC3339 timestamp
+fn local_slot(created_at: &str, tz: &FixedOffset) -> Option<(NaiveDate, usize, usize)> {
+    let local = DateTime::parse_from_rfc3339(created_at).ok()?.with_timezone(tz);
+    Some((
+        local.date_naive(),
+        local.weekday().num_days_from_monday() as usize,
+        local.hour() as usize,
+    ))
+}
+
+/// Sales count and revenue per weekday × hour in shop time.
+///
+/// Always returns 7 × 24 = 168 cells (weekday 0 = Pazartesi … 6 = Pazar), zero-filled,
+/// ordered by weekday then hour. Dates are inclusive and interpreted in shop time.
+pub fn get_sales_heatmap(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<HeatCell>, AppError> {
+    let start = parse_report_date(start_date)?;
+    let end = parse_report_date(end_date)?;
+    if end < start {
+        return Err(AppError::Validation("Bitiş tarihi başlangıç tarihinden önce olamaz".to_string()));
+    }
+
+    let tz = get_timezone_offset(conn)?;
+
+    let mut cells: Vec<HeatCell> = (0..7)
+        .flat_map(|weekday| {
+            (0..24).map(move |hour| HeatCell {
+                weekday,
+                hour,
+                count: 0,
+                revenue: 0.0,
+            })
+        })
+        .collect();
+
+    // UTC timestamps can fall one day either side of the local date
+    let mut stmt = conn.prepare(
+        "SELECT created_at, total FROM transactions
+         WHERE transaction_type = 'SALE' AND status != 'voided' AND created_at >= ?1 AND created_at < ?2",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            (start - Duration::days(1)).to_string(),
+            (end + Duration::days(2)).to_string()
+        ],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)),
+    )?;
+
+    for row in rows {
+        let (created_at, total) = row?;
+        let Some((date, weekday, hour)) = local_slot(&created_at, &tz) else {
+            continue;
+        };
+        if date < start || date > end {
+            continue;
+        }
+        let cell = &mut cells[weekday * 24 + hour];
+        cell.count += 1;
+        cell.revenue += total;
+    }
+
+    Ok(cells)
+}
+
+/// Sales count and total per hour of day (0-23, shop time), for staffing.
+///
+/// Always returns 24 buckets; the heatmap folded over weekdays.
+pub fn get_sales_by_hour(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<HourlySales>, AppError> {
+    let mut hours: Vec<HourlySales> = (0..24)
+        .map(|hour| HourlySales {
+            hour,
+            count: 0,
+            total: 0.0,
+        })
+        .collect();
+    for cell in get_sales_heatmap(conn, start_date, end_date)? {
+        let bucket = &mut hours[cell.hour as usize];
+        bucket.count += cell.count;
+        bucket.total += cell.revenue;
+    }
+    for bucket in &mut hours {
+        bucket.total = round_money(bucket.total);
+    }
+    Ok(hours)
+}
+
+/// Days of stock left, stock-out date and reorder-by date for a daily sales rate
+fn project_stock(
+    on_hand: f64,
+    avg_daily_units: f64,
+    today: NaiveDate,
+    lead_time_days: i64,
+) -> (Option<f64>, Option<NaiveDate>, Option<NaiveDate>) {
+    if avg_daily_units <= 0.0 {
+        return (None, None, None);
+    }
+    let days_remaining = on_hand.max(0.0) / avg_daily_units;
+    let stockout_date = today + Duration::days(days_remaining.floor() as i64);
+    let reorder_by = stockout_date - Duration::days(lead_time_days);
+    (Some(days_remaining), Some(stockout_date), Some(reorder_by))
+}
+
+/// Sales velocity of one item over the last `window_days` days (bugün dahil, mağaza saatiyle).
+///
+/// Satılan adet = satışlar - iadeler. Mağaza kaydı pencereden yeniyse pencere kısaltılır
+/// ve `insufficient_history` işaretlenir.
+pub fn get_item_velocity(
+    conn: &Connection,
+    sku: &str,
+    window_days: i64,
+    lead_time_days: Option<i64>,
+) -> Result<ItemVelocity, AppError> {
+    if !(1..=3650).contains(&window_days) {
+        return Err(AppError::Validation(format!("Geçersiz gün sayısı: {} (1-3650 arası olmalı)", window_days)));
+    }
+    let lead_time_days = lead_time_days.unwrap_or(DEFAULT_LEAD_TIME_DAYS).max(0);
+
+    let (name, on_hand): (String, f64) = conn
+        .query_row(
+            "SELECT name, quantity FROM inventory_items WHERE sku = ?1",
+            params![sku],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Ürün bulunamadı: {}", sku)))?;
+
+    let tz = get_timezone_offset(conn)?;
+    let today = chrono::Utc::now().with_timezone(&tz).date_naive();
+    let window_start = today - Duration::days(window_days - 1);
+
+    // Clip the window to when the shop started recording transactions
+    let first_transaction: Option<String> =
+        conn.query_row("SELECT MIN(created_at) FROM transactions", [], |row| row.get(0))?;
+    let history_start = first_transaction
+        .and_then(|ts| local_slot(&ts, &tz))
+        .map(|(date, _, _)| date);
+    let effective_start = match history_start {
+        Some(date) if date > window_start => date.min(today),
+        _ => window_start,
+    };
+    let effective_days = (today - effective_start).num_days() + 1;
+
+    let mut units_sold: f64 = 0.0;
+    let mut sale_dates = std::collections::HashSet::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT items, transaction_type, created_at FROM transactions
+             WHERE transaction_type IN ('SALE', 'RETURN') AND status != 'voided' AND created_at >= ?1",
+        )?;
+        let rows = stmt.query_map(params![(window_start - Duration::days(1)).to_string()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (items_json, transaction_type, created_at) = row?;
+            let Some((date, _, _)) = local_slot(&created_at, &tz) else {
+                continue;
+            };
+            if date < window_start || date > today {
+                continue;
+            }
+            let cart_items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
+            let qty: f64 = cart_items
+                .iter()
+                .filter(|line| line.sku == sku)
+                .map(|line| line.cart_quantity)
+                .sum();
+            if qty == 0.0 {
+                continue;
+            }
+            if transaction_type == "RETURN" {
+                units_sold -= qty;
+            } else {
+                units_sold += qty;
+                sale_dates.insert(date);
+            }
+        }
+    }
+
+    let avg_daily_units = units_sold.max(0.0) / effective_days as f64;
+    let (days_of_stock_remaining, stockout_date, reorder_by) =
+        project_stock(on_hand, avg_daily_units, today, lead_time_days);
+    let sale_days = sale_dates.len() as i64;
+    let insufficient_history = effective_start > window_start;
+    let too_few_sales = sale_days < MIN_SALE_DAYS;
+
+    Ok(ItemVelocity {
+        sku: sku.to_string(),
+        name,
+        window_days,
+        effective_days,
+        units_sold,
+        sale_days,
+        avg_daily_units,
+        on_hand,
+        days_of_stock_remaining,
+        stockout_date: stockout_date.map(|d| d.to_string()),
+        lead_time_days,
+        reorder_by: reorder_by.map(|d| d.to_string()),
+        reorder_now: reorder_by.is_some_and(|d| d <= today),
+        insufficient_history,
+        too_few_sales,
+        insufficient_data: insufficient_history || too_few_sales,
+    })
+}
+
+/// Sipariş önerisinin kaç günlük satışı karşılaması gerektiği (`reorder_cover_days`)
+fn get_reorder_cover_days(conn: &Connection) -> Result<i64, AppError> {
+    Ok(settings::get_setting(conn, REORDER_COVER_DAYS_SETTING)?
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|days| (1..=365).contains(days))
+        .unwrap_or(DEFAULT_REORDER_COVER_DAYS))
+}
+
+/// Net units sold (sales minus returns) per SKU over the last `window_days` days in shop time
+fn units_sold_by_sku(conn: &Connection, window_days: i64) -> Result<HashMap<String, f64>, AppError> {
+    let tz = get_timezone_offset(conn)?;
+    let today = chrono::Utc::now().with_timezone(&tz).date_naive();
+    let window_start = today - Duration::days(window_days - 1);
+
+    let mut stmt = conn.prepare(
+        "SELECT items, transaction_type, created_at FROM transactions
+         WHERE transaction_type IN ('SALE', 'RETURN') AND status != 'voided' AND created_at >= ?1",
+    )?;
+    let rows = stmt.query_map(params![(window_start - Duration::days(1)).to_string()], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+
+    let mut units: HashMap<String, f64> = HashMap::new();
+    for row in rows {
+        let (items_json, transaction_type, created_at) = row?;
+        let Some((date, _, _)) = local_slot(&created_at, &tz) else {
+            continue;
+        };
+        if date < window_start || date > today {
+            continue;
+        }
+        let sign = if transaction_type == "RETURN" { -1.0 } else { 1.0 };
+        let cart_items: Vec<CartItem> = serde_json::from_str(&items_json).unwrap_or_default();
+        for line in cart_items {
+            *units.entry(line.sku).or_insert(0.0) += sign * line.cart_quantity;
+        }
+    }
+    Ok(units)
+}
+
+/// Quantity that brings `quantity` up to `cover_days` of sales, and at least up to the minimum level
+fn suggest_reorder_quantity(quantity: f64, min_stock_level: i64, avg_daily_units: f64, cover_days: i64) -> f64 {
+    let target = (avg_daily_units * cover_days as f64).ceil().max(min_stock_level as f64);
+    (target - quantity.max(0.0)).max(0.0).ceil()
+}
+
+/// Sipariş önerileri: minimum stok seviyesinin altındaki ürünler, tedarikçiye göre gruplu.
+///
+/// Önerilen adet son 30 günün satış hızıyla `reorder_cover_days` gün (varsayılan 30) yetecek
+/// stoğu, en az da minimum stok seviyesini tamamlar. Tedarikçiler ada göre sıralanır;
+/// tedarikçisi olmayan ürünler en sondaki "Atanmamış" grubundadır.
+pub fn get_reorder_report(conn: &Connection) -> Result<Vec<ReorderSuggestion>, AppError> {
+    let cover_days = get_reorder_cover_days(conn)?;
+    let units_sold = units_sold_by_sku(conn, REORDER_WINDOW_DAYS)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT i.sku, i.name, i.quantity, i.min_stock_level, i.supplier_id, a.name
+         FROM inventory_items i
+         LEFT JOIN current_accounts a ON a.id = i.supplier_id
+         WHERE i.quantity < i.min_stock_level AND i.deleted_at IS NULL
+         ORDER BY i.name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, f64>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+        ))
+    })?;
+
+    let mut groups: Vec<ReorderSuggestion> = Vec::new();
+    for row in rows {
+        let (sku, name, quantity, min_stock_level, supplier_id, supplier_name) = row?;
+        let supplier_id = supplier_id.filter(|id| !id.trim().is_empty());
+        let units_sold_30d = units_sold.get(&sku).copied().unwrap_or(0.0).max(0.0);
+        let avg_daily_units = units_sold_30d / REORDER_WINDOW_DAYS as f64;
+        let line = ReorderLine {
+            suggested_quantity: suggest_reorder_quantity(quantity, min_stock_level, avg_daily_units, cover_days),
+            sku,
+            name,
+            quantity,
+            min_stock_level,
+            units_sold_30d,
+            avg_daily_units,
+        };
+
+        match groups.iter_mut().find(|g| g.supplier_id == supplier_id) {
+            Some(group) => group.items.push(line),
+            None => groups.push(ReorderSuggestion {
+                supplier_name: match &supplier_id {
+                    Some(id) => supplier_name.unwrap_or_else(|| id.clone()),
+                    None => UNASSIGNED_SUPPLIER.to_string(),
+                },
+                supplier_id,
+                items: vec![line],
+            }),
+        }
+    }
+
+    groups.sort_by(|a, b| {
+        a.supplier_id
+            .is_none()
+            .cmp(&b.supplier_id.is_none())
+            .then_with(|| a.supplier_name.cmp(&b.supplier_name))
+    });
+    Ok(groups)
+}
+
+/// Most-sold products of the SALE transactions in a date range (inclusive, `created_at` date part).
+///
+/// Ranked by quantity, then revenue. The name is the item's current one; items deleted since fall
+/// back to the name in the sale. Revenue is after line discounts; discount lines (negative price)
+/// are left out.
+pub fn get_top_products(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    limit: i64,
+) -> Result<Vec<ProductSales>, AppError> {
+    let start = parse_report_date(start_date)?;
+    let end = parse_report_date(end_date)?;
+    if end < start {
+        return Err(AppError::Validation("Bitiş tarihi başlangıç tarihinden önce olamaz".to_string()));
+    }
+
+    let mut products: HashMap<String, ProductSales> = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT items FROM transactions
+             WHERE transaction_type = 'SALE' AND status != 'voided'
+               AND substr(created_at, 1, 10) >= ?1 AND substr(created_at, 1, 10) <= ?2",
+        )?;
+        let rows = stmt.query_map(params![start.to_string(), end.to_string()], |row| row.get::<_, String>(0))?;
+        for items_json in rows {
+            let cart_items: Vec<CartItem> = serde_json::from_str(&items_json?).unwrap_or_default();
+            for line in cart_items.into_iter().filter(|l| l.price >= 0.0) {
+                let entry = products.entry(line.sku.clone()).or_insert_with(|| ProductSales {
+                    sku: line.sku.clone(),
+                    name: line.name.clone(),
+                    quantity_sold: 0.0,
+                    revenue: 0.0,
+                });
+                entry.quantity_sold += line.cart_quantity;
+                entry.revenue += discounts::line_net_total(&line);
+            }
+        }
+    }
+
+    let mut name_stmt = conn.prepare("SELECT name FROM inventory_items WHERE sku = ?1")?;
+    let mut ranked: Vec<ProductSales> = products.into_values().collect();
+    for product in &mut ranked {
+        if let Some(name) = name_stmt.query_row(params![&product.sku], |row| row.get(0)).optional()? {
+            product.name = name;
+        }
+        product.revenue = round_money(product.revenue);
+    }
+    ranked.sort_by(|a, b| {
+        b.quantity_sold
+            .total_cmp(&a.quantity_sold)
+            .then_with(|| b.revenue.total_cmp(&a.revenue))
+            .then_with(|| a.sku.cmp(&b.sku))
+    });
+    ranked.truncate(limit.clamp(1, 1000) as usize);
+    Ok(ranked)
+}
+
+/// Map the payment method spellings stored over time onto one bucket:
+/// NAKIT, KREDI_KARTI, HAVALE (havale/EFT/banka kartı), VERESIYE or DIGER
+pub fn canonical_payment_method(method: &str) -> &'static str {
+    match PaymentMethod::parse(method) {
+        PaymentMethod::Cash => "NAKIT",
+        PaymentMethod::Card => "KREDI_KARTI",
+        PaymentMethod::Bank => "HAVALE",
+        PaymentMethod::Credit => "VERESIYE",
+        PaymentMethod::Other => "DIGER",
+    }
+}
+
+/// One row per day (boş günler dahil) with sales, returns, expenses and net sales per payment method.
+///
+/// Günler `created_at` tarih kısmına göre gruplanır (get_sales_by_date_range ile aynı).
+/// Giderler = EXPENSE işlemleri + finans kayıtlarındaki giderler (get_finance_summary ile aynı).
+pub fn get_daily_summary(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<DailySummaryRow>, AppError> {
+    let start = parse_report_date(start_date)?;
+    let end = parse_report_date(end_date)?;
+    if end < start {
+        return Err(AppError::Validation("Bitiş tarihi başlangıç tarihinden önce olamaz".to_string()));
+    }
+    if (end - start).num_days() > 3660 {
+        return Err(AppError::Validation("Tarih aralığı en fazla 10 yıl olabilir".to_string()));
+    }
+
+    let mut rows: Vec<DailySummaryRow> = start
+        .iter_days()
+        .take_while(|d| *d <= end)
+        .map(|d| DailySummaryRow {
+            date: d.to_string(),
+            ..Default::default()
+        })
+        .collect();
+    let index = |date: &str| -> Option<usize> {
+        let d = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+        Some((d - start).num_days() as usize)
+    };
+
+    {
+        let mut stmt = conn.prepare(
+            "SELECT substr(created_at, 1, 10), transaction_type, payment_method, ABS(total)
+             FROM transactions
+             WHERE transaction_type IN ('SALE', 'RETURN', 'EXPENSE') AND status != 'voided'
+               AND substr(created_at, 1, 10) >= ?1 AND substr(created_at, 1, 10) <= ?2",
+        )?;
+        let tx_rows = stmt.query_map(params![start_date, end_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, f64>(3)?,
+            ))
+        })?;
+        for tx_row in tx_rows {
+            let (date, transaction_type, payment_method, amount) = tx_row?;
+            let Some(row) = index(&date).and_then(|i| rows.get_mut(i)) else {
+                continue;
+            };
+            let signed = match transaction_type.as_str() {
+                "SALE" => {
+                    row.sale_count += 1;
+                    row.gross_sales += amount;
+                    amount
+                }
+                "RETURN" => {
+                    row.returns += amount;
+                    -amount
+                }
+                _ => {
+                    row.expenses += amount;
+                    continue;
+                }
+            };
+            match canonical_payment_method(payment_method.as_deref().unwrap_or("")) {
+                "NAKIT" => row.cash += signed,
+                "KREDI_KARTI" => row.card += signed,
+                "HAVALE" => row.bank += signed,
+                "VERESIYE" => row.credit += signed,
+                _ => row.other += signed,
+            }
+        }
+    }
+
+    {
+        let mut stmt = conn.prepare(
+            "SELECT date, COALESCE(SUM(amount), 0) FROM finance_records
+             WHERE record_type = 'EXPENSE' AND date >= ?1 AND date <= ?2
+             GROUP BY date",
+        )?;
+        let expense_rows = stmt.query_map(params![start_date, end_date], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })?;
+        for expense_row in expense_rows {
+            let (date, amount) = expense_row?;
+            if let Some(row) = index(&date).and_then(|i| rows.get_mut(i)) {
+                row.expenses += amount;
+            }
+        }
+    }
+
+    for row in &mut rows {
+        row.net_sales = row.gross_sales - row.returns;
+    }
+
+    Ok(rows)
+}
+
+/// Daily summary as CSV (UTF-8 BOM, `;` ayraç, Türkçe başlıklar)
+pub fn daily_summary_to_csv(rows: &[DailySummaryRow]) -> String {
+    let mut csv = String::from("\u{FEFF}"); // UTF-8 BOM for Excel
+    csv.push_str("Tarih;Satış Adedi;Brüt Satış;İadeler;Net Satış;Giderler;Nakit;Kredi Kartı;Havale/EFT;Veresiye;Diğer\n");
+
+    for row in rows {
+        csv.push_str(&format!(
+            "{};{};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2}\n",
+            row.date,
+            row.sale_count,
+            row.gross_sales,
+            row.returns,
+            row.net_sales,
+            row.expenses,
+            row.cash,
+            row.card,
+            row.bank,
+            row.credit,
+            row.other
+        ));
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory database with the real schema
+    fn report_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    /// (id, sku, category, quantity, price, cost_price); the name is the SKU
+    type ItemRow<'a> = (&'a str, &'a str, &'a str, f64, f64, Option<f64>);
+
+    fn insert_items(conn: &Connection, items: &[ItemRow]) {
+        for (id, sku, category, quantity, price, cost_price) in items {
+            conn.execute(
+                "INSERT INTO inventory_items (id, sku, name, category, quantity, price, cost_price, last_updated)
+                 VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6, '2024-01-01T00:00:00Z')",
+                params![id, sku, category, quantity, price, cost_price],
+            )
+            .unwrap();
+        }
+    }
+
+    fn sale_line(sku: &str, name: &str, qty: f64, price: f64, cost: Option<f64>) -> CartItem {
+        CartItem {
+            id: sku.to_string(),
+            sku: sku.to_string(),
+            name: name.to_string(),
+            cart_quantity: qty,
+            price,
+            cost_price: cost,
+            tax_rate: 20.0,
+            discount_amount: None,
+            discount_percent: None,
+        }
+    }
+
+    /// Transactions as (id, lines, type, status, created_at); the total is the sum of the lines
+    fn insert_transactions<S: AsRef<str>>(conn: &Connection, transactions: &[(&str, Vec<CartItem>, &str, &str, S)]) {
+        for (id, items, transaction_type, status, created_at) in transactions {
+            let total: f64 = items.iter().map(|i| i.price * i.cart_quantity).sum();
+            conn.execute(
+                "INSERT INTO transactions (id, items, total, transaction_type, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id, serde_json::to_string(items).unwrap(), total, transaction_type, status, created_at.as_ref()],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_local_slot_applies_offset() {
+        let tz = FixedOffset::east_opt(180 * 60).unwrap();
+        // 2024-03-03 is a Sunday; 22:30 UTC is Monday 01:30 in UTC+3
+        let (date, weekday, hour) = local_slot("2024-03-03T22:30:00+00:00", &tz).unwrap();
+        assert_eq!(date, NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(weekday, 0);
+        assert_eq!(hour, 1);
+
+        assert!(local_slot("not a date", &tz).is_none());
+    }
+
+    #[test]
+    fn test_sales_by_hour_uses_shop_offset() {
+        let conn = report_db();
+        settings::set_setting(&conn, "timezone_offset_minutes", "180").unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions (id, items, total, transaction_type, status, created_at) VALUES
+                 ('t1', '[]', 100, 'SALE', 'completed', '2024-03-10T06:15:00+00:00'),
+                 ('t2', '[]', 50.5, 'SALE', 'completed', '2024-03-10T06:45:00Z'),
+                 ('t3', '[]', 20, 'SALE', 'completed', '2024-03-10T14:00:00Z'),
+                 -- 22:30 UTC on the 9th is 01:30 local on the 10th
+                 ('t4', '[]', 30, 'SALE', 'completed', '2024-03-09T22:30:00Z'),
+                 -- 21:30 UTC on the 10th is the 11th locally: out of range
+                 ('t5', '[]', 40, 'SALE', 'completed', '2024-03-10T21:30:00Z'),
+                 ('t6', '[]', 70, 'SALE', 'voided', '2024-03-10T06:30:00Z'),
+                 ('t7', '[]', -10, 'RETURN', 'completed', '2024-03-10T06:30:00Z');",
+        )
+        .unwrap();
+
+        let hours = get_sales_by_hour(&conn, "2024-03-10", "2024-03-10").unwrap();
+        assert_eq!(hours.len(), 24);
+        assert!(hours.iter().enumerate().all(|(i, h)| h.hour as usize == i));
+        assert_eq!((hours[9].count, hours[9].total), (2, 150.5));
+        assert_eq!((hours[17].count, hours[17].total), (1, 20.0));
+        assert_eq!((hours[1].count, hours[1].total), (1, 30.0));
+        assert_eq!(hours.iter().map(|h| h.count).sum::<i64>(), 4);
+        assert_eq!(hours[0].count + hours[6].count, 0);
+    }
+
+    #[test]
+    fn test_project_stock() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let (days, stockout, reorder_by) = project_stock(20.0, 2.0, today, 7);
+        assert_eq!(days, Some(10.0));
+        assert_eq!(stockout, NaiveDate::from_ymd_opt(2024, 3, 11));
+        assert_eq!(reorder_by, NaiveDate::from_ymd_opt(2024, 3, 4));
+
+        assert_eq!(project_stock(20.0, 0.0, today, 7), (None, None, None));
+    }
+
+    #[test]
+    fn test_turnover_report_per_category() {
+        let conn = report_db();
+        // A has a cost price of 0: valued at its sale price (20) like a missing cost
+        insert_items(
+            &conn,
+            &[
+                ("a", "A", "Boya", 10.0, 20.0, Some(0.0)),
+                ("b", "B", "Hirdavat", 4.0, 9.0, Some(5.0)),
+                ("c", "C", "Gida", 3.0, 4.0, Some(2.0)),
+            ],
+        );
+        conn.execute_batch(
+            "INSERT INTO inventory_lots (id, product_id, quantity, initial_quantity, buy_price, receipt_date, created_at) VALUES
+                 ('l1', 'a', 6, 6, 0, '2024-03-05', '2024-03-05T09:00:00Z'),
+                 ('l2', 'b', 2, 2, 5, '2024-03-15', '2024-03-15T09:00:00Z');",
+        )
+        .unwrap();
+        let line = |sku: &str, qty: f64, price: f64, cost: Option<f64>| sale_line(sku, sku, qty, price, cost);
+        insert_transactions(
+            &conn,
+            &[
+                // A line cost of 0 is missing too: the item's cost (20) is used
+                ("t1", vec![line("A", 4.0, 30.0, Some(0.0)), line("IND", 1.0, -5.0, None)], "SALE", "completed", "2024-03-03T10:00:00Z"),
+                ("t2", vec![line("A", 1.0, 30.0, None)], "RETURN", "completed", "2024-03-04T10:00:00Z"),
+                ("t3", vec![line("B", 2.0, 9.0, Some(4.0))], "SALE", "completed", "2024-03-06T10:00:00Z"),
+                ("t4", vec![line("B", 5.0, 9.0, Some(4.0))], "SALE", "voided", "2024-03-06T11:00:00Z"),
+                ("t5", vec![line("B", 1.0, 9.0, Some(4.0))], "SALE", "completed", "2024-03-12T10:00:00Z"),
+            ],
+        );
+
+        let report = get_turnover_report(&conn, "2024-03-01", "2024-03-10").unwrap();
+        let row = |category: &str| report.rows.iter().find(|r| r.category == category).unwrap();
+        assert_eq!(report.rows.iter().map(|r| r.category.as_str()).collect::<Vec<_>>(), ["Boya", "Gida", "Hirdavat"]);
+
+        // Boya: COGS 4×20 − 1×20; ends at 10×20, began before the lot (+120), sale (−80) and return (+20)
+        let paint = row("Boya");
+        assert_eq!((paint.cogs, paint.ending_value, paint.beginning_value), (60.0, 200.0, 140.0));
+        assert_eq!(paint.average_inventory_value, 170.0);
+        assert!((paint.turnover - 60.0 / 170.0).abs() < 1e-9);
+        assert!((paint.days_of_inventory.unwrap() - 10.0 * 170.0 / 60.0).abs() < 1e-9);
+
+        // Hirdavat: line cost 4 for COGS; the lot (+10) and sale (−5) after the end are rolled back
+        let hardware = row("Hirdavat");
+        assert_eq!((hardware.cogs, hardware.ending_value, hardware.beginning_value), (8.0, 15.0, 25.0));
+        assert_eq!((hardware.average_inventory_value, hardware.turnover), (20.0, 0.4));
+        assert_eq!(hardware.days_of_inventory, Some(25.0));
+
+        // Nothing sold: no turnover and no days of inventory
+        let food = row("Gida");
+        assert_eq!((food.cogs, food.average_inventory_value, food.turnover, food.days_of_inventory), (0.0, 6.0, 0.0, None));
+
+        assert!(get_turnover_report(&conn, "2024-03-10", "2024-03-01").is_err());
+    }
+
+    #[test]
+    fn test_profit_report_costs_from_lots_and_flags_missing() {
+        let conn = report_db();
+        insert_items(
+            &conn,
+            &[
+                ("a", "A", "Gida", 0.0, 10.0, Some(6.0)),
+                ("b", "B", "Gida", 0.0, 20.0, None),
+                ("c", "C", "Temizlik", 0.0, 15.0, Some(10.0)),
+            ],
+        );
+        conn.execute_batch(
+            "INSERT INTO sale_lot_consumptions (transaction_id, lot_id, product_id, quantity, unit_cost, created_at)
+                 VALUES ('t1', 'l1', 'a', 2, 5, '2024-03-01T10:00:00Z');",
+        )
+        .unwrap();
+        let line = |sku: &str, qty: f64, price: f64, cost: Option<f64>| sale_line(sku, sku, qty, price, cost);
+        insert_transactions(
+            &conn,
+            &[
+                // A: 2 units from the lot at 5, the third at the item cost 6. B has no cost. Discount 5.
+                ("t1", vec![line("A", 3.0, 10.0, None), line("B", 1.0, 20.0, None), line("IND", 1.0, -5.0, None)], "SALE", "completed", "2024-03-01T10:00:00Z"),
+                // C: line cost (8) wins over the current item cost
+                ("t2", vec![line("C", 3.0, 15.0, Some(8.0))], "SALE", "completed", "2024-03-02T10:00:00Z"),
+                ("t3", vec![line("C", 1.0, 15.0, Some(8.0))], "RETURN", "completed", "2024-03-03T10:00:00Z"),
+                ("t4", vec![line("C", 5.0, 15.0, Some(8.0))], "SALE", "voided", "2024-03-03T11:00:00Z"),
+                ("t5", vec![line("C", 5.0, 15.0, Some(8.0))], "SALE", "completed", "2024-04-01T10:00:00Z"),
+            ],
+        );
+
+        let report = get_profit_report(&conn, "2024-03-01", "2024-03-31").unwrap();
+        assert_eq!(report.categories.len(), 2);
+        let gida = &report.categories[0];
+        assert_eq!((gida.category.as_str(), gida.revenue, gida.cost, gida.gross_profit), ("Gida", 45.0, 16.0, 29.0));
+        assert_eq!(gida.missing_cost_lines, 1);
+        let temizlik = &report.categories[1];
+        assert_eq!((temizlik.revenue, temizlik.cost, temizlik.gross_profit), (30.0, 16.0, 14.0));
+        assert_eq!(temizlik.missing_cost_lines, 0);
+
+        assert_eq!((report.revenue, report.total_cost, report.gross_profit), (75.0, 32.0, 43.0));
+        assert_eq!(report.margin_percent, 57.33);
+        assert_eq!(report.missing_cost_lines, 1);
+
+        assert!(get_profit_report(&conn, "2024-03-31", "2024-03-01").is_err());
+    }
+
+    #[test]
+    fn test_reorder_report_fast_slow_and_unsold_items() {
+        let conn = report_db();
+        settings::set_setting(&conn, "timezone_offset_minutes", "0").unwrap();
+        conn.execute_batch(
+            "INSERT INTO current_accounts (id, name, account_type, created_at, updated_at)
+                 VALUES ('s1', 'Boya Tedarik', 'SUPPLIER', '2024-01-01', '2024-01-01');
+             INSERT INTO inventory_items (id, sku, name, quantity, min_stock_level, supplier_id, deleted_at, last_updated) VALUES
+                 ('1', 'FAST', 'Hizli', 5, 10, 's1', NULL, '2024-01-01'),
+                 ('2', 'SLOW', 'Yavas', 2, 5, 's1', NULL, '2024-01-01'),
+                 ('3', 'ZERO', 'Satmayan', 0, 4, NULL, NULL, '2024-01-01'),
+                 ('4', 'OK', 'Yeterli', 50, 10, 's1', NULL, '2024-01-01'),
+                 ('5', 'GONE', 'Silinmis', 0, 10, NULL, '2024-01-01', '2024-01-01');",
+        )
+        .unwrap();
+        let line = |sku: &str, qty: f64| sale_line(sku, sku, qty, 10.0, None);
+        let days_ago = |days: i64| (chrono::Utc::now() - Duration::days(days)).to_rfc3339();
+        insert_transactions(
+            &conn,
+            &[
+                ("t1", vec![line("FAST", 40.0), line("SLOW", 3.0)], "SALE", "completed", days_ago(2)),
+                ("t2", vec![line("FAST", 25.0)], "SALE", "completed", days_ago(10)),
+                ("t3", vec![line("FAST", 5.0)], "RETURN", "completed", days_ago(1)),
+                ("t4", vec![line("FAST", 100.0)], "SALE", "voided", days_ago(3)),
+                ("t5", vec![line("ZERO", 100.0)], "SALE", "completed", days_ago(45)),
+            ],
+        );
+
+        let report = get_reorder_report(&conn).unwrap();
+        assert_eq!(report.len(), 2);
+        assert_eq!((report[0].supplier_id.as_deref(), report[0].supplier_name.as_str()), (Some("s1"), "Boya Tedarik"));
+        assert_eq!((report[1].supplier_id.as_deref(), report[1].supplier_name.as_str()), (None, UNASSIGNED_SUPPLIER));
+
+        // Fast mover: 60 net units in 30 days = 2/day, 30 days of cover = 60, 5 on hand
+        let fast = &report[0].items[0];
+        assert_eq!((fast.sku.as_str(), fast.quantity, fast.min_stock_level), ("FAST", 5.0, 10));
+        assert_eq!((fast.units_sold_30d, fast.avg_daily_units, fast.suggested_quantity), (60.0, 2.0, 55.0));
+
+        // Slow mover: 3 units cover less than the minimum level, so order up to the minimum
+        let slow = &report[0].items[1];
+        assert_eq!((slow.sku.as_str(), slow.units_sold_30d, slow.suggested_quantity), ("SLOW", 3.0, 3.0));
+
+        // No sales in the window: still refill to the minimum level
+        assert_eq!(report[1].items.len(), 1);
+        let zero = &report[1].items[0];
+        assert_eq!((zero.sku.as_str(), zero.units_sold_30d, zero.suggested_quantity), ("ZERO", 0.0, 4.0));
+
+        // A shorter cover target shrinks the fast mover's order
+        settings::set_setting(&conn, REORDER_COVER_DAYS_SETTING, "10").unwrap();
+        let report = get_reorder_report(&conn).unwrap();
+        assert_eq!(report[0].items[0].suggested_quantity, 15.0);
+    }
+
+    #[test]
+    fn test_canonical_payment_method() {
+        assert_eq!(canonical_payment_method("Nakit"), "NAKIT");
+        assert_eq!(canonical_payment_method("CASH"), "NAKIT");
+        assert_eq!(canonical_payment_method("Kredi Kartı"), "KREDI_KARTI");
+        assert_eq!(canonical_payment_method("MAIL ORDER"), "KREDI_KARTI");
+        assert_eq!(canonical_payment_method("Banka Kartı"), "HAVALE");
+        assert_eq!(canonical_payment_method("VERESIYE"), "VERESIYE");
+        assert_eq!(canonical_payment_method("ÇEK"), "DIGER");
+    }
+
+    #[test]
+    fn test_top_products_ranked_by_quantity_then_revenue() {
+        let conn = report_db();
+        conn.execute_batch(
+            "INSERT INTO inventory_items (id, sku, name, last_updated) VALUES
+                 ('v', 'VIDA', 'Vida 4x40 (yeni ad)', '2024-01-01'), ('s', 'SOMUN', 'Somun M8', '2024-01-01');",
+        )
+        .unwrap();
+        let line = |sku: &str, name: &str, qty: f64, price: f64| sale_line(sku, name, qty, price, None);
+        insert_transactions(
+            &conn,
+            &[
+                ("t1", vec![line("VIDA", "Vida", 10.0, 2.0), line("SOMUN", "Somun", 4.0, 3.0)], "SALE", "completed", "2024-03-01T10:00:00Z"),
+                ("t2", vec![line("SOMUN", "Somun", 6.0, 3.0), line("IND", "Indirim", 1.0, -5.0)], "SALE", "completed", "2024-03-05T10:00:00Z"),
+                ("t3", vec![line("VIDA", "Vida", 5.0, 2.0), line("PUL", "Pul (silindi)", 10.0, 1.0)], "SALE", "completed", "2024-03-31T23:00:00Z"),
+                // Left out: voided, returns and sales outside the range
+                ("t4", vec![line("SOMUN", "Somun", 50.0, 3.0)], "SALE", "voided", "2024-03-10T10:00:00Z"),
+                ("t5", vec![line("VIDA", "Vida", 3.0, 2.0)], "RETURN", "completed", "2024-03-10T10:00:00Z"),
+                ("t6", vec![line("SOMUN", "Somun", 50.0, 3.0)], "SALE", "completed", "2024-04-01T00:00:00Z"),
+            ],
+        );
+
+        let top = get_top_products(&conn, "2024-03-01", "2024-03-31", 10).unwrap();
+        let summary: Vec<(&str, &str, f64, f64)> =
+            top.iter().map(|p| (p.sku.as_str(), p.name.as_str(), p.quantity_sold, p.revenue)).collect();
+        assert_eq!(
+            summary,
+            [
+                ("VIDA", "Vida 4x40 (yeni ad)", 15.0, 30.0),
+                // Same quantity as PUL, more revenue
+                ("SOMUN", "Somun M8", 10.0, 30.0),
+                ("PUL", "Pul (silindi)", 10.0, 10.0),
+            ]
+        );
+
+        assert_eq!(get_top_products(&conn, "2024-03-01", "2024-03-31", 1).unwrap().len(), 1);
+        assert!(get_top_products(&conn, "2024-03-31", "2024-03-01", 10).is_err());
+    }
+}
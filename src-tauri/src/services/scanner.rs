@@ -2,17 +2,26 @@
 //! 
 //! USB ve Bluetooth barkod tarayıcı desteği
 
+use chrono::{Datelike, NaiveDate};
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use crate::error::AppError;
+use crate::models::InventoryItem;
+use crate::services::{inventory, settings};
+
 static SCANNER_ACTIVE: AtomicBool = AtomicBool::new(false);
 
+/// Settings key of the JSON encoded `ScannerConfig`
+pub const SCANNER_CONFIG_SETTING: &str = "scanner_config";
+
 /// Barcode scanner configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ScannerConfig {
     /// Enable scanner listening
     pub enabled: bool,
-    /// Input buffer timeout in milliseconds
+    /// Longest gap between two keystrokes of one scan; slower input is manual typing
     #[serde(rename = "bufferTimeoutMs")]
     pub buffer_timeout_ms: u32,
     /// Minimum barcode length
@@ -21,6 +30,9 @@ pub struct ScannerConfig {
     /// Maximum barcode length
     #[serde(rename = "maxLength")]
     pub max_length: usize,
+    /// Characters the scanner sends before the code (empty when none)
+    #[serde(default)]
+    pub prefix: String,
     /// Expected suffix (e.g., "\r" or "\n")
     pub suffix: String,
 }
@@ -32,6 +44,7 @@ impl Default for ScannerConfig {
             buffer_timeout_ms: 50,
             min_length: 4,
             max_length: 50,
+            prefix: String::new(),
             suffix: "\r".to_string(),
         }
     }
@@ -58,38 +71,378 @@ pub struct ScannerStatus {
     pub scan_count: u32,
 }
 
+/// Result of checking a typed or scanned barcode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarcodeValidation {
+    pub valid: bool,
+    /// EAN-13, UPC-A or EAN-8 when the check digit was verified
+    pub symbology: Option<String>,
+    /// False for internal codes (non-standard length, store-internal EAN-8) that have no check digit
+    #[serde(rename = "checksumVerified")]
+    pub checksum_verified: bool,
+    /// Reason shown to the user when the barcode is invalid
+    pub message: Option<String>,
+}
+
 /// Barcode validator - checks if input looks like a barcode
+#[allow(dead_code)]
 pub fn is_valid_barcode(input: &str, config: &ScannerConfig) -> bool {
+    validate_barcode(input, config).valid
+}
+
+/// Length/charset checks plus the modulo-10 check digit of EAN-13, UPC-A and EAN-8 codes
+pub fn validate_barcode(input: &str, config: &ScannerConfig) -> BarcodeValidation {
+    let invalid = |message: String| BarcodeValidation {
+        valid: false,
+        symbology: None,
+        checksum_verified: false,
+        message: Some(message),
+    };
     let len = input.len();
     
     // Length check
     if len < config.min_length || len > config.max_length {
-        return false;
+        return invalid(format!(
+            "Barkod {}-{} karakter olmali",
+            config.min_length, config.max_length
+        ));
     }
     
     // Must be alphanumeric with optional hyphens
-    input.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    if !input.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return invalid("Barkod yalnizca harf, rakam, - ve _ icerebilir".to_string());
+    }
+
+    let symbology = match (len, input.chars().all(|c| c.is_ascii_digit())) {
+        (13, true) => Some("EAN-13"),
+        (12, true) => Some("UPC-A"),
+        // EAN-8 codes starting with 0 or 2 are store-internal (e.g. `00001036`) and carry no check digit
+        (8, true) if !input.starts_with('0') && !input.starts_with('2') => Some("EAN-8"),
+        _ => None,
+    };
+    let Some(symbology) = symbology else {
+        return BarcodeValidation { valid: true, symbology: None, checksum_verified: false, message: None };
+    };
+
+    if has_valid_check_digit(input) {
+        BarcodeValidation {
+            valid: true,
+            symbology: Some(symbology.to_string()),
+            checksum_verified: true,
+            message: None,
+        }
+    } else {
+        invalid(format!(
+            "{} kontrol basamagi hatali, barkod yanlis yazilmis olabilir",
+            symbology
+        ))
+    }
+}
+
+/// What a scanned payload turned out to be
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarcodeKind {
+    /// EAN/UPC/Code 128/Code 39 style code
+    Linear,
+    /// GS1 element string (QR, DataMatrix or GS1-128) with application identifiers
+    Gs1,
+    /// Free text of a 2D code, e.g. a URL
+    QrText,
 }
 
 /// Parse common barcode formats
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BarcodeInfo {
     pub raw: String,
+    pub kind: BarcodeKind,
     #[serde(rename = "barcodeType")]
     pub barcode_type: String,
     pub valid: bool,
+    /// Price printed into a scale barcode (28/29 prefix), in TL
+    #[serde(rename = "embeddedPrice")]
+    pub embedded_price: Option<f64>,
+    /// Item code of a scale barcode (digits 3-7)
+    #[serde(rename = "itemCode")]
+    pub item_code: Option<String>,
+    /// GTIN-14 of a GS1 code (AI 01)
+    pub gtin: Option<String>,
+    /// Batch/lot number of a GS1 code (AI 10)
+    pub batch: Option<String>,
+    /// Expiry date of a GS1 code (AI 17) as YYYY-MM-DD
+    pub expiry: Option<String>,
+}
+
+/// EAN-13 prefixes used by our scales for price-embedded labels
+const PRICE_EMBEDDED_PREFIXES: [&str; 2] = ["28", "29"];
+
+/// GS1 field separator (FNC1 inside the data)
+const GS: char = '\u{1d}';
+
+/// Symbology identifiers scanners put in front of GS1 data: QR, DataMatrix, GS1-128, DataBar
+const GS1_SYMBOLOGY_IDS: [&str; 4] = ["]Q3", "]d2", "]C1", "]e0"];
+
+/// Length of the fixed-length GS1 application identifiers we may meet on cartons
+fn gs1_fixed_length(ai: &str) -> Option<usize> {
+    match ai {
+        "00" => Some(18),
+        "01" | "02" => Some(14),
+        "11" | "12" | "13" | "15" | "16" | "17" => Some(6),
+        "20" => Some(2),
+        _ => None,
+    }
+}
+
+/// Variable-length identifiers (batch, serial, count…), ended by GS or the end of the data
+const GS1_VARIABLE_AIS: [&str; 6] = ["10", "21", "22", "30", "37", "240"];
+
+/// Application identifier/value pairs of a GS1 element string, or None when it is not one.
+///
+/// Accepts the raw form (`]Q3` / GS separated, or bare digits starting with AI 01) and the
+/// human readable form `(01)…(10)…`.
+fn parse_gs1_elements(payload: &str) -> Option<Vec<(String, String)>> {
+    if payload.starts_with('(') {
+        let mut elements = Vec::new();
+        for part in payload.split('(').skip(1) {
+            let (ai, value) = part.split_once(')')?;
+            if ai.len() < 2 || !ai.chars().all(|c| c.is_ascii_digit()) || value.is_empty() {
+                return None;
+            }
+            elements.push((ai.to_string(), value.to_string()));
+        }
+        return Some(elements);
+    }
+
+    let marked = GS1_SYMBOLOGY_IDS.iter().find_map(|id| payload.strip_prefix(id));
+    let data = match marked {
+        Some(rest) => rest.trim_start_matches(GS),
+        None if payload.starts_with(GS) => payload.trim_start_matches(GS),
+        // Bare digits only count when they open with a GTIN and carry more than it
+        None if payload.starts_with("01") && payload.len() > 16 => payload,
+        None => return None,
+    };
+
+    let mut elements = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (ai, length) = match gs1_fixed_length(rest.get(..2)?) {
+            Some(length) => (rest.get(..2)?, Some(length)),
+            None => {
+                let ai = GS1_VARIABLE_AIS.iter().find(|ai| rest.starts_with(*ai))?;
+                (*ai, None)
+            }
+        };
+        let after_ai = &rest[ai.len()..];
+        let (value, next) = match length {
+            Some(length) => (after_ai.get(..length)?, &after_ai[length..]),
+            None => after_ai.split_once(GS).unwrap_or((after_ai, "")),
+        };
+        if value.is_empty() {
+            return None;
+        }
+        elements.push((ai.to_string(), value.to_string()));
+        rest = next.trim_start_matches(GS);
+    }
+    (!elements.is_empty()).then_some(elements)
+}
+
+/// GS1 YYMMDD date as YYYY-MM-DD; day 00 means the last day of the month
+fn gs1_date(value: &str) -> Option<String> {
+    if value.len() != 6 || !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let year = 2000 + value[0..2].parse::<i32>().ok()?;
+    let month: u32 = value[2..4].parse().ok()?;
+    let day: u32 = value[4..6].parse().ok()?;
+    let date = if day == 0 {
+        let first_of_next = if month == 12 {
+            NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+        } else {
+            NaiveDate::from_ymd_opt(year, month + 1, 1)?
+        };
+        first_of_next.pred_opt()?
+    } else {
+        NaiveDate::from_ymd_opt(year, month, day)?
+    };
+    Some(format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day()))
+}
+
+fn parse_gs1(payload: &str, elements: Vec<(String, String)>) -> BarcodeInfo {
+    let value = |wanted: &str| elements.iter().find(|(ai, _)| ai == wanted).map(|(_, v)| v.clone());
+    let gtin = value("01");
+    let expiry_raw = value("17");
+    let expiry = expiry_raw.as_deref().and_then(gs1_date);
+
+    let gtin_valid = gtin.as_deref().is_none_or(|g| g.len() == 14 && has_valid_check_digit(g));
+    BarcodeInfo {
+        raw: payload.to_string(),
+        kind: BarcodeKind::Gs1,
+        barcode_type: "GS1".to_string(),
+        valid: gtin_valid && expiry_raw.is_none() == expiry.is_none(),
+        embedded_price: None,
+        item_code: None,
+        gtin,
+        batch: value("10"),
+        expiry,
+    }
 }
 
 pub fn parse_barcode(barcode: &str) -> BarcodeInfo {
+    if let Some(elements) = parse_gs1_elements(barcode) {
+        return parse_gs1(barcode, elements);
+    }
+
     let barcode_type = detect_barcode_type(barcode);
-    
+    if barcode_type == "Custom" || barcode_type == "Unknown" {
+        return BarcodeInfo {
+            raw: barcode.to_string(),
+            kind: BarcodeKind::QrText,
+            barcode_type: "QR".to_string(),
+            valid: true,
+            embedded_price: None,
+            item_code: None,
+            gtin: None,
+            batch: None,
+            expiry: None,
+        };
+    }
+    let valid = barcode_type != "EAN-13" || has_valid_check_digit(barcode);
+
+    let (item_code, embedded_price) = if valid && barcode_type == "EAN-13" {
+        parse_price_embedded(barcode).map_or((None, None), |(code, price)| (Some(code), Some(price)))
+    } else {
+        (None, None)
+    };
+
     BarcodeInfo {
         raw: barcode.to_string(),
+        kind: BarcodeKind::Linear,
         barcode_type,
-        valid: true,
+        valid,
+        embedded_price,
+        item_code,
+        gtin: None,
+        batch: None,
+        expiry: None,
     }
 }
 
+/// SKUs a scanned code may be stored under, most specific first.
+///
+/// A GTIN-14 is tried as is and as the EAN-13/UPC-A printed on the unit (leading zeros dropped);
+/// other codes by their raw value. Free QR text never matches a SKU.
+pub fn sku_candidates(info: &BarcodeInfo) -> Vec<String> {
+    match info.kind {
+        BarcodeKind::Gs1 => {
+            let Some(gtin) = info.gtin.as_deref().filter(|_| info.valid) else {
+                return Vec::new();
+            };
+            let mut candidates = vec![gtin.to_string()];
+            if let Some(ean13) = gtin.strip_prefix('0') {
+                candidates.push(ean13.to_string());
+                if let Some(upc) = ean13.strip_prefix('0') {
+                    candidates.push(upc.to_string());
+                }
+            }
+            candidates
+        }
+        BarcodeKind::Linear => vec![info.raw.clone()],
+        BarcodeKind::QrText => Vec::new(),
+    }
+}
+
+/// Inventory item of a scanned code, trying each of `sku_candidates`
+pub fn find_item_for_barcode(conn: &Connection, info: &BarcodeInfo) -> Result<Option<InventoryItem>, AppError> {
+    for sku in sku_candidates(info) {
+        if let Some(item) = inventory::get_item_by_sku(conn, &sku)? {
+            return Ok(Some(item));
+        }
+    }
+    Ok(None)
+}
+
+/// Which lookup found the item of a scanned code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarcodeMatch {
+    /// The code (or the EAN-13 of a GTIN-14) is the item's SKU
+    Sku,
+    /// The code is the barcode of the item's stock card
+    StockCard,
+    /// Scale label; the item was found by the item code in the barcode
+    PriceEmbedded,
+}
+
+/// Item of a scanned code and how it was found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarcodeLookup {
+    pub item: InventoryItem,
+    #[serde(rename = "matchedBy")]
+    pub matched_by: BarcodeMatch,
+    /// Price printed into a scale label, in TL
+    #[serde(rename = "embeddedPrice")]
+    pub embedded_price: Option<f64>,
+}
+
+/// Inventory item of a scanned code: by SKU, then by stock card barcode, then by the item code
+/// of a price-embedded scale label (as printed and without its leading zeros)
+pub fn lookup_barcode(conn: &Connection, info: &BarcodeInfo) -> Result<Option<BarcodeLookup>, AppError> {
+    let found = |item: InventoryItem, matched_by: BarcodeMatch| {
+        Some(BarcodeLookup {
+            item,
+            matched_by,
+            embedded_price: info.embedded_price,
+        })
+    };
+
+    if let Some(item) = find_item_for_barcode(conn, info)? {
+        return Ok(found(item, BarcodeMatch::Sku));
+    }
+    for barcode in sku_candidates(info) {
+        if let Some(item) = inventory::get_item_by_stock_card_barcode(conn, &barcode)? {
+            return Ok(found(item, BarcodeMatch::StockCard));
+        }
+    }
+    if let Some(code) = info.item_code.as_deref() {
+        let trimmed = code.trim_start_matches('0');
+        for sku in [code, trimmed] {
+            if sku.is_empty() {
+                continue;
+            }
+            if let Some(item) = inventory::get_item_by_sku(conn, sku)? {
+                return Ok(found(item, BarcodeMatch::PriceEmbedded));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// GTIN modulo-10 check: digits are weighted 3, 1, 3... from the right, next to the check digit
+fn has_valid_check_digit(barcode: &str) -> bool {
+    let digits: Vec<u32> = barcode.chars().filter_map(|c| c.to_digit(10)).collect();
+    let Some((check, payload)) = digits.split_last() else {
+        return false;
+    };
+    if digits.len() != barcode.len() {
+        return false;
+    }
+    let sum: u32 = payload
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+        .sum();
+    (10 - sum % 10) % 10 == *check
+}
+
+/// Item code (digits 3-7) and price (digits 8-12, in kurus) of a scale label
+fn parse_price_embedded(barcode: &str) -> Option<(String, f64)> {
+    if !PRICE_EMBEDDED_PREFIXES.iter().any(|p| barcode.starts_with(p)) {
+        return None;
+    }
+    let item_code = barcode.get(2..7)?.to_string();
+    let kurus: u32 = barcode.get(7..12)?.parse().ok()?;
+    Some((item_code, kurus as f64 / 100.0))
+}
+
 /// Detect barcode type based on format
 fn detect_barcode_type(barcode: &str) -> String {
     let len = barcode.len();
@@ -113,10 +466,66 @@ fn detect_barcode_type(barcode: &str) -> String {
     }
 }
 
-/// Start scanner listener flag
-pub fn start_scanner_listener() {
-    SCANNER_ACTIVE.store(true, Ordering::SeqCst);
-    println!("[Scanner] Listener started");
+/// Stored scanner configuration, or the default when none was saved (or it no longer parses)
+pub fn load_scanner_config(conn: &Connection) -> Result<ScannerConfig, AppError> {
+    let Some(json) = settings::get_setting(conn, SCANNER_CONFIG_SETTING)? else {
+        return Ok(ScannerConfig::default());
+    };
+    Ok(serde_json::from_str(&json).unwrap_or_else(|e| {
+        eprintln!("[Scanner] Kayitli tarayici ayari okunamadi, varsayilan kullaniliyor: {}", e);
+        ScannerConfig::default()
+    }))
+}
+
+/// Validate and store the scanner configuration
+pub fn save_scanner_config(conn: &Connection, config: &ScannerConfig) -> Result<(), AppError> {
+    if !(5..=1000).contains(&config.buffer_timeout_ms) {
+        return Err(AppError::Validation("Tuslar arasi sure 5-1000 ms olmali".to_string()));
+    }
+    if config.min_length == 0 || config.min_length > config.max_length {
+        return Err(AppError::Validation("Barkod uzunluk sinirlari gecersiz".to_string()));
+    }
+    if config.prefix.chars().count() > 8 || config.suffix.chars().count() > 8 {
+        return Err(AppError::Validation("On ek ve son ek en fazla 8 karakter olabilir".to_string()));
+    }
+    settings::set_setting(conn, SCANNER_CONFIG_SETTING, &serde_json::to_string(config)?)
+}
+
+/// Scanned value without the configured prefix and suffix (each only when present)
+pub fn strip_scanner_affixes<'a>(raw: &'a str, config: &ScannerConfig) -> &'a str {
+    let value = raw.strip_prefix(config.prefix.as_str()).unwrap_or(raw);
+    let value = value.strip_suffix(config.suffix.as_str()).unwrap_or(value);
+    // Some scanners send CR LF even when only one of them is configured
+    value.trim_end_matches(['\r', '\n'])
+}
+
+/// Buffered keyboard input as a scan, or None when it looks like manual typing.
+///
+/// `key_intervals_ms` are the gaps between consecutive keystrokes; a scanner sends the whole code
+/// faster than `buffer_timeout_ms` per key. The affixes are stripped and the length checked before
+/// the value is parsed.
+pub fn parse_scanned_input(raw: &str, key_intervals_ms: &[u32], config: &ScannerConfig) -> Option<BarcodeInfo> {
+    if key_intervals_ms.iter().any(|gap| *gap > config.buffer_timeout_ms) {
+        return None;
+    }
+    let barcode = strip_scanner_affixes(raw, config);
+    let len = barcode.chars().count();
+    if len < config.min_length || len > config.max_length {
+        return None;
+    }
+    Some(parse_barcode(barcode))
+}
+
+/// Start listening with the stored configuration; stays off when the scanner is disabled there
+pub fn start_scanner_listener(conn: &Connection) -> Result<ScannerConfig, AppError> {
+    let config = load_scanner_config(conn)?;
+    SCANNER_ACTIVE.store(config.enabled, Ordering::SeqCst);
+    if config.enabled {
+        println!("[Scanner] Listener started");
+    } else {
+        println!("[Scanner] Scanner disabled in settings, listener not started");
+    }
+    Ok(config)
 }
 
 /// Stop scanner listener flag
@@ -142,11 +551,215 @@ mod tests {
         assert_eq!(detect_barcode_type("ABC-123"), "Code 39");
     }
 
+    #[test]
+    fn test_price_embedded_barcode() {
+        let info = parse_barcode("2912345012502");
+        assert!(info.valid);
+        assert_eq!(info.barcode_type, "EAN-13");
+        assert_eq!(info.item_code.as_deref(), Some("12345"));
+        assert_eq!(info.embedded_price, Some(12.5));
+
+        let normal = parse_barcode("8690000000012");
+        assert!(normal.valid);
+        assert_eq!(normal.item_code, None);
+        assert_eq!(normal.embedded_price, None);
+
+        // Last digit off by one
+        let bad = parse_barcode("2912345012503");
+        assert!(!bad.valid);
+        assert_eq!(bad.embedded_price, None);
+
+        // Other formats are not checked
+        assert!(parse_barcode("ABC-123").valid);
+    }
+
     #[test]
     fn test_barcode_validation() {
         let config = ScannerConfig::default();
-        assert!(is_valid_barcode("12345678", &config));
+        assert!(is_valid_barcode("12345670", &config));
         assert!(is_valid_barcode("ABC-123-XYZ", &config));
         assert!(!is_valid_barcode("ab", &config)); // too short
     }
+
+    #[test]
+    fn test_barcode_check_digits() {
+        let config = ScannerConfig::default();
+        let ean13 = validate_barcode("8690000000012", &config);
+        assert!(ean13.valid && ean13.checksum_verified);
+        assert_eq!(ean13.symbology.as_deref(), Some("EAN-13"));
+
+        let upc = validate_barcode("036000291452", &config);
+        assert!(upc.valid && upc.checksum_verified);
+        assert_eq!(upc.symbology.as_deref(), Some("UPC-A"));
+
+        let ean8 = validate_barcode("96385074", &config);
+        assert!(ean8.valid && ean8.checksum_verified);
+        assert_eq!(ean8.symbology.as_deref(), Some("EAN-8"));
+
+        // One mistyped digit in each symbology
+        for barcode in ["8690000000013", "036000291453", "12345678"] {
+            let result = validate_barcode(barcode, &config);
+            assert!(!result.valid, "{}", barcode);
+            assert!(result.message.is_some());
+        }
+
+        // Internal SKUs pass without a check digit
+        let internal = validate_barcode("00001036", &config);
+        assert!(internal.valid && !internal.checksum_verified);
+        assert!(validate_barcode("1036-A", &config).valid);
+    }
+
+    #[test]
+    fn test_scanner_affixes_stripped_before_parsing() {
+        let config = ScannerConfig {
+            prefix: "]E0".to_string(),
+            suffix: "\t".to_string(),
+            ..ScannerConfig::default()
+        };
+        assert_eq!(strip_scanner_affixes("]E08690000000012\t", &config), "8690000000012");
+        assert_eq!(strip_scanner_affixes("8690000000012\r\n", &config), "8690000000012");
+        assert_eq!(strip_scanner_affixes("ABC-123", &config), "ABC-123");
+
+        let info = parse_scanned_input("]E02912345012502\t", &[8; 16], &config).unwrap();
+        assert_eq!(info.raw, "2912345012502");
+        assert_eq!(info.barcode_type, "EAN-13");
+        assert_eq!(info.embedded_price, Some(12.5));
+
+        // Only affixes left: too short to be a barcode
+        assert!(parse_scanned_input("]E0\t", &[8; 3], &config).is_none());
+    }
+
+    #[test]
+    fn test_slow_typing_is_not_a_scan() {
+        let config = ScannerConfig {
+            buffer_timeout_ms: 40,
+            ..ScannerConfig::default()
+        };
+        assert!(parse_scanned_input("12345670\r", &[12, 15, 9, 11, 14, 10, 13, 12], &config).is_some());
+        assert!(parse_scanned_input("12345670\r", &[40; 8], &config).is_some());
+        // Manual typing: one slow key is enough to reject it
+        assert!(parse_scanned_input("12345670\r", &[12, 15, 180, 11, 14, 10, 13, 12], &config).is_none());
+        assert!(parse_scanned_input("12345670\r", &[150, 210, 170, 190, 160, 200, 180, 175], &config).is_none());
+    }
+
+    #[test]
+    fn test_scanner_config_persisted() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT);").unwrap();
+        assert_eq!(load_scanner_config(&conn).unwrap(), ScannerConfig::default());
+
+        let config = ScannerConfig {
+            buffer_timeout_ms: 30,
+            prefix: "#".to_string(),
+            suffix: "\n".to_string(),
+            ..ScannerConfig::default()
+        };
+        save_scanner_config(&conn, &config).unwrap();
+        assert_eq!(load_scanner_config(&conn).unwrap(), config);
+
+        let too_fast = ScannerConfig { buffer_timeout_ms: 0, ..config.clone() };
+        assert!(save_scanner_config(&conn, &too_fast).is_err());
+        let bad_lengths = ScannerConfig { min_length: 20, max_length: 10, ..config.clone() };
+        assert!(save_scanner_config(&conn, &bad_lengths).is_err());
+        assert_eq!(load_scanner_config(&conn).unwrap(), config);
+    }
+
+    #[test]
+    fn test_gs1_qr_payload() {
+        // GTIN 09506000134352, batch ABC123, expiry 2025-12-31, FNC1 separated after the batch
+        let info = parse_barcode("]Q3010950600013435210ABC123\u{1d}17251231");
+        assert_eq!(info.kind, BarcodeKind::Gs1);
+        assert!(info.valid);
+        assert_eq!(info.gtin.as_deref(), Some("09506000134352"));
+        assert_eq!(info.batch.as_deref(), Some("ABC123"));
+        assert_eq!(info.expiry.as_deref(), Some("2025-12-31"));
+        assert_eq!(sku_candidates(&info), ["09506000134352", "9506000134352"]);
+
+        // Human readable form, day 00 = end of month, batch last
+        let info = parse_barcode("(01)08690000000012(17)240200(10)L-77");
+        assert_eq!(info.gtin.as_deref(), Some("08690000000012"));
+        assert_eq!(info.batch.as_deref(), Some("L-77"));
+        assert_eq!(info.expiry.as_deref(), Some("2024-02-29"));
+        assert_eq!(sku_candidates(&info)[1], "8690000000012");
+
+        // Wrong GTIN check digit
+        let bad = parse_barcode("]d2010950600013435317251231");
+        assert_eq!(bad.kind, BarcodeKind::Gs1);
+        assert!(!bad.valid);
+        assert!(sku_candidates(&bad).is_empty());
+
+        // Plain EAN-13 is not mistaken for GS1
+        assert_eq!(parse_barcode("8690000000012").kind, BarcodeKind::Linear);
+    }
+
+    fn lookup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (
+                 id TEXT PRIMARY KEY, sku TEXT UNIQUE, name TEXT, category TEXT, quantity INTEGER, location TEXT,
+                 price REAL, cost_price REAL, image TEXT, description TEXT, ai_tags TEXT, last_updated TEXT,
+                 currency TEXT, supplier_id TEXT, brand TEXT, min_stock_level INTEGER DEFAULT 5, deleted_at TEXT,
+                 unit TEXT NOT NULL DEFAULT 'ADET'
+             );
+             CREATE TABLE stock_cards (id TEXT PRIMARY KEY, barcode TEXT UNIQUE NOT NULL, name TEXT NOT NULL);
+             INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, last_updated) VALUES
+                 ('i1', '8690000000012', 'Tornavida', 'El Aleti', 5, 'A1', 50.0, 'now'),
+                 ('card-2', 'TORNAVIDA-YILDIZ', 'Yildiz Tornavida', 'El Aleti', 3, 'A1', 60.0, 'now'),
+                 ('i3', '123', 'Kablo (metre)', 'Elektrik', 100, 'B2', 8.0, 'now');
+             INSERT INTO stock_cards VALUES ('card-2', '8690000000029', 'Yildiz Tornavida');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn lookup(conn: &Connection, barcode: &str) -> Option<(String, BarcodeMatch, Option<f64>)> {
+        lookup_barcode(conn, &parse_barcode(barcode))
+            .unwrap()
+            .map(|found| (found.item.sku, found.matched_by, found.embedded_price))
+    }
+
+    #[test]
+    fn test_lookup_by_sku() {
+        let conn = lookup_db();
+        assert_eq!(lookup(&conn, "8690000000012"), Some(("8690000000012".to_string(), BarcodeMatch::Sku, None)));
+        // GTIN-14 of the same unit
+        assert_eq!(lookup(&conn, "(01)08690000000012").unwrap().1, BarcodeMatch::Sku);
+    }
+
+    #[test]
+    fn test_lookup_by_stock_card_barcode() {
+        let conn = lookup_db();
+        assert_eq!(
+            lookup(&conn, "8690000000029"),
+            Some(("TORNAVIDA-YILDIZ".to_string(), BarcodeMatch::StockCard, None))
+        );
+    }
+
+    #[test]
+    fn test_lookup_by_price_embedded_item_code() {
+        let conn = lookup_db();
+        // Item code 00123, 12.50 TL
+        let label = "2900123012509";
+        assert!(parse_barcode(label).valid);
+        assert_eq!(lookup(&conn, label), Some(("123".to_string(), BarcodeMatch::PriceEmbedded, Some(12.5))));
+    }
+
+    #[test]
+    fn test_lookup_without_match() {
+        let conn = lookup_db();
+        assert_eq!(lookup(&conn, "8690000000036"), None);
+        assert_eq!(lookup(&conn, "2999999012508"), None);
+        assert_eq!(lookup(&conn, "https://example.com/urun?id=42"), None);
+        conn.execute("UPDATE inventory_items SET deleted_at = 'now'", []).unwrap();
+        assert_eq!(lookup(&conn, "8690000000029"), None);
+    }
+
+    #[test]
+    fn test_plain_qr_text() {
+        let info = parse_barcode("https://tedarikci.example.com/urun?id=42");
+        assert_eq!(info.kind, BarcodeKind::QrText);
+        assert_eq!(info.raw, "https://tedarikci.example.com/urun?id=42");
+        assert_eq!(info.gtin, None);
+        assert!(sku_candidates(&info).is_empty());
+    }
 }
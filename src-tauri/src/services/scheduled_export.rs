@@ -0,0 +1,191 @@
+//! Scheduled Export Module
+//!
+//! Muhasebe programının her gece okuduğu klasöre günlük CSV dışa aktarımı: stok listesi ve
+//! günün işlemleri, ayarlanan saatte yazılır. Ayarlar `settings` tablosunda tutulur ki
+//! uygulama yeniden açıldığında zamanlayıcı kaldığı yerden devam etsin.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::{params, Connection};
+
+use crate::error::AppError;
+use crate::models::Transaction;
+use crate::services::{inventory, settings};
+
+/// Folder the CSV files are written to; the scheduler is off while this is empty
+pub const EXPORT_DIR_SETTING: &str = "scheduled_export_dir";
+
+/// Time of day of the export, "HH:MM" in local time
+pub const EXPORT_TIME_SETTING: &str = "scheduled_export_time";
+
+/// Bumped by every start/stop; a scheduler thread exits once its generation is no longer current
+static EXPORT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Longest single sleep, so a stop or restart is noticed within a minute
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Hour 0-23 and minute 0-59
+pub fn validate_export_time(hour: u32, minute: u32) -> Result<NaiveTime, AppError> {
+    NaiveTime::from_hms_opt(hour, minute, 0)
+        .ok_or_else(|| AppError::Validation(format!("Geçersiz saat: {:02}:{:02}", hour, minute)))
+}
+
+/// Next time the export runs: today at `at` if that is still ahead of `now`, otherwise tomorrow
+pub fn next_run(now: NaiveDateTime, at: NaiveTime) -> NaiveDateTime {
+    let today = now.date().and_time(at);
+    if today > now {
+        today
+    } else {
+        today + chrono::Duration::days(1)
+    }
+}
+
+/// Transactions as CSV (Excel uyumlu: BOM + noktalı virgül)
+pub fn transactions_to_csv(transactions: &[Transaction]) -> String {
+    let mut csv = String::from("\u{FEFF}"); // UTF-8 BOM for Excel
+    csv.push_str("İşlem No;Tarih;Tür;Ödeme;Durum;Kalem Sayısı;KDV;Toplam;Not\n");
+
+    for tx in transactions {
+        csv.push_str(&format!(
+            "{};{};{};{};{};{};{:.2};{:.2};{}\n",
+            tx.id,
+            tx.created_at,
+            tx.transaction_type,
+            tx.payment_method,
+            tx.status,
+            tx.items.len(),
+            tx.tax_total,
+            tx.total,
+            tx.note.as_deref().unwrap_or("").replace([';', '\n'], " ")
+        ));
+    }
+
+    csv
+}
+
+/// Write the inventory and the transactions of `date` into `dir`, creating it if needed.
+/// Files are named by date so a re-run overwrites that day's export.
+pub fn export_day(conn: &Connection, dir: &Path, date: NaiveDate) -> Result<Vec<PathBuf>, AppError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| AppError::Internal(format!("Dışa aktarım klasörü oluşturulamadı ({}): {}", dir.display(), e)))?;
+
+    let ids: Vec<String> = {
+        let mut stmt = conn.prepare("SELECT id FROM transactions WHERE substr(created_at, 1, 10) = ?1 ORDER BY created_at")?;
+        let rows = stmt.query_map(params![date.to_string()], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut transactions = Vec::with_capacity(ids.len());
+    for id in ids {
+        transactions.extend(inventory::get_transaction(conn, &id)?);
+    }
+
+    let inventory_path = dir.join(format!("stok_{}.csv", date));
+    std::fs::write(&inventory_path, inventory::export_to_csv(conn)?)?;
+    let transactions_path = dir.join(format!("islemler_{}.csv", date));
+    std::fs::write(&transactions_path, transactions_to_csv(&transactions))?;
+
+    Ok(vec![inventory_path, transactions_path])
+}
+
+/// Start the daily export at `at` (local time); a running scheduler is replaced
+pub fn start_export_scheduler(db_path: String, dir: PathBuf, at: NaiveTime) {
+    let generation = EXPORT_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    println!("[Export] Scheduler started ({} every day -> {})", at.format("%H:%M"), dir.display());
+
+    std::thread::spawn(move || {
+        let is_current = || EXPORT_GENERATION.load(Ordering::SeqCst) == generation;
+
+        while is_current() {
+            let due = next_run(Local::now().naive_local(), at);
+            // Sleep in short steps: stops, restarts and clock changes are picked up
+            loop {
+                let now = Local::now().naive_local();
+                if now >= due || !is_current() {
+                    break;
+                }
+                let remaining = (due - now).to_std().unwrap_or_default();
+                std::thread::sleep(remaining.min(MAX_SLEEP));
+            }
+            if !is_current() {
+                break;
+            }
+
+            let result = crate::database::open_database(&db_path)
+                .map_err(AppError::from)
+                .and_then(|conn| export_day(&conn, &dir, due.date()));
+            match result {
+                Ok(files) => println!("[Export] Scheduled export written: {} files in {}", files.len(), dir.display()),
+                Err(e) => eprintln!("[Export] Scheduled export failed: {}", e),
+            }
+        }
+
+        println!("[Export] Scheduler stopped");
+    });
+}
+
+/// Stop the daily export
+pub fn stop_export_scheduler() {
+    EXPORT_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Restart the export saved in settings (called at startup); does nothing while it is not configured
+pub fn resume_export_scheduler(conn: &Connection, db_path: &str) -> Result<(), AppError> {
+    let dir = settings::get_setting(conn, EXPORT_DIR_SETTING)?.filter(|d| !d.trim().is_empty());
+    let at = settings::get_setting(conn, EXPORT_TIME_SETTING)?
+        .and_then(|t| NaiveTime::parse_from_str(t.trim(), "%H:%M").ok());
+    if let (Some(dir), Some(at)) = (dir, at) {
+        start_export_scheduler(db_path.to_string(), PathBuf::from(dir), at);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_next_run() {
+        let target = validate_export_time(23, 30).unwrap();
+        // Later today
+        assert_eq!(next_run(at("2024-03-01", "09:15:00"), target), at("2024-03-01", "23:30:00"));
+        // Exactly at or past the target: tomorrow
+        assert_eq!(next_run(at("2024-03-01", "23:30:00"), target), at("2024-03-02", "23:30:00"));
+        assert_eq!(next_run(at("2024-03-01", "23:45:10"), target), at("2024-03-02", "23:30:00"));
+        // Across a month end
+        assert_eq!(next_run(at("2024-02-29", "23:59:59"), validate_export_time(0, 0).unwrap()), at("2024-03-01", "00:00:00"));
+
+        assert!(validate_export_time(24, 0).is_err());
+        assert!(validate_export_time(12, 60).is_err());
+    }
+
+    #[test]
+    fn test_export_day_creates_missing_directory() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('i1', 'BOYA-15', 'Boya 15L', 4, 900.0, 'now');
+             INSERT INTO transactions (id, items, total, payment_method, transaction_type, note, created_at) VALUES
+                 ('t1', '[]', 900.0, 'NAKIT', 'SALE', 'kapı; 3', '2024-03-01T10:00:00Z'),
+                 ('t2', '[]', 50.0, 'NAKIT', 'SALE', NULL, '2024-03-02T10:00:00Z');",
+        )
+        .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("nexus_export_{}", uuid::Uuid::new_v4())).join("gece");
+        let files = export_day(&conn, &dir, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()).unwrap();
+        assert_eq!(files, vec![dir.join("stok_2024-03-01.csv"), dir.join("islemler_2024-03-01.csv")]);
+
+        assert!(std::fs::read_to_string(&files[0]).unwrap().contains("BOYA-15;Boya 15L"));
+        let transactions = std::fs::read_to_string(&files[1]).unwrap();
+        assert_eq!(transactions.lines().count(), 2);
+        assert!(transactions.contains("t1;2024-03-01T10:00:00Z;SALE;NAKIT;completed;0;0.00;900.00;kapı  3"));
+
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+//! Settings Service Module
+//!
+//! `settings` tablosundaki anahtar/değer ayarları (mağaza adı, para birimi, KDV oranı, fiş alt yazısı…).
+//! Arayüz ayarları burada tutulur ki yeniden kurulumda kaybolmasın.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+
+/// Store name printed on receipts and invoices
+pub const STORE_NAME_SETTING: &str = "store_name";
+
+/// Currency printed on receipts and invoices
+pub const CURRENCY_SETTING: &str = "currency";
+
+/// Store address, phone and tax number printed on purchase orders
+pub const STORE_ADDRESS_SETTING: &str = "store_address";
+pub const STORE_PHONE_SETTING: &str = "store_phone";
+pub const STORE_TAX_NUMBER_SETTING: &str = "store_tax_number";
+
+/// "1"/"true" prints a QR of the transaction on receipts and invoices
+pub const RECEIPT_QR_SETTING: &str = "receipt_qr_enabled";
+
+/// Key the receipt QR tokens are signed with (generated on first use)
+pub const RECEIPT_QR_SECRET_SETTING: &str = "receipt_qr_secret";
+
+/// "1"/"true" lets a sale take more than is in stock; the quantity then goes negative (backorder).
+/// Off or unset, such a sale is rejected.
+pub const ALLOW_NEGATIVE_STOCK_SETTING: &str = "allow_negative_stock";
+
+fn validate_key(key: &str) -> Result<(), AppError> {
+    if key.trim().is_empty() {
+        return Err(AppError::Validation("Ayar anahtari bos olamaz".to_string()));
+    }
+    Ok(())
+}
+
+/// Value of `key`, or None when it was never set
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>, AppError> {
+    let value: Option<Option<String>> = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()?;
+    Ok(value.flatten())
+}
+
+/// Whether an on/off setting is "1" or "true"; unset counts as off
+pub fn is_enabled(conn: &Connection, key: &str) -> Result<bool, AppError> {
+    Ok(get_setting(conn, key)?.is_some_and(|v| matches!(v.trim(), "1" | "true")))
+}
+
+/// Insert or overwrite `key`
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<(), AppError> {
+    validate_key(key)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+/// Every setting with a value
+pub fn get_all_settings(conn: &Connection) -> Result<HashMap<String, String>, AppError> {
+    let mut stmt = conn.prepare("SELECT key, value FROM settings WHERE value IS NOT NULL")?;
+    let settings = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    Ok(settings)
+}
+
+/// `value` unless it is empty, otherwise the stored setting (empty when unset)
+pub fn value_or_setting(conn: &Connection, value: String, key: &str) -> Result<String, AppError> {
+    if !value.trim().is_empty() {
+        return Ok(value);
+    }
+    Ok(get_setting(conn, key)?.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY NOT NULL, value TEXT);")
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_set_setting_twice_keeps_last_value() {
+        let conn = settings_db();
+        set_setting(&conn, STORE_NAME_SETTING, "Nisa Boya").unwrap();
+        set_setting(&conn, STORE_NAME_SETTING, "Nisa Yapi").unwrap();
+        set_setting(&conn, CURRENCY_SETTING, "TL").unwrap();
+
+        assert_eq!(get_setting(&conn, STORE_NAME_SETTING).unwrap().as_deref(), Some("Nisa Yapi"));
+        let all = get_all_settings(&conn).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[CURRENCY_SETTING], "TL");
+
+        assert!(set_setting(&conn, " ", "x").is_err());
+    }
+
+    #[test]
+    fn test_missing_setting_reads_as_none() {
+        let conn = settings_db();
+        assert_eq!(get_setting(&conn, "receipt_footer").unwrap(), None);
+        assert_eq!(value_or_setting(&conn, String::new(), CURRENCY_SETTING).unwrap(), "");
+
+        set_setting(&conn, CURRENCY_SETTING, "TL").unwrap();
+        assert_eq!(value_or_setting(&conn, String::new(), CURRENCY_SETTING).unwrap(), "TL");
+        assert_eq!(value_or_setting(&conn, "USD".to_string(), CURRENCY_SETTING).unwrap(), "USD");
+    }
+}
@@ -1,3 +1,11 @@
+//! Multi-Device Sync Service Module
+//!
+//! Yerel değişiklikler `sync_outbox` üzerinden gönderilir, diğer cihazların değişiklikleri çekilip uygulanır.
+//! Çakışma stratejisi: stok hareketleri (`SALE`, `STOCK_IN`, `STOCK_OUT`) fark (delta) olarak uygulanır,
+//! böylece iki cihazın eşzamanlı hareketlerinin ikisi de geçerli olur. `PRICE_CHANGE` ve `ITEM_UPDATE`
+//! için `transaction_time` üzerinden son yazan kazanır. Yerel değişiklikle çakışan kararlar
+//! `sync_conflicts` tablosuna yazılır.
+
 use crate::error::AppError;
 use crate::license::{get_device_mac_address, get_local_license};
 use rusqlite::{params, Connection};
@@ -8,6 +16,8 @@ use std::thread;
 use std::time::Duration;
 use uuid::Uuid;
 
+pub mod lan;
+
 /// Transaction action types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ActionType {
@@ -41,7 +51,8 @@ pub struct SyncTransaction {
     pub action_type: String,
     pub item_sku: Option<String>,
     pub item_name: Option<String>,
-    pub quantity_change: i32,
+    /// Fractional for measured units (KG, MT)
+    pub quantity_change: f64,
     pub old_value: Option<f64>,
     pub new_value: Option<f64>,
     pub metadata: Option<String>,
@@ -94,7 +105,7 @@ pub struct RemoteTransaction {
     #[serde(rename = "itemName")]
     pub item_name: Option<String>,
     #[serde(rename = "quantityChange")]
-    pub quantity_change: i32,
+    pub quantity_change: f64,
     #[serde(rename = "oldValue")]
     pub old_value: Option<f64>,
     #[serde(rename = "newValue")]
@@ -104,6 +115,46 @@ pub struct RemoteTransaction {
     pub transaction_time: String,
 }
 
+impl RemoteTransaction {
+    /// A row of another device's outbox, as received from that device
+    pub fn from_outbox(txn: SyncTransaction, device_identifier: &str) -> Self {
+        RemoteTransaction {
+            id: txn.id,
+            device_identifier: device_identifier.to_string(),
+            action_type: txn.action_type,
+            item_sku: txn.item_sku,
+            item_name: txn.item_name,
+            quantity_change: txn.quantity_change,
+            old_value: txn.old_value,
+            new_value: txn.new_value,
+            metadata: txn
+                .metadata
+                .map(|m| serde_json::from_str(&m).unwrap_or(serde_json::Value::String(m))),
+            transaction_time: txn.transaction_time,
+        }
+    }
+}
+
+/// Conflict resolutions recorded in `sync_conflicts`
+pub const RESOLUTION_REMOTE_WINS: &str = "REMOTE_WINS";
+pub const RESOLUTION_LOCAL_WINS: &str = "LOCAL_WINS";
+pub const RESOLUTION_MERGED: &str = "MERGED";
+
+/// Audit row of a pulled change that met a local change of the same item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub id: i64,
+    pub remote_id: String,
+    pub device_identifier: Option<String>,
+    pub action_type: String,
+    pub item_sku: Option<String>,
+    pub resolution: String,
+    pub remote_time: String,
+    pub local_time: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: String,
+}
+
 /// Heartbeat request
 #[derive(Debug, Serialize)]
 struct HeartbeatRequest {
@@ -118,7 +169,7 @@ pub fn queue_transaction(
     action_type: ActionType,
     item_sku: Option<&str>,
     item_name: Option<&str>,
-    quantity_change: i32,
+    quantity_change: f64,
     old_value: Option<f64>,
     new_value: Option<f64>,
     metadata: Option<&str>,
@@ -145,6 +196,20 @@ pub fn queue_transaction(
     Ok(id)
 }
 
+fn outbox_row(row: &rusqlite::Row) -> rusqlite::Result<SyncTransaction> {
+    Ok(SyncTransaction {
+        id: row.get(0)?,
+        action_type: row.get(1)?,
+        item_sku: row.get(2)?,
+        item_name: row.get(3)?,
+        quantity_change: row.get(4)?,
+        old_value: row.get(5)?,
+        new_value: row.get(6)?,
+        metadata: row.get(7)?,
+        transaction_time: row.get(8)?,
+    })
+}
+
 /// Get pending transactions from outbox
 pub fn get_pending_transactions(conn: &Connection, limit: i32) -> Result<Vec<SyncTransaction>, AppError> {
     let mut stmt = conn.prepare(
@@ -152,25 +217,25 @@ pub fn get_pending_transactions(conn: &Connection, limit: i32) -> Result<Vec<Syn
          FROM sync_outbox WHERE synced = 0 ORDER BY transaction_time ASC LIMIT ?1"
     )?;
 
-    let transactions = stmt.query_map(params![limit], |row| {
-        Ok(SyncTransaction {
-            id: row.get(0)?,
-            action_type: row.get(1)?,
-            item_sku: row.get(2)?,
-            item_name: row.get(3)?,
-            quantity_change: row.get(4)?,
-            old_value: row.get(5)?,
-            new_value: row.get(6)?,
-            metadata: row.get(7)?,
-            transaction_time: row.get(8)?,
-        })
-    })?
+    let transactions = stmt.query_map(params![limit], outbox_row)?
     .filter_map(|r| r.ok())
     .collect();
 
     Ok(transactions)
 }
 
+/// Outbox rows written after `since` (all when None), oldest first, whether or not pushed to the cloud
+pub fn get_outbox_since(conn: &Connection, since: Option<&str>, limit: i32) -> Result<Vec<SyncTransaction>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, action_type, item_sku, item_name, quantity_change, old_value, new_value, metadata, transaction_time
+         FROM sync_outbox WHERE ?1 IS NULL OR transaction_time > ?1 ORDER BY transaction_time ASC LIMIT ?2"
+    )?;
+    let transactions = stmt
+        .query_map(params![since, limit], outbox_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(transactions)
+}
+
 /// Mark transactions as synced
 pub fn mark_as_synced(conn: &Connection, ids: &[String]) -> Result<(), AppError> {
     for id in ids {
@@ -182,6 +247,23 @@ pub fn mark_as_synced(conn: &Connection, ids: &[String]) -> Result<(), AppError>
     Ok(())
 }
 
+/// Synced outbox rows older than this are removed by the background sync once a day
+pub const OUTBOX_RETENTION_DAYS: i64 = 30;
+
+/// Delete outbox rows pushed to the cloud whose transaction time is older than `older_than_days`.
+/// Unsynced rows are never deleted. Returns the number of rows removed.
+pub fn purge_synced_outbox(conn: &Connection, older_than_days: i64) -> Result<usize, AppError> {
+    if older_than_days < 1 {
+        return Err(AppError::Validation("Gun sayisi en az 1 olmali".to_string()));
+    }
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(older_than_days)).to_rfc3339();
+    let removed = conn.execute(
+        "DELETE FROM sync_outbox WHERE synced = 1 AND transaction_time < ?1",
+        params![&cutoff],
+    )?;
+    Ok(removed)
+}
+
 /// Get pending transaction count
 pub fn get_pending_count(conn: &Connection) -> Result<i32, AppError> {
     let count: i32 = conn.query_row(
@@ -236,8 +318,8 @@ pub fn push_to_cloud(
             // Update last push time
             let now = chrono::Utc::now().to_rfc3339();
             conn.execute(
-                "INSERT OR REPLACE INTO sync_state (id, last_push_at, last_pull_at, sync_in_progress)
-                 VALUES (1, ?1, (SELECT last_pull_at FROM sync_state WHERE id = 1), 0)",
+                "INSERT INTO sync_state (id, last_push_at, sync_in_progress) VALUES (1, ?1, 0)
+                 ON CONFLICT(id) DO UPDATE SET last_push_at = excluded.last_push_at, sync_in_progress = 0",
                 params![&now],
             )?;
 
@@ -273,7 +355,7 @@ pub fn pull_from_cloud(
 
     let request = PullRequest {
         device_identifier: device_id.clone(),
-        since: last_received,
+        since: last_received.clone(),
     };
 
     let response = client
@@ -291,19 +373,20 @@ pub fn pull_from_cloud(
             .map_err(|e| AppError::Internal(format!("Pull yaniti ayristirilamadi: {}", e)))?;
 
         if result.success {
-            let transactions = result.transactions.unwrap_or_default();
+            let mut transactions = result.transactions.unwrap_or_default();
             let count = transactions.len() as i32;
 
-            // Apply each transaction
+            // Apply each transaction, oldest first so last-writer-wins sees them in order
+            transactions.sort_by(|a, b| compare_times(&a.transaction_time, &b.transaction_time));
             for txn in &transactions {
-                apply_remote_transaction(conn, txn)?;
+                apply_remote_transaction(conn, txn, last_received.as_deref())?;
             }
 
             // Update last pull time
             let now = chrono::Utc::now().to_rfc3339();
             conn.execute(
-                "INSERT OR REPLACE INTO sync_state (id, last_push_at, last_pull_at, sync_in_progress)
-                 VALUES (1, (SELECT last_push_at FROM sync_state WHERE id = 1), ?1, 0)",
+                "INSERT INTO sync_state (id, last_pull_at, sync_in_progress) VALUES (1, ?1, 0)
+                 ON CONFLICT(id) DO UPDATE SET last_pull_at = excluded.last_pull_at, sync_in_progress = 0",
                 params![&now],
             )?;
 
@@ -316,34 +399,135 @@ pub fn pull_from_cloud(
     }
 }
 
-/// Apply a remote transaction to local database
-fn apply_remote_transaction(conn: &Connection, txn: &RemoteTransaction) -> Result<(), AppError> {
+/// Order two RFC 3339 timestamps, falling back to text order when one does not parse
+fn compare_times(a: &str, b: &str) -> std::cmp::Ordering {
+    match (
+        chrono::DateTime::parse_from_rfc3339(a),
+        chrono::DateTime::parse_from_rfc3339(b),
+    ) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Latest local outbox time per action for the SKU, made after `since` when given
+fn latest_local_change(
+    conn: &Connection,
+    sku: &str,
+    actions: &[&str],
+    since: Option<&str>,
+) -> Result<Option<(String, i32)>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT transaction_time FROM sync_outbox WHERE item_sku = ?1 AND action_type IN ({})",
+        actions.iter().map(|a| format!("'{}'", a)).collect::<Vec<_>>().join(", ")
+    ))?;
+    let times = stmt
+        .query_map(params![sku], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut latest: Option<String> = None;
+    let mut count = 0;
+    for time in times {
+        if since.is_some_and(|since| compare_times(&time, since).is_le()) {
+            continue;
+        }
+        count += 1;
+        if latest.as_ref().is_none_or(|l| compare_times(&time, l).is_gt()) {
+            latest = Some(time);
+        }
+    }
+    Ok(latest.map(|time| (time, count)))
+}
+
+fn record_conflict(
+    conn: &Connection,
+    txn: &RemoteTransaction,
+    resolution: &str,
+    local_time: Option<&str>,
+    detail: &str,
+) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO sync_conflicts (remote_id, device_identifier, action_type, item_sku, resolution, remote_time, local_time, detail, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            &txn.id,
+            &txn.device_identifier,
+            &txn.action_type,
+            &txn.item_sku,
+            resolution,
+            &txn.transaction_time,
+            local_time,
+            detail,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Last-writer-wins check for a remote field edit. Returns whether the remote edit should be applied;
+/// a local edit of the same field wins when it is newer than the remote one.
+fn remote_edit_wins(conn: &Connection, txn: &RemoteTransaction, sku: &str) -> Result<bool, AppError> {
+    let Some((local_time, _)) = latest_local_change(conn, sku, &[txn.action_type.as_str()], None)? else {
+        return Ok(true);
+    };
+
+    if compare_times(&txn.transaction_time, &local_time).is_gt() {
+        record_conflict(conn, txn, RESOLUTION_REMOTE_WINS, Some(&local_time), "Uzak degisiklik daha yeni, yerel deger ezildi")?;
+        Ok(true)
+    } else {
+        record_conflict(conn, txn, RESOLUTION_LOCAL_WINS, Some(&local_time), "Yerel degisiklik daha yeni, uzak degisiklik reddedildi")?;
+        Ok(false)
+    }
+}
+
+/// Apply a remote transaction to local database.
+/// `since` is the previous pull time; local stock movements after it are concurrent with the remote one.
+/// A transaction received before (from the cloud or a LAN peer) is skipped.
+fn apply_remote_transaction(conn: &Connection, txn: &RemoteTransaction, since: Option<&str>) -> Result<(), AppError> {
+    let first_time = conn.execute(
+        "INSERT OR IGNORE INTO sync_received (id, device_identifier, received_at) VALUES (?1, ?2, ?3)",
+        params![&txn.id, &txn.device_identifier, chrono::Utc::now().to_rfc3339()],
+    )?;
+    if first_time == 0 {
+        return Ok(());
+    }
+
     match txn.action_type.as_str() {
-        "SALE" | "STOCK_OUT" => {
-            // Decrease quantity
+        "SALE" | "STOCK_OUT" | "STOCK_IN" => {
+            // Stock movements are deltas: both devices' movements take effect
             if let Some(sku) = &txn.item_sku {
+                let delta = if txn.action_type == "STOCK_IN" {
+                    txn.quantity_change.abs()
+                } else {
+                    -txn.quantity_change.abs()
+                };
                 conn.execute(
                     "UPDATE inventory_items SET quantity = MAX(0, quantity + ?1), last_updated = ?2 WHERE sku = ?3",
-                    params![txn.quantity_change, &txn.transaction_time, sku],
-                )?;
-            }
-        }
-        "STOCK_IN" => {
-            // Increase quantity
-            if let Some(sku) = &txn.item_sku {
-                conn.execute(
-                    "UPDATE inventory_items SET quantity = quantity + ?1, last_updated = ?2 WHERE sku = ?3",
-                    params![txn.quantity_change, &txn.transaction_time, sku],
+                    params![delta, &txn.transaction_time, sku],
                 )?;
+
+                if let Some((local_time, count)) =
+                    latest_local_change(conn, sku, &["SALE", "STOCK_IN", "STOCK_OUT"], since)?
+                {
+                    record_conflict(
+                        conn,
+                        txn,
+                        RESOLUTION_MERGED,
+                        Some(&local_time),
+                        &format!("{:+} birim, {} yerel stok hareketiyle birlikte uygulandi", delta, count),
+                    )?;
+                }
             }
         }
         "PRICE_CHANGE" => {
             // Update price
             if let (Some(sku), Some(new_price)) = (&txn.item_sku, txn.new_value) {
-                conn.execute(
-                    "UPDATE inventory_items SET price = ?1, last_updated = ?2 WHERE sku = ?3",
-                    params![new_price, &txn.transaction_time, sku],
-                )?;
+                if remote_edit_wins(conn, txn, sku)? {
+                    conn.execute(
+                        "UPDATE inventory_items SET price = ?1, last_updated = ?2 WHERE sku = ?3",
+                        params![new_price, &txn.transaction_time, sku],
+                    )?;
+                }
             }
         }
         "ITEM_CREATE" => {
@@ -360,10 +544,12 @@ fn apply_remote_transaction(conn: &Connection, txn: &RemoteTransaction) -> Resul
         "ITEM_UPDATE" => {
             // Update item
             if let (Some(sku), Some(name)) = (&txn.item_sku, &txn.item_name) {
-                conn.execute(
-                    "UPDATE inventory_items SET name = ?1, last_updated = ?2 WHERE sku = ?3",
-                    params![name, &txn.transaction_time, sku],
-                )?;
+                if remote_edit_wins(conn, txn, sku)? {
+                    conn.execute(
+                        "UPDATE inventory_items SET name = ?1, last_updated = ?2 WHERE sku = ?3",
+                        params![name, &txn.transaction_time, sku],
+                    )?;
+                }
             }
         }
         "ITEM_DELETE" => {
@@ -381,6 +567,31 @@ fn apply_remote_transaction(conn: &Connection, txn: &RemoteTransaction) -> Resul
     Ok(())
 }
 
+/// Recorded sync conflicts, newest first
+pub fn get_sync_conflicts(conn: &Connection, limit: i64) -> Result<Vec<SyncConflict>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, remote_id, device_identifier, action_type, item_sku, resolution, remote_time, local_time, detail, created_at
+         FROM sync_conflicts ORDER BY id DESC LIMIT ?1",
+    )?;
+    let conflicts = stmt
+        .query_map(params![limit.clamp(1, 1000)], |row| {
+            Ok(SyncConflict {
+                id: row.get(0)?,
+                remote_id: row.get(1)?,
+                device_identifier: row.get(2)?,
+                action_type: row.get(3)?,
+                item_sku: row.get(4)?,
+                resolution: row.get(5)?,
+                remote_time: row.get(6)?,
+                local_time: row.get(7)?,
+                detail: row.get(8)?,
+                created_at: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(conflicts)
+}
+
 /// Send heartbeat to cloud
 pub fn send_heartbeat(
     conn: &Connection,
@@ -412,8 +623,20 @@ pub fn send_heartbeat(
     Ok(())
 }
 
-/// Perform full sync cycle: push, pull, heartbeat
+/// Perform full sync cycle: push, pull, heartbeat.
+/// The outcome is kept in `sync_state`: a failure's message goes to `last_error`, a success clears it.
 pub fn perform_sync(conn: &Connection) -> Result<(i32, i32), AppError> {
+    conn.execute(
+        "INSERT INTO sync_state (id, sync_in_progress) VALUES (1, 1)
+         ON CONFLICT(id) DO UPDATE SET sync_in_progress = 1",
+        [],
+    )?;
+    let result = sync_cycle(conn);
+    record_sync_result(conn, &result)?;
+    result
+}
+
+fn sync_cycle(conn: &Connection) -> Result<(i32, i32), AppError> {
     // Get license info
     let license = get_local_license(conn)?
         .ok_or_else(|| AppError::Internal("Lisans bulunamadi".to_string()))?;
@@ -422,26 +645,77 @@ pub fn perform_sync(conn: &Connection) -> Result<(i32, i32), AppError> {
     let dealer_id = &license.dealer_id;
     let license_key = &license.license_key;
 
-    // Push local changes
-    let pushed = push_to_cloud(conn, api_base_url, dealer_id, license_key).unwrap_or(0);
+    // Push local changes; pull even when the push failed, the first error is reported
+    let pushed = push_to_cloud(conn, api_base_url, dealer_id, license_key);
 
     // Pull remote changes
-    let pulled = pull_from_cloud(conn, api_base_url, dealer_id, license_key).unwrap_or(0);
+    let pulled = pull_from_cloud(conn, api_base_url, dealer_id, license_key);
 
     // Send heartbeat
     let _ = send_heartbeat(conn, api_base_url, dealer_id, license_key);
 
-    Ok((pushed, pulled))
+    Ok((pushed?, pulled?))
+}
+
+/// Store how a sync cycle ended and clear the in-progress flag
+pub fn record_sync_result(conn: &Connection, result: &Result<(i32, i32), AppError>) -> Result<(), AppError> {
+    let last_error = result.as_ref().err().map(|e| e.to_string());
+    conn.execute(
+        "INSERT INTO sync_state (id, last_error, sync_in_progress) VALUES (1, ?1, 0)
+         ON CONFLICT(id) DO UPDATE SET last_error = excluded.last_error, sync_in_progress = 0",
+        params![last_error],
+    )?;
+    Ok(())
+}
+
+/// Message of the last failed sync; `None` once a later sync succeeded
+pub fn get_last_sync_error(conn: &Connection) -> Result<Option<String>, AppError> {
+    let result = conn.query_row("SELECT last_error FROM sync_state WHERE id = 1", [], |row| row.get(0));
+    match result {
+        Ok(error) => Ok(error),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(AppError::Database(e)),
+    }
+}
+
+/// Background sync cycle began
+pub const DEVICE_SYNC_STARTED_EVENT: &str = "device-sync-started";
+/// Background sync cycle finished, with the pushed and pulled counts
+pub const DEVICE_SYNC_DONE_EVENT: &str = "device-sync-done";
+/// Background sync cycle failed, with the message also kept in `sync_state.last_error`
+pub const DEVICE_SYNC_ERROR_EVENT: &str = "device-sync-error";
+
+/// Rows sent and received by one sync cycle
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncResult {
+    pub pushed: i32,
+    pub pulled: i32,
+}
+
+/// Payload of `DEVICE_SYNC_ERROR_EVENT`
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncFailed {
+    pub message: String,
+}
+
+/// What the background worker reports to its listener
+#[derive(Debug, Clone)]
+pub enum DeviceSyncEvent {
+    Started,
+    Done(SyncResult),
+    Error(SyncFailed),
 }
 
 /// Background sync worker state
 static SYNC_RUNNING: AtomicBool = AtomicBool::new(false);
 static SYNC_STOP_FLAG: AtomicBool = AtomicBool::new(false);
 
-/// Start background sync worker
+/// Start background sync worker. `listener` is called from the worker thread around every cycle,
+/// so it must not assume a window is open.
 pub fn start_background_sync(
     db_path: String,
     interval_seconds: u64,
+    listener: impl Fn(DeviceSyncEvent) + Send + 'static,
 ) -> Result<(), AppError> {
     if SYNC_RUNNING.load(Ordering::SeqCst) {
         return Ok(()); // Already running
@@ -451,6 +725,7 @@ pub fn start_background_sync(
     SYNC_STOP_FLAG.store(false, Ordering::SeqCst);
 
     thread::spawn(move || {
+        let mut last_purge: Option<std::time::Instant> = None;
         loop {
             // Check stop flag
             if SYNC_STOP_FLAG.load(Ordering::SeqCst) {
@@ -458,8 +733,31 @@ pub fn start_background_sync(
             }
 
             // Perform sync
-            if let Ok(conn) = rusqlite::Connection::open(&db_path) {
-                let _ = perform_sync(&conn);
+            listener(DeviceSyncEvent::Started);
+            let conn = match crate::database::open_database(&db_path) {
+                Ok(conn) => Some(conn),
+                Err(e) => {
+                    listener(DeviceSyncEvent::Error(SyncFailed { message: e.to_string() }));
+                    None
+                }
+            };
+            if let Some(conn) = conn {
+                match perform_sync(&conn) {
+                    Ok((pushed, pulled)) => listener(DeviceSyncEvent::Done(SyncResult { pushed, pulled })),
+                    Err(e) => listener(DeviceSyncEvent::Error(SyncFailed { message: e.to_string() })),
+                }
+
+                // Daily outbox cleanup
+                if last_purge.is_none_or(|at| at.elapsed() >= Duration::from_secs(24 * 60 * 60)) {
+                    match purge_synced_outbox(&conn, OUTBOX_RETENTION_DAYS) {
+                        Ok(removed) if removed > 0 => {
+                            eprintln!("[Sync] {} eski senkronize kayit silindi", removed);
+                        }
+                        Ok(_) => {}
+                        Err(e) => eprintln!("[Sync] Outbox temizligi basarisiz: {}", e),
+                    }
+                    last_purge = Some(std::time::Instant::now());
+                }
             }
 
             // Sleep for interval
@@ -490,13 +788,14 @@ pub fn is_sync_running() -> bool {
 /// Get sync status
 pub fn get_sync_state(conn: &Connection) -> Result<SyncState, AppError> {
     let result = conn.query_row(
-        "SELECT last_push_at, last_pull_at, sync_in_progress FROM sync_state WHERE id = 1",
+        "SELECT last_push_at, last_pull_at, sync_in_progress, last_error FROM sync_state WHERE id = 1",
         [],
         |row| {
             Ok(SyncState {
                 last_push_at: row.get(0)?,
                 last_pull_at: row.get(1)?,
-                sync_in_progress: row.get::<_, i32>(2)? == 1,
+                sync_in_progress: row.get::<_, Option<i32>>(2)? == Some(1),
+                last_error: row.get(3)?,
                 pending_count: 0,
             })
         },
@@ -508,6 +807,7 @@ pub fn get_sync_state(conn: &Connection) -> Result<SyncState, AppError> {
             last_push_at: None,
             last_pull_at: None,
             sync_in_progress: false,
+            last_error: None,
             pending_count: 0,
         },
     };
@@ -521,5 +821,213 @@ pub struct SyncState {
     pub last_push_at: Option<String>,
     pub last_pull_at: Option<String>,
     pub sync_in_progress: bool,
+    pub last_error: Option<String>,
     pub pending_count: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory_items (id TEXT PRIMARY KEY, sku TEXT UNIQUE, name TEXT, quantity REAL, price REAL, last_updated TEXT);
+             CREATE TABLE sync_outbox (
+                id TEXT PRIMARY KEY, action_type TEXT, item_sku TEXT, item_name TEXT, quantity_change REAL,
+                old_value REAL, new_value REAL, metadata TEXT, transaction_time TEXT, synced INTEGER, created_at TEXT
+             );
+             CREATE TABLE sync_conflicts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, remote_id TEXT NOT NULL, device_identifier TEXT, action_type TEXT NOT NULL,
+                item_sku TEXT, resolution TEXT NOT NULL, remote_time TEXT NOT NULL, local_time TEXT, detail TEXT, created_at TEXT NOT NULL
+             );
+             CREATE TABLE sync_received (id TEXT PRIMARY KEY, device_identifier TEXT, received_at TEXT NOT NULL);
+             INSERT INTO inventory_items VALUES ('i1', 'RP1', 'Boya', 10, 100.0, '2024-01-01T00:00:00+00:00');",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// Queue a local change at a fixed time, as the app would have done offline
+    fn local_change(conn: &Connection, action: ActionType, quantity_change: f64, new_value: Option<f64>, time: &str) {
+        match action {
+            ActionType::SALE | ActionType::STOCK_IN => {
+                conn.execute("UPDATE inventory_items SET quantity = quantity + ?1 WHERE sku = 'RP1'", params![quantity_change])
+                    .unwrap();
+            }
+            ActionType::PRICE_CHANGE => {
+                conn.execute("UPDATE inventory_items SET price = ?1 WHERE sku = 'RP1'", params![new_value]).unwrap();
+            }
+            _ => unreachable!(),
+        }
+        let id = queue_transaction(conn, action, Some("RP1"), Some("Boya"), quantity_change, None, new_value, None).unwrap();
+        conn.execute("UPDATE sync_outbox SET transaction_time = ?1 WHERE id = ?2", params![time, &id]).unwrap();
+    }
+
+    /// The other device's outbox as it comes back from a pull
+    fn outbox_as_remote(conn: &Connection, device: &str) -> Vec<RemoteTransaction> {
+        get_pending_transactions(conn, 100)
+            .unwrap()
+            .into_iter()
+            .map(|t| RemoteTransaction::from_outbox(t, device))
+            .collect()
+    }
+
+    fn quantity_and_price(conn: &Connection) -> (f64, f64) {
+        conn.query_row("SELECT quantity, price FROM inventory_items WHERE sku = 'RP1'", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_purge_synced_outbox_keeps_unsynced_and_recent_rows() {
+        let conn = device_db();
+        conn.execute_batch(
+            "CREATE TABLE sync_state (id INTEGER PRIMARY KEY, last_push_at TEXT, last_pull_at TEXT, last_received_id TEXT, sync_in_progress INTEGER);
+             INSERT INTO sync_state VALUES (1, NULL, NULL, 'remote-42', 0);",
+        )
+        .unwrap();
+        let now = chrono::Utc::now();
+        let rows = [
+            ("old-synced", 90, 1),
+            ("old-unsynced", 90, 0),
+            ("edge-synced", 29, 1),
+            ("new-synced", 1, 1),
+            ("new-unsynced", 1, 0),
+        ];
+        for (id, days_ago, synced) in rows {
+            conn.execute(
+                "INSERT INTO sync_outbox (id, action_type, item_sku, quantity_change, transaction_time, synced, created_at)
+                 VALUES (?1, 'SALE', 'RP1', -1, ?2, ?3, ?2)",
+                params![id, (now - chrono::Duration::days(days_ago)).to_rfc3339(), synced],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(purge_synced_outbox(&conn, 30).unwrap(), 1);
+        let mut stmt = conn.prepare("SELECT id FROM sync_outbox ORDER BY id").unwrap();
+        let left: Vec<String> = stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(left, vec!["edge-synced", "new-synced", "new-unsynced", "old-unsynced"]);
+
+        let cursor: Option<String> =
+            conn.query_row("SELECT last_received_id FROM sync_state WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(cursor.as_deref(), Some("remote-42"));
+
+        assert!(purge_synced_outbox(&conn, 0).is_err());
+    }
+
+    #[test]
+    fn test_failed_sync_records_last_error() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn.execute("INSERT INTO sync_state (id, last_push_at) VALUES (1, '2024-03-01T10:00:00+00:00')", [])
+            .unwrap();
+
+        // No license yet: the cycle fails before reaching the network
+        assert!(perform_sync(&conn).is_err());
+        let state = get_sync_state(&conn).unwrap();
+        assert_eq!(state.last_push_at.as_deref(), Some("2024-03-01T10:00:00+00:00"));
+        assert!(!state.sync_in_progress);
+        let error = get_last_sync_error(&conn).unwrap().unwrap();
+        assert!(error.contains("Lisans bulunamadi"), "{}", error);
+        assert_eq!(state.last_error.as_deref(), Some(error.as_str()));
+
+        // The next successful cycle clears it
+        record_sync_result(&conn, &Ok((2, 1))).unwrap();
+        assert_eq!(get_last_sync_error(&conn).unwrap(), None);
+        assert_eq!(get_sync_state(&conn).unwrap().last_push_at.as_deref(), Some("2024-03-01T10:00:00+00:00"));
+    }
+
+    #[test]
+    fn test_concurrent_price_edits_last_writer_wins() {
+        let a = device_db();
+        let b = device_db();
+        local_change(&a, ActionType::PRICE_CHANGE, 0.0, Some(120.0), "2024-03-01T10:00:00+00:00");
+        local_change(&b, ActionType::PRICE_CHANGE, 0.0, Some(130.0), "2024-03-01T07:30:00Z");
+        local_change(&b, ActionType::PRICE_CHANGE, 0.0, Some(150.0), "2024-03-01T12:00:00+03:00");
+
+        for txn in outbox_as_remote(&b, "device-b") {
+            apply_remote_transaction(&a, &txn, None).unwrap();
+        }
+        for txn in outbox_as_remote(&a, "device-a") {
+            apply_remote_transaction(&b, &txn, None).unwrap();
+        }
+
+        // 09:00 UTC (B) is older than 10:00 UTC (A): A's price is kept on both devices
+        assert_eq!(quantity_and_price(&a).1, 120.0);
+        assert_eq!(quantity_and_price(&b).1, 120.0);
+
+        let a_conflicts = get_sync_conflicts(&a, 10).unwrap();
+        assert_eq!(a_conflicts.len(), 2);
+        assert!(a_conflicts.iter().all(|c| c.resolution == RESOLUTION_LOCAL_WINS));
+        let b_conflicts = get_sync_conflicts(&b, 10).unwrap();
+        assert_eq!(b_conflicts.len(), 1);
+        assert_eq!(b_conflicts[0].resolution, RESOLUTION_REMOTE_WINS);
+        assert_eq!(b_conflicts[0].device_identifier.as_deref(), Some("device-a"));
+    }
+
+    #[test]
+    fn test_concurrent_sales_both_applied_as_deltas() {
+        let a = device_db();
+        let b = device_db();
+        local_change(&a, ActionType::SALE, -2.0, None, "2024-03-01T10:00:00+00:00");
+        local_change(&b, ActionType::SALE, -3.0, None, "2024-03-01T10:05:00+00:00");
+
+        for txn in outbox_as_remote(&b, "device-b") {
+            apply_remote_transaction(&a, &txn, None).unwrap();
+        }
+        for txn in outbox_as_remote(&a, "device-a") {
+            apply_remote_transaction(&b, &txn, None).unwrap();
+        }
+
+        assert_eq!(quantity_and_price(&a).0, 5.0);
+        assert_eq!(quantity_and_price(&b).0, 5.0);
+
+        // Receiving the same sale again (e.g. from the cloud after a LAN exchange) does nothing
+        for txn in outbox_as_remote(&b, "device-b") {
+            apply_remote_transaction(&a, &txn, None).unwrap();
+        }
+        assert_eq!(quantity_and_price(&a).0, 5.0);
+        for conn in [&a, &b] {
+            let conflicts = get_sync_conflicts(conn, 10).unwrap();
+            assert_eq!(conflicts.len(), 1);
+            assert_eq!(conflicts[0].resolution, RESOLUTION_MERGED);
+        }
+
+        // Local movements from before the previous pull are not concurrent
+        let c = device_db();
+        local_change(&c, ActionType::SALE, -1.0, None, "2024-02-01T10:00:00+00:00");
+        for txn in outbox_as_remote(&b, "device-b") {
+            apply_remote_transaction(&c, &txn, Some("2024-02-15T00:00:00+00:00")).unwrap();
+        }
+        assert_eq!(quantity_and_price(&c).0, 6.0);
+        assert!(get_sync_conflicts(&c, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fractional_stock_delta_syncs_exactly() {
+        let a = device_db();
+        let b = device_db();
+        // 0.4 KG weighed out on A, 1.25 KG received on B
+        local_change(&a, ActionType::SALE, -0.4, None, "2024-03-01T10:00:00+00:00");
+        local_change(&b, ActionType::STOCK_IN, 1.25, None, "2024-03-01T10:05:00+00:00");
+
+        // Over the wire (cloud push body, LAN exchange) the rows are JSON
+        let wire = |conn: &Connection, device: &str| -> Vec<RemoteTransaction> {
+            let json = serde_json::to_string(&get_pending_transactions(conn, 100).unwrap()).unwrap();
+            let rows: Vec<SyncTransaction> = serde_json::from_str(&json).unwrap();
+            rows.into_iter().map(|t| RemoteTransaction::from_outbox(t, device)).collect()
+        };
+        for txn in wire(&b, "device-b") {
+            apply_remote_transaction(&a, &txn, None).unwrap();
+        }
+        for txn in wire(&a, "device-a") {
+            apply_remote_transaction(&b, &txn, None).unwrap();
+        }
+
+        let expected = 10.0 - 0.4 + 1.25;
+        assert!((quantity_and_price(&a).0 - expected).abs() < 1e-9);
+        assert!((quantity_and_price(&b).0 - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,510 @@
+//! LAN Sync Service Module
+//!
+//! İnternet yokken aynı ağdaki kasalar birbirini mDNS ile bulur (`_nexus-sync._tcp`) ve
+//! `sync_outbox` kayıtlarını küçük bir TCP dinleyicisi üzerinden doğrudan değiş tokuş eder.
+//! Her bağlantıda satır sonuyla biten üç JSON mesajı gider: dinleyen taraf kendini tanıtır, istemci
+//! kendi yeni kayıtlarını gönderir, dinleyen taraf kendi yeni kayıtlarını döner. Gelen kayıtlar bulut
+//! senkronuyla aynı çakışma kurallarıyla uygulanır; aynı kayıt ikinci kez uygulanmaz.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{apply_remote_transaction, get_outbox_since, RemoteTransaction, SyncTransaction};
+use crate::error::AppError;
+
+/// mDNS service type advertised by every running app
+pub const LAN_SERVICE_TYPE: &str = "_nexus-sync._tcp.local.";
+
+/// Outbox rows sent per exchange; the rest goes with the next one
+const MAX_ROWS_PER_EXCHANGE: i32 = 500;
+
+/// Largest message accepted from a peer
+const MAX_MESSAGE_BYTES: u64 = 16 * 1024 * 1024;
+
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Who this device is on the LAN. Peers only exchange rows with devices of the same shop key (dealer id).
+#[derive(Debug, Clone)]
+pub struct LanIdentity {
+    pub device_identifier: String,
+    pub shop_key: String,
+}
+
+/// A device found on the LAN
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Peer {
+    pub device_identifier: String,
+    pub name: String,
+    /// `ip:port` of the peer's listener, as passed to `sync_with_peer`
+    pub address: String,
+    pub last_seen: String,
+}
+
+/// First message of the listener, so the requester can pick the cursors of that device
+#[derive(Debug, Serialize, Deserialize)]
+struct Hello {
+    device_identifier: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExchangeRequest {
+    device_identifier: String,
+    shop_key: String,
+    /// Newest transaction_time of the responder's rows the requester already has
+    since: Option<String>,
+    transactions: Vec<SyncTransaction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExchangeResponse {
+    success: bool,
+    device_identifier: String,
+    transactions: Vec<SyncTransaction>,
+    error: Option<String>,
+}
+
+/// Cursors of one peer from `lan_sync_peers`
+#[derive(Debug, Default)]
+struct PeerCursor {
+    last_sent_time: Option<String>,
+    last_received_time: Option<String>,
+    last_sync_at: Option<String>,
+}
+
+fn load_cursor(conn: &Connection, device_identifier: &str) -> Result<PeerCursor, AppError> {
+    let cursor = conn
+        .query_row(
+            "SELECT last_sent_time, last_received_time, last_sync_at FROM lan_sync_peers WHERE device_identifier = ?1",
+            params![device_identifier],
+            |row| {
+                Ok(PeerCursor {
+                    last_sent_time: row.get(0)?,
+                    last_received_time: row.get(1)?,
+                    last_sync_at: row.get(2)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(cursor.unwrap_or_default())
+}
+
+/// Move the peer's cursors past the rows of this exchange
+fn save_cursor(
+    conn: &Connection,
+    device_identifier: &str,
+    address: Option<&str>,
+    sent: &[SyncTransaction],
+    received: &[SyncTransaction],
+) -> Result<(), AppError> {
+    let newest = |rows: &[SyncTransaction]| rows.iter().map(|t| t.transaction_time.clone()).max();
+    conn.execute(
+        "INSERT INTO lan_sync_peers (device_identifier, address, last_sent_time, last_received_time, last_sync_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(device_identifier) DO UPDATE SET
+             address = COALESCE(excluded.address, address),
+             last_sent_time = COALESCE(excluded.last_sent_time, last_sent_time),
+             last_received_time = COALESCE(excluded.last_received_time, last_received_time),
+             last_sync_at = excluded.last_sync_at",
+        params![
+            device_identifier,
+            address,
+            newest(sent),
+            newest(received),
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Apply a peer's rows in one transaction
+fn apply_peer_rows(
+    conn: &Connection,
+    device_identifier: &str,
+    rows: &[SyncTransaction],
+    since: Option<&str>,
+) -> Result<(), AppError> {
+    let tx = conn.unchecked_transaction()?;
+    for row in rows {
+        apply_remote_transaction(&tx, &RemoteTransaction::from_outbox(row.clone(), device_identifier), since)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn read_message<T: DeserializeOwned>(stream: &TcpStream) -> Result<T, AppError> {
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_MESSAGE_BYTES)).read_line(&mut line)?;
+    if line.is_empty() {
+        return Err(AppError::Internal("LAN baglantisi yanitsiz kapandi".to_string()));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+fn write_message<T: Serialize>(mut stream: &TcpStream, message: &T) -> Result<(), AppError> {
+    let mut bytes = serde_json::to_vec(message)?;
+    bytes.push(b'\n');
+    stream.write_all(&bytes)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Serve one exchange: apply the requester's rows and answer with ours
+fn handle_connection(
+    stream: TcpStream,
+    identity: &LanIdentity,
+    open: &dyn Fn() -> Result<Connection, AppError>,
+    on_applied: &dyn Fn(usize),
+) -> Result<(), AppError> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    write_message(&stream, &Hello { device_identifier: identity.device_identifier.clone() })?;
+    let request: ExchangeRequest = read_message(&stream)?;
+    if request.shop_key != identity.shop_key {
+        return write_message(
+            &stream,
+            &ExchangeResponse {
+                success: false,
+                device_identifier: identity.device_identifier.clone(),
+                transactions: Vec::new(),
+                error: Some("Cihaz bu magazaya ait degil".to_string()),
+            },
+        );
+    }
+
+    let conn = open()?;
+    let cursor = load_cursor(&conn, &request.device_identifier)?;
+    apply_peer_rows(&conn, &request.device_identifier, &request.transactions, cursor.last_sync_at.as_deref())?;
+    if !request.transactions.is_empty() {
+        on_applied(request.transactions.len());
+    }
+    let outgoing = get_outbox_since(&conn, request.since.as_deref(), MAX_ROWS_PER_EXCHANGE)?;
+    save_cursor(&conn, &request.device_identifier, None, &outgoing, &request.transactions)?;
+
+    write_message(
+        &stream,
+        &ExchangeResponse {
+            success: true,
+            device_identifier: identity.device_identifier.clone(),
+            transactions: outgoing,
+            error: None,
+        },
+    )
+}
+
+/// A running TCP listener; stops accepting when `stop` is called
+pub struct LanListener {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+}
+
+impl LanListener {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Listen on `bind` (e.g. `0.0.0.0:0` for any free port) and serve peers' exchanges one at a time.
+/// `open` gives a database connection per exchange; `on_applied` gets the row count after a peer's
+/// rows were written, so cached reads can be dropped.
+pub fn start_listener<F, A>(bind: &str, identity: LanIdentity, open: F, on_applied: A) -> Result<LanListener, AppError>
+where
+    F: Fn() -> Result<Connection, AppError> + Send + 'static,
+    A: Fn(usize) + Send + 'static,
+{
+    let listener = TcpListener::bind(bind)
+        .map_err(|e| AppError::Internal(format!("LAN dinleyicisi acilamadi ({}): {}", bind, e)))?;
+    listener.set_nonblocking(true)?;
+    let addr = listener.local_addr()?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = stop.clone();
+    thread::spawn(move || {
+        while !stop_flag.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, peer_addr)) => {
+                    if let Err(e) = handle_connection(stream, &identity, &open, &on_applied) {
+                        eprintln!("[LAN] {} ile senkron basarisiz: {}", peer_addr, e);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => {
+                    eprintln!("[LAN] Baglanti kabul edilemedi: {}", e);
+                    thread::sleep(Duration::from_millis(100));
+                }
+            }
+        }
+    });
+
+    Ok(LanListener { addr, stop })
+}
+
+/// Exchange outbox rows with the peer listening at `addr`. Returns (pushed, pulled) row counts.
+pub fn sync_with_peer(conn: &Connection, identity: &LanIdentity, addr: &str) -> Result<(i32, i32), AppError> {
+    let socket_addr = addr
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| AppError::Validation(format!("Gecersiz cihaz adresi: {}", addr)))?;
+
+    let stream = TcpStream::connect_timeout(&socket_addr, IO_TIMEOUT)
+        .map_err(|e| AppError::Internal(format!("{} cihazina baglanilamadi: {}", addr, e)))?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let hello: Hello = read_message(&stream)?;
+    let cursor = load_cursor(conn, &hello.device_identifier)?;
+    let outgoing = get_outbox_since(conn, cursor.last_sent_time.as_deref(), MAX_ROWS_PER_EXCHANGE)?;
+
+    write_message(
+        &stream,
+        &ExchangeRequest {
+            device_identifier: identity.device_identifier.clone(),
+            shop_key: identity.shop_key.clone(),
+            since: cursor.last_received_time,
+            transactions: outgoing.clone(),
+        },
+    )?;
+    let response: ExchangeResponse = read_message(&stream)?;
+    if !response.success {
+        return Err(AppError::Auth(
+            response.error.unwrap_or_else(|| "LAN senkronu reddedildi".to_string()),
+        ));
+    }
+
+    apply_peer_rows(conn, &response.device_identifier, &response.transactions, cursor.last_sync_at.as_deref())?;
+    save_cursor(conn, &response.device_identifier, Some(addr), &outgoing, &response.transactions)?;
+
+    Ok((outgoing.len() as i32, response.transactions.len() as i32))
+}
+
+/// This device's identity: its MAC address and the dealer id of the local license
+pub fn local_identity(conn: &Connection) -> Result<LanIdentity, AppError> {
+    let license = crate::license::get_local_license(conn)?
+        .ok_or_else(|| AppError::Internal("Lisans bulunamadi".to_string()))?;
+    Ok(LanIdentity {
+        device_identifier: crate::license::get_device_mac_address()?,
+        shop_key: license.dealer_id,
+    })
+}
+
+/// Listener and mDNS daemon of the running app
+struct LanService {
+    listener: LanListener,
+    daemon: ServiceDaemon,
+}
+
+static LAN_SERVICE: Mutex<Option<LanService>> = Mutex::new(None);
+
+/// Peers found by mDNS, keyed by service full name
+fn peers() -> &'static Mutex<HashMap<String, Peer>> {
+    static PEERS: OnceLock<Mutex<HashMap<String, Peer>>> = OnceLock::new();
+    PEERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn mdns_error(e: mdns_sd::Error) -> AppError {
+    AppError::Internal(format!("mDNS hatasi: {}", e))
+}
+
+/// Start the listener, advertise it over mDNS and browse for peers. Returns the listener port;
+/// calling it again while running returns the same port. `on_applied` is called after rows from
+/// a peer were applied.
+pub fn start_lan_sync<A>(db_path: String, identity: LanIdentity, on_applied: A) -> Result<u16, AppError>
+where
+    A: Fn(usize) + Send + 'static,
+{
+    let mut service = LAN_SERVICE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(running) = service.as_ref() {
+        return Ok(running.listener.local_addr().port());
+    }
+
+    let own_device = identity.device_identifier.clone();
+    let listener = start_listener(
+        "0.0.0.0:0",
+        identity,
+        move || Ok(crate::database::open_database(&db_path)?),
+        on_applied,
+    )?;
+    let port = listener.local_addr().port();
+
+    let device_name = crate::license::get_device_name();
+    let instance: String = own_device
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let properties = HashMap::from([
+        ("device".to_string(), own_device.clone()),
+        ("name".to_string(), device_name.clone()),
+    ]);
+
+    let daemon = ServiceDaemon::new().map_err(mdns_error)?;
+    let info = ServiceInfo::new(
+        LAN_SERVICE_TYPE,
+        &instance,
+        &format!("{}.local.", instance),
+        "",
+        port,
+        properties,
+    )
+    .map_err(mdns_error)?
+    .enable_addr_auto();
+    daemon.register(info).map_err(mdns_error)?;
+
+    let events = daemon.browse(LAN_SERVICE_TYPE).map_err(mdns_error)?;
+    thread::spawn(move || {
+        while let Ok(event) = events.recv() {
+            match event {
+                ServiceEvent::ServiceResolved(info) => {
+                    let Some(device) = info.get_property_val_str("device").map(str::to_string) else {
+                        continue;
+                    };
+                    if device == own_device {
+                        continue;
+                    }
+                    let Some(ip) = info.get_addresses_v4().into_iter().next().copied() else {
+                        continue;
+                    };
+                    let peer = Peer {
+                        name: info.get_property_val_str("name").unwrap_or(&device).to_string(),
+                        device_identifier: device,
+                        address: format!("{}:{}", ip, info.get_port()),
+                        last_seen: chrono::Utc::now().to_rfc3339(),
+                    };
+                    peers()
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(info.get_fullname().to_string(), peer);
+                }
+                ServiceEvent::ServiceRemoved(_, fullname) => {
+                    peers().lock().unwrap_or_else(|e| e.into_inner()).remove(&fullname);
+                }
+                _ => {}
+            }
+        }
+    });
+
+    *service = Some(LanService { listener, daemon });
+    Ok(port)
+}
+
+/// Stop advertising and accepting exchanges
+pub fn stop_lan_sync() {
+    let running = LAN_SERVICE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(service) = running {
+        service.listener.stop();
+        let _ = service.daemon.shutdown();
+    }
+    peers().lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Peers currently advertised on the LAN, by name
+pub fn get_lan_peers() -> Vec<Peer> {
+    let mut list: Vec<Peer> = peers()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sync::{queue_transaction, ActionType};
+
+    fn device_db(name: &str) -> (String, Connection) {
+        let path = std::env::temp_dir()
+            .join(format!("nexus_lan_{}_{}.db", name, uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        let conn = Connection::open(&path).unwrap();
+        crate::database::migrations::run_migrations(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO inventory_items (id, sku, name, quantity, price, last_updated) VALUES ('i1', 'RP1', 'Boya', 10, 100.0, 'now')",
+            [],
+        )
+        .unwrap();
+        (path, conn)
+    }
+
+    fn identity(device: &str) -> LanIdentity {
+        LanIdentity { device_identifier: device.to_string(), shop_key: "dealer-1".to_string() }
+    }
+
+    fn quantity(conn: &Connection) -> i32 {
+        conn.query_row("SELECT quantity FROM inventory_items WHERE sku = 'RP1'", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn sell(conn: &Connection, quantity: i32) {
+        conn.execute("UPDATE inventory_items SET quantity = quantity - ?1 WHERE sku = 'RP1'", params![quantity])
+            .unwrap();
+        queue_transaction(conn, ActionType::SALE, Some("RP1"), Some("Boya"), -f64::from(quantity), None, None, None).unwrap();
+    }
+
+    #[test]
+    fn test_two_listeners_exchange_outbox_rows() {
+        let (a_path, a) = device_db("a");
+        let (b_path, b) = device_db("b");
+
+        let a_open = a_path.clone();
+        let a_listener =
+            start_listener("127.0.0.1:0", identity("device-a"), move || Ok(Connection::open(&a_open)?), |_| {}).unwrap();
+        let b_open = b_path.clone();
+        let b_applied = Arc::new(Mutex::new(Vec::new()));
+        let b_log = b_applied.clone();
+        let b_listener = start_listener(
+            "127.0.0.1:0",
+            identity("device-b"),
+            move || Ok(Connection::open(&b_open)?),
+            move |rows| b_log.lock().unwrap().push(rows),
+        )
+        .unwrap();
+
+        sell(&a, 2);
+        sell(&b, 3);
+
+        // A pushes its sale to B and pulls B's sale in the same exchange
+        let (pushed, pulled) = sync_with_peer(&a, &identity("device-a"), &b_listener.local_addr().to_string()).unwrap();
+        assert_eq!((pushed, pulled), (1, 1));
+        assert_eq!(quantity(&a), 5);
+        assert_eq!(quantity(&b), 5);
+        assert_eq!(*b_applied.lock().unwrap(), vec![1]);
+
+        // Nothing new in either direction: cursors advanced on both sides
+        let (pushed, pulled) = sync_with_peer(&b, &identity("device-b"), &a_listener.local_addr().to_string()).unwrap();
+        assert_eq!((pushed, pulled), (0, 0));
+        assert_eq!(quantity(&a), 5);
+        assert_eq!(quantity(&b), 5);
+
+        // A device of another shop is turned away
+        let stranger = LanIdentity { device_identifier: "device-c".to_string(), shop_key: "dealer-2".to_string() };
+        assert!(sync_with_peer(&b, &stranger, &a_listener.local_addr().to_string()).is_err());
+
+        a_listener.stop();
+        b_listener.stop();
+        drop(a);
+        drop(b);
+        for path in [a_path, b_path] {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Tax (KDV) Service Module
+//!
+//! Fiyatlar KDV dahildir. Satış satırları KDV oranına göre gruplanır; her grup için
+//! matrah (net), KDV tutarı ve KDV dahil toplam hesaplanır. Fiş, fatura ve kayıtlı
+//! `tax_total` aynı hesaplamayı kullanır.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Net, KDV and gross amounts of the lines with one KDV rate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TaxGroup {
+    /// Percent
+    pub rate: f64,
+    pub net: f64,
+    pub tax: f64,
+    pub gross: f64,
+}
+
+pub fn round_money(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+pub fn validate_tax_rate(rate: f64) -> Result<(), AppError> {
+    if !rate.is_finite() || !(0.0..=100.0).contains(&rate) {
+        return Err(AppError::Validation(format!("Gecersiz KDV orani: {}", rate)));
+    }
+    Ok(())
+}
+
+/// Group KDV-inclusive line amounts `(rate, amount)` by rate, lowest rate first.
+/// Each group is rounded once: the net is derived from the gross and the KDV is the difference,
+/// so net + KDV always equals the gross.
+pub fn tax_breakdown(lines: impl IntoIterator<Item = (f64, f64)>) -> Vec<TaxGroup> {
+    // Rates as hundredths of a percent so they can be map keys
+    let mut gross_by_rate: BTreeMap<i64, f64> = BTreeMap::new();
+    for (rate, amount) in lines {
+        *gross_by_rate.entry((rate * 100.0).round() as i64).or_insert(0.0) += amount;
+    }
+
+    gross_by_rate
+        .into_iter()
+        .map(|(rate, gross)| {
+            let rate = rate as f64 / 100.0;
+            let gross = round_money(gross);
+            let net = round_money(gross / (1.0 + rate / 100.0));
+            TaxGroup { rate, net, tax: round_money(gross - net), gross }
+        })
+        .collect()
+}
+
+/// Total KDV of the groups
+pub fn total_tax(groups: &[TaxGroup]) -> f64 {
+    round_money(groups.iter().map(|g| g.tax).sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tax_breakdown_groups_by_rate() {
+        let groups = tax_breakdown([(20.0, 120.0), (1.0, 50.5), (20.0, 60.0), (10.0, 110.0)]);
+        assert_eq!(groups.iter().map(|g| g.rate).collect::<Vec<_>>(), vec![1.0, 10.0, 20.0]);
+        assert_eq!((groups[2].net, groups[2].tax, groups[2].gross), (150.0, 30.0, 180.0));
+        assert_eq!((groups[1].net, groups[1].tax), (100.0, 10.0));
+        assert_eq!((groups[0].net, groups[0].tax), (50.0, 0.5));
+        assert_eq!(total_tax(&groups), 40.5);
+
+        assert!(validate_tax_rate(10.0).is_ok());
+        assert!(validate_tax_rate(-1.0).is_err());
+        assert!(validate_tax_rate(f64::NAN).is_err());
+    }
+}
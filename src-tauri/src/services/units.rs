@@ -0,0 +1,49 @@
+//! Units of Measure Module
+//!
+//! Stok miktarı ürünün birimiyle tutulur. ADET, PAKET, KOLİ gibi sayılan birimler tam sayı
+//! kalır; KG, LT, MT gibi ölçülen birimler kesirli satılıp alınabilir (2.5 metre kablo).
+
+/// Unit of items without a stock card or with a blank unit
+pub const DEFAULT_UNIT: &str = "ADET";
+
+/// Decimals kept for measured quantities; sums are rounded to this to avoid float drift
+pub const QUANTITY_DECIMALS: usize = 3;
+
+/// Canonical code of a unit; long names and blanks map to the codes on the stock card form
+pub fn normalize_unit(unit: &str) -> String {
+    let code = unit.trim().to_uppercase();
+    match code.as_str() {
+        "" => DEFAULT_UNIT.to_string(),
+        "KILOGRAM" | "KILO" => "KG".to_string(),
+        "GRAM" => "GR".to_string(),
+        "LITRE" => "LT".to_string(),
+        "METRE" | "M" => "MT".to_string(),
+        "METREKARE" => "M2".to_string(),
+        _ => code,
+    }
+}
+
+/// Decimals a quantity in `unit` is entered and shown with; 0 for counted units
+pub fn quantity_decimals(unit: &str) -> usize {
+    match normalize_unit(unit).as_str() {
+        "KG" | "LT" => 3,
+        "MT" | "M2" => 2,
+        _ => 0,
+    }
+}
+
+/// Whether `unit` may hold fractional quantities
+pub fn allows_fraction(unit: &str) -> bool {
+    quantity_decimals(unit) > 0
+}
+
+/// `quantity` rounded to `QUANTITY_DECIMALS`
+pub fn round_quantity(quantity: f64) -> f64 {
+    let factor = 10f64.powi(QUANTITY_DECIMALS as i32);
+    (quantity * factor).round() / factor
+}
+
+/// Quantity with the unit's precision, e.g. "2.50 MT" or "3 ADET"
+pub fn format_quantity(quantity: f64, unit: &str) -> String {
+    format!("{:.*} {}", quantity_decimals(unit), quantity, normalize_unit(unit))
+}
@@ -0,0 +1,302 @@
+//! Excel (.xlsx) Import/Export Service Module
+//!
+//! Envanter, CSV dışa aktarımıyla aynı sütunlarla biçimli bir başlık satırı ile .xlsx olarak yazılır.
+//! İçe aktarmada sütunlar konuma göre değil başlık adına göre eşlenir; tedarikçi listelerindeki fazla
+//! sütunlar yok sayılır. Satırlar CSV içe aktarmayla aynı şekilde SKU'ya göre eklenir/güncellenir.
+
+use calamine::{open_workbook_auto, Data, Reader};
+use rust_xlsxwriter::{Color, Format, FormatBorder, Workbook, XlsxError};
+use rusqlite::Connection;
+
+use crate::error::AppError;
+use crate::models::CsvImportResult;
+use crate::services::import_staging::{CsvImportRow, ImportRows};
+use crate::services::inventory;
+
+/// Column headers of the export, same as the CSV export
+pub const EXPORT_HEADERS: [&str; 10] = [
+    "ID",
+    "SKU",
+    "Ürün Adı",
+    "Kategori",
+    "Miktar",
+    "Konum",
+    "Satış Fiyatı",
+    "Alış Fiyatı",
+    "Para Birimi",
+    "Son Güncelleme",
+];
+
+fn xlsx_error(e: XlsxError) -> AppError {
+    AppError::Internal(format!("Excel dosyasi yazilamadi: {}", e))
+}
+
+/// Write all inventory items to `file_path`. Returns the number of rows written.
+pub fn export_inventory_xlsx(conn: &Connection, file_path: &str) -> Result<usize, AppError> {
+    let items = inventory::get_all_items(conn)?;
+
+    let mut workbook = Workbook::new();
+    let header_format = Format::new()
+        .set_bold()
+        .set_font_color(Color::White)
+        .set_background_color(Color::RGB(0x1F4E78))
+        .set_border(FormatBorder::Thin);
+    let money_format = Format::new().set_num_format("#,##0.00");
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Envanter").map_err(xlsx_error)?;
+    for (col, header) in EXPORT_HEADERS.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *header, &header_format).map_err(xlsx_error)?;
+    }
+
+    for (index, item) in items.iter().enumerate() {
+        let row = index as u32 + 1;
+        sheet.write_string(row, 0, &item.id).map_err(xlsx_error)?;
+        sheet.write_string(row, 1, &item.sku).map_err(xlsx_error)?;
+        sheet.write_string(row, 2, &item.name).map_err(xlsx_error)?;
+        sheet.write_string(row, 3, &item.category).map_err(xlsx_error)?;
+        sheet.write_number(row, 4, item.quantity).map_err(xlsx_error)?;
+        sheet.write_string(row, 5, &item.location).map_err(xlsx_error)?;
+        sheet.write_number_with_format(row, 6, item.price, &money_format).map_err(xlsx_error)?;
+        sheet
+            .write_number_with_format(row, 7, item.cost_price.unwrap_or(0.0), &money_format)
+            .map_err(xlsx_error)?;
+        sheet.write_string(row, 8, item.currency.as_deref().unwrap_or("")).map_err(xlsx_error)?;
+        sheet.write_string(row, 9, &item.last_updated).map_err(xlsx_error)?;
+    }
+
+    sheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+    sheet
+        .autofilter(0, 0, items.len() as u32, EXPORT_HEADERS.len() as u16 - 1)
+        .map_err(xlsx_error)?;
+    for (col, width) in [38, 18, 40, 18, 10, 14, 14, 14, 12, 28].into_iter().enumerate() {
+        sheet.set_column_width(col as u16, width).map_err(xlsx_error)?;
+    }
+
+    workbook.save(file_path).map_err(xlsx_error)?;
+    Ok(items.len())
+}
+
+/// Header text folded to lowercase ASCII so "Ürün Adı", "URUN ADI" and "urun adi" match
+fn normalize_header(header: &str) -> String {
+    header
+        .trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter_map(|c| match c {
+            'ç' => Some('c'),
+            'ğ' => Some('g'),
+            'ı' => Some('i'),
+            'ö' => Some('o'),
+            'ş' => Some('s'),
+            'ü' => Some('u'),
+            // Combining dot left by lowercasing 'İ'
+            '\u{307}' => None,
+            _ => Some(c),
+        })
+        .collect()
+}
+
+/// Positions of the known columns in a sheet's header row
+#[derive(Debug, Default)]
+struct ColumnMap {
+    sku: Option<usize>,
+    name: Option<usize>,
+    category: Option<usize>,
+    quantity: Option<usize>,
+    location: Option<usize>,
+    price: Option<usize>,
+    cost_price: Option<usize>,
+    currency: Option<usize>,
+}
+
+impl ColumnMap {
+    /// Map header names (ours and common supplier names); unknown columns are ignored
+    fn from_headers(headers: &[Data]) -> Result<Self, AppError> {
+        let mut map = ColumnMap::default();
+        for (index, header) in headers.iter().enumerate() {
+            let slot = match normalize_header(&cell_text(header)).as_str() {
+                "sku" | "barkod" | "stok kodu" | "urun kodu" => &mut map.sku,
+                "urun adi" | "ad" | "urun" | "name" => &mut map.name,
+                "kategori" | "category" => &mut map.category,
+                "miktar" | "adet" | "stok" | "quantity" => &mut map.quantity,
+                "konum" | "raf" | "location" => &mut map.location,
+                "satis fiyati" | "fiyat" | "liste fiyati" | "price" => &mut map.price,
+                "alis fiyati" | "maliyet" | "cost" | "cost price" => &mut map.cost_price,
+                "para birimi" | "doviz" | "currency" => &mut map.currency,
+                _ => continue,
+            };
+            // First matching column wins
+            slot.get_or_insert(index);
+        }
+
+        if map.sku.is_none() || map.name.is_none() {
+            return Err(AppError::Validation(
+                "Excel dosyasinda SKU ve Urun Adi basliklari bulunamadi".to_string(),
+            ));
+        }
+        Ok(map)
+    }
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(s) => s.trim().to_string(),
+        // Barcodes typed into Excel become numbers
+        Data::Float(f) if f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", *f as i64),
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+fn cell_number(row: &[Data], index: Option<usize>, label: &str) -> Result<Option<f64>, String> {
+    match index.and_then(|i| row.get(i)) {
+        None | Some(Data::Empty) => Ok(None),
+        Some(Data::Float(f)) => Ok(Some(*f)),
+        Some(Data::Int(i)) => Ok(Some(*i as f64)),
+        Some(cell) => {
+            let text = cell_text(cell).replace(',', ".");
+            if text.is_empty() {
+                return Ok(None);
+            }
+            text.parse().map(Some).map_err(|_| format!("Gecersiz {}: {}", label, text))
+        }
+    }
+}
+
+fn xlsx_import_row(row: &[Data], columns: &ColumnMap) -> Result<CsvImportRow, String> {
+    let text = |index: Option<usize>| index.and_then(|i| row.get(i)).map(cell_text).unwrap_or_default();
+    let text_or = |index: Option<usize>, default: &str| {
+        let value = text(index);
+        if value.is_empty() { default.to_string() } else { value }
+    };
+
+    let sku = text(columns.sku);
+    let name = text(columns.name);
+    if sku.is_empty() {
+        return Err("SKU bos".to_string());
+    }
+    if name.is_empty() {
+        return Err(format!("Urun adi bos: {}", sku));
+    }
+
+    Ok(CsvImportRow {
+        sku,
+        name,
+        category: text_or(columns.category, "Genel"),
+        quantity: cell_number(row, columns.quantity, "miktar")?.unwrap_or(0.0),
+        location: text(columns.location),
+        price: cell_number(row, columns.price, "fiyat")?.unwrap_or(0.0),
+        cost_price: cell_number(row, columns.cost_price, "maliyet")?.unwrap_or(0.0),
+        currency: text_or(columns.currency, "TL"),
+    })
+}
+
+/// Parse the data rows of the first sheet. Each entry has the sheet row number and either the row
+/// or why it was rejected; blank rows are skipped.
+pub fn read_xlsx_import_rows(file_path: &str) -> Result<ImportRows, AppError> {
+    let mut workbook = open_workbook_auto(file_path)
+        .map_err(|e| AppError::Validation(format!("Excel dosyasi acilamadi: {}", e)))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| AppError::Validation("Excel dosyasinda sayfa yok".to_string()))?
+        .map_err(|e| AppError::Validation(format!("Excel sayfasi okunamadi: {}", e)))?;
+
+    let first_row = range.start().map(|(row, _)| row as u64).unwrap_or(0);
+    let mut rows = range.rows();
+    let Some(headers) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let columns = ColumnMap::from_headers(headers)?;
+
+    Ok(rows
+        .enumerate()
+        .filter(|(_, row)| row.iter().any(|cell| !cell_text(cell).is_empty()))
+        // Header is sheet row first_row + 1, the first data row first_row + 2
+        .map(|(index, row)| (first_row + index as u64 + 2, xlsx_import_row(row, &columns)))
+        .collect())
+}
+
+/// Import the first sheet of an .xlsx file: rows are upserted by SKU, rejected rows are reported in `errors`
+pub fn import_inventory_xlsx(conn: &Connection, file_path: &str) -> Result<CsvImportResult, AppError> {
+    inventory::upsert_import_rows(conn, read_xlsx_import_rows(file_path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_xlsx(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("nexus_{}_{}.xlsx", name, uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn item_count(conn: &Connection) -> i32 {
+        conn.query_row("SELECT COUNT(*) FROM inventory_items", [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_xlsx_export_import_round_trip() {
+        let source = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&source).unwrap();
+        source
+            .execute_batch(
+                "INSERT INTO inventory_items (id, sku, name, category, quantity, location, price, cost_price, last_updated, currency)
+                 VALUES ('i1', 'BOYA-25', 'Ürün; İç Cephe 2.5L', 'Boya', 7, 'Raf 1', 275.5, 190.0, 'now', 'TL'),
+                        ('i2', '8690000000017', 'Rulo', 'Boya', 3, '', 45.0, 30.0, 'now', 'TL'),
+                        ('i3', 'VIDA-4', 'Vida', 'Hirdavat', 0, '', 1.25, NULL, 'now', 'TL');",
+            )
+            .unwrap();
+
+        let path = temp_xlsx("roundtrip");
+        assert_eq!(export_inventory_xlsx(&source, &path).unwrap(), 3);
+
+        let target = Connection::open_in_memory().unwrap();
+        crate::database::migrations::run_migrations(&target).unwrap();
+        let result = import_inventory_xlsx(&target, &path).unwrap();
+        assert_eq!((result.imported, result.skipped), (3, 0));
+        assert_eq!(item_count(&target), item_count(&source));
+
+        let (name, price, quantity): (String, f64, i32) = target
+            .query_row("SELECT name, price, quantity FROM inventory_items WHERE sku = 'BOYA-25'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((name.as_str(), price, quantity), ("Ürün; İç Cephe 2.5L", 275.5, 7));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_xlsx_import_maps_supplier_columns_by_header() {
+        let path = temp_xlsx("supplier");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        for (col, header) in ["Marka", "FİYAT", "Ürün Adı", "Barkod", "KDV", "Adet"].iter().enumerate() {
+            sheet.write_string(0, col as u16, *header).unwrap();
+        }
+        sheet.write_string(1, 0, "Marshall").unwrap();
+        sheet.write_number(1, 1, 310.0).unwrap();
+        sheet.write_string(1, 2, "Boya 2.5L").unwrap();
+        sheet.write_number(1, 3, 8690000000017.0).unwrap();
+        sheet.write_number(1, 4, 20.0).unwrap();
+        sheet.write_number(1, 5, 4.0).unwrap();
+        sheet.write_string(3, 1, "12,50").unwrap();
+        sheet.write_string(3, 3, "ADSIZ-1").unwrap();
+        workbook.save(&path).unwrap();
+
+        let rows = read_xlsx_import_rows(&path).unwrap();
+        assert_eq!(rows.len(), 2);
+        let paint = rows[0].1.as_ref().unwrap();
+        assert_eq!(rows[0].0, 2);
+        assert_eq!(paint.sku, "8690000000017");
+        assert_eq!((paint.name.as_str(), paint.price, paint.quantity), ("Boya 2.5L", 310.0, 4.0));
+        assert_eq!((paint.category.as_str(), paint.currency.as_str()), ("Genel", "TL"));
+        assert_eq!(rows[1].0, 4);
+        assert!(rows[1].1.as_ref().unwrap_err().contains("ADSIZ-1"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
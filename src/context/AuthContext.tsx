@@ -57,11 +57,15 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
     };
 
     const logout = () => {
+        if (user?.token && isTauri()) {
+            tauriInvoke('logout', { token: user.token }).catch(() => undefined);
+        }
         setUser(null);
     };
 
     const updateCurrentUser = (updatedUser: User) => {
-        setUser(updatedUser);
+        // Keep the session when the profile is refreshed
+        setUser(prev => ({ ...updatedUser, token: updatedUser.token ?? prev?.token, expiresAt: updatedUser.expiresAt ?? prev?.expiresAt }));
     };
 
     const value: AuthContextType = {
@@ -15,11 +15,14 @@ interface LicenseContextType {
   isExpired: boolean;
   isRevoked: boolean;
   daysUntilExpiry: number | null;
+  offlineGraceRemainingDays: number | null;
+  needsOnlineSoon: boolean;
   error: string | null;
   activateLicense: (apiBaseUrl: string, licenseKey: string) => Promise<boolean>;
   checkLicense: () => Promise<boolean>;
   validateOnline: () => Promise<boolean>;
-  deactivateLicense: () => Promise<void>;
+  /** Resolves with a warning when the server seat could not be freed */
+  deactivateLicense: () => Promise<string | null>;
   clearError: () => void;
 }
 
@@ -46,6 +49,8 @@ export const LicenseProvider: React.FC<LicenseProviderProps> = ({ children }) =>
   const [isExpired, setIsExpired] = useState(false);
   const [isRevoked, setIsRevoked] = useState(false);
   const [daysUntilExpiry, setDaysUntilExpiry] = useState<number | null>(null);
+  const [offlineGraceRemainingDays, setOfflineGraceRemainingDays] = useState<number | null>(null);
+  const [needsOnlineSoon, setNeedsOnlineSoon] = useState(false);
 
   const checkIntervalRef = useRef<NodeJS.Timeout | null>(null);
 
@@ -118,15 +123,19 @@ export const LicenseProvider: React.FC<LicenseProviderProps> = ({ children }) =>
       }
 
       // Yerel geçerlilik kontrolü (expiry vs)
-      const isLocalValid = await licenseService.checkLicenseValidity();
+      const localCheck = await licenseService.checkLicenseValidity();
       const days = licenseService.getDaysUntilExpiry(localLicense);
       setDaysUntilExpiry(days);
+      setOfflineGraceRemainingDays(localCheck.offlineGraceRemainingDays ?? null);
+      setNeedsOnlineSoon(localCheck.needsOnlineSoon);
 
-      if (!isLocalValid) {
+      if (!localCheck.valid) {
         setLicense(null);
         setIsLicensed(false);
         setIsExpired(true);
-        setError('Lisans süresi dolmuş');
+        setError(localCheck.offlineGraceRemainingDays === 0
+          ? 'Lisans uzun süredir çevrimiçi doğrulanamadı. Lütfen internete bağlanın.'
+          : 'Lisans süresi dolmuş');
         setIsLoading(false);
         return false;
       }
@@ -175,6 +184,11 @@ export const LicenseProvider: React.FC<LicenseProviderProps> = ({ children }) =>
       const response = await licenseService.activateLicense(apiBaseUrl, licenseKey);
 
       if (response.success) {
+        // Shows the temporary admin password when activation created the admin
+        if (response.message) {
+          alert(response.message);
+        }
+
         // Fetch the saved license
         const savedLicense = await licenseService.getLicenseStatus();
         setLicense(savedLicense);
@@ -195,14 +209,16 @@ export const LicenseProvider: React.FC<LicenseProviderProps> = ({ children }) =>
     }
   }, []);
 
-  const deactivateLicense = useCallback(async (): Promise<void> => {
+  const deactivateLicense = useCallback(async (): Promise<string | null> => {
     setIsLoading(true);
     try {
-      await licenseService.deactivateLicense();
+      const warning = await licenseService.deactivateLicense();
       setLicense(null);
+      return warning;
     } catch (err) {
       const errorMessage = err instanceof Error ? err.message : 'Lisans deaktivasyonu sirasinda hata olustu';
       setError(errorMessage);
+      return null;
     } finally {
       setIsLoading(false);
     }
@@ -263,6 +279,8 @@ export const LicenseProvider: React.FC<LicenseProviderProps> = ({ children }) =>
     isExpired,
     isRevoked,
     daysUntilExpiry,
+    offlineGraceRemainingDays,
+    needsOnlineSoon,
     error,
     activateLicense,
     checkLicense,
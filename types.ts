@@ -34,6 +34,9 @@ export interface User {
   createdAt: string;
   lastLogin?: string;
   mustChangePassword?: boolean;
+  /** Session token returned by login; required by admin-only commands */
+  token?: string;
+  expiresAt?: string;
 }
 
 export interface InventoryItem {
@@ -53,13 +56,46 @@ export interface InventoryItem {
   supplierId?: string; // Link to current account (Cari)
   brand?: string; // Product brand
   currency?: string;
+  minStockLevel?: number; // Low stock threshold (default 10)
+}
+
+// Item of a scanned code and which lookup found it
+export interface BarcodeLookup {
+  item: InventoryItem;
+  matchedBy: 'Sku' | 'StockCard' | 'PriceEmbedded';
+  embeddedPrice?: number; // Price printed into a scale label
+}
+
+// Item in the trash (soft-deleted)
+export interface DeletedItem extends InventoryItem {
+  deletedAt: string;
+}
+
+export interface AttributeValues {
+  key: string;
+  values: string[];
+  itemCount: number;
 }
 
 export interface CartItem extends InventoryItem {
   cartId: string;
   cartQuantity: number;
+  taxRate?: number; // KDV % (default 20)
+  discountAmount?: number; // line discount in money; price stays the list price
+  discountPercent?: number; // line discount in %; a line has this or discountAmount
+}
+
+// Whole-ticket discount, taken off after the line discounts
+export interface SaleDiscount {
+  amount?: number;
+  percent?: number;
 }
 
+// Sort columns accepted by the paginated item and transaction lists
+export type ItemSortColumn = 'name' | 'sku' | 'quantity' | 'price' | 'last_updated';
+export type TransactionSortColumn = 'created_at' | 'total';
+export type SortDirection = 'asc' | 'desc';
+
 export interface Category {
   id: string;
   name: string;
@@ -67,23 +103,72 @@ export interface Category {
   createdAt: string;
 }
 
+// Category with its subcategories; itemCount counts stock cards filed directly under it
+export interface CategoryNode {
+  id: string;
+  name: string;
+  parentId: string | null;
+  path: string; // e.g. "HIRDAVAT > Tornavida"
+  itemCount: number;
+  children: CategoryNode[];
+}
+
 export interface Transaction {
   id: string;
   items: CartItem[];
   total: number;
   paymentMethod: string;
   transactionType: string; // 'SALE' | 'RETURN'
-  status: string;
+  status: string; // 'completed' | 'voided'
   note?: string;
   createdAt: string;
   customerId?: string;
+  taxTotal?: number; // KDV included in total
+  lineDiscount?: number; // line discounts, already taken off total
+  ticketDiscount?: number; // whole-ticket discount, already taken off total
 }
 
 export interface DashboardStats {
   totalItems: number;
   totalQuantity: number;
   lowStockCount: number;
-  totalRevenue: number;
+  totalRevenue: number; // sales minus returns in periodStart..periodEnd
+  saleCount: number;
+  returnCount: number;
+  averageBasket: number;
+  periodStart: string; // YYYY-MM-DD
+  periodEnd: string;
+  currencyWarnings: string[]; // missing or stale exchange rates
+}
+
+export interface CsvImportResult {
+  imported: number;
+  skipped: number;
+  errors: string[]; // "Satir 4: SKU bos"
+}
+
+export interface ImportStageSummary {
+  batchId: string;
+  totalRows: number;
+  newItems: number;
+  updatedItems: number;
+  skippedLines: number;
+}
+
+export interface PriceOutlier {
+  sku: string;
+  name: string;
+  itemId: string;
+  oldPrice: number;
+  newPrice: number;
+  percentChange: number;
+  oldPriceSource: 'history' | 'current';
+}
+
+export interface ImportCommitResult {
+  applied: number;
+  excluded: number;
+  priceChanges: number;
 }
 
 // ==================== LICENSE TYPES ====================
@@ -109,6 +194,18 @@ export interface LicenseValidateResponse {
   message?: string;
 }
 
+export interface LicenseCheckResult {
+  valid: boolean;
+  /** Days left before the license stops working without an online validation */
+  offlineGraceRemainingDays?: number | null;
+  needsOnlineSoon: boolean;
+}
+
+export interface LicensePolicy {
+  revalidationDays: number;
+  offlineGraceDays: number;
+}
+
 export interface LicenseActivateResponse {
   success: boolean;
   dealer_id?: string;
@@ -128,6 +225,24 @@ export interface SyncStatus {
   autoSyncIntervalMinutes: number;
 }
 
+export interface DatabaseSchemaInfo {
+  detectedVersion: number;
+  currentVersion: number;
+  needsMigration: boolean;
+  compatible: boolean;
+  backupPath?: string;
+}
+
+export interface AutoSyncSettingsResult {
+  status: SyncStatus;
+  requestedIntervalMinutes: number;
+  effectiveIntervalMinutes: number;
+  intervalClamped: boolean;
+  serverReachable?: boolean;
+  firstSyncScheduled: boolean;
+  warnings: string[];
+}
+
 export interface CloudBackupResponse {
   success: boolean;
   backupId?: string;
@@ -137,6 +252,12 @@ export interface CloudBackupResponse {
   error?: string;
 }
 
+export interface CloudBackupProgress {
+  jobId: string;
+  bytesSent: number;
+  totalBytes: number;
+}
+
 export interface CloudStatusResponse {
   has_backup: boolean;
   last_backup_at?: string;
@@ -144,6 +265,30 @@ export interface CloudStatusResponse {
   backup_count?: number;
 }
 
+export interface DatabaseSnapshot {
+  lastModified?: string;
+  transactionCount: number;
+  itemCount: number;
+  accountCount: number;
+}
+
+export interface BackupComparison {
+  local: DatabaseSnapshot;
+  cloud: DatabaseSnapshot;
+  daysOlder?: number;
+  transactionDifference: number;
+  itemDifference: number;
+  accountDifference: number;
+  requiresForce: boolean;
+  warnings: string[];
+}
+
+export interface CloudRestoreResult {
+  restored: boolean;
+  comparison: BackupComparison;
+  message: string;
+}
+
 // ==================== PAGINATION TYPES ====================
 
 export interface PaginationParams {
@@ -177,6 +322,18 @@ export interface FinanceRecord {
   createdAt: string;
 }
 
+// Monthly expense (rent, utilities) written automatically once its day has come
+export interface RecurringExpense {
+  id: string; // empty when creating
+  description: string;
+  amount: number;
+  category: string;
+  paymentMethod: string;
+  dayOfMonth: number; // 1-31; last day in shorter months
+  active: boolean;
+  lastGeneratedPeriod?: string | null; // "YYYY-MM" already written
+}
+
 export interface FinanceSummary {
   totalIncome: number;
   totalExpense: number;
@@ -184,6 +341,61 @@ export interface FinanceSummary {
   cashBalance: number;
   cardBalance: number;
   bankBalance: number;
+  currencyWarnings: string[];
+}
+
+export interface ExchangeRate {
+  currency: string; // USD, EUR...
+  rateToTry: number;
+  updatedAt: string;
+  stale: boolean; // older than 24h
+}
+
+export interface PaymentBreakdown {
+  cash: number;
+  card: number;
+  bank: number;
+  credit: number; // Veresiye / vadeli
+  other: number;
+}
+
+// End-of-day close (Z report)
+export interface ZReport {
+  id: string;
+  date: string; // YYYY-MM-DD
+  saleCount: number;
+  grossSales: number;
+  salesByPayment: PaymentBreakdown;
+  returnCount: number;
+  returnsTotal: number;
+  expenseTotal: number;
+  otherIncome: number;
+  purchasesTotal: number;
+  supplierPayments: number;
+  expectedCash: number;
+  createdAt: string;
+}
+
+// Current account statement (cari ekstre)
+export interface AccountStatementLine {
+  transactionId: string;
+  date: string;
+  transactionType: string;
+  paymentMethod: string;
+  description: string;
+  amount: number; // positive increases the debt
+  balance: number; // balance after this line
+}
+
+export interface AccountStatement {
+  accountId: string;
+  accountName: string;
+  accountType: string;
+  startDate: string; // YYYY-MM-DD
+  endDate: string; // YYYY-MM-DD
+  openingBalance: number;
+  lines: AccountStatementLine[];
+  closingBalance: number;
 }
 
 export interface AccessCode {
@@ -198,4 +410,5 @@ export interface GoodsReceiptItem {
   product_id: string;
   quantity: number;
   buy_price: number;
+  expiry_date?: string; // YYYY-MM-DD
 }
\ No newline at end of file